@@ -1,5 +1,5 @@
 use clap::{App, Arg};
-use easy_fs::{BlockDevice, EasyFileSystem};
+use easy_fs::{block_cache_sync_all, BlockDevice, EasyFileSystem};
 use std::fs::{read_dir, File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::sync::Arc;
@@ -88,6 +88,8 @@ fn easy_fs_pack() -> std::io::Result<()> {
     for app in root_inode.ls() {
         println!("{}", app);
     }
+    // the block cache is write-back, flush it before exiting
+    block_cache_sync_all();
     Ok(())
 }
 
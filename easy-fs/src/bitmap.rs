@@ -74,4 +74,8 @@ impl Bitmap {
     pub fn maximum(&self) -> usize {
         self.blocks * BLOCK_BITS
     }
+    /// Get ids of the blocks holding the bitmap
+    pub fn block_ids(&self) -> core::ops::Range<usize> {
+        self.start_block_id..self.start_block_id + self.blocks
+    }
 }
@@ -4,9 +4,28 @@ use super::{
 };
 use alloc::collections::VecDeque;
 use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use lazy_static::*;
 use spin::Mutex;
 
+/// Counters of the block cache since boot
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlockCacheStats {
+    /// lookups served from memory
+    pub hits: usize,
+    /// lookups that had to read the block from disk
+    pub misses: usize,
+    /// cached blocks dropped to make room for others
+    pub evictions: usize,
+    /// dirty blocks written back to disk
+    pub writebacks: usize,
+}
+
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+static EVICTIONS: AtomicUsize = AtomicUsize::new(0);
+static WRITEBACKS: AtomicUsize = AtomicUsize::new(0);
+
 /// Cached block inside memory
 pub struct BlockCache {
     /// cached block data
@@ -65,6 +84,7 @@ impl BlockCache {
     pub fn sync(&mut self) {
         if self.modified {
             self.modified = false;
+            WRITEBACKS.fetch_add(1, Ordering::Relaxed);
             self.block_device.write_block(self.block_id, &self.cache);
         }
     }
@@ -79,6 +99,9 @@ impl Drop for BlockCache {
 /// Use a block cache of 16 blocks
 const BLOCK_CACHE_SIZE: usize = 16;
 
+/// Keeps at most BLOCK_CACHE_SIZE blocks in memory, ordered from the least
+/// recently used at the front to the most recently used at the back.
+/// Dirty blocks are only written back when they are evicted or synced.
 pub struct BlockCacheManager {
     queue: VecDeque<(usize, Arc<Mutex<BlockCache>>)>,
 }
@@ -93,19 +116,27 @@ impl BlockCacheManager {
         block_id: usize,
         block_device: Arc<dyn BlockDevice>,
     ) -> Arc<Mutex<BlockCache>> {
-        if let Some(pair) = self.queue
+        if let Some(idx) = self.queue
             .iter()
-            .find(|pair| pair.0 == block_id) {
-                Arc::clone(&pair.1)
+            .position(|pair| pair.0 == block_id) {
+            HITS.fetch_add(1, Ordering::Relaxed);
+            // move to the most recently used end
+            let pair = self.queue.remove(idx).unwrap();
+            let block_cache = Arc::clone(&pair.1);
+            self.queue.push_back(pair);
+            block_cache
         } else {
+            MISSES.fetch_add(1, Ordering::Relaxed);
             // substitute
             if self.queue.len() == BLOCK_CACHE_SIZE {
-                // from front to tail
+                // the least recently used block which is not in use
                 if let Some((idx, _)) = self.queue
                     .iter()
                     .enumerate()
                     .find(|(_, pair)| Arc::strong_count(&pair.1) == 1) {
-                    self.queue.drain(idx..=idx);
+                    EVICTIONS.fetch_add(1, Ordering::Relaxed);
+                    // written back by BlockCache::drop if dirty
+                    self.queue.remove(idx);
                 } else {
                     panic!("Run out of BlockCache!");
                 }
@@ -142,3 +173,24 @@ pub fn block_cache_sync_all() {
         cache.lock().sync();
     }
 }
+
+/// Sync the cached blocks whose ids are in block_ids to block device,
+/// none of them may be locked by the caller
+pub fn sync_blocks(block_ids: &[usize]) {
+    let manager = BLOCK_CACHE_MANAGER.lock();
+    for (block_id, cache) in manager.queue.iter() {
+        if block_ids.contains(block_id) {
+            cache.lock().sync();
+        }
+    }
+}
+
+/// Get the counters of the block cache
+pub fn block_cache_stats() -> BlockCacheStats {
+    BlockCacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        evictions: EVICTIONS.load(Ordering::Relaxed),
+        writebacks: WRITEBACKS.load(Ordering::Relaxed),
+    }
+}
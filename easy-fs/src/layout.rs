@@ -10,9 +10,9 @@ use alloc::vec::Vec;
 /// Magic number for sanity check
 const EFS_MAGIC: u32 = 0x3b800001;
 /// The max number of direct inodes
-const INODE_DIRECT_COUNT: usize = 28;
+const INODE_DIRECT_COUNT: usize = 27;
 /// The max length of inode name
-const NAME_LENGTH_LIMIT: usize = 27;
+pub const NAME_LENGTH_LIMIT: usize = 27;
 /// The max number of indirect1 inodes
 const INODE_INDIRECT1_COUNT: usize = BLOCK_SZ / 4;
 /// The max number of indirect2 inodes
@@ -78,6 +78,8 @@ impl SuperBlock {
 pub enum DiskInodeType {
     File,
     Directory,
+    /// A named pipe, its data lives in the kernel instead of on disk
+    Fifo,
 }
 
 /// A indirect block
@@ -92,6 +94,8 @@ pub struct DiskInode {
     pub direct: [u32; INODE_DIRECT_COUNT],
     pub indirect1: u32,
     pub indirect2: u32,
+    /// The number of directory entries referring to this inode
+    pub nlink: u32,
     type_: DiskInodeType,
 }
 
@@ -103,12 +107,17 @@ impl DiskInode {
         self.direct.iter_mut().for_each(|v| *v = 0);
         self.indirect1 = 0;
         self.indirect2 = 0;
+        self.nlink = 1;
         self.type_ = type_;
     }
     /// Whether this inode is a directory
     pub fn is_dir(&self) -> bool {
         self.type_ == DiskInodeType::Directory
     }
+    /// Whether this inode is a named pipe
+    pub fn is_fifo(&self) -> bool {
+        self.type_ == DiskInodeType::Fifo
+    }
     /// Whether this inode is a file
     #[allow(unused)]
     pub fn is_file(&self) -> bool {
@@ -343,6 +352,33 @@ impl DiskInode {
         self.indirect2 = 0;
         v
     }
+    /// Get ids of all data blocks and index blocks of current disk inode
+    pub fn block_ids(&self, block_device: &Arc<dyn BlockDevice>) -> Vec<u32> {
+        let data_blocks = self.data_blocks();
+        let mut v: Vec<u32> = (0..data_blocks)
+            .map(|inner_id| self.get_block_id(inner_id, block_device))
+            .collect();
+        let data_blocks = data_blocks as usize;
+        // indirect1 block
+        if data_blocks > INODE_DIRECT_COUNT {
+            v.push(self.indirect1);
+        }
+        // indirect2 block and its low-level indirect1 blocks
+        if data_blocks > INDIRECT1_BOUND {
+            v.push(self.indirect2);
+            let count = (data_blocks - INDIRECT1_BOUND + INODE_INDIRECT1_COUNT - 1)
+                / INODE_INDIRECT1_COUNT;
+            get_block_cache(
+                self.indirect2 as usize,
+                Arc::clone(block_device),
+            )
+            .lock()
+            .read(0, |indirect2: &IndirectBlock| {
+                v.extend_from_slice(&indirect2[..count]);
+            });
+        }
+        v
+    }
     /// Read data from current disk inode
     pub fn read_at(
         &self,
@@ -474,4 +510,8 @@ impl DirEntry {
     pub fn inode_number(&self) -> u32 {
         self.inode_number
     }
+    /// Whether the entry is a free slot left by a removed entry
+    pub fn is_empty(&self) -> bool {
+        self.name[0] == 0
+    }
 }
@@ -6,7 +6,7 @@ use super::{
     EasyFileSystem,
     DIRENT_SZ,
     get_block_cache,
-    block_cache_sync_all,
+    sync_blocks,
 };
 use alloc::sync::Arc;
 use alloc::string::String;
@@ -50,12 +50,13 @@ impl Inode {
             Arc::clone(&self.block_device)
         ).lock().modify(self.block_offset, f)
     }
-    /// Find inode under a disk inode by name
-    fn find_inode_id(
+    /// Find the directory entry under a disk inode by name,
+    /// return its index and inode number
+    fn find_dirent(
         &self,
         name: &str,
         disk_inode: &DiskInode,
-    ) -> Option<u32> {
+    ) -> Option<(usize, u32)> {
         // assert it is a directory
         assert!(disk_inode.is_dir());
         let file_count = (disk_inode.size as usize) / DIRENT_SZ;
@@ -69,12 +70,56 @@ impl Inode {
                 ),
                 DIRENT_SZ,
             );
-            if dirent.name() == name {
-                return Some(dirent.inode_number() as u32);
+            if !dirent.is_empty() && dirent.name() == name {
+                return Some((i, dirent.inode_number()));
             }
         }
         None
     }
+    /// Find inode under a disk inode by name
+    fn find_inode_id(
+        &self,
+        name: &str,
+        disk_inode: &DiskInode,
+    ) -> Option<u32> {
+        self.find_dirent(name, disk_inode).map(|(_, inode_id)| inode_id)
+    }
+    /// Add a directory entry to a disk inode,
+    /// reuse the first free slot or append it to the end
+    fn add_dirent(
+        &self,
+        name: &str,
+        inode_id: u32,
+        disk_inode: &mut DiskInode,
+        fs: &mut MutexGuard<EasyFileSystem>,
+    ) {
+        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let mut dirent = DirEntry::empty();
+        let slot = (0..file_count).find(|&i| {
+            disk_inode.read_at(
+                DIRENT_SZ * i,
+                dirent.as_bytes_mut(),
+                &self.block_device,
+            );
+            dirent.is_empty()
+        });
+        let slot = match slot {
+            Some(slot) => slot,
+            None => {
+                // increase size
+                let new_size = (file_count + 1) * DIRENT_SZ;
+                self.increase_size(new_size as u32, disk_inode, fs);
+                file_count
+            }
+        };
+        // write dirent
+        let dirent = DirEntry::new(name, inode_id);
+        disk_inode.write_at(
+            slot * DIRENT_SZ,
+            dirent.as_bytes(),
+            &self.block_device,
+        );
+    }
     /// Find inode under current inode by name
     pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
         let fs = self.fs.lock();
@@ -108,8 +153,8 @@ impl Inode {
         }
         disk_inode.increase_size(new_size, v, &self.block_device);
     }
-    /// Create inode under current inode by name
-    pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+    /// Create inode of the given type under current inode by name
+    fn create_inode(&self, name: &str, type_: DiskInodeType) -> Option<Arc<Inode>> {
         let mut fs = self.fs.lock();
         if self.modify_disk_inode(|root_inode| {
             // assert it is a directory
@@ -129,25 +174,14 @@ impl Inode {
             new_inode_block_id as usize,
             Arc::clone(&self.block_device)
         ).lock().modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
-            new_inode.initialize(DiskInodeType::File);
+            new_inode.initialize(type_);
         });
         self.modify_disk_inode(|root_inode| {
-            // append file in the dirent
-            let file_count = (root_inode.size as usize) / DIRENT_SZ;
-            let new_size = (file_count + 1) * DIRENT_SZ;
-            // increase size
-            self.increase_size(new_size as u32, root_inode, &mut fs);
-            // write dirent
-            let dirent = DirEntry::new(name, new_inode_id);
-            root_inode.write_at(
-                file_count * DIRENT_SZ,
-                dirent.as_bytes(),
-                &self.block_device,
-            );
+            // add file in the dirent
+            self.add_dirent(name, new_inode_id, root_inode, &mut fs);
         });
 
         let (block_id, block_offset) = fs.get_disk_inode_pos(new_inode_id);
-        block_cache_sync_all();
         // return inode
         Some(Arc::new(Self::new(
             block_id,
@@ -157,6 +191,68 @@ impl Inode {
         )))
         // release efs lock automatically by compiler
     }
+    /// Create inode under current inode by name
+    pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::File)
+    }
+    /// Create a directory under current inode by name
+    pub fn create_dir(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Directory)
+    }
+    /// Create a named pipe under current inode by name
+    pub fn create_fifo(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Fifo)
+    }
+    /// Create a hard link name under current inode to inode,
+    /// fail if name already exists
+    pub fn link(&self, name: &str, inode: &Inode) -> bool {
+        let mut fs = self.fs.lock();
+        let inode_id = fs.get_inode_id(inode.block_id as u32, inode.block_offset);
+        if !self.modify_disk_inode(|root_inode| {
+            if self.find_inode_id(name, root_inode).is_some() {
+                return false;
+            }
+            self.add_dirent(name, inode_id, root_inode, &mut fs);
+            true
+        }) {
+            return false;
+        }
+        inode.modify_disk_inode(|disk_inode| disk_inode.nlink += 1);
+        true
+    }
+    /// Remove the entry name under current inode,
+    /// return the inode it referred to.
+    /// The inode itself is kept until it is released by `destroy`
+    pub fn unlink(&self, name: &str) -> Option<Arc<Inode>> {
+        let fs = self.fs.lock();
+        let inode_id = self.modify_disk_inode(|root_inode| {
+            let (index, inode_id) = self.find_dirent(name, root_inode)?;
+            // leave a free slot
+            root_inode.write_at(
+                index * DIRENT_SZ,
+                DirEntry::empty().as_bytes(),
+                &self.block_device,
+            );
+            Some(inode_id)
+        })?;
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let inode = Arc::new(Self::new(
+            block_id,
+            block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        ));
+        inode.modify_disk_inode(|disk_inode| disk_inode.nlink -= 1);
+        Some(inode)
+    }
+    /// Release the data blocks and the disk inode of current inode,
+    /// it must not be referred to by any entry
+    pub fn destroy(&self) {
+        self.clear();
+        let mut fs = self.fs.lock();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        fs.dealloc_inode(inode_id);
+    }
     /// List inodes under current inode
     pub fn ls(&self) -> Vec<String> {
         let _fs = self.fs.lock();
@@ -173,11 +269,69 @@ impl Inode {
                     ),
                     DIRENT_SZ,
                 );
-                v.push(String::from(dirent.name()));
+                if !dirent.is_empty() {
+                    v.push(String::from(dirent.name()));
+                }
             }
             v
         })
     }
+    /// Inode number of current inode
+    pub fn inode_id(&self) -> u32 {
+        self.fs.lock().get_inode_id(self.block_id as u32, self.block_offset)
+    }
+    /// Whether current inode is a directory
+    pub fn is_dir(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_dir())
+    }
+    /// Whether current inode is a named pipe
+    pub fn is_fifo(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_fifo())
+    }
+    /// The number of directory entries referring to current inode
+    pub fn nlink(&self) -> u32 {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.nlink)
+    }
+    /// Find the first entry under current inode whose index is not less than from,
+    /// return its index, name and inode
+    pub fn read_dirent(&self, from: usize) -> Option<(usize, String, Arc<Inode>)> {
+        let fs = self.fs.lock();
+        let (index, dirent) = self.read_disk_inode(|disk_inode| {
+            assert!(disk_inode.is_dir());
+            let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+            (from..file_count).find_map(|i| {
+                let mut dirent = DirEntry::empty();
+                assert_eq!(
+                    disk_inode.read_at(
+                        i * DIRENT_SZ,
+                        dirent.as_bytes_mut(),
+                        &self.block_device,
+                    ),
+                    DIRENT_SZ,
+                );
+                if dirent.is_empty() {
+                    None
+                } else {
+                    Some((i, dirent))
+                }
+            })
+        })?;
+        let (block_id, block_offset) = fs.get_disk_inode_pos(dirent.inode_number());
+        Some((index, String::from(dirent.name()), Arc::new(Self::new(
+            block_id,
+            block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        ))))
+    }
+    /// Size of current inode in bytes
+    pub fn size(&self) -> usize {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.size as usize)
+    }
     /// Read data from current inode
     pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
         let _fs = self.fs.lock();
@@ -192,7 +346,6 @@ impl Inode {
             self.increase_size((offset + buf.len()) as u32, disk_inode, &mut fs);
             disk_inode.write_at(offset, buf, &self.block_device)
         });
-        block_cache_sync_all();
         size
     }
     /// Clear the data in current inode
@@ -206,6 +359,19 @@ impl Inode {
                 fs.dealloc_data(data_block);
             }
         });
-        block_cache_sync_all();
+    }
+    /// Write current inode, its data and index blocks
+    /// and the allocation bitmaps back to block device
+    pub fn sync(&self) {
+        let fs = self.fs.lock();
+        let mut block_ids: Vec<usize> = self
+            .read_disk_inode(|disk_inode| disk_inode.block_ids(&self.block_device))
+            .into_iter()
+            .map(|block_id| block_id as usize)
+            .collect();
+        block_ids.push(self.block_id);
+        block_ids.extend(fs.inode_bitmap.block_ids());
+        block_ids.extend(fs.data_bitmap.block_ids());
+        sync_blocks(&block_ids);
     }
 }
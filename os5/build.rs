@@ -1,67 +1,132 @@
 use std::fs::{read_dir, File};
 use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
 
 fn main() {
-    println!("cargo:rerun-if-changed=../user/src/");
-    println!("cargo:rerun-if-changed={}", TARGET_PATH);
-    insert_app_data().unwrap();
+    emit_kernel_version();
+    emit_kernel_symbols().unwrap();
+    pack_initramfs().unwrap();
 }
 
-static TARGET_PATH: &str = "../user/build/elf/";
-
-fn insert_app_data() -> Result<()> {
-    let mut f = File::create("src/link_app.S").unwrap();
-    let mut apps: Vec<_> = read_dir("../user/build/elf/")
-        .unwrap()
-        .into_iter()
-        .map(|dir_entry| {
-            let mut name_with_ext = dir_entry.unwrap().file_name().into_string().unwrap();
-            name_with_ext.drain(name_with_ext.find('.').unwrap()..name_with_ext.len());
-            name_with_ext
+//uname 返回的内核版本：构建模式与开启的特性，例如 "#1 release sched-cfs"
+fn emit_kernel_version() {
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
         })
         .collect();
-    apps.sort();
+    features.sort();
+    let profile = std::env::var("PROFILE").unwrap_or_default();
+    println!(
+        "cargo:rustc-env=KERNEL_VERSION=#1 {}",
+        [vec![profile], features].concat().join(" ")
+    );
+}
 
-    writeln!(
-        f,
-        r#"
-    .align 3
-    .section .data
-    .global _num_app
-_num_app:
-    .quad {}"#,
-        apps.len()
-    )?;
+//内核函数的符号表，由 Makefile 在第一次链接后用 rust-nm 导出，可以用 KSYMS 指定其他文件
+static KSYMS_FILE: &str = "target/ksyms.txt";
 
-    for i in 0..apps.len() {
-        writeln!(f, r#"    .quad app_{}_start"#, i)?;
+//把 nm 输出的 .text 符号（"地址 类型 名字"）转换为 ksyms.rs 中按地址升序排列的 KSYMS 数组，
+//panic 时用来符号化回溯的返回地址；符号表还不存在时生成空数组
+fn emit_kernel_symbols() -> Result<()> {
+    println!("cargo:rerun-if-env-changed=KSYMS");
+    let path = std::env::var("KSYMS").unwrap_or_else(|_| KSYMS_FILE.into());
+    println!("cargo:rerun-if-changed={}", path);
+    let mut symbols: Vec<(u64, String)> = std::fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, ' ');
+            let addr = u64::from_str_radix(fields.next()?, 16).ok()?;
+            let kind = fields.next()?;
+            let name = fields.next()?;
+            matches!(kind, "t" | "T").then(|| (addr, name.to_string()))
+        })
+        .collect();
+    symbols.sort();
+    symbols.dedup_by_key(|(addr, _)| *addr);
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("ksyms.rs");
+    let mut file = File::create(out)?;
+    writeln!(file, "static KSYMS: &[(usize, &str)] = &[")?;
+    for (addr, name) in symbols {
+        writeln!(file, "    ({:#x}, {:?}),", addr, name)?;
     }
-    writeln!(f, r#"    .quad app_{}_end"#, apps.len() - 1)?;
+    writeln!(file, "];")
+}
 
-    writeln!(
-        f,
-        r#"
-    .global _app_names
-_app_names:"#
-    )?;
-    for app in apps.iter() {
-        writeln!(f, r#"    .string "{}""#, app)?;
+//用户程序的 ELF 文件所在的目录，由用户程序的 Makefile 生成，可以用 INITRAMFS_DIR 指定其他目录
+static APP_ELF_DIR: &str = "../user/build/elf/";
+
+//把应用打包为 cpio（newc 格式）归档，内核用 include_bytes! 把它嵌入镜像作为 initramfs。
+//只有开启 initramfs 特性时才打包应用，否则归档中只有挂载点，内核镜像的大小不变。
+//应用名是 ELF 文件去掉 .elf 后缀的名字，放在归档的根目录中
+fn pack_initramfs() -> Result<()> {
+    println!("cargo:rerun-if-env-changed=INITRAMFS_DIR");
+    let out = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("initramfs.cpio");
+    let mut archive = File::create(out)?;
+    let mut ino = 1;
+    //devfs、procfs 与磁盘上的 easy-fs 的挂载点，只读的 initramfs 中不能再创建目录
+    for dir in ["dev", "proc", "mnt"] {
+        write_cpio_entry(&mut archive, ino, 0o040755, dir, &[])?;
+        ino += 1;
+    }
+    if std::env::var_os("CARGO_FEATURE_INITRAMFS").is_some() {
+        let dir = std::env::var("INITRAMFS_DIR").unwrap_or_else(|_| APP_ELF_DIR.into());
+        println!("cargo:rerun-if-changed={}", dir);
+        let mut apps: Vec<PathBuf> = read_dir(Path::new(&dir))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_>>()?;
+        apps.retain(|path| path.extension().map_or(false, |ext| ext == "elf"));
+        apps.sort();
+        for path in apps {
+            println!("cargo:rerun-if-changed={}", path.display());
+            let name = path.file_stem().unwrap().to_str().unwrap();
+            write_cpio_entry(&mut archive, ino, 0o100755, name, &std::fs::read(&path)?)?;
+            ino += 1;
+        }
     }
+    write_cpio_entry(&mut archive, 0, 0, "TRAILER!!!", &[])
+}
 
-    for (idx, app) in apps.iter().enumerate() {
-        println!("app_{}: {}", idx, app);
-        writeln!(
-            f,
-            r#"
-    .section .data
-    .global app_{0}_start
-    .global app_{0}_end
-    .align 3
-app_{0}_start:
-    .incbin "{2}{1}.elf"
-app_{0}_end:"#,
-            idx, app, TARGET_PATH
-        )?;
+//写入一项 newc 格式的记录：110 字节的头部、以 0 结尾的名字与文件内容，名字与内容都补齐到 4 字节
+fn write_cpio_entry(
+    archive: &mut File,
+    ino: u32,
+    mode: u32,
+    name: &str,
+    data: &[u8],
+) -> Result<()> {
+    let nlink = if mode & 0o040000 != 0 { 2 } else { 1 };
+    //依次是 ino、mode、uid、gid、nlink、mtime、filesize、devmajor、devminor、rdevmajor、rdevminor、namesize 与 check
+    let fields = [
+        ino,
+        mode,
+        0,
+        0,
+        nlink,
+        0,
+        data.len() as u32,
+        0,
+        0,
+        0,
+        0,
+        name.len() as u32 + 1,
+        0,
+    ];
+    let mut header = String::from("070701");
+    for field in fields {
+        header.push_str(&format!("{:08x}", field));
     }
-    Ok(())
+    archive.write_all(header.as_bytes())?;
+    archive.write_all(name.as_bytes())?;
+    archive.write_all(&[0])?;
+    archive.write_all(&vec![0; pad4(header.len() + name.len() + 1)])?;
+    archive.write_all(data)?;
+    archive.write_all(&vec![0; pad4(data.len())])
+}
+
+//补齐到 4 字节需要的字节数
+fn pad4(len: usize) -> usize {
+    (4 - len % 4) % 4
 }
@@ -0,0 +1,193 @@
+//! 内核 panic 时的现场信息：寄存器、当前任务、沿帧指针回溯的调用栈与最近的任务切换记录
+//!
+//! 内核以 -Cforce-frame-pointers=yes 构建，每个函数的栈帧中 fp - 8 处保存返回地址 ra，
+//! fp - 16 处保存调用者的 fp。符号表由 Makefile 在第一次链接后用 rust-nm 导出，
+//! 再由 build.rs 生成 ksyms.rs 嵌入第二次构建的内核；嵌入符号表只改变 .rodata 的大小，
+//! 位于它之前的 .text 中函数的地址不变。
+
+use crate::config::{KERNEL_STACK_SIZE, KERNEL_STACK_SLOTS, PAGE_SIZE, TRAMPOLINE};
+use crate::task::{
+    kernel_stack_position, try_current_task, try_recent_switches, SwitchRecord, IDLE_PID,
+};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+//KSYMS: &[(usize, &str)]，按地址升序排列的内核函数符号
+include!(concat!(env!("OUT_DIR"), "/ksyms.rs"));
+
+//最多回溯的栈帧数，防止损坏的帧指针链成环
+const MAX_FRAMES: usize = 64;
+//panic 时输出的任务切换记录条数
+const RECENT_SWITCHES: usize = 8;
+
+//panic 处理过程中再次 panic 时不再输出现场信息，以免无限递归
+static PANICKING: AtomicBool = AtomicBool::new(false);
+
+/// 第一次进入 panic 处理时返回 true，之后返回 false
+pub fn enter_panic() -> bool {
+    !PANICKING.swap(true, Ordering::Relaxed)
+}
+
+/// 输出 panic 时的现场信息
+pub fn dump() {
+    dump_registers();
+    dump_current_task();
+    print_backtrace();
+    dump_recent_switches();
+}
+
+fn dump_registers() {
+    let (ra, sp, fp, tp): (usize, usize, usize, usize);
+    let (sstatus, sepc, scause, stval): (usize, usize, usize, usize);
+    unsafe {
+        core::arch::asm!(
+            "mv {0}, ra",
+            "mv {1}, sp",
+            "mv {2}, s0",
+            "mv {3}, tp",
+            out(reg) ra,
+            out(reg) sp,
+            out(reg) fp,
+            out(reg) tp,
+        );
+        core::arch::asm!(
+            "csrr {0}, sstatus",
+            "csrr {1}, sepc",
+            "csrr {2}, scause",
+            "csrr {3}, stval",
+            out(reg) sstatus,
+            out(reg) sepc,
+            out(reg) scause,
+            out(reg) stval,
+        );
+    }
+    println!("[kernel] Registers:");
+    println!("    ra      {:#018x}  sp     {:#018x}", ra, sp);
+    println!("    fp      {:#018x}  tp     {:#018x}", fp, tp);
+    println!("    sstatus {:#018x}  sepc   {:#018x}", sstatus, sepc);
+    println!("    scause  {:#018x}  stval  {:#018x}", scause, stval);
+}
+
+//PROCESSOR 或任务控制块正被借用时只输出能拿到的部分
+fn dump_current_task() {
+    match try_current_task() {
+        Some(task) => match task.try_inner_exclusive_access() {
+            Some(inner) => println!(
+                "[kernel] Current task: pid {} tid {} ({})",
+                task.getpid(),
+                task.tid,
+                inner.name
+            ),
+            None => println!(
+                "[kernel] Current task: pid {} tid {}",
+                task.getpid(),
+                task.tid
+            ),
+        },
+        None => println!("[kernel] Current task: none"),
+    }
+}
+
+/// 从调用者的栈帧开始沿帧指针回溯，输出每一层的返回地址及其所在的函数
+#[inline(never)]
+pub fn print_backtrace() {
+    let mut fp: usize;
+    unsafe { core::arch::asm!("mv {}, s0", out(reg) fp) };
+    println!("[kernel] Backtrace:");
+    let mut prev: Option<(usize, (usize, usize))> = None;
+    for depth in 0..MAX_FRAMES {
+        let stack = match stack_bounds(fp) {
+            Some(stack) => stack,
+            None => break,
+        };
+        //ra 与上一层的 fp 都要落在同一个栈内，同一个栈上的帧地址只能向高处增长
+        if fp % 8 != 0 || fp < stack.0 + 16 {
+            break;
+        }
+        if let Some((prev_fp, prev_stack)) = prev {
+            if prev_stack == stack && fp <= prev_fp {
+                break;
+            }
+        }
+        let (ra, next_fp) = unsafe { (*((fp - 8) as *const usize), *((fp - 16) as *const usize)) };
+        if ra == 0 {
+            break;
+        }
+        //返回地址指向调用指令的下一条，减 1 后落在调用指令内，符号化为调用者
+        match symbolize(ra - 1) {
+            Some((name, offset)) => println!("    #{:<2} {:#018x} {}+{:#x}", depth, ra, name, offset + 1),
+            None => println!("    #{:<2} {:#018x} ??", depth, ra),
+        }
+        prev = Some((fp, stack));
+        fp = next_fp;
+    }
+    if KSYMS.is_empty() {
+        println!("[kernel] (no symbol table embedded, build with `make` to symbolize)");
+    }
+}
+
+//fp 所在的内核栈 [bottom, top]：启动栈、内核态 trap 使用的栈或某个线程的内核栈
+fn stack_bounds(fp: usize) -> Option<(usize, usize)> {
+    extern "C" {
+        fn boot_stack();
+        fn boot_stack_top();
+        fn kernel_trap_stack();
+        fn kernel_trap_stack_top();
+    }
+    //栈帧的 fp 指向帧的顶端，可以恰好等于栈顶
+    let stacks = [
+        (boot_stack as usize, boot_stack_top as usize),
+        (kernel_trap_stack as usize, kernel_trap_stack_top as usize),
+    ];
+    if let Some(&stack) = stacks.iter().find(|(bottom, top)| (*bottom..=*top).contains(&fp)) {
+        return Some(stack);
+    }
+    //每个槽位占据 KERNEL_STACK_SIZE + PAGE_SIZE，由 fp 到 TRAMPOLINE 的距离求出槽位
+    if fp > TRAMPOLINE {
+        return None;
+    }
+    let slot = (TRAMPOLINE - fp) / (KERNEL_STACK_SIZE + PAGE_SIZE);
+    if slot >= KERNEL_STACK_SLOTS {
+        return None;
+    }
+    let (bottom, top) = kernel_stack_position(slot);
+    (bottom..=top).contains(&fp).then(|| (bottom, top))
+}
+
+/// 返回 addr 所在的函数名及 addr 相对函数起始地址的偏移
+pub fn symbolize(addr: usize) -> Option<(&'static str, usize)> {
+    let index = match KSYMS.binary_search_by_key(&addr, |&(start, _)| start) {
+        Ok(index) => index,
+        Err(0) => return None,
+        Err(index) => index - 1,
+    };
+    extern "C" {
+        fn etext();
+    }
+    if addr >= etext as usize {
+        return None;
+    }
+    let (start, name) = KSYMS[index];
+    Some((name, addr - start))
+}
+
+//PROCESSOR 正被借用时没有切换记录可输出
+fn dump_recent_switches() {
+    let mut records = [SwitchRecord {
+        time_us: 0,
+        from_pid: IDLE_PID,
+        to_pid: IDLE_PID,
+        reason: 0,
+    }; RECENT_SWITCHES];
+    let count = try_recent_switches(&mut records);
+    println!("[kernel] Recent task switches:");
+    for record in &records[..count] {
+        let pid = |pid: usize| if pid == IDLE_PID { -1 } else { pid as isize };
+        println!(
+            "    {:>12}us  {:>5} -> {:<5} reason {}",
+            record.time_us,
+            pid(record.from_pid),
+            pid(record.to_pid),
+            record.reason
+        );
+    }
+}
@@ -1,12 +1,121 @@
 pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// 每个用户栈在虚拟地址空间中预留的大小：栈顶在预留区域的顶部，初始只映射 USER_STACK_SIZE，
+/// 之后在缺页时按需向下增长，最多增长到 RLIMIT_STACK 且不超过预留区域
+pub const USER_STACK_RESERVE: usize = 0x10_0000;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 20;
 pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
 pub const MEMORY_END: usize = 0x88000000;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
+/// 一个 2MiB 大页（SV39 第 1 级页表中的叶子页表项）包含的页面数
+pub const HUGE_PAGE_PAGES: usize = 512;
+/// 最多使用的 ASID 个数（包括内核的 0 号），实际个数还受硬件实现的 ASID 位数限制。
+/// 调小它可以让 ASID 很快用完，用来测试按代回收
+pub const ASID_LIMIT: usize = 1 << 16;
 pub const MAX_SYSCALL_NUM: usize = 500;
-pub const BIG_STRIDE: usize = usize::MAX;
+/// 每个进程最多同时打开的文件数，文件描述符总是小于它
+pub const MAX_FDS: usize = 256;
+/// 管道中环形缓冲区的大小（字节），缓冲区满时写者阻塞
+pub const PIPE_BUF_SIZE: usize = 4096;
+/// 优先级至少为 2，因此步长不超过 BIG_STRIDE / 2，pass 的有符号回绕比较总是成立
+pub const BIG_STRIDE: u64 = u64::MAX;
+/// 按优先级划分的时间片表：(优先级下限, 时间片长度)，时间片以时钟中断次数计，按顺序匹配第一个满足的项
+pub const TIME_SLICE_TABLE: [(usize, usize); 3] = [(64, 4), (16, 2), (0, 1)];
+/// 在就绪队列中等待超过这么多微秒的任务被视为饥饿，将被提升
+pub const AGING_THRESHOLD_US: usize = 500_000;
+/// 每经过这么多次时钟中断检查一次就绪队列中的饥饿任务
+pub const AGING_INTERVAL: usize = 10;
+/// 每隔这么多微秒对可运行的任务数采样一次，更新系统负载
+pub const LOADAVG_INTERVAL_US: usize = 5_000_000;
+/// MLFQ 队列的级数
+pub const MLFQ_LEVELS: usize = 3;
+/// MLFQ 每一级队列的时间片长度（时钟中断次数），级别越低时间片越长
+pub const MLFQ_TIME_SLICES: [usize; MLFQ_LEVELS] = [1, 2, 4];
+/// 每经过这么多次时钟中断，MLFQ 将所有任务提升回最高级队列
+pub const MLFQ_BOOST_INTERVAL: usize = 100;
+/// CFS 中权重为 1 的基准优先级：以该优先级运行时虚拟运行时间与真实运行时间同速增长
+pub const CFS_NICE0_PRIORITY: u64 = 16;
+/// 实时任务优先级的上限，实时优先级取值为 1..=RT_PRIO_MAX，数值越大越优先
+pub const RT_PRIO_MAX: usize = 99;
+/// SCHED_RR 实时任务的时间片长度（时钟中断次数）
+pub const RT_RR_TIME_SLICE: usize = 2;
+/// 任务切换跟踪环形缓冲区能保存的记录条数
+pub const SWITCH_TRACE_LEN: usize = 256;
+/// 进程名的最大长度（字节，含结尾的 \0），足以容纳全部应用名
+pub const TASK_NAME_LEN: usize = 32;
+/// 进程标识符的上限（不含），PID 从 0 开始，总是分配编号最小的空闲 PID
+pub const PID_MAX: usize = 4096;
+/// 内核栈槽位的数量，即系统中最多同时存在的线程数
+pub const KERNEL_STACK_SLOTS: usize = 4096;
+/// 用户堆的起始地址，远离 ELF 之后依次向上排列的各线程用户栈
+pub const USER_HEAP_BASE: usize = 0x20_0000_0000;
+/// mmap 没有指定地址时，内核从这里开始向上为映射选择位置
+pub const MMAP_BASE: usize = 0x10_0000_0000;
+/// 用户程序可以使用的虚拟地址上限，即 SV39 地址空间的低半部分
+pub const USER_SPACE_END: usize = 0x40_0000_0000;
+/// 内核从用户地址空间读取的路径、命令行参数等字符串的最大长度，包括结尾的 \0
+pub const USER_STR_MAX: usize = PAGE_SIZE;
+/// exec/spawn 的命令行参数与环境变量压栈后占用的最大字节数（字符串与指针数组），须放得进初始映射的用户栈
+pub const ARG_MAX: usize = USER_STACK_SIZE / 2;
+/// 处理器核数，目前只有单核
+pub const CPU_NUM: usize = 1;
+/// 允许在所有核上运行的亲和性掩码
+pub const CPU_MASK_ALL: usize = (1 << CPU_NUM) - 1;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT: usize = TRAMPOLINE - PAGE_SIZE;
+/// 信号处理函数返回时跳转到的用户态代码页，其中只有一次 sigreturn 系统调用
+pub const SIGRETURN_TRAMPOLINE: usize = TRAP_CONTEXT - PAGE_SIZE;
 pub const CLOCK_FREQ: usize = 12500000;
+/// QEMU virt 平台上第一个 virtio 设备的 MMIO 寄存器，这里挂载存放应用的文件系统镜像
+pub const VIRTIO0: usize = 0x1000_1000;
+/// QEMU virt 平台上第二个 virtio 设备的 MMIO 寄存器，这里挂载交换区
+pub const VIRTIO1: usize = 0x1000_2000;
+/// VIRTIO0 与 VIRTIO1 在 PLIC 上的中断号
+pub const VIRTIO0_IRQ: usize = 1;
+pub const VIRTIO1_IRQ: usize = 2;
+/// QEMU virt 平台上平台级中断控制器 PLIC 的 MMIO 寄存器
+pub const PLIC: usize = 0x0c00_0000;
+/// QEMU virt 平台上 Goldfish RTC 的 MMIO 寄存器
+pub const RTC: usize = 0x10_1000;
+/// QEMU virt 平台上 16550 串口的 MMIO 寄存器与它在 PLIC 上的中断号
+pub const UART0: usize = 0x1000_0000;
+pub const UART0_IRQ: usize = 10;
+/// QEMU virt 平台上 PCIe 主桥的 ECAM 配置空间，这里只用到 0 号总线的 1MiB
+pub const PCI_ECAM: usize = 0x3000_0000;
+pub const PCI_ECAM_BUS0_SIZE: usize = 0x10_0000;
+/// QEMU virt 平台上 PCI I/O 空间的窗口，I/O 端口 port 位于 PCI_PIO + port
+pub const PCI_PIO: usize = 0x0300_0000;
+pub const PCI_PIO_SIZE: usize = 0x1_0000;
+/// 内核调试器使用的第二个串口（QEMU 的 pci-serial 设备）分配到的 I/O 端口
+pub const GDB_SERIAL_PORT: usize = 0x1000;
+/// 内核需要恒等映射的 MMIO 区间 (起始地址, 长度)
+pub const MMIO: &[(usize, usize)] = &[
+    (VIRTIO0, 0x1000),
+    (VIRTIO1, 0x1000),
+    (PLIC, 0x40_0000),
+    (RTC, 0x1000),
+    (UART0, 0x1000),
+    (PCI_ECAM, PCI_ECAM_BUS0_SIZE),
+    (PCI_PIO, PCI_PIO_SIZE),
+];
+/// 交换区能容纳的页面数，交换设备的大小至少为 SWAP_SLOTS * PAGE_SIZE 字节
+pub const SWAP_SLOTS: usize = 8192;
+/// 空闲页帧少于这个数时开始换出页面，留下的页帧供页表等不能换出的分配使用
+pub const SWAP_RESERVE_FRAMES: usize = 32;
+/// 每隔这么多个时钟中断收集一次所有用户页面的访问位，更新页面的年龄
+pub const PAGE_AGING_TICKS: usize = 10;
+/// exec 与 spawn 按程序名查找可执行文件时默认搜索的目录，格式与环境变量 PATH 相同，目录之间以 : 分隔
+pub const DEFAULT_PATH: &str = "/";
+/// 每隔这么多个时钟中断把块缓存中的脏块写回磁盘
+pub const WRITEBACK_INTERVAL_TICKS: usize = 100;
+/// 终端输入缓冲区的字节数，规范模式下一行最多也是这么多字节，放不下的输入被丢弃
+pub const TTY_BUF_SIZE: usize = 1024;
+/// 内核日志环形缓冲区的字节数，写满后覆盖最旧的输出，用户态通过 syslog 读取
+pub const LOG_BUF_SIZE: usize = 16 * 1024;
+/// 确定性调度模式下，每执行这么多次系统调用产生一次虚拟时钟中断
+#[cfg(feature = "deterministic")]
+pub const DETERMINISTIC_TICK_SYSCALLS: usize = 16;
+/// 确定性调度模式下，伪时钟每被读取一次前进的周期数（1ms）
+#[cfg(feature = "deterministic")]
+pub const DETERMINISTIC_TIME_STEP: usize = CLOCK_FREQ / 1000;
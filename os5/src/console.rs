@@ -1,8 +1,12 @@
 /*！
     本模块实现了 print 和 println 宏
+    内核的所有输出除了写到串口，还保存在日志环形缓冲区中，用户态可以通过 syslog 读取
 */
 
+use crate::config::LOG_BUF_SIZE;
 use crate::sbi::console_putchar;
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
 use core::fmt::{self, Write};
 
 struct Stdout;
@@ -12,6 +16,10 @@ impl Write for Stdout {
         for c in s.chars() {
             console_putchar(c as usize);
         }
+        //日志缓冲区正被借用时（例如读取日志时 panic）只输出到串口
+        if let Some(mut log) = LOG_BUF.try_exclusive_access() {
+            log.push(s.as_bytes());
+        }
         Ok(())
     }
 }
@@ -33,3 +41,68 @@ macro_rules! println {
         $crate::console::print(format_args!(concat!($fmt, "\n") $(, $($arg)+)?));
     }
 }
+
+/// 内核日志的环形缓冲区，写满后覆盖最旧的字节
+struct LogBuffer {
+    buf: [u8; LOG_BUF_SIZE],
+    /// 启动以来写入的总字节数，下一个字节写在 total % LOG_BUF_SIZE 处
+    total: usize,
+    /// 上一次清空日志时的 total，此前的字节不再能读到
+    cleared: usize,
+    /// 正处于 ANSI 转义序列（日志的颜色）中，这些字节不写入缓冲区
+    in_escape: bool,
+}
+
+impl LogBuffer {
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            if byte == 0x1b {
+                self.in_escape = true;
+            } else if self.in_escape {
+                //转义序列以字母结尾，例如 \x1b[31m
+                self.in_escape = !byte.is_ascii_alphabetic();
+            } else {
+                self.buf[self.total % LOG_BUF_SIZE] = byte;
+                self.total += 1;
+            }
+        }
+    }
+    //还能读到的字节数
+    fn len(&self) -> usize {
+        (self.total - self.cleared).min(LOG_BUF_SIZE)
+    }
+}
+
+//直接放在 .bss 中而不用 lazy_static，避免第一次输出时在栈上构造整个缓冲区
+static LOG_BUF: UPSafeCell<LogBuffer> = unsafe {
+    UPSafeCell::new(LogBuffer {
+        buf: [0; LOG_BUF_SIZE],
+        total: 0,
+        cleared: 0,
+        in_escape: false,
+    })
+};
+
+/// 缓冲区中还能读到的日志字节数
+pub fn log_len() -> usize {
+    LOG_BUF.exclusive_access().len()
+}
+
+/// 返回最近的至多 len 字节日志，按时间先后排列；clear 为真时随后清空日志
+pub fn read_log(len: usize, clear: bool) -> Vec<u8> {
+    let mut log = LOG_BUF.exclusive_access();
+    let count = log.len().min(len);
+    let bytes = (log.total - count..log.total)
+        .map(|i| log.buf[i % LOG_BUF_SIZE])
+        .collect();
+    if clear {
+        log.cleared = log.total;
+    }
+    bytes
+}
+
+/// 清空日志，此后只能读到新的输出
+pub fn clear_log() {
+    let mut log = LOG_BUF.exclusive_access();
+    log.cleared = log.total;
+}
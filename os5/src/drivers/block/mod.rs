@@ -0,0 +1,45 @@
+//! 块设备
+//!
+//! 目前只支持 QEMU virt 平台上的 virtio 块设备：VIRTIO0 上是存放应用的 easy-fs 文件系统镜像，
+//! VIRTIO1 上是交换区。设备通过 PLIC 的外部中断通知请求完成，PLIC 初始化之前则轮询设备。
+
+mod virtio_blk;
+
+use super::register_irq;
+use crate::config::{VIRTIO0, VIRTIO0_IRQ, VIRTIO1, VIRTIO1_IRQ};
+use alloc::sync::Arc;
+use lazy_static::*;
+use virtio_blk::VirtIOBlock;
+
+/// 以块为单位读写的设备，easy-fs 通过它访问磁盘
+pub use easy_fs::BlockDevice;
+/// 块大小（字节），与 easy-fs 一致
+pub use easy_fs::BLOCK_SZ;
+
+lazy_static! {
+    //VIRTIO0 与 VIRTIO1 上探测到的设备
+    static ref VIRTIO_BLOCKS: [Option<Arc<VirtIOBlock>>; 2] = [
+        VirtIOBlock::probe(VIRTIO0, VIRTIO0_IRQ).map(Arc::new),
+        VirtIOBlock::probe(VIRTIO1, VIRTIO1_IRQ).map(Arc::new),
+    ];
+    /// 文件系统所在的块设备，内核从这里加载应用
+    pub static ref BLOCK_DEVICE: Arc<dyn BlockDevice> = VIRTIO_BLOCKS[0]
+        .clone()
+        .expect("no virtio block device for the file system");
+    /// 交换区所在的块设备，启动时没有检测到时为 None，此时不会换出页面
+    pub static ref SWAP_DEVICE: Option<Arc<dyn BlockDevice>> = VIRTIO_BLOCKS[1]
+        .clone()
+        .map(|device| device as Arc<dyn BlockDevice>);
+}
+
+/// 是否有文件系统所在的块设备，没有时不能使用 [`BLOCK_DEVICE`]
+pub fn block_device_present() -> bool {
+    VIRTIO_BLOCKS[0].is_some()
+}
+
+/// 为探测到的块设备注册外部中断
+pub fn init() {
+    for device in VIRTIO_BLOCKS.iter().flatten() {
+        register_irq(device.irq(), device.clone());
+    }
+}
@@ -0,0 +1,136 @@
+//! virtio 块设备驱动，基于 virtio-drivers 实现
+//!
+//! 请求以非阻塞的方式放入设备队列，发起者随后等待设备完成：设备完成请求后产生外部中断，
+//! 中断处理从已用环中取出完成的请求，发起者看到自己的请求完成后取回结果。
+
+use super::BlockDevice;
+use crate::drivers::{handle_external_interrupt, irq_ready, IrqHandler};
+use crate::mm::{
+    frame_alloc_contiguous, FrameTracker, PageTable, PhysAddr, VirtAddr, KERNEL_SPACE,
+};
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use lazy_static::*;
+use riscv::asm::wfi;
+use virtio_drivers::{BlkResp, DeviceType, Hal, RespStatus, VirtIOBlk, VirtIOHeader};
+
+pub struct VirtIOBlock {
+    blk: UPSafeCell<VirtIOBlk<'static, VirtioHal>>,
+    //设备在 PLIC 上的中断号
+    irq: usize,
+    //设备已经完成、发起者还没有取走的请求
+    completed: UPSafeCell<BTreeSet<u16>>,
+}
+
+impl VirtIOBlock {
+    /// 探测 MMIO 寄存器位于 base 处、中断号为 irq 的设备，它不是可用的 virtio 块设备时返回 None
+    pub fn probe(base: usize, irq: usize) -> Option<Self> {
+        let header = unsafe { &mut *(base as *mut VirtIOHeader) };
+        if !header.verify() || header.device_type() != DeviceType::Block {
+            return None;
+        }
+        let blk = VirtIOBlk::<VirtioHal>::new(header).ok()?;
+        Some(Self {
+            blk: unsafe { UPSafeCell::new(blk) },
+            irq,
+            completed: unsafe { UPSafeCell::new(BTreeSet::new()) },
+        })
+    }
+    pub fn irq(&self) -> usize {
+        self.irq
+    }
+    //等待请求 token 完成。内核态不响应中断，但只要 sie 中打开的中断处于待处理状态 wfi 就会返回，
+    //于是可以停在 wfi 上等设备的外部中断，醒来后再由 PLIC 分发；待处理的时钟中断会让 wfi 立即返回，
+    //这时退化为轮询 PLIC。PLIC 还没有初始化时直接轮询设备的已用环
+    fn wait(&self, token: u16) {
+        while !self.completed.exclusive_access().remove(&token) {
+            if irq_ready() {
+                unsafe { wfi() };
+                handle_external_interrupt();
+            } else {
+                self.handle_irq();
+            }
+        }
+    }
+}
+
+impl IrqHandler for VirtIOBlock {
+    /// 应答设备的中断，并从已用环中取出所有完成的请求
+    fn handle_irq(&self) {
+        let mut blk = self.blk.exclusive_access();
+        blk.ack_interrupt();
+        let mut completed = self.completed.exclusive_access();
+        while let Ok(token) = blk.pop_used() {
+            completed.insert(token);
+        }
+    }
+}
+
+impl BlockDevice for VirtIOBlock {
+    fn read_block(&self, block_id: usize, buf: &mut [u8]) {
+        let mut resp = BlkResp::default();
+        let token = unsafe {
+            self.blk
+                .exclusive_access()
+                .read_block_nb(block_id, buf, &mut resp)
+        }
+        .expect("Error when reading VirtIOBlk");
+        self.wait(token);
+        assert_eq!(
+            resp.status(),
+            RespStatus::Ok,
+            "Error when reading VirtIOBlk"
+        );
+    }
+    fn write_block(&self, block_id: usize, buf: &[u8]) {
+        let mut resp = BlkResp::default();
+        let token = unsafe {
+            self.blk
+                .exclusive_access()
+                .write_block_nb(block_id, buf, &mut resp)
+        }
+        .expect("Error when writing VirtIOBlk");
+        self.wait(token);
+        assert_eq!(
+            resp.status(),
+            RespStatus::Ok,
+            "Error when writing VirtIOBlk"
+        );
+    }
+}
+
+lazy_static! {
+    //分配给设备队列的页帧，设备存在期间一直被占用
+    static ref QUEUE_FRAMES: UPSafeCell<Vec<FrameTracker>> = unsafe { UPSafeCell::new(Vec::new()) };
+}
+
+pub struct VirtioHal;
+
+impl Hal for VirtioHal {
+    //设备要求物理地址连续
+    fn dma_alloc(pages: usize) -> usize {
+        let frames = frame_alloc_contiguous(pages).unwrap();
+        let ppn_base = frames[0].ppn;
+        QUEUE_FRAMES.exclusive_access().extend(frames);
+        PhysAddr::from(ppn_base).0
+    }
+    fn dma_dealloc(pa: usize, pages: usize) -> i32 {
+        let ppn_base = PhysAddr::from(pa).floor().0;
+        QUEUE_FRAMES
+            .exclusive_access()
+            .retain(|frame| frame.ppn.0 < ppn_base || frame.ppn.0 >= ppn_base + pages);
+        0
+    }
+    //物理内存在内核地址空间中是恒等映射的
+    fn phys_to_virt(addr: usize) -> usize {
+        addr
+    }
+    //请求头等数据可能位于不是恒等映射的内核栈上，需要查内核页表
+    fn virt_to_phys(vaddr: usize) -> usize {
+        let va = VirtAddr::from(vaddr);
+        let page_table = PageTable::from_token(KERNEL_SPACE.exclusive_access().token());
+        let pa: PhysAddr = page_table.translate(va.floor()).unwrap().ppn().into();
+        pa.0 + va.page_offset()
+    }
+}
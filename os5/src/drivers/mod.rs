@@ -0,0 +1,66 @@
+//! 设备驱动
+//!
+//! 产生外部中断的设备在初始化时用 [`register_irq`] 为自己的中断号安装处理者，
+//! 外部中断到来时 [`handle_external_interrupt`] 从 PLIC 取出中断号并分发给对应的处理者。
+
+pub mod block;
+pub mod pci;
+pub mod plic;
+pub mod rtc;
+pub mod uart;
+
+pub use block::{block_device_present, BLOCK_DEVICE, SWAP_DEVICE};
+
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use lazy_static::*;
+
+/// 外部中断的处理者
+pub trait IrqHandler: Send + Sync {
+    /// 处理设备的一次中断，返回时设备应当已经撤销中断
+    fn handle_irq(&self);
+}
+
+lazy_static! {
+    //PLIC 初始化之后设备才能用中断通知请求完成，在此之前只能轮询
+    static ref IRQ_READY: UPSafeCell<bool> = unsafe { UPSafeCell::new(false) };
+    //各中断号上安装的处理者
+    static ref IRQ_HANDLERS: UPSafeCell<BTreeMap<usize, Arc<dyn IrqHandler>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+/// 初始化 PLIC 与各个设备，打开设备注册的外部中断
+pub fn init() {
+    plic::init();
+    block::init();
+    uart::init();
+    unsafe {
+        riscv::register::sie::set_sext();
+    }
+    *IRQ_READY.exclusive_access() = true;
+}
+
+/// 为中断号 irq 安装处理者并在 PLIC 上打开这个中断，已有的处理者会被替换
+pub fn register_irq(irq: usize, handler: Arc<dyn IrqHandler>) {
+    IRQ_HANDLERS.exclusive_access().insert(irq, handler);
+    plic::enable(irq);
+}
+
+/// 是否可以等待设备的外部中断
+pub fn irq_ready() -> bool {
+    *IRQ_READY.exclusive_access()
+}
+
+/// 处理所有待处理的外部中断
+pub fn handle_external_interrupt() {
+    while let Some(irq) = plic::claim() {
+        //先取出处理者再调用，处理过程中不持有处理者表
+        let handler = IRQ_HANDLERS.exclusive_access().get(&irq).cloned();
+        match handler {
+            Some(handler) => handler.handle_irq(),
+            None => warn!("Unexpected external interrupt: {}", irq),
+        }
+        plic::complete(irq);
+    }
+}
@@ -0,0 +1,57 @@
+//! PCI 总线
+//!
+//! QEMU virt 平台上的 PCIe 主桥通过 ECAM 访问配置空间：0 号总线上设备 dev 功能 func 的配置空间
+//! 位于 PCI_ECAM + (dev << 15 | func << 12)。固件不会为设备分配地址，这里只扫描 0 号总线，
+//! 由使用设备的驱动为它的 BAR 指定 I/O 空间中的端口。
+
+use crate::config::{PCI_ECAM, PCI_PIO, PCI_PIO_SIZE};
+
+//配置空间中各字段的偏移
+const VENDOR_ID: usize = 0x00;
+const DEVICE_ID: usize = 0x02;
+const COMMAND: usize = 0x04;
+const BAR0: usize = 0x10;
+
+//COMMAND：允许设备响应 I/O 空间的访问
+const COMMAND_IO_ENABLE: u16 = 1 << 0;
+//BAR 的最低位为 1 表示它位于 I/O 空间
+const BAR_IO: u32 = 1 << 0;
+
+//0 号总线上的设备数与每个设备的功能数
+const DEVICES: usize = 32;
+const FUNCTIONS: usize = 8;
+
+/// 0 号总线上的一个 PCI 功能，保存它的配置空间的地址
+pub struct PciFunction {
+    config: usize,
+}
+
+impl PciFunction {
+    fn read16(&self, offset: usize) -> u16 {
+        unsafe { ((self.config + offset) as *const u16).read_volatile() }
+    }
+    fn write16(&self, offset: usize, value: u16) {
+        unsafe { ((self.config + offset) as *mut u16).write_volatile(value) }
+    }
+    fn write32(&self, offset: usize, value: u32) {
+        unsafe { ((self.config + offset) as *mut u32).write_volatile(value) }
+    }
+    /// 把第 bar 个 BAR 指定到 I/O 端口 port 并允许设备响应 I/O 访问，返回访问这些端口使用的地址
+    pub fn map_io_bar(&self, bar: usize, port: usize) -> usize {
+        assert!(port < PCI_PIO_SIZE);
+        self.write32(BAR0 + bar * 4, port as u32 | BAR_IO);
+        self.write16(COMMAND, self.read16(COMMAND) | COMMAND_IO_ENABLE);
+        PCI_PIO + port
+    }
+}
+
+/// 在 0 号总线上查找厂商号与设备号分别为 vendor 与 device 的第一个功能，找不到时返回 None
+pub fn find_function(vendor: u16, device: u16) -> Option<PciFunction> {
+    (0..DEVICES * FUNCTIONS)
+        .map(|index| PciFunction {
+            config: PCI_ECAM + (index << 12),
+        })
+        .find(|function| {
+            function.read16(VENDOR_ID) == vendor && function.read16(DEVICE_ID) == device
+        })
+}
@@ -0,0 +1,51 @@
+//! 平台级中断控制器
+//!
+//! QEMU virt 平台上的 PLIC 汇总外部设备的中断并送给各个核。内核只使用 0 号核的 S 态上下文：
+//! 中断到来时先 claim 得到中断号，交给设备处理之后再 complete，PLIC 才会再次送出这个中断。
+
+use crate::config::PLIC;
+
+//0 号核 S 态对应的上下文编号
+const CONTEXT: usize = 1;
+//各寄存器相对 PLIC 基址的偏移
+const PRIORITY: usize = 0x0;
+const ENABLE: usize = 0x2000;
+const ENABLE_STRIDE: usize = 0x80;
+const THRESHOLD: usize = 0x20_0000;
+const CLAIM: usize = 0x20_0004;
+const CONTEXT_STRIDE: usize = 0x1000;
+
+fn reg(offset: usize) -> *mut u32 {
+    (PLIC + offset) as *mut u32
+}
+
+/// 接收所有优先级大于 0 的中断
+pub fn init() {
+    unsafe {
+        reg(THRESHOLD + CONTEXT * CONTEXT_STRIDE).write_volatile(0);
+    }
+}
+
+/// 以优先级 1 打开中断 irq
+pub fn enable(irq: usize) {
+    unsafe {
+        reg(PRIORITY + irq * 4).write_volatile(1);
+        let enable = reg(ENABLE + CONTEXT * ENABLE_STRIDE + irq / 32 * 4);
+        enable.write_volatile(enable.read_volatile() | 1 << (irq % 32));
+    }
+}
+
+/// 取出一个待处理的中断号，没有待处理的中断时返回 None
+pub fn claim() -> Option<usize> {
+    match unsafe { reg(CLAIM + CONTEXT * CONTEXT_STRIDE).read_volatile() } {
+        0 => None,
+        irq => Some(irq as usize),
+    }
+}
+
+/// 通知 PLIC 中断 irq 已经处理完毕
+pub fn complete(irq: usize) {
+    unsafe {
+        reg(CLAIM + CONTEXT * CONTEXT_STRIDE).write_volatile(irq as u32);
+    }
+}
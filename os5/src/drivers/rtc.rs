@@ -0,0 +1,18 @@
+//! 实时时钟
+//!
+//! QEMU virt 平台上的 Goldfish RTC，给出自 1970-01-01 00:00:00 UTC 以来的纳秒数。
+
+use crate::config::RTC;
+
+//时间的低 32 位与高 32 位
+const TIME_LOW: usize = 0x00;
+const TIME_HIGH: usize = 0x04;
+
+/// 读取当前的墙上时间（纳秒）。必须先读低 32 位，设备此时锁存高 32 位
+pub fn read_ns() -> usize {
+    unsafe {
+        let low = ((RTC + TIME_LOW) as *const u32).read_volatile() as usize;
+        let high = ((RTC + TIME_HIGH) as *const u32).read_volatile() as usize;
+        (high << 32) | low
+    }
+}
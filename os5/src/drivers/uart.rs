@@ -0,0 +1,107 @@
+//! 串口
+//!
+//! QEMU virt 平台上的 16550 串口。输出仍然经由 SBI 完成，这里只打开接收中断：
+//! 收到字节时串口通过 PLIC 送来外部中断，中断处理取出接收 FIFO 中的所有字节交给终端。
+//! 挂在 PCI 上的第二个 16550 串口（如果有的话）以轮询方式供内核调试器使用。
+
+use super::{register_irq, IrqHandler};
+use crate::config::{UART0, UART0_IRQ};
+use crate::fs::tty_receive;
+use alloc::sync::Arc;
+
+//各寄存器相对串口基址的偏移
+//接收缓冲寄存器（读）
+const RBR: usize = 0;
+//发送保持寄存器（写）
+const THR: usize = 0;
+//中断使能寄存器
+const IER: usize = 1;
+//FIFO 控制寄存器（写）
+const FCR: usize = 2;
+//线路控制寄存器
+const LCR: usize = 3;
+//线路状态寄存器
+const LSR: usize = 5;
+
+//IER：接收到数据时产生中断
+const IER_RX_ENABLE: u8 = 1 << 0;
+//FCR：打开 FIFO 并清空收发 FIFO
+const FCR_FIFO_ENABLE: u8 = 1 << 0;
+const FCR_FIFO_CLEAR: u8 = 3 << 1;
+//LCR：8 位数据位、无校验、1 位停止位
+const LCR_8N1: u8 = 3;
+//LSR：接收 FIFO 中有数据
+const LSR_RX_READY: u8 = 1 << 0;
+//LSR：发送保持寄存器为空，可以写入下一个字节
+const LSR_TX_READY: u8 = 1 << 5;
+
+fn reg(offset: usize) -> *mut u8 {
+    (UART0 + offset) as *mut u8
+}
+
+/// 打开串口的接收中断并注册中断处理。波特率等参数已经由 SBI 设置好
+pub fn init() {
+    unsafe {
+        reg(FCR).write_volatile(FCR_FIFO_ENABLE | FCR_FIFO_CLEAR);
+        reg(IER).write_volatile(IER_RX_ENABLE);
+    }
+    register_irq(UART0_IRQ, Arc::new(Uart));
+}
+
+struct Uart;
+
+impl IrqHandler for Uart {
+    /// 把接收 FIFO 中的字节依次交给终端，读空 FIFO 之后串口撤销中断
+    fn handle_irq(&self) {
+        while unsafe { reg(LSR).read_volatile() } & LSR_RX_READY != 0 {
+            tty_receive(unsafe { reg(RBR).read_volatile() });
+        }
+    }
+}
+
+/// 以轮询方式收发的 16550 串口，不使用中断，在关中断的调试器中也可以使用
+pub struct PolledUart {
+    base: usize,
+}
+
+impl PolledUart {
+    /// 找到 QEMU 的 pci-serial 设备，把它的寄存器分配到 I/O 端口 port 并初始化，没有这个设备时返回 None
+    pub fn probe_pci(port: usize) -> Option<Self> {
+        const VENDOR_REDHAT: u16 = 0x1b36;
+        const DEVICE_PCI_SERIAL: u16 = 0x0002;
+        let function = super::pci::find_function(VENDOR_REDHAT, DEVICE_PCI_SERIAL)?;
+        let uart = Self {
+            base: function.map_io_bar(0, port),
+        };
+        uart.write_reg(IER, 0);
+        uart.write_reg(LCR, LCR_8N1);
+        uart.write_reg(FCR, FCR_FIFO_ENABLE | FCR_FIFO_CLEAR);
+        Some(uart)
+    }
+    fn read_reg(&self, offset: usize) -> u8 {
+        unsafe { ((self.base + offset) as *const u8).read_volatile() }
+    }
+    fn write_reg(&self, offset: usize, value: u8) {
+        unsafe { ((self.base + offset) as *mut u8).write_volatile(value) }
+    }
+    /// 取出一个收到的字节，接收 FIFO 为空时返回 None
+    pub fn try_read(&self) -> Option<u8> {
+        match self.read_reg(LSR) & LSR_RX_READY {
+            0 => None,
+            _ => Some(self.read_reg(RBR)),
+        }
+    }
+    /// 等待并取出一个收到的字节
+    pub fn read(&self) -> u8 {
+        loop {
+            if let Some(byte) = self.try_read() {
+                return byte;
+            }
+        }
+    }
+    /// 等到可以发送时发送一个字节
+    pub fn write(&self, byte: u8) {
+        while self.read_reg(LSR) & LSR_TX_READY == 0 {}
+        self.write_reg(THR, byte);
+    }
+}
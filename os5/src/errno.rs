@@ -0,0 +1,75 @@
+//! 内核统一使用的错误码
+//!
+//! 系统调用出错时返回错误码的相反数，数值与 Linux 保持一致，便于用户态程序对照。
+//! 例外只有实验框架的测试所约定的两处：sys_set_priority 的参数不合法时返回 -1，
+//! sys_waittid 在线程尚未退出时返回 -2。
+
+/// Error numbers returned (negated) by system calls
+#[allow(unused, clippy::upper_case_acronyms)]
+#[repr(isize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Errno {
+    /// 没有权限
+    EPERM = 1,
+    /// 文件不存在
+    ENOENT = 2,
+    /// 进程不存在
+    ESRCH = 3,
+    /// 阻塞的系统调用被信号打断
+    EINTR = 4,
+    /// 输入输出错误，例如访问不存在的寄存器
+    EIO = 5,
+    /// 设备或地址不存在，例如以非阻塞方式打开没有读者的命名管道的写端
+    ENXIO = 6,
+    /// exec 的命令行参数过长
+    E2BIG = 7,
+    /// 不是可执行文件
+    ENOEXEC = 8,
+    /// 文件描述符不合法，或者不允许这样访问
+    EBADF = 9,
+    /// 没有可等待的子进程
+    ECHILD = 10,
+    /// 资源暂时不可用，稍后重试
+    EAGAIN = 11,
+    /// 内存不足
+    ENOMEM = 12,
+    /// 没有访问权限，例如以写方式打开只读的文件
+    EACCES = 13,
+    /// 用户地址不合法
+    EFAULT = 14,
+    /// 对象正在使用中
+    EBUSY = 16,
+    /// 对象已经存在
+    EEXIST = 17,
+    /// 不能跨文件系统操作
+    EXDEV = 18,
+    /// 不支持的文件系统类型
+    ENODEV = 19,
+    /// 路径中的某一级不是目录
+    ENOTDIR = 20,
+    /// 对象是一个目录
+    EISDIR = 21,
+    /// 参数不合法
+    EINVAL = 22,
+    /// 进程打开的文件数已经达到上限
+    EMFILE = 24,
+    /// 不是终端，或者设备不支持这个 ioctl 请求
+    ENOTTY = 25,
+    /// 文件不支持随机访问
+    ESPIPE = 29,
+    /// 文件系统是只读的
+    EROFS = 30,
+    /// 管道的读端都已关闭
+    EPIPE = 32,
+    /// 路径或字符串过长
+    ENAMETOOLONG = 36,
+    /// 系统调用未实现
+    ENOSYS = 38,
+}
+
+impl Errno {
+    /// 作为系统调用返回值的负数形式
+    pub fn neg(self) -> isize {
+        -(self as isize)
+    }
+}
@@ -0,0 +1,189 @@
+//! 设备文件系统
+//!
+//! 挂载在 /dev 上，只有一层目录，其中是固定的几个字符设备：
+//! null 读到文件末尾、写入的数据被丢弃；zero 读出全为 0 的字节；console 是控制台，与标准输入输出相同；
+//! random 读出随机字节。设备文件不能创建或删除。
+
+use super::{
+    dirent64, File, FileSystem, OpenFlags, PollEvents, Stat, StatMode, Stdin, Stdout, DT_CHR,
+};
+use crate::errno::Errno;
+use crate::mm::UserBuffer;
+use crate::random::fill_random;
+use crate::sync::UPSafeCell;
+use crate::task::TaskControlBlock;
+use alloc::sync::Arc;
+
+/// devfs 中文件的设备编号，区别于 easy-fs 的 0
+const DEVFS_DEV: u64 = 1;
+
+/// 设备文件系统
+pub struct DevFs;
+
+/// devfs 中的设备，按在目录中的顺序排列
+#[derive(Clone, Copy)]
+enum Device {
+    Null,
+    Zero,
+    Console,
+    Random,
+}
+
+const DEVICES: [(&str, Device); 4] = [
+    ("null", Device::Null),
+    ("zero", Device::Zero),
+    ("console", Device::Console),
+    ("random", Device::Random),
+];
+
+/// 打开的设备文件
+struct DevFile {
+    device: Device,
+    //在目录中的序号，inode 编号为序号加 2，1 是目录本身
+    index: usize,
+    readable: bool,
+    writable: bool,
+}
+
+/// 打开的 /dev 目录，记录下一个要读的目录项的序号
+struct DevDir {
+    offset: UPSafeCell<usize>,
+}
+
+impl FileSystem for DevFs {
+    fn open(&self, path: &[&str], flags: OpenFlags) -> Result<Arc<dyn File>, Errno> {
+        let (readable, writable) = flags.read_write().ok_or(Errno::EINVAL)?;
+        let (name, rest) = match path.split_first() {
+            Some(split) => split,
+            None if writable => return Err(Errno::EISDIR),
+            None => {
+                return Ok(Arc::new(DevDir {
+                    offset: unsafe { UPSafeCell::new(0) },
+                }))
+            }
+        };
+        let index = DEVICES
+            .iter()
+            .position(|(dev_name, _)| dev_name == name)
+            .ok_or(Errno::ENOENT)?;
+        if !rest.is_empty() {
+            return Err(Errno::ENOTDIR);
+        }
+        Ok(Arc::new(DevFile {
+            device: DEVICES[index].1,
+            index,
+            readable,
+            writable,
+        }))
+    }
+}
+
+impl File for DevFile {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+    fn writable(&self) -> bool {
+        self.writable
+    }
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        match self.device {
+            Device::Null => Ok(0),
+            Device::Console => Stdin.read(buf),
+            //经由内核中的缓冲区填满用户缓冲区
+            Device::Zero | Device::Random => {
+                let mut chunk = [0u8; 64];
+                let mut filled = 0;
+                while filled < buf.len() {
+                    let len = chunk.len().min(buf.len() - filled);
+                    if let Device::Random = self.device {
+                        fill_random(&mut chunk[..len]);
+                    }
+                    buf.write_at(filled, &chunk[..len])?;
+                    filled += len;
+                }
+                Ok(filled)
+            }
+        }
+    }
+    fn read_nonblock(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        match self.device {
+            Device::Console => Stdin.read_nonblock(buf),
+            _ => self.read(buf),
+        }
+    }
+    fn poll(&self) -> PollEvents {
+        let events = match self.device {
+            Device::Console if self.readable => Stdin.poll(),
+            _ if self.readable => PollEvents::IN,
+            _ => PollEvents::empty(),
+        };
+        if self.writable {
+            events | PollEvents::OUT
+        } else {
+            events
+        }
+    }
+    //只有可读的控制台会在 poll 中等待，其余设备总是就绪
+    fn poll_register(&self, task: &Arc<TaskControlBlock>) {
+        if let (Device::Console, true) = (self.device, self.readable) {
+            Stdin.poll_register(task);
+        }
+    }
+    fn poll_unregister(&self, task: &Arc<TaskControlBlock>) {
+        if let (Device::Console, true) = (self.device, self.readable) {
+            Stdin.poll_unregister(task);
+        }
+    }
+    //写入 null、zero 与 random 的数据都被丢弃
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        match self.device {
+            Device::Console => Stdout.write(buf),
+            _ => Ok(buf.len()),
+        }
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(DEVFS_DEV, self.index as u64 + 2, StatMode::CHR, 1, 0)
+    }
+    fn ioctl(&self, request: usize, arg: usize) -> Result<isize, Errno> {
+        match self.device {
+            Device::Console => Stdin.ioctl(request, arg),
+            _ => Err(Errno::ENOTTY),
+        }
+    }
+}
+
+impl File for DevDir {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        Err(Errno::EISDIR)
+    }
+    fn write(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        Err(Errno::EISDIR)
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(DEVFS_DEV, 1, StatMode::DIR, 1, 0)
+    }
+    fn getdents(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let mut offset = self.offset.exclusive_access();
+        let mut written = 0;
+        while let Some((name, _)) = DEVICES.get(*offset) {
+            let record = dirent64(*offset as u32 + 2, *offset + 1, DT_CHR, name);
+            if written + record.len() > buf.len() {
+                //一条记录也放不下时报告缓冲区太小
+                if written == 0 {
+                    return Err(Errno::EINVAL);
+                }
+                break;
+            }
+            buf.write_at(written, &record)?;
+            written += record.len();
+            *offset += 1;
+        }
+        Ok(written)
+    }
+}
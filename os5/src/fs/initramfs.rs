@@ -0,0 +1,301 @@
+//! initramfs
+//!
+//! 构建内核时 build.rs 把应用打包为 cpio（newc 格式）归档，用 include_bytes! 嵌入内核镜像。
+//! 第一次使用时把归档解开为内存中的目录树，文件内容直接引用镜像中的数据，不再复制。
+//! 它是只读的，开启 initramfs 特性时挂载在根目录上，内核不需要块设备也能启动；
+//! 否则其中只有几个空的挂载点，可以用 mount 挂载到其他目录。
+
+use super::{dirent64, File, FileSystem, OpenFlags, Stat, StatMode, DT_DIR, DT_REG};
+use crate::errno::Errno;
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// initramfs 中文件的设备编号
+const INITRAMFS_DEV: u64 = 3;
+
+/// 嵌入内核镜像的 cpio 归档
+static ARCHIVE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/initramfs.cpio"));
+
+/// newc 格式的头部长度与魔数
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_MAGIC: &[u8] = b"070701";
+
+lazy_static! {
+    /// 解开之后的根目录
+    static ref ROOT: Node = unpack(ARCHIVE);
+}
+
+/// 嵌入内核镜像的只读文件系统
+pub struct InitRamFs;
+
+/// initramfs 中的目录或文件
+struct Node {
+    ino: u64,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    Dir(BTreeMap<String, Node>),
+    File(&'static [u8]),
+}
+
+impl Node {
+    fn new_dir(ino: u64) -> Self {
+        Self {
+            ino,
+            kind: NodeKind::Dir(BTreeMap::new()),
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self.kind, NodeKind::Dir(_))
+    }
+
+    //把 node 放到以 self 为根的 path 处，中间的目录不存在时自动创建。
+    //归档中的目录可能已经在创建它之下的文件时自动创建了，此时保留已有的目录
+    fn insert(&mut self, path: &[&str], node: Node, next_ino: &mut u64) {
+        let entries = match &mut self.kind {
+            NodeKind::Dir(entries) => entries,
+            NodeKind::File(_) => panic!("initramfs: {} is not under a directory", path[0]),
+        };
+        match path {
+            [] => {}
+            [name] => {
+                if !(node.is_dir() && entries.get(*name).map_or(false, Node::is_dir)) {
+                    entries.insert(String::from(*name), node);
+                }
+            }
+            [name, rest @ ..] => entries
+                .entry(String::from(*name))
+                .or_insert_with(|| {
+                    *next_ino += 1;
+                    Node::new_dir(*next_ino - 1)
+                })
+                .insert(rest, node, next_ino),
+        }
+    }
+}
+
+//补齐到 4 字节
+fn align4(pos: usize) -> usize {
+    (pos + 3) & !3
+}
+
+//把归档解开为目录树。归档由 build.rs 生成，格式不对说明镜像损坏，直接 panic。设备等其他类型的记录被忽略
+fn unpack(archive: &'static [u8]) -> Node {
+    let mut root = Node::new_dir(1);
+    let mut next_ino = 2;
+    let mut pos = 0;
+    loop {
+        let header = archive
+            .get(pos..pos + CPIO_HEADER_LEN)
+            .expect("initramfs: truncated header");
+        assert_eq!(&header[..6], CPIO_MAGIC, "initramfs: bad magic");
+        //魔数之后是 13 个 8 位十六进制数
+        let field = |i: usize| {
+            let hex = core::str::from_utf8(&header[6 + 8 * i..14 + 8 * i]).unwrap();
+            usize::from_str_radix(hex, 16).expect("initramfs: bad header field")
+        };
+        let (mode, size, name_size) = (field(1), field(6), field(11));
+        let name_start = pos + CPIO_HEADER_LEN;
+        let data_start = align4(name_start + name_size);
+        let name = core::str::from_utf8(&archive[name_start..name_start + name_size - 1])
+            .expect("initramfs: bad file name");
+        let data = archive
+            .get(data_start..data_start + size)
+            .expect("initramfs: truncated file");
+        pos = align4(data_start + size);
+        if name == "TRAILER!!!" {
+            break;
+        }
+        let kind = match mode & 0o170000 {
+            0o040000 => NodeKind::Dir(BTreeMap::new()),
+            0o100000 => NodeKind::File(data),
+            _ => continue,
+        };
+        let path: Vec<&str> = name
+            .split('/')
+            .filter(|name| !name.is_empty() && *name != ".")
+            .collect();
+        let node = Node {
+            ino: next_ino,
+            kind,
+        };
+        next_ino += 1;
+        root.insert(&path, node, &mut next_ino);
+    }
+    root
+}
+
+//从根目录开始依次查找各级目录。不存在时返回 Errno::ENOENT，中间某一级不是目录时返回 Errno::ENOTDIR
+fn lookup(path: &[&str]) -> Result<&'static Node, Errno> {
+    let mut node: &'static Node = &ROOT;
+    for name in path {
+        node = match &node.kind {
+            NodeKind::Dir(entries) => entries.get(*name).ok_or(Errno::ENOENT)?,
+            NodeKind::File(_) => return Err(Errno::ENOTDIR),
+        };
+    }
+    Ok(node)
+}
+
+//只读的文件系统中不能创建文件：已经存在时返回 Errno::EEXIST，上一级目录不存在时返回 Errno::ENOENT，
+//否则返回 Errno::EROFS
+fn create_error(path: &[&str]) -> Errno {
+    if lookup(path).is_ok() {
+        return Errno::EEXIST;
+    }
+    match lookup(&path[..path.len() - 1]) {
+        Ok(dir) if dir.is_dir() => Errno::EROFS,
+        Ok(_) => Errno::ENOTDIR,
+        Err(err) => err,
+    }
+}
+
+/// 根目录中的名字
+pub fn initramfs_ls() -> Vec<String> {
+    match &ROOT.kind {
+        NodeKind::Dir(entries) => entries.keys().cloned().collect(),
+        NodeKind::File(_) => Vec::new(),
+    }
+}
+
+/// 打开的 initramfs 目录或文件
+struct RamFile {
+    node: &'static Node,
+    //文件的读写位置，或者目录中下一个要读的目录项的序号
+    offset: UPSafeCell<usize>,
+}
+
+impl RamFile {
+    //文件的字节数，目录为 0
+    fn size(&self) -> usize {
+        match self.node.kind {
+            NodeKind::File(data) => data.len(),
+            NodeKind::Dir(_) => 0,
+        }
+    }
+}
+
+impl FileSystem for InitRamFs {
+    fn open(&self, path: &[&str], flags: OpenFlags) -> Result<Arc<dyn File>, Errno> {
+        let (_, writable) = flags.read_write().ok_or(Errno::EINVAL)?;
+        let node = match lookup(path) {
+            Ok(node) => node,
+            Err(Errno::ENOENT) if flags.contains(OpenFlags::CREATE) => {
+                return Err(create_error(path))
+            }
+            Err(err) => return Err(err),
+        };
+        if writable || flags.contains(OpenFlags::TRUNC) {
+            return Err(if node.is_dir() {
+                Errno::EISDIR
+            } else {
+                Errno::EROFS
+            });
+        }
+        Ok(Arc::new(RamFile {
+            node,
+            offset: unsafe { UPSafeCell::new(0) },
+        }))
+    }
+    fn mkdir(&self, path: &[&str]) -> Result<(), Errno> {
+        Err(create_error(path))
+    }
+    fn mkfifo(&self, path: &[&str]) -> Result<(), Errno> {
+        Err(create_error(path))
+    }
+    fn link(&self, old_path: &[&str], new_path: &[&str]) -> Result<(), Errno> {
+        if lookup(old_path)?.is_dir() {
+            return Err(Errno::EPERM);
+        }
+        Err(create_error(new_path))
+    }
+    fn unlink(&self, path: &[&str]) -> Result<(), Errno> {
+        if lookup(path)?.is_dir() {
+            return Err(Errno::EISDIR);
+        }
+        Err(Errno::EROFS)
+    }
+    fn read_file(&self, path: &[&str]) -> Result<Vec<u8>, Errno> {
+        match lookup(path)?.kind {
+            NodeKind::File(data) => Ok(data.to_vec()),
+            NodeKind::Dir(_) => Err(Errno::EACCES),
+        }
+    }
+}
+
+impl File for RamFile {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let data = match self.node.kind {
+            NodeKind::File(data) => data,
+            NodeKind::Dir(_) => return Err(Errno::EISDIR),
+        };
+        let mut offset = self.offset.exclusive_access();
+        let start = (*offset).min(data.len());
+        let len = buf.len().min(data.len() - start);
+        buf.write_at(0, &data[start..start + len])?;
+        *offset = start + len;
+        Ok(len)
+    }
+    fn write(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        Err(Errno::EBADF)
+    }
+    fn seek(&self, offset: isize, whence: usize) -> Result<usize, Errno> {
+        let mut current = self.offset.exclusive_access();
+        let base = match whence {
+            0 => 0,
+            1 => *current,
+            2 => self.size(),
+            _ => return Err(Errno::EINVAL),
+        };
+        let offset = base as isize + offset;
+        if offset < 0 {
+            return Err(Errno::EINVAL);
+        }
+        *current = offset as usize;
+        Ok(*current)
+    }
+    fn stat(&self) -> Stat {
+        let mode = if self.node.is_dir() {
+            StatMode::DIR
+        } else {
+            StatMode::FILE
+        };
+        Stat::new(INITRAMFS_DEV, self.node.ino, mode, 1, self.size() as u64)
+    }
+    fn getdents(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let entries = match &self.node.kind {
+            NodeKind::Dir(entries) => entries,
+            NodeKind::File(_) => return Err(Errno::ENOTDIR),
+        };
+        let mut offset = self.offset.exclusive_access();
+        let mut written = 0;
+        for (name, node) in entries.iter().skip(*offset) {
+            let d_type = if node.is_dir() { DT_DIR } else { DT_REG };
+            let record = dirent64(node.ino as u32, *offset + 1, d_type, name);
+            if written + record.len() > buf.len() {
+                //一条记录也放不下时报告缓冲区太小
+                if written == 0 {
+                    return Err(Errno::EINVAL);
+                }
+                break;
+            }
+            buf.write_at(written, &record)?;
+            written += record.len();
+            *offset += 1;
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,381 @@
+//! easy-fs 文件系统
+//!
+//! [`BLOCK_DEVICE`] 上是一个 easy-fs 文件系统镜像，应用的 ELF 文件保存在它的根目录中，
+//! 用户程序可以在其中创建文件与子目录。它作为 [`EasyFs`] 挂载在根目录上。
+//! 进程打开的文件由 [`OSInode`] 表示，它在 easy-fs 的 inode 之上记录读写位置，打开的目录则记录读到的目录项。
+//!
+//! 一个 inode 可以有多个硬链接。删除最后一个链接时如果文件仍被打开，数据块与 inode 要等到最后一个
+//! [`OSInode`] 释放时才回收，因此这里按 inode 编号记录打开的次数。
+//!
+//! 命名管道在磁盘上只有一个 inode，打开时得到的是内核中按 inode 编号共享的管道，见 [`open_fifo`]。
+
+use super::pipe::{forget_fifo, open_fifo};
+use super::{dirent64, File, FileSystem, Stat, StatMode, DT_DIR, DT_FIFO, DT_REG};
+use crate::config::WRITEBACK_INTERVAL_TICKS;
+use crate::drivers::BLOCK_DEVICE;
+use crate::errno::Errno;
+use crate::mm::UserBuffer;
+use crate::sync::{preempt_point, UPSafeCell};
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use bitflags::*;
+use easy_fs::{block_cache_sync_all, EasyFileSystem, Inode, NAME_LENGTH_LIMIT};
+use lazy_static::*;
+
+lazy_static! {
+    /// 文件系统的根目录，第一次使用时打开文件系统
+    pub static ref ROOT_INODE: Arc<Inode> = {
+        let efs = EasyFileSystem::open(BLOCK_DEVICE.clone());
+        Arc::new(EasyFileSystem::root_inode(&efs))
+    };
+    /// 每个 inode 编号被打开的 OSInode 数
+    static ref OPEN_INODES: UPSafeCell<BTreeMap<u32, usize>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+    /// 距离上一次定期写回经过的时钟中断次数
+    static ref WRITEBACK_TICKS: UPSafeCell<usize> = unsafe { UPSafeCell::new(0) };
+}
+
+/// 在时钟中断中调用，每经过 WRITEBACK_INTERVAL_TICKS 次时钟中断把块缓存中的脏块全部写回磁盘。
+/// 块缓存采用写回策略，没有定期写回时，QEMU 被直接终止会丢失进程退出前的全部写入
+pub fn writeback_tick() {
+    let mut ticks = WRITEBACK_TICKS.exclusive_access();
+    *ticks += 1;
+    if *ticks < WRITEBACK_INTERVAL_TICKS {
+        return;
+    }
+    *ticks = 0;
+    drop(ticks);
+    block_cache_sync_all();
+}
+
+//没有目录项指向、也没有被打开的文件，回收它的数据块与 inode
+fn release_if_orphan(inode: &Inode) {
+    let ino = inode.inode_id();
+    if OPEN_INODES.exclusive_access().contains_key(&ino) {
+        return;
+    }
+    if !inode.is_dir() && inode.nlink() == 0 {
+        inode.destroy();
+    }
+}
+
+/// 从头到尾读出 inode 的全部内容
+pub fn read_all(inode: &Inode) -> Vec<u8> {
+    let mut buffer = [0u8; 512];
+    let mut v: Vec<u8> = Vec::new();
+    loop {
+        let len = inode.read_at(v.len(), &mut buffer);
+        if len == 0 {
+            break;
+        }
+        v.extend_from_slice(&buffer[..len]);
+        preempt_point();
+    }
+    v
+}
+
+/// 进程打开的一个文件系统中的文件
+pub struct OSInode {
+    readable: bool,
+    writable: bool,
+    inner: UPSafeCell<OSInodeInner>,
+}
+
+pub struct OSInodeInner {
+    //下一次读写的位置
+    offset: usize,
+    inode: Arc<Inode>,
+}
+
+impl OSInode {
+    pub fn new(readable: bool, writable: bool, inode: Arc<Inode>) -> Self {
+        *OPEN_INODES
+            .exclusive_access()
+            .entry(inode.inode_id())
+            .or_insert(0) += 1;
+        Self {
+            readable,
+            writable,
+            inner: unsafe { UPSafeCell::new(OSInodeInner { offset: 0, inode }) },
+        }
+    }
+}
+
+impl Drop for OSInode {
+    fn drop(&mut self) {
+        let inner = self.inner.exclusive_access();
+        let ino = inner.inode.inode_id();
+        let mut open_inodes = OPEN_INODES.exclusive_access();
+        let count = open_inodes.get_mut(&ino).unwrap();
+        *count -= 1;
+        if *count == 0 {
+            open_inodes.remove(&ino);
+        }
+        drop(open_inodes);
+        release_if_orphan(&inner.inode);
+    }
+}
+
+bitflags! {
+    /// 打开文件的方式，与用户库中的 OpenFlags 一致
+    pub struct OpenFlags: u32 {
+        const RDONLY = 0;
+        const WRONLY = 1 << 0;
+        const RDWR = 1 << 1;
+        /// 文件不存在时创建它
+        const CREATE = 1 << 9;
+        /// 打开时把文件截断为空
+        const TRUNC = 1 << 10;
+        /// 读写管道时不阻塞，没有数据可读或者没有空间可写时返回 Errno::EAGAIN
+        const NONBLOCK = 1 << 11;
+        /// 每次写入之前先移动到文件末尾
+        const APPEND = 1 << 12;
+    }
+}
+
+impl OpenFlags {
+    /// 打开的文件是否可读、可写，WRONLY 与 RDWR 同时出现时返回 None
+    pub fn read_write(&self) -> Option<(bool, bool)> {
+        match (self.contains(Self::WRONLY), self.contains(Self::RDWR)) {
+            (false, false) => Some((true, false)),
+            (true, false) => Some((false, true)),
+            (false, true) => Some((true, true)),
+            (true, true) => None,
+        }
+    }
+    /// 打开之后仍然可以由 fcntl 修改的状态标志
+    pub fn status() -> Self {
+        Self::NONBLOCK | Self::APPEND
+    }
+}
+
+/// 挂载到命名空间中的 easy-fs
+pub struct EasyFs {
+    root: Arc<Inode>,
+}
+
+impl EasyFs {
+    pub fn new(root: Arc<Inode>) -> Self {
+        Self { root }
+    }
+
+    //从根目录开始依次查找各级目录。不存在时返回 Errno::ENOENT，中间某一级不是目录时返回 Errno::ENOTDIR
+    fn lookup(&self, path: &[&str]) -> Result<Arc<Inode>, Errno> {
+        let mut inode = self.root.clone();
+        for name in path {
+            if !inode.is_dir() {
+                return Err(Errno::ENOTDIR);
+            }
+            if name.len() > NAME_LENGTH_LIMIT {
+                return Err(Errno::ENAMETOOLONG);
+            }
+            inode = inode.find(name).ok_or(Errno::ENOENT)?;
+        }
+        Ok(inode)
+    }
+
+    //查找路径的上一级目录，返回目录与最后一级的名字。路径为空时指的是根目录，它一定存在，返回 Errno::EEXIST
+    fn lookup_parent<'a>(&self, path: &[&'a str]) -> Result<(Arc<Inode>, &'a str), Errno> {
+        let (name, parent) = path.split_last().ok_or(Errno::EEXIST)?;
+        if name.len() > NAME_LENGTH_LIMIT {
+            return Err(Errno::ENAMETOOLONG);
+        }
+        let dir = self.lookup(parent)?;
+        if !dir.is_dir() {
+            return Err(Errno::ENOTDIR);
+        }
+        Ok((dir, name))
+    }
+}
+
+impl FileSystem for EasyFs {
+    fn open(&self, path: &[&str], flags: OpenFlags) -> Result<Arc<dyn File>, Errno> {
+        let (readable, writable) = flags.read_write().ok_or(Errno::EINVAL)?;
+        let inode = match self.lookup_parent(path) {
+            Ok((dir, name)) => match dir.find(name) {
+                Some(inode) => inode,
+                None if flags.contains(OpenFlags::CREATE) => {
+                    dir.create(name).ok_or(Errno::EEXIST)?
+                }
+                None => return Err(Errno::ENOENT),
+            },
+            Err(Errno::EEXIST) => self.root.clone(),
+            Err(err) => return Err(err),
+        };
+        //命名管道只能以只读或只写方式打开，TRUNC 不起作用
+        if inode.is_fifo() {
+            if readable && writable {
+                return Err(Errno::EINVAL);
+            }
+            let nonblock = flags.contains(OpenFlags::NONBLOCK);
+            return Ok(open_fifo(inode.inode_id(), writable, nonblock)?);
+        }
+        if inode.is_dir() {
+            if writable || flags.contains(OpenFlags::TRUNC) {
+                return Err(Errno::EISDIR);
+            }
+        } else if flags.contains(OpenFlags::TRUNC) {
+            inode.clear();
+        }
+        Ok(Arc::new(OSInode::new(readable, writable, inode)))
+    }
+    fn mkdir(&self, path: &[&str]) -> Result<(), Errno> {
+        let (dir, name) = self.lookup_parent(path)?;
+        dir.create_dir(name).map(|_| ()).ok_or(Errno::EEXIST)
+    }
+    fn mkfifo(&self, path: &[&str]) -> Result<(), Errno> {
+        let (dir, name) = self.lookup_parent(path)?;
+        dir.create_fifo(name).map(|_| ()).ok_or(Errno::EEXIST)
+    }
+    fn link(&self, old_path: &[&str], new_path: &[&str]) -> Result<(), Errno> {
+        let inode = self.lookup(old_path)?;
+        if inode.is_dir() {
+            return Err(Errno::EPERM);
+        }
+        let (dir, name) = self.lookup_parent(new_path)?;
+        if dir.link(name, &inode) {
+            Ok(())
+        } else {
+            Err(Errno::EEXIST)
+        }
+    }
+    //删除最后一个链接且文件没有被打开时回收文件
+    fn unlink(&self, path: &[&str]) -> Result<(), Errno> {
+        let (dir, name) = self.lookup_parent(path).map_err(|err| match err {
+            Errno::EEXIST => Errno::EISDIR,
+            err => err,
+        })?;
+        let inode = dir.find(name).ok_or(Errno::ENOENT)?;
+        if inode.is_dir() {
+            return Err(Errno::EISDIR);
+        }
+        dir.unlink(name);
+        if inode.is_fifo() && inode.nlink() == 0 {
+            forget_fifo(inode.inode_id());
+        }
+        release_if_orphan(&inode);
+        Ok(())
+    }
+    fn read_file(&self, path: &[&str]) -> Result<Vec<u8>, Errno> {
+        let inode = self.lookup(path)?;
+        if inode.is_dir() || inode.is_fifo() {
+            return Err(Errno::EACCES);
+        }
+        Ok(read_all(&inode))
+    }
+    //所有挂载的 easy-fs 都在同一个块设备上，共用一个块缓存
+    fn sync(&self) {
+        block_cache_sync_all();
+    }
+}
+
+impl File for OSInode {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+    fn writable(&self) -> bool {
+        self.writable
+    }
+    //经由内核中的缓冲区在 inode 与用户缓冲区之间复制，每次不超过一个块。
+    //每复制一块都释放 inner 并经过一次抢占点，读写大文件时不会长时间占据处理器
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let inode = self.inner.exclusive_access().inode.clone();
+        if inode.is_dir() {
+            return Err(Errno::EISDIR);
+        }
+        let mut chunk = [0u8; 512];
+        let mut total_read_size = 0usize;
+        while total_read_size < buf.len() {
+            let len = chunk.len().min(buf.len() - total_read_size);
+            let mut inner = self.inner.exclusive_access();
+            let read_size = inode.read_at(inner.offset, &mut chunk[..len]);
+            if read_size == 0 {
+                break;
+            }
+            buf.write_at(total_read_size, &chunk[..read_size])?;
+            inner.offset += read_size;
+            drop(inner);
+            total_read_size += read_size;
+            preempt_point();
+        }
+        Ok(total_read_size)
+    }
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let inode = self.inner.exclusive_access().inode.clone();
+        let mut chunk = [0u8; 512];
+        let mut total_write_size = 0usize;
+        while total_write_size < buf.len() {
+            let len = chunk.len().min(buf.len() - total_write_size);
+            let mut inner = self.inner.exclusive_access();
+            buf.read_at(total_write_size, &mut chunk[..len])?;
+            let write_size = inode.write_at(inner.offset, &chunk[..len]);
+            assert_eq!(write_size, len);
+            inner.offset += write_size;
+            drop(inner);
+            total_write_size += write_size;
+            preempt_point();
+        }
+        Ok(total_write_size)
+    }
+    //可以移动到文件末尾之后，之后的写入在中间留下全为 0 的空洞
+    fn seek(&self, offset: isize, whence: usize) -> Result<usize, Errno> {
+        let mut inner = self.inner.exclusive_access();
+        let base = match whence {
+            0 => 0,
+            1 => inner.offset,
+            2 => inner.inode.size(),
+            _ => return Err(Errno::EINVAL),
+        };
+        let offset = base as isize + offset;
+        if offset < 0 {
+            return Err(Errno::EINVAL);
+        }
+        inner.offset = offset as usize;
+        Ok(inner.offset)
+    }
+    fn stat(&self) -> Stat {
+        let inner = self.inner.exclusive_access();
+        let ino = inner.inode.inode_id();
+        let mode = if inner.inode.is_dir() {
+            StatMode::DIR
+        } else {
+            StatMode::FILE
+        };
+        let nlink = inner.inode.nlink();
+        Stat::new(0, ino as u64, mode, nlink, inner.inode.size() as u64)
+    }
+    fn sync(&self) -> Result<(), Errno> {
+        self.inner.exclusive_access().inode.sync();
+        Ok(())
+    }
+    //目录的读写位置是下一个要读的目录项的序号，删除目录项不会改变其余目录项的序号
+    fn getdents(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let mut inner = self.inner.exclusive_access();
+        if !inner.inode.is_dir() {
+            return Err(Errno::ENOTDIR);
+        }
+        let mut written = 0;
+        while let Some((index, name, inode)) = inner.inode.read_dirent(inner.offset) {
+            let d_type = if inode.is_dir() {
+                DT_DIR
+            } else if inode.is_fifo() {
+                DT_FIFO
+            } else {
+                DT_REG
+            };
+            let record = dirent64(inode.inode_id(), index + 1, d_type, &name);
+            if written + record.len() > buf.len() {
+                //一条记录也放不下时报告缓冲区太小
+                if written == 0 {
+                    return Err(Errno::EINVAL);
+                }
+                break;
+            }
+            buf.write_at(written, &record)?;
+            written += record.len();
+            inner.offset = index + 1;
+        }
+        Ok(written)
+    }
+}
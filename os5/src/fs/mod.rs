@@ -0,0 +1,241 @@
+//! 文件抽象
+//!
+//! 进程通过文件描述符访问的对象都实现 [`File`] 接口，系统调用只需按文件描述符在进程的文件描述符表中
+//! 找到对应的对象，不必关心它是标准输入输出、管道、设备还是文件系统中的文件。
+//!
+//! 各个文件系统实现 [`FileSystem`] 接口，挂载到同一个命名空间中，按路径操作文件时由挂载表找到路径所在的文件系统。
+
+mod devfs;
+mod initramfs;
+mod inode;
+mod mount;
+mod pipe;
+mod procfs;
+mod stdio;
+mod tty;
+
+use crate::drivers::block_device_present;
+use crate::errno::Errno;
+use crate::mm::UserBuffer;
+use crate::task::TaskControlBlock;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use bitflags::*;
+
+/// 文件描述符指向的对象
+//seek 与 stat 给出了默认实现，只有支持随机访问或者有自己元数据的文件需要实现它们。
+pub trait File: Send + Sync {
+    fn readable(&self) -> bool;
+    fn writable(&self) -> bool;
+    /// 从文件中读出数据填入 buf，返回实际读到的字节数，0 表示已经读到文件末尾
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno>;
+    /// 把 buf 中的数据写入文件，返回实际写入的字节数
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno>;
+    /// 与 read 相同，但没有数据可读时不阻塞，返回 Errno::EAGAIN；默认与 read 相同，适用于读取从不阻塞的文件
+    fn read_nonblock(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        self.read(buf)
+    }
+    /// 与 write 相同，但一个字节也写不进去时不阻塞，返回 Errno::EAGAIN；默认与 write 相同
+    fn write_nonblock(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        self.write(buf)
+    }
+    /// 按 whence（0 为文件开头，1 为当前位置，2 为文件末尾）加上 offset 移动读写位置，返回新的位置；
+    /// 不支持随机访问的文件返回 Errno::ESPIPE
+    fn seek(&self, _offset: isize, _whence: usize) -> Result<usize, Errno> {
+        Err(Errno::ESPIPE)
+    }
+    /// 文件的元数据，默认只有一个链接、类型未知、大小为 0
+    fn stat(&self) -> Stat {
+        Stat::new(0, 0, StatMode::NULL, 1, 0)
+    }
+    /// 从读写位置开始把目录项逐条填入 buf，返回填入的字节数，0 表示已经读完；
+    /// 不是目录的文件返回 Errno::ENOTDIR
+    fn getdents(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        Err(Errno::ENOTDIR)
+    }
+    /// 把文件的数据与元数据写回磁盘；不在磁盘上的文件返回 Errno::EINVAL
+    fn sync(&self) -> Result<(), Errno> {
+        Err(Errno::EINVAL)
+    }
+    /// 文件当前就绪的事件，读写时可能阻塞的文件需要实现它；
+    /// 默认读写从不阻塞，可读的文件总是可读，可写的文件总是可写
+    fn poll(&self) -> PollEvents {
+        let mut events = PollEvents::empty();
+        if self.readable() {
+            events |= PollEvents::IN;
+        }
+        if self.writable() {
+            events |= PollEvents::OUT;
+        }
+        events
+    }
+    /// 把 task 加入 poll 报告的事件变化时会被唤醒的等待队列，ppoll 在没有文件就绪时以此等待；
+    /// 默认的文件总是就绪，不需要等待
+    fn poll_register(&self, _task: &Arc<TaskControlBlock>) {}
+    /// 把 task 移出 poll_register 加入的等待队列
+    fn poll_unregister(&self, _task: &Arc<TaskControlBlock>) {}
+    /// 设备相关的控制操作，返回值由请求决定；不是终端的文件返回 Errno::ENOTTY
+    fn ioctl(&self, _request: usize, _arg: usize) -> Result<isize, Errno> {
+        Err(Errno::ENOTTY)
+    }
+}
+
+bitflags! {
+    /// ppoll 等待与报告的事件，数值与 Linux 的 POLL* 一致
+    pub struct PollEvents: u16 {
+        /// 读取不会阻塞：有数据可读，或者已经读到文件末尾
+        const IN = 0x001;
+        /// 写入不会阻塞
+        const OUT = 0x004;
+        /// 出错，例如管道的读端都已关闭
+        const ERR = 0x008;
+        /// 对端已经关闭，例如管道的写端都已关闭
+        const HUP = 0x010;
+        /// 文件描述符没有打开
+        const NVAL = 0x020;
+    }
+}
+
+/// 文件描述符表中的一项：打开的文件，以及打开时的访问方式与状态标志
+//状态标志记录在文件描述符上而不是文件上：dup 与 fork 得到的文件描述符各自有一份拷贝，由 fcntl 分别修改
+#[derive(Clone)]
+pub struct FdEntry {
+    pub file: Arc<dyn File>,
+    /// WRONLY、RDWR 与 [`OpenFlags::status`] 中的标志，CREATE 与 TRUNC 只在打开时起作用，不保留
+    pub flags: OpenFlags,
+}
+
+impl FdEntry {
+    pub fn new(file: Arc<dyn File>, flags: OpenFlags) -> Self {
+        Self {
+            file,
+            flags: flags & (OpenFlags::WRONLY | OpenFlags::RDWR | OpenFlags::status()),
+        }
+    }
+}
+
+/// 可以挂载到命名空间中的文件系统
+//路径已经由挂载表规范化并去掉了挂载点，是挂载点之下的各级名字，不含 . 与 ..，为空时指文件系统的根目录。
+//只读的文件系统不必实现创建与删除，默认返回 Errno::EPERM。
+pub trait FileSystem: Send + Sync {
+    /// 按路径打开文件或目录
+    fn open(&self, path: &[&str], flags: OpenFlags) -> Result<Arc<dyn File>, Errno>;
+    /// 创建目录
+    fn mkdir(&self, _path: &[&str]) -> Result<(), Errno> {
+        Err(Errno::EPERM)
+    }
+    /// 创建命名管道
+    fn mkfifo(&self, _path: &[&str]) -> Result<(), Errno> {
+        Err(Errno::EPERM)
+    }
+    /// 为 old_path 指向的文件创建新的硬链接 new_path
+    fn link(&self, _old_path: &[&str], _new_path: &[&str]) -> Result<(), Errno> {
+        Err(Errno::EPERM)
+    }
+    /// 删除一个硬链接
+    fn unlink(&self, _path: &[&str]) -> Result<(), Errno> {
+        Err(Errno::EPERM)
+    }
+    /// 读出普通文件的全部内容，用于加载可执行文件；不能这样读取的文件返回 Errno::EACCES
+    fn read_file(&self, _path: &[&str]) -> Result<Vec<u8>, Errno> {
+        Err(Errno::EACCES)
+    }
+    /// 把文件系统中所有修改过的数据写回磁盘，不在磁盘上的文件系统什么也不做
+    fn sync(&self) {}
+}
+
+bitflags! {
+    /// 文件的类型
+    pub struct StatMode: u32 {
+        const NULL  = 0;
+        /// 管道
+        const FIFO  = 0o010000;
+        /// 字符设备
+        const CHR   = 0o020000;
+        /// 目录
+        const DIR   = 0o040000;
+        /// 普通文件
+        const FILE  = 0o100000;
+    }
+}
+
+/// 文件的元数据，布局与用户库中的 Stat 一致
+#[repr(C)]
+#[derive(Debug)]
+pub struct Stat {
+    /// 文件所在设备的编号
+    pub dev: u64,
+    /// inode 编号
+    pub ino: u64,
+    /// 文件的类型与权限
+    pub mode: StatMode,
+    /// 硬链接数
+    pub nlink: u32,
+    /// 文件的字节数
+    pub size: u64,
+    pad: [u64; 6],
+}
+
+impl Stat {
+    pub fn new(dev: u64, ino: u64, mode: StatMode, nlink: u32, size: u64) -> Self {
+        Self {
+            dev,
+            ino,
+            mode,
+            nlink,
+            size,
+            pad: [0; 6],
+        }
+    }
+}
+
+/// getdents64 返回的目录项中表示命名管道、字符设备、目录与普通文件的 d_type
+const DT_FIFO: u8 = 1;
+const DT_CHR: u8 = 2;
+const DT_DIR: u8 = 4;
+const DT_REG: u8 = 8;
+
+//按 Linux 的 linux_dirent64 布局生成一条记录：d_ino、d_off、d_reclen、d_type 与以 0 结尾的名字，
+//整条记录按 8 字节对齐
+fn dirent64(ino: u32, next: usize, d_type: u8, name: &str) -> Vec<u8> {
+    let reclen = (19 + name.len() + 1 + 7) & !7;
+    let mut record = vec![0u8; reclen];
+    record[0..8].copy_from_slice(&(ino as u64).to_ne_bytes());
+    record[8..16].copy_from_slice(&(next as i64).to_ne_bytes());
+    record[16..18].copy_from_slice(&(reclen as u16).to_ne_bytes());
+    record[18] = d_type;
+    record[19..19 + name.len()].copy_from_slice(name.as_bytes());
+    record
+}
+
+pub use devfs::DevFs;
+pub use easy_fs::{block_cache_stats, block_cache_sync_all};
+pub use initramfs::{initramfs_ls, InitRamFs};
+pub use inode::{read_all, writeback_tick, EasyFs, OSInode, OpenFlags, ROOT_INODE};
+pub use mount::{
+    link_file, make_dir, make_fifo, mount, open_file, read_file, sync_all, umount, unlink_file,
+};
+pub use pipe::make_pipe;
+pub use procfs::ProcFs;
+pub use stdio::{Stdin, Stdout};
+pub use tty::tty_receive;
+
+//挂载文件系统，挂载点不存在时先创建
+fn mount_at(fstype: &str, target: &str) {
+    match make_dir(target) {
+        Ok(()) | Err(Errno::EEXIST) => {}
+        Err(err) => panic!("cannot create mount point {}: {:?}", target, err),
+    }
+    mount(fstype, target).unwrap();
+}
+
+/// 挂载根目录之外的文件系统。开启 initramfs 特性时根目录是 initramfs，
+/// 磁盘上的 easy-fs 挂载在 /mnt 上，没有块设备时不挂载
+pub fn init() {
+    if cfg!(feature = "initramfs") && block_device_present() {
+        mount_at("easyfs", "/mnt");
+    }
+    mount_at("devfs", "/dev");
+    mount_at("procfs", "/proc");
+}
@@ -0,0 +1,202 @@
+//! 挂载表
+//!
+//! 所有文件系统共用一个以 / 为根的命名空间，根目录上是磁盘上的 easy-fs，开启 initramfs 特性时则是 initramfs。
+//! 按路径操作文件时先把路径规范化，再找出作为路径前缀的最长的挂载点，把挂载点之下的部分交给那个文件系统。
+
+use super::{DevFs, EasyFs, File, FileSystem, InitRamFs, OpenFlags, ProcFs, StatMode, ROOT_INODE};
+use crate::drivers::block_device_present;
+use crate::errno::Errno;
+use crate::sync::UPSafeCell;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// 一个挂载点
+struct Mount {
+    /// 挂载点规范化之后的各级名字，根目录为空
+    target: Vec<String>,
+    fs: Arc<dyn FileSystem>,
+}
+
+lazy_static! {
+    /// 挂载表，第一项总是根目录上的文件系统
+    static ref MOUNTS: UPSafeCell<Vec<Mount>> = unsafe {
+        UPSafeCell::new(vec![Mount {
+            target: Vec::new(),
+            fs: root_fs(),
+        }])
+    };
+}
+
+//根目录上的文件系统，initramfs 不需要块设备
+fn root_fs() -> Arc<dyn FileSystem> {
+    if cfg!(feature = "initramfs") {
+        Arc::new(InitRamFs)
+    } else {
+        Arc::new(EasyFs::new(ROOT_INODE.clone()))
+    }
+}
+
+//把路径规范化为各级名字，. 与 .. 分别表示当前目录与上一级目录，根目录的上一级是它自己。
+//目前没有当前工作目录，相对路径也从根目录开始解析
+fn normalize(path: &str) -> Result<Vec<&str>, Errno> {
+    if path.is_empty() {
+        return Err(Errno::ENOENT);
+    }
+    let mut names = Vec::new();
+    for name in path.split('/') {
+        match name {
+            "" | "." => {}
+            ".." => {
+                names.pop();
+            }
+            _ => names.push(name),
+        }
+    }
+    Ok(names)
+}
+
+//路径所在的挂载点
+struct Resolved<'a> {
+    //挂载点在挂载表中的下标
+    index: usize,
+    fs: Arc<dyn FileSystem>,
+    //路径在挂载点之下的部分
+    names: Vec<&'a str>,
+}
+
+//找出路径所在的文件系统
+fn resolve(path: &str) -> Result<Resolved, Errno> {
+    let mut names = normalize(path)?;
+    let mounts = MOUNTS.exclusive_access();
+    let (index, mount) = mounts
+        .iter()
+        .enumerate()
+        .filter(|(_, mount)| {
+            mount.target.len() <= names.len()
+                && mount.target.iter().zip(names.iter()).all(|(a, b)| a == b)
+        })
+        .max_by_key(|(_, mount)| mount.target.len())
+        .unwrap();
+    names.drain(..mount.target.len());
+    Ok(Resolved {
+        index,
+        fs: mount.fs.clone(),
+        names,
+    })
+}
+
+/// 按路径打开文件或目录。
+/// 文件不存在且没有 CREATE 时返回 Errno::ENOENT，文件名过长时返回 Errno::ENAMETOOLONG，
+/// 路径中间某一级不是目录时返回 Errno::ENOTDIR，标志不合法时返回 Errno::EINVAL，
+/// 以写方式打开目录或截断目录时返回 Errno::EISDIR
+pub fn open_file(path: &str, flags: OpenFlags) -> Result<Arc<dyn File>, Errno> {
+    let resolved = resolve(path)?;
+    resolved.fs.open(&resolved.names, flags)
+}
+
+/// 读出 path 处普通文件的全部内容。文件不存在时返回 Errno::ENOENT，
+/// 是目录、命名管道或者不在磁盘上的文件时返回 Errno::EACCES
+pub fn read_file(path: &str) -> Result<Vec<u8>, Errno> {
+    let resolved = resolve(path)?;
+    resolved.fs.read_file(&resolved.names)
+}
+
+/// 创建目录。已经存在时返回 Errno::EEXIST，上一级目录不存在时返回 Errno::ENOENT
+pub fn make_dir(path: &str) -> Result<(), Errno> {
+    let resolved = resolve(path)?;
+    resolved.fs.mkdir(&resolved.names)
+}
+
+/// 创建命名管道。已经存在时返回 Errno::EEXIST，上一级目录不存在时返回 Errno::ENOENT，
+/// 所在的文件系统不支持时返回 Errno::EPERM
+pub fn make_fifo(path: &str) -> Result<(), Errno> {
+    let resolved = resolve(path)?;
+    resolved.fs.mkfifo(&resolved.names)
+}
+
+/// 为 old_path 指向的文件创建一个新的硬链接 new_path，两者可以在同一个文件系统的不同目录中。
+/// old_path 不存在时返回 Errno::ENOENT，new_path 已经存在时返回 Errno::EEXIST，
+/// 不能为目录创建硬链接，返回 Errno::EPERM；两者不在同一个挂载点之下时返回 Errno::EXDEV
+pub fn link_file(old_path: &str, new_path: &str) -> Result<(), Errno> {
+    let old = resolve(old_path)?;
+    let new = resolve(new_path)?;
+    if old.index != new.index {
+        return Err(Errno::EXDEV);
+    }
+    old.fs.link(&old.names, &new.names)
+}
+
+/// 删除 path 这个硬链接。path 不存在时返回 Errno::ENOENT，path 是目录时返回 Errno::EISDIR
+pub fn unlink_file(path: &str) -> Result<(), Errno> {
+    let resolved = resolve(path)?;
+    resolved.fs.unlink(&resolved.names)
+}
+
+//按类型名创建文件系统
+fn new_fs(fstype: &str) -> Option<Arc<dyn FileSystem>> {
+    match fstype {
+        //磁盘上的 easy-fs 只有一个，再次挂载时看到的是同一棵目录树
+        "easyfs" if block_device_present() => Some(Arc::new(EasyFs::new(ROOT_INODE.clone()))),
+        "initramfs" => Some(Arc::new(InitRamFs)),
+        "devfs" => Some(Arc::new(DevFs)),
+        "procfs" => Some(Arc::new(ProcFs)),
+        _ => None,
+    }
+}
+
+/// 把 fstype 类型的文件系统挂载到 target，target 必须是已经存在的目录。
+/// 不认识的类型以及没有块设备时挂载 easyfs 返回 Errno::ENODEV，target 不是目录时返回 Errno::ENOTDIR，
+/// target 上已经挂载了文件系统时返回 Errno::EBUSY
+pub fn mount(fstype: &str, target: &str) -> Result<(), Errno> {
+    let fs = new_fs(fstype).ok_or(Errno::ENODEV)?;
+    if !open_file(target, OpenFlags::RDONLY)?
+        .stat()
+        .mode
+        .contains(StatMode::DIR)
+    {
+        return Err(Errno::ENOTDIR);
+    }
+    let target: Vec<String> = normalize(target)?.into_iter().map(String::from).collect();
+    let mut mounts = MOUNTS.exclusive_access();
+    if mounts.iter().any(|mount| mount.target == target) {
+        return Err(Errno::EBUSY);
+    }
+    mounts.push(Mount { target, fs });
+    Ok(())
+}
+
+/// 卸载 target 上的文件系统。target 不是挂载点时返回 Errno::EINVAL，
+/// 不能卸载根目录以及还有其他挂载点在其下的文件系统，返回 Errno::EBUSY
+pub fn umount(target: &str) -> Result<(), Errno> {
+    let target = normalize(target)?;
+    let mut mounts = MOUNTS.exclusive_access();
+    let index = mounts
+        .iter()
+        .position(|mount| mount.target == target)
+        .ok_or(Errno::EINVAL)?;
+    let busy = index == 0
+        || mounts.iter().any(|mount| {
+            mount.target.len() > target.len() && mount.target[..target.len()] == target[..]
+        });
+    if busy {
+        return Err(Errno::EBUSY);
+    }
+    mounts.remove(index);
+    Ok(())
+}
+
+/// 把所有挂载的文件系统中修改过的数据写回磁盘
+pub fn sync_all() {
+    //写回时不持有挂载表
+    let filesystems: Vec<_> = MOUNTS
+        .exclusive_access()
+        .iter()
+        .map(|mount| mount.fs.clone())
+        .collect();
+    for fs in filesystems {
+        fs.sync();
+    }
+}
@@ -0,0 +1,366 @@
+//! 管道
+//!
+//! 一个管道由读端与写端两个 [`Pipe`] 组成，它们共享一段有界的环形缓冲区，分别作为文件放入文件描述符表。
+//! 缓冲区为空时读者阻塞，所有写端都关闭后读到文件末尾；缓冲区满时写者阻塞，所有读端都关闭后写入失败。
+//!
+//! 命名管道是文件系统中的一个 inode，没有亲缘关系的进程按路径打开它，得到同一个缓冲区的读端或写端。
+//! 缓冲区按 inode 编号记录在 [`FIFOS`] 中，两端都关闭后释放，之后再打开时重新创建。
+
+use super::{File, PollEvents, Stat, StatMode};
+use crate::config::PIPE_BUF_SIZE;
+use crate::errno::Errno;
+use crate::mm::UserBuffer;
+use crate::sync::UPSafeCell;
+use crate::task::{
+    block_current_and_run_next, current_add_signal, current_task, signal_pending, SignalFlags,
+    TaskControlBlock, WaitQueue,
+};
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use lazy_static::*;
+
+/// 管道的一端
+pub struct Pipe {
+    readable: bool,
+    writable: bool,
+    buffer: Arc<UPSafeCell<PipeRingBuffer>>,
+}
+
+/// 读端与写端共享的环形缓冲区
+//读端与写端只以 Weak 记录，文件描述符表中指向某一端的最后一个引用消失时，这一端就关闭了
+struct PipeRingBuffer {
+    arr: [u8; PIPE_BUF_SIZE],
+    /// 下一个要读出的字节的位置
+    head: usize,
+    /// 缓冲区中的字节数
+    len: usize,
+    read_end: Weak<Pipe>,
+    write_end: Weak<Pipe>,
+    /// 等待缓冲区中有数据的读者
+    readers: WaitQueue,
+    /// 等待缓冲区中有空间的写者
+    writers: WaitQueue,
+    /// 作为命名管道时读端与写端各自被打开的次数，打开一端时用来等待另一端被打开
+    read_opens: usize,
+    write_opens: usize,
+}
+
+impl PipeRingBuffer {
+    fn new() -> Self {
+        Self {
+            arr: [0; PIPE_BUF_SIZE],
+            head: 0,
+            len: 0,
+            read_end: Weak::new(),
+            write_end: Weak::new(),
+            readers: WaitQueue::new(),
+            writers: WaitQueue::new(),
+            read_opens: 0,
+            write_opens: 0,
+        }
+    }
+    //从缓冲区中取出至多 dst.len() 个字节，返回取出的字节数
+    fn pop(&mut self, dst: &mut [u8]) -> usize {
+        let n = dst.len().min(self.len);
+        for byte in dst[..n].iter_mut() {
+            *byte = self.arr[self.head];
+            self.head = (self.head + 1) % PIPE_BUF_SIZE;
+        }
+        self.len -= n;
+        n
+    }
+    //把 src 中尽可能多的字节放入缓冲区，返回放入的字节数
+    fn push(&mut self, src: &[u8]) -> usize {
+        let n = src.len().min(PIPE_BUF_SIZE - self.len);
+        for &byte in src[..n].iter() {
+            self.arr[(self.head + self.len) % PIPE_BUF_SIZE] = byte;
+            self.len += 1;
+        }
+        n
+    }
+    fn all_read_ends_closed(&self) -> bool {
+        self.read_end.upgrade().is_none()
+    }
+    fn all_write_ends_closed(&self) -> bool {
+        self.write_end.upgrade().is_none()
+    }
+}
+
+/// 创建一个管道，返回 (读端, 写端)
+pub fn make_pipe() -> (Arc<Pipe>, Arc<Pipe>) {
+    let buffer = Arc::new(unsafe { UPSafeCell::new(PipeRingBuffer::new()) });
+    let read_end = Arc::new(Pipe {
+        readable: true,
+        writable: false,
+        buffer: buffer.clone(),
+    });
+    let write_end = Arc::new(Pipe {
+        readable: false,
+        writable: true,
+        buffer: buffer.clone(),
+    });
+    let mut ring = buffer.exclusive_access();
+    ring.read_end = Arc::downgrade(&read_end);
+    ring.write_end = Arc::downgrade(&write_end);
+    drop(ring);
+    (read_end, write_end)
+}
+
+lazy_static! {
+    /// 打开的命名管道的缓冲区，按 inode 编号查找
+    static ref FIFOS: UPSafeCell<BTreeMap<u32, Weak<UPSafeCell<PipeRingBuffer>>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
+}
+
+//找到 inode 编号为 ino 的命名管道正在使用的缓冲区，没有时创建一个
+fn fifo_buffer(ino: u32) -> Arc<UPSafeCell<PipeRingBuffer>> {
+    let mut fifos = FIFOS.exclusive_access();
+    //顺便清理两端都已经关闭的缓冲区
+    fifos.retain(|_, buffer| buffer.strong_count() > 0);
+    if let Some(buffer) = fifos.get(&ino).and_then(|buffer| buffer.upgrade()) {
+        return buffer;
+    }
+    let buffer = Arc::new(unsafe { UPSafeCell::new(PipeRingBuffer::new()) });
+    fifos.insert(ino, Arc::downgrade(&buffer));
+    buffer
+}
+
+/// 打开 inode 编号为 ino 的命名管道的读端或写端。
+/// 同一端的多次打开共享同一个 [`Pipe`]；另一端还没有被打开过时阻塞，直到有进程打开另一端。
+/// nonblock 时不等待：读端总是立即打开，没有读端时打开写端返回 Errno::ENXIO；
+/// 等待时被信号打断返回 Errno::EINTR
+pub fn open_fifo(ino: u32, writable: bool, nonblock: bool) -> Result<Arc<Pipe>, Errno> {
+    let buffer = fifo_buffer(ino);
+    let mut ring = buffer.exclusive_access();
+    if nonblock && writable && ring.all_read_ends_closed() {
+        return Err(Errno::ENXIO);
+    }
+    let existing = if writable {
+        ring.write_end.upgrade()
+    } else {
+        ring.read_end.upgrade()
+    };
+    let end = existing.unwrap_or_else(|| {
+        let end = Arc::new(Pipe {
+            readable: !writable,
+            writable,
+            buffer: buffer.clone(),
+        });
+        if writable {
+            ring.write_end = Arc::downgrade(&end);
+        } else {
+            ring.read_end = Arc::downgrade(&end);
+        }
+        end
+    });
+    //记下另一端此前被打开的次数：另一端打开过之后即使已经关闭，也不必再等待
+    let peer_opens = if writable {
+        ring.write_opens += 1;
+        ring.read_opens
+    } else {
+        ring.read_opens += 1;
+        ring.write_opens
+    };
+    ring.readers.wake_all();
+    ring.writers.wake_all();
+    loop {
+        let (peer_closed, opens) = if writable {
+            (ring.all_read_ends_closed(), ring.read_opens)
+        } else {
+            (ring.all_write_ends_closed(), ring.write_opens)
+        };
+        if nonblock || !peer_closed || opens != peer_opens {
+            break;
+        }
+        //返回前释放缓冲区的借用，关闭刚打开的这一端时要唤醒另一端的等待者
+        if signal_pending() {
+            drop(ring);
+            return Err(Errno::EINTR);
+        }
+        if writable {
+            ring.writers.push(current_task().unwrap());
+        } else {
+            ring.readers.push(current_task().unwrap());
+        }
+        drop(ring);
+        block_current_and_run_next();
+        ring = buffer.exclusive_access();
+        //被信号唤醒时还留在等待队列中
+        let task = current_task().unwrap();
+        ring.writers.remove(&task);
+        ring.readers.remove(&task);
+    }
+    drop(ring);
+    Ok(end)
+}
+
+/// 命名管道的 inode 被删除后忘掉它的缓冲区，已经打开的两端照常使用，
+/// 之后复用这个 inode 编号的命名管道不会接上原来的缓冲区
+pub fn forget_fifo(ino: u32) {
+    FIFOS.exclusive_access().remove(&ino);
+}
+
+impl File for Pipe {
+    fn readable(&self) -> bool {
+        self.readable
+    }
+    fn writable(&self) -> bool {
+        self.writable
+    }
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        self.read_buffer(buf, false)
+    }
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        self.write_buffer(buf, false)
+    }
+    fn read_nonblock(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        self.read_buffer(buf, true)
+    }
+    fn write_nonblock(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        self.write_buffer(buf, true)
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(0, 0, StatMode::FIFO, 1, 0)
+    }
+    //读端在缓冲区不空时可读，写端都关闭时报告 HUP；写端在缓冲区不满时可写，读端都关闭时报告 ERR
+    fn poll(&self) -> PollEvents {
+        let ring = self.buffer.exclusive_access();
+        let mut events = PollEvents::empty();
+        if self.readable {
+            if ring.len > 0 {
+                events |= PollEvents::IN;
+            }
+            if ring.all_write_ends_closed() {
+                events |= PollEvents::HUP;
+            }
+        }
+        if self.writable {
+            if ring.all_read_ends_closed() {
+                events |= PollEvents::ERR;
+            } else if ring.len < PIPE_BUF_SIZE {
+                events |= PollEvents::OUT;
+            }
+        }
+        events
+    }
+    //读端与写端的等待者在缓冲区变化或另一端关闭时被唤醒，poll 报告的事件正是随之变化
+    fn poll_register(&self, task: &Arc<TaskControlBlock>) {
+        let mut ring = self.buffer.exclusive_access();
+        if self.readable {
+            ring.readers.push(task.clone());
+        }
+        if self.writable {
+            ring.writers.push(task.clone());
+        }
+    }
+    fn poll_unregister(&self, task: &Arc<TaskControlBlock>) {
+        let mut ring = self.buffer.exclusive_access();
+        ring.readers.remove(task);
+        ring.writers.remove(task);
+    }
+}
+
+impl Pipe {
+    //缓冲区为空时阻塞，nonblock 时返回 Errno::EAGAIN，阻塞时被信号打断返回 Errno::EINTR；
+    //一旦有数据就返回，不等待读满 buf。
+    //访问用户缓冲区时不持有环形缓冲区：数据先取到内核中，复制失败时这些数据丢失
+    fn read_buffer(&self, buf: UserBuffer, nonblock: bool) -> Result<usize, Errno> {
+        if buf.len() == 0 {
+            return Ok(0);
+        }
+        let mut data = vec![0u8; buf.len().min(PIPE_BUF_SIZE)];
+        let n = loop {
+            let mut ring = self.buffer.exclusive_access();
+            if ring.len > 0 {
+                let n = ring.pop(&mut data);
+                ring.writers.wake_all();
+                break n;
+            }
+            if ring.all_write_ends_closed() {
+                return Ok(0);
+            }
+            if nonblock {
+                return Err(Errno::EAGAIN);
+            }
+            if signal_pending() {
+                return Err(Errno::EINTR);
+            }
+            //被唤醒之后重新检查缓冲区
+            ring.readers.push(current_task().unwrap());
+            drop(ring);
+            block_current_and_run_next();
+            //被信号唤醒时还留在等待队列中
+            let task = current_task().unwrap();
+            self.buffer.exclusive_access().readers.remove(&task);
+        };
+        buf.write_at(0, &data[..n])?;
+        Ok(n)
+    }
+    //写完 buf 中的全部数据才返回，缓冲区满时阻塞；
+    //nonblock 时只写入放得下的部分，一个字节也放不下时返回 Errno::EAGAIN；
+    //阻塞时被信号打断，返回已经写入的字节数，还没有写入任何数据时返回 Errno::EINTR。
+    //所有读端都关闭时，当前进程收到 SIGPIPE，还没有写入任何数据时返回 Errno::EPIPE
+    fn write_buffer(&self, buf: UserBuffer, nonblock: bool) -> Result<usize, Errno> {
+        let mut data = vec![0u8; buf.len().min(PIPE_BUF_SIZE)];
+        let mut written = 0;
+        while written < buf.len() {
+            let mut ring = self.buffer.exclusive_access();
+            if ring.all_read_ends_closed() {
+                drop(ring);
+                current_add_signal(SignalFlags::SIGPIPE);
+                return if written > 0 {
+                    Ok(written)
+                } else {
+                    Err(Errno::EPIPE)
+                };
+            }
+            let space = PIPE_BUF_SIZE - ring.len;
+            if space == 0 {
+                if nonblock {
+                    return if written > 0 {
+                        Ok(written)
+                    } else {
+                        Err(Errno::EAGAIN)
+                    };
+                }
+                if signal_pending() {
+                    return if written > 0 {
+                        Ok(written)
+                    } else {
+                        Err(Errno::EINTR)
+                    };
+                }
+                ring.writers.push(current_task().unwrap());
+                drop(ring);
+                block_current_and_run_next();
+                //被信号唤醒时还留在等待队列中
+                let task = current_task().unwrap();
+                self.buffer.exclusive_access().writers.remove(&task);
+                continue;
+            }
+            drop(ring);
+            let chunk = &mut data[..space.min(buf.len() - written)];
+            if let Err(err) = buf.read_at(written, chunk) {
+                return if written > 0 { Ok(written) } else { Err(err) };
+            }
+            let mut ring = self.buffer.exclusive_access();
+            written += ring.push(chunk);
+            ring.readers.wake_all();
+        }
+        Ok(written)
+    }
+}
+
+//一端关闭时唤醒另一端的等待者，让它们看到文件末尾或写入失败
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        let mut ring = self.buffer.exclusive_access();
+        if self.writable {
+            ring.readers.wake_all();
+        }
+        if self.readable {
+            ring.writers.wake_all();
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! 进程文件系统
+//!
+//! 挂载在 /proc 上的只读文件系统，文件内容在读取时由内核中的数据结构生成：
+//! /proc/meminfo 是物理内存、内核堆与交换区的使用情况；每个尚未退出的进程有一个以 PID 命名的目录，
+//! 其中的 status 给出进程的名字、状态、优先级、驻留集大小与各个系统调用的调用次数。
+
+use super::{dirent64, File, FileSystem, OpenFlags, Stat, StatMode, DT_DIR, DT_REG};
+use crate::config::{MAX_SYSCALL_NUM, PAGE_SIZE, SWAP_SLOTS};
+use crate::errno::Errno;
+use crate::mm::{frame_stats, heap_stats, swap_enabled, swap_used, UserBuffer};
+use crate::sync::UPSafeCell;
+use crate::task::{all_tasks, pid2task, TaskStatus};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// procfs 中文件的设备编号
+const PROCFS_DEV: u64 = 2;
+
+/// 进程文件系统
+pub struct ProcFs;
+
+/// procfs 中的目录或文件
+#[derive(Clone, Copy)]
+enum Node {
+    Root,
+    MemInfo,
+    /// 进程的目录
+    Process(usize),
+    /// 进程的 status 文件
+    Status(usize),
+}
+
+impl Node {
+    //按路径查找，进程不存在时返回 Errno::ENOENT
+    fn lookup(path: &[&str]) -> Result<Self, Errno> {
+        let node = match path {
+            [] => Node::Root,
+            ["meminfo", ..] => Node::MemInfo,
+            [pid, ..] => {
+                let pid = pid.parse::<usize>().map_err(|_| Errno::ENOENT)?;
+                pid2task(pid).ok_or(Errno::ENOENT)?;
+                match path.get(1) {
+                    None => Node::Process(pid),
+                    Some(&"status") => Node::Status(pid),
+                    Some(_) => return Err(Errno::ENOENT),
+                }
+            }
+        };
+        //文件之下不能再有路径
+        let depth = match node {
+            Node::Root => 0,
+            Node::MemInfo | Node::Process(_) => 1,
+            Node::Status(_) => 2,
+        };
+        if path.len() > depth {
+            return Err(Errno::ENOTDIR);
+        }
+        Ok(node)
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self, Node::Root | Node::Process(_))
+    }
+
+    fn ino(&self) -> u64 {
+        match *self {
+            Node::Root => 1,
+            Node::MemInfo => 2,
+            Node::Process(pid) => 0x1000 + 2 * pid as u64,
+            Node::Status(pid) => 0x1000 + 2 * pid as u64 + 1,
+        }
+    }
+
+    //目录中的目录项，每次读取时重新生成
+    fn entries(&self) -> Vec<(String, Node)> {
+        match *self {
+            Node::Root => {
+                let mut entries = vec![(String::from("meminfo"), Node::MemInfo)];
+                for task in all_tasks() {
+                    let pid = task.getpid();
+                    entries.push((format!("{}", pid), Node::Process(pid)));
+                }
+                entries
+            }
+            Node::Process(pid) => vec![(String::from("status"), Node::Status(pid))],
+            _ => Vec::new(),
+        }
+    }
+
+    //文件的内容，进程已经退出时返回 Errno::ESRCH
+    fn content(&self) -> Result<String, Errno> {
+        match *self {
+            Node::MemInfo => Ok(meminfo()),
+            Node::Status(pid) => status(pid),
+            _ => Err(Errno::EISDIR),
+        }
+    }
+}
+
+fn meminfo() -> String {
+    let kb = PAGE_SIZE / 1024;
+    let (total_frames, free_frames, peak_frames) = frame_stats();
+    let (heap_total, heap_used) = heap_stats();
+    let (swap_total, swap_free) = if swap_enabled() {
+        let used = swap_used();
+        (SWAP_SLOTS, SWAP_SLOTS - used)
+    } else {
+        (0, 0)
+    };
+    let mut s = String::new();
+    writeln!(s, "MemTotal:\t{} kB", total_frames * kb).unwrap();
+    writeln!(s, "MemFree:\t{} kB", free_frames * kb).unwrap();
+    writeln!(s, "MemPeak:\t{} kB", peak_frames * kb).unwrap();
+    writeln!(s, "HeapTotal:\t{} kB", heap_total / 1024).unwrap();
+    writeln!(s, "HeapUsed:\t{} kB", heap_used / 1024).unwrap();
+    writeln!(s, "SwapTotal:\t{} kB", swap_total * kb).unwrap();
+    writeln!(s, "SwapFree:\t{} kB", swap_free * kb).unwrap();
+    s
+}
+
+fn status(pid: usize) -> Result<String, Errno> {
+    let task = pid2task(pid).ok_or(Errno::ESRCH)?;
+    let (name, state, priority) = {
+        let inner = task.inner_exclusive_access();
+        let state = match inner.task_status {
+            TaskStatus::UnInit | TaskStatus::Ready => "R (ready)",
+            TaskStatus::Running => "R (running)",
+            TaskStatus::Blocked => "S (sleeping)",
+            TaskStatus::Zombie => "Z (zombie)",
+        };
+        (inner.name.clone(), state, inner.priority)
+    };
+    let process = task.process.clone();
+    let process_inner = process.inner_exclusive_access();
+    let ppid = process_inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.getpid());
+    let (rss, peak_rss) = process_inner.memory_set.rss();
+    let threads: Vec<_> = process_inner.tasks.iter().flatten().cloned().collect();
+    drop(process_inner);
+    //进程中所有线程的系统调用次数之和
+    let mut syscall_times = [0u32; MAX_SYSCALL_NUM];
+    for thread in threads.iter() {
+        let thread_inner = thread.inner_exclusive_access();
+        for (total, times) in syscall_times.iter_mut().zip(thread_inner.syscall_times) {
+            *total += times;
+        }
+    }
+    let kb = PAGE_SIZE / 1024;
+    let mut s = String::new();
+    writeln!(s, "Name:\t{}", name).unwrap();
+    writeln!(s, "Pid:\t{}", pid).unwrap();
+    writeln!(s, "PPid:\t{}", ppid).unwrap();
+    writeln!(s, "State:\t{}", state).unwrap();
+    writeln!(s, "Priority:\t{}", priority).unwrap();
+    writeln!(s, "Threads:\t{}", threads.len()).unwrap();
+    writeln!(s, "VmRSS:\t{} kB", rss * kb).unwrap();
+    writeln!(s, "VmHWM:\t{} kB", peak_rss * kb).unwrap();
+    //只列出调用过的系统调用，格式为 ID:次数
+    s.push_str("Syscalls:");
+    for (id, times) in syscall_times.iter().enumerate() {
+        if *times > 0 {
+            write!(s, " {}:{}", id, times).unwrap();
+        }
+    }
+    s.push('\n');
+    Ok(s)
+}
+
+/// 打开的 procfs 目录或文件
+struct ProcFile {
+    node: Node,
+    inner: UPSafeCell<ProcFileInner>,
+}
+
+struct ProcFileInner {
+    //文件的读写位置，或者目录中下一个要读的目录项的序号
+    offset: usize,
+    //从开头读文件时生成的内容，之后的读取都在这份内容上进行
+    content: Vec<u8>,
+}
+
+impl FileSystem for ProcFs {
+    fn open(&self, path: &[&str], flags: OpenFlags) -> Result<Arc<dyn File>, Errno> {
+        let (_, writable) = flags.read_write().ok_or(Errno::EINVAL)?;
+        let node = Node::lookup(path)?;
+        if writable || flags.contains(OpenFlags::TRUNC) {
+            return Err(if node.is_dir() {
+                Errno::EISDIR
+            } else {
+                Errno::EACCES
+            });
+        }
+        Ok(Arc::new(ProcFile {
+            node,
+            inner: unsafe {
+                UPSafeCell::new(ProcFileInner {
+                    offset: 0,
+                    content: Vec::new(),
+                })
+            },
+        }))
+    }
+}
+
+impl File for ProcFile {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        if self.node.is_dir() {
+            return Err(Errno::EISDIR);
+        }
+        let mut inner = self.inner.exclusive_access();
+        if inner.offset == 0 {
+            inner.content = self.node.content()?.into_bytes();
+        }
+        let start = inner.offset.min(inner.content.len());
+        let len = buf.len().min(inner.content.len() - start);
+        buf.write_at(0, &inner.content[start..start + len])?;
+        inner.offset = start + len;
+        Ok(len)
+    }
+    fn write(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        Err(Errno::EBADF)
+    }
+    //移动到开头之后再读会重新生成内容
+    fn seek(&self, offset: isize, whence: usize) -> Result<usize, Errno> {
+        let mut inner = self.inner.exclusive_access();
+        let base = match whence {
+            0 => 0,
+            1 => inner.offset,
+            2 => inner.content.len(),
+            _ => return Err(Errno::EINVAL),
+        };
+        let offset = base as isize + offset;
+        if offset < 0 {
+            return Err(Errno::EINVAL);
+        }
+        inner.offset = offset as usize;
+        Ok(inner.offset)
+    }
+    fn stat(&self) -> Stat {
+        let mode = if self.node.is_dir() {
+            StatMode::DIR
+        } else {
+            StatMode::FILE
+        };
+        Stat::new(PROCFS_DEV, self.node.ino(), mode, 1, 0)
+    }
+    fn getdents(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        if !self.node.is_dir() {
+            return Err(Errno::ENOTDIR);
+        }
+        let mut inner = self.inner.exclusive_access();
+        let entries = self.node.entries();
+        let mut written = 0;
+        while let Some((name, node)) = entries.get(inner.offset) {
+            let d_type = if node.is_dir() { DT_DIR } else { DT_REG };
+            let record = dirent64(node.ino() as u32, inner.offset + 1, d_type, name);
+            if written + record.len() > buf.len() {
+                //一条记录也放不下时报告缓冲区太小
+                if written == 0 {
+                    return Err(Errno::EINVAL);
+                }
+                break;
+            }
+            buf.write_at(written, &record)?;
+            written += record.len();
+            inner.offset += 1;
+        }
+        Ok(written)
+    }
+}
@@ -0,0 +1,80 @@
+//! 标准输入输出：输入来自终端，输出经由 SBI 控制台
+
+use super::tty::{tty_ioctl, tty_poll_register, tty_poll_unregister, tty_read, tty_readable};
+use super::{File, PollEvents, Stat, StatMode};
+use crate::errno::Errno;
+use crate::mm::UserBuffer;
+use crate::task::TaskControlBlock;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+
+/// 标准输入
+pub struct Stdin;
+
+/// 标准输出，也用作标准错误输出
+pub struct Stdout;
+
+impl File for Stdin {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    //终端中还没有输入时阻塞，由串口中断唤醒
+    fn read(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        tty_read(buf, false)
+    }
+    //终端中还没有输入时返回 Errno::EAGAIN
+    fn read_nonblock(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        tty_read(buf, true)
+    }
+    fn write(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        panic!("Cannot write to stdin!");
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(0, 0, StatMode::CHR, 1, 0)
+    }
+    fn poll(&self) -> PollEvents {
+        if tty_readable() {
+            PollEvents::IN
+        } else {
+            PollEvents::empty()
+        }
+    }
+    fn poll_register(&self, task: &Arc<TaskControlBlock>) {
+        tty_poll_register(task);
+    }
+    fn poll_unregister(&self, task: &Arc<TaskControlBlock>) {
+        tty_poll_unregister(task);
+    }
+    fn ioctl(&self, request: usize, arg: usize) -> Result<isize, Errno> {
+        tty_ioctl(request, arg)
+    }
+}
+
+impl File for Stdout {
+    fn readable(&self) -> bool {
+        false
+    }
+    fn writable(&self) -> bool {
+        true
+    }
+    fn read(&self, _buf: UserBuffer) -> Result<usize, Errno> {
+        panic!("Cannot read from stdout!");
+    }
+    //多字节字符可能跨越页面，拼接之后再解码，不合法的 UTF-8 序列输出为替换字符
+    fn write(&self, buf: UserBuffer) -> Result<usize, Errno> {
+        let mut bytes = vec![0u8; buf.len()];
+        buf.read_at(0, &mut bytes)?;
+        print!("{}", String::from_utf8_lossy(&bytes));
+        Ok(bytes.len())
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(0, 0, StatMode::CHR, 1, 0)
+    }
+    fn ioctl(&self, request: usize, arg: usize) -> Result<isize, Errno> {
+        tty_ioctl(request, arg)
+    }
+}
@@ -0,0 +1,189 @@
+//! 终端
+//!
+//! 控制台的输入由串口中断送来，经过行规程处理之后放入输入缓冲区，读者在缓冲区为空时阻塞，由中断唤醒。
+//! 原始模式下收到的字节立即可读；规范模式下按行编辑：退格删除行中最后一个字符，收到回车或换行之后整行才可读，
+//! 每次读取最多读到行尾。打开 ECHO 时把输入回显到控制台。
+//! 模式由 ioctl 的 TCGETS 与 TCSETS 读取与设置，默认为不回显的原始模式，用户态的 shell 自己回显与处理退格。
+
+use crate::config::TTY_BUF_SIZE;
+use crate::errno::Errno;
+use crate::mm::{copy_from_user, copy_to_user, UserBuffer};
+use crate::sbi::console_putchar;
+use crate::sync::UPSafeCell;
+use crate::task::{
+    block_current_and_run_next, current_task, current_user_token, signal_pending,
+    TaskControlBlock, WaitQueue,
+};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// ioctl 的请求：读取与设置终端属性，参数指向 Termios
+pub const TCGETS: usize = 0x5401;
+pub const TCSETS: usize = 0x5402;
+
+/// c_lflag：规范模式，按行编辑
+pub const ICANON: u32 = 0o2;
+/// c_lflag：回显输入
+pub const ECHO: u32 = 0o10;
+
+/// 终端属性，布局与 Linux 的 struct termios 一致，目前只有 c_lflag 中的 ICANON 与 ECHO 起作用
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct Termios {
+    pub c_iflag: u32,
+    pub c_oflag: u32,
+    pub c_cflag: u32,
+    pub c_lflag: u32,
+    pub c_line: u8,
+    pub c_cc: [u8; 19],
+}
+
+struct Tty {
+    termios: Termios,
+    /// 可以读取的字节
+    input: VecDeque<u8>,
+    /// 规范模式下正在编辑、还不能读取的一行
+    line: Vec<u8>,
+    /// 等待输入的读者
+    readers: WaitQueue,
+}
+
+lazy_static! {
+    static ref TTY: UPSafeCell<Tty> = unsafe {
+        UPSafeCell::new(Tty {
+            termios: Termios::default(),
+            input: VecDeque::new(),
+            line: Vec::new(),
+            readers: WaitQueue::new(),
+        })
+    };
+}
+
+impl Tty {
+    fn canonical(&self) -> bool {
+        self.termios.c_lflag & ICANON != 0
+    }
+    fn echo(&self, bytes: &[u8]) {
+        if self.termios.c_lflag & ECHO != 0 {
+            for &byte in bytes {
+                console_putchar(byte as usize);
+            }
+        }
+    }
+    //把字节放入输入缓冲区并唤醒读者，放不下的部分被丢弃
+    fn push_input(&mut self, bytes: &[u8]) {
+        let room = TTY_BUF_SIZE - self.input.len();
+        self.input.extend(bytes.iter().take(room));
+        self.readers.wake_all();
+    }
+}
+
+/// 串口收到一个字节，在串口中断中调用
+pub fn tty_receive(byte: u8) {
+    let mut tty = TTY.exclusive_access();
+    if !tty.canonical() {
+        tty.echo(&[byte]);
+        tty.push_input(&[byte]);
+        return;
+    }
+    match byte {
+        //终端发送的回车与换行都结束一行
+        b'\r' | b'\n' => {
+            tty.echo(b"\n");
+            let mut line = core::mem::take(&mut tty.line);
+            line.push(b'\n');
+            tty.push_input(&line);
+        }
+        //退格与删除键
+        0x08 | 0x7f => {
+            if tty.line.pop().is_some() {
+                tty.echo(b"\x08 \x08");
+            }
+        }
+        _ => {
+            if tty.line.len() < TTY_BUF_SIZE - 1 {
+                tty.echo(&[byte]);
+                tty.line.push(byte);
+            }
+        }
+    }
+}
+
+/// 从终端读取输入。没有可读的字节时阻塞，nonblock 时返回 Errno::EAGAIN；
+/// 阻塞时被信号打断返回 Errno::EINTR。一旦有字节就返回，不等待读满 buf，规范模式下最多读到行尾
+pub fn tty_read(buf: UserBuffer, nonblock: bool) -> Result<usize, Errno> {
+    if buf.len() == 0 {
+        return Ok(0);
+    }
+    let data = loop {
+        let mut tty = TTY.exclusive_access();
+        if !tty.input.is_empty() {
+            let mut data = vec![];
+            while data.len() < buf.len() {
+                match tty.input.pop_front() {
+                    Some(byte) => data.push(byte),
+                    None => break,
+                }
+                if tty.canonical() && data.last() == Some(&b'\n') {
+                    break;
+                }
+            }
+            break data;
+        }
+        if nonblock {
+            return Err(Errno::EAGAIN);
+        }
+        if signal_pending() {
+            return Err(Errno::EINTR);
+        }
+        tty.readers.push(current_task().unwrap());
+        drop(tty);
+        block_current_and_run_next();
+        //被信号唤醒时还留在等待队列中
+        TTY.exclusive_access().readers.remove(&current_task().unwrap());
+    };
+    buf.write_at(0, &data)?;
+    Ok(data.len())
+}
+
+/// 是否有可以读取的输入
+pub fn tty_readable() -> bool {
+    !TTY.exclusive_access().input.is_empty()
+}
+
+/// 有输入到来时唤醒 task，用于 ppoll 等待终端可读
+pub fn tty_poll_register(task: &Arc<TaskControlBlock>) {
+    TTY.exclusive_access().readers.push(task.clone());
+}
+
+/// 把 tty_poll_register 登记的 task 移出读者的等待队列
+pub fn tty_poll_unregister(task: &Arc<TaskControlBlock>) {
+    TTY.exclusive_access().readers.remove(task);
+}
+
+/// 终端的 ioctl：TCGETS 把终端属性写入 arg 指向的 Termios，TCSETS 从中读出新的属性。
+/// 从规范模式切换到原始模式时，正在编辑的一行立即可读。
+/// arg 不可访问时返回 Errno::EFAULT，其他请求返回 Errno::ENOTTY
+pub fn tty_ioctl(request: usize, arg: usize) -> Result<isize, Errno> {
+    let token = current_user_token();
+    match request {
+        TCGETS => {
+            let termios = TTY.exclusive_access().termios;
+            copy_to_user(token, arg as *mut Termios, &termios)?;
+        }
+        TCSETS => {
+            let termios: Termios = copy_from_user(token, arg as *const Termios)?;
+            let mut tty = TTY.exclusive_access();
+            tty.termios = termios;
+            if !tty.canonical() && !tty.line.is_empty() {
+                let line = core::mem::take(&mut tty.line);
+                tty.push_input(&line);
+            }
+        }
+        _ => return Err(Errno::ENOTTY),
+    }
+    Ok(0)
+}
@@ -0,0 +1,753 @@
+//! 内核调试器：GDB 远程串行协议（RSP）的服务端
+//!
+//! QEMU 带有 pci-serial 设备时（见 Makefile 中的 GDBSTUB），内核在这个串口上等待 GDB 连接：
+//! 内核 panic、执行编译进内核的 ebreak、命中 GDB 插入的断点、单步执行完一条指令或者收到 GDB 的 Ctrl-C 时，
+//! 在关中断的 trap 处理中停下来与 GDB 交互，GDB 让它继续运行之后再返回。
+//!
+//! 每个任务在 GDB 中是一个线程，线程号为 (pid + 1) << 16 | tid；没有当前任务（空闲或启动过程中）时
+//! 停下的执行流是 1 号线程。停下的线程的寄存器来自 trap 现场，其他线程只有任务切换时保存的 ra、sp 与 s0-s11，
+//! 它们的 pc 取 ra。停下时内核可能正借用着任务的状态，调试器只尝试借用，借用不到的任务不显示。
+//!
+//! 单步执行与 ptrace 一样在下一条指令可能的位置插入临时断点。内核代码段没有写权限，
+//! 但它是恒等映射的，断点在关闭分页之后按物理地址写入。
+
+use crate::config::GDB_SERIAL_PORT;
+use crate::drivers::uart::PolledUart;
+use crate::mm::{PageTable, PageTableEntry, VirtAddr};
+use crate::sync::UPSafeCell;
+use crate::task::{next_pcs, try_all_tasks, try_current_task, TaskControlBlock, TaskStatus};
+use crate::trap::KernelTrapFrame;
+use alloc::format;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
+
+/// 报告给 GDB 的停止原因：GDB 发来 Ctrl-C
+pub const SIGINT: usize = 2;
+/// 报告给 GDB 的停止原因：内核 panic
+pub const SIGABRT: usize = 6;
+//报告给 GDB 的停止原因：断点或单步执行
+const SIGTRAP: usize = 5;
+
+//收发的数据包（不含 $ 与校验和）的最大字节数，在 qSupported 中告诉 GDB
+const PACKET_SIZE: usize = 0x1000;
+//GDB 用 Z0 最多可以插入的断点数
+const MAX_BREAKPOINTS: usize = 32;
+//没有当前任务时停下的执行流的线程号
+const KERNEL_THREAD: usize = 1;
+//寄存器编号：x0-x31 之后是 pc
+const PC: usize = 32;
+const REGS: usize = 33;
+
+const EBREAK: u32 = 0x0010_0073;
+const C_EBREAK: u16 = 0x9002;
+
+lazy_static! {
+    static ref STUB: UPSafeCell<Option<GdbStub>> = unsafe { UPSafeCell::new(None) };
+}
+
+//内核主动停下（panic 或 Ctrl-C）时先记下原因，再执行 ebreak 进入调试器
+static STOP_SIGNAL: AtomicUsize = AtomicUsize::new(SIGTRAP);
+
+/// 探测调试器使用的串口，找到时此后内核态的断点与 panic 都进入调试器。要在设置好内核态 trap 入口之后调用
+pub fn init() {
+    if let Some(uart) = PolledUart::probe_pci(GDB_SERIAL_PORT) {
+        *STUB.exclusive_access() = Some(GdbStub::new(uart));
+        println!("[kernel] GDB stub is listening on the PCI serial port.");
+    }
+}
+
+//调试器是否可用；调试器自身出错时它正被借用，此时同样不可用
+fn enabled() -> bool {
+    STUB.try_exclusive_access()
+        .map_or(false, |stub| stub.is_some())
+}
+
+/// 停下来进入调试器，signal 为报告给 GDB 的原因；调试器不可用时直接返回
+pub fn breakpoint(signal: usize) {
+    if enabled() {
+        STOP_SIGNAL.store(signal, Ordering::Relaxed);
+        unsafe { core::arch::asm!("ebreak") };
+    }
+}
+
+/// 时钟中断时调用：内核运行期间 GDB 只会发来 Ctrl-C（0x03），收到时停下
+pub fn poll() {
+    let interrupted = STUB
+        .try_exclusive_access()
+        .and_then(|stub| stub.as_ref().and_then(|stub| stub.uart.try_read()))
+        == Some(0x03);
+    if interrupted {
+        breakpoint(SIGINT);
+    }
+}
+
+/// 内核态的断点异常，停下来交给 GDB，返回时 frame 为继续运行的现场。
+/// 调试器不可用时返回 false，由调用者按通常的方式处理
+pub fn handle_breakpoint(frame: &mut KernelTrapFrame) -> bool {
+    match STUB.try_exclusive_access() {
+        Some(mut stub) => match stub.as_mut() {
+            Some(stub) => {
+                stub.stop(frame);
+                true
+            }
+            None => false,
+        },
+        None => false,
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Breakpoint {
+    addr: usize,
+    len: usize,
+    //被断点指令覆盖的原来的字节
+    original: [u8; 4],
+}
+
+//处理完一个请求之后的动作
+enum Action {
+    Reply,
+    //继续运行，暂时不回复，再次停下时报告原因
+    Resume,
+    //GDB 断开连接，发送回复（如果有的话）之后继续运行
+    Detach,
+}
+
+struct GdbStub {
+    uart: PolledUart,
+    //GDB 是否已经连接：连接之后停下时主动报告原因，否则等 GDB 连接后用 ? 查询
+    attached: bool,
+    packet: [u8; PACKET_SIZE],
+    reply: Reply,
+    debugger: Debugger,
+}
+
+impl GdbStub {
+    fn new(uart: PolledUart) -> Self {
+        Self {
+            uart,
+            attached: false,
+            packet: [0; PACKET_SIZE],
+            reply: Reply::new(),
+            debugger: Debugger {
+                breakpoints: [None; MAX_BREAKPOINTS],
+                steps: Vec::new(),
+                thread: None,
+                signal: SIGTRAP,
+            },
+        }
+    }
+    //停下来与 GDB 交互，直到 GDB 让内核继续运行
+    fn stop(&mut self, frame: &mut KernelTrapFrame) {
+        self.debugger.enter(frame);
+        if self.attached {
+            self.reply.clear();
+            self.debugger.stop_reply(&mut self.reply);
+            self.send_reply();
+        }
+        loop {
+            let len = self.receive_packet();
+            self.attached = true;
+            self.reply.clear();
+            match self
+                .debugger
+                .handle(&self.packet[..len], frame, &mut self.reply)
+            {
+                Action::Reply => self.send_reply(),
+                Action::Resume => return,
+                Action::Detach => {
+                    if !self.reply.is_empty() {
+                        self.send_reply();
+                    }
+                    self.attached = false;
+                    return;
+                }
+            }
+        }
+    }
+    //收到一个校验正确的数据包，确认后返回它的长度；过长或校验错误的数据包要求 GDB 重发
+    fn receive_packet(&mut self) -> usize {
+        loop {
+            while self.uart.read() != b'$' {}
+            let mut len = 0;
+            let mut sum = 0u8;
+            let mut overflow = false;
+            loop {
+                let byte = self.uart.read();
+                if byte == b'#' {
+                    break;
+                }
+                sum = sum.wrapping_add(byte);
+                match self.packet.get_mut(len) {
+                    Some(slot) => *slot = byte,
+                    None => overflow = true,
+                }
+                len += 1;
+            }
+            let checksum = [self.uart.read(), self.uart.read()];
+            if !overflow && parse_hex(&checksum) == Some(sum as usize) {
+                self.uart.write(b'+');
+                return len;
+            }
+            self.uart.write(b'-');
+        }
+    }
+    //发送回复，直到 GDB 确认收到
+    fn send_reply(&self) {
+        let data = self.reply.as_bytes();
+        let sum = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        loop {
+            self.uart.write(b'$');
+            data.iter().for_each(|&byte| self.uart.write(byte));
+            self.uart.write(b'#');
+            self.uart.write(HEX[(sum >> 4) as usize]);
+            self.uart.write(HEX[(sum & 0xf) as usize]);
+            match self.uart.read() {
+                b'-' => continue,
+                _ => return,
+            }
+        }
+    }
+}
+
+//停下期间的调试状态
+struct Debugger {
+    //GDB 用 Z0 插入的断点
+    breakpoints: [Option<Breakpoint>; MAX_BREAKPOINTS],
+    //单步执行插入的临时断点，停下时撤掉
+    steps: Vec<Breakpoint>,
+    //Hg 选择的线程，g/G/p/P 访问它的寄存器；None 为停下的线程
+    thread: Option<usize>,
+    //这次停下的原因
+    signal: usize,
+}
+
+impl Debugger {
+    //停下时撤掉单步执行的断点，确定停下的原因
+    fn enter(&mut self, frame: &mut KernelTrapFrame) {
+        let pc = frame.sepc;
+        let stepped = self.steps.iter().any(|step| step.addr == pc);
+        for step in self.steps.drain(..).rev() {
+            write_memory(step.addr, &step.original[..step.len]);
+        }
+        let hit = self.breakpoints.iter().flatten().any(|bp| bp.addr == pc);
+        self.signal = if stepped || hit {
+            SIGTRAP
+        } else {
+            //编译进内核的 ebreak：继续运行时从下一条指令开始
+            frame.sepc += match read_u16(pc) {
+                Some(insn) if insn & 0b11 != 0b11 => 2,
+                _ => 4,
+            };
+            STOP_SIGNAL.swap(SIGTRAP, Ordering::Relaxed)
+        };
+        self.thread = None;
+    }
+    fn stop_reply(&self, reply: &mut Reply) {
+        let _ = write!(
+            reply,
+            "T{:02x}thread:{:x};",
+            self.signal,
+            current_thread_id()
+        );
+    }
+    //处理一个请求，回复写入 reply。不支持的请求回复空数据包
+    fn handle(&mut self, packet: &[u8], frame: &mut KernelTrapFrame, reply: &mut Reply) -> Action {
+        let (&command, args) = match packet.split_first() {
+            Some(split) => split,
+            None => return Action::Reply,
+        };
+        let result = match command {
+            b'?' => {
+                self.stop_reply(reply);
+                Ok(())
+            }
+            b'g' => self.read_registers(frame, reply),
+            b'G' => self.write_registers(args, frame).map(|_| ok(reply)),
+            b'p' => self.read_register(args, frame, reply),
+            b'P' => self.write_register(args, frame).map(|_| ok(reply)),
+            b'm' => read_memory_hex(args, reply),
+            b'M' => write_memory_hex(args).map(|_| ok(reply)),
+            b'c' => return self.resume(args, frame, false, reply),
+            b's' => return self.resume(args, frame, true, reply),
+            b'Z' | b'z' => self.breakpoint(command == b'Z', args, reply),
+            b'H' => self.select_thread(args).map(|_| ok(reply)),
+            b'T' => match parse_thread_id(args).filter(|&id| thread_exists(id)) {
+                Some(_) => {
+                    ok(reply);
+                    Ok(())
+                }
+                None => Err(()),
+            },
+            b'q' => {
+                query(args, reply);
+                return Action::Reply;
+            }
+            b'D' => {
+                ok(reply);
+                return Action::Detach;
+            }
+            //内核不能被 GDB 终止，断开连接继续运行，GDB 不等待回复
+            b'k' => return Action::Detach,
+            _ => return Action::Reply,
+        };
+        if result.is_err() {
+            reply.clear();
+            let _ = reply.write_str("E01");
+        }
+        Action::Reply
+    }
+    //Hg/Hc 选择线程：0 表示任意线程，-1 表示所有线程，都按停下的线程处理
+    fn select_thread(&mut self, args: &[u8]) -> Result<(), ()> {
+        let (&op, id) = args.split_first().ok_or(())?;
+        let id = match id {
+            b"0" | b"-1" => None,
+            id => Some(parse_thread_id(id).ok_or(())?),
+        };
+        if op == b'g' {
+            self.thread = id.filter(|&id| id != current_thread_id());
+        }
+        Ok(())
+    }
+    //Hg 选择的线程的寄存器，不知道的寄存器为 None
+    fn registers(&self, frame: &KernelTrapFrame) -> [Option<usize>; REGS] {
+        let mut regs = [None; REGS];
+        match self.thread {
+            None => {
+                for (reg, &value) in regs.iter_mut().zip(frame.x.iter()) {
+                    *reg = Some(value);
+                }
+                regs[PC] = Some(frame.sepc);
+            }
+            Some(id) => {
+                let task_cx = find_thread(id)
+                    .and_then(|task| task.try_inner_exclusive_access().map(|inner| inner.task_cx));
+                if let Some(task_cx) = task_cx {
+                    regs[0] = Some(0);
+                    for (reg, value) in task_cx.saved_regs() {
+                        regs[reg] = Some(value);
+                    }
+                    regs[PC] = regs[1];
+                }
+            }
+        }
+        regs
+    }
+    fn read_registers(&self, frame: &KernelTrapFrame, reply: &mut Reply) -> Result<(), ()> {
+        for reg in self.registers(frame) {
+            reply.push_register(reg);
+        }
+        Ok(())
+    }
+    fn read_register(
+        &self,
+        args: &[u8],
+        frame: &KernelTrapFrame,
+        reply: &mut Reply,
+    ) -> Result<(), ()> {
+        let reg = parse_hex(args).ok_or(())?;
+        reply.push_register(self.registers(frame).get(reg).copied().flatten());
+        Ok(())
+    }
+    //只能修改停下的线程的寄存器
+    fn write_registers(&self, args: &[u8], frame: &mut KernelTrapFrame) -> Result<(), ()> {
+        if self.thread.is_some() {
+            return Err(());
+        }
+        for (reg, value) in args.chunks(16).enumerate().take(REGS) {
+            //GDB 用 x 表示不知道的值，这些寄存器保持不变
+            if let Some(value) = parse_register(value) {
+                set_register(frame, reg, value);
+            }
+        }
+        Ok(())
+    }
+    fn write_register(&self, args: &[u8], frame: &mut KernelTrapFrame) -> Result<(), ()> {
+        let mut parts = args.splitn(2, |&byte| byte == b'=');
+        let reg = parse_hex(parts.next().ok_or(())?).ok_or(())?;
+        let value = parse_register(parts.next().ok_or(())?).ok_or(())?;
+        if self.thread.is_some() || reg >= REGS {
+            return Err(());
+        }
+        set_register(frame, reg, value);
+        Ok(())
+    }
+    //c/s [addr]：从 addr（省略时为停下的位置）继续运行，单步执行时先插入临时断点
+    fn resume(
+        &mut self,
+        args: &[u8],
+        frame: &mut KernelTrapFrame,
+        step: bool,
+        reply: &mut Reply,
+    ) -> Action {
+        if !args.is_empty() {
+            match parse_hex(args) {
+                Some(addr) => frame.sepc = addr,
+                None => {
+                    let _ = reply.write_str("E01");
+                    return Action::Reply;
+                }
+            }
+        }
+        if step && self.insert_steps(frame).is_err() {
+            let _ = reply.write_str("E14");
+            return Action::Reply;
+        }
+        Action::Resume
+    }
+    fn insert_steps(&mut self, frame: &KernelTrapFrame) -> Result<(), ()> {
+        let pc = frame.sepc;
+        let low = read_u16(pc).ok_or(())? as u32;
+        let insn = match low & 0b11 {
+            0b11 => low | (read_u16(pc + 2).ok_or(())? as u32) << 16,
+            _ => low,
+        };
+        for target in next_pcs(insn, pc, &frame.x) {
+            if target == pc || self.steps.iter().any(|step| step.addr == target) {
+                continue;
+            }
+            match insert_breakpoint(target, 2) {
+                Some(step) => self.steps.push(step),
+                None => {
+                    for step in self.steps.drain(..).rev() {
+                        write_memory(step.addr, &step.original[..step.len]);
+                    }
+                    return Err(());
+                }
+            }
+        }
+        Ok(())
+    }
+    //Z0/z0,addr,kind：插入/撤掉软件断点，kind 为断点指令的长度。其他类型的断点不支持
+    fn breakpoint(&mut self, insert: bool, args: &[u8], reply: &mut Reply) -> Result<(), ()> {
+        let mut parts = args.split(|&byte| byte == b',');
+        if parts.next() != Some(b"0") {
+            return Ok(());
+        }
+        let addr = parse_hex(parts.next().ok_or(())?).ok_or(())?;
+        let len = match parse_hex(parts.next().ok_or(())?) {
+            Some(2) => 2,
+            Some(4) => 4,
+            _ => return Err(()),
+        };
+        let slot = self
+            .breakpoints
+            .iter()
+            .position(|bp| bp.map_or(false, |bp| bp.addr == addr));
+        match (insert, slot) {
+            (true, Some(_)) => {}
+            (true, None) => {
+                let free = self
+                    .breakpoints
+                    .iter_mut()
+                    .find(|bp| bp.is_none())
+                    .ok_or(())?;
+                *free = Some(insert_breakpoint(addr, len).ok_or(())?);
+            }
+            (false, Some(slot)) => {
+                let bp = self.breakpoints[slot].take().unwrap();
+                write_memory(bp.addr, &bp.original[..bp.len]);
+            }
+            (false, None) => {}
+        }
+        ok(reply);
+        Ok(())
+    }
+}
+
+fn ok(reply: &mut Reply) {
+    let _ = reply.write_str("OK");
+}
+
+//处理 q 开头的查询
+fn query(args: &[u8], reply: &mut Reply) {
+    let _ = match args {
+        b"Supported" => write!(reply, "PacketSize={:x}", PACKET_SIZE),
+        _ if args.starts_with(b"Supported:") => write!(reply, "PacketSize={:x}", PACKET_SIZE),
+        b"Attached" => reply.write_str("1"),
+        b"C" => write!(reply, "QC{:x}", current_thread_id()),
+        b"fThreadInfo" => {
+            let _ = reply.write_str("m");
+            for (i, (id, _)) in threads().iter().enumerate() {
+                let _ = write!(reply, "{}{:x}", if i == 0 { "" } else { "," }, id);
+            }
+            Ok(())
+        }
+        b"sThreadInfo" => reply.write_str("l"),
+        _ if args.starts_with(b"ThreadExtraInfo,") => {
+            let info = parse_thread_id(&args[b"ThreadExtraInfo,".len()..])
+                .and_then(|id| threads().into_iter().find(|(tid, _)| *tid == id))
+                .map(|(_, task)| describe(task.as_ref()))
+                .unwrap_or_default();
+            info.bytes().for_each(|byte| reply.push_hex(byte));
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+}
+
+//线程的说明，显示在 GDB 的 info threads 中
+fn describe(task: Option<&Arc<TaskControlBlock>>) -> alloc::string::String {
+    let task = match task {
+        Some(task) => task,
+        None => return "kernel".into(),
+    };
+    let status = task.try_inner_exclusive_access().map(|inner| {
+        let status = match inner.task_status {
+            TaskStatus::UnInit => "uninit",
+            TaskStatus::Ready => "ready",
+            TaskStatus::Running => "running",
+            TaskStatus::Blocked => "blocked",
+            TaskStatus::Zombie => "zombie",
+        };
+        format!("{} {}", inner.name, status)
+    });
+    format!(
+        "pid {} tid {} {}",
+        task.getpid(),
+        task.tid,
+        status.as_deref().unwrap_or("busy")
+    )
+}
+
+fn thread_id(task: &TaskControlBlock) -> usize {
+    (task.getpid() + 1) << 16 | task.tid
+}
+
+fn current_thread_id() -> usize {
+    try_current_task().map_or(KERNEL_THREAD, |task| thread_id(&task))
+}
+
+//所有线程：(线程号, 任务)，停下的执行流不属于任何任务时它是 KERNEL_THREAD
+fn threads() -> Vec<(usize, Option<Arc<TaskControlBlock>>)> {
+    let mut threads = Vec::new();
+    match try_current_task() {
+        Some(task) => threads.push((thread_id(&task), Some(task))),
+        None => threads.push((KERNEL_THREAD, None)),
+    }
+    for process in try_all_tasks().unwrap_or_default() {
+        let inner = match process.process.try_inner_exclusive_access() {
+            Some(inner) => inner,
+            None => continue,
+        };
+        for task in inner.tasks.iter().flatten() {
+            let id = thread_id(task);
+            if threads.iter().all(|(tid, _)| *tid != id) {
+                threads.push((id, Some(task.clone())));
+            }
+        }
+    }
+    threads
+}
+
+fn find_thread(id: usize) -> Option<Arc<TaskControlBlock>> {
+    threads().into_iter().find(|(tid, _)| *tid == id)?.1
+}
+
+fn thread_exists(id: usize) -> bool {
+    threads().iter().any(|(tid, _)| *tid == id)
+}
+
+fn parse_thread_id(args: &[u8]) -> Option<usize> {
+    parse_hex(args).filter(|&id| id != 0)
+}
+
+fn set_register(frame: &mut KernelTrapFrame, reg: usize, value: usize) {
+    match reg {
+        0 => {}
+        PC => frame.sepc = value,
+        reg => frame.x[reg] = value,
+    }
+}
+
+//寄存器的值在 RSP 中按小端序的字节依次编码
+fn parse_register(hex: &[u8]) -> Option<usize> {
+    if hex.len() != 16 {
+        return None;
+    }
+    hex.chunks(2)
+        .rev()
+        .try_fold(0, |value, byte| Some(value << 8 | parse_hex(byte)?))
+}
+
+//m addr,len：读出内存，只能读出开头一部分时回复这一部分
+fn read_memory_hex(args: &[u8], reply: &mut Reply) -> Result<(), ()> {
+    let (addr, len) = parse_addr_len(args)?;
+    let len = len.min(PACKET_SIZE / 2);
+    for i in 0..len {
+        match kernel_pte(addr.wrapping_add(i)) {
+            Some(_) => reply.push_hex(unsafe { ((addr + i) as *const u8).read_volatile() }),
+            None if i == 0 => return Err(()),
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+//M addr,len:data：写入内存
+fn write_memory_hex(args: &[u8]) -> Result<(), ()> {
+    let mut parts = args.splitn(2, |&byte| byte == b':');
+    let (addr, len) = parse_addr_len(parts.next().ok_or(())?)?;
+    let data = parts.next().ok_or(())?;
+    if data.len() != len * 2 {
+        return Err(());
+    }
+    let mut bytes = [0u8; PACKET_SIZE / 2];
+    for (byte, hex) in bytes.iter_mut().zip(data.chunks(2)) {
+        *byte = parse_hex(hex).ok_or(())? as u8;
+    }
+    match write_memory(addr, &bytes[..len]) {
+        true => Ok(()),
+        false => Err(()),
+    }
+}
+
+fn parse_addr_len(args: &[u8]) -> Result<(usize, usize), ()> {
+    let mut parts = args.splitn(2, |&byte| byte == b',');
+    let addr = parse_hex(parts.next().ok_or(())?).ok_or(())?;
+    let len = parse_hex(parts.next().ok_or(())?).ok_or(())?;
+    Ok((addr, len))
+}
+
+//在 addr 处写入长度为 len 的断点指令，返回记录了原来字节的断点
+fn insert_breakpoint(addr: usize, len: usize) -> Option<Breakpoint> {
+    let mut bp = Breakpoint {
+        addr,
+        len,
+        original: [0; 4],
+    };
+    for (i, byte) in bp.original[..len].iter_mut().enumerate() {
+        kernel_pte(addr + i)?;
+        *byte = unsafe { ((addr + i) as *const u8).read_volatile() };
+    }
+    let insn = match len {
+        2 => (C_EBREAK as u32).to_le_bytes(),
+        _ => EBREAK.to_le_bytes(),
+    };
+    match write_memory(addr, &insn[..len]) {
+        true => Some(bp),
+        false => None,
+    }
+}
+
+fn read_u16(addr: usize) -> Option<u16> {
+    kernel_pte(addr)?;
+    kernel_pte(addr + 1)?;
+    Some(
+        unsafe { (addr as *const u8).read_volatile() } as u16
+            | (unsafe { ((addr + 1) as *const u8).read_volatile() } as u16) << 8,
+    )
+}
+
+//按当前的页表检查内核能否读取 addr，返回它所在页面的页表项
+fn kernel_pte(addr: usize) -> Option<PageTableEntry> {
+    //SV39 的虚拟地址第 39 位以上必须与第 38 位相同
+    if ((addr << 25) as isize >> 25) as usize != addr {
+        return None;
+    }
+    let satp = riscv::register::satp::read().bits();
+    let pte = PageTable::from_token(satp).translate(VirtAddr::from(addr).floor())?;
+    match pte.is_valid() && pte.readable() {
+        true => Some(pte),
+        false => None,
+    }
+}
+
+//写入内存：没有写权限的恒等映射页面（内核代码段）按物理地址写入。有任何一个字节不能写入时返回 false，
+//此前的字节已经写入
+fn write_memory(addr: usize, data: &[u8]) -> bool {
+    for (i, &byte) in data.iter().enumerate() {
+        let va = addr.wrapping_add(i);
+        match kernel_pte(va) {
+            Some(pte) if pte.writable() => unsafe { (va as *mut u8).write_volatile(byte) },
+            Some(pte) if pte.ppn().0 == VirtAddr::from(va).floor().0 => unsafe {
+                write_physical(va, byte)
+            },
+            _ => return false,
+        }
+    }
+    //写入的可能是指令
+    unsafe { core::arch::asm!("fence.i") };
+    true
+}
+
+//关闭分页写入物理地址 pa 处的一个字节。关闭分页期间只能访问恒等映射的地址，
+//所以整个过程在一段汇编中完成，不访问栈
+unsafe fn write_physical(pa: usize, byte: u8) {
+    core::arch::asm!(
+        "csrrw {satp}, satp, zero",
+        "sfence.vma",
+        "sb {byte}, 0({pa})",
+        "csrw satp, {satp}",
+        "sfence.vma",
+        satp = out(reg) _,
+        pa = in(reg) pa,
+        byte = in(reg) byte,
+    );
+}
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+fn parse_hex(hex: &[u8]) -> Option<usize> {
+    if hex.is_empty() || hex.len() > 16 {
+        return None;
+    }
+    hex.iter().try_fold(0, |value, &byte| {
+        Some(value << 4 | (byte as char).to_digit(16)? as usize)
+    })
+}
+
+//正在组装的回复
+struct Reply {
+    buf: [u8; PACKET_SIZE],
+    len: usize,
+}
+
+impl Reply {
+    fn new() -> Self {
+        Self {
+            buf: [0; PACKET_SIZE],
+            len: 0,
+        }
+    }
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+    //放不下的部分被丢弃
+    fn push(&mut self, byte: u8) {
+        if self.len < PACKET_SIZE {
+            self.buf[self.len] = byte;
+            self.len += 1;
+        }
+    }
+    fn push_hex(&mut self, byte: u8) {
+        self.push(HEX[(byte >> 4) as usize]);
+        self.push(HEX[(byte & 0xf) as usize]);
+    }
+    //不知道的寄存器用 x 表示
+    fn push_register(&mut self, value: Option<usize>) {
+        match value {
+            Some(value) => value
+                .to_le_bytes()
+                .iter()
+                .for_each(|&byte| self.push_hex(byte)),
+            None => (0..16).for_each(|_| self.push(b'x')),
+        }
+    }
+}
+
+impl Write for Reply {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.bytes().for_each(|byte| self.push(byte));
+        Ok(())
+    }
+}
@@ -1,3 +1,5 @@
+use crate::backtrace;
+use crate::gdbstub;
 use crate::sbi::shutdown;
 use core::panic::PanicInfo;
 
@@ -13,5 +15,11 @@ fn panic(info: &PanicInfo) -> ! {
     } else {
         println!("[kernel] Panicked: {}", info.message().unwrap());
     }
+    //输出现场信息时再次 panic 则直接关机
+    if backtrace::enter_panic() {
+        backtrace::dump();
+    }
+    //连接着内核调试器时停下来，GDB 让内核继续运行之后再关机
+    gdbstub::breakpoint(gdbstub::SIGABRT);
     shutdown()
 }
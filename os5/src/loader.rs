@@ -1,66 +1,84 @@
+use crate::config::DEFAULT_PATH;
+use crate::errno::Errno;
+use crate::fs::{initramfs_ls, read_file, ROOT_INODE};
+use alloc::format;
+use alloc::string::String;
 use alloc::vec::Vec;
-use lazy_static::*;
 
-pub fn get_num_app() -> usize {
-    extern "C" {
-        fn _num_app();
-    }
-    unsafe { (_num_app as usize as *const usize).read_volatile() }
+/// 加载应用失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// 文件开头不是 ELF 魔数
+    BadMagic,
+    /// 不是 64 位 RISC-V 可执行文件
+    UnsupportedArch,
+    /// ELF 头或某个段超出了文件末尾
+    Truncated,
+    /// 某个段同时可写与可执行，违反 W^X 策略
+    WriteExec,
+    /// 段的文件大小超过内存大小、段之间互相重叠，或者段越过了用户栈与用户堆所在的区域
+    BadLayout,
+    /// 物理内存不足，无法建立地址空间或内核栈
+    NoMemory,
+    /// 命令行参数与环境变量超过 ARG_MAX，新程序的用户栈放不下
+    ArgsTooBig,
 }
 
-pub fn get_app_data(app_id: usize) -> &'static [u8] {
-    extern "C" {
-        fn _num_app();
-    }
-    let num_app_ptr = _num_app as usize as *const usize;
-    let num_app = get_num_app();
-    let app_start = unsafe { core::slice::from_raw_parts(num_app_ptr.add(1), num_app + 1) };
-    assert!(app_id < num_app);
-    unsafe {
-        core::slice::from_raw_parts(
-            app_start[app_id] as *const u8,
-            app_start[app_id + 1] - app_start[app_id],
-        )
+impl LoadError {
+    /// 作为系统调用返回值的负错误码
+    pub fn errno(self) -> isize {
+        match self {
+            LoadError::BadMagic
+            | LoadError::UnsupportedArch
+            | LoadError::Truncated
+            | LoadError::WriteExec
+            | LoadError::BadLayout => Errno::ENOEXEC.neg(),
+            LoadError::NoMemory => Errno::ENOMEM.neg(),
+            LoadError::ArgsTooBig => Errno::E2BIG.neg(),
+        }
     }
 }
 
-//用一个全局可见的只读向量 APP_NAMES 来按照顺序将所有应用的名字保存在内存中
-lazy_static! {
-    static ref APP_NAMES: Vec<&'static str> = {
-        let num_app = get_num_app();
-        extern "C" {
-            fn _app_names();
-        }
-        let mut start = _app_names as usize as *const u8;
-        let mut v = Vec::new();
-        unsafe {
-            for _ in 0..num_app {
-                let mut end = start;
-                while end.read_volatile() != b'\0' {
-                    end = end.add(1);
-                }
-                let slice = core::slice::from_raw_parts(start, end as usize - start as usize);
-                let str = core::str::from_utf8(slice).unwrap();
-                v.push(str);
-                start = end.add(1);
-            }
+//功能：按照路径或者程序名找到可执行文件，读出它的 ELF 数据。
+//含有 / 的路径经由挂载表从根目录开始解析；不含 / 的程序名依次在环境变量 envs 中 PATH 列出的目录里查找，
+//没有 PATH 时在 DEFAULT_PATH 中查找，与 execvp 一样跳过不存在的目录。
+//找不到时返回 Errno::ENOENT，找到的都是目录、命名管道等不能执行的文件时返回 Errno::EACCES
+pub fn read_program(path: &str, envs: &[String]) -> Result<Vec<u8>, Errno> {
+    if path.contains('/') {
+        return read_file(path);
+    }
+    if path.is_empty() {
+        return Err(Errno::ENOENT);
+    }
+    let search = envs
+        .iter()
+        .find_map(|env| env.strip_prefix("PATH="))
+        .unwrap_or(DEFAULT_PATH);
+    let mut result = Err(Errno::ENOENT);
+    for dir in search.split(':').filter(|dir| !dir.is_empty()) {
+        match read_file(&format!("{}/{}", dir, path)) {
+            Err(Errno::ENOENT) | Err(Errno::ENOTDIR) => {}
+            Err(Errno::EACCES) => result = Err(Errno::EACCES),
+            other => return other,
         }
-        v
-    };
+    }
+    result
 }
 
-//功能：按照应用的名字来查找获得应用的 ELF 数据
-pub fn get_app_data_by_name(name: &str) -> Option<&'static [u8]> {
-    let num_app = get_num_app();
-    (0..num_app)
-        .find(|&i| APP_NAMES[i] == name)
-        .map(get_app_data)
+/// 进程名是可执行文件路径的最后一级
+pub fn program_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
 }
 
-//功能：在内核初始化时被调用，它可以打印出所有可用应用的名字
+//功能：在内核初始化时被调用，它可以打印出根目录上的文件系统中所有应用的名字
 pub fn list_apps() {
     println!("/**** APPS ****");
-    for app in APP_NAMES.iter() {
+    let apps = if cfg!(feature = "initramfs") {
+        initramfs_ls()
+    } else {
+        ROOT_INODE.ls()
+    };
+    for app in apps {
         println!("{}", app);
     }
     println!("**************/");
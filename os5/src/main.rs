@@ -12,11 +12,17 @@ extern crate alloc;
 
 #[macro_use]
 mod console;
+mod backtrace;
 mod config;
+mod drivers;
+mod errno;
+mod fs;
+mod gdbstub;
 mod lang_items;
 mod loader;
 mod logging;
 mod mm;
+mod random;
 mod sbi;
 mod sync;
 mod syscall;
@@ -25,7 +31,6 @@ mod timer;
 mod trap;
 
 core::arch::global_asm!(include_str!("entry.asm"));
-core::arch::global_asm!(include_str!("link_app.S"));
 
 fn clear_bss() {
     extern "C" {
@@ -45,9 +50,14 @@ pub fn rust_main() -> ! {
     println!("[kernel] Hello, world!");
     mm::init();
     mm::remap_test();
+    drivers::init();
+    timer::init();
+    random::init();
+    fs::init();
     task::add_initproc();
     info!("after initproc!");
     trap::init();
+    gdbstub::init();
     trap::enable_timer_interrupt();
     timer::set_next_trigger();
     loader::list_apps();
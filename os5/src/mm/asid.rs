@@ -0,0 +1,155 @@
+//! 地址空间标识符（ASID）
+//!
+//! 每个用户地址空间在第一次切换过去时分配一个 ASID，随页表一起写入 satp。TLB 中的表项按 ASID 区分，
+//! 切换地址空间时不再需要刷新 TLB，修改页表之后也只需刷新这个地址空间中被修改的页面。
+//!
+//! ASID 按代分配：一代中的 ASID 依次分配、不回收，用完时进入新的一代，刷新整个 TLB 后从头分配。
+//! 上一代分配的 ASID 随之作废，持有它们的地址空间下一次被切换过去时重新分配。
+//! 0 号 ASID 留给内核地址空间；硬件不支持 ASID 时用户地址空间也使用 0 号，由 trap.S 在切换时刷新 TLB。
+
+use super::VirtPageNum;
+use crate::config::ASID_LIMIT;
+use crate::sync::UPSafeCell;
+use lazy_static::*;
+use riscv::register::satp;
+
+/// satp 中 ASID 字段的位置和宽度
+const ASID_SHIFT: usize = 44;
+const ASID_MASK: usize = 0xffff;
+/// 内核地址空间的 ASID 所属的代，它的 ASID 永远有效
+const KERNEL_GENERATION: usize = usize::MAX;
+/// 一次要刷新的页面超过这个数目时，改为刷新地址空间在 TLB 中的全部表项
+const FLUSH_PAGES_LIMIT: usize = 64;
+
+/// 地址空间持有的 ASID，只在分配它的那一代中有效
+#[derive(Clone, Copy)]
+pub struct Asid {
+    generation: usize,
+    value: usize,
+}
+
+impl Asid {
+    /// 尚未分配 ASID 的用户地址空间
+    pub fn unassigned() -> Self {
+        Self {
+            generation: 0,
+            value: 0,
+        }
+    }
+    /// 内核地址空间固定使用 0 号 ASID
+    pub fn kernel() -> Self {
+        Self {
+            generation: KERNEL_GENERATION,
+            value: 0,
+        }
+    }
+}
+
+struct AsidAllocator {
+    //当前的代，从 1 开始，0 表示尚未分配
+    generation: usize,
+    //这一代中下一个要分配的 ASID
+    next: usize,
+    //可以使用的 ASID 个数，包括内核的 0 号
+    limit: usize,
+}
+
+impl AsidAllocator {
+    fn is_valid(&self, asid: &Asid) -> bool {
+        asid.generation == self.generation || asid.generation == KERNEL_GENERATION
+    }
+    //返回 asid 在当前这一代中的值，它已经作废时重新分配
+    fn refresh(&mut self, asid: &mut Asid) -> usize {
+        if self.is_valid(asid) {
+            return asid.value;
+        }
+        if self.limit <= 1 {
+            *asid = Asid {
+                generation: self.generation,
+                value: 0,
+            };
+            return 0;
+        }
+        if self.next == self.limit {
+            //这一代的 ASID 已经用完，TLB 中可能还有它们的表项，全部刷新之后才能重新分配
+            self.generation += 1;
+            self.next = 1;
+            unsafe {
+                riscv::asm::sfence_vma_all();
+            }
+        }
+        *asid = Asid {
+            generation: self.generation,
+            value: self.next,
+        };
+        self.next += 1;
+        asid.value
+    }
+}
+
+lazy_static! {
+    static ref ASID_ALLOCATOR: UPSafeCell<AsidAllocator> = unsafe {
+        UPSafeCell::new(AsidAllocator {
+            generation: 1,
+            next: 1,
+            limit: 1,
+        })
+    };
+}
+
+/// 探测硬件实现的 ASID 位数：向 satp 的 ASID 字段写入全 1 再读回，未实现的位读出为 0。
+/// 需要在内核地址空间启用之后调用
+pub fn init() {
+    let token = satp::read().bits();
+    let implemented = unsafe {
+        satp::write(token | ASID_MASK << ASID_SHIFT);
+        let implemented = satp::read().bits() >> ASID_SHIFT & ASID_MASK;
+        satp::write(token);
+        riscv::asm::sfence_vma_all();
+        implemented
+    };
+    let limit = (implemented + 1).min(ASID_LIMIT);
+    ASID_ALLOCATOR.exclusive_access().limit = limit;
+    info!("[kernel] {} ASIDs available", limit);
+}
+
+/// 在 token 中填入 asid 当前的值，得到切换到这个地址空间时写入 satp 的值
+pub fn satp_with_asid(token: usize, asid: &mut Asid) -> usize {
+    let value = ASID_ALLOCATOR.exclusive_access().refresh(asid);
+    token | value << ASID_SHIFT
+}
+
+/// 刷新 TLB 中属于地址空间 asid、落在 [start, end) 中的页面的表项。
+/// asid 已经作废时 TLB 中不会再有它的表项，不需要刷新
+pub fn flush_range(asid: &Asid, start: VirtPageNum, end: VirtPageNum) {
+    if !ASID_ALLOCATOR.exclusive_access().is_valid(asid) {
+        return;
+    }
+    if end.0.saturating_sub(start.0) > FLUSH_PAGES_LIMIT {
+        flush_asid(asid.value);
+        return;
+    }
+    for vpn in start.0..end.0 {
+        unsafe {
+            riscv::asm::sfence_vma(asid.value, canonical_va(vpn));
+        }
+    }
+}
+
+/// 刷新 TLB 中属于地址空间 asid 的全部表项
+pub fn flush_all(asid: &Asid) {
+    if ASID_ALLOCATOR.exclusive_access().is_valid(asid) {
+        flush_asid(asid.value);
+    }
+}
+
+//页号中只有 SV39 的 39 位地址，sfence.vma 按高位与第 38 位相同的完整虚拟地址匹配
+fn canonical_va(vpn: usize) -> usize {
+    ((vpn << 37) as isize >> 25) as usize
+}
+
+fn flush_asid(asid: usize) {
+    unsafe {
+        core::arch::asm!("sfence.vma zero, {}", in(reg) asid);
+    }
+}
@@ -2,12 +2,29 @@
 //! controls all the frames in the operating system.
 
 use super::{PhysAddr, PhysPageNum};
-use crate::config::MEMORY_END;
+use crate::config::{MEMORY_END, PAGE_SIZE};
 use crate::sync::UPSafeCell;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
+use core::mem::size_of;
 use lazy_static::*;
 
+//空闲页帧中每个字的内容：开启 frame-poison 时填入毒化值，分配时检查它是否被改写过；否则为 0
+#[cfg(feature = "frame-poison")]
+const FREE_WORD: usize = 0xdead_beef_dead_beef;
+#[cfg(not(feature = "frame-poison"))]
+const FREE_WORD: usize = 0;
+
+fn frame_words(ppn: PhysPageNum) -> &'static mut [usize; PAGE_SIZE / size_of::<usize>()] {
+    ppn.get_mut()
+}
+
+//页帧放回分配器之前填满 FREE_WORD，之前的内容不会泄露给之后得到它的进程
+fn scrub(ppn: PhysPageNum) {
+    frame_words(ppn).fill(FREE_WORD);
+}
+
 /// manage a frame which has the same lifecycle as the tracker
 pub struct FrameTracker {
     pub ppn: PhysPageNum,
@@ -15,10 +32,17 @@ pub struct FrameTracker {
 
 impl FrameTracker {
     pub fn new(ppn: PhysPageNum) -> Self {
-        // page cleaning
-        let bytes_array = ppn.get_bytes_array();
-        for i in bytes_array {
-            *i = 0;
+        // 空闲页帧在释放时已经清零，毒化模式下先检查释放之后没有被写过，再清零
+        #[cfg(feature = "frame-poison")]
+        {
+            let words = frame_words(ppn);
+            if let Some(i) = words.iter().position(|&word| word != FREE_WORD) {
+                panic!(
+                    "Frame ppn={:#x} was written after being freed: word {} is {:#x}",
+                    ppn.0, i, words[i]
+                );
+            }
+            words.fill(0);
         }
         Self { ppn }
     }
@@ -32,6 +56,7 @@ impl Debug for FrameTracker {
 
 impl Drop for FrameTracker {
     fn drop(&mut self) {
+        scrub(self.ppn);
         frame_dealloc(self.ppn);
     }
 }
@@ -39,53 +64,123 @@ impl Drop for FrameTracker {
 trait FrameAllocator {
     fn new() -> Self;
     fn alloc(&mut self) -> Option<PhysPageNum>;
+    fn alloc_contiguous(&mut self, pages: usize) -> Option<PhysPageNum>;
     fn dealloc(&mut self, ppn: PhysPageNum);
 }
 
-/// an implementation for frame allocator
-pub struct StackFrameAllocator {
-    current: usize,
+//伙伴系统中最大的块包含 2^MAX_ORDER 个页帧
+const MAX_ORDER: usize = 12;
+
+/// 伙伴系统页帧分配器
+///
+/// 空闲页帧被组织成大小为 2 的幂、起始页号按大小对齐的块，free_lists[k] 中是所有大小为 2^k 的空闲块的起始页号。
+/// 分配时从能满足要求的最小的块中切分，释放时与同样大小的空闲伙伴块（起始页号只在第 k 位不同）合并。
+pub struct BuddyFrameAllocator {
+    start: usize,
     end: usize,
-    recycled: Vec<usize>,
+    free_lists: Vec<BTreeSet<usize>>,
+    free: usize,
+    //同时被占用的页帧数的最大值
+    peak: usize,
 }
 
-impl StackFrameAllocator {
+impl BuddyFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
-        self.current = l.0;
+        self.start = l.0;
         self.end = r.0;
-        info!("last {} Physical Frames.", self.end - self.current);
+        //把 [l, r) 切分成尽量大的对齐的块
+        let mut ppn = l.0;
+        while ppn < r.0 {
+            let mut order = (ppn.trailing_zeros() as usize).min(MAX_ORDER);
+            while ppn + (1 << order) > r.0 {
+                order -= 1;
+            }
+            self.free_lists[order].insert(ppn);
+            ppn += 1 << order;
+        }
+        self.free = r.0 - l.0;
+        info!("last {} Physical Frames.", self.free);
+    }
+    //还能分配的物理页帧数
+    pub fn available(&self) -> usize {
+        self.free
+    }
+    //可供分配的页帧总数
+    pub fn total(&self) -> usize {
+        self.end - self.start
+    }
+    fn update_peak(&mut self) {
+        self.peak = self.peak.max(self.total() - self.free);
+    }
+    //分配一个大小为 2^order 的块
+    fn alloc_order(&mut self, order: usize) -> Option<usize> {
+        let from = (order..=MAX_ORDER).find(|&k| !self.free_lists[k].is_empty())?;
+        let ppn = *self.free_lists[from].iter().next().unwrap();
+        self.free_lists[from].remove(&ppn);
+        //把多余的后半部分依次放回较小的空闲链表
+        for k in (order..from).rev() {
+            self.free_lists[k].insert(ppn + (1 << k));
+        }
+        self.free -= 1 << order;
+        Some(ppn)
+    }
+    //释放一个大小为 2^order 的块，并尽可能与伙伴块合并
+    fn dealloc_order(&mut self, mut ppn: usize, mut order: usize) {
+        self.free += 1 << order;
+        while order < MAX_ORDER {
+            let buddy = ppn ^ (1 << order);
+            if !self.free_lists[order].remove(&buddy) {
+                break;
+            }
+            ppn = ppn.min(buddy);
+            order += 1;
+        }
+        self.free_lists[order].insert(ppn);
+    }
+    //页帧 ppn 是否位于某个空闲块中
+    fn is_free(&self, ppn: usize) -> bool {
+        (0..=MAX_ORDER).any(|k| self.free_lists[k].contains(&(ppn & !((1 << k) - 1))))
     }
 }
-impl FrameAllocator for StackFrameAllocator {
+impl FrameAllocator for BuddyFrameAllocator {
     fn new() -> Self {
         Self {
-            current: 0,
+            start: 0,
             end: 0,
-            recycled: Vec::new(),
+            free_lists: (0..=MAX_ORDER).map(|_| BTreeSet::new()).collect(),
+            free: 0,
+            peak: 0,
         }
     }
     fn alloc(&mut self) -> Option<PhysPageNum> {
-        if let Some(ppn) = self.recycled.pop() {
-            Some(ppn.into())
-        } else if self.current == self.end {
-            None
-        } else {
-            self.current += 1;
-            Some((self.current - 1).into())
+        let ppn = self.alloc_order(0)?;
+        self.update_peak();
+        Some(ppn.into())
+    }
+    //分配能容纳 pages 个页帧的最小的块，再把用不到的尾部释放掉
+    fn alloc_contiguous(&mut self, pages: usize) -> Option<PhysPageNum> {
+        let order = pages.next_power_of_two().trailing_zeros() as usize;
+        if pages == 0 || order > MAX_ORDER {
+            return None;
         }
+        let ppn = self.alloc_order(order)?;
+        for tail in ppn + pages..ppn + (1 << order) {
+            self.dealloc_order(tail, 0);
+        }
+        self.update_peak();
+        Some(ppn.into())
     }
     fn dealloc(&mut self, ppn: PhysPageNum) {
         let ppn = ppn.0;
         // validity check
-        if ppn >= self.current || self.recycled.iter().any(|v| *v == ppn) {
+        if ppn < self.start || ppn >= self.end || self.is_free(ppn) {
             panic!("Frame ppn={:#x} has not been allocated!", ppn);
         }
-        // recycle
-        self.recycled.push(ppn);
+        self.dealloc_order(ppn, 0);
     }
 }
 
-type FrameAllocatorImpl = StackFrameAllocator;
+type FrameAllocatorImpl = BuddyFrameAllocator;
 
 lazy_static! {
     /// frame allocator instance through lazy_static!
@@ -97,10 +192,13 @@ pub fn init_frame_allocator() {
     extern "C" {
         fn ekernel();
     }
-    FRAME_ALLOCATOR.exclusive_access().init(
-        PhysAddr::from(ekernel as usize).ceil(),
-        PhysAddr::from(MEMORY_END).floor(),
-    );
+    let start = PhysAddr::from(ekernel as usize).ceil();
+    let end = PhysAddr::from(MEMORY_END).floor();
+    //启动时内存中的内容未知，先把所有空闲页帧填满 FREE_WORD
+    for ppn in start.0..end.0 {
+        scrub(ppn.into());
+    }
+    FRAME_ALLOCATOR.exclusive_access().init(start, end);
 }
 
 /// initiate the frame allocator using `ekernel` and `MEMORY_END`
@@ -111,6 +209,27 @@ pub fn frame_alloc() -> Option<FrameTracker> {
         .map(FrameTracker::new)
 }
 
+/// 分配 pages 个物理地址连续的页帧，按页号从小到大排列
+pub fn frame_alloc_contiguous(pages: usize) -> Option<Vec<FrameTracker>> {
+    let start = FRAME_ALLOCATOR.exclusive_access().alloc_contiguous(pages)?;
+    Some(
+        (start.0..start.0 + pages)
+            .map(|ppn| FrameTracker::new(ppn.into()))
+            .collect(),
+    )
+}
+
+/// 还能分配的物理页帧数
+pub fn frame_available() -> usize {
+    FRAME_ALLOCATOR.exclusive_access().available()
+}
+
+/// 页帧的使用情况 (总数, 空闲数, 同时被占用的最大数)
+pub fn frame_stats() -> (usize, usize, usize) {
+    let allocator = FRAME_ALLOCATOR.exclusive_access();
+    (allocator.total(), allocator.available(), allocator.peak)
+}
+
 /// deallocate a frame
 fn frame_dealloc(ppn: PhysPageNum) {
     FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
@@ -132,5 +251,13 @@ pub fn frame_allocator_test() {
         v.push(frame);
     }
     drop(v);
+    let available = frame_available();
+    let run = frame_alloc_contiguous(5).unwrap();
+    for (i, frame) in run.iter().enumerate() {
+        assert_eq!(frame.ppn.0, run[0].ppn.0 + i);
+    }
+    assert_eq!(frame_available(), available - 5);
+    drop(run);
+    assert_eq!(frame_available(), available);
     info!("frame_allocator_test passed!");
 }
\ No newline at end of file
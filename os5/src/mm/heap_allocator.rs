@@ -1,11 +1,40 @@
 //! The global allocator
 
+use super::slab::{arc_layout, CacheStats, SlabAllocator, MAX_CACHES};
+use super::FrameTracker;
 use crate::config::KERNEL_HEAP_SIZE;
+use crate::task::{ProcessControlBlock, TaskControlBlock};
+use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
+use core::alloc::{GlobalAlloc, Layout};
+use spin::Mutex;
+
+/// 内核的全局分配器：登记过缓存的对象由 slab 分配器负责，其余的分配交给伙伴堆
+pub struct KernelAllocator {
+    slab: Mutex<SlabAllocator>,
+    heap: LockedHeap,
+}
+
+unsafe impl GlobalAlloc for KernelAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match self.slab.lock().alloc(layout, &self.heap) {
+            Some(ptr) => ptr,
+            None => self.heap.alloc(layout),
+        }
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if !self.slab.lock().dealloc(ptr, layout) {
+            self.heap.dealloc(ptr, layout);
+        }
+    }
+}
 
 #[global_allocator]
 /// heap allocator instance
-static HEAP_ALLOCATOR: LockedHeap = LockedHeap::empty();
+static HEAP_ALLOCATOR: KernelAllocator = KernelAllocator {
+    slab: Mutex::new(SlabAllocator::new()),
+    heap: LockedHeap::empty(),
+};
 
 #[alloc_error_handler]
 /// panic when heap allocation error occurs
@@ -20,9 +49,28 @@ static mut HEAP_SPACE: [u8; KERNEL_HEAP_SIZE] = [0; KERNEL_HEAP_SIZE];
 pub fn init_heap() {
     unsafe {
         HEAP_ALLOCATOR
+            .heap
             .lock()
             .init(HEAP_SPACE.as_ptr() as usize, KERNEL_HEAP_SIZE);
     }
+    //为频繁创建和销毁的内核对象登记缓存
+    let mut slab = HEAP_ALLOCATOR.slab.lock();
+    slab.register("TaskControlBlock", arc_layout::<TaskControlBlock>());
+    slab.register("ProcessControlBlock", arc_layout::<ProcessControlBlock>());
+    slab.register("Arc<FrameTracker>", arc_layout::<FrameTracker>());
+}
+
+/// 内核堆的使用情况 (总字节数, 已分配的字节数)，slab 占用的空间计入已分配的部分
+pub fn heap_stats() -> (usize, usize) {
+    let heap = HEAP_ALLOCATOR.heap.lock();
+    (heap.stats_total_bytes(), heap.stats_alloc_actual())
+}
+
+/// 各个 slab 缓存的统计信息
+pub fn slab_stats() -> Vec<CacheStats> {
+    //持有锁时不能分配内存，先复制出来
+    let stats: [Option<CacheStats>; MAX_CACHES] = HEAP_ALLOCATOR.slab.lock().stats();
+    stats.iter().flatten().copied().collect()
 }
 
 #[allow(unused)]
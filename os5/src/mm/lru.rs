@@ -0,0 +1,90 @@
+//! 页面老化
+//!
+//! 每个物理页帧有一个 8 位的年龄，用来近似 LRU：每次检查页面时把年龄右移一位，
+//! 页表项的访问位（A）被置位时再把最高位置 1，然后清除页表项的访问位和脏位（D）。
+//! 年龄越小说明页面越久没有被访问，年龄为 0 的页面在最近 8 次检查中都没有被访问过，
+//! 换出页面时选择的就是这样的页面。检查发生在定时收集访问位时（见 [`MemorySet::age_pages`]）
+//! 和时钟指针经过页面时（见 [`MemorySet::swap_out_from`]）。
+//!
+//! [`MemorySet::age_pages`]: super::MemorySet::age_pages
+//! [`MemorySet::swap_out_from`]: super::MemorySet::swap_out_from
+
+use super::{PhysAddr, PhysPageNum};
+use crate::config::MEMORY_END;
+use crate::sync::UPSafeCell;
+use alloc::vec;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// 刚被访问过的页面的年龄
+const AGE_YOUNG: u8 = 1 << 7;
+/// 年龄的位数，时钟指针最多经过这么多次才能把一个刚被访问过的页面老化到 0
+pub const AGE_BITS: usize = 8;
+
+/// 页面置换的统计信息，各项都是启动以来的累计值
+#[derive(Clone, Copy, Default)]
+pub struct PageStats {
+    /// 定时收集访问位的次数
+    pub harvests: usize,
+    /// 检查过的页面数
+    pub scanned: usize,
+    /// 其中访问位被置位的页面数
+    pub accessed: usize,
+    /// 其中脏位被置位的页面数
+    pub dirty: usize,
+    /// 被选为牺牲页并换出的页面数
+    pub swap_outs: usize,
+    /// 从交换区换入的页面数
+    pub swap_ins: usize,
+}
+
+struct PageAges {
+    base: usize,
+    ages: Vec<u8>,
+}
+
+lazy_static! {
+    static ref PAGE_AGES: UPSafeCell<PageAges> = unsafe {
+        extern "C" {
+            fn ekernel();
+        }
+        let base = PhysAddr::from(ekernel as usize).ceil().0;
+        let end = PhysAddr::from(MEMORY_END).floor().0;
+        UPSafeCell::new(PageAges {
+            base,
+            ages: vec![0; end - base],
+        })
+    };
+    pub(super) static ref PAGE_STATS: UPSafeCell<PageStats> =
+        unsafe { UPSafeCell::new(PageStats::default()) };
+}
+
+/// 页帧 ppn 刚被映射给一个页面，视为刚被访问过
+pub fn reset_age(ppn: PhysPageNum) {
+    let mut table = PAGE_AGES.exclusive_access();
+    let base = table.base;
+    table.ages[ppn.0 - base] = AGE_YOUNG;
+}
+
+/// 检查一次映射到页帧 ppn 的页面，accessed 和 dirty 是从页表项中取出的访问位和脏位，返回新的年龄
+pub fn age_page(ppn: PhysPageNum, accessed: bool, dirty: bool) -> u8 {
+    let mut stats = PAGE_STATS.exclusive_access();
+    stats.scanned += 1;
+    stats.accessed += accessed as usize;
+    stats.dirty += dirty as usize;
+    let mut table = PAGE_AGES.exclusive_access();
+    let base = table.base;
+    let age = &mut table.ages[ppn.0 - base];
+    *age = *age >> 1 | if accessed { AGE_YOUNG } else { 0 };
+    *age
+}
+
+/// 记录一次定时收集访问位
+pub fn count_harvest() {
+    PAGE_STATS.exclusive_access().harvests += 1;
+}
+
+/// 页面置换的统计信息
+pub fn page_stats() -> PageStats {
+    *PAGE_STATS.exclusive_access()
+}
@@ -1,12 +1,21 @@
 //! Implementation of [`MapArea`] and [`MemorySet`].
 
-use super::{frame_alloc, FrameTracker};
+use super::asid::{self, Asid};
+use super::{frame_alloc, frame_alloc_contiguous, frame_available, FrameTracker};
 use super::{PTEFlags, PageTable, PageTableEntry};
 use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
+use super::lru;
+use super::swap::SwapSlot;
 use super::{StepByOne, VPNRange};
-use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT, USER_STACK_SIZE};
+use crate::config::{
+    HUGE_PAGE_PAGES, MEMORY_END, MMIO, PAGE_SIZE, SIGRETURN_TRAMPOLINE, TRAMPOLINE, TRAP_CONTEXT,
+    USER_HEAP_BASE, USER_SPACE_END, USER_STACK_RESERVE, USER_STACK_SIZE,
+};
+use crate::errno::Errno;
+use crate::loader::LoadError;
+use crate::task::SIGRETURN_CODE;
 use crate::sync::UPSafeCell;
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use lazy_static::*;
@@ -29,67 +38,557 @@ lazy_static! {
     /// a memory set instance through lazy_static! managing kernel space
     pub static ref KERNEL_SPACE: Arc<UPSafeCell<MemorySet>> =
         Arc::new(unsafe { UPSafeCell::new(MemorySet::new_kernel()) });
+    /// 所有地址空间共享的全零页帧。惰性分配的页面第一次被读时只读地映射到它，第一次被写时才分配自己的页帧
+    static ref ZERO_FRAME: FrameTracker = frame_alloc().expect("no frame for the zero page");
 }
 
 /// memory set structure, controls virtual-memory space
 pub struct MemorySet {
     page_table: PageTable,
-    areas: Vec<MapArea>,
+    //逻辑段按起始页号排列，互不重叠。空的逻辑段（如尚未扩大的用户堆）也占据其起始的一页
+    areas: BTreeMap<VirtPageNum, MapArea>,
+    asid: Asid,
+    //占有页帧的用户页面数曾经达到的最大值
+    peak_rss: usize,
 }
 
+/// 需要分配物理页帧的操作在物理内存不足时返回 Errno::ENOMEM，地址空间保持操作之前的状态
 impl MemorySet {
-    pub fn new_bare() -> Self {
-        Self {
-            page_table: PageTable::new(),
-            areas: Vec::new(),
-        }
+    pub fn new_bare() -> Result<Self, Errno> {
+        Ok(Self {
+            page_table: PageTable::new()?,
+            areas: BTreeMap::new(),
+            asid: Asid::unassigned(),
+            peak_rss: 0,
+        })
     }
     pub fn token(&self) -> usize {
         self.page_table.token()
     }
+    /// 切换到这个地址空间时写入 satp 的值，其中包括地址空间的 ASID，必要时为它重新分配 ASID
+    pub fn satp(&mut self) -> usize {
+        asid::satp_with_asid(self.page_table.token(), &mut self.asid)
+    }
+    //修改 [start, end) 中页面的页表项之后，刷新 TLB 中这个地址空间的相应表项
+    fn flush_tlb(&self, start: VirtPageNum, end: VirtPageNum) {
+        asid::flush_range(&self.asid, start, end);
+    }
+    /// 所有逻辑段的总大小（字节），用于 RLIMIT_AS
+    pub fn total_size(&self) -> usize {
+        self.areas
+            .values()
+            .map(|area| area.vpn_range.get_end().0 - area.vpn_range.get_start().0)
+            .sum::<usize>()
+            * PAGE_SIZE
+    }
+    /// 用户页面的使用情况 (占有页帧的页面数, 被换出的页面数)
+    pub fn page_counts(&self) -> (usize, usize) {
+        self.areas
+            .values()
+            .filter(|area| area.map_perm.contains(MapPermission::U))
+            .fold((0, 0), |(mapped, swapped), area| {
+                (mapped + area.data_frames.len(), swapped + area.swapped.len())
+            })
+    }
+    /// 常驻集大小，即当前占有页帧的用户页面数，以及它曾经达到的最大值
+    pub fn rss(&self) -> (usize, usize) {
+        let rss = self.page_counts().0;
+        (rss, self.peak_rss.max(rss))
+    }
+    //为用户页面分配或映射了页帧之后记录常驻集大小的最大值
+    fn update_peak_rss(&mut self) {
+        self.peak_rss = self.rss().1;
+    }
+    /// 只映射到共享零页、尚未被写过的用户页面数，它们不占用自己的页帧
+    pub fn zero_pages(&self) -> usize {
+        self.areas
+            .values()
+            .filter(|area| area.map_perm.contains(MapPermission::U))
+            .map(|area| area.zero_pages.len())
+            .sum()
+    }
+    /// 用户页面中用大页映射的 2MiB 区域数
+    pub fn huge_pages(&self) -> usize {
+        self.areas
+            .values()
+            .filter(|area| area.map_perm.contains(MapPermission::U))
+            .map(|area| area.huge_pages(&self.page_table))
+            .sum()
+    }
+    //包含页面 vpn 的逻辑段只可能是起始页号不超过 vpn 的最后一个逻辑段
+    fn area_containing(&self, vpn: VirtPageNum) -> Option<&MapArea> {
+        self.areas
+            .range(..=vpn)
+            .next_back()
+            .map(|(_, area)| area)
+            .filter(|area| area.contains(vpn))
+    }
+    //结束于 end_vpn 的逻辑段的起始页号
+    fn area_ending_at(&self, end_vpn: VirtPageNum) -> Option<VirtPageNum> {
+        self.areas
+            .range(..end_vpn)
+            .next_back()
+            .filter(|(_, area)| area.vpn_range.get_end() == end_vpn)
+            .map(|(start, _)| *start)
+    }
+    fn insert_area(&mut self, area: MapArea) {
+        let start = area.vpn_range.get_start();
+        assert!(
+            self.areas.insert(start, area).is_none(),
+            "map area at {:?} overlaps an existing one",
+            start
+        );
+    }
+    //at 落在某个逻辑段的中间时，在 at 处将它一分为二
+    fn split_at(&mut self, at: VirtPageNum) {
+        let tail = match self.areas.range_mut(..at).next_back() {
+            Some((_, area)) if at < area.vpn_range.get_end() => area.split_off(at),
+            _ => return,
+        };
+        self.areas.insert(at, tail);
+    }
+    //起始于 start 的逻辑段能与紧挨在它之前的逻辑段合并时，把它并入前一个逻辑段
+    fn merge_at(&mut self, start: VirtPageNum) {
+        let next = match self.areas.get(&start) {
+            Some(next) => next,
+            None => return,
+        };
+        let prev = match self.areas.range(..start).next_back() {
+            Some((prev, area)) if area.can_merge(next) => *prev,
+            _ => return,
+        };
+        let area = self.areas.remove(&start).unwrap();
+        self.areas.get_mut(&prev).unwrap().append(area);
+    }
+    //合并起始页号落在 [start, end] 中的逻辑段与它们前面相邻的逻辑段
+    fn coalesce(&mut self, start: VirtPageNum, end: VirtPageNum) {
+        let starts: Vec<VirtPageNum> = self.areas.range(start..=end).map(|(vpn, _)| *vpn).collect();
+        for vpn in starts {
+            self.merge_at(vpn);
+        }
+    }
+    //[start_vpn, end_vpn) 中的页面是否都属于用户逻辑段
+    fn covered_by_user_areas(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        let mut vpn = start_vpn;
+        while vpn < end_vpn {
+            match self.area_containing(vpn) {
+                Some(area) if area.map_perm.contains(MapPermission::U) => {
+                    vpn = area.vpn_range.get_end();
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
     /// Assume that no conflicts.
     pub fn insert_framed_area(
         &mut self,
         start_va: VirtAddr,
         end_va: VirtAddr,
         permission: MapPermission,
-    ) {
+    ) -> Result<(), Errno> {
         self.push(
             MapArea::new(start_va, end_va, MapType::Framed, permission),
             None,
+        )
+    }
+    /// 插入一个惰性分配的逻辑段，页帧在第一次访问时才分配。调用者保证不与已有的逻辑段重叠。
+    /// 它与前后相邻的权限相同的惰性分配逻辑段合并为一个
+    pub fn insert_lazy_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) -> Result<(), Errno> {
+        self.push(
+            MapArea::new(start_va, end_va, MapType::Lazy, permission),
+            None,
+        )?;
+        self.coalesce(start_va.floor(), end_va.ceil());
+        Ok(())
+    }
+    /// 插入一个在 fork 时与子进程共享页帧的逻辑段，页帧立即分配。调用者保证不与已有的逻辑段重叠。
+    pub fn insert_shared_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) -> Result<(), Errno> {
+        self.push(
+            MapArea::new(start_va, end_va, MapType::Shared, permission),
+            None,
+        )
+    }
+    /// 从 start_vpn 开始依次映射 frames 中已有的页帧，插入一个共享的逻辑段。调用者保证不与已有的逻辑段重叠。
+    pub fn insert_frames_area(
+        &mut self,
+        start_vpn: VirtPageNum,
+        frames: &[Arc<FrameTracker>],
+        permission: MapPermission,
+    ) -> Result<(), Errno> {
+        let end_vpn = VirtPageNum(start_vpn.0 + frames.len());
+        let mut area = MapArea::new(
+            start_vpn.into(),
+            end_vpn.into(),
+            MapType::Shared,
+            permission,
         );
+        area.map_frames(
+            &mut self.page_table,
+            VPNRange::new(start_vpn, end_vpn)
+                .into_iter()
+                .zip(frames.iter().cloned()),
+        )?;
+        self.insert_area(area);
+        self.flush_tlb(start_vpn, end_vpn);
+        self.update_peak_rss();
+        Ok(())
     }
-    pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
-        if let Some((idx, area)) = self
+    /// 移除起始于 start_vpn 的共享逻辑段，找不到时返回 false
+    pub fn remove_shared_area(&mut self, start_vpn: VirtPageNum) -> bool {
+        let found = self
+            .areas
+            .get(&start_vpn)
+            .map_or(false, |area| area.map_type == MapType::Shared);
+        if found {
+            self.remove_area_with_start_vpn(start_vpn);
+        }
+        found
+    }
+    /// [start_vpn, end_vpn) 是否与已有的逻辑段重叠。空的区间与空的逻辑段一样按占据其起始的一页计算，
+    /// 因此不会有两个逻辑段起始于同一页
+    pub fn overlaps(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        let end_vpn = end_vpn.max(VirtPageNum(start_vpn.0 + 1));
+        //起始于 end_vpn 之前的逻辑段中，最后一个结束得最晚
+        self.areas
+            .range(..end_vpn)
+            .next_back()
+            .map_or(false, |(_, area)| start_vpn < area.occupied_end())
+    }
+    /// 从 from 开始向上找到第一段长度为 pages 页、不与任何逻辑段重叠的空闲区间（first-fit），
+    /// 返回其起始页号；空的逻辑段也占据其起始的一页，以免新区间挡住它之后的增长
+    pub fn find_free_area(&self, from: VirtPageNum, pages: usize) -> Option<VirtPageNum> {
+        //起始于 from 之前的逻辑段中只有最后一个可能越过 from
+        let first = self
+            .areas
+            .range(..from)
+            .next_back()
+            .map_or(from, |(start, _)| *start);
+        let mut candidate = from.0;
+        for (_, area) in self.areas.range(first..) {
+            if area.vpn_range.get_start().0 >= candidate + pages {
+                break;
+            }
+            candidate = candidate.max(area.occupied_end().0);
+        }
+        let limit: VirtPageNum = VirtAddr::from(USER_SPACE_END).floor();
+        if candidate + pages <= limit.0 {
+            Some(VirtPageNum(candidate))
+        } else {
+            None
+        }
+    }
+    /// 处理缺页：vpn 落在某个逻辑段中、逻辑段的权限包含 access 且页面已被换出时从交换区读回；
+    /// 页面属于惰性分配的逻辑段且尚未分配页帧时，读访问只把它映射到共享零页，写访问（以及内核代替用户
+    /// 以空权限进行的访问）为它分配一个清零的页帧；写只映射到零页的页面时同样为它分配页帧。
+    /// 这几种情况下建立映射后返回 true，否则是一次真正的非法访问，或者物理内存不足，返回 false。
+    pub fn handle_page_fault(&mut self, vpn: VirtPageNum, access: MapPermission) -> bool {
+        let area = match self.areas.range_mut(..=vpn).next_back() {
+            Some((_, area)) if area.contains(vpn) => area,
+            _ => return false,
+        };
+        if !area.map_perm.contains(access) || area.data_frames.contains_key(&vpn) {
+            return false;
+        }
+        let read_only = !access.is_empty() && !access.contains(MapPermission::W);
+        let result = if area.swapped.contains_key(&vpn) {
+            area.swap_in(&mut self.page_table, vpn)
+        } else if area.zero_pages.contains(&vpn) {
+            if read_only {
+                return false;
+            }
+            area.unshare_zero(&mut self.page_table, vpn)
+        } else if area.map_type == MapType::Lazy && read_only {
+            area.map_zero(&mut self.page_table, vpn)
+        } else if area.map_type == MapType::Lazy {
+            area.map_one(&mut self.page_table, vpn)
+        } else {
+            return false;
+        };
+        self.flush_tlb(vpn, VirtPageNum(vpn.0 + 1));
+        self.update_peak_rss();
+        result.is_ok()
+    }
+    /// 调试器代替用户访问页面 vpn：尚未分配、只映射到零页或已被换出的页面先调入一个私有的页帧，
+    /// 返回页面所在的页帧。页面不在任何逻辑段中或用户态不能访问时返回 None。
+    /// 调用者可以写入只读的页面（例如在代码中插入断点），这只影响本地址空间
+    pub fn debug_page(&mut self, vpn: VirtPageNum) -> Option<PhysPageNum> {
+        let zero = match self.areas.range(..=vpn).next_back() {
+            Some((_, area)) if area.contains(vpn) => area.zero_pages.contains(&vpn),
+            _ => return None,
+        };
+        let user_page = |pte: PageTableEntry| pte.is_valid() && pte.flags().contains(PTEFlags::U);
+        match self.translate(vpn).filter(|pte| user_page(*pte)) {
+            Some(pte) if !zero => Some(pte.ppn()),
+            _ if self.handle_page_fault(vpn, MapPermission::empty()) => self
+                .translate(vpn)
+                .filter(|pte| user_page(*pte))
+                .map(|pte| pte.ppn()),
+            _ => None,
+        }
+    }
+    /// 收集所有可以换出的用户页面的访问位和脏位，更新页面的年龄
+    pub fn age_pages(&mut self) {
+        for area in self.areas.values().filter(|area| area.swappable()) {
+            for &vpn in area.data_frames.keys() {
+                area.age(&mut self.page_table, vpn);
+            }
+        }
+        //访问位和脏位被清除，刷新 TLB 中缓存的页表项
+        asid::flush_all(&self.asid);
+    }
+    /// 时钟算法：从 from 开始按地址顺序检查可以换出的用户页面并更新它们的年龄，
+    /// 换出年龄老化到 0 的页面，直到换出了 count 个页面。
+    /// 返回时钟指针应该停在的页号；检查到地址空间的末尾或交换区已满时返回 None。
+    pub fn swap_out_from(&mut self, from: VirtPageNum, count: usize) -> Option<VirtPageNum> {
+        //逻辑段按地址排列，得到的 (逻辑段的起始页号, 页号) 已经按页号排序
+        let candidates: Vec<(VirtPageNum, VirtPageNum)> = self
             .areas
-            .iter_mut()
-            .enumerate()
-            .find(|(_, area)| area.vpn_range.get_start() == start_vpn)
+            .iter()
+            .filter(|(_, area)| area.swappable())
+            .flat_map(|(start, area)| {
+                area.data_frames
+                    .range(from..)
+                    .map(move |(vpn, _)| (*start, *vpn))
+            })
+            .collect();
+        let mut swapped = 0;
+        let mut hand = None;
+        for (start, vpn) in candidates {
+            if self.areas[&start].age(&mut self.page_table, vpn) != 0 {
+                continue;
+            }
+            if !self
+                .areas
+                .get_mut(&start)
+                .unwrap()
+                .swap_out(&mut self.page_table, vpn)
+            {
+                break;
+            }
+            swapped += 1;
+            if swapped == count {
+                hand = Some(VirtPageNum(vpn.0 + 1));
+                break;
+            }
+        }
+        //访问位被清除、页面被换出，刷新 TLB 中缓存的页表项
+        asid::flush_all(&self.asid);
+        hand
+    }
+    /// 将起始于 start 的逻辑段缩小到 new_end 为止，找不到该逻辑段时返回 false
+    pub fn shrink_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        if let Some(area) = self.areas.get_mut(&start.floor()) {
+            let end = area.vpn_range.get_end();
+            area.shrink_to(&mut self.page_table, new_end.ceil());
+            self.flush_tlb(new_end.ceil(), end);
+            true
+        } else {
+            false
+        }
+    }
+    /// 将起始于 start 的逻辑段扩大到 new_end 为止并为新增的页面分配页帧，找不到该逻辑段或物理内存不足时返回 false
+    pub fn append_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        if let Some(area) = self.areas.get_mut(&start.floor()) {
+            let end = area.vpn_range.get_end();
+            let result = area.append_to(&mut self.page_table, new_end.ceil());
+            self.flush_tlb(end, new_end.ceil());
+            self.update_peak_rss();
+            result.is_ok()
+        } else {
+            false
+        }
+    }
+    /// 将结束于 end_vpn 的逻辑段向下扩大到从 new_start 开始，并为新增的页面分配页帧。
+    /// 找不到该逻辑段、new_start 不在它的起始位置之下、新增的部分与其他逻辑段重叠或物理内存不足时返回 false。
+    pub fn grow_down(&mut self, end_vpn: VirtPageNum, new_start: VirtPageNum) -> bool {
+        let start = match self.area_ending_at(end_vpn) {
+            Some(start) if self.areas[&start].map_type == MapType::Framed => start,
+            _ => return false,
+        };
+        if new_start >= start
+            || self.overlaps(new_start, start)
+            || start.0 - new_start.0 > frame_available()
         {
+            return false;
+        }
+        //逻辑段的起始页号改变，需要重新插入
+        let mut area = self.areas.remove(&start).unwrap();
+        let result = area.prepend_to(&mut self.page_table, new_start);
+        self.insert_area(area);
+        self.flush_tlb(new_start, start);
+        self.update_peak_rss();
+        result.is_ok()
+    }
+    /// 将 [start_vpn, end_vpn) 中页面的权限改为 permission，区间的两端落在逻辑段中间时拆分该逻辑段，
+    /// 修改之后与相邻逻辑段的类型和权限都相同时再合并。
+    /// 区间中有不属于任何用户逻辑段的页面时不做任何修改，返回 false。
+    pub fn mprotect(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+        permission: MapPermission,
+    ) -> bool {
+        if !self.covered_by_user_areas(start_vpn, end_vpn) {
+            return false;
+        }
+        //拆分之后区间恰好由若干完整的逻辑段组成
+        self.split_at(start_vpn);
+        self.split_at(end_vpn);
+        let flags = PTEFlags::from_bits(permission.bits).unwrap();
+        for (_, area) in self.areas.range_mut(start_vpn..end_vpn) {
+            area.map_perm = permission;
+            //惰性分配尚未访问的页面在分配时使用新的权限
+            for vpn in area.data_frames.keys() {
+                self.page_table.set_flags(*vpn, flags);
+            }
+            //映射到零页的页面始终只读，写时再分配页帧
+            for vpn in area.zero_pages.iter() {
+                self.page_table.set_flags(*vpn, flags - PTEFlags::W);
+            }
+        }
+        //页表项被改写，刷新 TLB 中可能缓存的旧权限
+        self.flush_tlb(start_vpn, end_vpn);
+        self.coalesce(start_vpn, end_vpn);
+        true
+    }
+    /// 解除 [start_vpn, end_vpn) 的映射，区间的两端落在逻辑段中间时先拆分该逻辑段，区间可以跨越多个逻辑段。
+    /// 区间中有不属于任何用户逻辑段的页面时不做任何修改，返回 false。
+    pub fn remove_range(&mut self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        if !self.covered_by_user_areas(start_vpn, end_vpn) {
+            return false;
+        }
+        self.split_at(start_vpn);
+        self.split_at(end_vpn);
+        let starts: Vec<VirtPageNum> = self
+            .areas
+            .range(start_vpn..end_vpn)
+            .map(|(vpn, _)| *vpn)
+            .collect();
+        for start in starts {
+            let mut area = self.areas.remove(&start).unwrap();
             area.unmap(&mut self.page_table);
-            self.areas.remove(idx);
         }
+        self.flush_tlb(start_vpn, end_vpn);
+        true
     }
-    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
-        map_area.map(&mut self.page_table);
+    /// 将落在一个惰性分配逻辑段（即 mmap 得到的逻辑段）中的 [start_vpn, end_vpn) 调整为 new_pages 页，
+    /// 返回调整后的起始页号。区间的两端落在逻辑段中间时先拆分出这一部分。
+    /// 缩小时回收多余的页面；扩大时若紧随其后的页面空闲则原地扩大，
+    /// 否则在 may_move 时将已分配的页帧重新映射到新的位置（不复制数据）后再扩大。
+    /// 找不到这样的逻辑段、无法扩大或物理内存不足以建立新的页表时不做任何修改，返回 None。
+    pub fn remap(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+        new_pages: usize,
+        may_move: bool,
+    ) -> Option<VirtPageNum> {
+        let in_lazy_area = self.area_containing(start_vpn).map_or(false, |area| {
+            area.map_type == MapType::Lazy && end_vpn <= area.vpn_range.get_end()
+        });
+        if !in_lazy_area {
+            return None;
+        }
+        self.split_at(start_vpn);
+        self.split_at(end_vpn);
+        let result = self.remap_area(start_vpn, end_vpn, new_pages, may_move);
+        //失败时把拆分出来的部分合并回去；成功时调整后的逻辑段可能与新的邻居相邻
+        match result {
+            Some(new_start) => self.coalesce(new_start, VirtPageNum(new_start.0 + new_pages)),
+            None => self.coalesce(start_vpn, end_vpn),
+        }
+        result
+    }
+    //调整恰好为 [start_vpn, end_vpn) 的惰性分配逻辑段
+    fn remap_area(
+        &mut self,
+        start_vpn: VirtPageNum,
+        end_vpn: VirtPageNum,
+        new_pages: usize,
+        may_move: bool,
+    ) -> Option<VirtPageNum> {
+        let new_end = VirtPageNum(start_vpn.0 + new_pages);
+        if new_end <= end_vpn {
+            let area = self.areas.get_mut(&start_vpn).unwrap();
+            area.shrink_to(&mut self.page_table, new_end);
+            self.flush_tlb(new_end, end_vpn);
+            return Some(start_vpn);
+        }
+        //惰性分配的逻辑段扩大时不分配页帧，不会失败
+        if !self.overlaps(end_vpn, new_end) {
+            self.areas
+                .get_mut(&start_vpn)
+                .unwrap()
+                .append_to(&mut self.page_table, new_end)
+                .ok()?;
+            return Some(start_vpn);
+        }
+        if !may_move {
+            return None;
+        }
+        let new_start = self.find_free_area(end_vpn, new_pages)?;
+        //逻辑段的起始页号改变，需要重新插入；移动失败时它保持原样
+        let mut area = self.areas.remove(&start_vpn).unwrap();
+        let result = area.move_to(&mut self.page_table, new_start).and_then(|_| {
+            area.append_to(&mut self.page_table, VirtPageNum(new_start.0 + new_pages))
+        });
+        self.insert_area(area);
+        result.ok()?;
+        //已分配的页帧从原来的位置移走
+        self.flush_tlb(start_vpn, end_vpn);
+        self.flush_tlb(
+            new_start,
+            VirtPageNum(new_start.0 + end_vpn.0 - start_vpn.0),
+        );
+        Some(new_start)
+    }
+    pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
+        if let Some(mut area) = self.areas.remove(&start_vpn) {
+            area.unmap(&mut self.page_table);
+            self.flush_tlb(area.vpn_range.get_start(), area.vpn_range.get_end());
+        }
+    }
+    pub fn remove_area_with_end_vpn(&mut self, end_vpn: VirtPageNum) {
+        if let Some(start_vpn) = self.area_ending_at(end_vpn) {
+            self.remove_area_with_start_vpn(start_vpn);
+        }
+    }
+    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) -> Result<(), Errno> {
+        map_area.map(&mut self.page_table)?;
         if let Some(data) = data {
             map_area.copy_data(&mut self.page_table, data);
         }
-        self.areas.push(map_area);
+        self.flush_tlb(map_area.vpn_range.get_start(), map_area.vpn_range.get_end());
+        self.insert_area(map_area);
+        self.update_peak_rss();
+        Ok(())
     }
     /// Mention that trampoline is not collected by areas.
-    fn map_trampoline(&mut self) {
+    fn map_trampoline(&mut self) -> Result<(), Errno> {
         self.page_table.map(
             VirtAddr::from(TRAMPOLINE).into(),
             PhysAddr::from(strampoline as usize).into(),
             PTEFlags::R | PTEFlags::X,
-        );
+        )
     }
     /// Without kernel stacks.
+    //内核地址空间在启动时建立，此时物理内存一定足够
     pub fn new_kernel() -> Self {
-        let mut memory_set = Self::new_bare();
+        let mut memory_set = Self::new_bare().unwrap();
+        memory_set.asid = Asid::kernel();
         // map trampoline
-        memory_set.map_trampoline();
+        memory_set.map_trampoline().unwrap();
         // map kernel sections
         info!(".text [{:#x}, {:#x})", stext as usize, etext as usize);
         info!(".rodata [{:#x}, {:#x})", srodata as usize, erodata as usize);
@@ -99,155 +598,287 @@ impl MemorySet {
             sbss_with_stack as usize, ebss as usize
         );
         info!("mapping .text section");
-        memory_set.push(
-            MapArea::new(
-                (stext as usize).into(),
-                (etext as usize).into(),
-                MapType::Identical,
-                MapPermission::R | MapPermission::X,
-            ),
-            None,
-        );
+        memory_set
+            .push(
+                MapArea::new(
+                    (stext as usize).into(),
+                    (etext as usize).into(),
+                    MapType::Identical,
+                    MapPermission::R | MapPermission::X,
+                ),
+                None,
+            )
+            .unwrap();
         info!("mapping .rodata section");
-        memory_set.push(
-            MapArea::new(
-                (srodata as usize).into(),
-                (erodata as usize).into(),
-                MapType::Identical,
-                MapPermission::R,
-            ),
-            None,
-        );
+        memory_set
+            .push(
+                MapArea::new(
+                    (srodata as usize).into(),
+                    (erodata as usize).into(),
+                    MapType::Identical,
+                    MapPermission::R,
+                ),
+                None,
+            )
+            .unwrap();
         info!("mapping .data section");
-        memory_set.push(
-            MapArea::new(
-                (sdata as usize).into(),
-                (edata as usize).into(),
-                MapType::Identical,
-                MapPermission::R | MapPermission::W,
-            ),
-            None,
-        );
+        memory_set
+            .push(
+                MapArea::new(
+                    (sdata as usize).into(),
+                    (edata as usize).into(),
+                    MapType::Identical,
+                    MapPermission::R | MapPermission::W,
+                ),
+                None,
+            )
+            .unwrap();
         info!("mapping .bss section");
-        memory_set.push(
-            MapArea::new(
-                (sbss_with_stack as usize).into(),
-                (ebss as usize).into(),
-                MapType::Identical,
-                MapPermission::R | MapPermission::W,
-            ),
-            None,
-        );
+        memory_set
+            .push(
+                MapArea::new(
+                    (sbss_with_stack as usize).into(),
+                    (ebss as usize).into(),
+                    MapType::Identical,
+                    MapPermission::R | MapPermission::W,
+                ),
+                None,
+            )
+            .unwrap();
         info!("mapping physical memory");
-        memory_set.push(
-            MapArea::new(
-                (ekernel as usize).into(),
-                MEMORY_END.into(),
-                MapType::Identical,
-                MapPermission::R | MapPermission::W,
-            ),
-            None,
-        );
+        memory_set
+            .push(
+                MapArea::new(
+                    (ekernel as usize).into(),
+                    MEMORY_END.into(),
+                    MapType::Identical,
+                    MapPermission::R | MapPermission::W,
+                ),
+                None,
+            )
+            .unwrap();
+        info!("mapping memory-mapped registers");
+        for &(start, len) in MMIO {
+            memory_set
+                .push(
+                    MapArea::new(
+                        start.into(),
+                        (start + len).into(),
+                        MapType::Identical,
+                        MapPermission::R | MapPermission::W,
+                    ),
+                    None,
+                )
+                .unwrap();
+        }
         memory_set
     }
     /// Include sections in elf and trampoline and TrapContext and user stack,
     /// also returns user_sp and entry point.
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
-        let mut memory_set = Self::new_bare();
-        // map trampoline
-        memory_set.map_trampoline();
-        // map program headers of elf, with U flag
-        let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
+    /// Fails without allocating anything if the elf is not a loadable RISC-V executable,
+    /// and with LoadError::NoMemory if physical memory runs out while mapping it.
+    pub fn from_elf(elf_data: &[u8]) -> Result<(Self, usize, usize), LoadError> {
+        //在分配任何物理页帧之前检查完所有可能出错的地方
+        if elf_data.len() < 4 || elf_data[..4] != [0x7f, 0x45, 0x4c, 0x46] {
+            return Err(LoadError::BadMagic);
+        }
+        let elf = xmas_elf::ElfFile::new(elf_data).map_err(|_| LoadError::Truncated)?;
         let elf_header = elf.header;
-        let magic = elf_header.pt1.magic;
-        assert_eq!(magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf!");
+        if elf_header.pt1.class() != xmas_elf::header::Class::SixtyFour
+            || elf_header.pt2.machine().as_machine() != xmas_elf::header::Machine::RISC_V
+        {
+            return Err(LoadError::UnsupportedArch);
+        }
         let ph_count = elf_header.pt2.ph_count();
-        let mut max_end_vpn = VirtPageNum(0);
+        let mut load_headers = Vec::new();
+        //段都在用户堆之下，其后还要放得下保护页与用户栈的预留区域，
+        //因此也不会碰到用户地址空间的上界以及 SIGRETURN_TRAMPOLINE、TRAP_CONTEXT 与 TRAMPOLINE
+        let segment_limit = (USER_HEAP_BASE - PAGE_SIZE - USER_STACK_RESERVE) as u64;
         for i in 0..ph_count {
-            let ph = elf.program_header(i).unwrap();
-            if ph.get_type().unwrap() == xmas_elf::program::Type::Load {
-                let start_va: VirtAddr = (ph.virtual_addr() as usize).into();
-                let end_va: VirtAddr = ((ph.virtual_addr() + ph.mem_size()) as usize).into();
-                let mut map_perm = MapPermission::U;
-                let ph_flags = ph.flags();
-                if ph_flags.is_read() {
-                    map_perm |= MapPermission::R;
+            let ph = elf.program_header(i).map_err(|_| LoadError::Truncated)?;
+            if ph.get_type() == Ok(xmas_elf::program::Type::Load) {
+                let file_end = ph.offset().checked_add(ph.file_size());
+                if file_end.map_or(true, |end| end > elf_data.len() as u64) {
+                    return Err(LoadError::Truncated);
                 }
-                if ph_flags.is_write() {
-                    map_perm |= MapPermission::W;
+                //任何页面都不能同时可写与可执行
+                if ph.flags().is_write() && ph.flags().is_execute() {
+                    let start = ph.virtual_addr() as usize;
+                    wx_audit("ELF segment", start, start + ph.mem_size() as usize);
+                    return Err(LoadError::WriteExec);
                 }
-                if ph_flags.is_execute() {
-                    map_perm |= MapPermission::X;
+                //文件中的数据要放得进段里
+                let end = ph.virtual_addr().checked_add(ph.mem_size());
+                if ph.file_size() > ph.mem_size() || end.map_or(true, |end| end > segment_limit) {
+                    return Err(LoadError::BadLayout);
                 }
-                let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
-                max_end_vpn = map_area.vpn_range.get_end();
-                memory_set.push(
+                //大小为 0 的段不占用任何页面
+                if ph.mem_size() > 0 {
+                    load_headers.push(ph);
+                }
+            }
+        }
+        //段按页面映射，不同的段不能占用同一个页面
+        let mut page_ranges: Vec<(VirtPageNum, VirtPageNum)> = load_headers
+            .iter()
+            .map(|ph| {
+                let start = ph.virtual_addr() as usize;
+                let end = start + ph.mem_size() as usize;
+                (VirtAddr::from(start).floor(), VirtAddr::from(end).ceil())
+            })
+            .collect();
+        page_ranges.sort_unstable();
+        if page_ranges.windows(2).any(|pair| pair[0].1 > pair[1].0) {
+            return Err(LoadError::BadLayout);
+        }
+        //物理内存不足时已经建立的映射随 memory_set 一起释放
+        let no_memory = |_: Errno| LoadError::NoMemory;
+        let mut memory_set = Self::new_bare().map_err(no_memory)?;
+        // map trampoline
+        memory_set.map_trampoline().map_err(no_memory)?;
+        // map program headers of elf, with U flag
+        let mut max_end_vpn = VirtPageNum(0);
+        for ph in load_headers {
+            let start_va: VirtAddr = (ph.virtual_addr() as usize).into();
+            let end_va: VirtAddr = ((ph.virtual_addr() + ph.mem_size()) as usize).into();
+            let mut map_perm = MapPermission::U;
+            let ph_flags = ph.flags();
+            if ph_flags.is_read() {
+                map_perm |= MapPermission::R;
+            }
+            if ph_flags.is_write() {
+                map_perm |= MapPermission::W;
+            }
+            if ph_flags.is_execute() {
+                map_perm |= MapPermission::X;
+            }
+            let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
+            max_end_vpn = max_end_vpn.max(map_area.vpn_range.get_end());
+            memory_set
+                .push(
                     map_area,
                     Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
-                );
-            }
+                )
+                .map_err(no_memory)?;
         }
         // map user stack with U flags
         let max_end_va: VirtAddr = max_end_vpn.into();
         let mut user_stack_bottom: usize = max_end_va.into();
         // guard page
         user_stack_bottom += PAGE_SIZE;
-        let user_stack_top = user_stack_bottom + USER_STACK_SIZE;
-        memory_set.push(
-            MapArea::new(
-                user_stack_bottom.into(),
-                user_stack_top.into(),
-                MapType::Framed,
-                MapPermission::R | MapPermission::W | MapPermission::U,
-            ),
-            None,
-        );
+        // 用户栈在预留区域的顶部，初始只映射 USER_STACK_SIZE，缺页时再向下增长
+        let user_stack_top = user_stack_bottom + USER_STACK_RESERVE;
+        memory_set
+            .push(
+                MapArea::new(
+                    (user_stack_top - USER_STACK_SIZE).into(),
+                    user_stack_top.into(),
+                    MapType::Framed,
+                    MapPermission::R | MapPermission::W | MapPermission::U,
+                ),
+                None,
+            )
+            .map_err(no_memory)?;
+        // 用户堆，初始为空，由 sbrk 调整大小
+        memory_set
+            .push(
+                MapArea::new(
+                    USER_HEAP_BASE.into(),
+                    USER_HEAP_BASE.into(),
+                    MapType::Framed,
+                    MapPermission::R | MapPermission::W | MapPermission::U,
+                ),
+                None,
+            )
+            .map_err(no_memory)?;
+        // map sigreturn trampoline with U flag
+        memory_set
+            .push(
+                MapArea::new(
+                    SIGRETURN_TRAMPOLINE.into(),
+                    TRAP_CONTEXT.into(),
+                    MapType::Framed,
+                    MapPermission::R | MapPermission::X | MapPermission::U,
+                ),
+                Some(&SIGRETURN_CODE),
+            )
+            .map_err(no_memory)?;
         // map TrapContext
-        memory_set.push(
-            MapArea::new(
-                TRAP_CONTEXT.into(),
-                TRAMPOLINE.into(),
-                MapType::Framed,
-                MapPermission::R | MapPermission::W,
-            ),
-            None,
-        );
-        (
+        memory_set
+            .push(
+                MapArea::new(
+                    TRAP_CONTEXT.into(),
+                    TRAMPOLINE.into(),
+                    MapType::Framed,
+                    MapPermission::R | MapPermission::W,
+                ),
+                None,
+            )
+            .map_err(no_memory)?;
+        Ok((
             memory_set,
             user_stack_top,
             elf.header.pt2.entry_point() as usize,
-        )
+        ))
     }
     /// Copy an identical user_space
     //可以复制一个完全相同的地址空间。
-    pub fn from_existed_user(user_space: &MemorySet) -> MemorySet {
+    //物理内存不足时返回 Errno::ENOMEM，已经复制的部分随 memory_set 一起释放
+    pub fn from_existed_user(user_space: &MemorySet) -> Result<MemorySet, Errno> {
         //通过 new_bare 新创建一个空的地址空间
-        let mut memory_set = Self::new_bare();
+        let mut memory_set = Self::new_bare()?;
         // map trampoline
         //通过 map_trampoline 为这个地址空间映射上跳板页面，
         //这是因为我们解析 ELF 创建地址空间的时候，
-        //并没有将跳板页作为一个单独的逻辑段插入到地址空间的逻辑段向量 areas 中，所以这里需要单独映射上。
-        memory_set.map_trampoline();
+        //并没有将跳板页作为一个单独的逻辑段插入到地址空间的逻辑段集合 areas 中，所以这里需要单独映射上。
+        memory_set.map_trampoline()?;
         // copy data sections/trap_context/user_stack
         //复制数据节/陷入上下文/用户栈
         //剩下的逻辑段都包含在 areas 中。
         //我们遍历原地址空间中的所有逻辑段，将复制之后的逻辑段插入新的地址空间， 在插入的时候就已经实际分配了物理页帧了。   
-        for area in user_space.areas.iter() {
+        for (&start, area) in user_space.areas.iter() {
+            //共享的逻辑段直接映射到同一批页帧上，父子进程此后看到彼此的修改
+            if area.map_type == MapType::Shared {
+                let mut new_area = MapArea::from_another(area);
+                new_area.map_shared(&mut memory_set.page_table, area)?;
+                memory_set.insert_area(new_area);
+                continue;
+            }
             let new_area = MapArea::from_another(area);
-            memory_set.push(new_area, None);
+            memory_set.push(new_area, None)?;
+            //惰性分配的逻辑段只为已经访问过的页面分配页帧，其余页面在子进程中同样等到访问时再分配，
+            //只被读过的页面在子进程中同样映射到零页
+            if area.map_type == MapType::Lazy {
+                let new_area = memory_set.areas.get_mut(&start).unwrap();
+                for vpn in area.data_frames.keys() {
+                    new_area.map_one(&mut memory_set.page_table, *vpn)?;
+                }
+                for vpn in area.zero_pages.iter() {
+                    new_area.map_zero(&mut memory_set.page_table, *vpn)?;
+                }
+            }
             // copy data from another space
-            //接着我们遍历逻辑段中的每个虚拟页面，对应完成数据复制， 
+            //接着我们遍历逻辑段中每个已经分配了页帧的虚拟页面，对应完成数据复制， 
             //这只需要找出两个地址空间中的虚拟页面各被映射到哪个物理页帧，
             //就可转化为将数据从物理内存中的一个位置复制到另一个位置，使用 copy_from_slice 即可轻松实现。
-            for vpn in area.vpn_range {
+            for &vpn in area.data_frames.keys() {
                 let src_ppn = user_space.translate(vpn).unwrap().ppn();
                 let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
                 dst_ppn
                     .get_bytes_array()
                     .copy_from_slice(src_ppn.get_bytes_array());
             }
+            //原地址空间中已被换出的页面直接从交换区读入子进程的页帧
+            let new_area = memory_set.areas.get_mut(&start).unwrap();
+            for (vpn, slot) in area.swapped.iter() {
+                if area.map_type == MapType::Lazy {
+                    new_area.map_one(&mut memory_set.page_table, *vpn)?;
+                }
+                slot.read(new_area.data_frames[vpn].ppn);
+            }
         }
-        memory_set
+        memory_set.update_peak_rss();
+        Ok(memory_set)
     }
     pub fn activate(&self) {
         let satp = self.page_table.token();
@@ -259,6 +890,21 @@ impl MemorySet {
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.page_table.translate(vpn)
     }
+    /// 用户态可以访问的所有已映射页面，按虚拟页号从小到大排列。只映射到零页的页面也包括在内
+    pub fn user_pages(&self) -> Vec<(VirtPageNum, PhysPageNum)> {
+        let mut pages: Vec<_> = self
+            .areas
+            .values()
+            .filter(|area| area.map_perm.contains(MapPermission::U))
+            .flat_map(|area| {
+                let frames = area.data_frames.iter().map(|(vpn, frame)| (*vpn, frame.ppn));
+                let zeros = area.zero_pages.iter().map(|vpn| (*vpn, ZERO_FRAME.ppn));
+                frames.chain(zeros)
+            })
+            .collect();
+        pages.sort_by_key(|(vpn, _)| vpn.0);
+        pages
+    }
     pub fn recycle_data_pages(&mut self) {
         //*self = Self::new_bare();
         self.areas.clear();
@@ -268,7 +914,12 @@ impl MemorySet {
 /// map area structure, controls a contiguous piece of virtual memory
 pub struct MapArea {
     vpn_range: VPNRange,
-    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    //共享的页帧可能同时属于多个地址空间中的逻辑段
+    data_frames: BTreeMap<VirtPageNum, Arc<FrameTracker>>,
+    //已被换出到交换区的页面，与 data_frames 中的页面互不重叠
+    swapped: BTreeMap<VirtPageNum, SwapSlot>,
+    //只读地映射到共享零页的惰性分配页面，与 data_frames 和 swapped 中的页面互不重叠
+    zero_pages: BTreeSet<VirtPageNum>,
     map_type: MapType,
     map_perm: MapPermission,
 }
@@ -285,6 +936,8 @@ impl MapArea {
         Self {
             vpn_range: VPNRange::new(start_vpn, end_vpn),
             data_frames: BTreeMap::new(),
+            swapped: BTreeMap::new(),
+            zero_pages: BTreeSet::new(),
             map_type,
             map_perm,
         }
@@ -295,45 +948,367 @@ impl MapArea {
         Self {
             vpn_range: VPNRange::new(another.vpn_range.get_start(), another.vpn_range.get_end()),
             data_frames: BTreeMap::new(),
+            swapped: BTreeMap::new(),
+            zero_pages: BTreeSet::new(),
             map_type: another.map_type,
             map_perm: another.map_perm,
         }
     }
-    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
-        let ppn: PhysPageNum;
+    //物理内存不足时返回 Errno::ENOMEM，不留下任何映射
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Result<(), Errno> {
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
         match self.map_type {
-            MapType::Identical => {
-                ppn = PhysPageNum(vpn.0);
+            MapType::Identical => page_table.map(vpn, PhysPageNum(vpn.0), pte_flags),
+            MapType::Framed | MapType::Lazy | MapType::Shared => {
+                let frame = frame_alloc().ok_or(Errno::ENOMEM)?;
+                page_table.map(vpn, frame.ppn, pte_flags)?;
+                lru::reset_age(frame.ppn);
+                self.data_frames.insert(vpn, Arc::new(frame));
+                Ok(())
             }
-            MapType::Framed => {
-                let frame = frame_alloc().unwrap();
-                ppn = frame.ppn;
-                self.data_frames.insert(vpn, frame);
+        }
+    }
+    //把惰性分配的页面 vpn 只读地映射到共享零页。物理内存不足以建立页表时返回 Errno::ENOMEM
+    fn map_zero(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Result<(), Errno> {
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap() - PTEFlags::W;
+        page_table.map(vpn, ZERO_FRAME.ppn, pte_flags)?;
+        self.zero_pages.insert(vpn);
+        Ok(())
+    }
+    //第一次写只映射到零页的页面 vpn：为它分配一个清零的页帧，不需要复制数据。
+    //物理内存不足时页面仍映射到零页
+    fn unshare_zero(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Result<(), Errno> {
+        let frame = frame_alloc().ok_or(Errno::ENOMEM)?;
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        //页表已经存在，重新映射不会失败
+        page_table.unmap(vpn);
+        page_table.map(vpn, frame.ppn, pte_flags).unwrap();
+        lru::reset_age(frame.ppn);
+        self.zero_pages.remove(&vpn);
+        self.data_frames.insert(vpn, Arc::new(frame));
+        Ok(())
+    }
+    //能用一个大页映射 [vpn, vpn + HUGE_PAGE_PAGES) 时建立大页映射并返回 true。
+    //只有恒等映射和共享的逻辑段使用大页：它们的页面不会被换出，也不会被移动
+    fn map_huge(
+        &mut self,
+        page_table: &mut PageTable,
+        vpn: VirtPageNum,
+        end: VirtPageNum,
+    ) -> Result<bool, Errno> {
+        if vpn.0 % HUGE_PAGE_PAGES != 0 || vpn.0 + HUGE_PAGE_PAGES > end.0 {
+            return Ok(false);
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        match self.map_type {
+            MapType::Identical => page_table
+                .map_huge(vpn, PhysPageNum(vpn.0), pte_flags)
+                .map(|_| true),
+            //找不到连续的页帧时退回到普通页面
+            MapType::Shared => {
+                let frames = match frame_alloc_contiguous(HUGE_PAGE_PAGES) {
+                    Some(frames) => frames,
+                    None => return Ok(false),
+                };
+                page_table.map_huge(vpn, frames[0].ppn, pte_flags)?;
+                for (i, frame) in frames.into_iter().enumerate() {
+                    lru::reset_age(frame.ppn);
+                    self.data_frames
+                        .insert(VirtPageNum(vpn.0 + i), Arc::new(frame));
+                }
+                Ok(true)
             }
+            MapType::Framed | MapType::Lazy => Ok(false),
         }
+    }
+    //为 [start, end) 中的页面建立映射，对齐的完整 2MiB 区域尽量使用大页。
+    //物理内存不足时撤销其中已经建立的映射
+    fn map_range(
+        &mut self,
+        page_table: &mut PageTable,
+        start: VirtPageNum,
+        end: VirtPageNum,
+    ) -> Result<(), Errno> {
+        let mut vpn = start;
+        while vpn < end {
+            let pages = match self.map_huge(page_table, vpn, end) {
+                Ok(true) => Ok(HUGE_PAGE_PAGES),
+                Ok(false) => self.map_one(page_table, vpn).map(|_| 1),
+                Err(err) => Err(err),
+            };
+            match pages {
+                Ok(pages) => vpn = VirtPageNum(vpn.0 + pages),
+                Err(err) => {
+                    self.unmap_range(page_table, start, vpn);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+    //解除 [start, end) 中页面的映射，完整落在其中的大页整个解除，其余的大页先被拆分
+    fn unmap_range(&mut self, page_table: &mut PageTable, start: VirtPageNum, end: VirtPageNum) {
+        let mut vpn = start;
+        while vpn < end {
+            if vpn.0 % HUGE_PAGE_PAGES == 0
+                && vpn.0 + HUGE_PAGE_PAGES <= end.0
+                && page_table.is_huge(vpn)
+            {
+                page_table.unmap_huge(vpn);
+                for i in 0..HUGE_PAGE_PAGES {
+                    self.data_frames.remove(&VirtPageNum(vpn.0 + i));
+                }
+                vpn = VirtPageNum(vpn.0 + HUGE_PAGE_PAGES);
+            } else {
+                self.unmap_one(page_table, vpn);
+                vpn.step();
+            }
+        }
+    }
+    //为还没有页帧的逻辑段依次把页面映射到已有的页帧上，按大页对齐的 HUGE_PAGE_PAGES 个连续页帧
+    //映射到同样对齐的连续页面时使用大页。物理内存不足以建立页表时撤销已经建立的映射
+    fn map_frames(
+        &mut self,
+        page_table: &mut PageTable,
+        frames: impl Iterator<Item = (VirtPageNum, Arc<FrameTracker>)>,
+    ) -> Result<(), Errno> {
         let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
-        page_table.map(vpn, ppn, pte_flags);
+        let frames: Vec<_> = frames.collect();
+        let is_huge = |run: &[(VirtPageNum, Arc<FrameTracker>)]| {
+            let (vpn, frame) = &run[0];
+            vpn.0 % HUGE_PAGE_PAGES == 0
+                && frame.ppn.0 % HUGE_PAGE_PAGES == 0
+                && run.iter().enumerate().all(|(i, (page, page_frame))| {
+                    page.0 == vpn.0 + i && page_frame.ppn.0 == frame.ppn.0 + i
+                })
+        };
+        //已经建立的映射 (起始页号, 页面数)
+        let mut mapped = Vec::new();
+        let mut i = 0;
+        while i < frames.len() {
+            let (vpn, frame) = &frames[i];
+            let result = match frames.get(i..i + HUGE_PAGE_PAGES) {
+                Some(run) if is_huge(run) => page_table
+                    .map_huge(*vpn, frame.ppn, pte_flags)
+                    .map(|_| HUGE_PAGE_PAGES),
+                _ => page_table.map(*vpn, frame.ppn, pte_flags).map(|_| 1),
+            };
+            match result {
+                Ok(pages) => {
+                    mapped.push((*vpn, pages));
+                    i += pages;
+                }
+                Err(err) => {
+                    for (vpn, pages) in mapped {
+                        if pages == 1 {
+                            page_table.unmap(vpn);
+                        } else {
+                            page_table.unmap_huge(vpn);
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        self.data_frames.extend(frames);
+        Ok(())
+    }
+    /// 用大页映射的 2MiB 区域数
+    pub fn huge_pages(&self, page_table: &PageTable) -> usize {
+        self.data_frames
+            .keys()
+            .filter(|vpn| vpn.0 % HUGE_PAGE_PAGES == 0 && page_table.is_huge(**vpn))
+            .count()
     }
 
+    //与 another 映射到同一批页帧上，页帧在最后一个映射它的逻辑段被回收时才释放
+    pub fn map_shared(
+        &mut self,
+        page_table: &mut PageTable,
+        another: &MapArea,
+    ) -> Result<(), Errno> {
+        self.map_frames(
+            page_table,
+            another
+                .data_frames
+                .iter()
+                .map(|(vpn, frame)| (*vpn, frame.clone())),
+        )
+    }
     pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
-        #[allow(clippy::single_match)]
+        //已被换出的页面没有页帧，释放它在交换区中的位置
+        if self.swapped.remove(&vpn).is_some() {
+            page_table.clear_swapped(vpn);
+            return;
+        }
         match self.map_type {
-            MapType::Framed => {
+            MapType::Framed | MapType::Shared => {
                 self.data_frames.remove(&vpn);
             }
-            _ => {}
+            //从未被访问过的惰性分配页面没有建立映射
+            MapType::Lazy => {
+                if self.data_frames.remove(&vpn).is_none() && !self.zero_pages.remove(&vpn) {
+                    return;
+                }
+            }
+            MapType::Identical => {}
         }
         page_table.unmap(vpn);
     }
-    pub fn map(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
-            self.map_one(page_table, vpn);
+    //在 at 处将逻辑段一分为二，返回 [at, end) 的部分，其中已分配的页帧随之转移
+    pub fn split_off(&mut self, at: VirtPageNum) -> MapArea {
+        let tail = MapArea {
+            vpn_range: VPNRange::new(at, self.vpn_range.get_end()),
+            data_frames: self.data_frames.split_off(&at),
+            swapped: self.swapped.split_off(&at),
+            zero_pages: self.zero_pages.split_off(&at),
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+        };
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), at);
+        tail
+    }
+    //把紧接在后面的逻辑段 next 并入这个逻辑段，其中已分配的页帧和被换出的页面随之转移
+    pub fn append(&mut self, mut next: MapArea) {
+        debug_assert!(self.can_merge(&next));
+        self.data_frames.append(&mut next.data_frames);
+        self.swapped.append(&mut next.swapped);
+        self.zero_pages.append(&mut next.zero_pages);
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), next.vpn_range.get_end());
+    }
+    //只合并 mmap 得到的相邻且权限相同的私有映射。用户栈、堆和共享内存段分别按栈顶、
+    //起始地址找到，与其他逻辑段合并之后就找不到了
+    fn can_merge(&self, next: &MapArea) -> bool {
+        self.map_type == MapType::Lazy
+            && next.map_type == MapType::Lazy
+            && self.map_perm == next.map_perm
+            && self.vpn_range.get_end() == next.vpn_range.get_start()
+    }
+    //逻辑段占据的区间的末尾，空的逻辑段也占据其起始的一页，以免别的逻辑段挡住它之后的增长
+    fn occupied_end(&self) -> VirtPageNum {
+        let start = self.vpn_range.get_start();
+        self.vpn_range.get_end().max(VirtPageNum(start.0 + 1))
+    }
+    pub fn shrink_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        self.unmap_range(page_table, new_end, self.vpn_range.get_end());
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+    }
+    //惰性分配的逻辑段扩大时同样不立即分配页帧。物理内存不足时逻辑段保持原样
+    pub fn append_to(
+        &mut self,
+        page_table: &mut PageTable,
+        new_end: VirtPageNum,
+    ) -> Result<(), Errno> {
+        if self.map_type != MapType::Lazy {
+            self.map_range(page_table, self.vpn_range.get_end(), new_end)?;
         }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+        Ok(())
     }
-    pub fn unmap(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
-            self.unmap_one(page_table, vpn);
+    //向下扩大到从 new_start 开始，为新增的页面分配页帧。物理内存不足时逻辑段保持原样
+    pub fn prepend_to(
+        &mut self,
+        page_table: &mut PageTable,
+        new_start: VirtPageNum,
+    ) -> Result<(), Errno> {
+        self.map_range(page_table, new_start, self.vpn_range.get_start())?;
+        self.vpn_range = VPNRange::new(new_start, self.vpn_range.get_end());
+        Ok(())
+    }
+    //将逻辑段整体移动到从 new_start 开始的位置，已分配的页帧原样映射到新的虚拟页面上。
+    //先为所有新的页面分配好页表，物理内存不足时逻辑段保持原样
+    pub fn move_to(
+        &mut self,
+        page_table: &mut PageTable,
+        new_start: VirtPageNum,
+    ) -> Result<(), Errno> {
+        let start = self.vpn_range.get_start();
+        let end = self.vpn_range.get_end();
+        for vpn in self
+            .data_frames
+            .keys()
+            .chain(self.swapped.keys())
+            .chain(self.zero_pages.iter())
+        {
+            page_table.reserve(VirtPageNum(vpn.0 - start.0 + new_start.0))?;
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+        for (vpn, frame) in core::mem::take(&mut self.data_frames) {
+            let new_vpn = VirtPageNum(vpn.0 - start.0 + new_start.0);
+            page_table.unmap(vpn);
+            page_table.map(new_vpn, frame.ppn, pte_flags).unwrap();
+            self.data_frames.insert(new_vpn, frame);
+        }
+        for (vpn, slot) in core::mem::take(&mut self.swapped) {
+            let new_vpn = VirtPageNum(vpn.0 - start.0 + new_start.0);
+            page_table.clear_swapped(vpn);
+            page_table.set_swapped(new_vpn, slot.index());
+            self.swapped.insert(new_vpn, slot);
         }
+        for vpn in core::mem::take(&mut self.zero_pages) {
+            let new_vpn = VirtPageNum(vpn.0 - start.0 + new_start.0);
+            page_table.unmap(vpn);
+            self.map_zero(page_table, new_vpn).unwrap();
+        }
+        self.vpn_range = VPNRange::new(new_start, VirtPageNum(end.0 - start.0 + new_start.0));
+        Ok(())
+    }
+    pub fn contains(&self, vpn: VirtPageNum) -> bool {
+        self.vpn_range.get_start() <= vpn && vpn < self.vpn_range.get_end()
+    }
+    //只有用户的私有页面可以换出，共享的页帧可能还被其他地址空间使用
+    fn swappable(&self) -> bool {
+        matches!(self.map_type, MapType::Framed | MapType::Lazy)
+            && self.map_perm.contains(MapPermission::U)
+    }
+    //检查一次已映射的页面 vpn：取出并清除页表项的访问位和脏位，返回页面新的年龄
+    fn age(&self, page_table: &mut PageTable, vpn: VirtPageNum) -> u8 {
+        let (accessed, dirty) = page_table.take_access_bits(vpn);
+        lru::age_page(self.data_frames[&vpn].ppn, accessed, dirty)
+    }
+    //把页面 vpn 写入交换区并释放它的页帧，交换区已满时返回 false
+    fn swap_out(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> bool {
+        let slot = match SwapSlot::write(self.data_frames[&vpn].ppn) {
+            Some(slot) => slot,
+            None => return false,
+        };
+        page_table.set_swapped(vpn, slot.index());
+        self.data_frames.remove(&vpn);
+        self.swapped.insert(vpn, slot);
+        lru::PAGE_STATS.exclusive_access().swap_outs += 1;
+        true
+    }
+    //为已被换出的页面 vpn 分配页帧并从交换区读回，物理内存不足时页面留在交换区中
+    fn swap_in(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) -> Result<(), Errno> {
+        let slot = self.swapped.remove(&vpn).unwrap();
+        debug_assert_eq!(page_table.translate(vpn).unwrap().swap_slot(), slot.index());
+        page_table.clear_swapped(vpn);
+        if let Err(err) = self.map_one(page_table, vpn) {
+            page_table.set_swapped(vpn, slot.index());
+            self.swapped.insert(vpn, slot);
+            return Err(err);
+        }
+        slot.read(self.data_frames[&vpn].ppn);
+        lru::PAGE_STATS.exclusive_access().swap_ins += 1;
+        Ok(())
+    }
+    //惰性分配的逻辑段在插入时不建立任何映射
+    pub fn map(&mut self, page_table: &mut PageTable) -> Result<(), Errno> {
+        if self.map_type == MapType::Lazy {
+            return Ok(());
+        }
+        self.map_range(
+            page_table,
+            self.vpn_range.get_start(),
+            self.vpn_range.get_end(),
+        )
+    }
+    pub fn unmap(&mut self, page_table: &mut PageTable) {
+        self.unmap_range(
+            page_table,
+            self.vpn_range.get_start(),
+            self.vpn_range.get_end(),
+        );
     }
     /// data: start-aligned but maybe with shorter length
     /// assume that all frames were cleared before
@@ -360,10 +1335,14 @@ impl MapArea {
 }
 
 #[derive(Copy, Clone, PartialEq, Debug)]
-/// map type for memory set: identical or framed
+/// map type for memory set: identical, framed, lazily framed or shared
 pub enum MapType {
     Identical,
     Framed,
+    /// 与 Framed 相同，但页帧在第一次访问时才分配
+    Lazy,
+    /// 与 Framed 相同，但 fork 时子进程与父进程共享同一批页帧而不是复制
+    Shared,
 }
 
 bitflags! {
@@ -376,6 +1355,23 @@ bitflags! {
     }
 }
 
+impl MapPermission {
+    /// 是否同时可写与可执行，这样的映射违反 W^X 策略，一律拒绝
+    pub fn violates_wx(&self) -> bool {
+        self.contains(Self::W | Self::X)
+    }
+}
+
+/// 记录一次因违反 W^X 策略而被拒绝的映射 [start, end)，只在开启 wx-audit 特性时输出
+#[allow(unused_variables)]
+pub fn wx_audit(source: &str, start: usize, end: usize) {
+    #[cfg(feature = "wx-audit")]
+    println!(
+        "[kernel] W^X: refused {} [{:#x}, {:#x}) that is both writable and executable.",
+        source, start, end
+    );
+}
+
 #[allow(unused)]
 pub fn remap_test() {
     let mut kernel_space = KERNEL_SPACE.exclusive_access();
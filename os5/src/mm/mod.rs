@@ -8,22 +8,39 @@
 
 
 mod address;
+mod asid;
 mod frame_allocator;
 mod heap_allocator;
+mod lru;
 mod memory_set;
 mod page_table;
+mod shm;
+mod slab;
+mod swap;
 
 pub use address::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 pub use address::{StepByOne, VPNRange};
-pub use frame_allocator::{frame_alloc, FrameTracker};
+pub use heap_allocator::{heap_stats, slab_stats};
+pub use frame_allocator::{
+    frame_alloc, frame_alloc_contiguous, frame_available, frame_stats, FrameTracker,
+};
+pub use lru::{count_harvest, page_stats, PageStats, AGE_BITS};
 pub use memory_set::remap_test;
-pub use memory_set::{MapPermission, MemorySet, KERNEL_SPACE};
+pub use memory_set::{wx_audit, MapPermission, MemorySet, KERNEL_SPACE};
+pub use page_table::{
+    copy_bytes_from_user, copy_bytes_to_user, copy_from_user, copy_slice_to_user, copy_to_user,
+};
 pub use page_table::{translated_byte_buffer, translated_refmut, translated_str, PageTableEntry};
+pub use page_table::UserBuffer;
 pub use page_table::{PTEFlags, PageTable};
+pub use shm::{IPC_CREAT, IPC_EXCL, IPC_PRIVATE, SHM_SEGMENTS};
+pub use swap::{swap_enabled, swap_used};
 
 /// initiate heap allocator, frame allocator and kernel space
 pub fn init() {
     heap_allocator::init_heap();
     frame_allocator::init_frame_allocator();
     KERNEL_SPACE.exclusive_access().activate();
+    asid::init();
+    swap::init();
 }
@@ -1,10 +1,16 @@
 //! Implementation of [`PageTableEntry`] and [`PageTable`].
 
-use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
+use super::{
+    frame_alloc, FrameTracker, MapPermission, PhysAddr, PhysPageNum, VirtAddr, VirtPageNum,
+};
+use crate::config::{HUGE_PAGE_PAGES, PAGE_SIZE, USER_SPACE_END, USER_STR_MAX};
+use crate::errno::Errno;
+use crate::sync::PreemptGuard;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
+use core::mem::{size_of, MaybeUninit};
 
 bitflags! {
     /// page table entry flags
@@ -20,6 +26,10 @@ bitflags! {
     }
 }
 
+/// 页表项中留给软件使用的 RSW 位之一：V 为 0 且设置了该位表示页面已被换出，
+/// 此时 PPN 字段保存的是页面在交换区中的位置
+const PTE_SWAPPED: usize = 1 << 8;
+
 #[derive(Copy, Clone)]
 #[repr(C)]
 /// page table entry structure
@@ -36,6 +46,19 @@ impl PageTableEntry {
     pub fn empty() -> Self {
         PageTableEntry { bits: 0 }
     }
+    /// 已被换出到交换区中第 slot 个位置的页面
+    pub fn swapped(slot: usize) -> Self {
+        PageTableEntry {
+            bits: slot << 10 | PTE_SWAPPED,
+        }
+    }
+    pub fn is_swapped(&self) -> bool {
+        !self.is_valid() && self.bits & PTE_SWAPPED != 0
+    }
+    /// 已被换出的页面在交换区中的位置
+    pub fn swap_slot(&self) -> usize {
+        self.bits >> 10
+    }
     pub fn ppn(&self) -> PhysPageNum {
         (self.bits >> 10 & ((1usize << 44) - 1)).into()
     }
@@ -54,6 +77,10 @@ impl PageTableEntry {
     pub fn executable(&self) -> bool {
         (self.flags() & PTEFlags::X) != PTEFlags::empty()
     }
+    /// 有效且 R/W/X 中至少设置了一位的页表项直接映射页面，否则指向下一级页表
+    pub fn is_leaf(&self) -> bool {
+        self.is_valid() && (self.readable() || self.writable() || self.executable())
+    }
 }
 
 /// page table structure
@@ -62,14 +89,14 @@ pub struct PageTable {
     frames: Vec<FrameTracker>,
 }
 
-/// Assume that it won't oom when creating/mapping.
+/// 创建页表和建立映射时可能因为物理内存不足而失败，此时返回 Errno::ENOMEM
 impl PageTable {
-    pub fn new() -> Self {
-        let frame = frame_alloc().unwrap();
-        PageTable {
+    pub fn new() -> Result<Self, Errno> {
+        let frame = frame_alloc().ok_or(Errno::ENOMEM)?;
+        Ok(PageTable {
             root_ppn: frame.ppn,
             frames: vec![frame],
-        }
+        })
     }
     /// Temporarily used to get arguments from user space.
     pub fn from_token(satp: usize) -> Self {
@@ -78,47 +105,102 @@ impl PageTable {
             frames: Vec::new(),
         }
     }
+    //沿途缺少的页表按需分配，途经的大页先拆分成普通页面，物理内存不足时返回 None
     fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
-        let mut idxs = vpn.indexes();
+        self.find_pte_create_at(vpn, 2)
+    }
+    //找到 vpn 在第 level 级页表（根页表为第 0 级）中的页表项
+    fn find_pte_create_at(
+        &mut self,
+        vpn: VirtPageNum,
+        level: usize,
+    ) -> Option<&mut PageTableEntry> {
+        let idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
-        let mut result: Option<&mut PageTableEntry> = None;
-        for (i, idx) in idxs.iter_mut().enumerate() {
+        for (i, idx) in idxs.iter().enumerate() {
             let pte = &mut ppn.get_pte_array()[*idx];
-            if i == 2 {
-                result = Some(pte);
-                break;
+            if i == level {
+                return Some(pte);
             }
             if !pte.is_valid() {
-                let frame = frame_alloc().unwrap();
+                let frame = frame_alloc()?;
+                *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
+                self.frames.push(frame);
+            } else if pte.is_leaf() {
+                //只会遇到第 1 级的大页：拆分后每个页面仍映射到原来的页帧，权限不变
+                let frame = frame_alloc()?;
+                for (k, entry) in frame.ppn.get_pte_array().iter_mut().enumerate() {
+                    *entry = PageTableEntry::new((pte.ppn().0 + k).into(), pte.flags());
+                }
                 *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
                 self.frames.push(frame);
             }
             ppn = pte.ppn();
         }
-        result
+        None
     }
-    fn find_pte(&self, vpn: VirtPageNum) -> Option<&PageTableEntry> {
+    //找到映射 vpn 的页表项及其所在的级数，vpn 落在大页中时返回第 1 级的页表项
+    fn find_pte(&self, vpn: VirtPageNum) -> Option<(&PageTableEntry, usize)> {
         let idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
-        let mut result: Option<&PageTableEntry> = None;
         for (i, idx) in idxs.iter().enumerate() {
             let pte = &ppn.get_pte_array()[*idx];
-            if i == 2 {
-                result = Some(pte);
-                break;
+            if i == 2 || pte.is_leaf() {
+                return Some((pte, i));
             }
             if !pte.is_valid() {
                 return None;
             }
             ppn = pte.ppn();
         }
-        result
+        None
     }
     #[allow(unused)]
-    pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
-        let pte = self.find_pte_create(vpn).unwrap();
+    pub fn map(
+        &mut self,
+        vpn: VirtPageNum,
+        ppn: PhysPageNum,
+        flags: PTEFlags,
+    ) -> Result<(), Errno> {
+        let pte = self.find_pte_create(vpn).ok_or(Errno::ENOMEM)?;
         assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
         *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        Ok(())
+    }
+    /// 用一个第 1 级页表项把从 vpn 开始的 HUGE_PAGE_PAGES 个页面映射到从 ppn 开始的连续页帧，
+    /// vpn 和 ppn 都必须按大页对齐
+    pub fn map_huge(
+        &mut self,
+        vpn: VirtPageNum,
+        ppn: PhysPageNum,
+        flags: PTEFlags,
+    ) -> Result<(), Errno> {
+        assert!(vpn.0 % HUGE_PAGE_PAGES == 0 && ppn.0 % HUGE_PAGE_PAGES == 0);
+        let pte = self.find_pte_create_at(vpn, 1).ok_or(Errno::ENOMEM)?;
+        //这一范围之前映射过的普通页面全部解除之后会留下一张空的页表，直接用大页替换它，
+        //这张页表的页帧在页表销毁时回收
+        assert!(
+            !pte.is_valid()
+                || !pte.is_leaf() && pte.ppn().get_pte_array().iter().all(|e| e.bits == 0),
+            "vpn {:?} is mapped before mapping",
+            vpn
+        );
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+        Ok(())
+    }
+    /// 解除从 vpn 开始的整个大页的映射
+    pub fn unmap_huge(&mut self, vpn: VirtPageNum) {
+        let pte = self.find_pte_create_at(vpn, 1).unwrap();
+        assert!(pte.is_leaf(), "vpn {:?} is not a huge page", vpn);
+        *pte = PageTableEntry::empty();
+    }
+    /// 页面 vpn 是否落在一个大页中
+    pub fn is_huge(&self, vpn: VirtPageNum) -> bool {
+        matches!(self.find_pte(vpn), Some((_, level)) if level < 2)
+    }
+    /// 预先分配映射页面 vpn 所需的各级页表，之后对 vpn 的映射不会再因为物理内存不足而失败
+    pub fn reserve(&mut self, vpn: VirtPageNum) -> Result<(), Errno> {
+        self.find_pte_create(vpn).map(|_| ()).ok_or(Errno::ENOMEM)
     }
     #[allow(unused)]
     pub fn unmap(&mut self, vpn: VirtPageNum) {
@@ -126,17 +208,42 @@ impl PageTable {
         assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
         *pte = PageTableEntry::empty();
     }
+    /// 修改已映射页面的权限，映射到的物理页帧保持不变
+    pub fn set_flags(&mut self, vpn: VirtPageNum, flags: PTEFlags) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(
+            pte.is_valid(),
+            "vpn {:?} is invalid before changing flags",
+            vpn
+        );
+        *pte = PageTableEntry::new(pte.ppn(), flags | PTEFlags::V);
+    }
+    /// 页面 vpn 被换出到交换区中第 slot 个位置，页表项改为无效
+    pub fn set_swapped(&mut self, vpn: VirtPageNum, slot: usize) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        *pte = PageTableEntry::swapped(slot);
+    }
+    /// 清除已被换出的页面的页表项
+    pub fn clear_swapped(&mut self, vpn: VirtPageNum) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        assert!(pte.is_swapped(), "vpn {:?} is not swapped out", vpn);
+        *pte = PageTableEntry::empty();
+    }
+    /// 清除已映射页面的访问位（A）和脏位（D），返回清除之前的 (访问位, 脏位)。修改之后需要刷新 TLB
+    pub fn take_access_bits(&mut self, vpn: VirtPageNum) -> (bool, bool) {
+        let pte = self.find_pte_create(vpn).unwrap();
+        let flags = pte.flags();
+        pte.bits &= !((PTEFlags::A | PTEFlags::D).bits as usize);
+        (flags.contains(PTEFlags::A), flags.contains(PTEFlags::D))
+    }
+    //vpn 落在大页中时，换算出 vpn 自己的页帧并返回对应的普通页表项
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.find_pte(vpn).copied()
-    }
-    pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
-        self.find_pte(va.clone().floor()).map(|pte| {
-            //println!("translate_va:va = {:?}", va);
-            let aligned_pa: PhysAddr = pte.ppn().into();
-            //println!("translate_va:pa_align = {:?}", aligned_pa);
-            let offset = va.page_offset();
-            let aligned_pa_usize: usize = aligned_pa.into();
-            (aligned_pa_usize + offset).into()
+        self.find_pte(vpn).map(|(pte, level)| match level {
+            2 => *pte,
+            _ => PageTableEntry::new(
+                (pte.ppn().0 + (vpn.0 & (HUGE_PAGE_PAGES - 1))).into(),
+                pte.flags(),
+            ),
         })
     }
     pub fn token(&self) -> usize {
@@ -144,57 +251,191 @@ impl PageTable {
     }
 }
 
-/// translate a pointer to a mutable u8 Vec through page table
-pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
+//内核代替用户以 access 权限访问页面 vpn：页面必须允许用户态以这样的权限访问。
+//尚未分配的惰性页面和已被换出的页面先按缺页处理调入，地址不合法时返回 Errno::EFAULT
+fn user_page(
+    page_table: &PageTable,
+    vpn: VirtPageNum,
+    access: MapPermission,
+) -> Result<PhysPageNum, Errno> {
+    let permitted = |pte: &PageTableEntry| {
+        pte.is_valid()
+            && pte.flags().contains(PTEFlags::U)
+            && (pte.readable() || !access.contains(MapPermission::R))
+            && (pte.writable() || !access.contains(MapPermission::W))
+    };
+    if let Some(pte) = page_table.translate(vpn).filter(permitted) {
+        return Ok(pte.ppn());
+    }
+    if crate::task::fault_in_user_page(page_table.token(), vpn, access) {
+        if let Some(pte) = page_table.translate(vpn).filter(permitted) {
+            return Ok(pte.ppn());
+        }
+    }
+    Err(Errno::EFAULT)
+}
+
+//逐页访问用户地址空间 token 中的 [start, start + len)，对落在每个页面中的一段调用 f(这一段, 它在区间中的偏移)。
+//区间超出用户地址空间或其中有不能访问的页面时返回 Errno::EFAULT，此时前面的页面可能已经访问过
+fn for_each_user_chunk(
+    token: usize,
+    start: usize,
+    len: usize,
+    access: MapPermission,
+    mut f: impl FnMut(&'static mut [u8], usize),
+) -> Result<(), Errno> {
+    let end = start
+        .checked_add(len)
+        .filter(|end| *end <= USER_SPACE_END)
+        .ok_or(Errno::EFAULT)?;
     let page_table = PageTable::from_token(token);
-    let mut start = ptr as usize;
-    let end = start + len;
-    let mut v = Vec::new();
-    while start < end {
-        let start_va = VirtAddr::from(start);
-        let mut vpn = start_va.floor();
-        let ppn = page_table.translate(vpn).unwrap().ppn();
-        vpn.step();
-        let mut end_va: VirtAddr = vpn.into();
-        end_va = end_va.min(VirtAddr::from(end));
-        if end_va.page_offset() == 0 {
-            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..]);
-        } else {
-            v.push(&mut ppn.get_bytes_array()[start_va.page_offset()..end_va.page_offset()]);
+    let mut va = start;
+    while va < end {
+        //从查页表到访问完毕期间不能切换任务，否则页面可能被其他任务换出
+        let _preempt = PreemptGuard::new();
+        let vpn = VirtAddr::from(va).floor();
+        let ppn = user_page(&page_table, vpn, access)?;
+        let offset = va % PAGE_SIZE;
+        let chunk = (PAGE_SIZE - offset).min(end - va);
+        f(
+            &mut ppn.get_bytes_array()[offset..offset + chunk],
+            va - start,
+        );
+        va += chunk;
+    }
+    Ok(())
+}
+
+/// 把用户地址空间 token 中从 ptr 开始的 dst.len() 个字节复制到 dst，可以跨越多个页面。
+/// 地址不合法或页面不可读时返回 Errno::EFAULT
+pub fn copy_bytes_from_user(token: usize, ptr: *const u8, dst: &mut [u8]) -> Result<(), Errno> {
+    for_each_user_chunk(
+        token,
+        ptr as usize,
+        dst.len(),
+        MapPermission::R,
+        |chunk, offset| dst[offset..offset + chunk.len()].copy_from_slice(chunk),
+    )
+}
+
+/// 把 src 复制到用户地址空间 token 中从 ptr 开始的位置，可以跨越多个页面。
+/// 地址不合法或页面不可写时返回 Errno::EFAULT
+pub fn copy_bytes_to_user(token: usize, ptr: *mut u8, src: &[u8]) -> Result<(), Errno> {
+    for_each_user_chunk(
+        token,
+        ptr as usize,
+        src.len(),
+        MapPermission::W,
+        |chunk, offset| chunk.copy_from_slice(&src[offset..offset + chunk.len()]),
+    )
+}
+
+/// 从用户地址空间 token 中读出 ptr 指向的值。T 的任意字节内容都必须是合法的值
+pub fn copy_from_user<T: Copy>(token: usize, ptr: *const T) -> Result<T, Errno> {
+    let mut value = MaybeUninit::<T>::uninit();
+    let bytes =
+        unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, size_of::<T>()) };
+    copy_bytes_from_user(token, ptr as *const u8, bytes)?;
+    Ok(unsafe { value.assume_init() })
+}
+
+/// 把 value 写入用户地址空间 token 中 ptr 指向的位置
+pub fn copy_to_user<T>(token: usize, ptr: *mut T, value: &T) -> Result<(), Errno> {
+    copy_slice_to_user(token, ptr, core::slice::from_ref(value))
+}
+
+/// 把 values 依次写入用户地址空间 token 中从 ptr 开始的数组
+pub fn copy_slice_to_user<T>(token: usize, ptr: *mut T, values: &[T]) -> Result<(), Errno> {
+    let bytes = unsafe {
+        core::slice::from_raw_parts(values.as_ptr() as *const u8, size_of::<T>() * values.len())
+    };
+    copy_bytes_to_user(token, ptr as *mut u8, bytes)
+}
+
+/// 逐页取出用户地址空间 token 中 [ptr, ptr + len) 对应的内核可见的字节切片，缓冲区可以跨越多个页面。
+/// access 为内核要对缓冲区进行的访问，地址不合法或页面不允许这样访问时返回 Errno::EFAULT
+pub fn translated_byte_buffer(
+    token: usize,
+    ptr: *const u8,
+    len: usize,
+    access: MapPermission,
+) -> Result<Vec<&'static mut [u8]>, Errno> {
+    let mut buffers = Vec::new();
+    for_each_user_chunk(token, ptr as usize, len, access, |chunk, _| {
+        buffers.push(chunk)
+    })?;
+    Ok(buffers)
+}
+
+/// 用户地址空间 token 中的缓冲区 [ptr, ptr + len)
+//只记录地址，每次访问时重新查页表：读写管道时任务可能阻塞，期间缓冲区所在的页面可能被换出
+pub struct UserBuffer {
+    token: usize,
+    ptr: usize,
+    len: usize,
+}
+
+impl UserBuffer {
+    pub fn new(token: usize, ptr: *const u8, len: usize) -> Self {
+        Self {
+            token,
+            ptr: ptr as usize,
+            len,
         }
-        start = end_va.into();
     }
-    v
+    /// 缓冲区的总字节数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// 把缓冲区中从 offset 开始的 dst.len() 个字节复制到 dst
+    pub fn read_at(&self, offset: usize, dst: &mut [u8]) -> Result<(), Errno> {
+        assert!(offset + dst.len() <= self.len);
+        copy_bytes_from_user(self.token, (self.ptr + offset) as *const u8, dst)
+    }
+    /// 把 src 复制到缓冲区中从 offset 开始的位置
+    pub fn write_at(&self, offset: usize, src: &[u8]) -> Result<(), Errno> {
+        assert!(offset + src.len() <= self.len);
+        copy_bytes_to_user(self.token, (self.ptr + offset) as *mut u8, src)
+    }
 }
 
-//用来从用户地址空间中查找字符串，其原理就是逐字节查页表直到发现一个 \0 为止。
-// 因为内核不知道字符串的长度，且字符串可能是跨物理页的,所以要逐字节查页表
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
+/// 从用户地址空间 token 中读出 ptr 开始的以 \0 结尾的字符串，字符串可以跨越多个页面。
+/// 地址不合法或页面不可读时返回 Errno::EFAULT，加上 \0 超过 USER_STR_MAX 字节时返回 Errno::ENAMETOOLONG
+pub fn translated_str(token: usize, ptr: *const u8) -> Result<String, Errno> {
     let page_table = PageTable::from_token(token);
-    let mut string = String::new();
+    let mut bytes = Vec::new();
     let mut va = ptr as usize;
-    loop {
-        let ch: u8 = *(page_table
-            .translate_va(VirtAddr::from(va))
-            .unwrap()
-            .get_mut());
-        if ch == 0 {
-            break;
-        } else {
-            string.push(ch as char);
-            va += 1;
+    //内核不知道字符串的长度，逐页查找 \0，每个页面只查一次页表
+    while bytes.len() < USER_STR_MAX {
+        if va >= USER_SPACE_END {
+            return Err(Errno::EFAULT);
+        }
+        let ppn = user_page(&page_table, VirtAddr::from(va).floor(), MapPermission::R)?;
+        let chunk = &ppn.get_bytes_array()[va % PAGE_SIZE..];
+        match chunk.iter().position(|&ch| ch == 0) {
+            Some(len) => {
+                bytes.extend_from_slice(&chunk[..len]);
+                if bytes.len() >= USER_STR_MAX {
+                    break;
+                }
+                return Ok(bytes.iter().map(|&ch| ch as char).collect());
+            }
+            None => {
+                bytes.extend_from_slice(chunk);
+                va += chunk.len();
+            }
         }
     }
-    string
+    Err(Errno::ENAMETOOLONG)
 }
 
+/// 内核代替用户写入 ptr 指向的值，页面必须允许用户态写入。只映射到共享零页的页面先按写缺页处理，
+/// 得到自己的页帧之后再写入
 pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
-    //println!("into translated_refmut!");
     let page_table = PageTable::from_token(token);
-    let va = ptr as usize;
-    //println!("translated_refmut: before translate_va");
-    page_table
-        .translate_va(VirtAddr::from(va))
-        .unwrap()
-        .get_mut()
+    let va = VirtAddr::from(ptr as usize);
+    let ppn = user_page(&page_table, va.floor(), MapPermission::W)
+        .unwrap_or_else(|_| panic!("user page {:?} is not writable", va.floor()));
+    let pa: PhysAddr = ppn.into();
+    PhysAddr::from(pa.0 + va.page_offset()).get_mut()
 }
@@ -0,0 +1,104 @@
+//! System V 风格的共享内存段
+//!
+//! 共享内存段在创建时一次性分配好全部页帧，由全局的 [`SHM_SEGMENTS`] 按编号登记。
+//! 每次 attach 都把同一批页帧映射进进程的地址空间，页帧以引用计数的方式
+//! 同时属于登记表和所有映射了它的逻辑段：段被删除后不能再 attach，
+//! 已有的映射仍然有效，最后一个映射解除时页帧才被回收。
+
+use super::{frame_alloc, frame_available, FrameTracker};
+use crate::config::PAGE_SIZE;
+use crate::errno::Errno;
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// 不与任何键关联、总是创建新段的键
+pub const IPC_PRIVATE: usize = 0;
+/// shmget 的标志：键不存在时创建
+pub const IPC_CREAT: usize = 0o1000;
+/// shmget 的标志：与 IPC_CREAT 一起使用，键已存在时失败
+pub const IPC_EXCL: usize = 0o2000;
+
+/// 一个共享内存段
+pub struct ShmSegment {
+    key: usize,
+    size: usize,
+    frames: Vec<Arc<FrameTracker>>,
+}
+
+impl ShmSegment {
+    /// 段的大小（字节），即创建时指定的大小
+    pub fn size(&self) -> usize {
+        self.size
+    }
+    /// 段中的页帧，按页的顺序排列
+    pub fn frames(&self) -> &[Arc<FrameTracker>] {
+        &self.frames
+    }
+    /// 当前映射了这个段的逻辑段个数
+    //每个映射都持有每个页帧的一份引用，除去登记表自己持有的那一份
+    pub fn nattch(&self) -> usize {
+        Arc::strong_count(&self.frames[0]) - 1
+    }
+}
+
+/// 按编号登记的共享内存段，编号从 1 开始递增，不会重复使用
+pub struct ShmRegistry {
+    next_id: usize,
+    segments: BTreeMap<usize, ShmSegment>,
+}
+
+impl ShmRegistry {
+    fn new() -> Self {
+        Self {
+            next_id: 1,
+            segments: BTreeMap::new(),
+        }
+    }
+    /// 按键查找或创建一个至少为 size 字节的段，返回段的编号
+    pub fn get(&mut self, key: usize, size: usize, flags: usize) -> Result<usize, Errno> {
+        if key != IPC_PRIVATE {
+            if let Some((&id, segment)) = self.segments.iter().find(|(_, seg)| seg.key == key) {
+                if flags & IPC_CREAT != 0 && flags & IPC_EXCL != 0 {
+                    return Err(Errno::EEXIST);
+                }
+                if size > segment.size {
+                    return Err(Errno::EINVAL);
+                }
+                return Ok(id);
+            }
+            if flags & IPC_CREAT == 0 {
+                return Err(Errno::ENOENT);
+            }
+        }
+        if size == 0 {
+            return Err(Errno::EINVAL);
+        }
+        let pages = (size + PAGE_SIZE - 1) / PAGE_SIZE;
+        if pages > frame_available() {
+            return Err(Errno::ENOMEM);
+        }
+        let frames = (0..pages)
+            .map(|_| Arc::new(frame_alloc().unwrap()))
+            .collect();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.segments.insert(id, ShmSegment { key, size, frames });
+        Ok(id)
+    }
+    pub fn segment(&self, id: usize) -> Option<&ShmSegment> {
+        self.segments.get(&id)
+    }
+    /// 删除段：此后不能再 attach，已有的映射在解除之前仍然有效
+    pub fn remove(&mut self, id: usize) -> bool {
+        self.segments.remove(&id).is_some()
+    }
+}
+
+lazy_static! {
+    /// 系统中所有的共享内存段
+    pub static ref SHM_SEGMENTS: UPSafeCell<ShmRegistry> =
+        unsafe { UPSafeCell::new(ShmRegistry::new()) };
+}
@@ -0,0 +1,171 @@
+//! slab 分配器
+//!
+//! 频繁创建和销毁的内核对象（任务控制块、进程控制块、页帧的引用计数……）各有一个对象缓存。
+//! 缓存从内核堆中申请整块的 slab 并切分成大小相同的对象，空闲的对象串成链表，
+//! 释放的对象回到所属缓存的空闲链表中，下次分配时直接取用而不再经过通用的伙伴堆，
+//! 既避免了大量小对象在堆中造成碎片，也让 fork/exit 频繁时的分配更快。
+//!
+//! 全局分配器（见 heap_allocator）按 Layout 查找缓存：大小和对齐与缓存登记的完全相同的分配都由该缓存负责，
+//! 找不到缓存的分配仍交给伙伴堆。slab 一旦申请就不再归还给堆。
+
+use crate::config::PAGE_SIZE;
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr::null_mut;
+
+/// 最多能登记的对象缓存数
+pub const MAX_CACHES: usize = 8;
+//每个 slab 至少能容纳的对象数
+const MIN_OBJECTS_PER_SLAB: usize = 8;
+
+//空闲对象的开头保存链表中下一个空闲对象的地址
+struct FreeObject {
+    next: *mut FreeObject,
+}
+
+/// 一个对象缓存的统计信息
+#[derive(Clone, Copy)]
+pub struct CacheStats {
+    pub name: &'static str,
+    /// 对象的大小（字节）
+    pub object_size: usize,
+    /// 每个 slab 中的对象数
+    pub objects_per_slab: usize,
+    /// 已经申请的 slab 数
+    pub slabs: usize,
+    /// 正在使用的对象数
+    pub in_use: usize,
+    /// 累计分配的对象数
+    pub allocs: usize,
+}
+
+struct ObjectCache {
+    stats: CacheStats,
+    //登记时的 Layout，只有与它完全相同的分配由这个缓存负责
+    key: Layout,
+    slab_layout: Layout,
+    free: *mut FreeObject,
+}
+
+impl ObjectCache {
+    fn new(name: &'static str, key: Layout) -> Self {
+        //空闲对象要能放下链表指针
+        let object = key
+            .align_to(core::mem::align_of::<FreeObject>())
+            .unwrap()
+            .pad_to_align();
+        let object_size = object.size().max(core::mem::size_of::<FreeObject>());
+        let slab_size = (object_size * MIN_OBJECTS_PER_SLAB)
+            .next_power_of_two()
+            .max(PAGE_SIZE);
+        Self {
+            stats: CacheStats {
+                name,
+                object_size,
+                objects_per_slab: slab_size / object_size,
+                slabs: 0,
+                in_use: 0,
+                allocs: 0,
+            },
+            key,
+            slab_layout: Layout::from_size_align(slab_size, object.align().max(PAGE_SIZE)).unwrap(),
+            free: null_mut(),
+        }
+    }
+    //从堆中申请一个 slab，把其中的对象都放入空闲链表
+    unsafe fn grow(&mut self, heap: &impl GlobalAlloc) -> bool {
+        let slab = heap.alloc(self.slab_layout);
+        if slab.is_null() {
+            return false;
+        }
+        for i in (0..self.stats.objects_per_slab).rev() {
+            let object = slab.add(i * self.stats.object_size) as *mut FreeObject;
+            (*object).next = self.free;
+            self.free = object;
+        }
+        self.stats.slabs += 1;
+        true
+    }
+    unsafe fn alloc(&mut self, heap: &impl GlobalAlloc) -> *mut u8 {
+        if self.free.is_null() && !self.grow(heap) {
+            return null_mut();
+        }
+        let object = self.free;
+        self.free = (*object).next;
+        self.stats.in_use += 1;
+        self.stats.allocs += 1;
+        object as *mut u8
+    }
+    unsafe fn dealloc(&mut self, ptr: *mut u8) {
+        let object = ptr as *mut FreeObject;
+        (*object).next = self.free;
+        self.free = object;
+        self.stats.in_use -= 1;
+    }
+}
+
+/// 所有对象缓存，由全局分配器持有
+pub struct SlabAllocator {
+    caches: [Option<ObjectCache>; MAX_CACHES],
+}
+
+//空闲链表中的裸指针只在持有全局分配器的锁时访问
+unsafe impl Send for SlabAllocator {}
+
+impl SlabAllocator {
+    pub const fn new() -> Self {
+        const EMPTY: Option<ObjectCache> = None;
+        Self {
+            caches: [EMPTY; MAX_CACHES],
+        }
+    }
+    /// 为 Layout 为 key 的分配登记一个名为 name 的缓存，已经登记过相同的 Layout 或缓存已满时返回 false
+    pub fn register(&mut self, name: &'static str, key: Layout) -> bool {
+        if self.cache(key).is_some() {
+            return false;
+        }
+        match self.caches.iter_mut().find(|cache| cache.is_none()) {
+            Some(slot) => {
+                *slot = Some(ObjectCache::new(name, key));
+                true
+            }
+            None => false,
+        }
+    }
+    fn cache(&mut self, layout: Layout) -> Option<&mut ObjectCache> {
+        self.caches
+            .iter_mut()
+            .flatten()
+            .find(|cache| cache.key == layout)
+    }
+    /// 从 layout 对应的缓存中分配一个对象，没有对应的缓存时返回 None，堆空间不足时返回空指针
+    pub unsafe fn alloc(&mut self, layout: Layout, heap: &impl GlobalAlloc) -> Option<*mut u8> {
+        self.cache(layout).map(|cache| cache.alloc(heap))
+    }
+    /// 把对象归还给 layout 对应的缓存，没有对应的缓存时返回 false
+    pub unsafe fn dealloc(&mut self, ptr: *mut u8, layout: Layout) -> bool {
+        match self.cache(layout) {
+            Some(cache) => {
+                cache.dealloc(ptr);
+                true
+            }
+            None => false,
+        }
+    }
+    /// 各个缓存的统计信息，按登记的顺序排列。这里不能分配内存
+    pub fn stats(&self) -> [Option<CacheStats>; MAX_CACHES] {
+        let mut stats = [None; MAX_CACHES];
+        for (stat, cache) in stats.iter_mut().zip(self.caches.iter()) {
+            *stat = cache.as_ref().map(|cache| cache.stats);
+        }
+        stats
+    }
+}
+
+/// Arc::new(T) 申请内存时使用的 Layout：两个引用计数之后跟着 T
+pub fn arc_layout<T>() -> Layout {
+    Layout::new::<[usize; 2]>()
+        .extend(Layout::new::<T>())
+        .unwrap()
+        .0
+        .pad_to_align()
+}
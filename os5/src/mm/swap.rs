@@ -0,0 +1,111 @@
+//! 交换区
+//!
+//! 交换区位于交换设备 [`SWAP_DEVICE`] 的开头，按页划分为 SWAP_SLOTS 个位置。物理页帧不足时，
+//! 用户页面被写入交换区中的一个空闲位置，页表项改为无效并记录该位置（见 [`PageTableEntry::swapped`]），
+//! 页面被再次访问时从交换区读回。位置由 [`SwapSlot`] 持有，随它一起释放。
+//!
+//! [`PageTableEntry::swapped`]: super::PageTableEntry::swapped
+
+use super::PhysPageNum;
+use crate::config::{PAGE_SIZE, SWAP_SLOTS};
+use crate::drivers::block::BLOCK_SZ;
+use crate::drivers::SWAP_DEVICE;
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+//一页占用的块数
+const BLOCKS_PER_PAGE: usize = PAGE_SIZE / BLOCK_SZ;
+
+/// 交换区中被占用的一个位置
+pub struct SwapSlot(usize);
+
+impl SwapSlot {
+    /// 把页帧 ppn 的内容写入交换区的一个空闲位置，没有交换设备或交换区已满时返回 None
+    pub fn write(ppn: PhysPageNum) -> Option<Self> {
+        let device = SWAP_DEVICE.as_ref()?;
+        let slot = SWAP_ALLOCATOR.exclusive_access().alloc()?;
+        for (i, block) in ppn.get_bytes_array().chunks(BLOCK_SZ).enumerate() {
+            device.write_block(slot * BLOCKS_PER_PAGE + i, block);
+        }
+        Some(Self(slot))
+    }
+    /// 把这个位置中保存的页面读入页帧 ppn
+    pub fn read(&self, ppn: PhysPageNum) {
+        let device = SWAP_DEVICE.as_ref().unwrap();
+        for (i, block) in ppn.get_bytes_array().chunks_mut(BLOCK_SZ).enumerate() {
+            device.read_block(self.0 * BLOCKS_PER_PAGE + i, block);
+        }
+    }
+    /// 在交换区中的编号
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl Drop for SwapSlot {
+    fn drop(&mut self) {
+        SWAP_ALLOCATOR.exclusive_access().dealloc(self.0);
+    }
+}
+
+//与 StackFrameAllocator 相同的分配方式
+struct SwapAllocator {
+    current: usize,
+    recycled: Vec<usize>,
+}
+
+impl SwapAllocator {
+    fn alloc(&mut self) -> Option<usize> {
+        if let Some(slot) = self.recycled.pop() {
+            Some(slot)
+        } else if self.current == SWAP_SLOTS {
+            None
+        } else {
+            self.current += 1;
+            Some(self.current - 1)
+        }
+    }
+    fn used(&self) -> usize {
+        self.current - self.recycled.len()
+    }
+    fn dealloc(&mut self, slot: usize) {
+        assert!(
+            slot < self.current && !self.recycled.contains(&slot),
+            "swap slot {} has not been allocated!",
+            slot
+        );
+        self.recycled.push(slot);
+    }
+}
+
+lazy_static! {
+    static ref SWAP_ALLOCATOR: UPSafeCell<SwapAllocator> = unsafe {
+        UPSafeCell::new(SwapAllocator {
+            current: 0,
+            recycled: Vec::new(),
+        })
+    };
+}
+
+/// 是否有可用的交换设备
+pub fn swap_enabled() -> bool {
+    SWAP_DEVICE.is_some()
+}
+
+/// 交换区中已被占用的位置数
+pub fn swap_used() -> usize {
+    SWAP_ALLOCATOR.exclusive_access().used()
+}
+
+/// 探测交换设备并报告交换区的状态
+pub fn init() {
+    if swap_enabled() {
+        println!(
+            "[kernel] swap: {} pages on the virtio block device.",
+            SWAP_SLOTS
+        );
+    } else {
+        println!("[kernel] swap: no block device found, swapping is disabled.");
+    }
+}
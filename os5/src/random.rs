@@ -0,0 +1,164 @@
+//! 内核随机数
+//!
+//! 熵池收集每次陷入内核时 cycle 计数器的抖动，积累一批样本后混入密钥；
+//! 输出由以 ChaCha20 为基础的确定性随机比特生成器（DRBG）产生，每次输出之后立即换用新的密钥，
+//! 即使当前状态泄露，之前的输出也无法被推出。
+
+use crate::sync::UPSafeCell;
+use lazy_static::*;
+use riscv::register::cycle;
+
+/// 启动时收集的抖动样本数
+#[cfg(not(feature = "deterministic"))]
+const BOOT_SAMPLES: usize = 256;
+//积累这么多个样本后混入密钥
+const PENDING_SAMPLES: usize = 8;
+//"expand 32-byte k"
+const CHACHA_CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+//不同用途的 ChaCha20 分组使用不同的 nonce，互不重叠
+const NONCE_OUTPUT: u64 = 0;
+const NONCE_REKEY: u64 = 1;
+const NONCE_RESEED: u64 = 2;
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+//ChaCha20 的分组函数：由 256 位密钥、64 位计数器和 64 位 nonce 生成 64 字节的输出
+fn chacha20_block(key: &[u32; 8], counter: u64, nonce: u64) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&CHACHA_CONSTANTS);
+    state[4..12].copy_from_slice(key);
+    state[12] = counter as u32;
+    state[13] = (counter >> 32) as u32;
+    state[14] = nonce as u32;
+    state[15] = (nonce >> 32) as u32;
+    let mut working = state;
+    for _ in 0..10 {
+        quarter_round(&mut working, 0, 4, 8, 12);
+        quarter_round(&mut working, 1, 5, 9, 13);
+        quarter_round(&mut working, 2, 6, 10, 14);
+        quarter_round(&mut working, 3, 7, 11, 15);
+        quarter_round(&mut working, 0, 5, 10, 15);
+        quarter_round(&mut working, 1, 6, 11, 12);
+        quarter_round(&mut working, 2, 7, 8, 13);
+        quarter_round(&mut working, 3, 4, 9, 14);
+    }
+    for (word, init) in working.iter_mut().zip(state.iter()) {
+        *word = word.wrapping_add(*init);
+    }
+    working
+}
+
+fn first_key(block: &[u32; 16]) -> [u32; 8] {
+    let mut key = [0u32; 8];
+    key.copy_from_slice(&block[..8]);
+    key
+}
+
+/// 熵池与 DRBG 的状态
+struct EntropyPool {
+    key: [u32; 8],
+    //本密钥下已经输出的分组数
+    counter: u64,
+    //尚未混入密钥的样本
+    pending: [u32; PENDING_SAMPLES],
+    pending_len: usize,
+}
+
+impl EntropyPool {
+    fn add_sample(&mut self, sample: u32) {
+        self.pending[self.pending_len] = sample;
+        self.pending_len += 1;
+        if self.pending_len == PENDING_SAMPLES {
+            self.reseed();
+        }
+    }
+    //把样本异或进密钥，再经过一次 ChaCha20 分组函数，使每个样本都影响新密钥的每一位
+    fn reseed(&mut self) {
+        let mut key = self.key;
+        for (word, sample) in key.iter_mut().zip(self.pending.iter()) {
+            *word ^= sample;
+        }
+        self.key = first_key(&chacha20_block(&key, 0, NONCE_RESEED));
+        self.counter = 0;
+        self.pending_len = 0;
+    }
+    fn fill(&mut self, buf: &mut [u8]) {
+        for chunk in buf.chunks_mut(64) {
+            let block = chacha20_block(&self.key, self.counter, NONCE_OUTPUT);
+            self.counter += 1;
+            for (i, byte) in chunk.iter_mut().enumerate() {
+                *byte = (block[i / 4] >> (i % 4 * 8)) as u8;
+            }
+        }
+        //快速密钥擦除：输出之后立即换用新的密钥
+        self.key = first_key(&chacha20_block(&self.key, self.counter, NONCE_REKEY));
+        self.counter = 0;
+    }
+}
+
+lazy_static! {
+    static ref ENTROPY_POOL: UPSafeCell<EntropyPool> = unsafe {
+        UPSafeCell::new(EntropyPool {
+            key: [0; 8],
+            counter: 0,
+            pending: [0; PENDING_SAMPLES],
+            pending_len: 0,
+        })
+    };
+}
+
+//两次读取 cycle 计数器之间空转的次数取决于上一个样本，放大指令执行时间的抖动
+#[cfg(not(feature = "deterministic"))]
+fn jitter_sample(previous: u32) -> u32 {
+    let start = cycle::read();
+    for _ in 0..(previous & 0xf) {
+        core::hint::spin_loop();
+    }
+    (cycle::read().wrapping_sub(start) as u32) ^ (start as u32).rotate_left(16)
+}
+
+/// 启动时收集一批抖动样本作为初始熵
+#[cfg(not(feature = "deterministic"))]
+pub fn init() {
+    let mut pool = ENTROPY_POOL.exclusive_access();
+    let mut sample = 0;
+    for _ in 0..BOOT_SAMPLES {
+        sample = jitter_sample(sample);
+        pool.add_sample(sample);
+    }
+}
+
+/// 确定性模式下不收集抖动，初始熵来自编译时环境变量 SEED，随机数可以复现
+#[cfg(feature = "deterministic")]
+pub fn init() {
+    let seed: u64 = option_env!("SEED")
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or(0);
+    let mut pool = ENTROPY_POOL.exclusive_access();
+    pool.add_sample(seed as u32);
+    pool.add_sample((seed >> 32) as u32);
+    pool.reseed();
+}
+
+/// 每次陷入内核时调用，把此刻的 cycle 计数器作为一个样本加入熵池
+pub fn add_trap_jitter() {
+    if cfg!(not(feature = "deterministic")) {
+        ENTROPY_POOL
+            .exclusive_access()
+            .add_sample(cycle::read() as u32);
+    }
+}
+
+/// 用随机字节填满 buf
+pub fn fill_random(buf: &mut [u8]) {
+    ENTROPY_POOL.exclusive_access().fill(buf);
+}
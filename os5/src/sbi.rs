@@ -4,6 +4,14 @@ const SBI_SET_TIMER: usize = 0;
 const SBI_CONSOLE_PUTCHAR: usize = 1;
 const SBI_CONSOLE_GETCHAR: usize = 2;
 const SBI_SHUTDOWN: usize = 8;
+/// System Reset 扩展（"SRST"），其 0 号功能为 sbi_system_reset
+const SBI_EXT_SRST: usize = 0x53525354;
+
+/// sbi_system_reset 的复位类型
+pub const SRST_TYPE_SHUTDOWN: usize = 0;
+pub const SRST_TYPE_COLD_REBOOT: usize = 1;
+/// sbi_system_reset 的复位原因：没有原因
+const SRST_REASON_NONE: usize = 0;
 
 #[inline(always)]
 fn sbi_call(which: usize, arg0: usize, arg1: usize, arg2: usize) -> usize {
@@ -33,6 +41,11 @@ pub fn console_getchar() -> usize {
     sbi_call(SBI_CONSOLE_GETCHAR, 0, 0, 0)
 }
 
+/// 关机或重启，成功时不会返回；固件不支持 SRST 扩展时返回
+pub fn system_reset(reset_type: usize) {
+    sbi_call(SBI_EXT_SRST, reset_type, SRST_REASON_NONE, 0);
+}
+
 pub fn shutdown() -> ! {
     sbi_call(SBI_SHUTDOWN, 0, 0, 0);
     panic!("It should shutdown!");
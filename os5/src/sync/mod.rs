@@ -1,5 +1,9 @@
 //! Synchronization and interior mutability primitives
 
+mod mutex;
+mod preempt;
 mod up;
 
-pub use up::UPSafeCell;
+pub use mutex::{Mutex, MutexBlocking, MutexSpin};
+pub use preempt::{preempt_disable, preempt_enable, preempt_point, preemptible, PreemptGuard};
+pub use up::{UPRefMut, UPSafeCell};
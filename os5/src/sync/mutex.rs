@@ -0,0 +1,208 @@
+use super::UPSafeCell;
+use crate::errno::Errno;
+use crate::task::TaskControlBlock;
+use crate::task::{block_current_and_run_next, current_task, suspend_current_and_run_next};
+use crate::task::{inherit_priority, restore_priority, signal_pending, wakeup_task};
+use alloc::collections::VecDeque;
+use alloc::sync::{Arc, Weak};
+
+pub trait Mutex: Sync + Send {
+    /// 获取互斥锁，等待时被信号打断返回 EINTR
+    fn lock(&self) -> Result<(), Errno>;
+    /// 释放互斥锁，锁未被持有或持有者不是当前任务时返回 EPERM
+    fn unlock(&self) -> Result<(), Errno>;
+    /// 释放已经退出的任务持有的锁，并把它们从等待队列中移除
+    fn release_exited(&self);
+    /// 若 task 持有该锁，返回它应当从等待者那里继承的优先级
+    fn inherited_priority(&self, _task: &Arc<TaskControlBlock>) -> Option<usize> {
+        None
+    }
+}
+
+//自旋锁只记录持有者的弱引用，不会让已经退出的持有者的任务控制块迟迟得不到回收
+pub struct MutexSpin {
+    owner: UPSafeCell<Option<Weak<TaskControlBlock>>>,
+}
+
+impl MutexSpin {
+    pub fn new() -> Self {
+        Self {
+            owner: unsafe { UPSafeCell::new(None) },
+        }
+    }
+}
+
+impl Mutex for MutexSpin {
+    fn lock(&self) -> Result<(), Errno> {
+        loop {
+            let mut owner = self.owner.exclusive_access();
+            if owner.is_some() {
+                drop(owner);
+                if signal_pending() {
+                    return Err(Errno::EINTR);
+                }
+                suspend_current_and_run_next();
+                continue;
+            } else {
+                *owner = Some(Arc::downgrade(&current_task().unwrap()));
+                return Ok(());
+            }
+        }
+    }
+
+    fn unlock(&self) -> Result<(), Errno> {
+        let task = current_task().unwrap();
+        let mut owner = self.owner.exclusive_access();
+        match owner.as_ref() {
+            Some(weak) if Weak::ptr_eq(weak, &Arc::downgrade(&task)) => {
+                *owner = None;
+                Ok(())
+            }
+            _ => Err(Errno::EPERM),
+        }
+    }
+
+    fn release_exited(&self) {
+        let mut owner = self.owner.exclusive_access();
+        let exited = owner.as_ref().map_or(false, |weak| {
+            weak.upgrade()
+                .map_or(true, |task| task.inner_exclusive_access().is_zombie())
+        });
+        if exited {
+            *owner = None;
+        }
+    }
+}
+
+/// 支持优先级继承的阻塞互斥锁
+//低优先级的持有者被阻塞它的高优先级等待者临时提升到等待者的优先级，
+//避免在 stride 调度下持有者长时间得不到 CPU，使高优先级任务被无限期推迟。
+//解锁时持有者恢复优先级，锁直接交给优先级最高的等待者。
+//持有者或等待者退出时由 release_exited 把它们移除，锁同样交给下一个等待者。
+pub struct MutexBlocking {
+    inner: UPSafeCell<MutexBlockingInner>,
+}
+
+pub struct MutexBlockingInner {
+    owner: Option<Arc<TaskControlBlock>>,
+    wait_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl MutexBlocking {
+    pub fn new() -> Self {
+        Self {
+            inner: unsafe {
+                UPSafeCell::new(MutexBlockingInner {
+                    owner: None,
+                    wait_queue: VecDeque::new(),
+                })
+            },
+        }
+    }
+
+    //去掉已经退出的等待者后，把锁交给优先级最高的等待者，优先级相同时先来先得
+    fn hand_off(mutex_inner: &mut MutexBlockingInner) -> Option<Arc<TaskControlBlock>> {
+        mutex_inner
+            .wait_queue
+            .retain(|waiter| !waiter.inner_exclusive_access().is_zombie());
+        let next = (0..mutex_inner.wait_queue.len())
+            .rev()
+            .max_by_key(|&i| mutex_inner.wait_queue[i].inner_exclusive_access().priority)
+            .and_then(|i| mutex_inner.wait_queue.remove(i));
+        mutex_inner.owner = next.clone();
+        next
+    }
+
+    //新的持有者继承剩余等待者的优先级，随后被唤醒
+    fn wake_owner(&self, next: Option<Arc<TaskControlBlock>>) {
+        if let Some(next) = next {
+            if let Some(prio) = self.inherited_priority(&next) {
+                inherit_priority(&next, prio);
+            }
+            wakeup_task(next);
+        }
+    }
+}
+
+impl Mutex for MutexBlocking {
+    fn lock(&self) -> Result<(), Errno> {
+        let mut waiting = false;
+        loop {
+            let task = current_task().unwrap();
+            let mut mutex_inner = self.inner.exclusive_access();
+            let owner = match mutex_inner.owner.clone() {
+                None => {
+                    mutex_inner.owner = Some(task);
+                    return Ok(());
+                }
+                // 解锁时锁直接交到了等待者手中
+                Some(owner) if waiting && Arc::ptr_eq(&owner, &task) => return Ok(()),
+                Some(owner) => owner,
+            };
+            let queued = mutex_inner.wait_queue.iter().any(|waiter| Arc::ptr_eq(waiter, &task));
+            if signal_pending() {
+                // 被信号唤醒时仍在等待队列中，离开后持有者不必再继承它的优先级
+                if queued {
+                    mutex_inner.wait_queue.retain(|waiter| !Arc::ptr_eq(waiter, &task));
+                    drop(mutex_inner);
+                    restore_priority(&owner);
+                }
+                return Err(Errno::EINTR);
+            }
+            if !queued {
+                mutex_inner.wait_queue.push_back(task.clone());
+            }
+            drop(mutex_inner);
+            let prio = task.inner_exclusive_access().priority;
+            drop(task);
+            inherit_priority(&owner, prio);
+            drop(owner);
+            block_current_and_run_next();
+            waiting = true;
+        }
+    }
+
+    fn unlock(&self) -> Result<(), Errno> {
+        let task = current_task().unwrap();
+        let mut mutex_inner = self.inner.exclusive_access();
+        match mutex_inner.owner.as_ref() {
+            Some(owner) if Arc::ptr_eq(owner, &task) => {}
+            _ => return Err(Errno::EPERM),
+        }
+        let next = Self::hand_off(&mut mutex_inner);
+        drop(mutex_inner);
+        restore_priority(&task);
+        self.wake_owner(next);
+        Ok(())
+    }
+
+    fn release_exited(&self) {
+        let mut mutex_inner = self.inner.exclusive_access();
+        mutex_inner
+            .wait_queue
+            .retain(|waiter| !waiter.inner_exclusive_access().is_zombie());
+        let exited = mutex_inner
+            .owner
+            .as_ref()
+            .map_or(false, |owner| owner.inner_exclusive_access().is_zombie());
+        if !exited {
+            return;
+        }
+        //退出的持有者不会再运行，不必恢复它的优先级
+        let next = Self::hand_off(&mut mutex_inner);
+        drop(mutex_inner);
+        self.wake_owner(next);
+    }
+
+    fn inherited_priority(&self, task: &Arc<TaskControlBlock>) -> Option<usize> {
+        let mutex_inner = self.inner.exclusive_access();
+        match mutex_inner.owner.as_ref() {
+            Some(owner) if Arc::ptr_eq(owner, task) => mutex_inner
+                .wait_queue
+                .iter()
+                .map(|waiter| waiter.inner_exclusive_access().priority)
+                .max(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! 内核抢占
+//!
+//! 内核态下 sstatus.SIE 为 0，执行系统调用时不会被时钟中断打断。耗时较长的内核路径（例如读写大文件）
+//! 在循环中调用 [`preempt_point`]，短暂打开中断让待处理的时钟中断进入内核，时间片用完时在这里切换任务。
+//!
+//! 持有 [`UPSafeCell`](super::UPSafeCell) 的借用期间不能切换任务，否则其他任务再次借用时会 panic，
+//! 这样的临界区由 [`preempt_disable`]/[`preempt_enable`] 包围，嵌套计数不为 0 时抢占点什么也不做。
+//! UPSafeCell 的借用自动处于临界区中，其他临界区用 [`PreemptGuard`] 显式标出。
+
+use crate::task::current_task;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+//嵌套的临界区个数
+static PREEMPT_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// 进入临界区，离开之前不会在抢占点切换任务
+pub fn preempt_disable() {
+    PREEMPT_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 离开由 [`preempt_disable`] 进入的临界区
+pub fn preempt_enable() {
+    let count = PREEMPT_COUNT.fetch_sub(1, Ordering::Relaxed);
+    assert!(count > 0, "preempt_enable without preempt_disable");
+}
+
+/// 当前是否不在任何临界区中
+pub fn preemptible() -> bool {
+    PREEMPT_COUNT.load(Ordering::Relaxed) == 0
+}
+
+/// 在作用域内禁止抢占
+pub struct PreemptGuard;
+
+impl PreemptGuard {
+    pub fn new() -> Self {
+        preempt_disable();
+        Self
+    }
+}
+
+impl Drop for PreemptGuard {
+    fn drop(&mut self) {
+        preempt_enable();
+    }
+}
+
+/// 抢占点：当前任务不在临界区中时短暂打开中断，处理待处理的时钟中断与外部中断，
+/// 当前任务的时间片已经用完时切换到其他任务，之后再从这里继续执行。
+/// 调用者不能持有 easy-fs 中的锁以及用户页面的引用等 UPSafeCell 之外的资源
+pub fn preempt_point() {
+    //内核初始化期间还没有任务，也没有设置好内核态的 trap 入口
+    if !preemptible() || current_task().is_none() {
+        return;
+    }
+    unsafe {
+        riscv::register::sstatus::set_sie();
+        riscv::register::sstatus::clear_sie();
+    }
+}
@@ -1,6 +1,8 @@
 //! Uniprocessor interior mutability primitives
 
+use super::PreemptGuard;
 use core::cell::{RefCell, RefMut};
+use core::ops::{Deref, DerefMut};
 
 /// Wrap a static data structure inside it so that we are
 /// able to access it without any `unsafe`.
@@ -19,13 +21,47 @@ unsafe impl<T> Sync for UPSafeCell<T> {}
 impl<T> UPSafeCell<T> {
     /// User is responsible to guarantee that inner struct is only used in
     /// uniprocessor.
-    pub unsafe fn new(value: T) -> Self {
+    pub const unsafe fn new(value: T) -> Self {
         Self {
             inner: RefCell::new(value),
         }
     }
     /// Panic if the data has been borrowed.
-    pub fn exclusive_access(&self) -> RefMut<'_, T> {
-        self.inner.borrow_mut()
+    /// The kernel can not be preempted while the borrow is alive.
+    pub fn exclusive_access(&self) -> UPRefMut<'_, T> {
+        let preempt = PreemptGuard::new();
+        UPRefMut {
+            inner: self.inner.borrow_mut(),
+            _preempt: preempt,
+        }
+    }
+    /// Return None instead of panicking if the data has been borrowed,
+    /// for code such as the kernel debugger that may run in the middle of a borrow.
+    pub fn try_exclusive_access(&self) -> Option<UPRefMut<'_, T>> {
+        let preempt = PreemptGuard::new();
+        Some(UPRefMut {
+            inner: self.inner.try_borrow_mut().ok()?,
+            _preempt: preempt,
+        })
+    }
+}
+
+/// A mutable borrow of the data in a [`UPSafeCell`].
+pub struct UPRefMut<'a, T> {
+    //fields are dropped in order: release the borrow before allowing preemption
+    inner: RefMut<'a, T>,
+    _preempt: PreemptGuard,
+}
+
+impl<T> Deref for UPRefMut<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for UPRefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
     }
 }
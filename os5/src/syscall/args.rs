@@ -0,0 +1,49 @@
+//! 系统调用参数的类型转换
+
+/// 系统调用最多有 6 个参数，依次取自 a0-a5
+pub const SYSCALL_ARGS: usize = 6;
+
+/// 可以由一个寄存器中的系统调用参数转换得到的类型，转换规则与 `as` 相同
+pub trait FromSyscallArg {
+    fn from_arg(raw: usize) -> Self;
+}
+
+macro_rules! impl_from_syscall_arg {
+    ($($ty:ty),*) => {
+        $(
+            impl FromSyscallArg for $ty {
+                fn from_arg(raw: usize) -> Self {
+                    raw as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_from_syscall_arg!(usize, isize, u32, i32);
+
+impl<T> FromSyscallArg for *const T {
+    fn from_arg(raw: usize) -> Self {
+        raw as *const T
+    }
+}
+
+impl<T> FromSyscallArg for *mut T {
+    fn from_arg(raw: usize) -> Self {
+        raw as *mut T
+    }
+}
+
+/// 一次系统调用的全部参数
+#[derive(Clone, Copy)]
+pub struct SyscallArgs([usize; SYSCALL_ARGS]);
+
+impl SyscallArgs {
+    pub fn new(raw: [usize; SYSCALL_ARGS]) -> Self {
+        Self(raw)
+    }
+    /// 把第 i 个参数转换为 T，T 通常由接收它的 sys_* 函数的参数类型推导出来
+    pub fn get<T: FromSyscallArg>(&self, i: usize) -> T {
+        T::from_arg(self.0[i])
+    }
+}
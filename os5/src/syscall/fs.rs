@@ -1,54 +1,649 @@
 //! File and filesystem-related syscalls
 
-use crate::mm::translated_byte_buffer;
-use crate::sbi::console_getchar;
-use crate::task::{current_user_token, suspend_current_and_run_next};
+use super::process::TimeSpec;
+use crate::config::MAX_FDS;
+use crate::errno::Errno;
+use crate::fs::{
+    block_cache_stats, link_file, make_dir, make_fifo, make_pipe, mount, open_file, sync_all,
+    umount, unlink_file, FdEntry, File, OpenFlags, PollEvents, Stat,
+};
+use crate::mm::{
+    copy_from_user, copy_slice_to_user, copy_to_user, translated_byte_buffer, translated_str,
+    MapPermission, UserBuffer,
+};
+use crate::task::{block_current_and_run_next, current_task, current_user_token, signal_pending};
+use crate::timer::{add_sleeper, get_time_us, remove_sleeper};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-const FD_STDIN: usize = 0;
-const FD_STDOUT: usize = 1;
+/// 块缓存的统计信息，都是启动以来的累计值
+#[repr(C)]
+pub struct BlockCacheStats {
+    /// 在内存中找到块的次数与需要从磁盘读入块的次数
+    pub hits: usize,
+    pub misses: usize,
+    /// 为腾出位置而被淘汰的块数
+    pub evictions: usize,
+    /// 写回磁盘的脏块数
+    pub writebacks: usize,
+}
 
-pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
-    match fd {
-        FD_STDOUT => {
-            let buffers = translated_byte_buffer(current_user_token(), buf, len);
-            for buffer in buffers {
-                print!("{}", core::str::from_utf8(buffer).unwrap());
-            }
-            len as isize
-        }
-        _ => {
-            panic!("Unsupported fd in sys_write!");
+//在当前进程的文件描述符表中查找 fd 对应的一项
+//不能在持有进程 inner 的情况下读写文件：读标准输入时可能让出 CPU
+fn fd_entry(fd: usize) -> Result<FdEntry, Errno> {
+    let process = current_task().unwrap().process.clone();
+    let inner = process.inner_exclusive_access();
+    inner
+        .fd_table
+        .get(fd)
+        .and_then(|entry| entry.clone())
+        .ok_or(Errno::EBADF)
+}
+
+//在当前进程的文件描述符表中查找 fd 对应的文件
+fn fd_file(fd: usize) -> Result<Arc<dyn File>, Errno> {
+    fd_entry(fd).map(|entry| entry.file)
+}
+
+/// openat 的 dirfd 取这个值时相对于当前工作目录解析路径
+pub const AT_FDCWD: isize = -100;
+
+/// 功能：打开文件系统中的文件或目录，分配一个文件描述符指向它。目录只能以只读方式打开，用于 getdents64；
+///      命名管道只能以只读或只写方式打开，另一端还没有被打开过时阻塞，NONBLOCK 时不阻塞。
+/// 参数：dirfd 必须为 AT_FDCWD，目前没有当前工作目录，路径都从根目录开始解析，经过挂载点时进入挂载的文件系统；
+///      path 为文件的路径；flags 为 RDONLY、WRONLY 或 RDWR，可以加上 CREATE（文件不存在时创建）与 TRUNC（截断为空），
+///      以及记录在文件描述符上的状态标志 NONBLOCK（读写不阻塞）与 APPEND（每次写入前移动到文件末尾）；mode 暂不使用。
+/// 返回值：成功返回新的文件描述符；文件不存在时返回 -ENOENT；flags 不合法或者以读写方式打开命名管道时返回 -EINVAL；
+///      dirfd 不是 AT_FDCWD 时返回 -EBADF；文件名过长时返回 -ENAMETOOLONG；path 不可读时返回 -EFAULT；
+///      路径中间某一级不是目录时返回 -ENOTDIR；以写方式打开目录时返回 -EISDIR；打开的文件过多时返回 -EMFILE；
+///      以 NONBLOCK 只写方式打开没有读者的命名管道时返回 -ENXIO。
+/// syscall ID：56
+pub fn sys_openat(dirfd: isize, path: *const u8, flags: u32, _mode: u32) -> isize {
+    if dirfd != AT_FDCWD {
+        return Errno::EBADF.neg();
+    }
+    let flags = match OpenFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return Errno::EINVAL.neg(),
+    };
+    let path = match translated_str(current_user_token(), path) {
+        Ok(path) => path,
+        Err(err) => return err.neg(),
+    };
+    let file = match open_file(path.as_str(), flags) {
+        Ok(file) => file,
+        Err(err) => return err.neg(),
+    };
+    let process = current_task().unwrap().process.clone();
+    let mut inner = process.inner_exclusive_access();
+    match inner.alloc_fd() {
+        Ok(fd) => {
+            inner.fd_table[fd] = Some(FdEntry::new(file, flags));
+            fd as isize
         }
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：创建一个空目录。
+/// 参数：dirfd 必须为 AT_FDCWD；path 为新目录的路径，路径中的 . 与 .. 表示当前目录与上一级目录；mode 暂不使用。
+/// 返回值：成功返回 0；path 已经存在时返回 -EEXIST；上一级目录不存在时返回 -ENOENT；
+///      路径中间某一级不是目录时返回 -ENOTDIR；dirfd 不是 AT_FDCWD 时返回 -EBADF；
+///      名字过长时返回 -ENAMETOOLONG；路径不可读时返回 -EFAULT。
+/// syscall ID：34
+pub fn sys_mkdirat(dirfd: isize, path: *const u8, _mode: u32) -> isize {
+    if dirfd != AT_FDCWD {
+        return Errno::EBADF.neg();
+    }
+    match translated_str(current_user_token(), path).and_then(|path| make_dir(path.as_str())) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// mknodat 的 mode 中表示文件类型的位，以及命名管道的类型
+const S_IFMT: u32 = 0o170000;
+const S_IFIFO: u32 = 0o010000;
+
+/// 功能：创建特殊文件，目前只支持命名管道。没有亲缘关系的进程可以按路径打开同一个命名管道来通信，
+///      打开读端时阻塞到有进程打开写端，反之亦然。
+/// 参数：dirfd 必须为 AT_FDCWD；path 为新文件的路径；mode 中的文件类型必须为 S_IFIFO，权限位暂不使用；dev 暂不使用。
+/// 返回值：成功返回 0；文件类型不是 S_IFIFO 时返回 -EINVAL；path 已经存在时返回 -EEXIST；
+///      上一级目录不存在时返回 -ENOENT；路径中间某一级不是目录时返回 -ENOTDIR；所在的文件系统不支持时返回 -EPERM；
+///      dirfd 不是 AT_FDCWD 时返回 -EBADF；名字过长时返回 -ENAMETOOLONG；路径不可读时返回 -EFAULT。
+/// syscall ID：33
+pub fn sys_mknodat(dirfd: isize, path: *const u8, mode: u32, _dev: usize) -> isize {
+    if dirfd != AT_FDCWD {
+        return Errno::EBADF.neg();
+    }
+    if mode & S_IFMT != S_IFIFO {
+        return Errno::EINVAL.neg();
+    }
+    match translated_str(current_user_token(), path).and_then(|path| make_fifo(path.as_str())) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：读取打开的目录中的目录项，每条记录的布局与 Linux 的 linux_dirent64 相同：
+///      u64 的 inode 编号、i64 的下一条记录的位置、u16 的记录长度、u8 的类型（命名管道为 1，目录为 4，普通文件为 8），
+///      之后是以 0 结尾的名字，整条记录按 8 字节对齐。
+/// 参数：fd 为以只读方式打开的目录；buf 为缓冲区，len 为缓冲区的长度。
+/// 返回值：返回填入的字节数，目录已经读完时返回 0；fd 未打开时返回 -EBADF；fd 不是目录时返回 -ENOTDIR；
+///      缓冲区连一条记录也放不下时返回 -EINVAL；缓冲区不可写时返回 -EFAULT。
+/// syscall ID：61
+pub fn sys_getdents64(fd: usize, buf: *const u8, len: usize) -> isize {
+    let file = match fd_file(fd) {
+        Ok(file) => file,
+        Err(err) => return err.neg(),
+    };
+    let token = current_user_token();
+    if let Err(err) = translated_byte_buffer(token, buf, len, MapPermission::W) {
+        return err.neg();
+    }
+    match file.getdents(UserBuffer::new(token, buf, len)) {
+        Ok(n) => n as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：为文件创建一个新的硬链接，两个路径指向同一个 inode。
+/// 参数：olddirfd、newdirfd 必须为 AT_FDCWD；oldpath 为已有文件的路径，newpath 为新的路径，可以在另一个目录中；
+///      flags 必须为 0。
+/// 返回值：成功返回 0；oldpath 不存在时返回 -ENOENT；newpath 已经存在时返回 -EEXIST；oldpath 是目录时返回 -EPERM；
+///      两个路径不在同一个文件系统中时返回 -EXDEV；路径中间某一级不是目录时返回 -ENOTDIR；dirfd 不是 AT_FDCWD 时返回 -EBADF；flags 不为 0 时返回 -EINVAL；文件名过长时返回 -ENAMETOOLONG；
+///      路径不可读时返回 -EFAULT。
+/// syscall ID：37
+pub fn sys_linkat(
+    olddirfd: isize,
+    oldpath: *const u8,
+    newdirfd: isize,
+    newpath: *const u8,
+    flags: u32,
+) -> isize {
+    if olddirfd != AT_FDCWD || newdirfd != AT_FDCWD {
+        return Errno::EBADF.neg();
+    }
+    if flags != 0 {
+        return Errno::EINVAL.neg();
+    }
+    let token = current_user_token();
+    let result = translated_str(token, oldpath).and_then(|oldpath| {
+        let newpath = translated_str(token, newpath)?;
+        link_file(oldpath.as_str(), newpath.as_str())
+    });
+    match result {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：删除文件的一个硬链接。删除最后一个链接后文件不再能按路径打开，
+///      但已经打开的文件描述符仍然可以读写，最后一个文件描述符关闭时才回收文件占用的空间。
+/// 参数：dirfd 必须为 AT_FDCWD；path 为要删除的路径；flags 必须为 0。
+/// 返回值：成功返回 0；path 不存在时返回 -ENOENT；path 是目录时返回 -EISDIR；
+///      路径中间某一级不是目录时返回 -ENOTDIR；dirfd 不是 AT_FDCWD 时返回 -EBADF；
+///      flags 不为 0 时返回 -EINVAL；文件名过长时返回 -ENAMETOOLONG；路径不可读时返回 -EFAULT。
+/// syscall ID：35
+pub fn sys_unlinkat(dirfd: isize, path: *const u8, flags: u32) -> isize {
+    if dirfd != AT_FDCWD {
+        return Errno::EBADF.neg();
+    }
+    if flags != 0 {
+        return Errno::EINVAL.neg();
+    }
+    match translated_str(current_user_token(), path).and_then(|path| unlink_file(path.as_str())) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：把一个文件系统挂载到目录 target 上，之后 target 之下的路径都由这个文件系统解析。
+/// 参数：source 暂不使用；target 为已经存在的目录；fstype 为文件系统的类型，目前支持 easyfs、initramfs、devfs 与 procfs；flags 必须为 0；data 暂不使用。
+/// 返回值：成功返回 0；不认识的 fstype 以及没有块设备时挂载 easyfs 返回 -ENODEV；target 不存在时返回 -ENOENT；target 不是目录时返回 -ENOTDIR；
+///      target 上已经挂载了文件系统时返回 -EBUSY；flags 不为 0 时返回 -EINVAL；字符串不可读时返回 -EFAULT。
+/// syscall ID：40
+pub fn sys_mount(
+    _source: *const u8,
+    target: *const u8,
+    fstype: *const u8,
+    flags: usize,
+    _data: *const u8,
+) -> isize {
+    if flags != 0 {
+        return Errno::EINVAL.neg();
+    }
+    let token = current_user_token();
+    let result = translated_str(token, target).and_then(|target| {
+        let fstype = translated_str(token, fstype)?;
+        mount(fstype.as_str(), target.as_str())
+    });
+    match result {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：卸载目录 target 上挂载的文件系统，已经打开的文件仍然可以使用。
+/// 参数：target 为挂载点；flags 必须为 0。
+/// 返回值：成功返回 0；target 不是挂载点或 flags 不为 0 时返回 -EINVAL；
+///      target 是根目录或者其下还有其他挂载点时返回 -EBUSY；target 不可读时返回 -EFAULT。
+/// syscall ID：39
+pub fn sys_umount2(target: *const u8, flags: usize) -> isize {
+    if flags != 0 {
+        return Errno::EINVAL.neg();
+    }
+    match translated_str(current_user_token(), target).and_then(|target| umount(target.as_str())) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：将内存中缓冲区中的数据写入文件。
+/// 参数：fd 表示待写入文件的文件描述符；buf 表示内存中缓冲区的起始地址，可以跨越多个页面；len 表示缓冲区的长度。
+///      fd 带有 APPEND 时先移动到文件末尾再写入；带有 NONBLOCK 时只写入不必等待就能写入的部分。
+/// 返回值：返回成功写入的长度；fd 未打开或不可写时返回 -EBADF，缓冲区不可读时返回 -EFAULT；
+///      带有 NONBLOCK 而一个字节也写不进去时返回 -EAGAIN。
+/// syscall ID：64
+pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
+    let entry = match fd_entry(fd) {
+        Ok(entry) if entry.file.writable() => entry,
+        Ok(_) => return Errno::EBADF.neg(),
+        Err(err) => return err.neg(),
+    };
+    let token = current_user_token();
+    //先检查缓冲区，避免阻塞之后才发现缓冲区不合法
+    if let Err(err) = translated_byte_buffer(token, buf, len, MapPermission::R) {
+        return err.neg();
+    }
+    //管道等不支持随机访问的文件没有末尾可言，忽略移动失败
+    if entry.flags.contains(OpenFlags::APPEND) {
+        let _ = entry.file.seek(0, 2);
+    }
+    let buf = UserBuffer::new(token, buf, len);
+    let result = if entry.flags.contains(OpenFlags::NONBLOCK) {
+        entry.file.write_nonblock(buf)
+    } else {
+        entry.file.write(buf)
+    };
+    match result {
+        Ok(n) => n as isize,
+        Err(err) => err.neg(),
     }
 }
 
 /// 功能：从文件中读取一段内容到缓冲区。
 /// 参数：fd 是待读取文件的文件描述符，切片 buffer 则给出缓冲区。
-/// 返回值：如果出现了错误则返回 -1，否则返回实际读到的字节数。
+/// 返回值：返回实际读到的字节数；fd 未打开或不可读时返回 -EBADF，缓冲区不可写时返回 -EFAULT；
+///      fd 带有 NONBLOCK 而暂时没有数据可读时返回 -EAGAIN。
 /// syscall ID：63
 pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
-    match fd {
-        FD_STDIN => {
-            assert_eq!(len, 1, "Only support len = 1 in sys_read!");
-            let mut c: usize;
-            loop {
-                c = console_getchar();
-                if c == 0 {
-                    suspend_current_and_run_next();
-                    continue;
-                } else {
-                    break;
+    let entry = match fd_entry(fd) {
+        Ok(entry) if entry.file.readable() => entry,
+        Ok(_) => return Errno::EBADF.neg(),
+        Err(err) => return err.neg(),
+    };
+    let token = current_user_token();
+    //先检查缓冲区，避免读走数据之后才发现无处存放
+    if let Err(err) = translated_byte_buffer(token, buf, len, MapPermission::W) {
+        return err.neg();
+    }
+    let buf = UserBuffer::new(token, buf, len);
+    let result = if entry.flags.contains(OpenFlags::NONBLOCK) {
+        entry.file.read_nonblock(buf)
+    } else {
+        entry.file.read(buf)
+    };
+    match result {
+        Ok(n) => n as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：移动文件的读写位置。
+/// 参数：fd 为文件描述符；whence 为 0 时从文件开头、为 1 时从当前位置、为 2 时从文件末尾开始计算，加上 offset。
+/// 返回值：新的读写位置；fd 未打开时返回 -EBADF，文件不支持随机访问（如标准输入输出）时返回 -ESPIPE，
+///        whence 不合法或新的位置为负数时返回 -EINVAL。
+/// syscall ID：62
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    match fd_file(fd).and_then(|file| file.seek(offset, whence)) {
+        Ok(pos) => pos as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取文件的元数据，包括 inode 编号、类型、硬链接数与大小。
+/// 参数：fd 为文件描述符；st 指向保存结果的 Stat。
+/// 返回值：成功返回 0；fd 未打开时返回 -EBADF，st 不可写时返回 -EFAULT。
+/// syscall ID：80
+pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
+    let stat = match fd_file(fd) {
+        Ok(file) => file.stat(),
+        Err(err) => return err.neg(),
+    };
+    match copy_to_user(current_user_token(), st, &stat) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：把打开的文件的数据、inode 以及磁盘空间的分配情况写回磁盘，返回之后这些修改不会因为断电而丢失。
+/// 参数：fd 为文件描述符。
+/// 返回值：成功返回 0；fd 未打开时返回 -EBADF；fd 不是磁盘上的文件（如管道、标准输入输出与设备）时返回 -EINVAL。
+/// syscall ID：82
+pub fn sys_fsync(fd: usize) -> isize {
+    match fd_file(fd).and_then(|file| file.sync()) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：把文件系统中所有修改过的数据写回磁盘。目前所有磁盘上的文件系统共用一个块缓存，
+///      写回的是所有挂载的文件系统，而不只是 fd 所在的那一个。
+/// 参数：fd 为文件系统中任意一个打开的文件。
+/// 返回值：成功返回 0；fd 未打开时返回 -EBADF。
+/// syscall ID：267
+pub fn sys_syncfs(fd: usize) -> isize {
+    if let Err(err) = fd_file(fd) {
+        return err.neg();
+    }
+    sync_all();
+    0
+}
+
+/// 功能：关闭文件描述符。
+/// 参数：fd 为要关闭的文件描述符。文件在指向它的最后一个文件描述符关闭时才真正关闭，
+///      例如管道的所有写端都关闭后读者读到文件末尾。
+/// 返回值：成功返回 0；fd 未打开时返回 -EBADF。
+/// syscall ID：57
+pub fn sys_close(fd: usize) -> isize {
+    let process = current_task().unwrap().process.clone();
+    let mut inner = process.inner_exclusive_access();
+    let entry = match inner.fd_table.get_mut(fd).and_then(|entry| entry.take()) {
+        Some(entry) => entry,
+        None => return Errno::EBADF.neg(),
+    };
+    //关闭管道时要唤醒等待者，先释放对进程控制块的借用
+    drop(inner);
+    drop(entry);
+    0
+}
+
+/// 功能：创建一个管道。
+/// 参数：pipe 指向长度为 2 的数组，成功时 pipe[0] 为读端的文件描述符，pipe[1] 为写端的文件描述符。
+///      缓冲区为空时读者阻塞，写端全部关闭后读到文件末尾（返回 0）；缓冲区满时写者阻塞，
+///      读端全部关闭后写者收到 SIGPIPE，写入返回 -EPIPE。
+/// 返回值：成功返回 0；pipe 不可写时返回 -EFAULT，打开的文件数达到上限时返回 -EMFILE，此时都不会分配文件描述符。
+/// syscall ID：59
+pub fn sys_pipe(pipe: *mut usize) -> isize {
+    let token = current_user_token();
+    if let Err(err) = translated_byte_buffer(
+        token,
+        pipe as *const u8,
+        2 * size_of::<usize>(),
+        MapPermission::W,
+    ) {
+        return err.neg();
+    }
+    let (read_end, write_end) = make_pipe();
+    let process = current_task().unwrap().process.clone();
+    let mut inner = process.inner_exclusive_access();
+    let rfd = match inner.alloc_fd() {
+        Ok(fd) => fd,
+        Err(err) => return err.neg(),
+    };
+    inner.fd_table[rfd] = Some(FdEntry::new(read_end, OpenFlags::RDONLY));
+    let wfd = match inner.alloc_fd() {
+        Ok(fd) => fd,
+        Err(err) => {
+            let read_end = inner.fd_table[rfd].take();
+            drop(inner);
+            drop(read_end);
+            return err.neg();
+        }
+    };
+    inner.fd_table[wfd] = Some(FdEntry::new(write_end, OpenFlags::WRONLY));
+    drop(inner);
+    let fds = [rfd, wfd];
+    match copy_slice_to_user(token, pipe, &fds) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：复制文件描述符。
+/// 参数：fd 为要复制的文件描述符。
+/// 返回值：成功返回最小的空闲文件描述符，它与 fd 指向同一个文件；fd 未打开时返回 -EBADF，
+///        打开的文件数达到上限时返回 -EMFILE。
+/// syscall ID：24
+pub fn sys_dup(fd: usize) -> isize {
+    let process = current_task().unwrap().process.clone();
+    let mut inner = process.inner_exclusive_access();
+    let entry = match inner.fd_table.get(fd) {
+        Some(Some(entry)) => entry.clone(),
+        _ => return Errno::EBADF.neg(),
+    };
+    match inner.alloc_fd() {
+        Ok(new_fd) => {
+            inner.fd_table[new_fd] = Some(entry);
+            new_fd as isize
+        }
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：把文件描述符 old_fd 复制到 new_fd，用于重定向标准输入输出。
+/// 参数：old_fd 为要复制的文件描述符；new_fd 为目标文件描述符，它原来指向的文件先被关闭。
+/// 返回值：成功返回 new_fd；old_fd 未打开或 new_fd 不小于 MAX_FDS 时返回 -EBADF。
+///        old_fd 与 new_fd 相同时什么也不做。
+/// syscall ID：23
+pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
+    if new_fd >= MAX_FDS {
+        return Errno::EBADF.neg();
+    }
+    let process = current_task().unwrap().process.clone();
+    let mut inner = process.inner_exclusive_access();
+    let entry = match inner.fd_table.get(old_fd) {
+        Some(Some(entry)) => entry.clone(),
+        _ => return Errno::EBADF.neg(),
+    };
+    if old_fd == new_fd {
+        return new_fd as isize;
+    }
+    if new_fd >= inner.fd_table.len() {
+        inner.fd_table.resize(new_fd + 1, None);
+    }
+    //原来的文件可能是管道的最后一个写端，关闭时要唤醒读者，先释放对进程控制块的借用
+    let replaced = inner.fd_table[new_fd].replace(entry);
+    drop(inner);
+    drop(replaced);
+    new_fd as isize
+}
+
+/// fcntl 的命令：复制文件描述符、读取与设置状态标志
+pub const F_DUPFD: usize = 0;
+pub const F_GETFL: usize = 3;
+pub const F_SETFL: usize = 4;
+
+/// 功能：操作文件描述符。状态标志记录在文件描述符上，dup 与 fork 得到的文件描述符各自有一份拷贝。
+/// 参数：fd 为文件描述符；cmd 为 F_DUPFD 时把 fd 复制到不小于 arg 的最小空闲文件描述符上，
+///      为 F_GETFL 时读取 fd 的访问方式与状态标志，为 F_SETFL 时把状态标志 NONBLOCK 与 APPEND 设为 arg 中的值，
+///      arg 中的其他标志被忽略。
+/// 返回值：F_DUPFD 返回新的文件描述符，F_GETFL 返回标志，F_SETFL 返回 0；fd 未打开时返回 -EBADF；
+///      cmd 不支持或者 F_DUPFD 的 arg 不小于 MAX_FDS 时返回 -EINVAL；打开的文件数达到上限时返回 -EMFILE。
+/// syscall ID：25
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    let process = current_task().unwrap().process.clone();
+    let mut inner = process.inner_exclusive_access();
+    let entry = match inner.fd_table.get_mut(fd) {
+        Some(Some(entry)) => entry,
+        _ => return Errno::EBADF.neg(),
+    };
+    match cmd {
+        F_DUPFD => {
+            if arg >= MAX_FDS {
+                return Errno::EINVAL.neg();
+            }
+            let entry = entry.clone();
+            match inner.alloc_fd_from(arg) {
+                Ok(new_fd) => {
+                    inner.fd_table[new_fd] = Some(entry);
+                    new_fd as isize
                 }
+                Err(err) => err.neg(),
             }
-            let ch = c as u8;
-            let mut buffers = translated_byte_buffer(current_user_token(), buf, len);
-            unsafe {
-                buffers[0].as_mut_ptr().write_volatile(ch);
+        }
+        F_GETFL => entry.flags.bits() as isize,
+        F_SETFL => {
+            let status = OpenFlags::status();
+            let flags = OpenFlags::from_bits_truncate(arg as u32) & status;
+            entry.flags = (entry.flags - status) | flags;
+            0
+        }
+        _ => Errno::EINVAL.neg(),
+    }
+}
+
+/// 功能：对设备文件进行控制操作。目前只有终端（标准输入输出与 /dev/console）支持，
+///      TCGETS 把终端属性写入 arg 指向的 Termios，TCSETS 从中读出新的属性：
+///      c_lflag 中有 ICANON 时为按行编辑的规范模式，有 ECHO 时回显输入，默认两者都没有。
+/// 参数：fd 为打开的文件描述符；request 为请求；arg 为请求的参数。
+/// 返回值：成功返回 0；fd 未打开时返回 -EBADF；文件不是终端或者不认识的请求返回 -ENOTTY；arg 不可访问时返回 -EFAULT。
+/// syscall ID：29
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    match fd_file(fd).and_then(|file| file.ioctl(request, arg)) {
+        Ok(ret) => ret,
+        Err(err) => err.neg(),
+    }
+}
+
+/// poll 等待的一项，与 Linux 的 struct pollfd 布局一致
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct PollFd {
+    pub fd: i32,
+    /// 关心的事件
+    pub events: i16,
+    /// 就绪的事件，由内核填入
+    pub revents: i16,
+}
+
+//检查一遍所有文件描述符，填入 revents，返回 revents 不为 0 的项数。
+//ERR、HUP 与 NVAL 不论是否关心都会报告
+fn poll_fds(polls: &mut [PollFd]) -> usize {
+    let always = PollEvents::ERR | PollEvents::HUP | PollEvents::NVAL;
+    let mut ready = 0;
+    for poll in polls.iter_mut() {
+        let revents = if poll.fd < 0 {
+            PollEvents::empty()
+        } else {
+            let events = PollEvents::from_bits_truncate(poll.events as u16) | always;
+            match fd_file(poll.fd as usize) {
+                Ok(file) => file.poll() & events,
+                Err(_) => PollEvents::NVAL,
             }
-            1
+        };
+        poll.revents = revents.bits() as i16;
+        if !revents.is_empty() {
+            ready += 1;
         }
-        _ => {
-            panic!("Unsupported fd in sys_read!");
+    }
+    ready
+}
+
+fn ppoll(fds: *mut PollFd, nfds: usize, timeout: *const TimeSpec) -> Result<usize, Errno> {
+    if nfds > MAX_FDS {
+        return Err(Errno::EINVAL);
+    }
+    let token = current_user_token();
+    let mut polls = Vec::with_capacity(nfds);
+    for i in 0..nfds {
+        polls.push(copy_from_user(token, fds.wrapping_add(i))?);
+    }
+    //超时时间大到截止时刻溢出时视为没有截止时刻
+    let deadline_us = if timeout.is_null() {
+        None
+    } else {
+        let timeout: TimeSpec = copy_from_user(token, timeout)?;
+        if timeout.tv_nsec >= 1_000_000_000 {
+            return Err(Errno::EINVAL);
+        }
+        timeout
+            .tv_sec
+            .checked_mul(1_000_000)
+            .and_then(|us| us.checked_add(timeout.tv_nsec / 1000))
+            .and_then(|us| us.checked_add(get_time_us()))
+    };
+    //没有文件就绪时在各个文件的等待队列上登记，有截止时刻时同时加入睡眠队列，
+    //文件的状态变化、超时或者信号都会唤醒当前任务，醒来后重新检查
+    let ready = loop {
+        let ready = poll_fds(&mut polls);
+        if ready > 0 || deadline_us.map_or(false, |deadline_us| get_time_us() >= deadline_us) {
+            break ready;
+        }
+        if signal_pending() {
+            return Err(Errno::EINTR);
+        }
+        let files: Vec<Arc<dyn File>> = polls
+            .iter()
+            .filter(|poll| poll.fd >= 0)
+            .filter_map(|poll| fd_file(poll.fd as usize).ok())
+            .collect();
+        let task = current_task().unwrap();
+        for file in files.iter() {
+            file.poll_register(&task);
+        }
+        if let Some(deadline_us) = deadline_us {
+            add_sleeper(deadline_us, task.clone());
+        }
+        drop(task);
+        block_current_and_run_next();
+        let task = current_task().unwrap();
+        for file in files.iter() {
+            file.poll_unregister(&task);
         }
+        remove_sleeper(&task);
+    };
+    copy_slice_to_user(token, fds, &polls)?;
+    Ok(ready)
+}
+
+/// 功能：等待一组文件描述符中的任意一个就绪，用于同时等待控制台与多个管道。
+///      控制台有输入时可读；管道的读端在缓冲区不空时可读，写端在缓冲区不满时可写；其他文件总是可读写。
+/// 参数：fds 指向 nfds 项 PollFd，events 为关心的事件 POLLIN 与 POLLOUT，revents 由内核填入就绪的事件，
+///      POLLERR（管道的读端都已关闭）、POLLHUP（管道的写端都已关闭）与 POLLNVAL（fd 没有打开）总是会报告，
+///      fd 为负数的项被忽略；timeout 指向最长的等待时间，为 NULL 时一直等到有文件就绪，指向 0 时只检查一次；
+///      timeout 大到截止时刻溢出时与 NULL 相同；sigmask 暂不使用。
+/// 返回值：成功返回 revents 不为 0 的项数，超时返回 0；nfds 大于 MAX_FDS 或者 timeout 的纳秒数不小于 10^9 时返回 -EINVAL；
+///      fds 或 timeout 不可访问时返回 -EFAULT；等待时被信号打断返回 -EINTR。
+/// syscall ID：73
+pub fn sys_ppoll(
+    fds: *mut PollFd,
+    nfds: usize,
+    timeout: *const TimeSpec,
+    _sigmask: usize,
+) -> isize {
+    match ppoll(fds, nfds, timeout) {
+        Ok(ready) => ready as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取文件系统块缓存的命中、缺失、淘汰与写回次数。
+/// 参数：stats 指向保存结果的 BlockCacheStats。
+/// 返回值：成功返回 0；stats 不可写时返回 -EFAULT。
+/// syscall ID：417
+pub fn sys_block_cache_stats(stats: *mut BlockCacheStats) -> isize {
+    let cache_stats = block_cache_stats();
+    let result = BlockCacheStats {
+        hits: cache_stats.hits,
+        misses: cache_stats.misses,
+        evictions: cache_stats.evictions,
+        writebacks: cache_stats.writebacks,
+    };
+    match copy_to_user(current_user_token(), stats, &result) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
     }
-}
\ No newline at end of file
+}
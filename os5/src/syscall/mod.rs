@@ -5,47 +5,225 @@
 // 为了清楚起见，每个系统调用都是作为自己的函数实现的，名为`sys_`，然后是系统调用的名称。
 // 您可以在子模块中找到类似的函数，您还应该以这种方式实现系统调用。
 
+const SYSCALL_DUP2: usize = 23;
+const SYSCALL_DUP: usize = 24;
+const SYSCALL_FCNTL: usize = 25;
+const SYSCALL_IOCTL: usize = 29;
+const SYSCALL_MKNODAT: usize = 33;
+const SYSCALL_MKDIRAT: usize = 34;
+const SYSCALL_UNLINKAT: usize = 35;
+const SYSCALL_LINKAT: usize = 37;
+const SYSCALL_UMOUNT2: usize = 39;
+const SYSCALL_MOUNT: usize = 40;
+const SYSCALL_OPENAT: usize = 56;
+const SYSCALL_CLOSE: usize = 57;
+const SYSCALL_PIPE: usize = 59;
+const SYSCALL_GETDENTS64: usize = 61;
+const SYSCALL_LSEEK: usize = 62;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_PPOLL: usize = 73;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_FSYNC: usize = 82;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_SET_TID_ADDRESS: usize = 96;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_CLOCK_GETTIME: usize = 113;
+const SYSCALL_SYSLOG: usize = 116;
+const SYSCALL_PTRACE: usize = 117;
+const SYSCALL_KILL: usize = 129;
+const SYSCALL_SIGACTION: usize = 134;
+const SYSCALL_SIGPROCMASK: usize = 135;
+const SYSCALL_SIGRETURN: usize = 139;
+const SYSCALL_TIMES: usize = 153;
+const SYSCALL_SETPGID: usize = 154;
+const SYSCALL_GETPGID: usize = 155;
+const SYSCALL_GETSID: usize = 156;
+const SYSCALL_SETSID: usize = 157;
+const SYSCALL_UNAME: usize = 160;
+const SYSCALL_SCHED_SETSCHEDULER: usize = 119;
+const SYSCALL_SCHED_GETSCHEDULER: usize = 120;
+const SYSCALL_SCHED_SETAFFINITY: usize = 122;
+const SYSCALL_SCHED_GETAFFINITY: usize = 123;
 const SYSCALL_YIELD: usize = 124;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_PRCTL: usize = 167;
+const SYSCALL_SYNCFS: usize = 267;
+const SYSCALL_SECCOMP: usize = 277;
+const SYSCALL_GETRANDOM: usize = 278;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
-const SYSCALL_FORK: usize = 220;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_GETTID: usize = 178;
+const SYSCALL_SYSINFO: usize = 179;
+const SYSCALL_SHMGET: usize = 194;
+const SYSCALL_SHMCTL: usize = 195;
+const SYSCALL_SHMAT: usize = 196;
+const SYSCALL_SHMDT: usize = 197;
+const SYSCALL_CLONE: usize = 220;
 const SYSCALL_EXEC: usize = 221;
-const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_WAIT4: usize = 260;
 const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_SBRK: usize = 214;
 const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_MREMAP: usize = 216;
 const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
 const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_REBOOT: usize = 142;
 const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_THREAD_CREATE: usize = 460;
+const SYSCALL_WAITTID: usize = 462;
+const SYSCALL_MUTEX_CREATE: usize = 463;
+const SYSCALL_MUTEX_LOCK: usize = 464;
+const SYSCALL_MUTEX_UNLOCK: usize = 466;
+const SYSCALL_SWITCH_TRACE: usize = 411;
+const SYSCALL_SCHED_STATS: usize = 412;
+const SYSCALL_PS: usize = 413;
+const SYSCALL_SWAP_STATS: usize = 414;
+const SYSCALL_SLAB_STATS: usize = 415;
+const SYSCALL_MEMINFO: usize = 416;
+const SYSCALL_BLOCK_CACHE_STATS: usize = 417;
 
+mod args;
 mod fs;
 mod process;
+mod shm;
+mod sync;
+mod thread;
 
+pub use args::SYSCALL_ARGS;
+use args::SyscallArgs;
 use fs::*;
 use process::*;
+use shm::*;
+use sync::*;
+use thread::*;
+use crate::errno::Errno;
 use crate::task;
 
 /// 使用`syscall_id`和其他参数处理syscall异常
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+pub fn syscall(syscall_id: usize, args: [usize; SYSCALL_ARGS]) -> isize {
     task::update_syscall_times(syscall_id);
+    let args = SyscallArgs::new(args);
+    //exit 与 sigreturn 不受过滤器限制，受限的进程总能退出，也总能从信号处理函数返回
+    if syscall_id != SYSCALL_EXIT && syscall_id != SYSCALL_SIGRETURN {
+        match task::seccomp_action(syscall_id) {
+            task::SeccompAction::Allow => {}
+            task::SeccompAction::Errno => return Errno::EPERM.neg(),
+            task::SeccompAction::Kill => {
+                task::seccomp_kill(syscall_id);
+                return Errno::EPERM.neg();
+            }
+        }
+    }
 
     match syscall_id {
-        SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
-        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
-        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_DUP2 => sys_dup2(args.get(0), args.get(1)),
+        SYSCALL_DUP => sys_dup(args.get(0)),
+        SYSCALL_FCNTL => sys_fcntl(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_IOCTL => sys_ioctl(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_MKNODAT => sys_mknodat(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_MKDIRAT => sys_mkdirat(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_UNLINKAT => sys_unlinkat(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_LINKAT => sys_linkat(
+            args.get(0),
+            args.get(1),
+            args.get(2),
+            args.get(3),
+            args.get(4),
+        ),
+        SYSCALL_UMOUNT2 => sys_umount2(args.get(0), args.get(1)),
+        SYSCALL_MOUNT => sys_mount(
+            args.get(0),
+            args.get(1),
+            args.get(2),
+            args.get(3),
+            args.get(4),
+        ),
+        SYSCALL_OPENAT => sys_openat(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_CLOSE => sys_close(args.get(0)),
+        SYSCALL_PIPE => sys_pipe(args.get(0)),
+        SYSCALL_GETDENTS64 => sys_getdents64(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_LSEEK => sys_lseek(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_READ => sys_read(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_WRITE => sys_write(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_PPOLL => sys_ppoll(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_FSTAT => sys_fstat(args.get(0), args.get(1)),
+        SYSCALL_FSYNC => sys_fsync(args.get(0)),
+        SYSCALL_EXIT => sys_exit(args.get(0)),
+        SYSCALL_SET_TID_ADDRESS => sys_set_tid_address(args.get(0)),
+        SYSCALL_FUTEX => sys_futex(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SLEEP => sys_sleep(args.get(0)),
+        SYSCALL_CLOCK_GETTIME => sys_clock_gettime(args.get(0), args.get(1)),
+        SYSCALL_PTRACE => sys_ptrace(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_SCHED_SETSCHEDULER => sys_sched_setscheduler(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SCHED_GETSCHEDULER => sys_sched_getscheduler(args.get(0)),
+        SYSCALL_SCHED_SETAFFINITY => sys_sched_setaffinity(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SCHED_GETAFFINITY => sys_sched_getaffinity(args.get(0), args.get(1), args.get(2)),
         SYSCALL_YIELD => sys_yield(),
+        SYSCALL_KILL => sys_kill(args.get(0), args.get(1)),
+        SYSCALL_SIGACTION => sys_sigaction(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SIGPROCMASK => sys_sigprocmask(args.get(0)),
+        SYSCALL_SIGRETURN => sys_sigreturn(),
+        SYSCALL_TIMES => sys_times(args.get(0)),
+        SYSCALL_SETPGID => sys_setpgid(args.get(0), args.get(1)),
+        SYSCALL_GETPGID => sys_getpgid(args.get(0)),
+        SYSCALL_GETSID => sys_getsid(args.get(0)),
+        SYSCALL_SETSID => sys_setsid(),
+        SYSCALL_UNAME => sys_uname(args.get(0)),
         SYSCALL_GETPID => sys_getpid(),
-        SYSCALL_FORK => sys_fork(),
-        SYSCALL_EXEC => sys_exec(args[0] as *const u8),
-        SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
-        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
-        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
-        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
-        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
-        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
-        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
-        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+        SYSCALL_GETPPID => sys_getppid(),
+        SYSCALL_REBOOT => sys_reboot(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SYSLOG => sys_syslog(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_GETTID => sys_gettid(),
+        SYSCALL_SYSINFO => sys_sysinfo(args.get(0)),
+        SYSCALL_CLONE => sys_clone(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_EXEC => sys_exec(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_WAIT4 => sys_wait4(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_GETRLIMIT => sys_getrlimit(args.get(0), args.get(1)),
+        SYSCALL_SETRLIMIT => sys_setrlimit(args.get(0), args.get(1)),
+        SYSCALL_PRCTL => sys_prctl(args.get(0), args.get(1)),
+        SYSCALL_SYNCFS => sys_syncfs(args.get(0)),
+        SYSCALL_SECCOMP => sys_seccomp(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_GETRANDOM => sys_getrandom(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_GET_TIME => sys_get_time(args.get(0), args.get(1)),
+        SYSCALL_MMAP => sys_mmap(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_MUNMAP => sys_munmap(args.get(0), args.get(1)),
+        SYSCALL_SBRK => sys_sbrk(args.get(0)),
+        SYSCALL_MPROTECT => sys_mprotect(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_MREMAP => sys_mremap(args.get(0), args.get(1), args.get(2), args.get(3)),
+        SYSCALL_SHMGET => sys_shmget(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SHMCTL => sys_shmctl(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SHMAT => sys_shmat(args.get(0), args.get(1), args.get(2)),
+        SYSCALL_SHMDT => sys_shmdt(args.get(0)),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args.get(0)),
+        SYSCALL_TASK_INFO => sys_task_info(args.get(0)),
+        SYSCALL_SPAWN => sys_spawn(
+            args.get(0),
+            args.get(1),
+            args.get(2),
+            args.get(3),
+            args.get(4),
+        ),
+        SYSCALL_THREAD_CREATE => sys_thread_create(args.get(0), args.get(1)),
+        SYSCALL_WAITTID => sys_waittid(args.get(0)),
+        SYSCALL_SWITCH_TRACE => sys_switch_trace(args.get(0), args.get(1)),
+        SYSCALL_MUTEX_CREATE => sys_mutex_create(args.get::<usize>(0) == 1),
+        SYSCALL_MUTEX_LOCK => sys_mutex_lock(args.get(0)),
+        SYSCALL_MUTEX_UNLOCK => sys_mutex_unlock(args.get(0)),
+        SYSCALL_SCHED_STATS => sys_sched_stats(args.get(0)),
+        SYSCALL_PS => sys_ps(args.get(0), args.get(1)),
+        SYSCALL_SWAP_STATS => sys_swap_stats(args.get(0)),
+        SYSCALL_SLAB_STATS => sys_slab_stats(args.get(0), args.get(1)),
+        SYSCALL_MEMINFO => sys_meminfo(args.get(0)),
+        SYSCALL_BLOCK_CACHE_STATS => sys_block_cache_stats(args.get(0)),
+        _ => {
+            //未知的系统调用号只说明用户程序有误，不应使内核崩溃
+            warn!("Unsupported syscall_id: {}", syscall_id);
+            Errno::ENOSYS.neg()
+        }
     }
 }
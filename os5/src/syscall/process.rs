@@ -1,32 +1,194 @@
 //!流程管理系统调用
 
-use crate::loader::get_app_data_by_name;
-use crate::mm::{translated_refmut, translated_str};
+use crate::loader::{program_name, read_program};
+use crate::mm::{
+    copy_bytes_from_user, copy_bytes_to_user, copy_from_user, copy_slice_to_user, copy_to_user,
+};
+use crate::mm::translated_str;
+use crate::mm::{frame_available, frame_stats, heap_stats, page_stats, slab_stats};
+use crate::mm::{swap_enabled, swap_used};
+use crate::task::{
+    add_task, block_current_and_run_next, current_task, current_trap_cx, current_user_token,
+    exit_current_and_run_next, sleep_current_and_run_next, suspend_current_and_run_next,
+    TaskControlBlock, TaskStatus, set_priority, mmap, munmap, mprotect, mremap, sbrk, self
+};
+use crate::task::{get_affinity, get_scheduler, set_affinity, set_scheduler, SchedPolicy};
+use crate::task::{clock_hand, sched_stats, switch_trace, SwitchRecord};
+use crate::task::{send_signal, signal_pending, sigreturn, SignalAction, SignalFlags, SIG_IGN};
+use crate::task::{ptrace, ptrace_stop, PTRACE_PEEKDATA, PTRACE_PEEKUSER};
+use crate::task::{all_tasks, get_pgid, get_sid, set_pgid, set_sid};
+use crate::task::{loadavg, process_count, FdRedirects};
+use crate::task::{get_rlimit, nproc_exceeded, rlimit_supported, set_rlimit, Rlimit};
+use crate::task::{kstack_available, pid_available};
 use crate::task::{
-    add_task, current_task, current_user_token, exit_current_and_run_next,
-    suspend_current_and_run_next, TaskStatus,
-    set_priority, mmap, munmap, self
+    install_seccomp, SeccompFilter, SECCOMP_BITMAP_BYTES, SECCOMP_MODE_ERRNO, SECCOMP_MODE_KILL,
 };
-use crate::timer::get_time_us;
+use crate::random::fill_random;
+use crate::timer::{get_realtime_ns, get_time_ns, get_time_us};
+use alloc::string::String;
 use alloc::sync::Arc;
-use crate::config::MAX_SYSCALL_NUM;
+use alloc::vec::Vec;
+use crate::config::{
+    ARG_MAX, LOG_BUF_SIZE, MAX_FDS, MAX_SYSCALL_NUM, PAGE_SIZE, SWAP_SLOTS, TASK_NAME_LEN,
+};
+use crate::console::{clear_log, log_len, read_log};
+use crate::errno::Errno;
+use crate::fs::block_cache_sync_all;
+use crate::sbi::{shutdown, system_reset, SRST_TYPE_COLD_REBOOT, SRST_TYPE_SHUTDOWN};
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct TimeVal {
     pub sec: usize,
     pub usec: usize,
 }
 
+/// 与 Linux 的 struct timespec 布局一致
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimeSpec {
+    pub tv_sec: usize,
+    pub tv_nsec: usize,
+}
+
+/// 时钟：墙上时间
+pub const CLOCK_REALTIME: usize = 0;
+/// 时钟：启动以来经过的时间，单调递增
+pub const CLOCK_MONOTONIC: usize = 1;
+
+//时钟 clock_id 的当前时间（纳秒），不支持的时钟返回 None
+fn clock_ns(clock_id: usize) -> Option<usize> {
+    match clock_id {
+        CLOCK_REALTIME => Some(get_realtime_ns()),
+        CLOCK_MONOTONIC => Some(get_time_ns()),
+        _ => None,
+    }
+}
+
+/// 与 Linux 的 struct sched_param 布局一致
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SchedParam {
+    pub sched_priority: i32,
+}
+
+/// 调度统计信息，时间以微秒计
+#[repr(C)]
+pub struct SchedStats {
+    /// 启动以来的时间
+    pub uptime_us: usize,
+    /// 处理器空闲的累计时间
+    pub idle_us: usize,
+    /// 任务切换总次数
+    pub switches: usize,
+}
+
+/// 页面置换统计信息，除了当前状态之外都是启动以来的累计值
+#[repr(C)]
+pub struct SwapStats {
+    /// 定时收集访问位的次数
+    pub harvests: usize,
+    /// 检查访问位的页面数，以及其中访问位、脏位被置位的页面数
+    pub scanned: usize,
+    pub accessed: usize,
+    pub dirty: usize,
+    /// 被选为牺牲页换出的页面数和换入的页面数
+    pub swap_outs: usize,
+    pub swap_ins: usize,
+    /// 当前在交换区中的页面数和空闲页帧数
+    pub swapped: usize,
+    pub free_frames: usize,
+    /// 时钟指针的位置：下一次换出从进程 clock_pid 的页号 clock_vpn 开始检查
+    pub clock_pid: usize,
+    pub clock_vpn: usize,
+}
+
+/// 内存使用情况
+#[repr(C)]
+pub struct MemInfo {
+    /// 物理页帧的总数、空闲数和同时被占用的最大数
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub peak_frames: usize,
+    /// 内核堆的总字节数和已分配的字节数
+    pub heap_total: usize,
+    pub heap_used: usize,
+    /// 当前进程占有页帧的用户页面数和被换出的用户页面数
+    pub mapped_pages: usize,
+    pub swapped_pages: usize,
+    /// 当前进程中用 2MiB 大页映射的区域数
+    pub huge_pages: usize,
+    /// 当前进程中只映射到共享零页、不占用自己页帧的页面数
+    pub zero_pages: usize,
+}
+
+/// SlabInfo 中缓存名的长度，包括结尾的 \0
+pub const SLAB_NAME_LEN: usize = 32;
+
+/// sys_slab_stats 返回的 slab 缓存信息
+#[repr(C)]
+pub struct SlabInfo {
+    /// 以 \0 结尾的缓存名
+    pub name: [u8; SLAB_NAME_LEN],
+    /// 对象的大小（字节）和每个 slab 中的对象数
+    pub object_size: usize,
+    pub objects_per_slab: usize,
+    /// 已经申请的 slab 数，正在使用的对象数，累计分配的对象数
+    pub slabs: usize,
+    pub in_use: usize,
+    pub allocs: usize,
+}
+
+/// sys_ps 返回的进程信息
+#[repr(C)]
+pub struct ProcInfo {
+    pub pid: usize,
+    /// 父进程的 PID，initproc 为 0
+    pub ppid: usize,
+    /// TaskStatus 的取值：0 UnInit，1 Ready，2 Running，3 Blocked，4 Zombie
+    pub status: usize,
+    /// 当前的有效优先级
+    pub priority: usize,
+    /// 以 \0 结尾的进程名
+    pub name: [u8; TASK_NAME_LEN],
+    /// 第一次被调度以来经过的时间（毫秒），尚未运行过时为 0
+    pub run_time: usize,
+}
+
 #[derive(Clone, Copy)]
 pub struct TaskInfo {
     pub status: TaskStatus,
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
     pub time: usize,
+    /// 用户态运行时间（毫秒）
+    pub utime: usize,
+    /// 内核态运行时间（毫秒）
+    pub stime: usize,
+    /// 常驻集大小，即当前占有页帧的用户页面数，以及它曾经达到的最大值
+    pub rss: usize,
+    pub peak_rss: usize,
+}
+
+/// sys_times 的结果，与 Linux 的 struct tms 字段相同，但单位为微秒而不是时钟滴答
+#[repr(C)]
+pub struct Tms {
+    /// 当前进程的用户态时间
+    pub tms_utime: usize,
+    /// 当前进程的内核态时间
+    pub tms_stime: usize,
+    /// 已回收子进程的用户态时间之和
+    pub tms_cutime: usize,
+    /// 已回收子进程的内核态时间之和
+    pub tms_cstime: usize,
 }
 
 pub fn sys_exit(exit_code: i32) -> ! {
-    debug!("[kernel] Application exited with code {}", exit_code);
+    debug!(
+        "[kernel] Application {} (pid {}) exited with code {}",
+        current_task().unwrap().name(),
+        current_task().unwrap().getpid(),
+        exit_code
+    );
     exit_current_and_run_next(exit_code);
     panic!("Unreachable in sys_exit!");
 }
@@ -38,112 +200,797 @@ pub fn sys_yield() -> isize {
     0
 }
 
+/// 功能：当前任务睡眠一段时间，期间不占用 CPU。
+/// 参数：sleep_ms 为睡眠的毫秒数。
+/// 返回值：睡眠结束返回 0，被信号打断时返回 -EINTR。
+/// syscall ID：101
+pub fn sys_sleep(sleep_ms: usize) -> isize {
+    match sleep_current_and_run_next(sleep_ms) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：向进程或进程组发送信号。
+/// 参数：pid 大于 0 时为目标进程的 PID；为 0 时表示当前进程所在的进程组；
+///      为 -1 时表示除当前进程外的所有进程；小于 -1 时表示进程组 -pid。
+///      signum 为信号编号，为 0 时只检查目标是否存在。
+/// 返回值：成功返回 0；信号编号不合法时返回 -EINVAL；目标不存在时返回 -ESRCH。
+/// syscall ID：129
+pub fn sys_kill(pid: isize, signum: usize) -> isize {
+    send_signal(pid, signum)
+}
+
+/// 功能：跟踪子进程，读写它的内存与寄存器，控制它的执行，用于实现调试器。
+/// 参数：request 为 PTRACE_TRACEME(0) 时当前进程请求由父进程跟踪，此后执行 ebreak 或 exec 成功时停下，
+///      父进程在 wait4 中得知，其余参数忽略；只有主线程可以这样请求；
+///      其余请求的 pid 为当前进程跟踪的、已经停下的子进程：
+///      PTRACE_PEEKDATA(2)/PTRACE_POKEDATA(5) 读出/写入子进程地址 addr 处的 8 字节，可以写入代码段；
+///      PTRACE_PEEKUSER(3)/PTRACE_POKEUSER(6) 读出/写入编号为 addr 的寄存器，0 为 pc，1~31 为 x1~x31；
+///      读出的值保存在 data 指向的 usize 中，写入的值为 data；
+///      PTRACE_CONT(7) 让子进程继续运行，PTRACE_SINGLESTEP(9) 让它执行一条指令后再次停下，
+///      data 不为 0 时同时向子进程发送编号为 data 的信号；
+///      PTRACE_KILL(8) 终止子进程，它不必已经停下。
+/// 返回值：成功返回 0；pid 不是当前进程跟踪的、已经停下的子进程时返回 -ESRCH；
+///      已经被跟踪时再请求 PTRACE_TRACEME 返回 -EPERM；request 或信号编号不合法时返回 -EINVAL；
+///      寄存器编号不合法时返回 -EIO；子进程的地址或 data 不可访问时返回 -EFAULT。
+/// syscall ID：117
+pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> isize {
+    match (request, ptrace(request, pid, addr, data)) {
+        (PTRACE_PEEKDATA | PTRACE_PEEKUSER, Ok(value)) => {
+            match copy_to_user(current_user_token(), data as *mut usize, &value) {
+                Ok(()) => 0,
+                Err(err) => err.neg(),
+            }
+        }
+        (_, Ok(_)) => 0,
+        (_, Err(err)) => err.neg(),
+    }
+}
+
+/// 功能：为信号注册处理动作。
+/// 参数：signum 为信号编号，SIGKILL 与 SIGSTOP 不能被捕获；
+///      action 指向新的处理动作，为 0 时不修改；old_action 保存原来的处理动作，为 0 时不保存。
+/// 返回值：成功返回 0；信号编号不合法时返回 -EINVAL；action 不可读或 old_action 不可写时返回 -EFAULT。
+/// syscall ID：134
+pub fn sys_sigaction(
+    signum: usize,
+    action: *const SignalAction,
+    old_action: *mut SignalAction,
+) -> isize {
+    let signal = match SignalFlags::from_signum(signum) {
+        Some(signal) if !SignalFlags::unmaskable().contains(signal) => signal,
+        _ => return Errno::EINVAL.neg(),
+    };
+    let task = current_task().unwrap();
+    let token = task.get_user_token();
+    //访问用户内存时可能需要为用户栈分配页面，这期间不能持有线程的 inner
+    let new_action = if action.is_null() {
+        None
+    } else {
+        match copy_from_user(token, action) {
+            Ok(action) => Some(action),
+            Err(err) => return err.neg(),
+        }
+    };
+    let mut inner = task.inner_exclusive_access();
+    let old = inner.signal_actions[signum];
+    if let Some(mut new_action) = new_action {
+        new_action.mask -= SignalFlags::unmaskable();
+        inner.signal_actions[signum] = new_action;
+        //改为忽略时丢弃已经待处理的该信号
+        if new_action.handler == SIG_IGN {
+            inner.signals.remove(signal);
+        }
+    }
+    drop(inner);
+    if !old_action.is_null() {
+        if let Err(err) = copy_to_user(token, old_action, &old) {
+            return err.neg();
+        }
+    }
+    0
+}
+
+/// 功能：设置被屏蔽的信号集合，SIGKILL 与 SIGSTOP 不能被屏蔽。
+/// 参数：mask 的第 i 位为 1 表示屏蔽编号为 i 的信号。
+/// 返回值：原来的屏蔽集合。
+/// syscall ID：135
+pub fn sys_sigprocmask(mask: u32) -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let old_mask = inner.signal_mask;
+    inner.signal_mask = SignalFlags::from_bits_truncate(mask) - SignalFlags::unmaskable();
+    old_mask.bits() as isize
+}
+
+/// 功能：从信号处理函数返回，恢复被信号打断的执行流。由 sigreturn 跳板自动调用。
+/// 返回值：被打断时 a0 寄存器的值；不在信号处理函数中时返回 -EINVAL。
+/// syscall ID：139
+pub fn sys_sigreturn() -> isize {
+    sigreturn()
+}
+
+/// 功能：设置进程的进程组。
+/// 参数：pid 为当前进程或其子进程的 PID，为 0 表示当前进程；
+///      pgid 为目标进程组 ID，为 0 表示以目标进程的 PID 新建进程组。
+/// 返回值：成功返回 0；目标进程既不是当前进程也不是其子进程时返回 -ESRCH；
+///        目标进程是会话首进程、与当前进程不在同一会话中或进程组不合法时返回 -EPERM。
+/// syscall ID：154
+pub fn sys_setpgid(pid: usize, pgid: usize) -> isize {
+    set_pgid(pid, pgid)
+}
+
+/// 功能：获取进程的进程组 ID。
+/// 参数：pid 为目标进程的 PID，为 0 表示当前进程。
+/// 返回值：进程组 ID；进程不存在时返回 -ESRCH。
+/// syscall ID：155
+pub fn sys_getpgid(pid: usize) -> isize {
+    get_pgid(pid)
+}
+
+/// 功能：获取进程的会话 ID。
+/// 参数：pid 为目标进程的 PID，为 0 表示当前进程。
+/// 返回值：会话 ID；进程不存在时返回 -ESRCH。
+/// syscall ID：156
+pub fn sys_getsid(pid: usize) -> isize {
+    get_sid(pid)
+}
+
+/// 功能：新建一个会话，当前进程成为会话首进程和新进程组的组长。
+/// 返回值：新的会话 ID；当前进程已经是进程组组长时返回 -EPERM。
+/// syscall ID：157
+pub fn sys_setsid() -> isize {
+    set_sid()
+}
+
 pub fn sys_getpid() -> isize {
-    current_task().unwrap().pid.0 as isize
+    current_task().unwrap().getpid() as isize
+}
+
+/// 功能：获取父进程的 PID。
+/// 返回值：父进程的 PID。父进程退出后当前进程被过继给 initproc，此时返回 initproc 的 PID，
+/// 本内核中 initproc 是第一个分配 PID 的进程，其 PID 为 0；initproc 自身没有父进程，同样返回 0。
+/// syscall ID：173
+pub fn sys_getppid() -> isize {
+    let task = current_task().unwrap();
+    let process_inner = task.process.inner_exclusive_access();
+    process_inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.getpid() as isize)
+}
+
+/// 功能：读取当前进程的资源限制。
+/// 参数：resource 为 RLIMIT_CPU (0)、RLIMIT_STACK (3)、RLIMIT_CORE (4)、RLIMIT_NPROC (6)、RLIMIT_AS (9)
+///      或 RLIMIT_RTPRIO (14)；
+///      rlim 指向保存结果的 Rlimit。
+/// 返回值：成功返回 0，resource 不支持时返回 -EINVAL，rlim 不可写时返回 -EFAULT。
+/// syscall ID：163
+pub fn sys_getrlimit(resource: usize, rlim: *mut Rlimit) -> isize {
+    if !rlimit_supported(resource) {
+        return Errno::EINVAL.neg();
+    }
+    match copy_to_user(current_user_token(), rlim, &get_rlimit(resource)) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// utsname 中每个字段的长度，包括结尾的 \0
+pub const UTSNAME_LEN: usize = 65;
+
+/// 与 Linux 的 struct utsname 布局一致，每个字段都是以 \0 结尾的字符串
+#[repr(C)]
+pub struct UtsName {
+    pub sysname: [u8; UTSNAME_LEN],
+    pub nodename: [u8; UTSNAME_LEN],
+    pub release: [u8; UTSNAME_LEN],
+    pub version: [u8; UTSNAME_LEN],
+    pub machine: [u8; UTSNAME_LEN],
+    pub domainname: [u8; UTSNAME_LEN],
+}
+
+//把 s 复制到以 \0 结尾的 utsname 字段中，过长的部分被截断
+fn uts_field(s: &str) -> [u8; UTSNAME_LEN] {
+    let mut field = [0u8; UTSNAME_LEN];
+    let len = s.len().min(UTSNAME_LEN - 1);
+    field[..len].copy_from_slice(&s.as_bytes()[..len]);
+    field
+}
+
+/// 功能：获取内核的名字、版本与硬件平台。
+/// 参数：buf 指向保存结果的 UtsName。sysname 为 "rCore-lab"，release 为内核的版本号，
+///      version 为构建编号、构建模式以及开启的特性，machine 为 "riscv64"。
+/// 返回值：成功返回 0，buf 不可写时返回 -EFAULT。
+/// syscall ID：160
+pub fn sys_uname(buf: *mut UtsName) -> isize {
+    let uts = UtsName {
+        sysname: uts_field("rCore-lab"),
+        nodename: uts_field("rcore"),
+        release: uts_field(env!("CARGO_PKG_VERSION")),
+        version: uts_field(option_env!("KERNEL_VERSION").unwrap_or("#1")),
+        machine: uts_field("riscv64"),
+        domainname: uts_field("(none)"),
+    };
+    match copy_to_user(current_user_token(), buf, &uts) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// SysInfo 中系统负载的定点数表示中小数部分的位数
+pub const SI_LOAD_SHIFT: usize = 16;
+
+/// 与 Linux 的 struct sysinfo 布局一致
+#[repr(C)]
+pub struct SysInfo {
+    /// 启动以来经过的秒数
+    pub uptime: isize,
+    /// 1、5、15 分钟内可运行任务数的平均值，SI_LOAD_SHIFT 位定点数
+    pub loads: [usize; 3],
+    /// 内存与交换区的大小，以 mem_unit 字节为单位
+    pub totalram: usize,
+    pub freeram: usize,
+    pub sharedram: usize,
+    pub bufferram: usize,
+    pub totalswap: usize,
+    pub freeswap: usize,
+    /// 尚未退出的进程数
+    pub procs: u16,
+    pub pad: u16,
+    pub totalhigh: usize,
+    pub freehigh: usize,
+    pub mem_unit: u32,
+}
+
+/// 功能：获取系统的整体统计信息。
+/// 参数：info 指向保存结果的 SysInfo。内存以页为单位（mem_unit 为页面大小），
+///      没有共享内存、缓冲区与高端内存的统计，对应字段总为 0；没有交换设备时交换区大小为 0。
+/// 返回值：成功返回 0，info 不可写时返回 -EFAULT。
+/// syscall ID：179
+pub fn sys_sysinfo(info: *mut SysInfo) -> isize {
+    let (total_frames, free_frames, _) = frame_stats();
+    let totalswap = if swap_enabled() { SWAP_SLOTS } else { 0 };
+    let result = SysInfo {
+        uptime: (get_time_us() / 1_000_000) as isize,
+        loads: loadavg(SI_LOAD_SHIFT),
+        totalram: total_frames,
+        freeram: free_frames,
+        sharedram: 0,
+        bufferram: 0,
+        totalswap,
+        freeswap: totalswap - swap_used(),
+        procs: process_count().min(u16::MAX as usize) as u16,
+        pad: 0,
+        totalhigh: 0,
+        freehigh: 0,
+        mem_unit: PAGE_SIZE as u32,
+    };
+    match copy_to_user(current_user_token(), info, &result) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// getrandom 标志：熵不足时不阻塞。熵池在启动时就已初始化，从不阻塞，因此没有作用
+pub const GRND_NONBLOCK: usize = 1;
+/// getrandom 标志：使用阻塞的熵源。所有随机数都来自同一个熵池，因此没有作用
+pub const GRND_RANDOM: usize = 2;
+
+/// 功能：用内核熵池产生的随机字节填满用户缓冲区。
+/// 参数：buf 指向长度为 len 字节的缓冲区；flags 为 GRND_NONBLOCK(1) 与 GRND_RANDOM(2) 的组合。
+/// 返回值：写入的字节数，总是等于 len；flags 不合法时返回 -EINVAL，buf 不可写时返回 -EFAULT，
+///        已经写入了一部分时返回已写入的字节数。
+/// syscall ID：278
+pub fn sys_getrandom(buf: *mut u8, len: usize, flags: usize) -> isize {
+    if flags & !(GRND_NONBLOCK | GRND_RANDOM) != 0 {
+        return Errno::EINVAL.neg();
+    }
+    let token = current_user_token();
+    let mut chunk = [0u8; 256];
+    let mut done = 0;
+    while done < len {
+        let n = (len - done).min(chunk.len());
+        fill_random(&mut chunk[..n]);
+        if let Err(err) = copy_bytes_to_user(token, buf.wrapping_add(done), &chunk[..n]) {
+            return if done > 0 { done as isize } else { err.neg() };
+        }
+        done += n;
+    }
+    len as isize
+}
+
+/// 功能：修改当前进程的资源限制，新的限制会被之后 fork/spawn 出的子进程继承。
+/// 参数：resource 同 getrlimit；rlim 指向新的限制，软限制不能超过硬限制，硬限制只能降低不能提高。
+/// 返回值：成功返回 0，resource 不支持或软限制超过硬限制时返回 -EINVAL，提高硬限制时返回 -EPERM，
+/// rlim 不可读时返回 -EFAULT。
+/// syscall ID：164
+pub fn sys_setrlimit(resource: usize, rlim: *const Rlimit) -> isize {
+    if !rlimit_supported(resource) {
+        return Errno::EINVAL.neg();
+    }
+    match copy_from_user(current_user_token(), rlim) {
+        Ok(limit) => set_rlimit(resource, limit),
+        Err(err) => err.neg(),
+    }
 }
 
-/// Syscall Fork which returns 0 for child process and child_pid for parent process
-/// 功能：由当前进程 fork 出一个子进程。
-/// 返回值：对于子进程返回 0，对于当前进程则返回子进程的 PID 。
+/// 功能：为当前进程安装系统调用过滤器，之后 fork/spawn 出的子进程与新线程继承它，exec 后保留。
+/// 已有过滤器时新的过滤器与它叠加，只允许两者都允许的系统调用，因此只能收紧不能放宽。exit 与 sigreturn 总是允许。
+/// 参数：mode 为 SECCOMP_MODE_ERRNO(0) 时被拒绝的系统调用返回 -EPERM，为 SECCOMP_MODE_KILL(1) 时以 SIGSYS 终止进程；
+///      allowed 指向 len 字节的位图，第 i / 8 字节的第 i % 8 位为 1 表示允许系统调用号 i，位图之外的系统调用都被拒绝。
+/// 返回值：成功返回 0，mode 不合法或 len 超过 SECCOMP_BITMAP_BYTES 时返回 -EINVAL，位图不可读时返回 -EFAULT。
+/// syscall ID：277
+pub fn sys_seccomp(mode: usize, allowed: *const u8, len: usize) -> isize {
+    let kill = match mode {
+        SECCOMP_MODE_ERRNO => false,
+        SECCOMP_MODE_KILL => true,
+        _ => return Errno::EINVAL.neg(),
+    };
+    if len > SECCOMP_BITMAP_BYTES {
+        return Errno::EINVAL.neg();
+    }
+    let mut bitmap = [0u8; SECCOMP_BITMAP_BYTES];
+    if let Err(err) = copy_bytes_from_user(current_user_token(), allowed, &mut bitmap[..len]) {
+        return err.neg();
+    }
+    install_seccomp(SeccompFilter::new(bitmap, kill));
+    0
+}
+
+/// prctl 选项：修改当前进程的名字
+pub const PR_SET_NAME: usize = 15;
+/// prctl 选项：读取当前进程的名字
+pub const PR_GET_NAME: usize = 16;
+
+/// 功能：对当前进程进行设置，目前只支持读取与修改进程名。
+/// 参数：option 为 PR_SET_NAME 时，arg2 指向以 \0 结尾的新名字，超过 TASK_NAME_LEN - 1 字节的部分被截断；
+///      option 为 PR_GET_NAME 时，arg2 指向长度为 TASK_NAME_LEN 字节的缓冲区，写入以 \0 结尾的进程名。
+/// 返回值：成功返回 0，option 不支持时返回 -EINVAL，PR_SET_NAME 的名字不可读或 PR_GET_NAME 的缓冲区不可写时返回 -EFAULT。
+/// syscall ID：167
+pub fn sys_prctl(option: usize, arg2: usize) -> isize {
+    let token = current_user_token();
+    let task = current_task().unwrap();
+    match option {
+        PR_SET_NAME => {
+            let name = match translated_str(token, arg2 as *const u8) {
+                Ok(name) => name,
+                Err(err) => return err.neg(),
+            };
+            task.inner_exclusive_access().set_name(name.as_str());
+            0
+        }
+        PR_GET_NAME => {
+            let mut name = [0u8; TASK_NAME_LEN];
+            let inner = task.inner_exclusive_access();
+            name[..inner.name.len()].copy_from_slice(inner.name.as_bytes());
+            drop(inner);
+            match copy_bytes_to_user(token, arg2 as *mut u8, &name) {
+                Ok(()) => 0,
+                Err(err) => err.neg(),
+            }
+        }
+        _ => Errno::EINVAL.neg(),
+    }
+}
+
+/// clone 标志：与当前线程共享地址空间，即创建同一进程中的新线程
+pub const CLONE_VM: usize = 0x100;
+/// clone 标志：把新执行流的 tp 寄存器设置为参数 tls，供用户态实现线程局部存储
+pub const CLONE_SETTLS: usize = 0x80000;
+
+/// 功能：创建一个新的执行流。flags 不含 CLONE_VM 时等同于 fork；包含 CLONE_VM 时在当前进程中创建一个新线程，
+/// 新线程从 clone 返回处开始执行，返回值为 0，其余寄存器与当前线程相同。
+/// 参数：flags 目前只支持 CLONE_VM 与 CLONE_SETTLS 的组合；
+///      stack 为新线程的用户栈栈顶，为 0 时使用内核为其分配的用户栈，fork 时忽略；
+///      ptid 暂不支持，忽略；tls 在指定了 CLONE_SETTLS 时作为新执行流的 tp。
+/// 返回值：对于新的执行流返回 0；对于当前线程，fork 返回子进程的 PID，创建线程返回新线程的线程号；
+///        物理内存不足时返回 -ENOMEM(-12)；flags 不合法、或者 fork 时进程中还有其他线程时返回 -EINVAL(-22)；
+///        超出 RLIMIT_NPROC、PID 或内核栈已用完时返回 -EAGAIN(-11)。
 /// syscall ID：220
-pub fn sys_fork() -> isize {
-    let current_task = current_task().unwrap();
-    let new_task = current_task.fork();
-    let new_pid = new_task.pid.0;
+pub fn sys_clone(flags: usize, stack: usize, _ptid: usize, tls: usize) -> isize {
+    if flags & !(CLONE_VM | CLONE_SETTLS) != 0 {
+        return Errno::EINVAL.neg();
+    }
+    let task = current_task().unwrap();
+    let new_task = if flags & CLONE_VM != 0 {
+        if !kstack_available() {
+            return Errno::EAGAIN.neg();
+        }
+        let new_task = match task.new_thread() {
+            Ok(new_task) => new_task,
+            Err(err) => return err.neg(),
+        };
+        let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
+        *trap_cx = *current_trap_cx();
+        trap_cx.kernel_sp = new_task.kernel_stack.get_top();
+        trap_cx.x[2] = if stack == 0 {
+            task.process.inner_exclusive_access().ustack_top(new_task.tid)
+        } else {
+            stack
+        };
+        new_task
+    } else {
+        match fork(&task) {
+            Ok(new_task) => new_task,
+            Err(err) => return err,
+        }
+    };
     // 修改newtask的陷阱上下文，因为它在切换后立即返回
     let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
     // 因为我们以前已经做过了，所以我们不必转到下一个指令
-    // 对于子进程，fork返回0
+    // 对于新的执行流，clone返回0
     trap_cx.x[10] = 0;
+    if flags & CLONE_SETTLS != 0 {
+        trap_cx.x[4] = tls;
+    }
+    let ret = if flags & CLONE_VM != 0 {
+        new_task.tid
+    } else {
+        new_task.getpid()
+    };
     // 将新任务添加到计划程序
     add_task(new_task);
-    new_pid as isize
+    ret as isize
+}
+
+/// Fork which returns the main thread of the child process
+//由当前进程 fork 出一个子进程，只有单线程的进程才能 fork；
+//超出 RLIMIT_NPROC、PID 或内核栈已用完时返回 Err(-EAGAIN)，进程中还有其他线程时返回 Err(-EINVAL)，
+//物理内存不足时返回 Err(-ENOMEM)
+fn fork(task: &Arc<TaskControlBlock>) -> Result<Arc<TaskControlBlock>, isize> {
+    if nproc_exceeded() || !pid_available() || !kstack_available() {
+        return Err(Errno::EAGAIN.neg());
+    }
+    if task.process.inner_exclusive_access().live_thread_count() > 1 {
+        return Err(Errno::EINVAL.neg());
+    }
+    task.fork().map_err(Errno::neg)
 }
 
 /// Syscall Exec which accepts the elf path
 /// 功能：将当前进程的地址空间清空并加载一个特定的可执行文件，返回用户态后开始它的执行。
-/// 参数：字符串 path 给出了要加载的可执行文件的名字；
-/// 返回值：如果出错的话（如找不到名字相符的可执行文件）则返回 -1，否则不应该返回。
+/// 参数：字符串 path 给出了要加载的可执行文件：含有 / 时是从根目录开始解析的路径，可以经过挂载点；
+///      否则是程序名，依次在新程序的环境变量 PATH 列出的目录中查找，没有 PATH 时在根目录中查找。进程名为路径的最后一级；
+///      args 为以空指针结尾的命令行参数字符串指针数组，可以为空指针；
+///      envp 为以空指针结尾的环境变量字符串指针数组，为空指针时沿用当前进程的环境变量。
+/// 返回值：进程中还有其他线程时返回 -EINVAL(-22)；找不到可执行文件时返回 -ENOENT(-2)；
+///      path 是目录、命名管道或设备等不能执行的文件时返回 -EACCES(-13)；路径中间某一级不是目录时返回 -ENOTDIR(-20)；
+///      文件不是可加载的 RISC-V ELF（魔数错误、体系结构不符、被截断、段的大小或位置不合法）时返回 -ENOEXEC(-8)；
+///      物理内存不足以建立新的地址空间时返回 -ENOMEM(-12)；path、args、envp 或其中的字符串不可读时返回 -EFAULT(-14)；
+///      字符串加上结尾的 \0 超过 USER_STR_MAX 字节时返回 -ENAMETOOLONG(-36)；
+///      命令行参数与环境变量的字符串及指针数组合计超过 ARG_MAX 字节时返回 -E2BIG(-7)；
+///      出错时当前进程保持原样，否则不应该返回。
 /// 注意：path 必须以 "\0" 结尾，否则内核将无法确定其长度
 /// syscall ID：221
-pub fn sys_exec(path: *const u8) -> isize {
+pub fn sys_exec(path: *const u8, args: *const usize, envp: *const usize) -> isize {
     let token = current_user_token();
-    //调用 translated_str 找到要执行的应用名
-    let path = translated_str(token, path);
-    //调用get_app_data_by_name 接口获取对应的 ELF 数据，
-    //如果找到的话就调用 TaskControlBlock::exec 替换地址空间。
-    if let Some(data) = get_app_data_by_name(path.as_str()) {
-        let task = current_task().unwrap();
-        task.exec(data);
-        0
+    //调用 translated_str 找到要执行的应用名，参数字符串需要在替换地址空间之前复制到内核中
+    let (path, args, envs) = match translated_exec_args(token, path, args, envp) {
+        Ok(exec_args) => exec_args,
+        Err(err) => return err.neg(),
+    };
+    //经由文件系统读出对应的 ELF 数据，如果找到的话就调用 TaskControlBlock::exec 替换地址空间。
+    let task = current_task().unwrap();
+    if task.process.inner_exclusive_access().live_thread_count() > 1 {
+        return Errno::EINVAL.neg();
+    }
+    let data = match read_exec_program(path.as_str(), envs.as_deref()) {
+        Ok(data) => data,
+        Err(err) => return err.neg(),
+    };
+    let argc = args.len();
+    match task.exec(program_name(path.as_str()), data.as_slice(), args, envs) {
+        // 返回值会写入 a0，作为新程序的 argc
+        Ok(()) => {
+            //被跟踪的进程在新程序的第一条指令之前停下，跟踪者可以在这时插入断点
+            ptrace_stop(SignalFlags::SIGTRAP);
+            argc as isize
+        }
+        Err(err) => err.errno(),
+    }
+}
+
+//按新程序的环境变量中的 PATH 查找程序，envs 为 None 时新程序沿用当前进程的环境变量
+fn read_exec_program(path: &str, envs: Option<&[String]>) -> Result<Vec<u8>, Errno> {
+    match envs {
+        Some(envs) => read_program(path, envs),
+        None => {
+            let task = current_task().unwrap();
+            let environ = task.process.inner_exclusive_access().environ.clone();
+            read_program(path, &environ)
+        }
+    }
+}
+
+/// 从用户地址空间中读取以空指针结尾的字符串指针数组，args 为空指针时表示没有参数。
+/// 读到的字符串与指针合计超过 ARG_MAX 时返回 Errno::E2BIG，不再继续复制
+fn translated_args(token: usize, mut args: *const usize) -> Result<Vec<String>, Errno> {
+    let mut args_vec = Vec::new();
+    if args.is_null() {
+        return Ok(args_vec);
+    }
+    let word = core::mem::size_of::<usize>();
+    let mut size = word;
+    loop {
+        let arg_str_ptr = copy_from_user(token, args)?;
+        if arg_str_ptr == 0 {
+            break;
+        }
+        let arg = translated_str(token, arg_str_ptr as *const u8)?;
+        size += arg.len() + 1 + word;
+        if size > ARG_MAX {
+            return Err(Errno::E2BIG);
+        }
+        args_vec.push(arg);
+        args = args.wrapping_add(1);
+    }
+    Ok(args_vec)
+}
+
+/// 读取用户传入的环境变量，envp 为空指针时返回 None，表示沿用原来的环境变量
+fn translated_envs(token: usize, envp: *const usize) -> Result<Option<Vec<String>>, Errno> {
+    if envp.is_null() {
+        Ok(None)
     } else {
-        -1
+        translated_args(token, envp).map(Some)
     }
 }
 
+/// exec 与 spawn 的参数：可执行文件名、命令行参数和环境变量
+type ExecArgs = (String, Vec<String>, Option<Vec<String>>);
+
+/// 把 exec 与 spawn 的路径、命令行参数和环境变量复制到内核中，它们都可以跨越页面。
+/// 任何一个指针不可读时返回 Errno::EFAULT，字符串过长时返回 Errno::ENAMETOOLONG
+fn translated_exec_args(
+    token: usize,
+    path: *const u8,
+    args: *const usize,
+    envp: *const usize,
+) -> Result<ExecArgs, Errno> {
+    let path = translated_str(token, path)?;
+    let args = translated_args(token, args)?;
+    let envs = translated_envs(token, envp)?;
+    Ok((path, args, envs))
+}
+
+/// waitpid 选项：没有已经结束的子进程时立即返回，而不是阻塞
+pub const WNOHANG: usize = 1;
+
+/// 子进程的资源使用情况，与 Linux 的 struct rusage 布局一致
+#[repr(C)]
+#[derive(Default)]
+pub struct Rusage {
+    /// 用户态时间，包括它回收的后代
+    pub ru_utime: TimeVal,
+    /// 内核态时间，包括它回收的后代
+    pub ru_stime: TimeVal,
+    /// 以下字段暂不统计，总为 0
+    pub ru_maxrss: usize,
+    pub ru_ixrss: usize,
+    pub ru_idrss: usize,
+    pub ru_isrss: usize,
+    pub ru_minflt: usize,
+    pub ru_majflt: usize,
+    pub ru_nswap: usize,
+    pub ru_inblock: usize,
+    pub ru_oublock: usize,
+    pub ru_msgsnd: usize,
+    pub ru_msgrcv: usize,
+    pub ru_nsignals: usize,
+    pub ru_nvcsw: usize,
+    pub ru_nivcsw: usize,
+}
+
+fn us_to_timeval(us: usize) -> TimeVal {
+    TimeVal {
+        sec: us / 1_000_000,
+        usec: us % 1_000_000,
+    }
+}
 
 /// 功能：当前进程等待一个子进程变为僵尸进程，回收其全部资源并收集其返回值。
-/// 参数：pid 表示要等待的子进程的进程 ID，如果为 -1 的话表示等待任意一个子进程；
-///      exit_code 表示保存子进程返回值的地址，如果这个地址为 0 的话表示不必保存。
-/// 返回值：如果要等待的子进程不存在则返回 -1；否则如果要等待的子进程均未结束则返回 -2；
-///        否则返回结束的子进程的进程 ID。
+/// 参数：pid 表示要等待的子进程的进程 ID，如果为 -1 的话表示等待任意一个子进程，
+///      为 0 表示等待与当前进程同组的任意子进程，小于 -1 表示等待进程组 -pid 中的任意子进程；
+///      exit_code 表示保存子进程返回值的地址，如果这个地址为 0 的话表示不必保存；
+///      options 为 0 或 WNOHANG；
+///      rusage 表示保存子进程资源使用情况的地址，如果为 0 的话表示不必保存。
+/// 返回值：options 不合法时返回 -EINVAL；如果要等待的子进程不存在则返回 -ECHILD；
+///        如果指定了 WNOHANG 而符合条件的子进程均未结束则返回 0；
+///        否则阻塞直到有符合条件的子进程结束，返回结束的子进程的进程 ID，阻塞时被信号打断返回 -EINTR；
+///        exit_code 或 rusage 不可写时子进程仍被回收，返回 -EFAULT。
+///        被当前进程跟踪的子进程停下时也会返回它的进程 ID，但不回收它，
+///        exit_code 中保存 (停下的信号 << 8) | 0x7f，rusage 不填写；每次停下只报告一次。
 /// syscall ID：260
-pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
-    let task = current_task().unwrap();
-    // find a child process
+pub fn sys_wait4(
+    pid: isize,
+    exit_code_ptr: *mut i32,
+    options: usize,
+    rusage: *mut Rusage,
+) -> isize {
+    if options & !WNOHANG != 0 {
+        return Errno::EINVAL.neg();
+    }
+    loop {
+        let task = current_task().unwrap();
+        let process = task.process.clone();
+        // find a child process
 
-    // ---- access current TCB exclusively
-    //仅访问当前TCB
-    let mut inner = task.inner_exclusive_access();
-    if !inner
-        .children
-        .iter()
-        .any(|p| pid == -1 || pid as usize == p.getpid())
-    {
-        return -1;
+        // ---- access current TCB exclusively
+        //仅访问当前TCB
+        let mut inner = task.inner_exclusive_access();
+        let mut process_inner = process.inner_exclusive_access();
+        let pgid = process_inner.pgid;
+        let matches = |p: &Arc<TaskControlBlock>| match pid {
+            -1 => true,
+            0 => p.process.inner_exclusive_access().pgid == pgid,
+            pid if pid > 0 => pid as usize == p.getpid(),
+            pid => p.process.inner_exclusive_access().pgid == (-pid) as usize,
+        };
+        if !process_inner.children.iter().any(matches) {
+            return Errno::ECHILD.neg();
+            // ---- release current PCB
+        }
+        //被跟踪的子进程停下时也会被找到，但不回收
+        let pair = process_inner.children.iter().enumerate().find(|(_, p)| {
+            // ++++ temporarily access child PCB lock exclusively
+            let child_inner = p.inner_exclusive_access();
+            let found = child_inner.is_zombie() || child_inner.ptrace.stop_signal.is_some();
+            drop(child_inner);
+            // ++++ release child PCB
+            found && matches(p)
+        });
+        let stopped = pair.and_then(|(_, p)| {
+            let mut child_inner = p.inner_exclusive_access();
+            match child_inner.is_zombie() {
+                true => None,
+                false => child_inner.ptrace.stop_signal.take(),
+            }
+        });
+        if let (Some((_, child)), Some(signum)) = (pair, stopped) {
+            let found_pid = child.getpid();
+            let token = process_inner.get_user_token();
+            drop(process_inner);
+            let status = (signum << 8 | 0x7f) as i32;
+            if !exit_code_ptr.is_null() {
+                if let Err(err) = copy_to_user(token, exit_code_ptr, &status) {
+                    return err.neg();
+                }
+            }
+            return found_pid as isize;
+        }
+        if let Some((idx, _)) = pair {
+            let child = process_inner.children.remove(idx);
+            //结束进程的普通线程留在线程列表中，到这里才释放它
+            child.process.inner_exclusive_access().tasks.clear();
+            //等待队列等内核对象可能暂时还持有子进程的引用，它们释放时子进程的资源随之回收
+            let found_pid = child.getpid();
+            // ++++ temporarily access child TCB exclusively
+            let child_inner = child.inner_exclusive_access();
+            let exit_code = child_inner.exit_code;
+            //子进程及其已回收后代的 CPU 时间计入当前进程的 cutime/cstime
+            let utime = child_inner.utime + child_inner.cutime;
+            let stime = child_inner.stime + child_inner.cstime;
+            inner.cutime += utime;
+            inner.cstime += stime;
+            drop(child_inner);
+            // ++++ release child PCB
+            //写回结果时可能要为惰性分配的页面分配页帧，先释放对进程控制块的借用
+            let token = process_inner.get_user_token();
+            drop(process_inner);
+            //子进程已经被回收，结果写不回用户时仍然返回 -EFAULT
+            if !exit_code_ptr.is_null() {
+                if let Err(err) = copy_to_user(token, exit_code_ptr, &exit_code) {
+                    return err.neg();
+                }
+            }
+            if !rusage.is_null() {
+                let usage = Rusage {
+                    ru_utime: us_to_timeval(utime),
+                    ru_stime: us_to_timeval(stime),
+                    ..Default::default()
+                };
+                if let Err(err) = copy_to_user(token, rusage, &usage) {
+                    return err.neg();
+                }
+            }
+            return found_pid as isize;
+        }
         // ---- release current PCB
+        drop(process_inner);
+        drop(inner);
+        if options & WNOHANG != 0 {
+            return 0;
+        }
+        if signal_pending() {
+            return Errno::EINTR.neg();
+        }
+        //子进程都还在运行：阻塞在本进程的 child_exit 队列上，任一子进程退出时被唤醒后重新检查
+        process.child_exit.exclusive_access().push(task.clone());
+        drop(process);
+        drop(task);
+        block_current_and_run_next();
+        //被信号唤醒时还留在 child_exit 队列中
+        let task = current_task().unwrap();
+        task.process.child_exit.exclusive_access().remove(&task);
     }
-    let pair = inner.children.iter().enumerate().find(|(_, p)| {
-        // ++++ temporarily access child PCB lock exclusively
-        p.inner_exclusive_access().is_zombie() && (pid == -1 || pid as usize == p.getpid())
-        // ++++ release child PCB
-    });
-    if let Some((idx, _)) = pair {
-        let child = inner.children.remove(idx);
-        // confirm that child will be deallocated after removing from children list
-        assert_eq!(Arc::strong_count(&child), 1);
-        let found_pid = child.getpid();
-        // ++++ temporarily access child TCB exclusively
-        let exit_code = child.inner_exclusive_access().exit_code;
-        // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
-        found_pid as isize
-    } else {
-        -2
+}
+
+/// 功能：读取启动以来经过的时间，与 CLOCK_MONOTONIC 相同；墙上时间需要使用 clock_gettime(CLOCK_REALTIME)。
+/// 参数：ts 指向保存结果的 TimeVal；tz 忽略。
+/// 返回值：成功返回 0，ts 不可写时返回 -EFAULT。
+/// syscall ID：169
+pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
+    let us = clock_ns(CLOCK_MONOTONIC).unwrap() / 1000;
+    let time_val = TimeVal {
+        sec: us / 1_000_000,
+        usec: us % 1_000_000,
+    };
+    match copy_to_user(current_user_token(), ts, &time_val) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
     }
-    // ---- release current PCB lock automatically
 }
 
-// YOUR JOB: 引入虚地址后重写 sys_get_time
-pub fn sys_get_time(_ts: *mut TimeVal, _tz: usize) -> isize {
-    let _us = get_time_us();
-    let phy_ts = translated_refmut(current_user_token(), _ts);
-    *phy_ts = TimeVal {
-        sec: _us / 1_000_000,
-        usec: _us % 1_000_000,
+/// 功能：读取时钟 clock_id 的当前时间，精度为纳秒。
+/// 参数：clock_id 为 CLOCK_REALTIME(0) 时读取墙上时间，即自 1970-01-01 00:00:00 UTC 以来的时间，
+///      由启动时读取的 RTC 加上启动以来经过的时间得到；为 CLOCK_MONOTONIC(1) 时读取启动以来经过的时间；
+///      tp 指向保存结果的 TimeSpec。
+/// 返回值：成功返回 0，clock_id 不支持时返回 -EINVAL，tp 不可写时返回 -EFAULT。
+/// syscall ID：113
+pub fn sys_clock_gettime(clock_id: usize, tp: *mut TimeSpec) -> isize {
+    let ns = match clock_ns(clock_id) {
+        Some(ns) => ns,
+        None => return Errno::EINVAL.neg(),
     };
-    0
+    let time_spec = TimeSpec {
+        tv_sec: ns / 1_000_000_000,
+        tv_nsec: ns % 1_000_000_000,
+    };
+    match copy_to_user(current_user_token(), tp, &time_spec) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
 }
 
 // YOUR JOB: 引入虚地址后重写 sys_task_info
 pub fn sys_task_info(_ti: *mut TaskInfo) -> isize {
-    let phy_ti = translated_refmut(current_user_token(), _ti);
-    *phy_ti = TaskInfo {
+    let (utime, stime, _, _) = task::get_times();
+    let process = current_task().unwrap().process.clone();
+    let (rss, peak_rss) = process.inner_exclusive_access().memory_set.rss();
+    let task_info = TaskInfo {
         status: TaskStatus::Running,
         syscall_times: task::get_syscall_times(),
-        time: task::get_run_time() / 1000
+        time: task::get_run_time() / 1000,
+        utime: utime / 1000,
+        stime: stime / 1000,
+        rss,
+        peak_rss,
     };
-    0
+    match copy_to_user(current_user_token(), _ti, &task_info) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取当前进程以及已回收子进程在用户态、内核态运行的时间。
+/// 参数：tms 指向保存结果的 Tms，时间单位为微秒。
+/// 返回值：启动以来经过的时间（微秒）；tms 不可写时返回 -EFAULT。
+/// syscall ID：153
+pub fn sys_times(tms: *mut Tms) -> isize {
+    let (utime, stime, cutime, cstime) = task::get_times();
+    let times = Tms {
+        tms_utime: utime,
+        tms_stime: stime,
+        tms_cutime: cutime,
+        tms_cstime: cstime,
+    };
+    match copy_to_user(current_user_token(), tms, &times) {
+        Ok(()) => get_time_us() as isize,
+        Err(err) => err.neg(),
+    }
 }
 
 // YOUR JOB: 实现sys_set_priority，为任务添加优先级
@@ -151,27 +998,461 @@ pub fn sys_set_priority(_prio: isize) -> isize {
     set_priority(_prio)
 }
 
+/// 功能：设置进程的调度类。实时任务（SCHED_FIFO / SCHED_RR）总是优先于普通任务运行，
+/// 实时优先级高的任务会在下一次时钟中断时抢占实时优先级低的任务。
+/// 参数：pid 为 0 或当前进程的 PID；policy 为 0 (SCHED_OTHER)、1 (SCHED_FIFO) 或 2 (SCHED_RR)；
+/// param 指向的 sched_priority 对普通任务必须为 0，对实时任务取值为 1..=99。
+/// 返回值：成功返回 0；pid 不是 0 或当前进程的 PID 时返回 -ESRCH；policy 或 sched_priority 不合法时返回 -EINVAL；
+/// 没有特权的进程把实时优先级提高到超过 RLIMIT_RTPRIO 的软限制时返回 -EPERM；param 不可读时返回 -EFAULT。
+/// syscall ID：119
+pub fn sys_sched_setscheduler(pid: usize, policy: usize, param: *const SchedParam) -> isize {
+    if !is_current_pid(pid) {
+        return Errno::ESRCH.neg();
+    }
+    let policy = match policy {
+        0 => SchedPolicy::Normal,
+        1 => SchedPolicy::Fifo,
+        2 => SchedPolicy::RoundRobin,
+        _ => return Errno::EINVAL.neg(),
+    };
+    let param = match copy_from_user(current_user_token(), param) {
+        Ok(param) => param,
+        Err(err) => return err.neg(),
+    };
+    if param.sched_priority < 0 {
+        return Errno::EINVAL.neg();
+    }
+    set_scheduler(policy, param.sched_priority as usize)
+}
+
+/// 功能：获取进程的调度类。
+/// 参数：pid 为 0 或当前进程的 PID。
+/// 返回值：成功返回调度类编号；pid 不是 0 或当前进程的 PID 时返回 -ESRCH。
+/// syscall ID：120
+pub fn sys_sched_getscheduler(pid: usize) -> isize {
+    if !is_current_pid(pid) {
+        return Errno::ESRCH.neg();
+    }
+    get_scheduler() as isize
+}
+
+/// 功能：设置进程的 CPU 亲和性掩码，进程此后只会在掩码允许的核上运行。
+/// 参数：pid 为 0 或当前进程的 PID；cpusetsize 为掩码的字节数；mask 指向掩码。
+/// 返回值：成功返回 0；pid 不是 0 或当前进程的 PID 时返回 -ESRCH；cpusetsize 过小或掩码中不包含任何存在的核时
+/// 返回 -EINVAL；mask 不可读时返回 -EFAULT。
+/// syscall ID：122
+pub fn sys_sched_setaffinity(pid: usize, cpusetsize: usize, mask: *const usize) -> isize {
+    if !is_current_pid(pid) {
+        return Errno::ESRCH.neg();
+    }
+    if cpusetsize < core::mem::size_of::<usize>() {
+        return Errno::EINVAL.neg();
+    }
+    match copy_from_user(current_user_token(), mask) {
+        Ok(mask) => set_affinity(mask),
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取进程的 CPU 亲和性掩码。
+/// 参数：pid 为 0 或当前进程的 PID；cpusetsize 为掩码的字节数；mask 指向保存结果的位置。
+/// 返回值：成功返回 0；pid 不是 0 或当前进程的 PID 时返回 -ESRCH；cpusetsize 过小时返回 -EINVAL；
+/// mask 不可写时返回 -EFAULT。
+/// syscall ID：123
+pub fn sys_sched_getaffinity(pid: usize, cpusetsize: usize, mask: *mut usize) -> isize {
+    if !is_current_pid(pid) {
+        return Errno::ESRCH.neg();
+    }
+    if cpusetsize < core::mem::size_of::<usize>() {
+        return Errno::EINVAL.neg();
+    }
+    match copy_to_user(current_user_token(), mask, &get_affinity()) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// reboot 的两个魔数，与 Linux 一致，防止误调用
+const LINUX_REBOOT_MAGIC1: usize = 0xfee1dead;
+const LINUX_REBOOT_MAGIC2: usize = 672274793;
+/// reboot 的命令：重启
+const LINUX_REBOOT_CMD_RESTART: usize = 0x01234567;
+/// reboot 的命令：关机
+const LINUX_REBOOT_CMD_POWER_OFF: usize = 0x4321fedc;
+
+/// 功能：通过 SBI 关机或重启机器。只有 initproc 以及由它直接创建的进程（如用户 shell）可以调用。
+/// 参数：magic1、magic2 必须分别为 LINUX_REBOOT_MAGIC1 与 LINUX_REBOOT_MAGIC2；
+///      cmd 为 LINUX_REBOOT_CMD_POWER_OFF 或 LINUX_REBOOT_CMD_RESTART。
+/// 返回值：成功时不会返回；没有权限时返回 -EPERM；魔数或 cmd 不合法时返回 -EINVAL；
+///      固件不支持重启时返回 -ENOSYS。
+/// syscall ID：142
+pub fn sys_reboot(magic1: usize, magic2: usize, cmd: usize) -> isize {
+    let task = current_task().unwrap();
+    if !task.inner_exclusive_access().privileged {
+        return Errno::EPERM.neg();
+    }
+    if magic1 != LINUX_REBOOT_MAGIC1 || magic2 != LINUX_REBOOT_MAGIC2 {
+        return Errno::EINVAL.neg();
+    }
+    //关机或重启前把块缓存中的脏块写回磁盘
+    block_cache_sync_all();
+    match cmd {
+        LINUX_REBOOT_CMD_POWER_OFF => {
+            println!("[kernel] Power off requested by pid {}.", task.getpid());
+            system_reset(SRST_TYPE_SHUTDOWN);
+            //不支持 SRST 扩展的旧固件只能用传统的关机调用
+            shutdown()
+        }
+        LINUX_REBOOT_CMD_RESTART => {
+            println!("[kernel] Restart requested by pid {}.", task.getpid());
+            system_reset(SRST_TYPE_COLD_REBOOT);
+            Errno::ENOSYS.neg()
+        }
+        _ => Errno::EINVAL.neg(),
+    }
+}
+
+/// syslog 的操作：读取缓冲区中的全部日志
+const SYSLOG_ACTION_READ_ALL: usize = 3;
+/// syslog 的操作：读取全部日志后清空缓冲区
+const SYSLOG_ACTION_READ_CLEAR: usize = 4;
+/// syslog 的操作：清空缓冲区
+const SYSLOG_ACTION_CLEAR: usize = 5;
+/// syslog 的操作：返回缓冲区中日志的字节数
+const SYSLOG_ACTION_SIZE_UNREAD: usize = 9;
+/// syslog 的操作：返回缓冲区的总大小
+const SYSLOG_ACTION_SIZE_BUFFER: usize = 10;
+
+/// 功能：读取或清空内核日志环形缓冲区，内核通过 println! 与 log 输出的内容都保存在其中。
+/// 参数：action 为 SYSLOG_ACTION_* 之一；读取时 buf 指向用户态缓冲区，len 为它的长度。
+/// 返回值：读取时返回写入 buf 的字节数，日志多于 len 字节时只写入最近的 len 字节；
+///      查询大小时返回字节数；清空时返回 0。清空日志需要与 reboot 相同的权限，否则返回 -EPERM；
+///      action 不合法时返回 -EINVAL；buf 不可写时返回 -EFAULT。
+/// syscall ID：116
+pub fn sys_syslog(action: usize, buf: *mut u8, len: usize) -> isize {
+    let privileged = current_task().unwrap().inner_exclusive_access().privileged;
+    match action {
+        SYSLOG_ACTION_READ_ALL | SYSLOG_ACTION_READ_CLEAR => {
+            if action == SYSLOG_ACTION_READ_CLEAR && !privileged {
+                return Errno::EPERM.neg();
+            }
+            let log = read_log(len, action == SYSLOG_ACTION_READ_CLEAR);
+            match copy_bytes_to_user(current_user_token(), buf, &log) {
+                Ok(()) => log.len() as isize,
+                Err(err) => err.neg(),
+            }
+        }
+        SYSLOG_ACTION_CLEAR => {
+            if !privileged {
+                return Errno::EPERM.neg();
+            }
+            clear_log();
+            0
+        }
+        SYSLOG_ACTION_SIZE_UNREAD => log_len() as isize,
+        SYSLOG_ACTION_SIZE_BUFFER => LOG_BUF_SIZE as isize,
+        _ => Errno::EINVAL.neg(),
+    }
+}
+
+//目前只支持设置当前进程自己（pid 为 0 表示当前进程）
+fn is_current_pid(pid: usize) -> bool {
+    pid == 0 || pid == current_task().unwrap().getpid()
+}
+
+/// mmap 的标志：映射在 fork 之后由父子进程共享
+pub const MAP_SHARED: usize = 0x01;
+/// mmap 的标志：映射在 fork 时复制（默认）
+pub const MAP_PRIVATE: usize = 0x02;
+
 // YOUR JOB: 扩展内核以实现 sys_mmap 和 sys_munmap
-pub fn sys_mmap(_start: usize, _len: usize, _port: usize) -> isize {
-    mmap(_start, _len, _port)
+// flags 为 MAP_SHARED 或 MAP_PRIVATE，为 0 时视为 MAP_PRIVATE；两者同时设置或含有其他位时返回 -EINVAL
+// port 不合法、len 为 0 或指定的区间越过用户地址空间时返回 -EINVAL，与已有映射重叠时返回 -EEXIST；
+// port 同时包含可写与可执行时违反 W^X 策略，返回 -EACCES；
+// 超出 RLIMIT_AS、找不到足够大的空闲区间时返回 -ENOMEM，MAP_SHARED 的页帧立即分配，物理内存不足时也返回 -ENOMEM
+pub fn sys_mmap(_start: usize, _len: usize, _port: usize, flags: usize) -> isize {
+    if flags & !(MAP_SHARED | MAP_PRIVATE) != 0 || flags == MAP_SHARED | MAP_PRIVATE {
+        return Errno::EINVAL.neg();
+    }
+    mmap(_start, _len, _port, flags == MAP_SHARED)
 }
 
+/// 功能：解除 [start, start + len) 的映射。区间的边界落在一段映射的中间时拆分这段映射，区间可以跨越多段映射。
+/// 参数：start 须按页对齐，len 向上取整到整页。
+/// 返回值：成功返回 0；start 未对齐、区间溢出、区间中有未映射的页面或区间与用户堆重叠时返回 -EINVAL。
+/// syscall ID：215
 pub fn sys_munmap(_start: usize, _len: usize) -> isize {
     munmap(_start, _len)
 }
 
+/// mremap 的标志：原地无法扩大时允许移动到新的地址
+pub const MREMAP_MAYMOVE: usize = 1;
+
+/// 功能：调整由 mmap 得到的映射中 [old_addr, old_addr + old_size) 这一部分的大小，它可以是一段映射的一部分，
+/// 此时先把它拆分出来。
+/// 缩小时回收多余的页面；扩大时若其后的地址空闲则原地扩大，否则在设置了 MREMAP_MAYMOVE 时
+/// 把映射连同其中的数据移动到其后第一段足够大的空闲区间，已分配的页帧直接映射到新的位置而不复制。
+/// 参数：old_addr 须按页对齐，[old_addr, old_addr + old_size) 须落在同一段映射中，new_size 为新的大小，
+///      flags 只支持 MREMAP_MAYMOVE。
+/// 返回值：调整后映射的起始地址；参数不合法时返回 -EINVAL；找不到对应的映射、无法扩大或超出 RLIMIT_AS 时返回 -ENOMEM。
+/// syscall ID：216
+pub fn sys_mremap(old_addr: usize, old_size: usize, new_size: usize, flags: usize) -> isize {
+    if flags & !MREMAP_MAYMOVE != 0 {
+        return Errno::EINVAL.neg();
+    }
+    mremap(old_addr, old_size, new_size, flags & MREMAP_MAYMOVE != 0)
+}
+
+/// 功能：将 [start, start + len) 中页面的权限改为 port，其编码与 mmap 相同。
+/// 区间的边界落在一段映射的中间时，这段映射被拆分为权限不同的几段；修改之后权限重新相同的相邻映射再合并。
+/// 参数：start 须按页对齐，port 的第 0、1、2 位分别表示可读、可写、可执行，其余位须为 0 且不能全为 0，
+///      可写与可执行不能同时设置（W^X）。
+/// 返回值：成功返回 0；参数不合法、区间溢出或区间与用户堆重叠时返回 -EINVAL；违反 W^X 时返回 -EACCES；
+/// 区间中有未映射的页面时返回 -ENOMEM。
+/// syscall ID：226
+pub fn sys_mprotect(start: usize, len: usize, port: usize) -> isize {
+    mprotect(start, len, port)
+}
+
+/// 功能：将用户堆扩大或缩小 size 字节，扩大时为新增的页面分配页帧，缩小时回收不再使用的页面。
+/// 参数：size 为堆大小的变化量，为 0 时只查询当前的 program break。
+/// 返回值：调整之前的 program break；堆会缩小到起始地址之下时返回 -EINVAL；
+/// 与其他映射重叠、超出 RLIMIT_AS 或物理内存不足时返回 -ENOMEM。
+/// syscall ID：214
+pub fn sys_sbrk(size: i32) -> isize {
+    sbrk(size)
+}
+
+/// spawn 的一项重定向：子进程的文件描述符 child_fd 指向父进程的文件描述符 parent_fd 所指的文件
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SpawnRedirect {
+    pub child_fd: usize,
+    pub parent_fd: usize,
+}
+
+//从用户地址空间读出 len 项重定向，并在当前进程的文件描述符表中找到它们指向的文件。
+//所有 parent_fd 都按调用者的文件描述符表解释，因此 {0 -> 1, 1 -> 0} 可以交换子进程的标准输入与标准输出
+fn translated_redirects(
+    token: usize,
+    redirects: *const SpawnRedirect,
+    len: usize,
+) -> Result<FdRedirects, Errno> {
+    if len > MAX_FDS {
+        return Err(Errno::EINVAL);
+    }
+    let mut entries: Vec<SpawnRedirect> = Vec::with_capacity(len);
+    for i in 0..len {
+        entries.push(copy_from_user(token, redirects.wrapping_add(i))?);
+    }
+    let process = current_task().unwrap().process.clone();
+    let inner = process.inner_exclusive_access();
+    entries
+        .iter()
+        .map(|entry| match inner.fd_table.get(entry.parent_fd) {
+            Some(Some(fd_entry)) if entry.child_fd < MAX_FDS => {
+                Ok((entry.child_fd, fd_entry.clone()))
+            }
+            _ => Err(Errno::EBADF),
+        })
+        .collect()
+}
+
 //
 // YOUR JOB: 实现 sys_spawn 系统调用
 // ALERT: 注意在实现 SPAWN 时不需要复制父进程地址空间，SPAWN != FORK + EXEC 
-pub fn sys_spawn(_path: *const u8) -> isize {
+// 与 sys_exec 一样按路径或者 PATH 查找可执行文件，
+// 出错时返回 -ENOENT、-EACCES、-ENOTDIR、-ENOEXEC、-ENOMEM、-EFAULT、-ENAMETOOLONG 或 -E2BIG，
+// 超出 RLIMIT_NPROC、PID 或内核栈已用完时返回 -EAGAIN
+// 子进程继承父进程的文件描述符表，之后按 redirects 中的 len 项依次重定向：
+// 重定向多于 MAX_FDS 项时返回 -EINVAL，parent_fd 未打开或 child_fd 不小于 MAX_FDS 时返回 -EBADF
+pub fn sys_spawn(
+    _path: *const u8,
+    args: *const usize,
+    envp: *const usize,
+    redirects: *const SpawnRedirect,
+    len: usize,
+) -> isize {
     let token = current_user_token();
-    let path = translated_str(token, _path);
-    if let Some(data) = get_app_data_by_name(path.as_str()) {
-        let task = current_task().unwrap().spawn(data);
-        let pid = task.pid.0 as isize;
-        add_task(task);
-        pid
-    } else {
-        -1
+    let (path, args, envs) = match translated_exec_args(token, _path, args, envp) {
+        Ok(exec_args) => exec_args,
+        Err(err) => return err.neg(),
+    };
+    let redirects = match translated_redirects(token, redirects, len) {
+        Ok(redirects) => redirects,
+        Err(err) => return err.neg(),
+    };
+    if nproc_exceeded() || !pid_available() || !kstack_available() {
+        return Errno::EAGAIN.neg();
+    }
+    let data = match read_exec_program(path.as_str(), envs.as_deref()) {
+        Ok(data) => data,
+        Err(err) => return err.neg(),
+    };
+    match current_task().unwrap().spawn(
+        program_name(path.as_str()),
+        data.as_slice(),
+        args,
+        envs,
+        redirects,
+    ) {
+        Ok(task) => {
+            let pid = task.getpid() as isize;
+            add_task(task);
+            pid
+        }
+        Err(err) => err.errno(),
+    }
+}
+
+/// 功能：导出最近的任务切换记录，用于观察调度行为。
+/// 参数：buf 指向用户态的 SwitchRecord 数组，len 为数组的长度。
+/// 返回值：写入的记录条数，记录按时间先后排列；记录多于 len 条时只写入最近的 len 条；buf 不可写时返回 -EFAULT。
+/// syscall ID：411
+pub fn sys_switch_trace(buf: *mut SwitchRecord, len: usize) -> isize {
+    let records = switch_trace();
+    let count = records.len().min(len);
+    match copy_slice_to_user(current_user_token(), buf, &records[records.len() - count..]) {
+        Ok(()) => count as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：列出所有尚未退出的进程，按 PID 从小到大排列。
+/// 参数：buf 指向用户态的 ProcInfo 数组，len 为数组的长度。
+/// 返回值：写入的进程条数，进程多于 len 个时只写入 PID 最小的 len 个；buf 不可写时返回 -EFAULT。
+/// syscall ID：413
+pub fn sys_ps(buf: *mut ProcInfo, len: usize) -> isize {
+    let now = get_time_us();
+    let infos: Vec<ProcInfo> = all_tasks()
+        .iter()
+        .take(len)
+        .map(|task| {
+            let inner = task.inner_exclusive_access();
+            let mut name = [0u8; TASK_NAME_LEN];
+            name[..inner.name.len()].copy_from_slice(inner.name.as_bytes());
+            ProcInfo {
+                pid: task.getpid(),
+                ppid: task
+                    .process
+                    .inner_exclusive_access()
+                    .parent
+                    .as_ref()
+                    .and_then(|parent| parent.upgrade())
+                    .map_or(0, |parent| parent.getpid()),
+                status: inner.task_status as usize,
+                priority: inner.priority,
+                name,
+                run_time: if inner.start_time == 0 {
+                    0
+                } else {
+                    (now - inner.start_time) / 1000
+                },
+            }
+        })
+        .collect();
+    match copy_slice_to_user(current_user_token(), buf, &infos) {
+        Ok(()) => infos.len() as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取调度统计信息，包括处理器的累计空闲时间。
+/// 参数：stats 指向保存结果的 SchedStats。
+/// 返回值：成功返回 0；stats 不可写时返回 -EFAULT。
+/// syscall ID：412
+pub fn sys_sched_stats(stats: *mut SchedStats) -> isize {
+    let (switches, idle_us) = sched_stats();
+    let result = SchedStats {
+        uptime_us: get_time_us(),
+        idle_us,
+        switches,
+    };
+    match copy_to_user(current_user_token(), stats, &result) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取页面置换的统计信息，包括访问位的收集情况、换入换出的页面数和时钟指针的位置。
+/// 参数：stats 指向保存结果的 SwapStats。
+/// 返回值：成功返回 0；stats 不可写时返回 -EFAULT。
+/// syscall ID：414
+pub fn sys_swap_stats(stats: *mut SwapStats) -> isize {
+    let page_stats = page_stats();
+    let (clock_pid, clock_vpn) = clock_hand();
+    let result = SwapStats {
+        harvests: page_stats.harvests,
+        scanned: page_stats.scanned,
+        accessed: page_stats.accessed,
+        dirty: page_stats.dirty,
+        swap_outs: page_stats.swap_outs,
+        swap_ins: page_stats.swap_ins,
+        swapped: swap_used(),
+        free_frames: frame_available(),
+        clock_pid,
+        clock_vpn: clock_vpn.0,
+    };
+    match copy_to_user(current_user_token(), stats, &result) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取内核各个 slab 对象缓存的统计信息。
+/// 参数：buf 指向用户态的 SlabInfo 数组，len 为数组的长度。
+/// 返回值：写入的缓存条数，缓存多于 len 个时只写入最先登记的 len 个；buf 不可写时返回 -EFAULT。
+/// syscall ID：415
+pub fn sys_slab_stats(buf: *mut SlabInfo, len: usize) -> isize {
+    let infos: Vec<SlabInfo> = slab_stats()
+        .iter()
+        .take(len)
+        .map(|stats| {
+            let mut name = [0u8; SLAB_NAME_LEN];
+            let len = stats.name.len().min(SLAB_NAME_LEN - 1);
+            name[..len].copy_from_slice(&stats.name.as_bytes()[..len]);
+            SlabInfo {
+                name,
+                object_size: stats.object_size,
+                objects_per_slab: stats.objects_per_slab,
+                slabs: stats.slabs,
+                in_use: stats.in_use,
+                allocs: stats.allocs,
+            }
+        })
+        .collect();
+    match copy_slice_to_user(current_user_token(), buf, &infos) {
+        Ok(()) => infos.len() as isize,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：获取物理内存、内核堆和当前进程的内存使用情况。
+/// 参数：info 指向保存结果的 MemInfo。
+/// 返回值：成功返回 0；info 不可写时返回 -EFAULT。
+/// syscall ID：416
+pub fn sys_meminfo(info: *mut MemInfo) -> isize {
+    let process = current_task().unwrap().process.clone();
+    let inner = process.inner_exclusive_access();
+    let (mapped_pages, swapped_pages) = inner.memory_set.page_counts();
+    let huge_pages = inner.memory_set.huge_pages();
+    let zero_pages = inner.memory_set.zero_pages();
+    drop(inner);
+    let (total_frames, free_frames, peak_frames) = frame_stats();
+    let (heap_total, heap_used) = heap_stats();
+    let result = MemInfo {
+        total_frames,
+        free_frames,
+        peak_frames,
+        heap_total,
+        heap_used,
+        mapped_pages,
+        swapped_pages,
+        huge_pages,
+        zero_pages,
+    };
+    match copy_to_user(current_user_token(), info, &result) {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
     }
 }
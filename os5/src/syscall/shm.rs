@@ -0,0 +1,86 @@
+//! System V 风格共享内存相关的系统调用
+
+use crate::config::PAGE_SIZE;
+use crate::errno::Errno;
+use crate::mm::{copy_to_user, IPC_CREAT, SHM_SEGMENTS};
+use crate::task::{current_user_token, reclaim_frames, shmat, shmdt};
+
+/// shmat 的标志：只读映射
+const SHM_RDONLY: usize = 0o10000;
+/// shmctl 的命令：删除共享内存段
+const IPC_RMID: usize = 0;
+/// shmctl 的命令：查询共享内存段的状态
+const IPC_STAT: usize = 2;
+
+/// 共享内存段的状态
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ShmStat {
+    /// 段的大小（字节）
+    pub size: usize,
+    /// 当前映射了这个段的次数
+    pub nattch: usize,
+}
+
+/// 功能：按键查找共享内存段，或者创建一个新的段，新段的内容全为 0。
+/// 参数：key 为 IPC_PRIVATE（0）时总是创建新段；flags 含 IPC_CREAT 时键不存在则创建，
+/// 同时含 IPC_EXCL 时键已存在则失败；size 为段的大小，查找已有的段时不能超过它的大小。
+/// 返回值：段的编号；键不存在时返回 -ENOENT，键已存在且指定了 IPC_EXCL 时返回 -EEXIST，
+/// size 不合法时返回 -EINVAL，物理内存不足时返回 -ENOMEM。
+/// syscall ID：194
+pub fn sys_shmget(key: usize, size: usize, flags: usize) -> isize {
+    if flags & IPC_CREAT != 0 {
+        reclaim_frames((size + PAGE_SIZE - 1) / PAGE_SIZE, false);
+    }
+    match SHM_SEGMENTS.exclusive_access().get(key, size, flags) {
+        Ok(id) => id as isize,
+        Err(errno) => errno.neg(),
+    }
+}
+
+/// 功能：将共享内存段映射到当前进程的地址空间，fork 出的子进程继承这一映射。
+/// 参数：id 为 shmget 返回的编号；addr 为映射的地址，须按页对齐，为 0 时由内核选择；
+/// flags 含 SHM_RDONLY 时映射为只读，否则可读写。
+/// 返回值：映射的地址；段不存在、addr 不合法或与已有映射重叠时返回 -EINVAL，超出 RLIMIT_AS 或物理内存不足以建立页表时返回 -ENOMEM。
+/// syscall ID：196
+pub fn sys_shmat(id: usize, addr: usize, flags: usize) -> isize {
+    shmat(id, addr, flags & SHM_RDONLY != 0)
+}
+
+/// 功能：解除 shmat 在 addr 处建立的映射，段被删除且没有映射时页帧随之回收。
+/// 返回值：成功返回 0；addr 处没有共享内存段的映射时返回 -EINVAL。
+/// syscall ID：197
+pub fn sys_shmdt(addr: usize) -> isize {
+    shmdt(addr)
+}
+
+/// 功能：删除共享内存段或查询它的状态。
+/// 参数：cmd 为 IPC_RMID 时删除段，此后不能再映射它，已有的映射在解除之前仍然有效；
+/// cmd 为 IPC_STAT 时将段的状态写入 buf 指向的 ShmStat。
+/// 返回值：成功返回 0；段不存在或 cmd 不合法时返回 -EINVAL，buf 不可写时返回 -EFAULT。
+/// syscall ID：195
+pub fn sys_shmctl(id: usize, cmd: usize, buf: *mut ShmStat) -> isize {
+    match cmd {
+        IPC_RMID => {
+            if SHM_SEGMENTS.exclusive_access().remove(id) {
+                0
+            } else {
+                Errno::EINVAL.neg()
+            }
+        }
+        IPC_STAT => {
+            let stat = match SHM_SEGMENTS.exclusive_access().segment(id) {
+                Some(segment) => ShmStat {
+                    size: segment.size(),
+                    nattch: segment.nattch(),
+                },
+                None => return Errno::EINVAL.neg(),
+            };
+            match copy_to_user(current_user_token(), buf, &stat) {
+                Ok(()) => 0,
+                Err(err) => err.neg(),
+            }
+        }
+        _ => Errno::EINVAL.neg(),
+    }
+}
@@ -0,0 +1,127 @@
+//! 互斥锁与 futex 相关的系统调用
+
+use crate::errno::Errno;
+use crate::mm::copy_from_user;
+use crate::sync::{Mutex, MutexBlocking, MutexSpin};
+use crate::task::{
+    block_current_and_run_next, current_task, current_user_token, signal_pending, WaitQueue,
+};
+use alloc::sync::Arc;
+
+const FUTEX_WAIT: usize = 0;
+const FUTEX_WAKE: usize = 1;
+/// futex 总是进程私有的，接受这一标志以兼容用户态线程库
+const FUTEX_PRIVATE_FLAG: usize = 128;
+
+/// 功能：创建一个互斥锁，进程中的所有线程以及 fork 出的子进程共享它。
+/// 参数：blocking 为真时创建阻塞互斥锁（支持优先级继承），否则创建自旋互斥锁。
+/// 返回值：互斥锁的编号。
+/// syscall ID：463
+pub fn sys_mutex_create(blocking: bool) -> isize {
+    let task = current_task().unwrap();
+    let mutex: Option<Arc<dyn Mutex>> = if !blocking {
+        Some(Arc::new(MutexSpin::new()))
+    } else {
+        Some(Arc::new(MutexBlocking::new()))
+    };
+    let mut process_inner = task.process.inner_exclusive_access();
+    if let Some(id) = process_inner
+        .mutex_list
+        .iter()
+        .enumerate()
+        .find(|(_, item)| item.is_none())
+        .map(|(id, _)| id)
+    {
+        process_inner.mutex_list[id] = mutex;
+        id as isize
+    } else {
+        process_inner.mutex_list.push(mutex);
+        process_inner.mutex_list.len() as isize - 1
+    }
+}
+
+/// 功能：获取互斥锁，锁被占用时阻塞（或让出 CPU）直到获得锁。
+/// 返回值：成功返回 0；编号不合法时返回 -EINVAL；等待时被信号打断返回 -EINTR。
+/// syscall ID：464
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let mutex = match get_mutex(mutex_id) {
+        Some(mutex) => mutex,
+        None => return Errno::EINVAL.neg(),
+    };
+    match mutex.lock() {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：释放互斥锁。
+/// 返回值：成功返回 0；编号不合法时返回 -EINVAL；锁未被持有或不由当前线程持有时返回 -EPERM。
+/// syscall ID：466
+pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
+    let mutex = match get_mutex(mutex_id) {
+        Some(mutex) => mutex,
+        None => return Errno::EINVAL.neg(),
+    };
+    match mutex.unlock() {
+        Ok(()) => 0,
+        Err(err) => err.neg(),
+    }
+}
+
+/// 功能：在用户地址 uaddr 处的 32 位变量上等待或唤醒，用户态锁只在发生竞争时才陷入内核。
+/// 参数：op 为 FUTEX_WAIT 时，若 *uaddr 仍等于 val 则阻塞当前线程，直到被 FUTEX_WAKE 唤醒；
+/// op 为 FUTEX_WAKE 时，按等待的先后唤醒至多 val 个在 uaddr 上等待的线程。
+/// 返回值：FUTEX_WAIT 被唤醒后返回 0，*uaddr 不等于 val 时返回 -EAGAIN，被信号打断时返回 -EINTR；
+/// FUTEX_WAKE 返回被唤醒的线程数；
+/// uaddr 未按 4 字节对齐或 op 不合法时返回 -EINVAL，FUTEX_WAIT 时 uaddr 不可读返回 -EFAULT。
+/// syscall ID：98
+pub fn sys_futex(uaddr: usize, op: usize, val: usize) -> isize {
+    if uaddr % core::mem::size_of::<u32>() != 0 {
+        return Errno::EINVAL.neg();
+    }
+    let task = current_task().unwrap();
+    match op & !FUTEX_PRIVATE_FLAG {
+        FUTEX_WAIT => {
+            //单核且内核态不可抢占，比较与入队之间不会有其他线程修改该变量或执行 FUTEX_WAKE
+            let value = match copy_from_user(current_user_token(), uaddr as *const u32) {
+                Ok(value) => value,
+                Err(err) => return err.neg(),
+            };
+            if value != val as u32 {
+                return Errno::EAGAIN.neg();
+            }
+            if signal_pending() {
+                return Errno::EINTR.neg();
+            }
+            let mut process_inner = task.process.inner_exclusive_access();
+            process_inner
+                .futex_queues
+                .entry(uaddr)
+                .or_insert_with(WaitQueue::new)
+                .push(task.clone());
+            drop(process_inner);
+            drop(task);
+            block_current_and_run_next();
+            //被 FUTEX_WAKE 唤醒时已经出队，仍在队列中说明是被信号唤醒的
+            let task = current_task().unwrap();
+            let queued = task
+                .process
+                .inner_exclusive_access()
+                .futex_unqueue(uaddr, &task);
+            if queued {
+                Errno::EINTR.neg()
+            } else {
+                0
+            }
+        }
+        FUTEX_WAKE => task.process.inner_exclusive_access().futex_wake(uaddr, val) as isize,
+        _ => Errno::EINVAL.neg(),
+    }
+}
+
+//取出互斥锁的一份引用，调用者在加锁/解锁前不再持有进程控制块的借用
+fn get_mutex(mutex_id: usize) -> Option<Arc<dyn Mutex>> {
+    let task = current_task().unwrap();
+    let process_inner = task.process.inner_exclusive_access();
+    process_inner.mutex_list.get(mutex_id)?.clone()
+}
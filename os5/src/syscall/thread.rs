@@ -0,0 +1,79 @@
+//! 线程相关的系统调用
+
+use crate::errno::Errno;
+use crate::mm::KERNEL_SPACE;
+use crate::task::{add_task, current_task, kstack_available};
+use crate::trap::{trap_handler, TrapContext};
+
+/// 功能：在当前进程中创建一个线程，从 entry 开始执行，使用内核分配的用户栈。
+/// 参数：entry 为线程函数的入口地址，arg 作为第一个参数通过 a0 传给它。
+/// 线程函数不能返回，必须调用 exit 结束线程。
+/// 返回值：新线程的线程号；物理内存不足时返回 -ENOMEM(-12)，内核栈已用完时返回 -EAGAIN(-11)。
+/// syscall ID：460
+pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    if !kstack_available() {
+        return Errno::EAGAIN.neg();
+    }
+    let task = current_task().unwrap();
+    let new_task = match task.new_thread() {
+        Ok(new_task) => new_task,
+        Err(err) => return err.neg(),
+    };
+    let new_tid = new_task.tid;
+    let ustack_top = task.process.inner_exclusive_access().ustack_top(new_tid);
+    let trap_cx = new_task.inner_exclusive_access().get_trap_cx();
+    *trap_cx = TrapContext::app_init_context(
+        entry,
+        ustack_top,
+        KERNEL_SPACE.exclusive_access().token(),
+        new_task.kernel_stack.get_top(),
+        trap_handler as usize,
+    );
+    trap_cx.x[10] = arg;
+    add_task(new_task);
+    new_tid as isize
+}
+
+/// 功能：获取当前线程的线程号。
+/// 返回值：线程号，主线程为 0。
+/// syscall ID：178
+pub fn sys_gettid() -> isize {
+    current_task().unwrap().tid as isize
+}
+
+/// 功能：登记当前线程的 clear_child_tid 地址，线程退出时内核向该地址写入一个 32 位的 0，
+/// 线程库可以据此得知线程已经结束。exec 之后登记被清除。
+/// 参数：tidptr 为用户地址，为 0 表示取消登记。
+/// 返回值：当前线程的线程号。
+/// syscall ID：96
+pub fn sys_set_tid_address(tidptr: usize) -> isize {
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().clear_child_tid = tidptr;
+    task.tid as isize
+}
+
+/// 功能：等待当前进程中的另一个线程退出并回收它的线程号。
+/// 参数：tid 为要等待的线程号。
+/// 返回值：等待的是自己时返回 -EINVAL；线程不存在时返回 -ESRCH；线程尚未退出时返回 -2，
+/// 由用户库让出 CPU 之后重试；否则返回线程的退出码。
+/// syscall ID：462
+pub fn sys_waittid(tid: usize) -> isize {
+    let task = current_task().unwrap();
+    if task.tid == tid {
+        return Errno::EINVAL.neg();
+    }
+    let mut process_inner = task.process.inner_exclusive_access();
+    let waited_task = match process_inner.get_task(tid) {
+        Some(waited_task) => waited_task,
+        None => return Errno::ESRCH.neg(),
+    };
+    let waited_inner = waited_task.inner_exclusive_access();
+    if !waited_inner.is_zombie() {
+        return -2;
+    }
+    let exit_code = waited_inner.exit_code;
+    drop(waited_inner);
+    process_inner.tasks[tid] = None;
+    process_inner.dealloc_tid(tid);
+    exit_code as isize
+}
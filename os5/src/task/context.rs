@@ -22,6 +22,16 @@ impl TaskContext {
             s: [0; 12],
         }
     }
+    /// 保存的寄存器 (寄存器编号, 值)：ra、sp 与 s0-s11
+    pub fn saved_regs(&self) -> [(usize, usize); 14] {
+        let mut regs = [(1, self.ra); 14];
+        regs[1] = (2, self.sp);
+        for (i, &s) in self.s.iter().enumerate() {
+            //s0、s1 为 x8、x9，s2-s11 为 x18-x27
+            regs[i + 2] = (if i < 2 { 8 + i } else { 16 + i }, s);
+        }
+        regs
+    }
     pub fn goto_trap_return(kstack_ptr: usize) -> Self {
         Self {
             ra: trap_return as usize,
@@ -0,0 +1,60 @@
+//! 进程因访存错误或非法指令被终止时，将寄存器与用户内存以文本形式输出到串口
+//!
+//! 每行都以 `[core]` 开头，便于从串口日志中筛选出来离线分析：
+//!
+//! ```text
+//! [core] begin pid=<pid> tid=<tid> signal=<signum> name=<name>
+//! [core] reg pc <hex>
+//! [core] reg x<i> <hex>                 （i 从 1 到 31）
+//! [core] page <va>                      （一个被转储的页面）
+//! [core] mem <va> <64 字节的十六进制>   （页面中全为 0 的行省略）
+//! [core] end pages=<n> truncated=<0|1>
+//! ```
+//!
+//! 内存部分的大小受 RLIMIT_CORE 限制，超出的页面不输出，并在 end 行中标记 truncated=1。
+
+use super::TaskControlBlock;
+use crate::config::PAGE_SIZE;
+use crate::mm::VirtAddr;
+use alloc::sync::Arc;
+
+/// mem 行包含的字节数
+const BYTES_PER_LINE: usize = 64;
+
+/// 输出 task 的 core dump，limit 为内存部分的字节数上限
+pub fn dump(task: &Arc<TaskControlBlock>, signum: usize, limit: usize) {
+    let trap_cx = *task.inner_exclusive_access().get_trap_cx();
+    println!(
+        "[core] begin pid={} tid={} signal={} name={}",
+        task.getpid(),
+        task.tid,
+        signum,
+        task.name()
+    );
+    println!("[core] reg pc {:#x}", trap_cx.sepc);
+    for (i, reg) in trap_cx.x.iter().enumerate().skip(1) {
+        println!("[core] reg x{} {:#x}", i, reg);
+    }
+    let pages = task.process.inner_exclusive_access().memory_set.user_pages();
+    let max_pages = limit / PAGE_SIZE;
+    for (vpn, ppn) in pages.iter().take(max_pages) {
+        let base: usize = VirtAddr::from(*vpn).into();
+        println!("[core] page {:#x}", base);
+        let bytes = ppn.get_bytes_array();
+        for (line, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+            if chunk.iter().all(|byte| *byte == 0) {
+                continue;
+            }
+            print!("[core] mem {:#x} ", base + line * BYTES_PER_LINE);
+            for byte in chunk {
+                print!("{:02x}", byte);
+            }
+            println!("");
+        }
+    }
+    println!(
+        "[core] end pages={} truncated={}",
+        pages.len().min(max_pages),
+        (pages.len() > max_pages) as usize
+    );
+}
@@ -2,68 +2,477 @@
 //它仅用于基于就绪队列管理流程和调度流程。
 //其他CPU进程监控功能在处理器中。
 
-use super::TaskControlBlock;
+use super::task::TaskControlBlockInner;
+use super::{SchedPolicy, TaskControlBlock};
 use crate::config;
 use crate::sync::UPSafeCell;
-use alloc::collections::VecDeque;
+use crate::timer::{get_time, get_time_us};
+use alloc::vec::Vec;
+use alloc::collections::{BTreeMap, BinaryHeap, VecDeque};
 use alloc::sync::Arc;
+use core::cmp::Ordering;
 use lazy_static::*;
 
-//TaskManager 将所有的任务控制块用引用计数 Arc 智能指针包裹后放在一个双端队列 VecDeque 中。 
-//使用智能指针的原因在于，任务控制块经常需要被放入/取出，如果直接移动任务控制块自身将会带来大量的数据拷贝开销， 
-//而对于智能指针进行移动则没有多少开销。
-//其次，允许任务控制块的共享引用在某些情况下能够让我们的实现更加方便。
-pub struct TaskManager {
-    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+/// 调度策略的抽象：就绪队列如何组织、下一个任务如何选出都由具体实现决定。
+pub trait Scheduler {
+    fn new() -> Self;
+    /// 将任务放入就绪队列
+    fn add(&mut self, task: Arc<TaskControlBlock>);
+    /// 选出下一个要运行的任务
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>>;
+    /// 放回刚被 fetch 选出、却没有运行的任务，撤销 fetch 的副作用，使它保持原来的位置
+    fn unfetch(&mut self, task: Arc<TaskControlBlock>) {
+        self.add(task);
+    }
+    /// 时钟中断到来时对当前正在运行的任务调用
+    fn on_tick(&mut self, _task: &Arc<TaskControlBlock>) {}
+    /// 当前正在运行的任务阻塞或睡眠、离开处理器时调用
+    fn on_block(&mut self, _task: &Arc<TaskControlBlock>) {}
+    /// 任务优先级被修改后调用
+    fn on_priority_change(&mut self, _task: &Arc<TaskControlBlock>, _prio: usize) {}
+    /// 提升在 `deadline`（微秒）之前就已进入就绪队列、一直没有被调度的任务
+    fn age(&mut self, _deadline: usize) {}
+}
+
+fn is_starving(task: &Arc<TaskControlBlock>, deadline: usize) -> bool {
+    task.inner_exclusive_access().ready_since < deadline
 }
 
-// YOUR JOB: FIFO->Stride
 /// A simple FIFO scheduler.
-impl TaskManager {
-    pub fn new() -> Self {
+//最简单的 RR 算法：add 将任务加入队尾，fetch 从队头取出。
+pub struct FifoScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl Scheduler for FifoScheduler {
+    fn new() -> Self {
         Self {
             ready_queue: VecDeque::new(),
         }
     }
-    ///将进程添加回就绪队列
-    //TaskManager 提供 add/fetch 两个操作，前者表示将一个任务加入队尾，后者则表示从队头中取出一个任务来执行。 
-    //从调度算法来看，这里用到的就是最简单的 RR 算法。
-    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
         self.ready_queue.push_back(task);
     }
-    ///将进程从就绪队列中取出
-    pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
-        let mut min_pass: usize = usize::MAX;
-        let mut idx = 0;
-        for i in 0..self.ready_queue.len() {
-            let task = &self.ready_queue[i];
-            let inner = task.inner_exclusive_access();
-            if i == 0 {
-                min_pass = inner.pass;
-                idx = i;
-            } else {
-                if ((inner.pass - min_pass) as i8) < 0 {
-                    min_pass = inner.pass;
-                    idx = i;
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.ready_queue.pop_front()
+    }
+    fn unfetch(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_front(task);
+    }
+}
+
+/// 就绪队列中的一项：入队时记录下任务的 pass 值，堆按照它来排序。
+//任务在就绪队列中时不会被调度，其 pass 不会变化，因此入队时的快照始终有效。
+struct StrideEntry {
+    pass: u64,
+    task: Arc<TaskControlBlock>,
+}
+
+impl StrideEntry {
+    fn new(task: Arc<TaskControlBlock>) -> Self {
+        let pass = task.inner_exclusive_access().pass;
+        Self { pass, task }
+    }
+}
+
+impl PartialEq for StrideEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for StrideEntry {}
+
+impl PartialOrd for StrideEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//BinaryHeap 是大根堆，这里把比较反过来，使 pass 最小的任务位于堆顶。
+//pass 允许回绕：只要就绪任务间的 pass 差值不超过 BIG_STRIDE / 2，
+//把差值解释为有符号数就能得到正确的先后关系。
+//pass 相同时 pid 小的优先，保证调度顺序不依赖于堆的内部布局。
+impl Ord for StrideEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.pass.wrapping_sub(self.pass) as i64)
+            .cmp(&0)
+            .then_with(|| other.task.getpid().cmp(&self.task.getpid()))
+    }
+}
+
+/// A stride scheduler backed by a min-heap.
+pub struct StrideScheduler {
+    ready_queue: BinaryHeap<StrideEntry>,
+}
+
+impl Scheduler for StrideScheduler {
+    fn new() -> Self {
+        Self {
+            ready_queue: BinaryHeap::new(),
+        }
+    }
+    //入堆的代价为 O(log n)。
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push(StrideEntry::new(task));
+    }
+    //堆顶即为 pass 最小的任务，取出后为其累加一个步长，代价为 O(log n)。
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let entry = self.ready_queue.pop()?;
+        let mut inner = entry.task.inner_exclusive_access();
+        let stride = config::BIG_STRIDE / inner.priority as u64;
+        inner.pass = inner.pass.wrapping_add(stride);
+        drop(inner);
+        Some(entry.task)
+    }
+    //退回 fetch 累加的步长
+    fn unfetch(&mut self, task: Arc<TaskControlBlock>) {
+        let mut inner = task.inner_exclusive_access();
+        let stride = config::BIG_STRIDE / inner.priority as u64;
+        inner.pass = inner.pass.wrapping_sub(stride);
+        drop(inner);
+        self.add(task);
+    }
+    //把饥饿任务的 pass 拉到当前最小的 pass，使它们尽快被调度；由于堆中的 pass 是快照，需要重建堆。
+    fn age(&mut self, deadline: usize) {
+        let min_pass = match self.ready_queue.peek() {
+            Some(entry) => entry.pass,
+            None => return,
+        };
+        let mut entries: Vec<StrideEntry> = core::mem::take(&mut self.ready_queue).into_vec();
+        for entry in entries.iter_mut() {
+            if is_starving(&entry.task, deadline) {
+                entry.task.inner_exclusive_access().pass = min_pass;
+                entry.pass = min_pass;
+            }
+        }
+        self.ready_queue = BinaryHeap::from(entries);
+    }
+}
+
+/// A multi-level feedback queue scheduler.
+//新任务进入最高级队列；在某一级上用完该级时间片的任务被降一级，
+//而经常主动让出 CPU 的交互式任务会一直停留在高级队列中。
+//为避免低级队列中的任务饿死，每隔 MLFQ_BOOST_INTERVAL 个时钟中断将所有任务提升回最高级。
+pub struct MlfqScheduler {
+    queues: [VecDeque<Arc<TaskControlBlock>>; config::MLFQ_LEVELS],
+    ticks_since_boost: usize,
+}
+
+impl MlfqScheduler {
+    fn boost(&mut self, current: &Arc<TaskControlBlock>) {
+        for level in 1..config::MLFQ_LEVELS {
+            while let Some(task) = self.queues[level].pop_front() {
+                let mut inner = task.inner_exclusive_access();
+                inner.mlfq_level = 0;
+                inner.mlfq_ticks = 0;
+                drop(inner);
+                self.queues[0].push_back(task);
+            }
+        }
+        let mut inner = current.inner_exclusive_access();
+        inner.mlfq_level = 0;
+        inner.mlfq_ticks = 0;
+    }
+}
+
+impl Scheduler for MlfqScheduler {
+    fn new() -> Self {
+        Self {
+            queues: core::array::from_fn(|_| VecDeque::new()),
+            ticks_since_boost: 0,
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        let level = task.inner_exclusive_access().mlfq_level;
+        self.queues[level].push_back(task);
+    }
+    //总是从最高的非空队列中取出任务
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.queues.iter_mut().find_map(|queue| queue.pop_front())
+    }
+    fn unfetch(&mut self, task: Arc<TaskControlBlock>) {
+        let level = task.inner_exclusive_access().mlfq_level;
+        self.queues[level].push_front(task);
+    }
+    //饥饿任务直接移入最高级队列
+    fn age(&mut self, deadline: usize) {
+        for level in 1..config::MLFQ_LEVELS {
+            let queue = core::mem::take(&mut self.queues[level]);
+            for task in queue {
+                if is_starving(&task, deadline) {
+                    let mut inner = task.inner_exclusive_access();
+                    inner.mlfq_level = 0;
+                    inner.mlfq_ticks = 0;
+                    drop(inner);
+                    self.queues[0].push_back(task);
+                } else {
+                    self.queues[level].push_back(task);
                 }
             }
-            drop(inner);
-            drop(task);
         }
-        let task = &self.ready_queue[idx];
+    }
+    fn on_tick(&mut self, task: &Arc<TaskControlBlock>) {
+        let mut inner = task.inner_exclusive_access();
+        inner.mlfq_ticks += 1;
+        if inner.mlfq_ticks >= config::MLFQ_TIME_SLICES[inner.mlfq_level] {
+            inner.mlfq_level = (inner.mlfq_level + 1).min(config::MLFQ_LEVELS - 1);
+            inner.mlfq_ticks = 0;
+        }
+        drop(inner);
+        self.ticks_since_boost += 1;
+        if self.ticks_since_boost >= config::MLFQ_BOOST_INTERVAL {
+            self.ticks_since_boost = 0;
+            self.boost(task);
+        }
+    }
+}
+
+/// A CFS-style fair scheduler.
+//每个任务的虚拟运行时间按 真实运行时间 * CFS_NICE0_PRIORITY / priority 增长，
+//优先级越高 vruntime 增长越慢，从而获得更多 CPU；每次总是选出 vruntime 最小的任务。
+//就绪队列以 (vruntime, pid) 为键，pid 保证键的唯一性。
+pub struct CfsScheduler {
+    ready_queue: BTreeMap<(u64, usize), Arc<TaskControlBlock>>,
+    /// 单调不减的最小 vruntime，新加入或长期未运行的任务从这里开始计时
+    min_vruntime: u64,
+}
+
+impl Scheduler for CfsScheduler {
+    fn new() -> Self {
+        Self {
+            ready_queue: BTreeMap::new(),
+            min_vruntime: 0,
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>) {
+        let mut inner = task.inner_exclusive_access();
+        // 结算上一次运行消耗的时间；阻塞的任务在离开处理器时已经结算过
+        settle_vruntime(&mut inner);
+        inner.vruntime = inner.vruntime.max(self.min_vruntime);
+        let key = (inner.vruntime, task.getpid());
+        drop(inner);
+        self.ready_queue.insert(key, task);
+    }
+    fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let key = *self.ready_queue.keys().next()?;
+        let task = self.ready_queue.remove(&key).unwrap();
+        let (vruntime, _) = key;
+        self.min_vruntime = self.min_vruntime.max(vruntime);
+        task.inner_exclusive_access().exec_start = get_time();
+        Some(task)
+    }
+    //没有运行过，不结算 vruntime
+    fn unfetch(&mut self, task: Arc<TaskControlBlock>) {
+        task.inner_exclusive_access().exec_start = 0;
+        self.add(task);
+    }
+    //阻塞期间不在运行，不能计入 vruntime：离开处理器时就结算，唤醒后从下一次被选中时重新计时
+    fn on_block(&mut self, task: &Arc<TaskControlBlock>) {
+        settle_vruntime(&mut task.inner_exclusive_access());
+    }
+}
+
+//把从 exec_start 开始的运行时间按优先级加权计入 vruntime，并停止计时
+fn settle_vruntime(inner: &mut TaskControlBlockInner) {
+    if inner.exec_start != 0 {
+        let delta = (get_time() - inner.exec_start) as u64;
+        inner.vruntime += delta * config::CFS_NICE0_PRIORITY / inner.priority as u64;
+        inner.exec_start = 0;
+    }
+}
+
+/// 实时任务的就绪队列：按实时优先级分组，同一优先级内先进先出。
+//SCHED_FIFO 与 SCHED_RR 的区别只在于时间片：RR 任务用完时间片后被放回本优先级队列的队尾，实现轮转。
+struct RtQueue {
+    queues: BTreeMap<usize, VecDeque<Arc<TaskControlBlock>>>,
+}
+
+impl RtQueue {
+    fn new() -> Self {
+        Self {
+            queues: BTreeMap::new(),
+        }
+    }
+    fn add(&mut self, task: Arc<TaskControlBlock>, rt_priority: usize) {
+        self.queues
+            .entry(rt_priority)
+            .or_insert_with(VecDeque::new)
+            .push_back(task);
+    }
+    //从最高实时优先级开始，选出第一个允许在 hart_id 号核上运行的任务
+    fn fetch(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+        let (rt_priority, pos) = self.queues.iter().rev().find_map(|(&rt_priority, queue)| {
+            queue
+                .iter()
+                .position(|task| task.inner_exclusive_access().can_run_on(hart_id))
+                .map(|pos| (rt_priority, pos))
+        })?;
+        let queue = self.queues.get_mut(&rt_priority).unwrap();
+        let task = queue.remove(pos);
+        if queue.is_empty() {
+            self.queues.remove(&rt_priority);
+        }
+        task
+    }
+    /// 就绪实时任务中最高的实时优先级，没有就绪实时任务时为 0
+    fn highest_priority(&self) -> usize {
+        self.queues.keys().next_back().copied().unwrap_or(0)
+    }
+}
+
+//调度器特性互斥，同时开启两个时实际使用哪个调度器取决于 cfg 的顺序，直接报错
+#[cfg(any(
+    all(feature = "sched-fifo", feature = "sched-mlfq"),
+    all(feature = "sched-fifo", feature = "sched-cfs"),
+    all(feature = "sched-mlfq", feature = "sched-cfs"),
+))]
+compile_error!("features sched-fifo, sched-mlfq and sched-cfs are mutually exclusive");
+
+#[cfg(feature = "sched-fifo")]
+type SchedulerImpl = FifoScheduler;
+#[cfg(feature = "sched-mlfq")]
+type SchedulerImpl = MlfqScheduler;
+#[cfg(feature = "sched-cfs")]
+type SchedulerImpl = CfsScheduler;
+#[cfg(not(any(feature = "sched-fifo", feature = "sched-mlfq", feature = "sched-cfs")))]
+type SchedulerImpl = StrideScheduler;
+
+/// 系统负载的定点数表示中小数部分的位数
+const FSHIFT: usize = 11;
+const FIXED_1: usize = 1 << FSHIFT;
+/// 每个采样周期的衰减系数 e^(-5s/1min)、e^(-5s/5min)、e^(-5s/15min)，以 FSHIFT 位定点数表示
+const LOADAVG_EXP: [usize; 3] = [1884, 2014, 2037];
+//空闲太久之后负载早已衰减到 0，补采样时最多补这么多个周期（15 分钟）
+const LOADAVG_MAX_CATCHUP: usize = 180;
+
+//TaskManager 将所有的任务控制块用引用计数 Arc 智能指针包裹后交给调度器 S 管理。
+//使用智能指针的原因在于，任务控制块经常需要被放入/取出，如果直接移动任务控制块自身将会带来大量的数据拷贝开销，
+//而对于智能指针进行移动则没有多少开销。
+//其次，允许任务控制块的共享引用在某些情况下能够让我们的实现更加方便。
+//TaskManager 还负责老化：每隔 AGING_INTERVAL 个时钟中断，让调度器提升等待过久的任务。
+//实时任务不经过调度器 S，而是放在单独的 RtQueue 中，并且总是先于普通任务被选出。
+//此外它记录就绪队列的长度，定期采样可运行的任务数，计算与 Linux 相同的 1、5、15 分钟系统负载。
+pub struct TaskManager<S: Scheduler> {
+    scheduler: S,
+    rt_queue: RtQueue,
+    ticks: usize,
+    /// 就绪队列中的任务数
+    nr_ready: usize,
+    /// 可运行任务数的指数移动平均，FSHIFT 位定点数
+    loadavg: [usize; 3],
+    /// 下一次采样的时刻（微秒）
+    next_loadavg_us: usize,
+}
+
+impl<S: Scheduler> TaskManager<S> {
+    pub fn new() -> Self {
+        Self {
+            scheduler: S::new(),
+            rt_queue: RtQueue::new(),
+            ticks: 0,
+            nr_ready: 0,
+            loadavg: [0; 3],
+            next_loadavg_us: config::LOADAVG_INTERVAL_US,
+        }
+    }
+    ///将进程添加回就绪队列
+    pub fn add(&mut self, task: Arc<TaskControlBlock>) {
         let mut inner = task.inner_exclusive_access();
-        let stride: u8 = (config::BIG_STRIDE as u8) / inner.priority;
-        inner.pass += stride as usize;
+        inner.ready_since = get_time_us();
+        let (policy, rt_priority) = (inner.sched_policy, inner.rt_priority);
         drop(inner);
-        drop(task);
-        self.ready_queue.remove(idx)
+        self.nr_ready += 1;
+        match policy {
+            SchedPolicy::Normal => self.scheduler.add(task),
+            SchedPolicy::Fifo | SchedPolicy::RoundRobin => self.rt_queue.add(task, rt_priority),
+        }
+    }
+    ///将进程从就绪队列中取出，只会取出允许在 hart_id 号核上运行的进程
+    pub fn fetch(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+        let task = self.fetch_ready(hart_id);
+        if task.is_some() {
+            self.nr_ready -= 1;
+        }
+        task
+    }
+    fn fetch_ready(&mut self, hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+        if let Some(task) = self.rt_queue.fetch(hart_id) {
+            return Some(task);
+        }
+        //跳过亲和性掩码不包含该核的任务，选出任务后再按相反的顺序把它们原样放回就绪队列
+        let mut skipped = Vec::new();
+        let task = loop {
+            match self.scheduler.fetch() {
+                Some(task) if !task.inner_exclusive_access().can_run_on(hart_id) => {
+                    skipped.push(task)
+                }
+                task => break task,
+            }
+        };
+        for task in skipped.into_iter().rev() {
+            self.scheduler.unfetch(task);
+        }
+        task
+    }
+    pub fn on_tick(&mut self, task: &Arc<TaskControlBlock>) {
+        if task.inner_exclusive_access().sched_policy == SchedPolicy::Normal {
+            self.scheduler.on_tick(task);
+        }
+        self.ticks += 1;
+        if self.ticks % config::AGING_INTERVAL == 0 {
+            let deadline = get_time_us().saturating_sub(config::AGING_THRESHOLD_US);
+            self.scheduler.age(deadline);
+        }
+        self.sample_load();
+    }
+    //时钟中断只在有任务运行时到来，可运行的任务数为就绪任务数加上当前任务。
+    //距上次采样经过了多个周期时，之前的周期里处理器是空闲的，按可运行任务数为 0 补上
+    fn sample_load(&mut self) {
+        let now = get_time_us();
+        if now < self.next_loadavg_us {
+            return;
+        }
+        let periods = (now - self.next_loadavg_us) / config::LOADAVG_INTERVAL_US + 1;
+        self.next_loadavg_us += periods * config::LOADAVG_INTERVAL_US;
+        for _ in 1..periods.min(LOADAVG_MAX_CATCHUP) {
+            self.update_loadavg(0);
+        }
+        self.update_loadavg(self.nr_ready + 1);
+    }
+    //load = load * exp + active * (1 - exp)
+    fn update_loadavg(&mut self, active: usize) {
+        let active = active * FIXED_1;
+        for (load, exp) in self.loadavg.iter_mut().zip(LOADAVG_EXP.iter()) {
+            *load = (*load * exp + active * (FIXED_1 - exp)) >> FSHIFT;
+        }
+    }
+    /// 1、5、15 分钟的系统负载，以 shift 位定点数表示
+    pub fn loadavg(&self, shift: usize) -> [usize; 3] {
+        self.loadavg.map(|load| (load << shift) >> FSHIFT)
+    }
+    pub fn on_priority_change(&mut self, task: &Arc<TaskControlBlock>, prio: usize) {
+        self.scheduler.on_priority_change(task, prio);
+    }
+    pub fn on_block(&mut self, task: &Arc<TaskControlBlock>) {
+        if task.inner_exclusive_access().sched_policy == SchedPolicy::Normal {
+            self.scheduler.on_block(task);
+        }
+    }
+    /// 就绪队列中是否有实时优先级高于 rt_priority 的任务
+    pub fn rt_preempts(&self, rt_priority: usize) -> bool {
+        self.rt_queue.highest_priority() > rt_priority
     }
 }
 
 lazy_static! {
     /// TASK_MANAGER instance through lazy_static!
-    pub static ref TASK_MANAGER: UPSafeCell<TaskManager> =
+    pub static ref TASK_MANAGER: UPSafeCell<TaskManager<SchedulerImpl>> =
         unsafe { UPSafeCell::new(TaskManager::new()) };
+    /// 所有尚未退出的进程，按 pid 索引
+    //就绪队列只能找到 Ready 状态的任务，Running 与 Blocked 的任务要通过它来查找。
+    pub static ref PID2TCB: UPSafeCell<BTreeMap<usize, Arc<TaskControlBlock>>> =
+        unsafe { UPSafeCell::new(BTreeMap::new()) };
 }
 
 //全局实例 TASK_MANAGER 提供给内核的其他子模块 add_task/fetch_task 两个函数。
@@ -71,6 +480,59 @@ pub fn add_task(task: Arc<TaskControlBlock>) {
     TASK_MANAGER.exclusive_access().add(task);
 }
 
-pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
-    TASK_MANAGER.exclusive_access().fetch()
+pub fn fetch_task(hart_id: usize) -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.exclusive_access().fetch(hart_id)
+}
+
+pub fn on_tick(task: &Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().on_tick(task);
+}
+
+pub fn on_priority_change(task: &Arc<TaskControlBlock>, prio: usize) {
+    TASK_MANAGER.exclusive_access().on_priority_change(task, prio);
+}
+
+pub fn on_block(task: &Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().on_block(task);
+}
+
+pub fn rt_preempts(rt_priority: usize) -> bool {
+    TASK_MANAGER.exclusive_access().rt_preempts(rt_priority)
+}
+
+pub fn loadavg(shift: usize) -> [usize; 3] {
+    TASK_MANAGER.exclusive_access().loadavg(shift)
+}
+
+//进程创建时加入 PID2TCB，退出时移除
+pub fn insert_into_pid2task(pid: usize, task: Arc<TaskControlBlock>) {
+    PID2TCB.exclusive_access().insert(pid, task);
+}
+
+pub fn remove_from_pid2task(pid: usize) {
+    let mut map = PID2TCB.exclusive_access();
+    if map.remove(&pid).is_none() {
+        panic!("cannot find pid {} in pid2task!", pid);
+    }
+}
+
+pub fn pid2task(pid: usize) -> Option<Arc<TaskControlBlock>> {
+    PID2TCB.exclusive_access().get(&pid).cloned()
+}
+
+/// 所有尚未退出的进程，按 pid 从小到大排列
+pub fn all_tasks() -> Vec<Arc<TaskControlBlock>> {
+    PID2TCB.exclusive_access().values().cloned().collect()
+}
+
+/// 同 all_tasks，进程表正在被借用时返回 None
+pub fn try_all_tasks() -> Option<Vec<Arc<TaskControlBlock>>> {
+    PID2TCB
+        .try_exclusive_access()
+        .map(|tasks| tasks.values().cloned().collect())
+}
+
+/// 尚未退出的进程数
+pub fn process_count() -> usize {
+    PID2TCB.exclusive_access().len()
 }
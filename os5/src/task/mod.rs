@@ -6,32 +6,84 @@
 
 
 mod context;
+mod core_dump;
 mod manager;
 mod pid;
+mod process;
 mod processor;
+mod ptrace;
+mod reclaim;
+mod rlimit;
+mod seccomp;
+mod signal;
 mod switch;
 #[allow(clippy::module_inception)]
 mod task;
+mod wait_queue;
 
-use crate::loader::get_app_data_by_name;
+use crate::config::SIGRETURN_TRAMPOLINE;
+use crate::errno::Errno;
+use crate::fs::block_cache_sync_all;
+use crate::loader::read_program;
+use crate::mm::copy_to_user;
+use crate::sync::Mutex;
+use crate::timer::{add_sleeper, get_time_us, remove_sleeper};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
-use manager::fetch_task;
+use manager::{
+    fetch_task, on_block, on_priority_change, on_tick, remove_from_pid2task, rt_preempts,
+};
 use switch::__switch;
-pub use task::{TaskControlBlock, TaskStatus};
+pub use task::{FdRedirects, SchedPolicy, TaskControlBlock, TaskStatus};
 
 pub use context::TaskContext;
-pub use manager::add_task;
-pub use pid::{pid_alloc, KernelStack, PidHandle};
+pub use manager::{
+    add_task, all_tasks, insert_into_pid2task, loadavg, pid2task, process_count, try_all_tasks,
+};
+pub use wait_queue::WaitQueue;
+pub use rlimit::{
+    initial_rlimits, rlimit_supported, Rlimit, RLIMIT_AS, RLIMIT_CORE, RLIMIT_CPU, RLIMIT_NPROC,
+    RLIMIT_RTPRIO, RLIMIT_STACK, RLIM_INFINITY, RLIM_NLIMITS,
+};
+pub use seccomp::{
+    SeccompAction, SeccompFilter, SECCOMP_BITMAP_BYTES, SECCOMP_MODE_ERRNO, SECCOMP_MODE_KILL,
+};
+pub use signal::{SignalAction, SignalFlags, MAX_SIG, SIGRETURN_CODE, SIG_DFL, SIG_IGN};
+pub use pid::{
+    kernel_stack_guard_slot, kernel_stack_position, kstack_alloc, kstack_available, pid_alloc,
+    pid_available, KernelStack, PidHandle, RecycleAllocator,
+};
+pub use process::{ProcessControlBlock, ProcessControlBlockInner};
 pub use processor::{
-    current_task, current_trap_cx, current_user_token, run_tasks, schedule, take_current_task,
+    current_task, current_trap_cx, current_trap_cx_user_va, current_user_satp, current_user_token, run_tasks,
+    schedule, take_current_task, try_current_task,
 
-    set_priority, mmap, munmap, update_syscall_times, get_run_time, get_syscall_times
+    set_priority, mmap, munmap, update_syscall_times, get_run_time, get_syscall_times, scheduler_tick,
+    consume_time_slice, set_affinity, get_affinity, hart_id, set_scheduler, get_scheduler,
+    switch_trace, try_recent_switches, sched_stats, SwitchReason, SwitchRecord, IDLE_PID,
+    inherit_priority, restore_priority,
+    get_rlimit, set_rlimit, nproc_exceeded, account_trap_enter, account_trap_return, get_times,
+    fault_in_user_page, sbrk, mprotect, mremap, shmat, shmdt, install_seccomp, seccomp_action,
+    take_fp_regs, discard_fp_regs,
+};
+pub use ptrace::{
+    next_pcs, ptrace, ptrace_detach, ptrace_stop, Ptrace, PTRACE_PEEKDATA, PTRACE_PEEKUSER,
 };
+pub use reclaim::{aging_tick, clock_hand, reclaim_frames};
 
 /// 暂停当前任务，并切换到下一个任务
 //当仅有一个任务的时候， suspend_current_and_run_next 的效果是会继续执行这个任务。
 pub fn suspend_current_and_run_next() {
+    suspend_current(SwitchReason::Yield);
+}
+
+/// 时钟中断到来时抢占当前任务，并切换到下一个任务
+pub fn preempt_current_and_run_next() {
+    suspend_current(SwitchReason::Preempt);
+}
+
+fn suspend_current(reason: SwitchReason) {
     // There must be an application running.
     //取出当前正在执行的任务
     let task = take_current_task().unwrap();
@@ -44,58 +96,429 @@ pub fn suspend_current_and_run_next() {
     task_inner.task_status = TaskStatus::Ready;
     drop(task_inner);
     // ---- release current PCB
+    let pid = task.getpid();
 
     // push back to ready queue.
     //将这个任务放入任务管理器的队尾
     add_task(task);
     // jump to scheduling cycle
     //调用 schedule 函数来触发调度并切换任务。
-    schedule(task_cx_ptr);
+    schedule(task_cx_ptr, pid, reason);
+}
+
+/// 阻塞当前任务并切换到下一个任务
+//调用者需要事先把当前任务放入某个等待队列，否则它将永远不会被唤醒。
+pub fn block_current_and_run_next() {
+    let task = take_current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    let task_cx_ptr = &mut task_inner.task_cx as *mut TaskContext;
+    task_inner.task_status = TaskStatus::Blocked;
+    drop(task_inner);
+    on_block(&task);
+    let pid = task.getpid();
+    // 当前任务的 Arc 由等待队列持有
+    drop(task);
+    schedule(task_cx_ptr, pid, SwitchReason::Block);
+}
+
+/// 唤醒一个处于 Blocked 状态的任务，将其放回就绪队列
+pub fn wakeup_task(task: Arc<TaskControlBlock>) {
+    let mut task_inner = task.inner_exclusive_access();
+    if task_inner.task_status != TaskStatus::Blocked {
+        return;
+    }
+    task_inner.task_status = TaskStatus::Ready;
+    drop(task_inner);
+    add_task(task);
+}
+
+/// 让当前任务睡眠 `ms` 毫秒，由时钟中断处理中的 check_sleepers 唤醒；
+/// 被信号打断时提前返回 Errno::EINTR
+pub fn sleep_current_and_run_next(ms: usize) -> Result<(), Errno> {
+    let expire_us = get_time_us().saturating_add(ms.saturating_mul(1000));
+    loop {
+        if signal_pending() {
+            return Err(Errno::EINTR);
+        }
+        if get_time_us() >= expire_us {
+            return Ok(());
+        }
+        add_sleeper(expire_us, current_task().unwrap());
+        block_current_and_run_next();
+        //提前醒来时睡眠队列中还留着这一项
+        remove_sleeper(&current_task().unwrap());
+    }
+}
+
+/// 当前任务是否有会打断阻塞的待处理信号，阻塞的系统调用据此提前返回 Errno::EINTR
+pub fn signal_pending() -> bool {
+    current_task().unwrap().inner_exclusive_access().signal_interrupts()
 }
 
 /// Exit current task, recycle process resources and switch to the next task
-//退出当前任务，回收进程资源并切换到下一个任务
+//退出当前线程并切换到下一个任务；主线程退出时整个进程随之退出，回收进程的资源
 pub fn exit_current_and_run_next(exit_code: i32) {
+    exit_current(exit_code, false);
+}
+
+/// 以 exit_code 终止当前线程所在的整个进程并切换到下一个任务，用于信号与 seccomp 的终止动作
+pub fn exit_current_process_and_run_next(exit_code: i32) {
+    exit_current(exit_code, true);
+}
+
+//whole_process 为假时只有主线程的退出会结束整个进程
+fn exit_current(exit_code: i32, whole_process: bool) {
+    //向 set_tid_address 登记的地址写入 0，等待该线程退出的线程库据此得知它已经结束。
+    //此时线程仍是当前任务，写入时可以按需分配或换入页面；地址不可写时直接忽略
+    let task = current_task().unwrap();
+    let clear_child_tid = task.inner_exclusive_access().clear_child_tid;
+    if clear_child_tid != 0 {
+        let _ = copy_to_user(task.get_user_token(), clear_child_tid as *mut u32, &0u32);
+    }
+    drop(task);
     // take from Processor
-    //调用 take_current_task 来将当前进程控制块从处理器监控 PROCESSOR 中取出，
-    //而不只是得到一份拷贝，这是为了正确维护进程控制块的引用计数
+    //调用 take_current_task 来将当前任务控制块从处理器监控 PROCESSOR 中取出，
+    //而不只是得到一份拷贝，这是为了正确维护任务控制块的引用计数
     let task = take_current_task().unwrap();
+    let tid = task.tid;
     // **** access current TCB exclusively
     let mut inner = task.inner_exclusive_access();
     // Change status to Zombie
-    //将进程控制块中的状态修改为 TaskStatus::Zombie 即僵尸进程
+    //将任务控制块中的状态修改为 TaskStatus::Zombie
     inner.task_status = TaskStatus::Zombie;
     // Record exit code
-    //将传入的退出码 exit_code 写入进程控制块中，后续父进程在 waitpid 的时候可以收集
+    //将传入的退出码 exit_code 写入任务控制块中，后续父进程在 waitpid 或同一进程的线程在 waittid 的时候可以收集
     inner.exit_code = exit_code;
+    drop(inner);
+    // **** release current TCB
+    let process = task.process.clone();
+    let pid = task.getpid();
+    let mut process_inner = process.inner_exclusive_access();
+    //唤醒一个在 clear_child_tid 上 futex 等待的线程
+    if clear_child_tid != 0 {
+        process_inner.futex_wake(clear_child_tid, 1);
+    }
+    if tid != 0 && !whole_process {
+        //普通线程只回收自己的用户栈与 Trap 上下文，线程号留到 waittid 时回收
+        process_inner.dealloc_user_res(tid);
+        let mutex_list = process_inner.mutex_list.clone();
+        drop(process_inner);
+        release_mutexes(&mutex_list);
+        drop(task);
+        let mut _unused = TaskContext::zero_init();
+        schedule(&mut _unused as *mut _, pid, SwitchReason::Exit);
+        return;
+    }
+    //由普通线程结束整个进程时，父进程回收的是主线程，退出码记在主线程上
+    if tid != 0 {
+        if let Some(main_thread) = process_inner.tasks[0].as_ref() {
+            main_thread.inner_exclusive_access().exit_code = exit_code;
+        }
+    }
+    //退出的进程不再能通过 pid 找到，但仍留在父进程的 children 中等待回收
+    remove_from_pid2task(pid);
+    //进程中的其他线程随之终止：仍在就绪队列中的会在被取出时丢弃，阻塞中的不会再被唤醒
+    for thread in process_inner.tasks.iter().flatten() {
+        if thread.tid != tid {
+            thread.inner_exclusive_access().task_status = TaskStatus::Zombie;
+        }
+    }
+    //线程持有进程的 Arc，清空线程列表以打破引用环；futex 等待队列中的线程同理。
+    //由普通线程结束进程时它还运行在自己的内核栈上，留在列表中，等父进程回收本进程时再释放
+    if tid == 0 {
+        process_inner.tasks.clear();
+    } else {
+        for slot in process_inner.tasks.iter_mut() {
+            if slot.as_ref().map_or(false, |thread| thread.tid != tid) {
+                *slot = None;
+            }
+        }
+    }
+    process_inner.futex_queues.clear();
+    //互斥锁可能与 fork 出的进程共享，进程中的线程持有的锁要交给其他等待者
+    let mutex_list = core::mem::take(&mut process_inner.mutex_list);
     // do not move to its parent but under initproc
 
-    // ++++++ access initproc TCB exclusively
+    // ++++++ access initproc PCB exclusively
     //将当前进程的所有子进程挂在初始进程 initproc 下面
     {
-        let mut initproc_inner = INITPROC.inner_exclusive_access();
-        for child in inner.children.iter() {
-            child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
+        let mut initproc_inner = INITPROC.process.inner_exclusive_access();
+        for child in process_inner.children.iter() {
+            child.process.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
             initproc_inner.children.push(child.clone());
         }
     }
     // ++++++ release parent PCB
+    //被过继的子进程中可能已经有僵尸进程，唤醒 initproc 来回收它们
+    if !process_inner.children.is_empty() {
+        INITPROC.process.child_exit.exclusive_access().wake_all();
+    }
+    //唤醒正在 waitpid 中等待的父进程
+    if let Some(parent) = process_inner.parent.as_ref().and_then(|parent| parent.upgrade()) {
+        parent.process.child_exit.exclusive_access().wake_all();
+    }
 
-    //将当前进程的孩子向量清空
-    inner.children.clear();
+    //将当前进程的孩子向量清空，它跟踪的子进程留到释放 inner 之后再解除跟踪
+    let children = core::mem::take(&mut process_inner.children);
     // deallocate user space
     //对于当前进程占用的资源进行早期回收
     //MemorySet::recycle_data_pages 只是将地址空间中的逻辑段列表 areas 清空，
     //这将导致应用地址空间的所有数据被存放在的物理页帧被回收，而用来存放页表的那些物理页帧此时则不会被回收。
-    inner.memory_set.recycle_data_pages();
-    drop(inner);
+    process_inner.memory_set.recycle_data_pages();
+    //关闭所有打开的文件：管道的另一端随即看到文件末尾或写入失败，不必等到父进程回收本进程。
+    //关闭管道时要唤醒等待者，先释放对进程控制块的借用
+    let fd_table = core::mem::take(&mut process_inner.fd_table);
+    drop(process_inner);
+    drop(fd_table);
+    release_mutexes(&mutex_list);
+    drop(mutex_list);
+    for child in children.iter() {
+        ptrace_detach(child);
+    }
+    drop(children);
+    //块缓存采用写回策略，进程退出时把它写过的块落盘
+    block_cache_sync_all();
     // **** release current PCB
+    drop(process);
     // drop task manually to maintain rc correctly
     drop(task);
     // we do not have to save task context
     let mut _unused = TaskContext::zero_init();
     //调用 schedule 触发调度及任务切换，我们再也不会回到该进程的执行过程，因此无需关心任务上下文的保存。
-    schedule(&mut _unused as *mut _);
+    schedule(&mut _unused as *mut _, pid, SwitchReason::Exit);
+}
+
+//释放已经退出的线程持有的互斥锁，调用者不能持有进程控制块的借用
+fn release_mutexes(mutex_list: &[Option<Arc<dyn Mutex>>]) {
+    for mutex in mutex_list.iter().flatten() {
+        mutex.release_exited();
+    }
+}
+
+/// 向进程发送编号为 signum 的信号
+//target 大于 0 时为目标进程的 pid；为 0 时表示调用者所在的进程组；
+//为 -1 时表示除调用者之外的所有进程；小于 -1 时表示进程组 -target。
+//signum 为 0 时只检查目标是否存在。initproc 与僵尸进程不接受信号。
+//信号在目标进程下一次返回用户态之前被处理。阻塞中的进程收到会打断阻塞的信号时被唤醒，
+//由阻塞的系统调用返回 EINTR；被跟踪而停下的进程只有 SIGKILL 能让它继续运行。
+pub fn send_signal(target: isize, signum: usize) -> isize {
+    let signal = match SignalFlags::from_signum(signum) {
+        Some(signal) => Some(signal),
+        None if signum == 0 => None,
+        None => return Errno::EINVAL.neg(),
+    };
+    let current = current_task().unwrap();
+    let current_pgid = current.process.inner_exclusive_access().pgid;
+    let targets: Vec<Arc<TaskControlBlock>> = all_tasks()
+        .into_iter()
+        .filter(|task| !Arc::ptr_eq(task, &INITPROC))
+        .filter(|task| {
+            let pgid = task.process.inner_exclusive_access().pgid;
+            !task.inner_exclusive_access().is_zombie()
+                && match target {
+                    -1 => !Arc::ptr_eq(&task.process, &current.process),
+                    0 => pgid == current_pgid,
+                    t if t > 0 => task.getpid() == t as usize,
+                    t => pgid == (-t) as usize,
+                }
+        })
+        .collect();
+    if targets.is_empty() {
+        return Errno::ESRCH.neg();
+    }
+    if let Some(signal) = signal {
+        for task in targets {
+            let mut inner = task.inner_exclusive_access();
+            inner.signals.insert(signal);
+            let wake = inner.task_status == TaskStatus::Blocked
+                && match inner.ptrace.stopped {
+                    true => signal == SignalFlags::SIGKILL,
+                    false => inner.signal_interrupts(),
+                };
+            if wake && inner.ptrace.stopped {
+                inner.ptrace.stopped = false;
+                inner.ptrace.stop_signal = None;
+            }
+            drop(inner);
+            if wake {
+                wakeup_task(task);
+            }
+        }
+    }
+    0
+}
+
+/// 设置进程 pid 的进程组，pid 为 0 表示当前进程，pgid 为 0 表示以目标进程的 pid 作为进程组 ID
+//只能设置当前进程或其子进程，会话首进程不能改变进程组；
+//只能新建以目标进程为组长的进程组，或者加入同一会话中已经存在的进程组。
+pub fn set_pgid(pid: usize, pgid: usize) -> isize {
+    let current = current_task().unwrap();
+    let target = if pid == 0 || pid == current.getpid() {
+        current.clone()
+    } else {
+        let process_inner = current.process.inner_exclusive_access();
+        match process_inner.children.iter().find(|child| child.getpid() == pid) {
+            Some(child) => child.clone(),
+            None => return Errno::ESRCH.neg(),
+        }
+    };
+    let target_pid = target.getpid();
+    let pgid = if pgid == 0 { target_pid } else { pgid };
+    let sid = target.process.inner_exclusive_access().sid;
+    if sid == target_pid || sid != current.process.inner_exclusive_access().sid {
+        return Errno::EPERM.neg();
+    }
+    if pgid != target_pid
+        && !all_tasks().iter().any(|task| {
+            let process_inner = task.process.inner_exclusive_access();
+            process_inner.pgid == pgid
+                && process_inner.sid == sid
+                && !task.inner_exclusive_access().is_zombie()
+        })
+    {
+        return Errno::EPERM.neg();
+    }
+    target.process.inner_exclusive_access().pgid = pgid;
+    0
+}
+
+/// 获取进程 pid 的进程组 ID，pid 为 0 表示当前进程
+pub fn get_pgid(pid: usize) -> isize {
+    let task = if pid == 0 {
+        current_task()
+    } else {
+        pid2task(pid)
+    };
+    match task {
+        Some(task) => task.process.inner_exclusive_access().pgid as isize,
+        None => Errno::ESRCH.neg(),
+    }
+}
+
+/// 当前进程新建一个会话，并成为其中唯一进程组的组长，返回新的会话 ID
+//进程组组长不能新建会话，否则原进程组中的其他进程会与组长处于不同的会话中。
+pub fn set_sid() -> isize {
+    let current = current_task().unwrap();
+    let pid = current.getpid();
+    if all_tasks()
+        .iter()
+        .any(|task| task.process.inner_exclusive_access().pgid == pid)
+    {
+        return Errno::EPERM.neg();
+    }
+    let mut process_inner = current.process.inner_exclusive_access();
+    process_inner.pgid = pid;
+    process_inner.sid = pid;
+    pid as isize
+}
+
+/// 获取进程 pid 的会话 ID，pid 为 0 表示当前进程
+pub fn get_sid(pid: usize) -> isize {
+    let task = if pid == 0 {
+        current_task()
+    } else {
+        pid2task(pid)
+    };
+    match task {
+        Some(task) => task.process.inner_exclusive_access().sid as isize,
+        None => Errno::ESRCH.neg(),
+    }
+}
+
+/// 向当前任务发送一个信号，用于缺页等由当前指令触发的信号
+pub fn current_add_signal(signal: SignalFlags) {
+    let task = current_task().unwrap();
+    task.inner_exclusive_access().signals.insert(signal);
+}
+
+/// 处理当前任务待处理且未被屏蔽的信号，在返回用户态之前调用
+//注册了处理函数的信号一次只处理一个：保存被打断的 Trap 上下文，让任务返回用户态后从处理函数开始执行，
+//处理函数返回到 SIGRETURN_TRAMPOLINE，由 sigreturn 恢复原来的上下文。处理期间不会嵌套处理其他信号。
+pub fn handle_signals() {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let mut blocked = inner.signal_mask;
+    if let Some(signum) = inner.handling_sig {
+        blocked |= inner.signal_actions[signum].mask | SignalFlags::from_signum(signum).unwrap();
+    }
+    blocked -= SignalFlags::unmaskable() | SignalFlags::synchronous();
+    let mut deliverable = inner.signals - blocked;
+    while let Some(signum) = deliverable.first_signum() {
+        let signal = SignalFlags::from_signum(signum).unwrap();
+        deliverable.remove(signal);
+        let action = inner.signal_actions[signum];
+        let catchable = !SignalFlags::unmaskable().contains(signal);
+        if catchable && action.handler == SIG_IGN && !SignalFlags::synchronous().contains(signal) {
+            inner.signals.remove(signal);
+            continue;
+        }
+        if catchable && action.handler != SIG_DFL && action.handler != SIG_IGN {
+            if inner.handling_sig.is_some() {
+                //同步信号无法等到当前处理函数返回，只能终止进程；其余信号保持待处理
+                if !SignalFlags::synchronous().contains(signal) {
+                    continue;
+                }
+            } else {
+                inner.signals.remove(signal);
+                inner.handling_sig = Some(signum);
+                let trap_cx = inner.get_trap_cx();
+                let backup = *trap_cx;
+                trap_cx.sepc = action.handler;
+                trap_cx.x[1] = SIGRETURN_TRAMPOLINE;
+                trap_cx.x[10] = signum;
+                inner.trap_cx_backup = Some(backup);
+                return;
+            }
+        }
+        if SignalFlags::default_terminate().contains(signal) {
+            drop(inner);
+            let core_limit = task.process.inner_exclusive_access().rlimits[RLIMIT_CORE].cur;
+            println!(
+                "[kernel] Application {} (pid {}) killed by signal {}.",
+                task.name(),
+                task.getpid(),
+                signum
+            );
+            if SignalFlags::default_core().contains(signal) && core_limit > 0 {
+                core_dump::dump(&task, signum, core_limit);
+            }
+            drop(task);
+            exit_current_process_and_run_next(-(signum as i32));
+            return;
+        }
+        //其余信号的默认动作为忽略
+        inner.signals.remove(signal);
+    }
+}
+
+/// 当前进程调用了过滤器拒绝的系统调用 id，以 SIGSYS 终止它，不经过信号处理
+pub fn seccomp_kill(id: usize) {
+    let task = current_task().unwrap();
+    println!(
+        "[kernel] Application {} (pid {}) killed by seccomp: syscall {}.",
+        task.name(),
+        task.getpid(),
+        id
+    );
+    drop(task);
+    let signum = SignalFlags::SIGSYS.first_signum().unwrap();
+    exit_current_process_and_run_next(-(signum as i32));
+}
+
+/// 从信号处理函数返回：恢复被信号打断时的 Trap 上下文，返回值即原来的 a0
+pub fn sigreturn() -> isize {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    match inner.trap_cx_backup.take() {
+        Some(backup) => {
+            inner.handling_sig = None;
+            *inner.get_trap_cx() = backup;
+            //浮点寄存器也回到被信号打断时的状态
+            discard_fp_regs();
+            backup.x[10] as isize
+        }
+        None => Errno::EINVAL.neg(),
+    }
 }
 
 //内核初始化完毕之后，即会调用 task 子模块提供的 add_initproc 函数来将初始进程 initproc 加入任务管理器，
@@ -106,14 +529,16 @@ lazy_static! {
     /// but we have user_shell, so we don't need to change it.
     //功能：调用 TaskControlBlock::new 来创建一个进程控制块，
     //参数：它需要传入 ELF 可执行文件的数据切片作为参数， 
-    //这可以通过加载器 loader 子模块提供的 get_app_data_by_name 接口查找 initproc 的 ELF 数据来获得。
-    pub static ref INITPROC: Arc<TaskControlBlock> = Arc::new(TaskControlBlock::new(
-        get_app_data_by_name("ch5b_initproc").unwrap()
-    ));
+    //这可以通过加载器 loader 子模块提供的 read_program 接口从文件系统中读出 initproc 的 ELF 数据来获得。
+    pub static ref INITPROC: Arc<TaskControlBlock> = TaskControlBlock::new(
+        "ch5b_initproc",
+        read_program("ch5b_initproc", &[]).unwrap().as_slice()
+    );
 }
 
 //在初始化 INITPROC 之后，
 //调用 task 的任务管理器 manager 子模块提供的 add_task 接口将进程控制块加入到任务管理器。
 pub fn add_initproc() {
+    insert_into_pid2task(INITPROC.getpid(), INITPROC.clone());
     add_task(INITPROC.clone());
 }
@@ -1,44 +1,66 @@
 // 任务pid实现。
-// 将PID分配给此处的进程。同时，应用程序内核堆栈的位置根据PID确定。
+// 将PID分配给此处的进程。内核栈的位置由单独分配的槽位决定，与 PID 无关。
 
-use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE};
+use crate::config::{KERNEL_STACK_SIZE, KERNEL_STACK_SLOTS, PAGE_SIZE, PID_MAX, TRAMPOLINE};
+use crate::errno::Errno;
 use crate::mm::{MapPermission, VirtAddr, KERNEL_SPACE};
 use crate::sync::UPSafeCell;
 use alloc::vec::Vec;
 use lazy_static::*;
 
-//实现一个同样使用简单栈式分配策略的进程标识符分配器 PidAllocator ，并将其全局实例化为 PID_ALLOCATOR
-/// Process identifier allocator using stack allocation
+/// 位图中的字数
+const PID_WORDS: usize = (PID_MAX + 63) / 64;
+
+//进程标识符分配器 PidAllocator 用位图记录 PID 的占用情况，并被全局实例化为 PID_ALLOCATOR。
+//总是分配编号最小的空闲 PID，因此 PID 不会无限增长，回收后的复用顺序也是确定的。
+/// Process identifier allocator backed by a bitmap
 struct PidAllocator {
-    /// A new PID to be assigned
-    current: usize,
-    /// Recycled PID sequence
-    recycled: Vec<usize>,
+    /// 第 i 位为 1 表示 PID i 已被占用
+    bitmap: [u64; PID_WORDS],
+    /// 下标小于它的字都已经占满，分配时从这里开始查找
+    first_free: usize,
+    /// 已分配的 PID 数
+    used: usize,
 }
 
 impl PidAllocator {
     pub fn new() -> Self {
         PidAllocator {
-            current: 0,
-            recycled: Vec::new(),
+            bitmap: [0; PID_WORDS],
+            first_free: 0,
+            used: 0,
         }
     }
-    pub fn alloc(&mut self) -> PidHandle {
-        if let Some(pid) = self.recycled.pop() {
-            PidHandle(pid)
-        } else {
-            self.current += 1;
-            PidHandle(self.current - 1)
+    pub fn alloc(&mut self) -> Option<usize> {
+        while self.first_free < PID_WORDS {
+            let word = self.bitmap[self.first_free];
+            if word != u64::MAX {
+                let pid = self.first_free * 64 + word.trailing_ones() as usize;
+                if pid >= PID_MAX {
+                    return None;
+                }
+                self.bitmap[self.first_free] |= 1 << (pid % 64);
+                self.used += 1;
+                return Some(pid);
+            }
+            self.first_free += 1;
         }
+        None
     }
     pub fn dealloc(&mut self, pid: usize) {
-        assert!(pid < self.current);
+        assert!(pid < PID_MAX);
+        let (word, bit) = (pid / 64, 1 << (pid % 64));
         assert!(
-            !self.recycled.iter().any(|ppid| *ppid == pid),
+            self.bitmap[word] & bit != 0,
             "pid {} has been deallocated!",
             pid
         );
-        self.recycled.push(pid);
+        self.bitmap[word] &= !bit;
+        self.used -= 1;
+        self.first_free = self.first_free.min(word);
+    }
+    pub fn available(&self) -> bool {
+        self.used < PID_MAX
     }
 }
 
@@ -53,10 +75,20 @@ lazy_static! {
 //这里将其抽象为一个 PidHandle 类型，当它的生命周期结束后，对应的整数会被编译器自动回收：
 pub struct PidHandle(pub usize);
 
-// PidAllocator::alloc 将会分配出去一个将 usize 包装之后的 PidHandle 。
-// 我们将其包装为一个全局分配进程标识符的接口 pid_alloc
+// PidAllocator::alloc 将会分配出去一个 PID，我们将其包装为 PidHandle，
+// 作为一个全局分配进程标识符的接口 pid_alloc。调用者应当先用 pid_available 检查是否还有空闲的 PID
 pub fn pid_alloc() -> PidHandle {
-    PID_ALLOCATOR.exclusive_access().alloc()
+    PidHandle(
+        PID_ALLOCATOR
+            .exclusive_access()
+            .alloc()
+            .expect("PID space exhausted"),
+    )
+}
+
+/// 是否还有空闲的 PID
+pub fn pid_available() -> bool {
+    PID_ALLOCATOR.exclusive_access().available()
 }
 
 //同时我们也需要为 PidHandle 实现 Drop Trait 来允许编译器进行自动的资源回收
@@ -67,34 +99,132 @@ impl Drop for PidHandle {
     }
 }
 
+/// 进程内线程号的分配器，回收的线程号按后进先出的顺序复用，每个进程各有一个
+pub struct RecycleAllocator {
+    current: usize,
+    recycled: Vec<usize>,
+}
 
-/// KernelStack corresponding to PID
-//我们将应用编号替换为进程标识符来决定每个进程内核栈在地址空间中的位置。
-//在内核栈 KernelStack 中保存着它所属进程的 PID
+impl RecycleAllocator {
+    pub fn new() -> Self {
+        RecycleAllocator {
+            current: 0,
+            recycled: Vec::new(),
+        }
+    }
+    pub fn alloc(&mut self) -> usize {
+        if let Some(id) = self.recycled.pop() {
+            id
+        } else {
+            self.current += 1;
+            self.current - 1
+        }
+    }
+    pub fn dealloc(&mut self, id: usize) {
+        assert!(id < self.current);
+        assert!(
+            !self.recycled.iter().any(|i| *i == id),
+            "id {} has been deallocated!",
+            id
+        );
+        self.recycled.push(id);
+    }
+}
+
+/// 内核栈槽位的分配器，每个线程占用一个槽位
+//槽位号决定内核栈在内核地址空间中的位置，回收的槽位优先复用，因此槽位号总是小于 KERNEL_STACK_SLOTS
+pub struct KernelStackAllocator {
+    slots: RecycleAllocator,
+    /// 正在使用的槽位数
+    used: usize,
+}
+
+impl KernelStackAllocator {
+    pub fn new() -> Self {
+        KernelStackAllocator {
+            slots: RecycleAllocator::new(),
+            used: 0,
+        }
+    }
+    pub fn alloc(&mut self) -> Option<usize> {
+        if self.used == KERNEL_STACK_SLOTS {
+            return None;
+        }
+        self.used += 1;
+        Some(self.slots.alloc())
+    }
+    pub fn dealloc(&mut self, slot: usize) {
+        self.slots.dealloc(slot);
+        self.used -= 1;
+    }
+    pub fn available(&self) -> bool {
+        self.used < KERNEL_STACK_SLOTS
+    }
+}
+
+lazy_static! {
+    static ref KSTACK_ALLOCATOR: UPSafeCell<KernelStackAllocator> =
+        unsafe { UPSafeCell::new(KernelStackAllocator::new()) };
+}
+
+/// 分配一个槽位并在其上建立内核栈，调用者应当先用 kstack_available 检查是否还有空闲的槽位。
+/// 物理内存不足时归还槽位并返回 Errno::ENOMEM
+pub fn kstack_alloc() -> Result<KernelStack, Errno> {
+    let slot = KSTACK_ALLOCATOR
+        .exclusive_access()
+        .alloc()
+        .expect("kernel stack slots exhausted");
+    KernelStack::new(slot)
+}
+
+/// 是否还有空闲的内核栈槽位
+pub fn kstack_available() -> bool {
+    KSTACK_ALLOCATOR.exclusive_access().available()
+}
+
+/// Kernel stack of a thread, placed by its slot id
+//内核栈 KernelStack 中保存着它占用的槽位号，释放时一并归还槽位
 pub struct KernelStack {
-    pid: usize,
+    slot: usize,
 }
 
 /// Return (bottom, top) of a kernel stack in kernel space.
-//根据进程标识符计算内核栈在内核地址空间中的位置
-pub fn kernel_stack_position(app_id: usize) -> (usize, usize) {
-    let top = TRAMPOLINE - app_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
+//根据槽位号计算内核栈在内核地址空间中的位置，相邻的内核栈之间留有一个保护页
+pub fn kernel_stack_position(slot: usize) -> (usize, usize) {
+    let top = TRAMPOLINE - slot * (KERNEL_STACK_SIZE + PAGE_SIZE);
     let bottom = top - KERNEL_STACK_SIZE;
     (bottom, top)
 }
 
+/// 若 addr 落在某个内核栈下方的保护页中，返回该内核栈的槽位号
+//每个槽位占据 [top - KERNEL_STACK_SIZE - PAGE_SIZE, top)，其中最低的一页是不映射的保护页，
+//内核栈溢出时首先访问到它而触发缺页异常，不会破坏相邻的内核栈
+pub fn kernel_stack_guard_slot(addr: usize) -> Option<usize> {
+    if addr >= TRAMPOLINE {
+        return None;
+    }
+    let slot = (TRAMPOLINE - 1 - addr) / (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let (bottom, _) = kernel_stack_position(slot);
+    if slot < KERNEL_STACK_SLOTS && addr < bottom && addr >= bottom - PAGE_SIZE {
+        Some(slot)
+    } else {
+        None
+    }
+}
+
 impl KernelStack {
-    //new 方法可以从一个 PidHandle ，也就是一个已分配的进程标识符中对应生成一个内核栈 KernelStack
-    pub fn new(pid_handle: &PidHandle) -> Self {
-        let pid = pid_handle.0;
-        let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(pid);
+    //new 方法在槽位 slot 对应的位置生成一个内核栈 KernelStack，槽位由 KernelStackAllocator 分配
+    //物理内存不足时 stack 在返回前被回收，槽位随之归还
+    pub fn new(slot: usize) -> Result<Self, Errno> {
+        let stack = KernelStack { slot };
+        let (kernel_stack_bottom, kernel_stack_top) = kernel_stack_position(slot);
         //将一个逻辑段插入内核地址空间 KERNEL_SPACE 中
-        KERNEL_SPACE.exclusive_access().insert_framed_area(
+        let result = KERNEL_SPACE.exclusive_access().insert_framed_area(
             kernel_stack_bottom.into(),
             kernel_stack_top.into(),
             MapPermission::R | MapPermission::W,
         );
-        KernelStack { pid: pid_handle.0 }
+        result.map(|_| stack)
     }
     #[allow(unused)]
     /// Push a variable of type T into the top of the KernelStack and return its raw pointer
@@ -110,9 +240,12 @@ impl KernelStack {
         }
         ptr_mut
     }
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
     //获取当前内核栈顶在内核地址空间中的地址。
     pub fn get_top(&self) -> usize {
-        let (_, kernel_stack_top) = kernel_stack_position(self.pid);
+        let (_, kernel_stack_top) = kernel_stack_position(self.slot);
         kernel_stack_top
     }
 }
@@ -123,10 +256,11 @@ impl KernelStack {
 //为此在 MemorySet 中新增了一个名为 remove_area_with_start_vpn 的方法
 impl Drop for KernelStack {
     fn drop(&mut self) {
-        let (kernel_stack_bottom, _) = kernel_stack_position(self.pid);
+        let (kernel_stack_bottom, _) = kernel_stack_position(self.slot);
         let kernel_stack_bottom_va: VirtAddr = kernel_stack_bottom.into();
         KERNEL_SPACE
             .exclusive_access()
             .remove_area_with_start_vpn(kernel_stack_bottom_va.into());
+        KSTACK_ALLOCATOR.exclusive_access().dealloc(self.slot);
     }
 }
\ No newline at end of file
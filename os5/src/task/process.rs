@@ -0,0 +1,275 @@
+//! Implementation of [`ProcessControlBlock`]
+//!
+//! 进程是资源分配的单位：地址空间、PID、父子关系、文件描述符表以及互斥锁由进程中的所有线程共享；
+//! 线程（[`TaskControlBlock`]）是调度的单位，各自拥有 Trap 上下文、任务上下文、内核栈与用户栈。
+
+use super::{
+    initial_rlimits, PidHandle, RecycleAllocator, Rlimit, SeccompFilter, TaskControlBlock,
+    TaskStatus, WaitQueue, RLIM_NLIMITS,
+};
+use crate::config::{
+    MAX_FDS, PAGE_SIZE, SIGRETURN_TRAMPOLINE, TRAP_CONTEXT, USER_HEAP_BASE, USER_STACK_RESERVE,
+    USER_STACK_SIZE,
+};
+use crate::errno::Errno;
+use crate::fs::{FdEntry, OpenFlags, Stdin, Stdout};
+use crate::mm::{MapPermission, MemorySet, PhysPageNum, VirtAddr, VirtPageNum};
+use crate::sync::{Mutex, UPRefMut, UPSafeCell};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Process control block structure
+pub struct ProcessControlBlock {
+    // immutable
+    /// 进程标识符
+    pub pid: PidHandle,
+    /// 在 waitpid 中等待子进程退出的线程
+    //与 inner 分开加锁，子进程退出时可以在持有自身 inner 的同时唤醒父进程。
+    pub child_exit: UPSafeCell<WaitQueue>,
+    // mutable
+    inner: UPSafeCell<ProcessControlBlockInner>,
+}
+
+/// 进程中所有线程共享、会在运行中改变的资源
+//父子关系中的进程用它们的主线程来表示，进程的退出码、信号等状态也记录在主线程中。
+pub struct ProcessControlBlockInner {
+    ///  表示应用地址空间。
+    pub memory_set: MemorySet,
+    //应用数据仅有可能出现在应用地址空间低于 base_size 字节的区域中。
+    pub base_size: usize,
+    /// 主线程用户栈预留区域的底部，其余线程的用户栈预留区域按线程号依次排在它的上方
+    pub ustack_base: usize,
+    /// 指向父进程的主线程（如果存在的话）。
+    /// 注意我们使用 Weak 而非 Arc 来包裹另一个任务控制块，因此这个智能指针将不会影响父进程的引用计数。
+    pub parent: Option<Weak<TaskControlBlock>>,
+    /// 所有子进程的主线程
+    pub children: Vec<Arc<TaskControlBlock>>,
+    /// 按文件描述符索引的文件描述符表，已关闭的位置为 None；fork 出的子进程得到一份拷贝，指向相同的文件
+    pub fd_table: Vec<Option<FdEntry>>,
+    /// 按线程号索引的线程，已被回收的位置为 None；进程退出时清空，以打破与线程之间的引用环
+    pub tasks: Vec<Option<Arc<TaskControlBlock>>>,
+    /// 线程号分配器，主线程的线程号总是 0
+    pub task_res_allocator: RecycleAllocator,
+    /// 进程创建的互斥锁，被进程中的所有线程以及 fork 出的子进程共享
+    pub mutex_list: Vec<Option<Arc<dyn Mutex>>>,
+    /// 按用户虚拟地址索引的 futex 等待队列，没有等待者的地址不占用表项
+    pub futex_queues: BTreeMap<usize, WaitQueue>,
+    /// 用户堆的底部
+    pub heap_bottom: usize,
+    /// 用户堆的顶部（program break），[heap_bottom, program_brk) 为堆中可用的部分
+    pub program_brk: usize,
+    /// 进程组 ID，等于进程组组长的 pid
+    pub pgid: usize,
+    /// 会话 ID，等于会话首进程的 pid
+    pub sid: usize,
+    /// 环境变量，每一项形如 "NAME=value"；fork 时复制，exec/spawn 未指定时沿用
+    pub environ: Vec<String>,
+    /// 资源限制，按资源编号索引，fork/spawn 时继承
+    pub rlimits: [Rlimit; RLIM_NLIMITS],
+    /// 系统调用过滤器，为 None 时不限制；对进程中的所有线程生效，fork/spawn 时继承，exec 后保留
+    pub seccomp: Option<SeccompFilter>,
+    /// 时钟中断到来时进程中有线程正在运行的次数，用来估计进程占用的 CPU 时间
+    pub cpu_ticks: usize,
+}
+
+impl ProcessControlBlockInner {
+    pub fn get_user_token(&self) -> usize {
+        self.memory_set.token()
+    }
+    pub fn alloc_tid(&mut self) -> usize {
+        self.task_res_allocator.alloc()
+    }
+    pub fn dealloc_tid(&mut self, tid: usize) {
+        self.task_res_allocator.dealloc(tid)
+    }
+    /// 文件描述符表中最小的空闲文件描述符，必要时扩大文件描述符表；已经打开了 MAX_FDS 个文件时返回 Errno::EMFILE
+    pub fn alloc_fd(&mut self) -> Result<usize, Errno> {
+        self.alloc_fd_from(0)
+    }
+    /// 不小于 min 的最小的空闲文件描述符，必要时扩大文件描述符表；没有这样的文件描述符时返回 Errno::EMFILE
+    pub fn alloc_fd_from(&mut self, min: usize) -> Result<usize, Errno> {
+        if let Some(fd) = (min..self.fd_table.len()).find(|&fd| self.fd_table[fd].is_none()) {
+            return Ok(fd);
+        }
+        let fd = self.fd_table.len().max(min);
+        if fd >= MAX_FDS {
+            return Err(Errno::EMFILE);
+        }
+        self.fd_table.resize(fd + 1, None);
+        Ok(fd)
+    }
+    pub fn get_task(&self, tid: usize) -> Option<Arc<TaskControlBlock>> {
+        self.tasks.get(tid)?.clone()
+    }
+    //记录线程号为 tid 的线程
+    pub fn attach_task(&mut self, tid: usize, task: Arc<TaskControlBlock>) {
+        while self.tasks.len() < tid + 1 {
+            self.tasks.push(None);
+        }
+        self.tasks[tid] = Some(task);
+    }
+    //尚未退出的线程数
+    pub fn live_thread_count(&self) -> usize {
+        self.tasks
+            .iter()
+            .flatten()
+            .filter(|task| task.inner_exclusive_access().task_status != TaskStatus::Zombie)
+            .count()
+    }
+    //为线程 tid 映射用户栈与 Trap 上下文，主线程的这两者在加载 ELF 时就已经映射好了。
+    //物理内存不足时不留下任何映射，返回 Errno::ENOMEM
+    pub fn alloc_user_res(&mut self, tid: usize) -> Result<(), Errno> {
+        let ustack_top = self.ustack_top(tid);
+        self.memory_set.insert_framed_area(
+            (ustack_top - USER_STACK_SIZE).into(),
+            ustack_top.into(),
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        )?;
+        let trap_cx_bottom = trap_cx_bottom_from_tid(tid);
+        let result = self.memory_set.insert_framed_area(
+            trap_cx_bottom.into(),
+            (trap_cx_bottom + PAGE_SIZE).into(),
+            MapPermission::R | MapPermission::W,
+        );
+        if result.is_err() {
+            let ustack_top_va: VirtAddr = ustack_top.into();
+            self.memory_set
+                .remove_area_with_end_vpn(ustack_top_va.into());
+        }
+        result
+    }
+    //线程退出时解除其用户栈与 Trap 上下文的映射
+    pub fn dealloc_user_res(&mut self, tid: usize) {
+        //用户栈可能已经向下增长过，按栈顶找到它
+        let ustack_top_va: VirtAddr = self.ustack_top(tid).into();
+        self.memory_set
+            .remove_area_with_end_vpn(ustack_top_va.into());
+        let trap_cx_bottom_va: VirtAddr = trap_cx_bottom_from_tid(tid).into();
+        self.memory_set
+            .remove_area_with_start_vpn(trap_cx_bottom_va.into());
+    }
+    //线程 tid 的 Trap 上下文所在的物理页帧
+    pub fn trap_cx_ppn(&self, tid: usize) -> PhysPageNum {
+        let trap_cx_bottom_va: VirtAddr = trap_cx_bottom_from_tid(tid).into();
+        self.memory_set
+            .translate(trap_cx_bottom_va.into())
+            .unwrap()
+            .ppn()
+    }
+    //线程 tid 的用户栈栈顶
+    pub fn ustack_top(&self, tid: usize) -> usize {
+        ustack_bottom_from_tid(self.ustack_base, tid) + USER_STACK_RESERVE
+    }
+    /// vpn 落在某个线程用户栈的预留区域中、位于当前栈底之下时，将该用户栈向下扩大到包含 vpn，
+    /// 扩大后的大小不能超过 limit 字节。返回是否扩大成功。
+    pub fn grow_user_stack(&mut self, vpn: VirtPageNum, limit: usize) -> bool {
+        let va = VirtAddr::from(vpn).0;
+        if va < self.ustack_base {
+            return false;
+        }
+        let tid = (va - self.ustack_base) / (PAGE_SIZE + USER_STACK_RESERVE);
+        //预留区域之间的保护页不属于任何用户栈
+        if va >= ustack_bottom_from_tid(self.ustack_base, tid) + USER_STACK_RESERVE
+            || self.get_task(tid).is_none()
+        {
+            return false;
+        }
+        let ustack_top = self.ustack_top(tid);
+        ustack_top - va <= limit
+            && self
+                .memory_set
+                .grow_down(VirtAddr::from(ustack_top).into(), vpn)
+    }
+    /// 唤醒至多 count 个在 uaddr 上等待的线程，返回被唤醒的线程数
+    pub fn futex_wake(&mut self, uaddr: usize, count: usize) -> usize {
+        let queue = match self.futex_queues.get_mut(&uaddr) {
+            Some(queue) => queue,
+            None => return 0,
+        };
+        let woken = queue.wake(count);
+        if queue.is_empty() {
+            self.futex_queues.remove(&uaddr);
+        }
+        woken
+    }
+    //把被信号唤醒的线程移出 uaddr 上的等待队列，返回它是否还在队列中
+    pub fn futex_unqueue(&mut self, uaddr: usize, task: &Arc<TaskControlBlock>) -> bool {
+        let queue = match self.futex_queues.get_mut(&uaddr) {
+            Some(queue) => queue,
+            None => return false,
+        };
+        let queued = queue.remove(task);
+        if queue.is_empty() {
+            self.futex_queues.remove(&uaddr);
+        }
+        queued
+    }
+}
+
+impl ProcessControlBlock {
+    //新进程自成一个进程组与会话，资源限制为初始值；fork/spawn 随后用父进程的设置覆盖它们
+    pub fn new(pid: PidHandle, memory_set: MemorySet, user_sp: usize) -> Self {
+        let pid_num = pid.0;
+        Self {
+            pid,
+            child_exit: unsafe { UPSafeCell::new(WaitQueue::new()) },
+            inner: unsafe {
+                UPSafeCell::new(ProcessControlBlockInner {
+                    memory_set,
+                    base_size: user_sp,
+                    ustack_base: user_sp - USER_STACK_RESERVE,
+                    parent: None,
+                    children: Vec::new(),
+                    fd_table: vec![
+                        // 0 -> stdin
+                        Some(FdEntry::new(Arc::new(Stdin), OpenFlags::RDONLY)),
+                        // 1 -> stdout
+                        Some(FdEntry::new(Arc::new(Stdout), OpenFlags::WRONLY)),
+                        // 2 -> stderr
+                        Some(FdEntry::new(Arc::new(Stdout), OpenFlags::WRONLY)),
+                    ],
+                    tasks: Vec::new(),
+                    task_res_allocator: RecycleAllocator::new(),
+                    mutex_list: Vec::new(),
+                    futex_queues: BTreeMap::new(),
+                    heap_bottom: USER_HEAP_BASE,
+                    program_brk: USER_HEAP_BASE,
+                    pgid: pid_num,
+                    sid: pid_num,
+                    environ: Vec::new(),
+                    rlimits: initial_rlimits(),
+                    seccomp: None,
+                    cpu_ticks: 0,
+                })
+            },
+        }
+    }
+    pub fn inner_exclusive_access(&self) -> UPRefMut<'_, ProcessControlBlockInner> {
+        self.inner.exclusive_access()
+    }
+    /// inner 已经被借用时返回 None
+    pub fn try_inner_exclusive_access(&self) -> Option<UPRefMut<'_, ProcessControlBlockInner>> {
+        self.inner.try_exclusive_access()
+    }
+    pub fn getpid(&self) -> usize {
+        self.pid.0
+    }
+}
+
+/// 线程 tid 的 Trap 上下文在应用地址空间中的位置
+//主线程使用 TRAP_CONTEXT，其余线程的 Trap 上下文从 sigreturn 跳板页下方开始向下排列
+pub fn trap_cx_bottom_from_tid(tid: usize) -> usize {
+    if tid == 0 {
+        TRAP_CONTEXT
+    } else {
+        SIGRETURN_TRAMPOLINE - tid * PAGE_SIZE
+    }
+}
+
+//线程 tid 的用户栈预留区域的底部，相邻的预留区域之间留有一个保护页
+fn ustack_bottom_from_tid(ustack_base: usize, tid: usize) -> usize {
+    ustack_base + tid * (PAGE_SIZE + USER_STACK_SIZE)
+}
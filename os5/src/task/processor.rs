@@ -3,15 +3,89 @@
 // 在这里，用户应用程序在CPU中持续运行，记录CPU的当前运行状态，并执行不同应用程序控制流的替换和转移。
 
 use super::__switch;
-use super::{fetch_task, TaskStatus};
-use super::{TaskContext, TaskControlBlock};
+use super::process::trap_cx_bottom_from_tid;
+use super::reclaim_frames;
+use super::{fetch_task, on_priority_change, on_tick, rt_preempts, SchedPolicy, TaskStatus};
+use super::{all_tasks, Rlimit, SignalFlags, TaskContext, TaskControlBlock};
+use super::{RLIMIT_AS, RLIMIT_CPU, RLIMIT_NPROC, RLIMIT_RTPRIO, RLIMIT_STACK};
+use super::{SeccompAction, SeccompFilter};
+use crate::errno::Errno;
 use crate::sync::UPSafeCell;
 use crate::trap::TrapContext;
-use alloc::sync::Arc;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::cmp::Ordering;
 use lazy_static::*;
 
 use crate::{config, mm, timer};
 
+/// 切换跟踪记录中表示“没有上一个任务”的 pid
+pub const IDLE_PID: usize = usize::MAX;
+
+/// 任务让出处理器的原因
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(usize)]
+pub enum SwitchReason {
+    /// 主动让出（sys_yield）
+    Yield = 0,
+    /// 时间片用完或被实时任务抢占
+    Preempt = 1,
+    /// 在等待队列上阻塞
+    Block = 2,
+    /// 退出
+    Exit = 3,
+}
+
+/// 一次任务切换的记录，布局与用户库中的 SwitchRecord 一致
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct SwitchRecord {
+    /// 切换发生的时刻（微秒）
+    pub time_us: usize,
+    /// 让出处理器的任务，首次调度时为 IDLE_PID
+    pub from_pid: usize,
+    /// 被调度上处理器的任务
+    pub to_pid: usize,
+    /// SwitchReason 的数值
+    pub reason: usize,
+}
+
+/// 固定大小的环形缓冲区，写满后覆盖最旧的记录
+struct SwitchTrace {
+    records: [SwitchRecord; config::SWITCH_TRACE_LEN],
+    /// 下一条记录写入的位置
+    next: usize,
+    /// 有效记录条数
+    len: usize,
+}
+
+impl SwitchTrace {
+    fn new() -> Self {
+        Self {
+            records: [SwitchRecord {
+                time_us: 0,
+                from_pid: IDLE_PID,
+                to_pid: IDLE_PID,
+                reason: 0,
+            }; config::SWITCH_TRACE_LEN],
+            next: 0,
+            len: 0,
+        }
+    }
+    fn push(&mut self, record: SwitchRecord) {
+        self.records[self.next] = record;
+        self.next = (self.next + 1) % config::SWITCH_TRACE_LEN;
+        self.len = (self.len + 1).min(config::SWITCH_TRACE_LEN);
+    }
+    //按时间先后返回所有有效记录
+    fn records(&self) -> Vec<SwitchRecord> {
+        let first = self.next + config::SWITCH_TRACE_LEN - self.len;
+        (0..self.len)
+            .map(|i| self.records[(first + i) % config::SWITCH_TRACE_LEN])
+            .collect()
+    }
+}
+
 /// Processor management structure
 //处理器管理结构 Processor 负责维护从任务管理器 TaskManager 分离出去的那部分 CPU 状态：
 pub struct Processor {
@@ -19,6 +93,17 @@ pub struct Processor {
     current: Option<Arc<TaskControlBlock>>,
     /// 表示当前处理器上的 idle 控制流的任务上下文的地址。
     idle_task_cx: TaskContext,
+    /// 上一个让出处理器的任务及其原因，在下一个任务被调度上来时写入切换记录
+    switched_out: Option<(usize, SwitchReason)>,
+    /// 任务切换跟踪记录
+    trace: SwitchTrace,
+    /// 启动以来发生的任务切换总次数
+    switch_count: usize,
+    /// 没有就绪任务、处理器空闲的累计时间（微秒）
+    idle_time_us: usize,
+    /// 处理器上的浮点寄存器中是哪个任务的状态，切换任务时不保存浮点寄存器，
+    /// 返回用户态时发现它们属于其他任务才从 Trap 上下文中恢复
+    fp_owner: Option<Weak<TaskControlBlock>>,
 }
 
 impl Processor {
@@ -26,6 +111,11 @@ impl Processor {
         Self {
             current: None,
             idle_task_cx: TaskContext::zero_init(),
+            switched_out: None,
+            trace: SwitchTrace::new(),
+            switch_count: 0,
+            idle_time_us: 0,
+            fp_owner: None,
         }
     }
     fn get_idle_task_cx_ptr(&mut self) -> *mut TaskContext {
@@ -41,6 +131,11 @@ impl Processor {
     }
 }
 
+/// 当前处理器核的编号，单核环境下总是 0
+pub fn hart_id() -> usize {
+    0
+}
+
 //在单核环境下，我们仅创建单个 Processor 的全局实例 PROCESSOR
 lazy_static! {
     /// PROCESSOR instance through lazy_static!
@@ -56,27 +151,81 @@ lazy_static! {
 pub fn run_tasks() {
     loop {
         let mut processor = PROCESSOR.exclusive_access();
-        if let Some(task) = fetch_task() {
+        if let Some(task) = fetch_task(hart_id()) {
             let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
             // access coming task TCB exclusively
             let mut task_inner = task.inner_exclusive_access();
+            //所属进程已经退出的线程不再运行，直接丢弃
+            if task_inner.task_status == TaskStatus::Zombie {
+                continue;
+            }
             let next_task_cx_ptr = &task_inner.task_cx as *const TaskContext;
             task_inner.task_status = TaskStatus::Running;
+            task_inner.refill_time_slice();
+            //第一次被调度时记录开始运行的时刻
+            if task_inner.start_time == 0 {
+                task_inner.start_time = timer::get_time_us();
+            }
+            //不在处理器上的时间不计入 utime/stime
+            task_inner.last_timestamp = timer::get_time_us();
             drop(task_inner);
             // release coming task TCB manually
+            let (from_pid, reason) = processor
+                .switched_out
+                .take()
+                .unwrap_or((IDLE_PID, SwitchReason::Yield));
+            processor.trace.push(SwitchRecord {
+                time_us: timer::get_time_us(),
+                from_pid,
+                to_pid: task.getpid(),
+                reason: reason as usize,
+            });
+            processor.switch_count += 1;
             processor.current = Some(task);
             // release processor manually
             drop(processor);
             unsafe {
                 __switch(idle_task_cx_ptr, next_task_cx_ptr);
             }
+        } else {
+            drop(processor);
+            idle();
         }
     }
 }
 
+//没有就绪任务时，用 wfi 让处理器停下来等待中断，而不是反复空转调用 fetch_task。
+//内核态下 sstatus.SIE 为 0，中断不会打断内核，但只要 sie 中打开了中断，它到来时 wfi 就会返回。
+//这时短暂打开 SIE，待处理的时钟中断、外部中断（例如终端输入）立即经由内核态的 trap 入口处理：
+//此处没有持有任何资源，被中断打断是安全的。先 wfi 再打开 SIE，不会错过两者之间到来的中断
+#[cfg(not(feature = "deterministic"))]
+fn idle() {
+    let start = timer::get_time_us();
+    unsafe {
+        riscv::asm::wfi();
+        riscv::register::sstatus::set_sie();
+        riscv::register::sstatus::clear_sie();
+    }
+    PROCESSOR.exclusive_access().idle_time_us += timer::get_time_us() - start;
+}
+
+//确定性模式下没有真实的时钟中断，wfi 将永远不会返回，只能在这里推进伪时钟并轮询外部中断
+#[cfg(feature = "deterministic")]
+fn idle() {
+    let start = timer::get_time_us();
+    timer::check_sleepers();
+    crate::drivers::handle_external_interrupt();
+    PROCESSOR.exclusive_access().idle_time_us += timer::get_time_us() - start;
+}
+
 /// Get current task through take, leaving a None in its place
+//任务只在被换出处理器时才会被取下，此时把这段内核态时间记入它的 stime
 pub fn take_current_task() -> Option<Arc<TaskControlBlock>> {
-    PROCESSOR.exclusive_access().take_current()
+    let task = PROCESSOR.exclusive_access().take_current();
+    if let Some(task) = task.as_ref() {
+        task.inner_exclusive_access().account_stime();
+    }
+    task
 }
 
 /// Get a copy of the current task
@@ -84,11 +233,22 @@ pub fn current_task() -> Option<Arc<TaskControlBlock>> {
     PROCESSOR.exclusive_access().current()
 }
 
+/// 同 current_task，PROCESSOR 正在被借用时也返回 None，供内核调试器在任意位置停下时使用
+pub fn try_current_task() -> Option<Arc<TaskControlBlock>> {
+    PROCESSOR.try_exclusive_access()?.current()
+}
+
 /// Get token of the address space of current task
 pub fn current_user_token() -> usize {
     let task = current_task().unwrap();
-    let token = task.inner_exclusive_access().get_user_token();
-    token
+    task.get_user_token()
+}
+
+/// 返回用户态时写入 satp 的值，其中包括当前进程地址空间的 ASID
+pub fn current_user_satp() -> usize {
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    process_inner.memory_set.satp()
 }
 
 /// Get the mutable reference to trap context of current task
@@ -99,11 +259,38 @@ pub fn current_trap_cx() -> &'static mut TrapContext {
         .get_trap_cx()
 }
 
+/// 返回用户态前调用：处理器上的浮点寄存器不是当前任务的状态时返回 true，
+/// 此时需要从 Trap 上下文中恢复，之后它们属于当前任务
+pub fn take_fp_regs() -> bool {
+    let mut processor = PROCESSOR.exclusive_access();
+    let task = processor.current().unwrap();
+    let owned = processor
+        .fp_owner
+        .as_ref()
+        .map_or(false, |owner| owner.as_ptr() == Arc::as_ptr(&task));
+    if !owned {
+        processor.fp_owner = Some(Arc::downgrade(&task));
+    }
+    !owned
+}
+
+/// 内核改写了当前任务 Trap 上下文中的浮点寄存器（例如 exec 与 sigreturn），返回用户态时需要重新加载
+pub fn discard_fp_regs() {
+    PROCESSOR.exclusive_access().fp_owner = None;
+}
+
+/// 当前线程的 Trap 上下文在应用地址空间中的位置
+pub fn current_trap_cx_user_va() -> usize {
+    trap_cx_bottom_from_tid(current_task().unwrap().tid)
+}
+
 /// Return to idle control flow for new scheduling
 //当一个应用交出 CPU 使用权时，进入内核后它会调用 schedule 函数来切换到 idle 控制流并开启新一轮的任务调度。
 //切换回去之后，我们将跳转到 Processor::run 中 __switch 返回之后的位置，也即开启了下一轮循环。
-pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
+//pid 与 reason 说明是哪个任务因为什么原因让出了处理器，用于记录任务切换。
+pub fn schedule(switched_task_cx_ptr: *mut TaskContext, pid: usize, reason: SwitchReason) {
     let mut processor = PROCESSOR.exclusive_access();
+    processor.switched_out = Some((pid, reason));
     let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
     drop(processor);
     unsafe {
@@ -112,12 +299,153 @@ pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
 }
 
 
+/// 返回 (任务切换总次数, 累计空闲时间)
+pub fn sched_stats() -> (usize, usize) {
+    let processor = PROCESSOR.exclusive_access();
+    (processor.switch_count, processor.idle_time_us)
+}
+
+/// 按时间先后返回最近的任务切换记录
+pub fn switch_trace() -> Vec<SwitchRecord> {
+    PROCESSOR.exclusive_access().trace.records()
+}
+
+/// 把最近的若干条任务切换记录按时间先后复制到 out 中，返回复制的条数。
+/// 不分配内存，PROCESSOR 正被借用时返回 0，供 panic 处理使用
+pub fn try_recent_switches(out: &mut [SwitchRecord]) -> usize {
+    let processor = match PROCESSOR.try_exclusive_access() {
+        Some(processor) => processor,
+        None => return 0,
+    };
+    let trace = &processor.trace;
+    let count = out.len().min(trace.len);
+    let first = trace.next + config::SWITCH_TRACE_LEN - count;
+    for (i, record) in out[..count].iter_mut().enumerate() {
+        *record = trace.records[(first + i) % config::SWITCH_TRACE_LEN];
+    }
+    count
+}
+
+//时钟中断到来时通知调度器，当前任务又用掉了一个时间片
+pub fn scheduler_tick() {
+    if let Some(task) = current_task() {
+        on_tick(&task);
+        charge_cpu_tick(&task);
+    }
+}
+
+//把这次时钟中断计入当前进程的 CPU 时间，并检查 RLIMIT_CPU：
+//超过硬限制时发送 SIGKILL，超过软限制后每满一秒发送一次 SIGXCPU
+fn charge_cpu_tick(task: &Arc<TaskControlBlock>) {
+    let mut process_inner = task.process.inner_exclusive_access();
+    process_inner.cpu_ticks += 1;
+    let limit = process_inner.rlimits[RLIMIT_CPU];
+    let cpu_ticks = process_inner.cpu_ticks;
+    drop(process_inner);
+    if cpu_ticks % timer::TICKS_PER_SEC != 0 {
+        return;
+    }
+    let seconds = cpu_ticks / timer::TICKS_PER_SEC;
+    let mut inner = task.inner_exclusive_access();
+    if seconds >= limit.max {
+        inner.signals.insert(SignalFlags::SIGKILL);
+    } else if seconds >= limit.cur {
+        inner.signals.insert(SignalFlags::SIGXCPU);
+    }
+}
+
+//从用户态陷入内核时调用，结算当前任务的用户态时间
+pub fn account_trap_enter() {
+    if let Some(task) = current_task() {
+        task.inner_exclusive_access().account_utime();
+    }
+}
+
+//即将返回用户态时调用，结算当前任务的内核态时间
+pub fn account_trap_return() {
+    if let Some(task) = current_task() {
+        task.inner_exclusive_access().account_stime();
+    }
+}
+
+//当前进程及其已回收子进程的 (utime, stime, cutime, cstime)，单位为微秒
+pub fn get_times() -> (usize, usize, usize, usize) {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    //把正在进行的这次系统调用也算进去
+    inner.account_stime();
+    (inner.utime, inner.stime, inner.cutime, inner.cstime)
+}
+
+//读取当前进程的资源限制
+pub fn get_rlimit(resource: usize) -> Rlimit {
+    current_task().unwrap().process.inner_exclusive_access().rlimits[resource]
+}
+
+//修改当前进程的资源限制：软限制不能超过硬限制，硬限制只能降低
+pub fn set_rlimit(resource: usize, limit: Rlimit) -> isize {
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    if limit.cur > limit.max {
+        return Errno::EINVAL.neg();
+    }
+    if limit.max > process_inner.rlimits[resource].max {
+        return Errno::EPERM.neg();
+    }
+    process_inner.rlimits[resource] = limit;
+    0
+}
+
+//为当前进程安装系统调用过滤器，对进程中的所有线程生效；已有过滤器时与它叠加，只能收紧不能放宽
+pub fn install_seccomp(filter: SeccompFilter) {
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    process_inner.seccomp = Some(match process_inner.seccomp {
+        Some(old) => old.tighten(&filter),
+        None => filter,
+    });
+}
+
+//当前进程的过滤器对系统调用 id 的处理，没有过滤器时总是允许
+pub fn seccomp_action(id: usize) -> SeccompAction {
+    let task = current_task().unwrap();
+    let process_inner = task.process.inner_exclusive_access();
+    process_inner
+        .seccomp
+        .map_or(SeccompAction::Allow, |filter| filter.action(id))
+}
+
+//当前进程再创建一个进程是否会超出 RLIMIT_NPROC
+pub fn nproc_exceeded() -> bool {
+    let limit = get_rlimit(RLIMIT_NPROC).cur;
+    all_tasks().len() >= limit
+}
+
+//时钟中断到来时扣减当前任务的时间片，返回是否需要切换任务：
+//时间片已经用完，或者有实时优先级更高的任务就绪（普通任务的实时优先级视为 0）
+pub fn consume_time_slice() -> bool {
+    match current_task() {
+        Some(task) => {
+            let mut inner = task.inner_exclusive_access();
+            inner.time_slice = inner.time_slice.saturating_sub(1);
+            let expired = inner.time_slice == 0;
+            let rt_priority = inner.rt_priority;
+            drop(inner);
+            expired || rt_preempts(rt_priority)
+        }
+        None => true,
+    }
+}
+
 //更新系统调用次数
 pub fn update_syscall_times(id: usize) {
     let task = current_task().unwrap();
     // **** access current TCB exclusively
     let mut inner = task.inner_exclusive_access();
-    inner.syscall_times[id] += 1;
+    //未知的系统调用号可能超出统计范围
+    if let Some(times) = inner.syscall_times.get_mut(id) {
+        *times += 1;
+    }
 }
 
 //得到系统调用次数
@@ -131,76 +459,404 @@ pub fn get_run_time() -> usize {
     timer::get_time_us() - start_time
 }
 
-//设置优先级
+//设置优先级，优先级小于 2 时返回 -1（实验框架的测试按 -1 判断，不使用错误码）
 pub fn set_priority(_prio: isize) -> isize {
     if _prio < 2 {
         return -1;
     } else {
-        current_task().unwrap().inner_exclusive_access().priority = _prio as u8;
+        let task = current_task().unwrap();
+        task.inner_exclusive_access().base_priority = _prio as usize;
+        //正在从等待者那里继承优先级时，有效优先级不会低于继承来的优先级
+        restore_priority(&task);
         return _prio;
     }
 }
 
+//优先级继承：把互斥锁持有者的有效优先级提升到 prio
+pub fn inherit_priority(task: &Arc<TaskControlBlock>, prio: usize) {
+    let mut inner = task.inner_exclusive_access();
+    if inner.priority >= prio {
+        return;
+    }
+    inner.priority = prio;
+    drop(inner);
+    on_priority_change(task, prio);
+}
+
+//重新计算有效优先级：基础优先级与仍持有的各个锁上等待者优先级中的最大值
+pub fn restore_priority(task: &Arc<TaskControlBlock>) {
+    let base_priority = task.inner_exclusive_access().base_priority;
+    let mutex_list = task.process.inner_exclusive_access().mutex_list.clone();
+    let prio = mutex_list
+        .iter()
+        .flatten()
+        .filter_map(|mutex| mutex.inherited_priority(task))
+        .fold(base_priority, usize::max);
+    task.inner_exclusive_access().priority = prio;
+    on_priority_change(task, prio);
+}
+
+//设置当前任务的调度类与实时优先级：普通任务的实时优先级必须为 0，实时任务为 1..=RT_PRIO_MAX。
+//没有特权的任务只能把实时优先级提高到 RLIMIT_RTPRIO 的软限制，降低总是允许的；参数不合法时返回 -EINVAL，权限不足时返回 -EPERM
+pub fn set_scheduler(policy: SchedPolicy, rt_priority: usize) -> isize {
+    let valid = match policy {
+        SchedPolicy::Normal => rt_priority == 0,
+        SchedPolicy::Fifo | SchedPolicy::RoundRobin => {
+            (1..=config::RT_PRIO_MAX).contains(&rt_priority)
+        }
+    };
+    if !valid {
+        return Errno::EINVAL.neg();
+    }
+    let task = current_task().unwrap();
+    let rtprio_limit = task.process.inner_exclusive_access().rlimits[RLIMIT_RTPRIO].cur;
+    let mut inner = task.inner_exclusive_access();
+    if !inner.privileged && rt_priority > inner.rt_priority && rt_priority > rtprio_limit {
+        return Errno::EPERM.neg();
+    }
+    inner.sched_policy = policy;
+    inner.rt_priority = rt_priority;
+    inner.refill_time_slice();
+    0
+}
+
+//获取当前任务的调度类
+pub fn get_scheduler() -> SchedPolicy {
+    current_task().unwrap().inner_exclusive_access().sched_policy
+}
+
+//设置当前任务的 CPU 亲和性掩码，掩码中至少要包含一个存在的核
+pub fn set_affinity(mask: usize) -> isize {
+    let mask = mask & config::CPU_MASK_ALL;
+    if mask == 0 {
+        return Errno::EINVAL.neg();
+    }
+    current_task().unwrap().inner_exclusive_access().cpu_mask = mask;
+    0
+}
+
+//获取当前任务的 CPU 亲和性掩码
+pub fn get_affinity() -> usize {
+    current_task().unwrap().inner_exclusive_access().cpu_mask
+}
+
 //申请内存
-pub fn mmap(_start: usize, _len: usize, _port: usize) -> isize {
-    if (_start % config::PAGE_SIZE != 0) || (_port & !0x7 != 0) || (_port & 0x7 == 0) {
-        return -1;
+//start 为按页对齐的非零地址时映射到这个位置，成功返回 0；
+//start 为 0 或未按页对齐时只作为提示，由内核从 MMAP_BASE（或提示地址）向上选择第一段足够大的空闲区间，返回选定的地址
+//shared 为 true 时页帧立即分配，fork 时与子进程共享，物理内存不足时返回 -ENOMEM
+pub fn mmap(_start: usize, _len: usize, _port: usize, shared: bool) -> isize {
+    if (_port & !0x7 != 0) || (_port & 0x7 == 0) {
+        return Errno::EINVAL.neg();
     }
-    let start_address = mm::VirtAddr(_start);
-    let end_address = mm::VirtAddr(_start + _len);
+    let fixed = _start != 0 && _start % config::PAGE_SIZE == 0;
+    if !fixed && _len == 0 {
+        return Errno::EINVAL.neg();
+    }
+    let pages = _len / config::PAGE_SIZE + usize::from(_len % config::PAGE_SIZE != 0);
 
     let map_permission = mm::MapPermission::from_bits((_port as u8) << 1).unwrap() | mm::MapPermission::U;
+    if map_permission.violates_wx() {
+        mm::wx_audit("mmap", _start, _start.saturating_add(_len));
+        return Errno::EACCES.neg();
+    }
+    //共享映射立即分配页帧，需要时先换出其他页面，这要在借用地址空间之前完成
+    if shared {
+        reclaim_frames(pages, false);
+    }
 
-    for vpn in mm::VPNRange::new(mm::VirtPageNum::from(start_address), end_address.ceil()) {
-        if let Some(pte) = current_task()
-            .unwrap()
-            .inner_exclusive_access()
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    let limit = process_inner.rlimits[RLIMIT_AS].cur;
+    // 映射之后的地址空间不能超过 RLIMIT_AS
+    let len = pages.saturating_mul(config::PAGE_SIZE);
+    if process_inner.memory_set.total_size().saturating_add(len) > limit {
+        return Errno::ENOMEM.neg();
+    }
+    if shared && pages > mm::frame_available() {
+        return Errno::ENOMEM.neg();
+    }
+
+    let start_vpn = if fixed {
+        //与 find_free_area 一样以 USER_SPACE_END 为上界：页表只取页号的低 27 位，
+        //越过上界的页面会与不属于任何逻辑段的 TRAMPOLINE 等映射重合
+        if user_range_end(_start, len).is_none() {
+            return Errno::EINVAL.neg();
+        }
+        let start_vpn = mm::VirtAddr(_start).floor();
+        if process_inner
             .memory_set
-            .translate(vpn) {
-            if pte.is_valid() {
-                return -1;
-            }
+            .overlaps(start_vpn, mm::VirtPageNum(start_vpn.0 + pages))
+        {
+            return Errno::EEXIST.neg();
+        }
+        start_vpn
+    } else {
+        let hint = if _start == 0 {
+            config::MMAP_BASE
+        } else {
+            _start
         };
+        match process_inner
+            .memory_set
+            .find_free_area(mm::VirtAddr(hint).ceil(), pages)
+        {
+            Some(start_vpn) => start_vpn,
+            None => return Errno::ENOMEM.neg(),
+        }
+    };
+
+    let start_va = start_vpn.into();
+    let end_va = mm::VirtPageNum(start_vpn.0 + pages).into();
+    let result = if shared {
+        process_inner
+            .memory_set
+            .insert_shared_area(start_va, end_va, map_permission)
+    } else {
+        //只记录逻辑段，页帧在第一次访问时由缺页处理分配
+        process_inner
+            .memory_set
+            .insert_lazy_area(start_va, end_va, map_permission)
+    };
+    if let Err(err) = result {
+        return err.neg();
     }
 
-    current_task()
-        .unwrap()
-        .inner_exclusive_access()
-        .memory_set
-        .insert_framed_area(start_address, end_address, map_permission);
+    if fixed {
+        0
+    } else {
+        mm::VirtAddr::from(start_vpn).0 as isize
+    }
+}
 
-    0
+//检查 [start, start + len) 没有溢出且不越过用户地址空间的上界，返回区间的结束地址
+fn user_range_end(start: usize, len: usize) -> Option<usize> {
+    start
+        .checked_add(len)
+        .filter(|&end| end <= config::USER_SPACE_END)
 }
 
-//释放内存
+//释放内存，区间可以是一段映射的一部分，也可以跨越多段映射
 pub fn munmap(_start: usize, _len: usize) -> isize {
     if _start % config::PAGE_SIZE != 0 {
-        return -1;
+        return Errno::EINVAL.neg();
     }
+    let end = match user_range_end(_start, _len) {
+        Some(end) => end,
+        None => return Errno::EINVAL.neg(),
+    };
+    let start_vpn = mm::VirtAddr(_start).floor();
+    let end_vpn = mm::VirtAddr(end).ceil();
 
-    let start_address = mm::VirtAddr(_start);
-    let end_address = mm::VirtAddr(_start + _len);
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    //用户堆由 sbrk 调整，与 mprotect 一样不允许拆分它
+    let heap_start = mm::VirtAddr(process_inner.heap_bottom).floor();
+    let heap_end = mm::VirtAddr(process_inner.program_brk).ceil();
+    if start_vpn < heap_end.max(mm::VirtPageNum(heap_start.0 + 1)) && heap_start < end_vpn {
+        return Errno::EINVAL.neg();
+    }
+    //惰性分配的页面可能还没有映射，只要求整个区间都落在用户逻辑段中
+    if !process_inner.memory_set.remove_range(start_vpn, end_vpn) {
+        return Errno::EINVAL.neg();
+    }
+    0
+}
 
-    for vpn in mm::VPNRange::new(mm::VirtPageNum::from(start_address), end_address.ceil()) {
-        match current_task()
-            .unwrap()
-            .inner_exclusive_access()
-            .memory_set
-            .translate(vpn) {
-            Some(pte) => {
-                if pte.is_valid() == false {
-                    return -1;
-                }
-            }
-            None => {
-                return -1;
-            }
+//将共享内存段 id 映射到 addr（为 0 时由内核选择），返回映射的地址
+pub fn shmat(id: usize, addr: usize, readonly: bool) -> isize {
+    if addr % config::PAGE_SIZE != 0 {
+        return Errno::EINVAL.neg();
+    }
+    let registry = mm::SHM_SEGMENTS.exclusive_access();
+    let segment = match registry.segment(id) {
+        Some(segment) => segment,
+        None => return Errno::EINVAL.neg(),
+    };
+    let pages = segment.frames().len();
+    let mut map_permission = mm::MapPermission::R | mm::MapPermission::U;
+    if !readonly {
+        map_permission |= mm::MapPermission::W;
+    }
+
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    let limit = process_inner.rlimits[RLIMIT_AS].cur;
+    let memory_set = &mut process_inner.memory_set;
+    if memory_set
+        .total_size()
+        .saturating_add(pages * config::PAGE_SIZE)
+        > limit
+    {
+        return Errno::ENOMEM.neg();
+    }
+    let start_vpn = if addr != 0 {
+        if user_range_end(addr, pages * config::PAGE_SIZE).is_none() {
+            return Errno::EINVAL.neg();
         }
+        let start_vpn = mm::VirtAddr(addr).floor();
+        if memory_set.overlaps(start_vpn, mm::VirtPageNum(start_vpn.0 + pages)) {
+            return Errno::EINVAL.neg();
+        }
+        start_vpn
+    } else {
+        match memory_set.find_free_area(mm::VirtAddr(config::MMAP_BASE).floor(), pages) {
+            Some(start_vpn) => start_vpn,
+            None => return Errno::ENOMEM.neg(),
+        }
+    };
+    if let Err(err) = memory_set.insert_frames_area(start_vpn, segment.frames(), map_permission) {
+        return err.neg();
+    }
+    mm::VirtAddr::from(start_vpn).0 as isize
+}
+
+//解除起始于 addr 的共享内存段映射
+pub fn shmdt(addr: usize) -> isize {
+    if addr % config::PAGE_SIZE != 0 {
+        return Errno::EINVAL.neg();
     }
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    if process_inner
+        .memory_set
+        .remove_shared_area(mm::VirtAddr(addr).floor())
+    {
+        0
+    } else {
+        Errno::EINVAL.neg()
+    }
+}
 
-    for vpn in mm::VPNRange::new(mm::VirtPageNum::from(start_address), end_address.ceil()) {
-        current_task().unwrap().inner_exclusive_access().memory_set.remove_area_with_start_vpn(vpn);
+//修改已映射内存的权限
+pub fn mprotect(_start: usize, _len: usize, _port: usize) -> isize {
+    if (_start % config::PAGE_SIZE != 0) || (_port & !0x7 != 0) || (_port & 0x7 == 0) {
+        return Errno::EINVAL.neg();
+    }
+    let end = match user_range_end(_start, _len) {
+        Some(end) => end,
+        None => return Errno::EINVAL.neg(),
+    };
+    let start_vpn = mm::VirtAddr(_start).floor();
+    let end_vpn = mm::VirtAddr(end).ceil();
+    let map_permission = mm::MapPermission::from_bits((_port as u8) << 1).unwrap() | mm::MapPermission::U;
+    if map_permission.violates_wx() {
+        mm::wx_audit("mprotect", _start, end);
+        return Errno::EACCES.neg();
     }
 
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    //用户堆由 sbrk 按整个逻辑段调整，不允许拆分它（堆为空时也不能包含堆的起始地址）
+    let heap_start = mm::VirtAddr(process_inner.heap_bottom).floor();
+    let heap_end = mm::VirtAddr(process_inner.program_brk).ceil();
+    if start_vpn < heap_end.max(mm::VirtPageNum(heap_start.0 + 1)) && heap_start < end_vpn {
+        return Errno::EINVAL.neg();
+    }
+    if !process_inner
+        .memory_set
+        .mprotect(start_vpn, end_vpn, map_permission)
+    {
+        return Errno::ENOMEM.neg();
+    }
     0
-}
\ No newline at end of file
+}
+
+//调整一段已映射内存的大小，原地无法扩大且 may_move 时移动到新的地址，返回调整后的地址
+pub fn mremap(old_addr: usize, old_size: usize, new_size: usize, may_move: bool) -> isize {
+    if old_addr % config::PAGE_SIZE != 0 || old_size == 0 || new_size == 0 {
+        return Errno::EINVAL.neg();
+    }
+    let end = match user_range_end(old_addr, old_size) {
+        Some(end) => end,
+        None => return Errno::EINVAL.neg(),
+    };
+    let start_vpn = mm::VirtAddr(old_addr).floor();
+    let end_vpn = mm::VirtAddr(end).ceil();
+    let new_pages = mm::VirtAddr(new_size).ceil().0;
+
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    let limit = process_inner.rlimits[RLIMIT_AS].cur;
+    // 扩大之后的地址空间不能超过 RLIMIT_AS
+    let grow = new_pages.saturating_sub(end_vpn.0 - start_vpn.0) * config::PAGE_SIZE;
+    if process_inner.memory_set.total_size().saturating_add(grow) > limit {
+        return Errno::ENOMEM.neg();
+    }
+    match process_inner
+        .memory_set
+        .remap(start_vpn, end_vpn, new_pages, may_move)
+    {
+        Some(new_start) => mm::VirtAddr::from(new_start).0 as isize,
+        None => Errno::ENOMEM.neg(),
+    }
+}
+
+//调整用户堆的大小，返回调整之前的 program break
+pub fn sbrk(size: i32) -> isize {
+    if size > 0 {
+        reclaim_frames(size as usize / config::PAGE_SIZE + 1, false);
+    }
+    let task = current_task().unwrap();
+    let mut process_inner = task.process.inner_exclusive_access();
+    let limit = process_inner.rlimits[RLIMIT_AS].cur;
+    let heap_bottom = process_inner.heap_bottom;
+    let old_brk = process_inner.program_brk;
+    let new_brk = old_brk as isize + size as isize;
+    if new_brk < heap_bottom as isize {
+        return Errno::EINVAL.neg();
+    }
+    let new_brk = new_brk as usize;
+    let old_end = mm::VirtAddr(old_brk).ceil();
+    let new_end = mm::VirtAddr(new_brk).ceil();
+    let memory_set = &mut process_inner.memory_set;
+    let result = match new_end.cmp(&old_end) {
+        Ordering::Less => memory_set.shrink_to(mm::VirtAddr(heap_bottom), mm::VirtAddr(new_brk)),
+        Ordering::Greater => {
+            //新增的页面不能与其他逻辑段重叠，也不能超出 RLIMIT_AS 与剩余的物理内存
+            //堆为空时它占据起始的一页，这一页不会被其他逻辑段使用
+            let pages = new_end.0 - old_end.0;
+            let check_from = old_end.max(mm::VirtPageNum(mm::VirtAddr(heap_bottom).floor().0 + 1));
+            if (check_from < new_end && memory_set.overlaps(check_from, new_end))
+                || memory_set.total_size().saturating_add(pages * config::PAGE_SIZE) > limit
+                || pages > mm::frame_available()
+            {
+                return Errno::ENOMEM.neg();
+            }
+            memory_set.append_to(mm::VirtAddr(heap_bottom), mm::VirtAddr(new_brk))
+        }
+        Ordering::Equal => true,
+    };
+    if !result {
+        return Errno::ENOMEM.neg();
+    }
+    process_inner.program_brk = new_brk;
+    old_brk as isize
+}
+
+/// 地址空间 token 属于当前进程时，为其中惰性分配的页面 vpn 在首次访问时分配页帧，已被换出的页面从交换区读回；
+/// vpn 落在某个用户栈当前栈底之下的预留区域中时，在 RLIMIT_STACK 允许的范围内向下扩大该用户栈。
+/// access 为这次访问需要的权限，内核代替用户访问时传入空权限。
+/// 返回值：成功分配时返回 true；否则是一次非法访问，返回 false。
+pub fn fault_in_user_page(token: usize, vpn: mm::VirtPageNum, access: mm::MapPermission) -> bool {
+    let task = match current_task() {
+        Some(task) => task,
+        None => return false,
+    };
+    //换出页面需要借用各进程的地址空间，要在借用当前进程的地址空间之前完成。
+    //内存不足且无法换出时这次访问失败，至少要留下一个页面以及两级新页表所需的页帧
+    if !reclaim_frames(1, access.is_empty()) && mm::frame_available() < 3 {
+        return false;
+    }
+    let mut process_inner = task.process.inner_exclusive_access();
+    if process_inner.get_user_token() != token {
+        return false;
+    }
+    if process_inner.memory_set.handle_page_fault(vpn, access) {
+        return true;
+    }
+    if !(mm::MapPermission::R | mm::MapPermission::W).contains(access) {
+        return false;
+    }
+    let limit = process_inner.rlimits[RLIMIT_STACK].cur;
+    process_inner.grow_user_stack(vpn, limit)
+}
@@ -0,0 +1,329 @@
+//! 进程跟踪（ptrace 的一个子集）
+//!
+//! 子进程用 PTRACE_TRACEME 请求由父进程跟踪，此后它执行 ebreak 或者 exec 成功时停下来（阻塞），
+//! 父进程在 wait4 中得知它停下，可以读写它的内存与寄存器，再让它继续运行或者单步执行。
+//! RISC-V 没有硬件单步：内核解码将要执行的指令，在它之后可能执行的每个位置插入临时断点，
+//! 任务再次停下时撤掉这些断点。
+
+use super::{block_current_and_run_next, current_task, reclaim_frames, wakeup_task};
+use super::{SignalFlags, TaskControlBlock};
+use crate::config::PAGE_SIZE;
+use crate::errno::Errno;
+use crate::mm::VirtAddr;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// 当前进程由父进程跟踪
+pub const PTRACE_TRACEME: usize = 0;
+/// 读出被跟踪进程地址空间中的一个字
+pub const PTRACE_PEEKDATA: usize = 2;
+/// 读出被跟踪进程的一个寄存器
+pub const PTRACE_PEEKUSER: usize = 3;
+/// 写入被跟踪进程地址空间中的一个字
+pub const PTRACE_POKEDATA: usize = 5;
+/// 写入被跟踪进程的一个寄存器
+pub const PTRACE_POKEUSER: usize = 6;
+/// 让停下的被跟踪进程继续运行
+pub const PTRACE_CONT: usize = 7;
+/// 终止被跟踪进程
+pub const PTRACE_KILL: usize = 8;
+/// 让停下的被跟踪进程执行一条指令后再次停下
+pub const PTRACE_SINGLESTEP: usize = 9;
+
+/// PEEKUSER/POKEUSER 的寄存器编号：0 为 pc，1~31 为 x1~x31，与 Linux 的 user_regs_struct 一致
+pub const PTRACE_REGS: usize = 32;
+
+//c.ebreak 的编码，单步执行插入的临时断点只覆盖 2 个字节，在压缩指令与普通指令上都可以使用
+const C_EBREAK: u16 = 0x9002;
+
+/// 任务的跟踪状态
+#[derive(Default)]
+pub struct Ptrace {
+    /// 是否被父进程跟踪
+    pub traced: bool,
+    /// 是否停了下来，等待跟踪者让它继续运行
+    pub stopped: bool,
+    /// 停下的原因（信号编号），跟踪者在 wait4 中取走之前不为 None
+    pub stop_signal: Option<usize>,
+    /// 继续运行时要发送给任务的信号，0 表示不发送
+    pub resume_signal: usize,
+    /// 单步执行插入的临时断点：(地址, 原来的 2 个字节)
+    pub step_breakpoints: Vec<(usize, u16)>,
+}
+
+/// 被跟踪的当前任务因为信号 signal 停下（断点与 exec 都是 SIGTRAP），唤醒跟踪者后阻塞，
+/// 直到跟踪者让它继续运行。当前任务没有被跟踪时返回 false，由调用者按通常的方式处理
+pub fn ptrace_stop(signal: SignalFlags) -> bool {
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    if !inner.ptrace.traced {
+        return false;
+    }
+    inner.ptrace.stopped = true;
+    inner.ptrace.stop_signal = signal.first_signum();
+    let breakpoints = core::mem::take(&mut inner.ptrace.step_breakpoints);
+    drop(inner);
+    remove_breakpoints(&task, breakpoints);
+    let parent = task.process.inner_exclusive_access().parent.clone();
+    if let Some(parent) = parent.and_then(|parent| parent.upgrade()) {
+        parent.process.child_exit.exclusive_access().wake_all();
+    }
+    //被跟踪的任务由父进程的 children 持有，跟踪者通过它找到并唤醒任务
+    drop(task);
+    block_current_and_run_next();
+    let task = current_task().unwrap();
+    let mut inner = task.inner_exclusive_access();
+    let signum = core::mem::take(&mut inner.ptrace.resume_signal);
+    if let Some(signal) = SignalFlags::from_signum(signum) {
+        inner.signals.insert(signal);
+    }
+    true
+}
+
+/// 跟踪者退出：不再跟踪子进程 child，撤掉单步执行的断点，停下的子进程继续运行
+pub fn ptrace_detach(child: &Arc<TaskControlBlock>) {
+    let mut inner = child.inner_exclusive_access();
+    if !inner.ptrace.traced {
+        return;
+    }
+    inner.ptrace.traced = false;
+    let breakpoints = core::mem::take(&mut inner.ptrace.step_breakpoints);
+    let stopped = core::mem::take(&mut inner.ptrace.stopped);
+    inner.ptrace.stop_signal = None;
+    drop(inner);
+    remove_breakpoints(child, breakpoints);
+    if stopped {
+        wakeup_task(child.clone());
+    }
+}
+
+/// 执行一个跟踪请求，成功时返回 0
+pub fn ptrace(request: usize, pid: usize, addr: usize, data: usize) -> Result<usize, Errno> {
+    if request == PTRACE_TRACEME {
+        let task = current_task().unwrap();
+        //只有主线程能够被跟踪，父进程在 wait4 中才能看到它停下
+        if task.tid != 0 {
+            return Err(Errno::EINVAL);
+        }
+        let mut inner = task.inner_exclusive_access();
+        if inner.ptrace.traced {
+            return Err(Errno::EPERM);
+        }
+        inner.ptrace.traced = true;
+        return Ok(0);
+    }
+    let tracee = stopped_tracee(pid, request == PTRACE_KILL)?;
+    match request {
+        PTRACE_PEEKDATA => {
+            let mut word = [0u8; 8];
+            access_tracee(&tracee, addr, &mut word, false)?;
+            Ok(usize::from_le_bytes(word))
+        }
+        PTRACE_POKEDATA => {
+            access_tracee(&tracee, addr, &mut data.to_le_bytes(), true)?;
+            Ok(0)
+        }
+        PTRACE_PEEKUSER | PTRACE_POKEUSER => {
+            if addr >= PTRACE_REGS {
+                return Err(Errno::EIO);
+            }
+            let trap_cx = tracee.inner_exclusive_access().get_trap_cx();
+            let reg = match addr {
+                0 => &mut trap_cx.sepc,
+                _ => &mut trap_cx.x[addr],
+            };
+            if request == PTRACE_POKEUSER {
+                *reg = data;
+            }
+            Ok(*reg)
+        }
+        PTRACE_CONT => resume(tracee, data).map(|_| 0),
+        PTRACE_SINGLESTEP => {
+            insert_step_breakpoints(&tracee)?;
+            resume(tracee, data).map(|_| 0)
+        }
+        //停下的子进程继续运行时收到 SIGKILL，正在运行的子进程在下一次返回用户态之前处理它
+        PTRACE_KILL => {
+            let mut inner = tracee.inner_exclusive_access();
+            inner.signals.insert(SignalFlags::SIGKILL);
+            let stopped = inner.ptrace.stopped;
+            drop(inner);
+            if stopped {
+                resume(tracee, 0)?;
+            }
+            Ok(0)
+        }
+        _ => Err(Errno::EINVAL),
+    }
+}
+
+//当前进程跟踪的子进程 pid。除 PTRACE_KILL 之外的请求都要求它已经停下，否则返回 Errno::ESRCH
+fn stopped_tracee(pid: usize, running_ok: bool) -> Result<Arc<TaskControlBlock>, Errno> {
+    let current = current_task().unwrap();
+    let tracee = current
+        .process
+        .inner_exclusive_access()
+        .children
+        .iter()
+        .find(|child| child.getpid() == pid)
+        .cloned()
+        .ok_or(Errno::ESRCH)?;
+    let inner = tracee.inner_exclusive_access();
+    if !inner.ptrace.traced || inner.is_zombie() || !(inner.ptrace.stopped || running_ok) {
+        return Err(Errno::ESRCH);
+    }
+    drop(inner);
+    Ok(tracee)
+}
+
+//让停下的被跟踪进程继续运行，signum 不为 0 时向它发送这个信号
+fn resume(tracee: Arc<TaskControlBlock>, signum: usize) -> Result<(), Errno> {
+    if signum != 0 && SignalFlags::from_signum(signum).is_none() {
+        return Err(Errno::EINVAL);
+    }
+    let mut inner = tracee.inner_exclusive_access();
+    inner.ptrace.stopped = false;
+    inner.ptrace.stop_signal = None;
+    inner.ptrace.resume_signal = signum;
+    drop(inner);
+    wakeup_task(tracee);
+    Ok(())
+}
+
+//读写被跟踪进程地址空间中的 [addr, addr + buf.len())，可以跨越多个页面，也可以写入只读的代码页。
+//write 为 true 时把 buf 写入，否则读到 buf 中。地址不合法时返回 Errno::EFAULT
+fn access_tracee(
+    tracee: &Arc<TaskControlBlock>,
+    addr: usize,
+    buf: &mut [u8],
+    write: bool,
+) -> Result<(), Errno> {
+    let mut done = 0;
+    while done < buf.len() {
+        let va = addr.checked_add(done).ok_or(Errno::EFAULT)?;
+        //可能要为被跟踪进程调入页面，先在不持有任何进程的 inner 时准备好页帧
+        reclaim_frames(1, true);
+        let ppn = tracee
+            .process
+            .inner_exclusive_access()
+            .memory_set
+            .debug_page(VirtAddr::from(va).floor())
+            .ok_or(Errno::EFAULT)?;
+        let offset = va % PAGE_SIZE;
+        let len = (PAGE_SIZE - offset).min(buf.len() - done);
+        let page = &mut ppn.get_bytes_array()[offset..offset + len];
+        if write {
+            page.copy_from_slice(&buf[done..done + len]);
+        } else {
+            buf[done..done + len].copy_from_slice(page);
+        }
+        done += len;
+    }
+    Ok(())
+}
+
+//在被跟踪进程 pc 处的指令之后可能执行的每个位置插入临时断点
+fn insert_step_breakpoints(tracee: &Arc<TaskControlBlock>) -> Result<(), Errno> {
+    let trap_cx = tracee.inner_exclusive_access().get_trap_cx();
+    let pc = trap_cx.sepc;
+    let mut insn = [0u8; 4];
+    access_tracee(tracee, pc, &mut insn[..2], false)?;
+    if insn[0] & 0b11 == 0b11 {
+        access_tracee(tracee, pc + 2, &mut insn[2..], false)?;
+    }
+    let mut breakpoints: Vec<(usize, u16)> = Vec::new();
+    for target in next_pcs(u32::from_le_bytes(insn), pc, &trap_cx.x) {
+        //跳转到自身的指令不插入断点，否则它永远不会被执行
+        if target == pc || breakpoints.iter().any(|(addr, _)| *addr == target) {
+            continue;
+        }
+        let mut original = [0u8; 2];
+        let inserted = access_tracee(tracee, target, &mut original, false)
+            .and_then(|_| access_tracee(tracee, target, &mut C_EBREAK.to_le_bytes(), true));
+        if let Err(err) = inserted {
+            remove_breakpoints(tracee, breakpoints);
+            return Err(err);
+        }
+        breakpoints.push((target, u16::from_le_bytes(original)));
+    }
+    tracee.inner_exclusive_access().ptrace.step_breakpoints = breakpoints;
+    Ok(())
+}
+
+//撤掉临时断点，恢复原来的指令
+fn remove_breakpoints(tracee: &Arc<TaskControlBlock>, breakpoints: Vec<(usize, u16)>) {
+    for (addr, original) in breakpoints.into_iter().rev() {
+        let _ = access_tracee(tracee, addr, &mut original.to_le_bytes(), true);
+    }
+}
+
+//把 value 的低 bits 位作为有符号数扩展
+fn sign_extend(value: u32, bits: u32) -> usize {
+    ((value << (32 - bits)) as i32 >> (32 - bits)) as isize as usize
+}
+
+/// 位于 pc 的指令 insn（压缩指令只有低 16 位有效）执行之后，下一条指令可能的位置。
+/// x 为通用寄存器，间接跳转的目标由它决定；条件分支两个方向都有可能
+pub fn next_pcs(insn: u32, pc: usize, x: &[usize; 32]) -> Vec<usize> {
+    let reg = |r: u32| if r == 0 { 0 } else { x[r as usize] };
+    if insn & 0b11 == 0b11 {
+        let next = pc + 4;
+        match insn & 0x7f {
+            //jal
+            0x6f => {
+                let imm = (insn >> 31) << 20
+                    | (insn >> 12 & 0xff) << 12
+                    | (insn >> 20 & 1) << 11
+                    | (insn >> 21 & 0x3ff) << 1;
+                vec![pc.wrapping_add(sign_extend(imm, 21))]
+            }
+            //jalr
+            0x67 => {
+                let imm = sign_extend(insn >> 20, 12);
+                vec![reg(insn >> 15 & 0x1f).wrapping_add(imm) & !1]
+            }
+            //beq/bne/blt/bge/bltu/bgeu
+            0x63 => {
+                let imm = (insn >> 31) << 12
+                    | (insn >> 7 & 1) << 11
+                    | (insn >> 25 & 0x3f) << 5
+                    | (insn >> 8 & 0xf) << 1;
+                vec![next, pc.wrapping_add(sign_extend(imm, 13))]
+            }
+            _ => vec![next],
+        }
+    } else {
+        let insn = insn & 0xffff;
+        let next = pc + 2;
+        let funct3 = insn >> 13;
+        match (insn & 0b11, funct3) {
+            //c.j
+            (0b01, 0b101) => {
+                let imm = (insn >> 12 & 1) << 11
+                    | (insn >> 11 & 1) << 4
+                    | (insn >> 9 & 3) << 8
+                    | (insn >> 8 & 1) << 10
+                    | (insn >> 7 & 1) << 6
+                    | (insn >> 6 & 1) << 7
+                    | (insn >> 3 & 7) << 1
+                    | (insn >> 2 & 1) << 5;
+                vec![pc.wrapping_add(sign_extend(imm, 12))]
+            }
+            //c.beqz/c.bnez
+            (0b01, 0b110) | (0b01, 0b111) => {
+                let imm = (insn >> 12 & 1) << 8
+                    | (insn >> 10 & 3) << 3
+                    | (insn >> 5 & 3) << 6
+                    | (insn >> 3 & 3) << 1
+                    | (insn >> 2 & 1) << 5;
+                vec![next, pc.wrapping_add(sign_extend(imm, 9))]
+            }
+            //c.jr/c.jalr：rs2 为 0、rs1 不为 0
+            (0b10, 0b100) if insn >> 2 & 0x1f == 0 && insn >> 7 & 0x1f != 0 => {
+                vec![reg(insn >> 7 & 0x1f) & !1]
+            }
+            _ => vec![next],
+        }
+    }
+}
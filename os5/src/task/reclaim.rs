@@ -0,0 +1,95 @@
+//! 页帧回收
+//!
+//! 空闲页帧不足时，用时钟算法依次从各个进程中选出最近没有被访问过的用户页面换出到交换区。
+//! 时钟指针记录为 (pid, 页号)，按 pid 从小到大、在每个进程中按地址从低到高移动；
+//! 指针每经过一个页面就根据访问位更新一次它的年龄，年龄老化到 0（最近 AGE_BITS 次检查中都没有被访问过）
+//! 的页面被换出。此外每隔 PAGE_AGING_TICKS 个时钟中断收集一次所有进程的访问位，让年龄反映访问的先后。
+
+use super::{all_tasks, current_task};
+use crate::config::{PAGE_AGING_TICKS, SWAP_RESERVE_FRAMES, SWAP_SLOTS};
+use crate::mm::{count_harvest, frame_available, swap_enabled, swap_used, VirtPageNum, AGE_BITS};
+use crate::sync::UPSafeCell;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+lazy_static! {
+    static ref CLOCK_HAND: UPSafeCell<(usize, VirtPageNum)> =
+        unsafe { UPSafeCell::new((0, VirtPageNum(0))) };
+    //距离上次收集访问位经过的时钟中断数
+    static ref AGING_TICKS: UPSafeCell<usize> = unsafe { UPSafeCell::new(0) };
+}
+
+/// 时钟中断到来时调用，每隔 PAGE_AGING_TICKS 次收集一次所有进程的访问位。调用时不能持有任何进程的 inner
+pub fn aging_tick() {
+    let mut ticks = AGING_TICKS.exclusive_access();
+    *ticks += 1;
+    if *ticks < PAGE_AGING_TICKS {
+        return;
+    }
+    *ticks = 0;
+    drop(ticks);
+    count_harvest();
+    for task in all_tasks() {
+        task.process.inner_exclusive_access().memory_set.age_pages();
+    }
+}
+
+/// 时钟指针的位置 (pid, 页号)：下一次回收从这里开始检查页面
+pub fn clock_hand() -> (usize, VirtPageNum) {
+    *CLOCK_HAND.exclusive_access()
+}
+
+/// 换出页面，直到空闲页帧除了保留给页表等的 SWAP_RESERVE_FRAMES 个之外还有 pages 个。
+/// skip_current 为 true 时不换出当前进程的页面，内核代替用户访问内存时可能还持有当前进程其他页面的引用。
+/// 调用时不能持有任何进程的 inner。返回值：是否有足够的空闲页帧。
+pub fn reclaim_frames(pages: usize, skip_current: bool) -> bool {
+    let target = pages + SWAP_RESERVE_FRAMES;
+    if frame_available() >= target {
+        return true;
+    }
+    if !swap_enabled() {
+        return false;
+    }
+    let skipped = current_task()
+        .filter(|_| skip_current)
+        .map(|task| task.getpid());
+    let processes: Vec<_> = all_tasks()
+        .into_iter()
+        .filter(|task| Some(task.getpid()) != skipped)
+        .collect();
+    let mut hand = CLOCK_HAND.exclusive_access();
+    let (mut pid, mut vpn) = *hand;
+    //刚被访问过的页面要被指针经过 AGE_BITS 次才会老化到 0，
+    //多绕一圈仍换不出页面说明已经没有可以换出的页面或交换区已满
+    let mut laps = 0;
+    while frame_available() < target && laps <= AGE_BITS && swap_used() < SWAP_SLOTS {
+        let task = match processes.iter().find(|task| task.getpid() >= pid) {
+            Some(task) => task,
+            None => {
+                laps += 1;
+                pid = 0;
+                vpn = VirtPageNum(0);
+                continue;
+            }
+        };
+        if task.getpid() != pid {
+            pid = task.getpid();
+            vpn = VirtPageNum(0);
+        }
+        let wanted = target - frame_available();
+        let next = task
+            .process
+            .inner_exclusive_access()
+            .memory_set
+            .swap_out_from(vpn, wanted);
+        match next {
+            Some(next) => vpn = next,
+            None => {
+                pid += 1;
+                vpn = VirtPageNum(0);
+            }
+        }
+    }
+    *hand = (pid, vpn);
+    frame_available() >= target
+}
@@ -0,0 +1,62 @@
+//! 进程资源限制
+
+use crate::config::{RT_PRIO_MAX, USER_STACK_RESERVE};
+
+/// 不限制
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// 资源编号与 Linux 一致，目前只支持以下六种
+/// CPU 时间（秒），超过软限制后每秒收到一次 SIGXCPU，超过硬限制时收到 SIGKILL
+pub const RLIMIT_CPU: usize = 0;
+/// 用户栈大小（字节），用户栈向下增长时不能超过软限制
+pub const RLIMIT_STACK: usize = 3;
+/// core dump 中内存部分的大小（字节），为 0 时不生成 core dump
+pub const RLIMIT_CORE: usize = 4;
+/// 系统中的进程数，达到软限制后 fork/spawn 失败
+pub const RLIMIT_NPROC: usize = 6;
+/// 地址空间大小（字节），超过软限制的 mmap 失败
+pub const RLIMIT_AS: usize = 9;
+/// 没有特权的进程能设置的最高实时优先级，为 0 时不能切换到实时调度类
+pub const RLIMIT_RTPRIO: usize = 14;
+/// 资源编号的上限
+pub const RLIM_NLIMITS: usize = 16;
+
+/// 一项资源限制，与 Linux 的 struct rlimit 布局一致
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct Rlimit {
+    /// 软限制，进程可以在不超过硬限制的范围内随意调整
+    pub cur: usize,
+    /// 硬限制，只能降低不能提高
+    pub max: usize,
+}
+
+impl Default for Rlimit {
+    fn default() -> Self {
+        Self {
+            cur: RLIM_INFINITY,
+            max: RLIM_INFINITY,
+        }
+    }
+}
+
+/// 初始进程的资源限制：默认不生成 core dump，用户栈最多增长到整个预留区域，
+/// 默认不能使用实时调度类（需要先在硬限制内提高软限制），其余不限制
+pub fn initial_rlimits() -> [Rlimit; RLIM_NLIMITS] {
+    let mut rlimits = [Rlimit::default(); RLIM_NLIMITS];
+    rlimits[RLIMIT_CORE].cur = 0;
+    rlimits[RLIMIT_STACK].cur = USER_STACK_RESERVE;
+    rlimits[RLIMIT_RTPRIO] = Rlimit {
+        cur: 0,
+        max: RT_PRIO_MAX,
+    };
+    rlimits
+}
+
+/// 资源编号是否受支持
+pub fn rlimit_supported(resource: usize) -> bool {
+    matches!(
+        resource,
+        RLIMIT_CPU | RLIMIT_STACK | RLIMIT_CORE | RLIMIT_NPROC | RLIMIT_AS | RLIMIT_RTPRIO
+    )
+}
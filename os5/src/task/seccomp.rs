@@ -0,0 +1,55 @@
+//! 系统调用过滤
+
+use crate::config::MAX_SYSCALL_NUM;
+
+/// 过滤模式：被拒绝的系统调用返回 -EPERM
+pub const SECCOMP_MODE_ERRNO: usize = 0;
+/// 过滤模式：调用被拒绝的系统调用时以 SIGSYS 终止进程，不能被捕获
+pub const SECCOMP_MODE_KILL: usize = 1;
+/// 允许集合位图的字节数，第 i 位对应系统调用号 i，更大的系统调用号总是被拒绝
+pub const SECCOMP_BITMAP_BYTES: usize = (MAX_SYSCALL_NUM + 7) / 8;
+
+/// 过滤器对一次系统调用的处理
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SeccompAction {
+    Allow,
+    Errno,
+    Kill,
+}
+
+/// 系统调用过滤器：只允许位图中的系统调用
+#[derive(Copy, Clone)]
+pub struct SeccompFilter {
+    allowed: [u8; SECCOMP_BITMAP_BYTES],
+    kill: bool,
+}
+
+impl SeccompFilter {
+    pub fn new(allowed: [u8; SECCOMP_BITMAP_BYTES], kill: bool) -> Self {
+        Self { allowed, kill }
+    }
+    pub fn action(&self, id: usize) -> SeccompAction {
+        let allowed = self
+            .allowed
+            .get(id / 8)
+            .map_or(false, |byte| byte & (1 << (id % 8)) != 0);
+        if allowed {
+            SeccompAction::Allow
+        } else if self.kill {
+            SeccompAction::Kill
+        } else {
+            SeccompAction::Errno
+        }
+    }
+    /// 在这个过滤器之上再安装 other：只允许两者都允许的系统调用，其中任何一个要求终止进程时都终止
+    pub fn tighten(&self, other: &Self) -> Self {
+        let mut allowed = self.allowed;
+        for (byte, other) in allowed.iter_mut().zip(other.allowed.iter()) {
+            *byte &= other;
+        }
+        Self {
+            allowed,
+            kill: self.kill || other.kill,
+        }
+    }
+}
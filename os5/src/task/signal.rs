@@ -0,0 +1,114 @@
+//! 信号
+
+use bitflags::*;
+
+/// 信号编号的上限
+pub const MAX_SIG: usize = 31;
+
+/// 信号处理函数：采取默认动作
+pub const SIG_DFL: usize = 0;
+/// 信号处理函数：忽略该信号
+pub const SIG_IGN: usize = 1;
+
+/// 映射在 SIGRETURN_TRAMPOLINE 处的用户态代码：
+/// li a7, 139 (SYSCALL_SIGRETURN); ecall
+//信号处理函数的返回地址被设置为这里，处理函数返回后即回到内核恢复被打断的上下文。
+pub const SIGRETURN_CODE: [u8; 8] = [0x93, 0x08, 0xb0, 0x08, 0x73, 0x00, 0x00, 0x00];
+
+bitflags! {
+    /// 信号集合，第 i 位对应编号为 i 的信号
+    pub struct SignalFlags: u32 {
+        const SIGHUP    = 1 << 1;
+        const SIGINT    = 1 << 2;
+        const SIGQUIT   = 1 << 3;
+        const SIGILL    = 1 << 4;
+        const SIGTRAP   = 1 << 5;
+        const SIGABRT   = 1 << 6;
+        const SIGBUS    = 1 << 7;
+        const SIGFPE    = 1 << 8;
+        const SIGKILL   = 1 << 9;
+        const SIGUSR1   = 1 << 10;
+        const SIGSEGV   = 1 << 11;
+        const SIGUSR2   = 1 << 12;
+        const SIGPIPE   = 1 << 13;
+        const SIGALRM   = 1 << 14;
+        const SIGTERM   = 1 << 15;
+        const SIGSTKFLT = 1 << 16;
+        const SIGCHLD   = 1 << 17;
+        const SIGCONT   = 1 << 18;
+        const SIGSTOP   = 1 << 19;
+        const SIGTSTP   = 1 << 20;
+        const SIGTTIN   = 1 << 21;
+        const SIGTTOU   = 1 << 22;
+        const SIGURG    = 1 << 23;
+        const SIGXCPU   = 1 << 24;
+        const SIGXFSZ   = 1 << 25;
+        const SIGVTALRM = 1 << 26;
+        const SIGPROF   = 1 << 27;
+        const SIGWINCH  = 1 << 28;
+        const SIGIO     = 1 << 29;
+        const SIGPWR    = 1 << 30;
+        const SIGSYS    = 1 << 31;
+    }
+}
+
+impl SignalFlags {
+    /// 编号为 signum 的信号，编号不合法时返回 None
+    pub fn from_signum(signum: usize) -> Option<Self> {
+        if signum == 0 || signum > MAX_SIG {
+            return None;
+        }
+        Self::from_bits(1 << signum)
+    }
+    /// 集合中编号最小的信号的编号
+    pub fn first_signum(&self) -> Option<usize> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.bits().trailing_zeros() as usize)
+        }
+    }
+    /// 默认动作为终止进程的信号，其余信号的默认动作为忽略
+    pub fn default_terminate() -> Self {
+        Self::SIGKILL
+            | Self::SIGTERM
+            | Self::SIGSEGV
+            | Self::SIGILL
+            | Self::SIGBUS
+            | Self::SIGTRAP
+            | Self::SIGXCPU
+            | Self::SIGPIPE
+    }
+    /// 默认动作终止进程时还会生成 core dump 的信号
+    pub fn default_core() -> Self {
+        Self::SIGSEGV | Self::SIGILL | Self::SIGBUS | Self::SIGTRAP
+    }
+    /// 由当前指令触发的同步信号：不能被屏蔽，也无法交给处理函数时只能终止进程，
+    /// 否则返回用户态后会再次执行出错的指令
+    pub fn synchronous() -> Self {
+        Self::SIGSEGV | Self::SIGILL | Self::SIGBUS | Self::SIGTRAP
+    }
+    /// 不能被屏蔽的信号
+    pub fn unmaskable() -> Self {
+        Self::SIGKILL | Self::SIGSTOP
+    }
+}
+
+/// 用户注册的信号处理动作，布局与用户库中的 SignalAction 一致
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SignalAction {
+    /// 处理函数的地址，或者 SIG_DFL / SIG_IGN
+    pub handler: usize,
+    /// 处理函数运行期间额外屏蔽的信号
+    pub mask: SignalFlags,
+}
+
+impl Default for SignalAction {
+    fn default() -> Self {
+        Self {
+            handler: SIG_DFL,
+            mask: SignalFlags::empty(),
+        }
+    }
+}
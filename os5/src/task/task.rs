@@ -1,26 +1,41 @@
 //! Types related to task management & Functions for completely changing TCB
 
 use super::TaskContext;
-use super::{pid_alloc, KernelStack, PidHandle};
-use crate::config::{TRAP_CONTEXT, MAX_SYSCALL_NUM};
-use crate::mm::{MemorySet, PhysPageNum, VirtAddr, KERNEL_SPACE};
-use crate::sync::UPSafeCell;
+use super::{
+    discard_fp_regs, insert_into_pid2task, kstack_alloc, pid_alloc, KernelStack,
+    ProcessControlBlock, Ptrace, SignalAction, SignalFlags, MAX_SIG, SIG_DFL, SIG_IGN,
+};
+use crate::config::{
+    ARG_MAX, CPU_MASK_ALL, MAX_SYSCALL_NUM, RT_RR_TIME_SLICE, TASK_NAME_LEN, USER_STACK_RESERVE,
+};
+use crate::errno::Errno;
+use crate::fs::FdEntry;
+use crate::loader::LoadError;
+use crate::mm::{translated_refmut, MemorySet, PhysPageNum, KERNEL_SPACE};
+use crate::sync::{UPRefMut, UPSafeCell};
+use crate::timer::get_time_us;
 use crate::trap::{trap_handler, TrapContext};
-use alloc::sync::{Arc, Weak};
+use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::cell::RefMut;
+
+/// spawn 时对子进程文件描述符表的重定向，每一项 (fd, entry) 让子进程的 fd 指向 entry 中的文件，状态标志一并复制
+pub type FdRedirects = Vec<(usize, FdEntry)>;
 
 /// Task control block structure
 /// Directly save the contents that will not change during running
 // 直接保存运行中不会更改的内容
 //在初始化之后就不再变化的作为一个字段直接放在任务控制块中。
+//任务控制块描述的是一个线程，进程级的资源放在它所属的 ProcessControlBlock 中。
 pub struct TaskControlBlock {
     // immutable
-    /// 进程标识符
-    pub pid: PidHandle,
-    /// Kernel stack corresponding to PID
-    //PID对应的内核栈
+    /// Kernel stack of this thread
+    //线程对应的内核栈
     pub kernel_stack: KernelStack,
+    /// 线程所属的进程
+    pub process: Arc<ProcessControlBlock>,
+    /// 进程内的线程号，主线程为 0
+    pub tid: usize,
     // mutable
     inner: UPSafeCell<TaskControlBlockInner>,
 }
@@ -32,20 +47,10 @@ pub struct TaskControlBlock {
 pub struct TaskControlBlockInner {
     //指出了应用地址空间中的 Trap 上下文被放在的物理页帧的物理页号。
     pub trap_cx_ppn: PhysPageNum,
-    //应用数据仅有可能出现在应用地址空间低于 base_size 字节的区域中。
-    //借助它我们可以清楚的知道应用有多少数据驻留在内存中。
-    pub base_size: usize,
     /// 保存任务上下文，用于任务切换。
     pub task_cx: TaskContext,
     /// 维护当前进程的执行状态。
     pub task_status: TaskStatus,
-    ///  表示应用地址空间。
-    pub memory_set: MemorySet,
-    /// 指向当前进程的父进程（如果存在的话）。
-    /// 注意我们使用 Weak 而非 Arc 来包裹另一个任务控制块，因此这个智能指针将不会影响父进程的引用计数。
-    pub parent: Option<Weak<TaskControlBlock>>,
-    /// 将当前进程的所有子进程的任务控制块以 Arc 智能指针的形式保存在一个向量中，这样才能够更方便的找到它们。
-    pub children: Vec<Arc<TaskControlBlock>>,
     //当进程调用 exit 系统调用主动退出或者执行出错由内核终止的时候，
     //它的退出码 exit_code 会被内核保存在它的任务控制块中，
     //并等待它的父进程通过 waitpid 回收它的资源的同时也收集它的 PID 以及退出码。
@@ -54,8 +59,67 @@ pub struct TaskControlBlockInner {
     pub start_time: usize,
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
 
-    pub priority: u8,
-    pub pass: usize,
+    /// 调度使用的有效优先级，持有互斥锁时可能被等待者临时提升
+    pub priority: usize,
+    /// 通过 set_priority 设置的基础优先级
+    pub base_priority: usize,
+    pub pass: u64,
+    /// 本次上 CPU 后剩余的时间片（时钟中断次数）
+    pub time_slice: usize,
+    /// 最近一次进入就绪队列的时刻（微秒），用于老化机制计算等待时间
+    pub ready_since: usize,
+
+    /// 任务在 MLFQ 中所处的队列级别，0 为最高
+    pub mlfq_level: usize,
+    /// 任务在当前级别上已经用掉的时钟中断数
+    pub mlfq_ticks: usize,
+
+    /// 按优先级加权后的虚拟运行时间，CFS 调度使用
+    pub vruntime: u64,
+    /// 本次被 CFS 调度上 CPU 的时刻
+    pub exec_start: usize,
+
+    /// CPU 亲和性掩码：第 i 位为 1 表示允许在 i 号核上运行
+    pub cpu_mask: usize,
+
+    /// 调度类：普通任务交给 Scheduler 调度，实时任务总是优先于普通任务
+    pub sched_policy: SchedPolicy,
+    /// 实时优先级，普通任务为 0
+    pub rt_priority: usize,
+
+    /// 已经收到、尚未处理的信号
+    pub signals: SignalFlags,
+    /// 被屏蔽的信号，它们会一直保持待处理状态
+    pub signal_mask: SignalFlags,
+    /// 每个信号的处理动作
+    pub signal_actions: [SignalAction; MAX_SIG + 1],
+    /// 正在由用户处理函数处理的信号
+    pub handling_sig: Option<usize>,
+    /// 进入信号处理函数之前被打断的 Trap 上下文，sigreturn 时恢复
+    pub trap_cx_backup: Option<TrapContext>,
+
+    /// 进程名，默认为加载的应用名，可以通过 prctl 修改
+    pub name: String,
+
+
+    /// 在用户态运行的累计时间（微秒）
+    pub utime: usize,
+    /// 在内核态运行的累计时间（微秒）
+    pub stime: usize,
+    /// 已回收的子进程（包括它们回收的后代）的 utime 之和
+    pub cutime: usize,
+    /// 已回收的子进程（包括它们回收的后代）的 stime 之和
+    pub cstime: usize,
+    /// 上一次记账的时刻，此后的时间尚未计入 utime/stime
+    pub last_timestamp: usize,
+
+    /// 由 set_tid_address 设置的用户地址，线程退出时内核向其中写入 0，为 0 表示不需要
+    pub clear_child_tid: usize,
+
+    /// 是否允许关机、重启等特权操作：只有 initproc 以及由它直接创建的进程拥有，exec 后保留
+    pub privileged: bool,
+    /// 被父进程跟踪的状态
+    pub ptrace: Ptrace,
 }
 
 /// Simple access to its internal fields
@@ -66,63 +130,170 @@ impl TaskControlBlockInner {
         &self.task_cx_ptr as *const usize
     }
     */
+    //新任务的初始状态：第一次被调度时从 trap_return 返回用户态，调度参数与信号处理动作均为默认值。
+    //fork、spawn 与创建线程时在此基础上改写从父进程或创建者继承的字段
+    pub fn new(
+        trap_cx_ppn: PhysPageNum,
+        kernel_stack_top: usize,
+        name: String,
+        privileged: bool,
+    ) -> Self {
+        Self {
+            trap_cx_ppn,
+            task_cx: TaskContext::goto_trap_return(kernel_stack_top),
+            task_status: TaskStatus::Ready,
+            exit_code: 0,
+            priority: 16,
+            base_priority: 16,
+            pass: 0,
+
+            start_time: 0,
+            syscall_times: [0; MAX_SYSCALL_NUM],
+            time_slice: 0,
+            ready_since: 0,
+            mlfq_level: 0,
+            mlfq_ticks: 0,
+            vruntime: 0,
+            exec_start: 0,
+            cpu_mask: CPU_MASK_ALL,
+            sched_policy: SchedPolicy::Normal,
+            rt_priority: 0,
+            signals: SignalFlags::empty(),
+            signal_mask: SignalFlags::empty(),
+            signal_actions: [SignalAction::default(); MAX_SIG + 1],
+            handling_sig: None,
+            trap_cx_backup: None,
+            name,
+            utime: 0,
+            stime: 0,
+            cutime: 0,
+            cstime: 0,
+            last_timestamp: 0,
+            clear_child_tid: 0,
+            privileged,
+            ptrace: Ptrace::default(),
+        }
+    }
     pub fn get_trap_cx(&self) -> &'static mut TrapContext {
         self.trap_cx_ppn.get_mut()
     }
-    pub fn get_user_token(&self) -> usize {
-        self.memory_set.token()
-    }
     fn get_status(&self) -> TaskStatus {
         self.task_status
     }
     pub fn is_zombie(&self) -> bool {
         self.get_status() == TaskStatus::Zombie
     }
+    //修改进程名，超出 TASK_NAME_LEN - 1 字节的部分被截断
+    pub fn set_name(&mut self, name: &str) {
+        self.name = truncate_name(name);
+    }
+    //将上一次记账以来的时间计入用户态时间，在从用户态陷入内核时调用
+    pub fn account_utime(&mut self) {
+        let now = get_time_us();
+        self.utime += now - self.last_timestamp;
+        self.last_timestamp = now;
+    }
+    //将上一次记账以来的时间计入内核态时间，在返回用户态或被换出处理器时调用
+    pub fn account_stime(&mut self) {
+        let now = get_time_us();
+        self.stime += now - self.last_timestamp;
+        self.last_timestamp = now;
+    }
+    //根据调度类与当前优先级查表，重新装满时间片；SCHED_FIFO 任务的时间片永远不会用完。
+    //MLFQ 调度时普通任务的时间片由所在级别决定，为该级时间片中尚未用掉的部分，用完时恰好被降级
+    pub fn refill_time_slice(&mut self) {
+        self.time_slice = match self.sched_policy {
+            #[cfg(feature = "sched-mlfq")]
+            SchedPolicy::Normal => crate::config::MLFQ_TIME_SLICES[self.mlfq_level]
+                .saturating_sub(self.mlfq_ticks)
+                .max(1),
+            #[cfg(not(feature = "sched-mlfq"))]
+            SchedPolicy::Normal => crate::config::TIME_SLICE_TABLE
+                .iter()
+                .find(|(min_prio, _)| self.priority >= *min_prio)
+                .map_or(1, |(_, slice)| *slice),
+            SchedPolicy::Fifo => usize::MAX,
+            SchedPolicy::RoundRobin => RT_RR_TIME_SLICE,
+        };
+    }
+    //任务是否允许在 hart_id 号核上运行
+    pub fn can_run_on(&self, hart_id: usize) -> bool {
+        self.cpu_mask & (1 << hart_id) != 0
+    }
+    //是否有会打断阻塞的待处理信号：返回用户态时会进入处理函数或者终止进程的信号。
+    //判断与 handle_signals 一致，被忽略的信号以及要等当前处理函数返回才能处理的信号不算
+    pub fn signal_interrupts(&self) -> bool {
+        let mut blocked = self.signal_mask;
+        if let Some(signum) = self.handling_sig {
+            blocked |= self.signal_actions[signum].mask | SignalFlags::from_signum(signum).unwrap();
+        }
+        blocked -= SignalFlags::unmaskable() | SignalFlags::synchronous();
+        let mut deliverable = self.signals - blocked;
+        while let Some(signum) = deliverable.first_signum() {
+            let signal = SignalFlags::from_signum(signum).unwrap();
+            deliverable.remove(signal);
+            let handler = self.signal_actions[signum].handler;
+            let catchable = !SignalFlags::unmaskable().contains(signal);
+            let synchronous = SignalFlags::synchronous().contains(signal);
+            if catchable && handler == SIG_IGN && !synchronous {
+                continue;
+            }
+            if catchable && handler != SIG_DFL && handler != SIG_IGN {
+                if self.handling_sig.is_none() || synchronous {
+                    return true;
+                }
+                continue;
+            }
+            if SignalFlags::default_terminate().contains(signal) {
+                return true;
+            }
+        }
+        false
+    }
 }
 
 impl TaskControlBlock {
     //尝试获取互斥锁来得到 TaskControlBlockInner 的可变引用。
-    pub fn inner_exclusive_access(&self) -> RefMut<'_, TaskControlBlockInner> {
+    pub fn inner_exclusive_access(&self) -> UPRefMut<'_, TaskControlBlockInner> {
         self.inner.exclusive_access()
     }
+    //inner 已经被借用时返回 None，供内核调试器在任意位置停下时查看任务
+    pub fn try_inner_exclusive_access(&self) -> Option<UPRefMut<'_, TaskControlBlockInner>> {
+        self.inner.try_exclusive_access()
+    }
 
-    //new 用来创建一个新的进程，目前仅用于内核中手动创建唯一一个初始进程 initproc 。
-    pub fn new(elf_data: &[u8]) -> Self {
+    //new 用来创建一个新的进程及其主线程，目前仅用于内核中手动创建唯一一个初始进程 initproc 。
+    pub fn new(name: &str, elf_data: &[u8]) -> Arc<Self> {
         // 解析 ELF 得到应用地址空间 memory_set ，
         //用户栈在应用地址空间中的位置 user_sp 以及应用的入口点 entry_point 。
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
-        //手动查页表找到应用地址空间中的 Trap 上下文实际所在的物理页帧。
-        let trap_cx_ppn = memory_set
-            .translate(VirtAddr::from(TRAP_CONTEXT).into())
-            .unwrap()
-            .ppn();
-        //在内核空间中分配进程标识符和内核栈,并记录下内核栈在内核地址空间的位置 kernel_stack_top 。
+        let (memory_set, user_sp, entry_point) =
+            MemorySet::from_elf(elf_data).expect("initproc is not a loadable ELF");
+        //在内核空间中分配进程标识符，主线程的内核栈由它决定
         let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let process = Arc::new(ProcessControlBlock::new(pid_handle, memory_set, user_sp));
+        let mut process_inner = process.inner_exclusive_access();
+        let tid = process_inner.alloc_tid();
+        //手动查页表找到应用地址空间中的 Trap 上下文实际所在的物理页帧。
+        let trap_cx_ppn = process_inner.trap_cx_ppn(tid);
+        let kernel_stack = kstack_alloc().expect("no memory for the kernel stack of initproc");
         let kernel_stack_top = kernel_stack.get_top();
         // push a task context which goes to trap_return to the top of kernel stack
-        //整合之前的部分信息创建进程控制块 task_control_block 。
-        let task_control_block = Self {
-            pid: pid_handle,
+        //整合之前的部分信息创建任务控制块 task_control_block 。
+        let task_control_block = Arc::new(Self {
             kernel_stack,
+            process: process.clone(),
+            tid,
             inner: unsafe {
-                UPSafeCell::new(TaskControlBlockInner {
+                UPSafeCell::new(TaskControlBlockInner::new(
                     trap_cx_ppn,
-                    base_size: user_sp,
-                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
-                    task_status: TaskStatus::Ready,
-                    memory_set,
-                    parent: None,
-                    children: Vec::new(),
-                    exit_code: 0,
-                    priority: 16,
-                    pass: 0,
-
-                    start_time: 0,
-                    syscall_times: [0; MAX_SYSCALL_NUM],
-                })
+                    kernel_stack_top,
+                    truncate_name(name),
+                    true,
+                ))
             },
-        };
+        });
+        process_inner.attach_task(tid, task_control_block.clone());
+        drop(process_inner);
         // prepare TrapContext in user space
         //初始化位于该进程应用地址空间中的 Trap 上下文，使得第一次进入用户态时，
         //能正确跳转到应用入口点并设置好用户栈， 同时也保证在 Trap 的时候用户态能正确进入内核态。
@@ -137,22 +308,48 @@ impl TaskControlBlock {
         task_control_block
     }
     /// exec 用来实现 exec 系统调用，即当前进程加载并执行另一个 ELF 格式可执行文件。
-    pub fn exec(&self, elf_data: &[u8]) {
+    //envs 为 None 时沿用当前进程的环境变量；ELF 无法加载时返回错误，当前进程保持原样
+    pub fn exec(
+        &self,
+        name: &str,
+        elf_data: &[u8],
+        args: Vec<String>,
+        envs: Option<Vec<String>>,
+    ) -> Result<(), LoadError> {
+        let envs = envs.unwrap_or_else(|| self.process.inner_exclusive_access().environ.clone());
+        if args_size(&args, &envs) > ARG_MAX {
+            return Err(LoadError::ArgsTooBig);
+        }
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data);
-        let trap_cx_ppn = memory_set
-            .translate(VirtAddr::from(TRAP_CONTEXT).into())
-            .unwrap()
-            .ppn();
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data)?;
+        let ustack_top = user_sp;
+        let (user_sp, argv_base, envp_base) = push_args(&memory_set, user_sp, &args, &envs);
 
-        // **** access inner exclusively
-        let mut inner = self.inner_exclusive_access();
         // substitute memory_set
         //从 ELF 生成一个全新的地址空间并直接替换进来，
         //原有地址空间生命周期结束，里面包含的全部物理页帧都会被回收
-        inner.memory_set = memory_set;
+        let mut process_inner = self.process.inner_exclusive_access();
+        process_inner.memory_set = memory_set;
+        process_inner.base_size = ustack_top;
+        process_inner.ustack_base = ustack_top - USER_STACK_RESERVE;
+        //新的地址空间中用户堆为空
+        process_inner.program_brk = process_inner.heap_bottom;
+        process_inner.environ = envs;
         // update trap_cx ppn
         //修改新的地址空间中的 Trap 上下文，
+        let trap_cx_ppn = process_inner.trap_cx_ppn(self.tid);
+        drop(process_inner);
+
+        // **** access inner exclusively
+        let mut inner = self.inner_exclusive_access();
+        //原来的信号处理函数在新程序中不再有意义
+        inner.signal_actions = [SignalAction::default(); MAX_SIG + 1];
+        inner.handling_sig = None;
+        inner.trap_cx_backup = None;
+        inner.clear_child_tid = 0;
+        //单步执行的临时断点随原来的地址空间一起消失
+        inner.ptrace.step_breakpoints.clear();
+        inner.set_name(name);
         inner.trap_cx_ppn = trap_cx_ppn;
         // initialize trap_cx
         //将解析得到的应用入口点、用户栈位置以及一些内核的信息进行初始化，这样才能正常实现 Trap 机制。
@@ -164,119 +361,299 @@ impl TaskControlBlock {
             self.kernel_stack.get_top(),
             trap_handler as usize,
         );
+        //用户态 _start(argc, argv) 从 a0/a1 取得命令行参数
+        trap_cx.x[10] = args.len();
+        trap_cx.x[11] = argv_base;
+        trap_cx.x[12] = envp_base;
+        //新程序从全为 0 的浮点寄存器开始执行
+        discard_fp_regs();
         // **** release inner automatically
+        Ok(())
     }
     ///fork 用来实现 fork 系统调用，即当前进程 fork 出来一个与之几乎相同的子进程。
-    //从父进程的进程控制块创建一份子进程的控制块
-    pub fn fork(self: &Arc<TaskControlBlock>) -> Arc<TaskControlBlock> {
+    //从父进程的进程控制块创建一份子进程的控制块；只有单线程的进程可以 fork，此时 self 就是主线程。
+    //物理内存不足时返回 Errno::ENOMEM，已经分配的资源全部释放，不会分配 PID
+    pub fn fork(self: &Arc<TaskControlBlock>) -> Result<Arc<TaskControlBlock>, Errno> {
         // ---- access parent PCB exclusively
-        let mut parent_inner = self.inner_exclusive_access();
+        let mut parent_process_inner = self.process.inner_exclusive_access();
+        let parent_inner = self.inner_exclusive_access();
         // copy user space(include trap context)
         //子进程的地址空间不是通过解析 ELF，
         //而是通过调用 MemorySet::from_existed_user 复制父进程地址空间得到的
-        let memory_set = MemorySet::from_existed_user(&parent_inner.memory_set);
-        let trap_cx_ppn = memory_set
-            .translate(VirtAddr::from(TRAP_CONTEXT).into())
-            .unwrap()
-            .ppn();
+        let memory_set = MemorySet::from_existed_user(&parent_process_inner.memory_set)?;
         // alloc a pid and a kernel stack in kernel space
-        //在内核空间中分配pid和内核栈
-        let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        //在内核空间中分配pid和内核栈，内核栈需要分配页帧，先于 pid 分配
+        let kernel_stack = kstack_alloc()?;
+        let process = Arc::new(ProcessControlBlock::new(
+            pid_alloc(),
+            memory_set,
+            parent_process_inner.base_size,
+        ));
+        let mut process_inner = process.inner_exclusive_access();
+        process_inner.parent = Some(Arc::downgrade(self));
+        process_inner.fd_table = parent_process_inner.fd_table.clone();
+        process_inner.mutex_list = parent_process_inner.mutex_list.clone();
+        process_inner.program_brk = parent_process_inner.program_brk;
+        process_inner.pgid = parent_process_inner.pgid;
+        process_inner.sid = parent_process_inner.sid;
+        process_inner.environ = parent_process_inner.environ.clone();
+        process_inner.rlimits = parent_process_inner.rlimits;
+        process_inner.seccomp = parent_process_inner.seccomp;
+        let tid = process_inner.alloc_tid();
+        let trap_cx_ppn = process_inner.trap_cx_ppn(tid);
         let kernel_stack_top = kernel_stack.get_top();
         let task_control_block = Arc::new(TaskControlBlock {
-            pid: pid_handle,
             kernel_stack,
+            process: process.clone(),
+            tid,
             inner: unsafe {
                 UPSafeCell::new(TaskControlBlockInner {
-                    trap_cx_ppn,
-                    base_size: parent_inner.base_size,
-                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
-                    task_status: TaskStatus::Ready,
-                    memory_set,
-                    parent: Some(Arc::downgrade(self)),
-                    children: Vec::new(),
-                    exit_code: 0,
-                    priority: 16,
-                    pass: 0,
-
-                    start_time: 0,
-                    syscall_times: [0; MAX_SYSCALL_NUM],
+                    //子进程从父进程当前的 pass 出发，保证与就绪任务间的差值不会过大
+                    pass: parent_inner.pass,
+                    cpu_mask: parent_inner.cpu_mask,
+                    sched_policy: parent_inner.sched_policy,
+                    rt_priority: parent_inner.rt_priority,
+                    signal_mask: parent_inner.signal_mask,
+                    signal_actions: parent_inner.signal_actions,
+                    handling_sig: parent_inner.handling_sig,
+                    trap_cx_backup: parent_inner.trap_cx_backup,
+                    ..TaskControlBlockInner::new(
+                        trap_cx_ppn,
+                        kernel_stack_top,
+                        parent_inner.name.clone(),
+                        self.getpid() == 0,
+                    )
                 })
             },
         });
+        process_inner.attach_task(tid, task_control_block.clone());
+        drop(process_inner);
         // add child
         //将子进程插入到父进程的孩子向量 children 中
-        parent_inner.children.push(task_control_block.clone());
+        parent_process_inner.children.push(task_control_block.clone());
+        insert_into_pid2task(task_control_block.getpid(), task_control_block.clone());
         // modify kernel_sp in trap_cx
         // **** access children PCB exclusively
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
         trap_cx.kernel_sp = kernel_stack_top;
         // return
-        task_control_block
+        Ok(task_control_block)
         // ---- release parent PCB automatically
         // **** release children PCB automatically
     }
     //以 usize 的形式返回当前进程的进程标识符。
     pub fn getpid(&self) -> usize {
-        self.pid.0
+        self.process.getpid()
+    }
+    //当前进程地址空间的 token
+    pub fn get_user_token(&self) -> usize {
+        self.process.inner_exclusive_access().get_user_token()
+    }
+    //返回进程名的副本，用于内核日志等场合
+    pub fn name(&self) -> String {
+        self.inner_exclusive_access().name.clone()
     }
 
-    //功能：新建子进程，使其执行目标程序
-    //返回值：成功返回子进程id，否则返回-1。
-    pub fn spawn(self: &Arc<TaskControlBlock>, _elf_data: &[u8]) -> Arc<TaskControlBlock> {
+    //功能：新建子进程，使其执行目标程序，redirects 中的每一项 (fd, file) 让子进程的文件描述符 fd 指向 file
+    //返回值：成功返回子进程的主线程；ELF 无法加载或物理内存不足时返回错误，不会分配 PID。
+    pub fn spawn(
+        self: &Arc<TaskControlBlock>,
+        name: &str,
+        elf_data: &[u8],
+        args: Vec<String>,
+        envs: Option<Vec<String>>,
+        redirects: FdRedirects,
+    ) -> Result<Arc<TaskControlBlock>, LoadError> {
+        let envs = envs.unwrap_or_else(|| self.process.inner_exclusive_access().environ.clone());
+        if args_size(&args, &envs) > ARG_MAX {
+            return Err(LoadError::ArgsTooBig);
+        }
+        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(elf_data)?;
+        // alloc a kernel stack in kernel space
+        let kernel_stack = kstack_alloc().map_err(|_| LoadError::NoMemory)?;
         // ---- access parent PCB exclusively
-        let mut parent_inner = self.inner_exclusive_access();
-        // copy user space(include trap context)
-        let (memory_set, user_sp, entry_point) = MemorySet::from_elf(_elf_data);
-        let trap_cx_ppn = memory_set
-            .translate(VirtAddr::from(TRAP_CONTEXT).into())
-            .unwrap()
-            .ppn();
-        // alloc a pid and a kernel stack in kernel space
-        let pid_handle = pid_alloc();
-        let kernel_stack = KernelStack::new(&pid_handle);
+        let mut parent_process_inner = self.process.inner_exclusive_access();
+        let parent_inner = self.inner_exclusive_access();
+        let process = Arc::new(ProcessControlBlock::new(pid_alloc(), memory_set, user_sp));
+        let mut process_inner = process.inner_exclusive_access();
+        let (user_sp, argv_base, envp_base) =
+            push_args(&process_inner.memory_set, user_sp, &args, &envs);
+        process_inner.parent = Some(Arc::downgrade(self));
+        process_inner.pgid = parent_process_inner.pgid;
+        process_inner.sid = parent_process_inner.sid;
+        process_inner.environ = envs;
+        process_inner.rlimits = parent_process_inner.rlimits;
+        process_inner.seccomp = parent_process_inner.seccomp;
+        //与 fork + exec 一样，新进程继承父进程打开的文件
+        process_inner.fd_table = parent_process_inner.fd_table.clone();
+        //再让子进程的文件描述符 fd 指向 file，例如把标准输出接到管道上
+        for (fd, entry) in redirects {
+            if fd >= process_inner.fd_table.len() {
+                process_inner.fd_table.resize(fd + 1, None);
+            }
+            process_inner.fd_table[fd] = Some(entry);
+        }
+        let tid = process_inner.alloc_tid();
+        let trap_cx_ppn = process_inner.trap_cx_ppn(tid);
         let kernel_stack_top = kernel_stack.get_top();
         let task_control_block = Arc::new(TaskControlBlock {
-            pid: pid_handle,
             kernel_stack,
+            process: process.clone(),
+            tid,
             inner: unsafe {
                 UPSafeCell::new(TaskControlBlockInner {
-                    trap_cx_ppn,
-                    base_size: parent_inner.base_size,
-                    task_cx: TaskContext::goto_trap_return(kernel_stack_top),
-                    task_status: TaskStatus::Ready,
-                    memory_set,
-                    parent: Some(Arc::downgrade(self)),
-                    children: Vec::new(),
-                    exit_code: 0,
-                    priority: 16,
-                    pass: 0,
-
-                    start_time: 0,
-                    syscall_times: [0; MAX_SYSCALL_NUM],
+                    pass: parent_inner.pass,
+                    cpu_mask: parent_inner.cpu_mask,
+                    sched_policy: parent_inner.sched_policy,
+                    rt_priority: parent_inner.rt_priority,
+                    signal_mask: parent_inner.signal_mask,
+                    ..TaskControlBlockInner::new(
+                        trap_cx_ppn,
+                        kernel_stack_top,
+                        truncate_name(name),
+                        self.getpid() == 0,
+                    )
                 })
             },
         });
+        process_inner.attach_task(tid, task_control_block.clone());
+        drop(process_inner);
         // add child
-        parent_inner.children.push(task_control_block.clone());
+        parent_process_inner.children.push(task_control_block.clone());
+        insert_into_pid2task(task_control_block.getpid(), task_control_block.clone());
         // modify kernel_sp in trap_cx
         // **** access children PCB exclusively
         let trap_cx = task_control_block.inner_exclusive_access().get_trap_cx();
-        *trap_cx = TrapContext::app_init_context(entry_point, user_sp, KERNEL_SPACE.exclusive_access().token(), kernel_stack_top, trap_handler as usize);
-        trap_cx.kernel_sp = kernel_stack_top;
+        *trap_cx = TrapContext::app_init_context(
+            entry_point,
+            user_sp,
+            KERNEL_SPACE.exclusive_access().token(),
+            kernel_stack_top,
+            trap_handler as usize,
+        );
+        trap_cx.x[10] = args.len();
+        trap_cx.x[11] = argv_base;
+        trap_cx.x[12] = envp_base;
         // return
-        task_control_block
+        Ok(task_control_block)
         // ---- release parent PCB automatically
         // **** release children PCB automatically
     }
+
+    /// 在当前进程中创建一个新线程，为它分配线程号、用户栈、Trap 上下文与内核栈
+    //新线程的调度参数、信号处理动作等从 self 继承；Trap 上下文的内容由调用者填写，
+    //之后再由调用者把它加入就绪队列。物理内存不足时归还线程号并返回 Errno::ENOMEM
+    pub fn new_thread(self: &Arc<TaskControlBlock>) -> Result<Arc<TaskControlBlock>, Errno> {
+        let mut process_inner = self.process.inner_exclusive_access();
+        let tid = process_inner.alloc_tid();
+        if let Err(err) = process_inner.alloc_user_res(tid) {
+            process_inner.dealloc_tid(tid);
+            return Err(err);
+        }
+        let kernel_stack = match kstack_alloc() {
+            Ok(kernel_stack) => kernel_stack,
+            Err(err) => {
+                process_inner.dealloc_user_res(tid);
+                process_inner.dealloc_tid(tid);
+                return Err(err);
+            }
+        };
+        let trap_cx_ppn = process_inner.trap_cx_ppn(tid);
+        let kernel_stack_top = kernel_stack.get_top();
+        let creator_inner = self.inner_exclusive_access();
+        let task_control_block = Arc::new(TaskControlBlock {
+            kernel_stack,
+            process: self.process.clone(),
+            tid,
+            inner: unsafe {
+                UPSafeCell::new(TaskControlBlockInner {
+                    priority: creator_inner.base_priority,
+                    base_priority: creator_inner.base_priority,
+                    pass: creator_inner.pass,
+                    cpu_mask: creator_inner.cpu_mask,
+                    sched_policy: creator_inner.sched_policy,
+                    rt_priority: creator_inner.rt_priority,
+                    signal_mask: creator_inner.signal_mask,
+                    signal_actions: creator_inner.signal_actions,
+                    ..TaskControlBlockInner::new(
+                        trap_cx_ppn,
+                        kernel_stack_top,
+                        creator_inner.name.clone(),
+                        creator_inner.privileged,
+                    )
+                })
+            },
+        });
+        process_inner.attach_task(tid, task_control_block.clone());
+        Ok(task_control_block)
+    }
+}
+
+/// 截取进程名的前 TASK_NAME_LEN - 1 个字节，不会截断多字节字符
+fn truncate_name(name: &str) -> String {
+    let mut end = name.len().min(TASK_NAME_LEN - 1);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    String::from(&name[..end])
+}
+
+/// 命令行参数与环境变量压栈后占用的字节数：各个字符串及结尾的 \0，加上两个以 0 结尾的指针数组
+fn args_size(args: &[String], envs: &[String]) -> usize {
+    let word = core::mem::size_of::<usize>();
+    let strs = args.iter().chain(envs.iter());
+    strs.map(|s| s.len() + 1).sum::<usize>() + (args.len() + envs.len() + 2) * word
+}
+
+/// 将命令行参数与环境变量压入新地址空间的用户栈，返回压栈后的 user_sp 以及 argv、envp 数组的地址
+//与 Linux 的布局相同，栈顶处是以 0 结尾的 argv 指针数组，紧接着是以 0 结尾的 envp 指针数组，
+//其下依次存放各个以 \0 结尾的字符串，最后将 sp 向下对齐到 16 字节，满足 RISC-V 调用约定对栈指针的要求。
+fn push_args(
+    memory_set: &MemorySet,
+    mut user_sp: usize,
+    args: &[String],
+    envs: &[String],
+) -> (usize, usize, usize) {
+    let token = memory_set.token();
+    let word = core::mem::size_of::<usize>();
+    user_sp -= (args.len() + envs.len() + 2) * word;
+    let argv_base = user_sp;
+    let envp_base = argv_base + (args.len() + 1) * word;
+    for (base, strs) in [(argv_base, args), (envp_base, envs)].iter() {
+        *translated_refmut(token, (base + strs.len() * word) as *mut usize) = 0;
+        for (i, s) in strs.iter().enumerate() {
+            user_sp -= s.len() + 1;
+            *translated_refmut(token, (base + i * word) as *mut usize) = user_sp;
+            let mut p = user_sp;
+            for c in s.as_bytes() {
+                *translated_refmut(token, p as *mut u8) = *c;
+                p += 1;
+            }
+            *translated_refmut(token, p as *mut u8) = 0;
+        }
+    }
+    user_sp -= user_sp % 16;
+    (user_sp, argv_base, envp_base)
 }
 
 #[derive(Copy, Clone, PartialEq)]
-/// task status: UnInit, Ready, Running, Exited
+/// task status: UnInit, Ready, Running, Blocked, Exited
 pub enum TaskStatus {
     UnInit,
     Ready,
     Running,
+    /// 在某个等待队列上等待事件发生
+    Blocked,
     Zombie,
 }
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// 调度类，取值与 Linux 的 SCHED_OTHER / SCHED_FIFO / SCHED_RR 一致
+pub enum SchedPolicy {
+    /// 普通任务，由 stride 等调度算法管理
+    Normal = 0,
+    /// 实时任务，一直运行到阻塞、让出或被更高实时优先级的任务抢占
+    Fifo = 1,
+    /// 实时任务，同一实时优先级的任务之间按时间片轮转
+    RoundRobin = 2,
+}
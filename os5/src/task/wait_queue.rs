@@ -0,0 +1,66 @@
+//! Implementation of [`WaitQueue`]
+//!
+//! 等待某个事件（子进程退出、定时器到期……）的任务被放入等待队列并进入 Blocked 状态，
+//! 它们既不在就绪队列中，也不占用处理器，直到事件发生时被唤醒并重新加入就绪队列。
+
+use super::{wakeup_task, TaskControlBlock};
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
+/// A queue of tasks blocked on the same event.
+pub struct WaitQueue {
+    queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl WaitQueue {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+    //将任务加入等待队列，调用者随后应当释放所有借用并调用 block_current_and_run_next
+    pub fn push(&mut self, task: Arc<TaskControlBlock>) {
+        self.queue.push_back(task);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+    /// 把任务移出队列，返回它是否还在队列中。
+    //被信号唤醒的任务仍留在队列里，醒来后调用这个函数，以免之后被这个队列误唤醒
+    pub fn remove(&mut self, task: &Arc<TaskControlBlock>) -> bool {
+        let len = self.queue.len();
+        self.queue.retain(|waiting| !Arc::ptr_eq(waiting, task));
+        self.queue.len() != len
+    }
+    #[allow(unused)]
+    /// 唤醒最早进入队列的一个任务
+    pub fn wake_one(&mut self) -> bool {
+        match self.queue.pop_front() {
+            Some(task) => {
+                wakeup_task(task);
+                true
+            }
+            None => false,
+        }
+    }
+    /// 按进入队列的顺序唤醒至多 count 个任务，返回被唤醒的任务数
+    pub fn wake(&mut self, count: usize) -> usize {
+        let mut woken = 0;
+        while woken < count {
+            match self.queue.pop_front() {
+                Some(task) => wakeup_task(task),
+                None => break,
+            }
+            woken += 1;
+        }
+        woken
+    }
+    /// 唤醒队列中的所有任务，返回被唤醒的任务数
+    pub fn wake_all(&mut self) -> usize {
+        let count = self.queue.len();
+        while let Some(task) = self.queue.pop_front() {
+            wakeup_task(task);
+        }
+        count
+    }
+}
@@ -1,18 +1,156 @@
 use crate::config::CLOCK_FREQ;
+use crate::drivers::rtc;
 use crate::sbi::set_timer;
+use crate::sync::UPSafeCell;
+use crate::task::{wakeup_task, TaskControlBlock};
+use alloc::collections::BinaryHeap;
+use alloc::sync::Arc;
+use core::cmp::Ordering;
+use lazy_static::*;
 use riscv::register::time;
 
-const TICKS_PER_SEC: usize = 100;
+pub const TICKS_PER_SEC: usize = 100;
 const MICRO_PER_SEC: usize = 1_000_000;
+const NANO_PER_SEC: usize = 1_000_000_000;
 
+#[cfg(not(feature = "deterministic"))]
 pub fn get_time() -> usize {
     time::read()
 }
 
+#[cfg(feature = "deterministic")]
+pub fn get_time() -> usize {
+    deterministic::read()
+}
+
 pub fn get_time_us() -> usize {
-    time::read() / (CLOCK_FREQ / MICRO_PER_SEC)
+    get_time() / (CLOCK_FREQ / MICRO_PER_SEC)
+}
+
+/// 启动以来经过的时间（纳秒）
+pub fn get_time_ns() -> usize {
+    let ticks = get_time();
+    ticks / CLOCK_FREQ * NANO_PER_SEC + ticks % CLOCK_FREQ * NANO_PER_SEC / CLOCK_FREQ
+}
+
+lazy_static! {
+    //启动时刻的墙上时间（纳秒），由 RTC 的读数减去此时已经经过的时间得到
+    static ref BOOT_REALTIME_NS: usize = rtc::read_ns().saturating_sub(get_time_ns());
+}
+
+/// 读取 RTC 记下启动时刻的墙上时间，需要在内核地址空间映射了 RTC 之后调用
+pub fn init() {
+    lazy_static::initialize(&BOOT_REALTIME_NS);
+}
+
+/// 墙上时间，即自 1970-01-01 00:00:00 UTC 以来的纳秒数：启动时刻的墙上时间加上启动以来经过的时间
+pub fn get_realtime_ns() -> usize {
+    *BOOT_REALTIME_NS + get_time_ns()
 }
 
 pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
+    set_timer(time::read() + CLOCK_FREQ / TICKS_PER_SEC);
+}
+
+/// 睡眠队列中的一项：到达 expire_us 时刻后唤醒 task
+pub struct SleepEntry {
+    pub expire_us: usize,
+    pub task: Arc<TaskControlBlock>,
+}
+
+impl PartialEq for SleepEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.expire_us == other.expire_us
+    }
+}
+
+impl Eq for SleepEntry {}
+
+impl PartialOrd for SleepEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+//唤醒时刻最早的任务位于堆顶
+impl Ord for SleepEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.expire_us.cmp(&self.expire_us)
+    }
+}
+
+lazy_static! {
+    /// 按唤醒时刻排序的睡眠队列
+    static ref SLEEP_QUEUE: UPSafeCell<BinaryHeap<SleepEntry>> =
+        unsafe { UPSafeCell::new(BinaryHeap::new()) };
+}
+
+/// 将任务加入睡眠队列，调用者随后应当阻塞该任务
+pub fn add_sleeper(expire_us: usize, task: Arc<TaskControlBlock>) {
+    SLEEP_QUEUE
+        .exclusive_access()
+        .push(SleepEntry { expire_us, task });
+}
+
+/// 将任务移出睡眠队列，用于被信号提前唤醒的任务
+pub fn remove_sleeper(task: &Arc<TaskControlBlock>) {
+    let mut sleep_queue = SLEEP_QUEUE.exclusive_access();
+    let entries = core::mem::take(&mut *sleep_queue);
+    *sleep_queue = entries
+        .into_iter()
+        .filter(|entry| !Arc::ptr_eq(&entry.task, task))
+        .collect();
+}
+
+/// 唤醒所有睡眠时间已到的任务，只需查看堆顶
+pub fn check_sleepers() {
+    let now = get_time_us();
+    let mut sleep_queue = SLEEP_QUEUE.exclusive_access();
+    while let Some(entry) = sleep_queue.peek() {
+        if entry.expire_us > now {
+            break;
+        }
+        let entry = sleep_queue.pop().unwrap();
+        wakeup_task(entry.task);
+    }
+}
+
+/// 确定性调度模式下的伪时钟与虚拟时钟中断
+//真实的 time 寄存器和时钟中断都与宿主机负载有关，无法复现。
+//此模式下关闭真实时钟中断，改为每执行 DETERMINISTIC_TICK_SYSCALLS 次系统调用产生一次虚拟时钟中断；
+//时间则由一个伪时钟给出：起点由编译时环境变量 SEED 决定，每读取一次前进 DETERMINISTIC_TIME_STEP。
+#[cfg(feature = "deterministic")]
+pub mod deterministic {
+    use crate::config::{DETERMINISTIC_TICK_SYSCALLS, DETERMINISTIC_TIME_STEP};
+    use crate::sync::UPSafeCell;
+    use lazy_static::*;
+
+    struct PseudoClock {
+        now: usize,
+        syscalls: usize,
+    }
+
+    lazy_static! {
+        static ref PSEUDO_CLOCK: UPSafeCell<PseudoClock> = unsafe {
+            UPSafeCell::new(PseudoClock {
+                now: option_env!("SEED")
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or(0),
+                syscalls: 0,
+            })
+        };
+    }
+
+    pub fn read() -> usize {
+        let mut clock = PSEUDO_CLOCK.exclusive_access();
+        clock.now += DETERMINISTIC_TIME_STEP;
+        clock.now
+    }
+
+    /// 每次系统调用后调用，返回是否应当产生一次虚拟时钟中断
+    pub fn syscall_tick() -> bool {
+        let mut clock = PSEUDO_CLOCK.exclusive_access();
+        clock.syscalls += 1;
+        clock.syscalls % DETERMINISTIC_TICK_SYSCALLS == 0
+    }
 }
@@ -3,6 +3,7 @@
 use riscv::register::sstatus::{self, Sstatus, SPP};
 
 #[repr(C)]
+#[derive(Clone, Copy)]
 /// trap context structure containing sstatus, sepc and registers
 pub struct TrapContext {
     /// General-Purpose Register x0-31
@@ -17,6 +18,11 @@ pub struct TrapContext {
     pub kernel_sp: usize,
     /// Virtual address of trap handler entry point in kernel
     pub trap_handler: usize,
+    /// 浮点寄存器 f0-f31：用户态写过浮点寄存器之后陷入内核时才保存，
+    /// 处理器上的浮点寄存器属于其他任务时，返回用户态前从这里恢复
+    pub f: [usize; 32],
+    /// 浮点控制与状态寄存器
+    pub fcsr: usize,
 }
 
 impl TrapContext {
@@ -40,6 +46,8 @@ impl TrapContext {
             kernel_satp,
             kernel_sp,
             trap_handler,
+            f: [0; 32],
+            fcsr: 0,
         };
         cx.set_sp(sp);
         cx
@@ -3,30 +3,43 @@
 // 所有陷阱都经过`__alltraps`，它在`trap.S`中定义。
 //汇编语言代码只做了足够的工作来恢复内核空间上下文，确保Rust代码安全运行，并将控制权转移到[`trap_handler（）`]。
 // 然后，它根据异常的具体情况调用不同的功能。例如，计时器中断触发任务抢占，系统调用转到[`syscall（）`]。
+// 内核态发生的 trap 则经过`__kernel_trap`进入[`trap_from_kernel（）`]，处理完中断后回到内核被打断的位置。
 
 mod context;
 
-use crate::config::{TRAMPOLINE, TRAP_CONTEXT};
-use crate::syscall::syscall;
+use crate::config::TRAMPOLINE;
+use crate::drivers::handle_external_interrupt;
+use crate::fs::writeback_tick;
+use crate::gdbstub;
+use crate::mm::{copy_from_user, MapPermission, VirtAddr};
+use crate::random::add_trap_jitter;
+use crate::syscall::{syscall, SYSCALL_ARGS};
 use crate::task::{
-    current_trap_cx, current_user_token, exit_current_and_run_next, suspend_current_and_run_next,
+    account_trap_enter, account_trap_return, aging_tick, consume_time_slice, current_add_signal, current_task,
+    current_trap_cx, current_trap_cx_user_va, current_user_satp, current_user_token, fault_in_user_page,
+    handle_signals, kernel_stack_guard_slot, kernel_stack_position, preempt_current_and_run_next,
+    ptrace_stop, scheduler_tick, take_fp_regs, SignalFlags,
 };
-use crate::timer::set_next_trigger;
+use crate::timer::{check_sleepers, set_next_trigger};
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
-    sie, stval, stvec,
+    stval, stvec,
 };
 
 core::arch::global_asm!(include_str!("trap.S"));
+core::arch::global_asm!(include_str!("kernel_trap.S"));
 
 pub fn init() {
     set_kernel_trap_entry();
 }
 
 fn set_kernel_trap_entry() {
+    extern "C" {
+        fn __kernel_trap();
+    }
     unsafe {
-        stvec::write(trap_from_kernel as usize, TrapMode::Direct);
+        stvec::write(__kernel_trap as usize, TrapMode::Direct);
     }
 }
 
@@ -37,14 +50,18 @@ fn set_user_trap_entry() {
 }
 
 pub fn enable_timer_interrupt() {
+    // 确定性模式下不使用真实的时钟中断，见 timer::deterministic
+    #[cfg(not(feature = "deterministic"))]
     unsafe {
-        sie::set_stimer();
+        riscv::register::sie::set_stimer();
     }
 }
 
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
+    account_trap_enter();
+    add_trap_jitter();
     let scause = scause::read();
     let stval = stval::read();
     match scause.cause() {
@@ -53,51 +70,140 @@ pub fn trap_handler() -> ! {
             let mut cx = current_trap_cx();
             cx.sepc += 4;
             // get system call return value
-            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]);
+            // a0-a5 中依次是系统调用的参数
+            let mut args = [0; SYSCALL_ARGS];
+            args.copy_from_slice(&cx.x[10..10 + SYSCALL_ARGS]);
+            let result = syscall(cx.x[17], args);
             // cx is changed during sys_exec, so we have to call it again
             cx = current_trap_cx();
             cx.x[10] = result as usize;
+            #[cfg(feature = "deterministic")]
+            if crate::timer::deterministic::syscall_tick() {
+                check_sleepers();
+                scheduler_tick();
+                aging_tick();
+                writeback_tick();
+                if consume_time_slice() {
+                    preempt_current_and_run_next();
+                }
+            }
         }
+        //惰性分配的页面第一次被访问：分配页帧后回到出错的指令重新执行
+        Trap::Exception(Exception::StorePageFault)
+            if fault_in_lazy_page(stval, MapPermission::W) => {}
+        Trap::Exception(Exception::LoadPageFault)
+            if fault_in_lazy_page(stval, MapPermission::R) => {}
+        Trap::Exception(Exception::InstructionPageFault)
+            if fault_in_lazy_page(stval, MapPermission::X) => {}
         Trap::Exception(Exception::StoreFault)
         | Trap::Exception(Exception::StorePageFault)
         | Trap::Exception(Exception::InstructionFault)
         | Trap::Exception(Exception::InstructionPageFault)
         | Trap::Exception(Exception::LoadFault)
         | Trap::Exception(Exception::LoadPageFault) => {
-            println!(
-                "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, core dumped.",
-                scause.cause(),
-                stval,
-                current_trap_cx().sepc,
-            );
-            // page fault exit code
-            exit_current_and_run_next(-2);
+            user_fault(scause.cause(), stval, SignalFlags::SIGSEGV);
+        }
+        Trap::Exception(Exception::StoreMisaligned)
+        | Trap::Exception(Exception::LoadMisaligned)
+        | Trap::Exception(Exception::InstructionMisaligned) => {
+            user_fault(scause.cause(), stval, SignalFlags::SIGBUS);
+        }
+        //被跟踪的任务停下来交给跟踪者处理，否则按出错终止
+        Trap::Exception(Exception::Breakpoint) => {
+            if !ptrace_stop(SignalFlags::SIGTRAP) {
+                user_fault(scause.cause(), stval, SignalFlags::SIGTRAP);
+            }
         }
         Trap::Exception(Exception::IllegalInstruction) => {
-            println!("[kernel] IllegalInstruction in application, core dumped.");
-            // illegal instruction exit code
-            exit_current_and_run_next(-3);
+            user_fault(scause.cause(), stval, SignalFlags::SIGILL);
+        }
+        //其他无法处理的用户态异常同样按非法指令处理
+        Trap::Exception(_) => {
+            user_fault(scause.cause(), stval, SignalFlags::SIGILL);
+        }
+        //串口等设备的中断交给注册的处理者；块设备的请求是同步完成的，这里只会处理到等待期间没有被取走的中断
+        Trap::Interrupt(Interrupt::SupervisorExternal) => {
+            handle_external_interrupt();
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
-            set_next_trigger();
-            suspend_current_and_run_next();
+            timer_tick();
         }
-        _ => {
-            panic!(
-                "Unsupported trap {:?}, stval = {:#x}!",
-                scause.cause(),
-                stval
+        //内核没有打开其他中断，忽略意外到来的中断，继续运行当前任务
+        cause => {
+            warn!(
+                "Unexpected trap {:?} from user mode, stval = {:#x}",
+                cause, stval
             );
         }
     }
+    handle_signals();
     trap_return();
 }
 
+//时钟中断：推进时钟与调度器的计数，时间片用完才切换任务
+fn timer_tick() {
+    set_next_trigger();
+    check_sleepers();
+    gdbstub::poll();
+    scheduler_tick();
+    aging_tick();
+    writeback_tick();
+    if consume_time_slice() {
+        preempt_current_and_run_next();
+    }
+}
+
+/// 用户程序执行出错：报告出错的地址（非法指令则是指令的编码）与指令位置后向当前任务发送信号 signal，
+/// 交给它的处理函数，没有注册处理函数时终止进程，退出码为 -signum，内核继续调度其他任务
+fn user_fault(cause: Trap, stval: usize, signal: SignalFlags) {
+    let task = current_task().unwrap();
+    let sepc = current_trap_cx().sepc;
+    let (what, value) = match cause {
+        Trap::Exception(Exception::IllegalInstruction) => {
+            ("instruction", illegal_instruction(stval, sepc))
+        }
+        _ => ("bad addr", stval),
+    };
+    println!(
+        "[kernel] {:?} in application {} (pid {}, tid {}), {} = {:#x}, sepc = {:#x}, signal {}.",
+        cause,
+        task.name(),
+        task.getpid(),
+        task.tid,
+        what,
+        value,
+        sepc,
+        signal.first_signum().unwrap(),
+    );
+    current_add_signal(signal);
+}
+
+//非法指令的编码：处理器通常把它写在 stval 中，没有写（stval 为 0）时从用户地址空间中 sepc 处读出，
+//低两位不全为 1 的是 2 字节的压缩指令。读不出来时返回 0
+fn illegal_instruction(stval: usize, sepc: usize) -> usize {
+    if stval != 0 {
+        return stval;
+    }
+    let token = current_user_token();
+    match copy_from_user(token, sepc as *const u16) {
+        Ok(low) if low & 0b11 != 0b11 => low as usize,
+        Ok(_) => copy_from_user(token, sepc as *const u32).map_or(0, |insn| insn as usize),
+        Err(_) => 0,
+    }
+}
+
+//用户访问地址 va 时发生缺页，若它是尚未分配的惰性页面且逻辑段允许 access 权限的访问则为其分配页帧
+fn fault_in_lazy_page(va: usize, access: MapPermission) -> bool {
+    fault_in_user_page(current_user_token(), VirtAddr::from(va).floor(), access)
+}
+
 #[no_mangle]
 pub fn trap_return() -> ! {
     set_user_trap_entry();
-    let trap_cx_ptr = TRAP_CONTEXT;
-    let user_satp = current_user_token();
+    account_trap_return();
+    let trap_cx_ptr = current_trap_cx_user_va();
+    let user_satp = current_user_satp();
+    let restore_fp = take_fp_regs();
     extern "C" {
         fn __alltraps();
         fn __restore();
@@ -110,14 +216,108 @@ pub fn trap_return() -> ! {
             restore_va = in(reg) restore_va,
             in("a0") trap_cx_ptr,
             in("a1") user_satp,
+            in("a2") restore_fp as usize,
             options(noreturn)
         );
     }
 }
 
+/// 内核态 trap 发生时的现场，由 __kernel_trap 保存在栈上，返回时按它恢复
+#[repr(C)]
+pub struct KernelTrapFrame {
+    /// 通用寄存器 x0-x31
+    pub x: [usize; 32],
+    pub sstatus: usize,
+    pub sepc: usize,
+}
+
+/// 内核态发生的 trap，由 __kernel_trap 调用。
+/// 中断在出错时的内核栈上处理，之后回到被打断的位置继续执行；
+/// 异常在专用的栈上处理，能恢复的异常处理后返回，其他异常报告出错的现场后 panic
 #[no_mangle]
-pub fn trap_from_kernel() -> ! {
-    panic!("a trap {:?} from kernel!", scause::read().cause());
+pub extern "C" fn trap_from_kernel(frame: &mut KernelTrapFrame) {
+    let scause = scause::read();
+    let stval = stval::read();
+    match scause.cause() {
+        //内核态只在空闲时与抢占点打开中断，此时没有持有任何资源。
+        //抢占点上的时钟中断与用户态一样处理，时间片用完时在内核中切换任务；空闲时没有当前任务，只推进时钟
+        Trap::Interrupt(Interrupt::SupervisorTimer) => {
+            if current_task().is_some() {
+                timer_tick();
+            } else {
+                set_next_trigger();
+                check_sleepers();
+                gdbstub::poll();
+            }
+        }
+        Trap::Interrupt(Interrupt::SupervisorExternal) => {
+            handle_external_interrupt();
+        }
+        //内核中的断点：交给内核调试器，没有调试器时报告位置后跳过断点指令继续执行
+        Trap::Exception(Exception::Breakpoint) => {
+            if !gdbstub::handle_breakpoint(frame) {
+                println!("[kernel] Breakpoint in kernel, sepc = {:#x}.", frame.sepc);
+                frame.sepc += instruction_len(frame.sepc);
+            }
+        }
+        //访问内核栈下方的保护页说明内核栈溢出，报告出错的任务与栈的范围
+        Trap::Exception(Exception::StorePageFault) | Trap::Exception(Exception::LoadPageFault)
+            if kernel_stack_guard_slot(stval).is_some() =>
+        {
+            let slot = kernel_stack_guard_slot(stval).unwrap();
+            let (bottom, top) = kernel_stack_position(slot);
+            match current_task() {
+                Some(task) if task.kernel_stack.slot() == slot => {
+                    println!(
+                        "[kernel] Kernel stack overflow in application {} (pid {}, tid {}).",
+                        task.name(),
+                        task.getpid(),
+                        task.tid
+                    );
+                }
+                _ => {
+                    println!(
+                        "[kernel] Kernel stack overflow in kernel stack slot {}.",
+                        slot
+                    );
+                }
+            }
+            panic!(
+                "kernel stack [{:#x}, {:#x}) overflowed, sp = {:#x}, bad addr = {:#x}, sepc = {:#x}",
+                bottom, top, frame.x[2], stval, frame.sepc
+            );
+        }
+        cause => {
+            dump_kernel_frame(frame);
+            panic!(
+                "a trap {:?} from kernel, sp = {:#x}, stval = {:#x}, sepc = {:#x}!",
+                cause, frame.x[2], stval, frame.sepc
+            );
+        }
+    }
+}
+
+//位于 addr 处的指令长度：低两位不全为 1 的是 2 字节的压缩指令
+fn instruction_len(addr: usize) -> usize {
+    if unsafe { (addr as *const u16).read_volatile() } & 0b11 == 0b11 {
+        4
+    } else {
+        2
+    }
+}
+
+//打印出错时的通用寄存器
+fn dump_kernel_frame(frame: &KernelTrapFrame) {
+    for (i, regs) in frame.x.chunks(4).enumerate() {
+        println!(
+            "[kernel] x{:<2} {:#018x} {:#018x} {:#018x} {:#018x}",
+            i * 4,
+            regs[0],
+            regs[1],
+            regs[2],
+            regs[3]
+        );
+    }
 }
 
 pub use context::TrapContext;
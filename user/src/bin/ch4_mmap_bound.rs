@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, mmap_with_flags, mprotect, munmap, MAP_PRIVATE, MAP_SHARED};
+
+/// mmap 上界测试：指定的地址区间越过用户地址空间的上界时失败，
+/// 而不是与页号低位相同的 TRAMPOLINE 等内核映射冲突；munmap 与 mprotect 的区间溢出时返回 -EINVAL。
+/// 正确输出：
+/// Test mmap bound OK!
+
+/// 用户地址空间的上界，与内核的 USER_SPACE_END 相同
+const USER_SPACE_END: usize = 0x40_0000_0000;
+/// 页号的低 27 位与 TRAMPOLINE 相同的页面
+const TRAMPOLINE_ALIAS: usize = 0x7F_FFFF_F000;
+const EINVAL: isize = -22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let len: usize = 4096;
+    let prot: usize = 3;
+    assert!(mmap_with_flags(TRAMPOLINE_ALIAS, len, prot, MAP_SHARED) < 0);
+    assert!(mmap_with_flags(TRAMPOLINE_ALIAS, len, prot, MAP_PRIVATE) < 0);
+    // 跨过上界的区间，以及起止地址相加溢出的区间
+    assert!(mmap(USER_SPACE_END - len, 2 * len, prot) < 0);
+    assert!(mmap(usize::MAX - len + 1, 2 * len, prot) < 0);
+    // 紧贴上界的区间仍然可以映射
+    assert_eq!(mmap(USER_SPACE_END - len, len, prot), 0);
+    // 起始地址加长度溢出的 munmap 与 mprotect
+    let start = USER_SPACE_END - len;
+    assert_eq!(munmap(start, usize::MAX - len), EINVAL);
+    assert_eq!(mprotect(start, usize::MAX - len, 1), EINVAL);
+    assert_eq!(mprotect(start, len, 1), 0);
+    assert_eq!(munmap(start, len), 0);
+    println!("Test mmap bound OK!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, sched_getaffinity, sched_setaffinity, wait};
+
+/// CPU 亲和性测试：子进程继承父进程的掩码，非法掩码被拒绝。
+/// 正确输出：
+/// Test affinity OK!
+
+const ESRCH: isize = -3;
+const EINVAL: isize = -22;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut mask = 0usize;
+    assert_eq!(sched_getaffinity(0, &mut mask), 0);
+    // 至少允许在 0 号核上运行
+    assert!(mask & 1 != 0);
+    // 掩码中不包含任何存在的核
+    assert_eq!(sched_setaffinity(0, 0), EINVAL);
+    // 只支持设置自己
+    assert_eq!(sched_setaffinity(getpid() as usize + 1, 1), ESRCH);
+    assert_eq!(sched_setaffinity(getpid() as usize, 1), 0);
+    if fork() == 0 {
+        let mut child_mask = 0usize;
+        assert_eq!(sched_getaffinity(0, &mut child_mask), 0);
+        assert_eq!(child_mask, 1);
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert!(wait(&mut exit_code) > 0);
+    assert_eq!(exit_code, 0);
+    println!("Test affinity OK!");
+    0
+}
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, exit, fork, spawn_args, waitpid};
+
+/// 命令行参数测试：通过 exec 与 spawn 启动自身并传入参数，子进程检查收到的 argc/argv。
+/// 正确输出：
+/// Test argv OK!
+
+const ARGS_OK: i32 = 42;
+
+fn check_args(argv: &[&str]) -> i32 {
+    let expected = ["ch5b_argv", "child", "", "hello, world", "0123456789abcdef"];
+    if argv.len() == expected.len() && argv.iter().zip(expected.iter()).all(|(a, b)| a == b) {
+        ARGS_OK
+    } else {
+        println!("unexpected argv: {:?}", argv);
+        -1
+    }
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc > 1 && argv[1] == "child" {
+        return check_args(argv);
+    }
+    let args = [
+        "ch5b_argv\0".as_ptr(),
+        "child\0".as_ptr(),
+        "\0".as_ptr(),
+        "hello, world\0".as_ptr(),
+        "0123456789abcdef\0".as_ptr(),
+        core::ptr::null::<u8>(),
+    ];
+    let mut exit_code: i32 = 0;
+    let pid = fork();
+    if pid == 0 {
+        exec("ch5b_argv\0", &args);
+        exit(-1);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, ARGS_OK);
+    let pid = spawn_args("ch5b_argv\0", &args);
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, ARGS_OK);
+    println!("Test argv OK!");
+    0
+}
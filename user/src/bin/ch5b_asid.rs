@@ -0,0 +1,94 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, mmap, mprotect, munmap, wait, waitpid, yield_};
+
+/// ASID 测试：许多进程在同一个虚拟地址上映射各自的页面并轮流运行，切换地址空间时不再刷新整个 TLB，
+/// 每个进程仍然只看到自己的数据；反复解除映射再重新映射同一个地址，读到的总是新分配的清零页面；
+/// 权限被改为只读或映射被解除之后，访问立即出错。
+/// 正确输出：
+/// Test asid OK!
+
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x6000_0000;
+const NUM: usize = 32;
+const ROUNDS: usize = 50;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+
+fn page() -> *mut usize {
+    START as *mut usize
+}
+
+fn run_child(f: fn()) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn touch() {
+    unsafe { page().write_volatile(1) };
+}
+
+//刚写过的页面改为只读之后再写
+fn write_after_mprotect() {
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_R | PROT_W), 0);
+    touch();
+    assert_eq!(mprotect(START, PAGE_SIZE, PROT_R), 0);
+    assert_eq!(unsafe { page().read_volatile() }, 1);
+    touch();
+}
+
+//刚写过的页面解除映射之后再写
+fn write_after_munmap() {
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_R | PROT_W), 0);
+    touch();
+    assert_eq!(munmap(START, PAGE_SIZE), 0);
+    touch();
+}
+
+fn worker() -> ! {
+    let value = getpid() as usize;
+    for round in 0..ROUNDS {
+        assert_eq!(mmap(START, PAGE_SIZE, PROT_R | PROT_W), 0);
+        // 重新映射之后是一个新的清零页面
+        assert_eq!(unsafe { page().read_volatile() }, 0);
+        unsafe { page().write_volatile(value + round) };
+        yield_();
+        assert_eq!(unsafe { page().read_volatile() }, value + round);
+        assert_eq!(munmap(START, PAGE_SIZE), 0);
+        yield_();
+    }
+    exit(0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..NUM {
+        let pid = fork();
+        if pid == 0 {
+            worker();
+        }
+        assert!(pid > 0);
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..NUM {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+
+    // 页面被改为只读或解除映射之后，TLB 中不会留下旧的表项
+    assert_eq!(run_child(write_after_mprotect), -SIGSEGV);
+    assert_eq!(run_child(write_after_munmap), -SIGSEGV);
+    println!("Test asid OK!");
+    0
+}
@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    block_cache_stats, spawn, syscall, waitpid, BlockCacheStats, SYSCALL_BLOCK_CACHE_STATS,
+};
+
+/// 块缓存测试：从文件系统加载应用会查询块缓存，命中与缺失次数随之增加，各项计数只增不减；
+/// 结果不可写时返回 -EFAULT。
+/// 正确输出：
+/// Test block cache OK!
+
+const EFAULT: isize = -14;
+
+fn run(app: &str) {
+    let pid = spawn(app);
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = BlockCacheStats::default();
+    assert_eq!(block_cache_stats(&mut before), 0);
+    run("ch2b_hello_world\0");
+    let mut after = BlockCacheStats::default();
+    assert_eq!(block_cache_stats(&mut after), 0);
+    assert!(after.hits + after.misses > before.hits + before.misses);
+    assert!(after.misses >= before.misses);
+    assert!(after.evictions >= before.evictions);
+    assert!(after.writebacks >= before.writebacks);
+    // 缓存已满时每次缺失都要淘汰一个块
+    assert!(after.evictions - before.evictions <= after.misses - before.misses);
+    assert_eq!(syscall(SYSCALL_BLOCK_CACHE_STATS, [0, 0, 0]), EFAULT);
+    println!("Test block cache OK!");
+    0
+}
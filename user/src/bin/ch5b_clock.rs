@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    clock_gettime, get_time, sleep, syscall, TimeSpec, CLOCK_MONOTONIC, CLOCK_REALTIME,
+    SYSCALL_CLOCK_GETTIME,
+};
+
+/// 时钟测试：CLOCK_MONOTONIC 与 get_time 一致且单调递增；CLOCK_REALTIME 来自 RTC，
+/// 是一个合理的日期，与 CLOCK_MONOTONIC 之差保持不变；不支持的时钟返回 -EINVAL，结果不可写时返回 -EFAULT。
+/// 正确输出：
+/// Test clock OK!
+
+const EINVAL: isize = -22;
+const EFAULT: isize = -14;
+const NANO_PER_SEC: usize = 1_000_000_000;
+const NANO_PER_MILLI: usize = 1_000_000;
+/// 2020-01-01 00:00:00 UTC
+const YEAR_2020: usize = 1_577_836_800;
+
+fn now(clock_id: usize) -> usize {
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(clock_id, &mut ts), 0);
+    assert!(ts.tv_nsec < NANO_PER_SEC);
+    ts.tv_sec * NANO_PER_SEC + ts.tv_nsec
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mono = now(CLOCK_MONOTONIC);
+    let ms = get_time() as usize;
+    assert!(mono / NANO_PER_MILLI <= ms);
+    assert!(ms < mono / NANO_PER_MILLI + 100);
+
+    let real = now(CLOCK_REALTIME);
+    assert!(real / NANO_PER_SEC > YEAR_2020);
+    let offset = real - now(CLOCK_MONOTONIC);
+
+    sleep(100);
+    let mono2 = now(CLOCK_MONOTONIC);
+    assert!(mono2 - mono >= 100 * NANO_PER_MILLI);
+    // 墙上时间与单调时钟以同样的速度前进
+    let real2 = now(CLOCK_REALTIME);
+    let offset2 = real2 - now(CLOCK_MONOTONIC);
+    assert!(offset2.abs_diff(offset) < 10 * NANO_PER_MILLI);
+
+    let mut ts = TimeSpec::default();
+    assert_eq!(clock_gettime(2, &mut ts), EINVAL);
+    assert_eq!(
+        syscall(SYSCALL_CLOCK_GETTIME, [CLOCK_MONOTONIC, 0, 0]),
+        EFAULT
+    );
+    println!("Test clock OK!");
+    0
+}
@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, pipe, read, sigaction, waitpid, write, SignalAction, SIGPIPE, SIG_IGN,
+};
+
+/// 关闭文件描述符测试：管道的所有写端关闭后，读者读完剩余数据再读到文件末尾；
+/// 进程退出时自动关闭它打开的文件。所有读端关闭后写入管道的进程被 SIGPIPE 终止，
+/// 忽略 SIGPIPE 时写入返回 -EPIPE。
+/// 正确输出：
+/// Test close OK!
+
+const EBADF: isize = -9;
+const EPIPE: isize = -32;
+
+fn new_pipe() -> (usize, usize) {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    (fds[0], fds[1])
+}
+
+fn wait_child(pid: isize) -> i32 {
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(close(64), EBADF);
+
+    // 子进程写完之后不关闭写端直接退出，由内核关闭；父进程关闭自己的写端后能读到文件末尾
+    let (rfd, wfd) = new_pipe();
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(close(rfd), 0);
+        assert_eq!(write(wfd, b"eof"), 3);
+        exit(0);
+    }
+    assert_eq!(close(wfd), 0);
+    assert_eq!(close(wfd), EBADF);
+    let mut buf = [0u8; 16];
+    assert_eq!(read(rfd, &mut buf), 3);
+    assert_eq!(&buf[..3], b"eof");
+    assert_eq!(read(rfd, &mut buf), 0);
+    assert_eq!(wait_child(pid), 0);
+    assert_eq!(close(rfd), 0);
+
+    // 关闭的文件描述符被再次分配
+    let (rfd2, wfd2) = new_pipe();
+    assert_eq!((rfd2, wfd2), (rfd, wfd));
+
+    // 读端全部关闭后写入：默认被 SIGPIPE 终止
+    assert_eq!(close(rfd2), 0);
+    let pid = fork();
+    if pid == 0 {
+        write(wfd2, b"lost");
+        exit(0);
+    }
+    assert_eq!(wait_child(pid), -SIGPIPE);
+    // 忽略 SIGPIPE 时写入返回 -EPIPE
+    let action = SignalAction {
+        handler: SIG_IGN,
+        mask: 0,
+    };
+    assert_eq!(sigaction(SIGPIPE, Some(&action), None), 0);
+    assert_eq!(write(wfd2, b"lost"), EPIPE);
+    assert_eq!(close(wfd2), 0);
+    println!("Test close OK!");
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getrlimit, setrlimit, waitpid, Rlimit, RLIMIT_CORE, RLIM_INFINITY};
+
+/// core dump 测试：RLIMIT_CORE 默认为 0；设置之后访存错误会在串口上输出以 [core] 开头的
+/// 寄存器与内存转储，其中应当能找到 MARKER 的内容；进程照常以 SIGSEGV 结束。
+/// 正确输出：
+/// Test core dump OK!
+
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+
+static mut MARKER: [u8; 16] = *b"core-dump-marker";
+
+fn fault(core_pages: usize) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        if core_pages > 0 {
+            let limit = Rlimit {
+                cur: core_pages * PAGE_SIZE,
+                max: RLIM_INFINITY,
+            };
+            assert_eq!(setrlimit(RLIMIT_CORE, &limit), 0);
+        }
+        unsafe {
+            MARKER[0] = b'C';
+            core::ptr::null_mut::<u8>().write_volatile(0);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut limit = Rlimit::default();
+    assert_eq!(getrlimit(RLIMIT_CORE, &mut limit), 0);
+    assert_eq!(limit.cur, 0);
+    // 默认不输出 core dump
+    assert_eq!(fault(0), -SIGSEGV);
+    // 足够容纳整个地址空间的用户页面
+    assert_eq!(fault(64), -SIGSEGV);
+    println!("Test core dump OK!");
+    0
+}
@@ -0,0 +1,123 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, syscall, waitpid, SYSCALL_EXEC, SYSCALL_SPAWN, SYSCALL_WRITE,
+};
+
+/// 跨页字符串测试：exec 与 spawn 的路径、参数指针数组和参数字符串，以及 write 的缓冲区都跨越页面边界，
+/// 内核逐页取出它们；其中有页面未映射时返回 -EFAULT，字符串过长时返回 -ENAMETOOLONG。
+/// 正确输出：
+/// cross page write: 跨页写入
+/// Test cross page OK!
+
+const EFAULT: isize = -14;
+const ENAMETOOLONG: isize = -36;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x7200_0000;
+const PAGES: usize = 4;
+const FD_STDOUT: usize = 1;
+const PTR_SIZE: usize = core::mem::size_of::<usize>();
+/// ch5b_argv 收到这些参数时以 42 退出
+const ARGS: [&str; 5] = ["ch5b_argv", "child", "", "hello, world", "0123456789abcdef"];
+const ARGS_OK: i32 = 42;
+
+fn page(i: usize) -> usize {
+    START + i * PAGE_SIZE
+}
+
+//把 bytes 复制到 addr，返回 addr
+fn place(addr: usize, bytes: &[u8]) -> usize {
+    let dst = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, bytes.len()) };
+    dst.copy_from_slice(bytes);
+    addr
+}
+
+//把 s 加上 \0 复制到 addr，返回 addr
+fn place_str(addr: usize, s: &str) -> usize {
+    place(addr, s.as_bytes());
+    place(addr + s.len(), b"\0");
+    addr
+}
+
+fn place_ptrs(addr: usize, ptrs: &[usize]) -> usize {
+    for (i, ptr) in ptrs.iter().enumerate() {
+        place(addr + i * PTR_SIZE, &ptr.to_ne_bytes());
+    }
+    addr
+}
+
+fn wait_exit(pid: isize) -> i32 {
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn exec_raw(path: usize, args: usize) -> isize {
+    syscall(SYSCALL_EXEC, [path, args, 0])
+}
+
+fn spawn_raw(path: usize, args: usize) -> isize {
+    syscall(SYSCALL_SPAWN, [path, args, 0])
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 每个页面单独映射，之后可以只解除其中一个
+    for i in 0..PAGES {
+        assert_eq!(mmap(page(i), PAGE_SIZE, 3), 0);
+    }
+
+    // 参数字符串放在第一个页面中，其中一个跨越第二、三个页面的边界；
+    // 参数指针数组跨越第一、二个页面的边界，路径跨越第三、四个页面的边界
+    let mut ptrs = [0usize; ARGS.len() + 1];
+    let mut next = page(0);
+    for (i, arg) in ARGS.iter().enumerate() {
+        ptrs[i] = place_str(next, arg);
+        next += arg.len() + 1;
+    }
+    ptrs[3] = place_str(page(2) - 5, ARGS[3]);
+    let args = place_ptrs(page(1) - 2 * PTR_SIZE, &ptrs);
+    let path = place_str(page(3) - 4, ARGS[0]);
+
+    assert_eq!(wait_exit(spawn_raw(path, args)), ARGS_OK);
+    let pid = fork();
+    if pid == 0 {
+        exec_raw(path, args);
+        exit(-1);
+    }
+    assert_eq!(wait_exit(pid), ARGS_OK);
+
+    // 多字节字符跨越页面边界
+    let text = "cross page write: 跨页写入\n";
+    let split = text.find('页').unwrap() + 1;
+    let buf = place(page(2) - split, text.as_bytes());
+    assert_eq!(
+        syscall(SYSCALL_WRITE, [FD_STDOUT, buf, text.len()]),
+        text.len() as isize
+    );
+
+    // 一个页面中都没有 \0 的字符串
+    place(page(0), &[b'a'; PAGE_SIZE]);
+    place(page(1), &[b'a'; PAGE_SIZE]);
+    assert_eq!(spawn_raw(page(0), 0), ENAMETOOLONG);
+
+    // 第四个页面被解除映射之后，跨越边界的路径、指针数组和缓冲区都不可读
+    assert_eq!(munmap(page(3), PAGE_SIZE), 0);
+    place(page(3) - 4, b"ch5b");
+    assert_eq!(spawn_raw(page(3) - 4, 0), EFAULT);
+    assert_eq!(exec_raw(page(3) - 4, 0), EFAULT);
+    let path = place_str(page(2), ARGS[0]);
+    let args = place_ptrs(page(3) - PTR_SIZE, &[path]);
+    assert_eq!(spawn_raw(path, args), EFAULT);
+    assert_eq!(syscall(SYSCALL_WRITE, [FD_STDOUT, page(3) - 4, 8]), EFAULT);
+    for i in 0..PAGES - 1 {
+        assert_eq!(munmap(page(i), PAGE_SIZE), 0);
+    }
+    println!("Test cross page OK!");
+    0
+}
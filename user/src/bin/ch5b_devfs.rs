@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fstat, getdents, mkdir, open, read, spawn_redirect, unlink, waitpid, write, Dirents,
+    OpenFlags, SpawnRedirect, Stat, StatMode, DT_CHR,
+};
+
+/// devfs 测试：/dev/null 读到文件末尾、写入全部被丢弃，/dev/zero 读出全为 0 的字节，/dev/random 读出随机字节，
+/// /dev/console 与标准输出相同；getdents 列出这四个字符设备。子进程的标准输出可以重定向到 /dev/null。
+/// 设备文件不能创建或删除，返回 -EPERM；不存在的设备返回 -ENOENT。
+/// 正确输出：
+/// Test devfs OK!
+
+const EPERM: isize = -1;
+const ENOENT: isize = -2;
+const ENOTDIR: isize = -20;
+const EISDIR: isize = -21;
+
+fn open_fd(path: &str, flags: OpenFlags) -> usize {
+    let fd = open(path, flags);
+    assert!(fd > 2);
+    fd as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // /dev/null
+    let null = open_fd("/dev/null\0", OpenFlags::RDWR);
+    let mut buf = [0xffu8; 100];
+    assert_eq!(write(null, &buf), 100);
+    assert_eq!(read(null, &mut buf), 0);
+    let st = Stat::new();
+    assert_eq!(fstat(null, &st), 0);
+    assert_eq!(st.mode, StatMode::CHR);
+
+    // /dev/zero
+    let zero = open_fd("/dev/zero\0", OpenFlags::RDONLY);
+    assert_eq!(read(zero, &mut buf), 100);
+    assert!(buf.iter().all(|&b| b == 0));
+    assert_eq!(close(zero), 0);
+
+    // /dev/random
+    let random = open_fd("/dev/random\0", OpenFlags::RDONLY);
+    let (mut a, mut b) = ([0u8; 32], [0u8; 32]);
+    assert_eq!(read(random, &mut a), 32);
+    assert_eq!(read(random, &mut b), 32);
+    assert!(a != [0u8; 32] && a != b);
+    assert_eq!(close(random), 0);
+
+    // 不存在的设备，设备文件不能创建或删除
+    assert_eq!(open("/dev/nope\0", OpenFlags::RDONLY), ENOENT);
+    assert_eq!(open("/dev/null/x\0", OpenFlags::RDONLY), ENOTDIR);
+    assert_eq!(open("/dev\0", OpenFlags::RDWR), EISDIR);
+    assert_eq!(mkdir("/dev/x\0"), EPERM);
+    assert_eq!(unlink("/dev/null\0"), EPERM);
+
+    // 列出 /dev
+    let dir = open_fd("/dev\0", OpenFlags::RDONLY);
+    let mut dents = [0u8; 256];
+    let n = getdents(dir, &mut dents);
+    assert!(n > 0);
+    let mut count = 0;
+    for dirent in Dirents::new(&dents[..n as usize]) {
+        assert_eq!(dirent.d_type, DT_CHR);
+        assert!(["null", "zero", "console", "random"].contains(&dirent.name));
+        count += 1;
+    }
+    assert_eq!(count, 4);
+    assert_eq!(getdents(dir, &mut dents), 0);
+    assert_eq!(close(dir), 0);
+
+    // 子进程的标准输出重定向到 /dev/null
+    let args = [core::ptr::null::<u8>()];
+    let redirects = [SpawnRedirect {
+        child_fd: 1,
+        parent_fd: null,
+    }];
+    let pid = spawn_redirect("ch2b_hello_world\0", &args, &redirects);
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(close(null), 0);
+
+    // /dev/console 与标准输出相同
+    let console = open_fd("/dev/console\0", OpenFlags::WRONLY);
+    let msg = b"Test devfs OK!\n";
+    assert_eq!(write(console, msg), msg.len() as isize);
+    assert_eq!(close(console), 0);
+    0
+}
@@ -0,0 +1,118 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fstat, getdents, link, mkdir, open, read, unlink, write, Dirents, OpenFlags, Stat,
+    StatMode, DT_DIR, DT_REG,
+};
+
+/// 目录测试：mkdir 创建子目录，子目录中的文件可以通过含有 . 与 .. 的路径打开，硬链接可以跨目录；
+/// 以只读方式打开目录后用 getdents 逐条读出目录项，读完后返回 0。已经存在时 mkdir 返回 -EEXIST，
+/// 上一级不存在返回 -ENOENT，路径中间是普通文件返回 -ENOTDIR；读目录或以写方式打开目录返回 -EISDIR，
+/// 对普通文件 getdents 返回 -ENOTDIR，缓冲区放不下一条记录返回 -EINVAL。
+/// 正确输出：
+/// Test dir OK!
+
+const ENOENT: isize = -2;
+const EEXIST: isize = -17;
+const ENOTDIR: isize = -20;
+const EISDIR: isize = -21;
+const EINVAL: isize = -22;
+const DATA: &[u8] = b"file in a directory";
+
+fn open_fd(path: &str, flags: OpenFlags) -> usize {
+    let fd = open(path, flags);
+    assert!(fd > 2);
+    fd as usize
+}
+
+// 列出目录中的全部目录项，返回 (名字, 类型) 中满足 f 的个数
+fn count_entries(path: &str, f: impl Fn(&str, u8) -> bool) -> usize {
+    let fd = open_fd(path, OpenFlags::RDONLY);
+    let mut buf = [0u8; 64];
+    let mut count = 0;
+    loop {
+        let n = getdents(fd, &mut buf);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        for dirent in Dirents::new(&buf[..n as usize]) {
+            assert!(dirent.ino > 0);
+            if f(dirent.name, dirent.d_type) {
+                count += 1;
+            }
+        }
+    }
+    assert_eq!(close(fd), 0);
+    count
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let r = mkdir("ch5b_dir\0");
+    assert!(r == 0 || r == EEXIST);
+    assert_eq!(mkdir("/ch5b_dir/\0"), EEXIST);
+    let r = mkdir("ch5b_dir/sub\0");
+    assert!(r == 0 || r == EEXIST);
+    assert_eq!(mkdir("ch5b_no_dir/sub\0"), ENOENT);
+
+    // 子目录中的文件
+    let fd = open_fd(
+        "ch5b_dir/f\0",
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC,
+    );
+    assert_eq!(write(fd, DATA), DATA.len() as isize);
+    assert_eq!(close(fd), 0);
+    assert_eq!(mkdir("ch5b_dir/f/x\0"), ENOTDIR);
+    assert_eq!(open("ch5b_dir/f/x\0", OpenFlags::RDONLY), ENOTDIR);
+    let fd = open_fd("/ch5b_dir/./sub/../f\0", OpenFlags::RDONLY);
+    let mut buf = [0u8; 32];
+    assert_eq!(read(fd, &mut buf), DATA.len() as isize);
+    assert_eq!(&buf[..DATA.len()], DATA);
+    assert_eq!(getdents(fd, &mut buf), ENOTDIR);
+
+    // 跨目录的硬链接
+    assert_eq!(link("ch5b_dir/f\0", "ch5b_dir_f\0"), 0);
+    let st = Stat::new();
+    assert_eq!(fstat(fd, &st), 0);
+    assert_eq!(st.nlink, 2);
+    assert_eq!(unlink("ch5b_dir_f\0"), 0);
+    assert_eq!(fstat(fd, &st), 0);
+    assert_eq!(st.nlink, 1);
+    assert_eq!(close(fd), 0);
+
+    // 打开目录
+    assert_eq!(open("ch5b_dir\0", OpenFlags::RDWR), EISDIR);
+    assert_eq!(unlink("ch5b_dir\0"), EISDIR);
+    let fd = open_fd("ch5b_dir\0", OpenFlags::RDONLY);
+    assert_eq!(read(fd, &mut buf), EISDIR);
+    assert_eq!(fstat(fd, &st), 0);
+    assert_eq!(st.mode, StatMode::DIR);
+    assert_eq!(getdents(fd, &mut buf[..8]), EINVAL);
+    assert_eq!(close(fd), 0);
+
+    // 列出目录
+    assert_eq!(count_entries("ch5b_dir\0", |_, _| true), 2);
+    assert_eq!(
+        count_entries("ch5b_dir\0", |name, t| name == "f" && t == DT_REG),
+        1
+    );
+    assert_eq!(
+        count_entries("ch5b_dir/\0", |name, t| name == "sub" && t == DT_DIR),
+        1
+    );
+    assert_eq!(
+        count_entries("/\0", |name, t| name == "ch5b_dir" && t == DT_DIR),
+        1
+    );
+    assert_eq!(
+        count_entries("/\0", |name, t| name == "ch5b_dir" && t == DT_REG),
+        0
+    );
+    println!("Test dir OK!");
+    0
+}
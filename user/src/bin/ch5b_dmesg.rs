@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use alloc::vec;
+use user_lib::{
+    dmesg, dmesg_clear, dmesg_len, exit, fork, sys_syslog, syscall, waitpid,
+    SYSCALL_SYSLOG, SYSLOG_ACTION_READ_ALL, SYSLOG_ACTION_READ_CLEAR, SYSLOG_ACTION_SIZE_BUFFER,
+};
+
+/// 内核日志测试：进程出错时内核在串口上输出的报告同样保存在日志缓冲区中，可以通过 syslog 读出，
+/// 读出的日志不含颜色转义序列；缓冲区小于日志时只读到最近的部分；没有权限的进程不能清空日志，
+/// action 不合法时返回 -EINVAL，buf 不可写时返回 -EFAULT。
+/// 正确输出：
+/// Test dmesg OK!
+
+const EPERM: isize = -1;
+const EFAULT: isize = -14;
+const EINVAL: isize = -22;
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 子进程访问空指针，内核报告出错的进程后把它终止
+    let pid = fork();
+    if pid == 0 {
+        unsafe { core::ptr::null_mut::<usize>().write_volatile(0) };
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+
+    let size = sys_syslog(SYSLOG_ACTION_SIZE_BUFFER, &mut []);
+    assert!(size > 0);
+    let len = dmesg_len();
+    assert!(len > 0 && len <= size);
+    let mut log = vec![0u8; size as usize];
+    let read = dmesg(&mut log);
+    assert!(read >= len);
+    let log = &log[..read as usize];
+    assert!(contains(log, format!("(pid {}, tid", pid).as_bytes()));
+    assert!(!log.contains(&0x1b));
+
+    // 只读到最近的部分，以刚才读到的日志的结尾结束
+    let mut tail = [0u8; 16];
+    assert_eq!(dmesg(&mut tail), 16);
+    assert_eq!(&tail[..], &log[log.len() - 16..]);
+
+    // 测试程序由 shell 启动，不能清空日志
+    assert_eq!(dmesg_clear(), EPERM);
+    assert_eq!(sys_syslog(SYSLOG_ACTION_READ_CLEAR, &mut tail), EPERM);
+    assert!(dmesg_len() >= len);
+    assert_eq!(sys_syslog(0, &mut []), EINVAL);
+    assert_eq!(syscall(SYSCALL_SYSLOG, [SYSLOG_ACTION_READ_ALL, 0, 16]), EFAULT);
+    println!("Test dmesg OK!");
+    0
+}
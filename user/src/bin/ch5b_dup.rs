@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{dup, dup2, exit, fork, fstat, pipe, read, waitpid, write, Stat, StatMode, STDOUT};
+
+/// 文件描述符复制测试：dup 返回最小的空闲文件描述符，与原文件描述符指向同一个文件；
+/// dup2 把管道的写端复制到标准输出后，println 的输出进入管道，由父进程读出。
+/// 正确输出：
+/// Test dup OK!
+
+const EBADF: isize = -9;
+const MAX_FDS: usize = 256;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(dup(64), EBADF);
+    assert_eq!(dup2(64, 3), EBADF);
+    assert_eq!(dup2(STDOUT, MAX_FDS), EBADF);
+    assert_eq!(dup2(STDOUT, STDOUT), STDOUT as isize);
+
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (rfd, wfd) = (fds[0], fds[1]);
+    // 复制出的文件描述符与原来的指向同一个管道
+    let wfd2 = dup(wfd);
+    assert!(wfd2 > wfd as isize);
+    let wfd2 = wfd2 as usize;
+    assert_eq!(write(wfd2, b"dup"), 3);
+    let mut buf = [0u8; 64];
+    assert_eq!(read(rfd, &mut buf), 3);
+    assert_eq!(&buf[..3], b"dup");
+    // 复制到一个比当前所有文件描述符都大的位置
+    assert_eq!(dup2(wfd, 100), 100);
+    let stat = Stat::new();
+    assert_eq!(fstat(100, &stat), 0);
+    assert_eq!(stat.mode, StatMode::FIFO);
+
+    // 子进程把标准输出重定向到管道
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(dup2(wfd, STDOUT), STDOUT as isize);
+        println!("redirected");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    let n = read(rfd, &mut buf);
+    assert_eq!(&buf[..n as usize], b"redirected\n");
+    println!("Test dup OK!");
+    0
+}
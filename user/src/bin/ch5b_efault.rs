@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mmap, munmap, sys_waitpid, syscall, waitpid, TimeVal, SYSCALL_GETRLIMIT,
+    SYSCALL_GETTIMEOFDAY, SYSCALL_MEMINFO, SYSCALL_SETRLIMIT, SYSCALL_TASK_INFO, SYSCALL_TIMES,
+};
+
+/// 用户指针检查测试：系统调用的指针参数为空、未映射、指向内核地址、回绕或指向只读页面时返回 -EFAULT，
+/// 而不是让内核崩溃；跨越两个页面的结构体可以正常读写。
+/// 正确输出：
+/// Test efault OK!
+
+const ECHILD: isize = -10;
+const EFAULT: isize = -14;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x7000_0000;
+const UNMAPPED: usize = 0x7100_0000;
+const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
+const RLIMIT_STACK: usize = 3;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for &addr in [0, UNMAPPED, TRAMPOLINE, usize::MAX - 7].iter() {
+        assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [addr, 0, 0]), EFAULT);
+        assert_eq!(syscall(SYSCALL_TASK_INFO, [addr, 0, 0]), EFAULT);
+        assert_eq!(syscall(SYSCALL_TIMES, [addr, 0, 0]), EFAULT);
+        assert_eq!(syscall(SYSCALL_GETRLIMIT, [RLIMIT_STACK, addr, 0]), EFAULT);
+        assert_eq!(syscall(SYSCALL_SETRLIMIT, [RLIMIT_STACK, addr, 0]), EFAULT);
+        assert_eq!(syscall(SYSCALL_MEMINFO, [addr, 0, 0]), EFAULT);
+    }
+    // 代码段只读，不能作为输出参数
+    let text = main as usize;
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [text, 0, 0]), EFAULT);
+
+    // 跨越两个页面的结构体，第二个页面尚未访问过
+    assert_eq!(mmap(START, PAGE_SIZE, 3), 0);
+    assert_eq!(mmap(START + PAGE_SIZE, PAGE_SIZE, 3), 0);
+    let time_val = START + PAGE_SIZE - core::mem::size_of::<usize>();
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [time_val, 0, 0]), 0);
+    let time_val = unsafe { &*(time_val as *const TimeVal) };
+    assert!(time_val.sec > 0 || time_val.usec > 0);
+    // 第二个页面被解除映射之后
+    assert_eq!(munmap(START + PAGE_SIZE, PAGE_SIZE), 0);
+    assert_eq!(
+        syscall(SYSCALL_GETTIMEOFDAY, [START + PAGE_SIZE - 8, 0, 0]),
+        EFAULT
+    );
+    assert_eq!(munmap(START, PAGE_SIZE), 0);
+
+    // exit_code 不可写时子进程仍然被回收
+    let pid = fork();
+    if pid == 0 {
+        exit(3);
+    }
+    assert!(pid > 0);
+    assert_eq!(sys_waitpid(pid, UNMAPPED as *mut i32, 0), EFAULT);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), ECHILD);
+    println!("Test efault OK!");
+    0
+}
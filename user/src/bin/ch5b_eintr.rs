@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+use user_lib::{
+    close, exit, fork, get_time, kill, pipe, read, sigaction, sleep_blocking, waitpid,
+    SignalAction, SIGKILL, SIGUSR1,
+};
+
+/// 信号打断阻塞测试：
+/// 1. 阻塞在管道读上的进程收到注册了处理函数的信号时，处理函数运行，read 返回 -EINTR；
+/// 2. 睡眠中的进程收到 SIGKILL 后立即终止，不必等到睡眠结束。
+/// 正确输出：
+/// Test eintr OK!
+
+const EINTR: isize = -4;
+const LONG_SLEEP_MS: usize = 10_000;
+
+static HANDLED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn usr1_handler(_signum: i32) {
+    HANDLED.store(true, Ordering::SeqCst);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code: i32 = 0;
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let pid = fork();
+    if pid == 0 {
+        // 父进程还持有写端，读端会一直阻塞
+        close(pipe_fd[1]);
+        let action = SignalAction {
+            handler: usr1_handler as usize,
+            mask: 0,
+        };
+        sigaction(SIGUSR1, Some(&action), None);
+        let mut buf = [0u8; 1];
+        let ret = read(pipe_fd[0], &mut buf);
+        exit((ret != EINTR || !HANDLED.load(Ordering::SeqCst)) as i32);
+    }
+    close(pipe_fd[0]);
+    sleep_blocking(50);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    close(pipe_fd[1]);
+
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(LONG_SLEEP_MS);
+        exit(0);
+    }
+    sleep_blocking(50);
+    let start = get_time();
+    assert_eq!(kill(pid, SIGKILL), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -SIGKILL);
+    assert!(get_time() - start < LONG_SLEEP_MS as isize / 2);
+    println!("Test eintr OK!");
+    0
+}
@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getpid, syscall};
+
+/// 未知系统调用测试：内核对未实现的系统调用号返回 -ENOSYS 而不是崩溃，
+/// 包括超出系统调用统计范围的编号。
+/// 正确输出：
+/// Test enosys OK!
+
+const ENOSYS: isize = 38;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for &id in [0, 499, 500, 9999, usize::MAX].iter() {
+        assert_eq!(syscall(id, [0; 3]), -ENOSYS);
+    }
+    // 之后的系统调用不受影响
+    assert!(getpid() >= 0);
+    println!("Test enosys OK!");
+    0
+}
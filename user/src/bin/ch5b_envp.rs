@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{environ, exec, execve, exit, fork, getenv, spawnve, waitpid};
+
+/// 环境变量测试：execve/spawnve 设置的环境变量被 fork 与不指定环境变量的 exec 继承。
+/// 正确输出：
+/// Test envp OK!
+
+const ENV_OK: i32 = 42;
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    if argc > 1 {
+        // 被 execve/spawnve 启动的子进程
+        let ok = getenv("HOME") == Some("/root")
+            && getenv("GREETING") == Some("hello=world")
+            && getenv("EMPTY") == Some("")
+            && getenv("MISSING").is_none()
+            && environ().len() == 3;
+        if !ok {
+            println!("unexpected environ: {:?}", environ());
+            return -1;
+        }
+        if argv[1] == "fork" {
+            // fork 出的子进程复制父进程的环境变量，exec 不指定环境变量时沿用它们
+            let pid = fork();
+            if pid == 0 {
+                exec(
+                    "ch5b_envp\0",
+                    &[
+                        "ch5b_envp\0".as_ptr(),
+                        "check\0".as_ptr(),
+                        core::ptr::null(),
+                    ],
+                );
+                exit(-1);
+            }
+            let mut exit_code: i32 = 0;
+            assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+            return exit_code;
+        }
+        return ENV_OK;
+    }
+    let envp = [
+        "HOME=/root\0".as_ptr(),
+        "GREETING=hello=world\0".as_ptr(),
+        "EMPTY=\0".as_ptr(),
+        core::ptr::null::<u8>(),
+    ];
+    let mut exit_code: i32 = 0;
+    let pid = fork();
+    if pid == 0 {
+        execve(
+            "ch5b_envp\0",
+            &["ch5b_envp\0".as_ptr(), "fork\0".as_ptr(), core::ptr::null()],
+            &envp,
+        );
+        exit(-1);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, ENV_OK);
+    let pid = spawnve(
+        "ch5b_envp\0",
+        &[
+            "ch5b_envp\0".as_ptr(),
+            "spawn\0".as_ptr(),
+            core::ptr::null(),
+        ],
+        &envp,
+    );
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, ENV_OK);
+    println!("Test envp OK!");
+    0
+}
@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, execve, fork, getpid, spawn, waitpid};
+
+/// exec/spawn 出错测试：找不到应用时返回 -ENOENT，命令行参数或环境变量过长时返回 -E2BIG，
+/// exec 失败后当前进程照常运行。
+/// 正确输出：
+/// Test exec error OK!
+
+const ENOENT: isize = 2;
+const E2BIG: isize = 7;
+/// 每个参数的长度，8 个这样的参数超过了内核的 ARG_MAX
+const LONG_ARG_LEN: usize = 1000;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    assert_eq!(exec("ch5b_no_such_app\0", &[core::ptr::null()]), -ENOENT);
+    assert_eq!(spawn("ch5b_no_such_app\0"), -ENOENT);
+    let mut long_arg = [b'a'; LONG_ARG_LEN];
+    long_arg[LONG_ARG_LEN - 1] = 0;
+    let mut args = [long_arg.as_ptr(); 9];
+    args[8] = core::ptr::null();
+    assert_eq!(exec("ch5b_argv\0", &args), -E2BIG);
+    // 环境变量与命令行参数计入同一个上限
+    assert_eq!(execve("ch5b_argv\0", &[core::ptr::null()], &args), -E2BIG);
+    // exec 失败不应破坏地址空间，子进程仍能正常退出
+    assert_eq!(getpid(), pid);
+    let child = fork();
+    if child == 0 {
+        assert_eq!(exec("\0", &[core::ptr::null()]), -ENOENT);
+        return 3;
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    assert_eq!(exit_code, 3);
+    println!("Test exec error OK!");
+    0
+}
@@ -0,0 +1,76 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, exit, fork, link, mkdir, spawn, spawnve, unlink, waitpid};
+
+/// 按路径执行测试：exec 与 spawn 可以使用以 / 开头的绝对路径以及子目录中的相对路径；
+/// 不含 / 的程序名在环境变量 PATH 列出的目录中依次查找，跳过不存在的目录，没有 PATH 时只在根目录中查找。
+/// 路径指向目录或设备时返回 -EACCES，找不到时返回 -ENOENT，路径中间是普通文件时返回 -ENOTDIR。
+/// 正确输出：
+/// Test exec path OK!
+
+const ENOENT: isize = -2;
+const EACCES: isize = -13;
+const EEXIST: isize = -17;
+const ENOTDIR: isize = -20;
+const CHILD_OK: i32 = 7;
+
+// 启动子进程并等待它退出，返回退出码
+fn wait_child(pid: isize) -> i32 {
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main(argc: usize) -> i32 {
+    if argc > 1 {
+        return CHILD_OK;
+    }
+    let args = [
+        "ch5b_exec_path\0".as_ptr(),
+        "child\0".as_ptr(),
+        core::ptr::null(),
+    ];
+    // 绝对路径
+    let pid = spawnve("/ch5b_exec_path\0", &args, &[core::ptr::null()]);
+    assert_eq!(wait_child(pid), CHILD_OK);
+
+    // 子目录中的程序
+    let r = mkdir("ch5b_path_bin\0");
+    assert!(r == 0 || r == EEXIST);
+    let r = link("ch5b_exec_path\0", "ch5b_path_bin/prog\0");
+    assert!(r == 0 || r == EEXIST);
+    let pid = spawnve(
+        "ch5b_path_bin/../ch5b_path_bin/prog\0",
+        &args,
+        &[core::ptr::null()],
+    );
+    assert_eq!(wait_child(pid), CHILD_OK);
+    let envp = [
+        "PATH=/ch5b_no_dir:/ch5b_exec_path:/ch5b_path_bin\0".as_ptr(),
+        core::ptr::null(),
+    ];
+    let pid = spawnve("prog\0", &args, &envp);
+    assert_eq!(wait_child(pid), CHILD_OK);
+    assert_eq!(spawnve("prog\0", &args, &[core::ptr::null()]), ENOENT);
+    let pid = fork();
+    if pid == 0 {
+        exec("/ch5b_path_bin/prog\0", &args);
+        exit(-1);
+    }
+    assert_eq!(wait_child(pid), CHILD_OK);
+
+    // 不能执行的文件
+    assert_eq!(spawn("/ch5b_path_bin\0"), EACCES);
+    assert_eq!(spawn("/dev/null\0"), EACCES);
+    assert_eq!(spawn("/ch5b_path_bin/none\0"), ENOENT);
+    assert_eq!(spawn("/ch5b_exec_path/prog\0"), ENOTDIR);
+    assert_eq!(unlink("ch5b_path_bin/prog\0"), 0);
+    println!("Test exec path OK!");
+    0
+}
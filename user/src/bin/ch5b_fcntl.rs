@@ -0,0 +1,129 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fcntl, lseek, mkfifo, open, pipe, read, unlink, write, OpenFlags, F_DUPFD, F_GETFL,
+    F_SETFL, SEEK_SET,
+};
+
+/// 文件状态标志测试：以 APPEND 打开的文件每次写入都追加到末尾，即使之前移动过读写位置；
+/// fcntl 的 F_GETFL 读出访问方式与状态标志，F_SETFL 为管道设置 NONBLOCK 之后，空管道读取与满管道写入返回 -EAGAIN；
+/// F_DUPFD 复制到不小于 arg 的文件描述符并带上状态标志。以 NONBLOCK 只写方式打开没有读者的命名管道返回 -ENXIO。
+/// 正确输出：
+/// Test fcntl OK!
+
+const ENXIO: isize = -6;
+const EBADF: isize = -9;
+const EAGAIN: isize = -11;
+const EINVAL: isize = -22;
+const FILE: &str = "ch5b_fcntl\0";
+const FIFO: &str = "ch5b_fcntl_fifo\0";
+
+fn open_fd(path: &str, flags: OpenFlags) -> usize {
+    let fd = open(path, flags);
+    assert!(fd > 2);
+    fd as usize
+}
+
+fn getfl(fd: usize) -> OpenFlags {
+    let flags = fcntl(fd, F_GETFL, 0);
+    assert!(flags >= 0);
+    OpenFlags::from_bits(flags as u32).unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // APPEND
+    let fd = open_fd(
+        FILE,
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC,
+    );
+    assert_eq!(write(fd, b"abc"), 3);
+    assert_eq!(close(fd), 0);
+    let fd = open_fd(FILE, OpenFlags::WRONLY | OpenFlags::APPEND);
+    assert_eq!(getfl(fd), OpenFlags::WRONLY | OpenFlags::APPEND);
+    assert_eq!(write(fd, b"de"), 2);
+    assert_eq!(lseek(fd, 0, SEEK_SET), 0);
+    assert_eq!(write(fd, b"f"), 1);
+    assert_eq!(close(fd), 0);
+    let fd = open_fd(FILE, OpenFlags::RDONLY);
+    let mut buf = [0u8; 64];
+    assert_eq!(read(fd, &mut buf), 6);
+    assert_eq!(&buf[..6], b"abcdef");
+    assert_eq!(close(fd), 0);
+    assert_eq!(unlink(FILE), 0);
+
+    // 非阻塞的管道
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (rfd, wfd) = (fds[0], fds[1]);
+    assert_eq!(getfl(rfd), OpenFlags::RDONLY);
+    assert_eq!(fcntl(rfd, F_SETFL, OpenFlags::NONBLOCK.bits() as usize), 0);
+    assert_eq!(getfl(rfd), OpenFlags::NONBLOCK);
+    assert_eq!(read(rfd, &mut buf), EAGAIN);
+    assert_eq!(write(wfd, b"x"), 1);
+    assert_eq!(read(rfd, &mut buf), 1);
+    // 写满管道
+    assert_eq!(
+        fcntl(
+            wfd,
+            F_SETFL,
+            (OpenFlags::NONBLOCK | OpenFlags::CREATE).bits() as usize
+        ),
+        0
+    );
+    assert_eq!(getfl(wfd), OpenFlags::WRONLY | OpenFlags::NONBLOCK);
+    let mut total = 0;
+    loop {
+        let n = write(wfd, &buf);
+        if n == EAGAIN {
+            break;
+        }
+        assert!(n > 0);
+        total += n as usize;
+    }
+    assert!(total > 0);
+    // 复制的文件描述符带有相同的状态标志
+    let new_fd = fcntl(wfd, F_DUPFD, 10);
+    assert!(new_fd >= 10);
+    assert_eq!(
+        getfl(new_fd as usize),
+        OpenFlags::WRONLY | OpenFlags::NONBLOCK
+    );
+    assert_eq!(write(new_fd as usize, b"y"), EAGAIN);
+    assert_eq!(close(new_fd as usize), 0);
+    assert_eq!(close(wfd), 0);
+    // 写端关闭后读完剩余数据，读到文件末尾
+    let mut drained = 0;
+    loop {
+        let n = read(rfd, &mut buf);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        drained += n as usize;
+    }
+    assert_eq!(drained, total);
+    assert_eq!(close(rfd), 0);
+    assert_eq!(fcntl(rfd, F_GETFL, 0), EBADF);
+    assert_eq!(fcntl(0, 100, 0), EINVAL);
+
+    // 非阻塞地打开命名管道
+    unlink(FIFO);
+    assert_eq!(mkfifo(FIFO), 0);
+    assert_eq!(open(FIFO, OpenFlags::WRONLY | OpenFlags::NONBLOCK), ENXIO);
+    let rfd = open_fd(FIFO, OpenFlags::RDONLY | OpenFlags::NONBLOCK);
+    let wfd = open_fd(FIFO, OpenFlags::WRONLY | OpenFlags::NONBLOCK);
+    assert_eq!(read(rfd, &mut buf), EAGAIN);
+    assert_eq!(write(wfd, b"z"), 1);
+    assert_eq!(read(rfd, &mut buf), 1);
+    assert_eq!(close(wfd), 0);
+    assert_eq!(read(rfd, &mut buf), 0);
+    assert_eq!(close(rfd), 0);
+    assert_eq!(unlink(FIFO), 0);
+    println!("Test fcntl OK!");
+    0
+}
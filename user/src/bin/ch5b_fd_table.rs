@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, flush, fork, read, syscall, waitpid, write, STDIN, STDOUT, SYSCALL_WRITE};
+
+/// 文件描述符表测试：进程启动时 0、1、2 分别为标准输入、标准输出与标准错误输出；
+/// 读写未打开的文件描述符或以不允许的方向访问时返回 -EBADF，fork 出的子进程继承父进程的文件描述符表。
+/// 正确输出：
+/// Test fd table OK!
+
+const STDERR: usize = 2;
+const EBADF: isize = -9;
+const EFAULT: isize = -14;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut byte = [0u8; 1];
+    // 标准输出与标准错误输出只能写，标准输入只能读
+    assert_eq!(write(STDIN, b"x"), EBADF);
+    assert_eq!(read(STDOUT, &mut byte), EBADF);
+    assert_eq!(read(STDERR, &mut byte), EBADF);
+    // 未打开的文件描述符
+    for &fd in [3, 64, usize::MAX].iter() {
+        assert_eq!(write(fd, b"x"), EBADF);
+        assert_eq!(read(fd, &mut byte), EBADF);
+    }
+    // 检查文件描述符先于检查缓冲区
+    assert_eq!(syscall(SYSCALL_WRITE, [3, 0, 1]), EBADF);
+    assert_eq!(syscall(SYSCALL_WRITE, [STDOUT, 0, 1]), EFAULT);
+
+    flush();
+    assert_eq!(write(STDERR, b""), 0);
+    let pid = fork();
+    if pid == 0 {
+        let msg = b"child writes through inherited fd\n";
+        assert_eq!(write(STDOUT, msg), msg.len() as isize);
+        assert_eq!(write(STDERR, msg), msg.len() as isize);
+        assert_eq!(write(STDIN, msg), EBADF);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test fd table OK!");
+    0
+}
@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, fstat, mkfifo, open, read, sleep, unlink, waitpid, write, OpenFlags, Stat,
+    StatMode,
+};
+
+/// 命名管道测试：mkfifo 创建命名管道后，两个进程不继承文件描述符，而是各自按路径打开它的读端与写端来通信。
+/// 先打开读端的进程阻塞到另一个进程打开写端；写端关闭后读到文件末尾。已经存在时 mkfifo 返回 -EEXIST，
+/// 以读写方式打开返回 -EINVAL；删除之后不再能按路径打开。
+/// 正确输出：
+/// Test fifo OK!
+
+const ENOENT: isize = -2;
+const EEXIST: isize = -17;
+const EINVAL: isize = -22;
+const FIFO: &str = "ch5b_fifo\0";
+const DATA: &[u8] = b"through a named pipe";
+
+#[no_mangle]
+pub fn main() -> i32 {
+    unlink(FIFO);
+    assert_eq!(mkfifo(FIFO), 0);
+    assert_eq!(mkfifo(FIFO), EEXIST);
+    assert_eq!(open(FIFO, OpenFlags::RDWR), EINVAL);
+
+    let pid = fork();
+    if pid == 0 {
+        // 让父进程先打开读端并阻塞
+        sleep(50);
+        let fd = open(FIFO, OpenFlags::WRONLY);
+        assert!(fd > 2);
+        assert_eq!(write(fd as usize, DATA), DATA.len() as isize);
+        close(fd as usize);
+        exit(0);
+    }
+    assert!(pid > 0);
+    let fd = open(FIFO, OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let fd = fd as usize;
+    let st = Stat::new();
+    assert_eq!(fstat(fd, &st), 0);
+    assert_eq!(st.mode, StatMode::FIFO);
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    loop {
+        let n = read(fd, &mut buf[len..]);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    assert_eq!(&buf[..len], DATA);
+    assert_eq!(close(fd), 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    assert_eq!(unlink(FIFO), 0);
+    assert_eq!(open(FIFO, OpenFlags::RDONLY), ENOENT);
+    println!("Test fifo OK!");
+    0
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, lseek, open, read, write, OpenFlags, SEEK_CUR, SEEK_END, SEEK_SET};
+
+/// 普通文件读写测试：CREATE 创建文件，写入跨越多个块的数据后重新打开读回；lseek 可以从文件开头、
+/// 当前位置或文件末尾定位，移动到末尾之后再写入会留下全为 0 的空洞；TRUNC 把文件截断为空。
+/// 只读打开的文件不可写、只写打开的文件不可读，返回 -EBADF；文件不存在时返回 -ENOENT。
+/// 正确输出：
+/// Test file rw OK!
+
+const ENOENT: isize = -2;
+const EBADF: isize = -9;
+const EINVAL: isize = -22;
+const ENAMETOOLONG: isize = -36;
+const LEN: usize = 2000;
+
+fn byte(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+fn open_fd(path: &str, flags: OpenFlags) -> usize {
+    let fd = open(path, flags);
+    assert!(fd > 2);
+    fd as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let path = "ch5b_file_rw.tmp\0";
+    assert_eq!(open("ch5b_no_such_file\0", OpenFlags::RDONLY), ENOENT);
+    assert_eq!(
+        open("a_file_name_longer_than_27_bytes\0", OpenFlags::CREATE),
+        ENAMETOOLONG
+    );
+    assert_eq!(open(path, OpenFlags::WRONLY | OpenFlags::RDWR), EINVAL);
+
+    // 写入跨越多个块的数据
+    let mut data = [0u8; LEN];
+    for (i, b) in data.iter_mut().enumerate() {
+        *b = byte(i);
+    }
+    let fd = open_fd(
+        path,
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC,
+    );
+    assert_eq!(write(fd, &data[..700]), 700);
+    assert_eq!(write(fd, &data[700..]), (LEN - 700) as isize);
+    let mut buf = [0u8; LEN];
+    assert_eq!(read(fd, &mut buf), EBADF);
+    assert_eq!(close(fd), 0);
+
+    // 读回并定位
+    let fd = open_fd(path, OpenFlags::RDONLY);
+    assert_eq!(write(fd, b"x"), EBADF);
+    assert_eq!(read(fd, &mut buf), LEN as isize);
+    assert_eq!(buf, data);
+    assert_eq!(read(fd, &mut buf), 0);
+    assert_eq!(lseek(fd, 600, SEEK_SET), 600);
+    assert_eq!(read(fd, &mut buf[..10]), 10);
+    assert_eq!(&buf[..10], &data[600..610]);
+    assert_eq!(lseek(fd, -110, SEEK_CUR), 500);
+    assert_eq!(read(fd, &mut buf[..24]), 24);
+    assert_eq!(&buf[..24], &data[500..524]);
+    assert_eq!(lseek(fd, -8, SEEK_END), (LEN - 8) as isize);
+    assert_eq!(read(fd, &mut buf), 8);
+    assert_eq!(&buf[..8], &data[LEN - 8..]);
+    assert_eq!(lseek(fd, -1, SEEK_SET), EINVAL);
+    assert_eq!(lseek(fd, 0, 3), EINVAL);
+    assert_eq!(close(fd), 0);
+
+    // 移动到末尾之后写入，中间是全为 0 的空洞
+    let fd = open_fd(path, OpenFlags::RDWR);
+    assert_eq!(lseek(fd, 100, SEEK_END), (LEN + 100) as isize);
+    assert_eq!(write(fd, b"tail"), 4);
+    assert_eq!(lseek(fd, LEN as isize, SEEK_SET), LEN as isize);
+    let mut hole = [0xffu8; 104];
+    assert_eq!(read(fd, &mut hole), 104);
+    assert!(hole[..100].iter().all(|&b| b == 0));
+    assert_eq!(&hole[100..], b"tail");
+    assert_eq!(close(fd), 0);
+
+    // TRUNC 截断为空
+    let fd = open_fd(path, OpenFlags::RDWR | OpenFlags::TRUNC);
+    assert_eq!(read(fd, &mut buf), 0);
+    assert_eq!(lseek(fd, 0, SEEK_END), 0);
+    assert_eq!(close(fd), 0);
+    println!("Test file rw OK!");
+    0
+}
@@ -6,9 +6,11 @@ extern crate user_lib;
 
 use user_lib::{fork, getpid, wait};
 
+const ECHILD: isize = -10;
+
 #[no_mangle]
 pub fn main() -> i32 {
-    assert_eq!(wait(&mut 0i32), -1);
+    assert_eq!(wait(&mut 0i32), ECHILD);
     println!("sys_wait without child process test passed!");
     println!("parent start, pid = {}!", getpid());
     let pid = fork();
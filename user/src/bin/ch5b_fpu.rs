@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, wait, yield_};
+
+/// 浮点寄存器测试：几个子进程用各自不同的初值做同样的浮点迭代，每一步之后都让出处理器，
+/// 迭代的中间结果保存在浮点寄存器中，任务切换不能让它们被其他进程改写；
+/// 结果与不让出处理器时算出的值逐位相同。
+/// 正确输出：
+/// Test fpu OK!
+
+const PROCS: usize = 4;
+const STEPS: usize = 200;
+
+fn iterate(seed: f64, yield_each_step: bool) -> f64 {
+    let mut x = seed;
+    let mut y = 1.0 / seed;
+    for _ in 0..STEPS {
+        x = x * 1.0001 + y;
+        y = y * 0.9999 - x * 1e-6;
+        if yield_each_step {
+            yield_();
+        }
+    }
+    x + y
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for i in 0..PROCS {
+        if fork() == 0 {
+            let seed = (i + 1) as f64 * 1.5;
+            let expected = iterate(seed, false);
+            let result = iterate(seed, true);
+            exit(if result.to_bits() == expected.to_bits() {
+                0
+            } else {
+                1
+            });
+        }
+    }
+    for _ in 0..PROCS {
+        let mut exit_code = 0;
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0, "floating-point state was corrupted");
+    }
+    println!("Test fpu OK!");
+    0
+}
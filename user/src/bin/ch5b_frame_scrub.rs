@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, waitpid};
+
+/// 页帧清理测试：一个进程写满数据后解除映射或退出，它释放的页帧再分配给自己或其他进程时，
+/// 只有新写入的内容，其余部分全部为 0，之前的数据不会泄露。
+/// 正确输出：
+/// Test frame scrub OK!
+
+const PAGE_SIZE: usize = 4096;
+const WORDS: usize = PAGE_SIZE / core::mem::size_of::<usize>();
+const START: usize = 0x7400_0000;
+const PAGES: usize = 256;
+const ROUNDS: usize = 4;
+const SECRET: usize = 0x5ec2_e75e_c2e7_5ec2;
+const LEN: usize = PAGES * PAGE_SIZE;
+
+fn page(i: usize) -> &'static mut [usize] {
+    unsafe { core::slice::from_raw_parts_mut((START + i * PAGE_SIZE) as *mut usize, WORDS) }
+}
+
+//写满 SECRET 之后解除映射
+fn leave_secrets() {
+    assert_eq!(mmap(START, LEN, 3), 0);
+    for i in 0..PAGES {
+        page(i).fill(SECRET);
+    }
+    assert_eq!(munmap(START, LEN), 0);
+}
+
+//重新映射之后每个页面只写一个字，其余的字都应为 0
+fn check_clean() {
+    assert_eq!(mmap(START, LEN, 3), 0);
+    for i in 0..PAGES {
+        let words = page(i);
+        words[0] = i + 1;
+        assert!(words[1..].iter().all(|&word| word == 0));
+        assert_eq!(words[0], i + 1);
+    }
+    assert_eq!(munmap(START, LEN), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 同一个进程中
+    for _ in 0..ROUNDS {
+        leave_secrets();
+        check_clean();
+    }
+
+    // 子进程写满数据后退出，它的页帧回到分配器
+    for _ in 0..ROUNDS {
+        let pid = fork();
+        if pid == 0 {
+            assert_eq!(mmap(START, LEN, 3), 0);
+            for i in 0..PAGES {
+                page(i).fill(SECRET);
+            }
+            exit(0);
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, 0);
+        check_clean();
+    }
+    println!("Test frame scrub OK!");
+    0
+}
@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, open, write, OpenFlags, Stat, StatMode, STDOUT};
+
+/// 普通文件的 fstat 测试：报告普通文件的类型、大小与链接数，写入之后大小随之增长；
+/// 同一个文件的两次打开得到相同的 inode 编号，不同的文件编号不同；标准输出的大小为 0。
+/// 正确输出：
+/// Test fstat file OK!
+
+fn stat_of(fd: usize) -> Stat {
+    let stat = Stat::new();
+    assert_eq!(fstat(fd, &stat), 0);
+    stat
+}
+
+fn open_fd(path: &str, flags: OpenFlags) -> usize {
+    let fd = open(path, flags);
+    assert!(fd > 2);
+    fd as usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fda = open_fd(
+        "ch5b_fstat_a.tmp\0",
+        OpenFlags::CREATE | OpenFlags::RDWR | OpenFlags::TRUNC,
+    );
+    let stat = stat_of(fda);
+    assert_eq!(stat.mode, StatMode::FILE);
+    assert_eq!(stat.nlink, 1);
+    assert_eq!(stat.size, 0);
+    assert_ne!(stat.ino, 0);
+    let data = [7u8; 1234];
+    assert_eq!(write(fda, &data), 1234);
+    assert_eq!(stat_of(fda).size, 1234);
+
+    // 同一个文件的 inode 编号相同
+    let fda2 = open_fd("ch5b_fstat_a.tmp\0", OpenFlags::RDONLY);
+    let stat2 = stat_of(fda2);
+    assert_eq!(stat2.ino, stat.ino);
+    assert_eq!(stat2.size, 1234);
+    // 不同的文件编号不同
+    let fdb = open_fd("ch5b_fstat_b.tmp\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert_ne!(stat_of(fdb).ino, stat.ino);
+
+    let stdout = stat_of(STDOUT);
+    assert_eq!(stdout.mode, StatMode::CHR);
+    assert_eq!(stdout.size, 0);
+    for fd in [fda, fda2, fdb] {
+        assert_eq!(close(fd), 0);
+    }
+    println!("Test fstat file OK!");
+    0
+}
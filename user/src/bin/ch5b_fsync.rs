@@ -0,0 +1,66 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    block_cache_stats, close, fsync, open, pipe, read, syncfs, unlink, write, BlockCacheStats,
+    OpenFlags,
+};
+
+/// 写回测试：写入文件之后 fsync 把修改过的块写回磁盘，块缓存的写回次数增加，写回之后数据照常可读；
+/// syncfs 写回整个文件系统。对管道与标准输出 fsync 返回 -EINVAL，对未打开的文件描述符 fsync 与 syncfs 返回 -EBADF。
+/// 正确输出：
+/// Test fsync OK!
+
+const EBADF: isize = -9;
+const EINVAL: isize = -22;
+const FILE: &str = "ch5b_fsync\0";
+const DATA: &[u8] = b"data that must survive a crash";
+
+fn writebacks() -> usize {
+    let mut stats = BlockCacheStats::default();
+    assert_eq!(block_cache_stats(&mut stats), 0);
+    stats.writebacks
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open(
+        FILE,
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC,
+    );
+    assert!(fd > 2);
+    let fd = fd as usize;
+    let before = writebacks();
+    assert_eq!(write(fd, DATA), DATA.len() as isize);
+    // 写入的块或者由定期写回写到磁盘，或者由 fsync 写到磁盘
+    assert_eq!(fsync(fd), 0);
+    assert!(writebacks() > before);
+    assert_eq!(syncfs(fd), 0);
+    assert_eq!(close(fd), 0);
+
+    let fd = open(FILE, OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let fd = fd as usize;
+    let mut buf = [0u8; 64];
+    assert_eq!(read(fd, &mut buf), DATA.len() as isize);
+    assert_eq!(&buf[..DATA.len()], DATA);
+    assert_eq!(fsync(fd), 0);
+    assert_eq!(close(fd), 0);
+    assert_eq!(unlink(FILE), 0);
+
+    // 不在磁盘上的文件
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(fsync(fds[0]), EINVAL);
+    assert_eq!(fsync(1), EINVAL);
+    assert_eq!(syncfs(fds[1]), 0);
+    assert_eq!(close(fds[0]), 0);
+    assert_eq!(close(fds[1]), 0);
+    assert_eq!(fsync(fds[0]), EBADF);
+    assert_eq!(syncfs(fds[0]), EBADF);
+    println!("Test fsync OK!");
+    0
+}
@@ -0,0 +1,89 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use user_lib::{exit, futex_wait, futex_wake, set_tid_address, thread_create, waittid, yield_};
+
+/// futex 测试：值不匹配时 FUTEX_WAIT 立即返回，没有等待者时 FUTEX_WAKE 返回 0；
+/// 用 futex 实现的用户态互斥锁保护共享计数器；线程退出时 clear_child_tid 上的等待者被唤醒。
+/// 正确输出：
+/// Test futex OK!
+
+const EAGAIN: isize = -11;
+const THREADS: usize = 4;
+const PER_THREAD: usize = 500;
+
+// 0：未加锁；1：已加锁且无等待者；2：已加锁且可能有等待者
+static LOCK: AtomicU32 = AtomicU32::new(0);
+static mut COUNTER: usize = 0;
+static EXITED: AtomicU32 = AtomicU32::new(1);
+
+fn lock() {
+    if LOCK
+        .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+        .is_ok()
+    {
+        return;
+    }
+    while LOCK.swap(2, Ordering::Acquire) != 0 {
+        futex_wait(&LOCK, 2);
+    }
+}
+
+fn unlock() {
+    if LOCK.swap(0, Ordering::Release) == 2 {
+        futex_wake(&LOCK, 1);
+    }
+}
+
+fn adder(_: usize) -> ! {
+    for _ in 0..PER_THREAD {
+        lock();
+        // 读与写之间让出 CPU，锁失效时一定会丢失更新
+        let value = unsafe { COUNTER };
+        yield_();
+        unsafe {
+            COUNTER = value + 1;
+        }
+        unlock();
+    }
+    exit(0)
+}
+
+fn slow_exit(_: usize) -> ! {
+    set_tid_address(&EXITED as *const AtomicU32 as *mut u32);
+    for _ in 0..10 {
+        yield_();
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let word = AtomicU32::new(5);
+    assert_eq!(futex_wait(&word, 6), EAGAIN);
+    assert_eq!(futex_wake(&word, 1), 0);
+
+    let mut tids = [0isize; THREADS];
+    for tid in tids.iter_mut() {
+        *tid = thread_create(adder as usize, 0);
+        assert!(*tid > 0);
+    }
+    for &tid in tids.iter() {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    assert_eq!(unsafe { COUNTER }, THREADS * PER_THREAD);
+    assert_eq!(LOCK.load(Ordering::Relaxed), 0);
+
+    // 像线程库的 join 一样，在 clear_child_tid 变为 0 之前睡在它上面
+    let tid = thread_create(slow_exit as usize, 0);
+    while EXITED.load(Ordering::Acquire) != 0 {
+        futex_wait(&EXITED, 1);
+    }
+    assert_eq!(waittid(tid as usize), 0);
+    println!("Test futex OK!");
+    0
+}
@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, getppid, kill, sleep_blocking, waitpid};
+
+/// getppid 测试：两次 fork 得到的孙进程在父进程退出后被过继给 initproc（PID 为 0）。
+/// 正确输出：
+/// Test getppid OK!
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let child = fork();
+    if child == 0 {
+        assert_eq!(getppid(), pid);
+        let me = getpid();
+        let grandchild = fork();
+        if grandchild == 0 {
+            assert_eq!(getppid(), me);
+            // 等待父进程退出
+            while getppid() == me {
+                sleep_blocking(1);
+            }
+            assert_eq!(getppid(), 0);
+            println!("Test getppid OK!");
+            exit(0);
+        }
+        // 把孙进程的 PID 作为退出码交给测试进程
+        exit(grandchild as i32);
+    }
+    let mut grandchild: i32 = 0;
+    assert_eq!(waitpid(child as usize, &mut grandchild), child);
+    assert!(grandchild > 0);
+    // 孙进程由 initproc 回收，等待它退出
+    while kill(grandchild as isize, 0) == 0 {
+        sleep_blocking(1);
+    }
+    0
+}
@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{getrandom, syscall, GRND_NONBLOCK, GRND_RANDOM, SYSCALL_GETRANDOM};
+
+/// getrandom 测试：每次得到不同的随机字节，跨越多个页面的大块输出中每个字节值都出现、各位中 1 的比例接近一半；
+/// flags 不合法时返回 -EINVAL，缓冲区不可写时返回 -EFAULT。
+/// 正确输出：
+/// Test getrandom OK!
+
+const EINVAL: isize = -22;
+const EFAULT: isize = -14;
+const LARGE: usize = 16384;
+
+static mut LARGE_BUF: [u8; LARGE] = [0; LARGE];
+
+fn random_block() -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    assert_eq!(getrandom(&mut buf, GRND_RANDOM), 32);
+    buf
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let a = random_block();
+    let b = random_block();
+    assert_ne!(a, b);
+    assert_ne!(a, [0; 32]);
+
+    // 跨越多个页面的大块输出
+    let large = unsafe { &mut LARGE_BUF };
+    assert_eq!(getrandom(large, GRND_NONBLOCK), LARGE as isize);
+    let mut seen = [false; 256];
+    let mut ones = 0;
+    for &byte in large.iter() {
+        seen[byte as usize] = true;
+        ones += byte.count_ones() as usize;
+    }
+    assert!(seen.iter().all(|&seen| seen));
+    let bits = LARGE * 8;
+    assert!(ones > bits * 48 / 100 && ones < bits * 52 / 100);
+
+    let mut empty = [0u8; 0];
+    assert_eq!(getrandom(&mut empty, 0), 0);
+    let mut buf = [0u8; 8];
+    assert_eq!(getrandom(&mut buf, 4), EINVAL);
+    assert_eq!(syscall(SYSCALL_GETRANDOM, [0, 8, 0]), EFAULT);
+    println!("Test getrandom OK!");
+    0
+}
@@ -0,0 +1,105 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, meminfo, mmap, mmap_with_flags, mprotect, munmap, waitpid, MemInfo,
+    MAP_SHARED,
+};
+
+/// 大页测试：按 2MiB 对齐的 MAP_SHARED 映射会用大页建立，fork 之后子进程中仍然是大页；
+/// 只修改其中一个页面的权限会把所在的大页拆分成普通页面，数据保持不变。
+/// 同时分别按页跨步访问大页映射和同样大小的普通映射，比较两者所用的时间。
+/// 正确输出：
+/// Test huge page OK!
+
+const PAGE_SIZE: usize = 4096;
+const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+const LEN: usize = 4 * HUGE_PAGE_SIZE;
+const PAGES: usize = LEN / PAGE_SIZE;
+const HUGE_START: usize = 0x30_0000_0000;
+const SMALL_START: usize = 0x30_4000_0000;
+const ROUNDS: usize = 100;
+
+fn huge_pages() -> usize {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info.huge_pages
+}
+
+fn page(start: usize, i: usize) -> *mut usize {
+    (start + i * PAGE_SIZE) as *mut usize
+}
+
+fn fill(start: usize) {
+    for i in 0..PAGES {
+        unsafe { page(start, i).write_volatile(i) };
+    }
+}
+
+fn check(start: usize) {
+    for i in 0..PAGES {
+        assert_eq!(unsafe { page(start, i).read_volatile() }, i);
+    }
+}
+
+//每个页面访问一次，重复 ROUNDS 轮，返回所用的毫秒数
+fn bench(start: usize) -> isize {
+    let begin = get_time();
+    let mut sum = 0;
+    for _ in 0..ROUNDS {
+        for i in 0..PAGES {
+            sum += unsafe { page(start, i).read_volatile() };
+        }
+    }
+    assert_eq!(sum, ROUNDS * PAGES * (PAGES - 1) / 2);
+    get_time() - begin
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(huge_pages(), 0);
+    assert_eq!(mmap_with_flags(HUGE_START, LEN, 3, MAP_SHARED), 0);
+    // 物理内存中找不到足够的对齐的连续页帧时，部分区域会退回到普通页面
+    let huge = huge_pages();
+    assert!(huge > 0 && huge <= LEN / HUGE_PAGE_SIZE);
+    fill(HUGE_START);
+
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(huge_pages(), huge);
+        check(HUGE_START);
+        unsafe { page(HUGE_START, 0).write_volatile(PAGES) };
+        exit(0);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(unsafe { page(HUGE_START, 0).read_volatile() }, PAGES);
+    unsafe { page(HUGE_START, 0).write_volatile(0) };
+
+    // 同样大小的私有映射只使用普通页面
+    assert_eq!(mmap(SMALL_START, LEN, 3), 0);
+    fill(SMALL_START);
+    assert_eq!(huge_pages(), huge);
+    let huge_time = bench(HUGE_START);
+    let small_time = bench(SMALL_START);
+    println!(
+        "hugepage: {} pages x {} rounds, 2MiB pages {} ms, 4KiB pages {} ms",
+        PAGES, ROUNDS, huge_time, small_time
+    );
+
+    // 大页从低地址开始依次分配，第一个 2MiB 区域一定是大页：修改其中一个页面的权限会拆分它
+    assert_eq!(mprotect(HUGE_START + PAGE_SIZE, PAGE_SIZE, 1), 0);
+    assert_eq!(huge_pages(), huge - 1);
+    check(HUGE_START);
+
+    assert_eq!(munmap(HUGE_START, LEN), 0);
+    assert_eq!(munmap(SMALL_START, LEN), 0);
+    assert_eq!(huge_pages(), 0);
+    println!("Test huge page OK!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{sched_stats, sleep_blocking, SchedStats};
+
+/// 空闲统计测试：唯一的任务阻塞睡眠期间，处理器应当处于空闲状态并被计入空闲时间。
+/// 正确输出：
+/// Test idle stats OK!
+
+const SLEEP_MS: usize = 200;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = SchedStats::default();
+    let mut after = SchedStats::default();
+    assert_eq!(sched_stats(&mut before), 0);
+    sleep_blocking(SLEEP_MS);
+    assert_eq!(sched_stats(&mut after), 0);
+    let idle_us = after.idle_us - before.idle_us;
+    let uptime_us = after.uptime_us - before.uptime_us;
+    println!(
+        "idle {} us of {} us, {} switches",
+        idle_us,
+        uptime_us,
+        after.switches - before.switches
+    );
+    assert!(uptime_us >= SLEEP_MS * 1000);
+    assert!(idle_us <= uptime_us);
+    // 其他任务最多只会占用一小部分睡眠时间
+    assert!(idle_us >= SLEEP_MS * 1000 / 2);
+    assert!(after.switches > before.switches);
+    println!("Test idle stats OK!");
+    0
+}
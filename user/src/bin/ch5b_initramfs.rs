@@ -0,0 +1,101 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{
+    close, fstat, getdents, link, mkdir, mount, open, read, umount, unlink, Dirents, OpenFlags,
+    Stat, StatMode, DT_DIR,
+};
+
+/// initramfs 测试：内核镜像中嵌入的只读文件系统可以挂载到任意目录，其中有 dev、proc 与 mnt 三个挂载点；
+/// 开启 initramfs 特性时它就是根目录，其中还有全部应用。不能在其中创建或删除文件，返回 -EROFS，
+/// 已经存在的名字返回 -EEXIST，上一级目录不存在时返回 -ENOENT，以写方式打开目录返回 -EISDIR。
+/// 正确输出：
+/// Test initramfs OK!
+
+const ENOENT: isize = -2;
+const EEXIST: isize = -17;
+const EISDIR: isize = -21;
+const EROFS: isize = -30;
+
+fn path(base: &str, name: &str) -> String {
+    format!("{}/{}\0", base, name)
+}
+
+// 目录中的名字与类型
+fn list(dir: &str) -> Vec<(String, u8)> {
+    let fd = open(dir, OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let st = Stat::new();
+    assert_eq!(fstat(fd as usize, &st), 0);
+    assert_eq!(st.mode, StatMode::DIR);
+    let mut entries = Vec::new();
+    let mut dents = [0u8; 512];
+    loop {
+        let n = getdents(fd as usize, &mut dents);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        for dirent in Dirents::new(&dents[..n as usize]) {
+            entries.push((String::from(dirent.name), dirent.d_type));
+        }
+    }
+    assert_eq!(close(fd as usize), 0);
+    entries
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 根目录已经是只读的 initramfs 时不能创建挂载点，直接在根目录中测试
+    let mounted = match mkdir("/ch5b_initrd\0") {
+        0 | EEXIST => true,
+        EROFS => false,
+        r => panic!("mkdir returned {}", r),
+    };
+    let base = if mounted { "/ch5b_initrd" } else { "" };
+    if mounted {
+        assert_eq!(mount("none\0", "/ch5b_initrd\0", "initramfs\0"), 0);
+    }
+
+    let entries = list(&path(base, ""));
+    for dir in ["dev", "proc", "mnt"] {
+        assert!(entries
+            .iter()
+            .any(|(name, d_type)| name == dir && *d_type == DT_DIR));
+    }
+    let none = path(base, "ch5b_none");
+    assert_eq!(open(&none, OpenFlags::RDONLY), ENOENT);
+    assert_eq!(open(&none, OpenFlags::CREATE | OpenFlags::WRONLY), EROFS);
+    assert_eq!(mkdir(&none), EROFS);
+    assert_eq!(mkdir(&path(base, "ch5b_none/dir")), ENOENT);
+
+    if mounted {
+        // 挂载点本身没有被其他文件系统覆盖
+        assert_eq!(open(&path(base, "dev"), OpenFlags::WRONLY), EISDIR);
+        assert_eq!(mkdir(&path(base, "dev")), EEXIST);
+        assert_eq!(unlink(&path(base, "dev")), EISDIR);
+        assert_eq!(umount("/ch5b_initrd\0"), 0);
+    } else {
+        // 应用就在 initramfs 的根目录中
+        let app = path(base, "ch5b_initramfs");
+        let fd = open(&app, OpenFlags::RDONLY);
+        assert!(fd > 2);
+        let mut magic = [0u8; 4];
+        assert_eq!(read(fd as usize, &mut magic), 4);
+        assert_eq!(&magic, b"\x7fELF");
+        assert_eq!(close(fd as usize), 0);
+        assert_eq!(open(&app, OpenFlags::WRONLY), EROFS);
+        assert_eq!(open(&app, OpenFlags::RDONLY | OpenFlags::TRUNC), EROFS);
+        assert_eq!(link(&app, &none), EROFS);
+        assert_eq!(unlink(&app), EROFS);
+    }
+    println!("Test initramfs OK!");
+    0
+}
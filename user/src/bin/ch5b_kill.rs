@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getpid, kill, sleep_blocking, waitpid, SIGKILL, SIGTERM, SIGUSR1,
+};
+
+/// kill 测试：SIGKILL 与 SIGTERM 终止失控的子进程，其余信号被忽略。
+/// 被信号终止的进程退出码为 -signum。
+/// 正确输出：
+/// Test kill OK!
+
+const ESRCH: isize = -3;
+const EINVAL: isize = -22;
+
+fn spin_forever() -> ! {
+    loop {
+        get_time();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code: i32 = 0;
+    for signum in [SIGKILL, SIGTERM] {
+        let pid = fork();
+        if pid == 0 {
+            spin_forever();
+        }
+        // 只检查进程是否存在
+        assert_eq!(kill(pid, 0), 0);
+        assert_eq!(kill(pid, signum), 0);
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, -signum);
+        // 进程已被回收
+        assert_eq!(kill(pid, 0), ESRCH);
+    }
+    // 默认忽略的信号
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(kill(getpid(), SIGUSR1), 0);
+        exit(7);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    // 不合法的信号编号
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(50);
+        exit(0);
+    }
+    assert_eq!(kill(pid, 32), EINVAL);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test kill OK!");
+    0
+}
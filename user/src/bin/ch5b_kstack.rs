@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use user_lib::{exit, fork, futex_wait, set_tid_address, thread_create, waitpid, waittid, yield_};
+
+/// 内核栈槽位测试：线程的内核栈不再占用 PID，已退出但尚未回收的线程不影响 fork 得到的 PID；
+/// 反复创建与回收线程时内核栈槽位被复用。需要单独运行。
+/// 正确输出：
+/// Test kstack OK!
+
+const ROUNDS: usize = 200;
+
+static EXITED: AtomicU32 = AtomicU32::new(1);
+
+fn fork_and_reap() -> isize {
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    pid
+}
+
+fn quit(_: usize) -> ! {
+    exit(0)
+}
+
+fn register_and_quit(_: usize) -> ! {
+    set_tid_address(&EXITED as *const AtomicU32 as *mut u32);
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..ROUNDS {
+        let tid = thread_create(quit as usize, 0);
+        assert!(tid > 0);
+        assert_eq!(waittid(tid as usize), 0);
+        yield_();
+    }
+
+    let pid = fork_and_reap();
+    // 线程退出后在被 waittid 回收之前仍然保留着内核栈
+    let tid = thread_create(register_and_quit as usize, 0);
+    while EXITED.load(Ordering::Acquire) != 0 {
+        futex_wait(&EXITED, 1);
+    }
+    assert_eq!(fork_and_reap(), pid);
+    assert_eq!(waittid(tid as usize), 0);
+    println!("Test kstack OK!");
+    0
+}
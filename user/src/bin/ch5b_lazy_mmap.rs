@@ -0,0 +1,100 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, waitpid};
+
+/// 惰性分配测试：mmap 一段远大于物理内存的区域并稀疏地访问其中的页面，
+/// 未写过的页面读出 0；fork 出的子进程看到父进程写入的内容而写入互不影响；
+/// 系统调用可以直接写入尚未访问过的页面；没有写权限的页面仍然不能写。
+/// 正确输出：
+/// Test lazy mmap OK!
+
+const EEXIST: isize = -17;
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x4000_0000;
+// 1 GiB，远大于物理内存，只有实际访问到的页面才会分配页帧
+const LEN: usize = 1 << 30;
+// 每隔 1 MiB 访问一个页面
+const STRIDE: usize = 1 << 20;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * STRIDE) as *mut usize
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn child_sees_parent() -> i32 {
+    for i in 0..LEN / STRIDE {
+        let value = unsafe { page(i).read_volatile() };
+        if value != i {
+            return -1;
+        }
+        unsafe { page(i).write_volatile(i + 1) };
+    }
+    // 父进程没有访问过的页面在子进程中同样按需分配
+    let untouched = (START + PAGE_SIZE) as *mut usize;
+    unsafe {
+        assert_eq!(untouched.read_volatile(), 0);
+        untouched.write_volatile(7);
+    }
+    0
+}
+
+fn write_read_only() -> i32 {
+    let start = START + LEN;
+    assert_eq!(mmap(start, PAGE_SIZE, 1), 0);
+    // 只读页面可以读，读出 0
+    assert_eq!(unsafe { (start as *const usize).read_volatile() }, 0);
+    unsafe { (start as *mut usize).write_volatile(1) };
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, LEN, 3), 0);
+    // 与已有区域重叠的映射失败
+    assert_eq!(mmap(START + LEN - PAGE_SIZE, 2 * PAGE_SIZE, 3), EEXIST);
+    for i in 0..LEN / STRIDE {
+        assert_eq!(unsafe { page(i).read_volatile() }, 0);
+        unsafe { page(i).write_volatile(i) };
+    }
+
+    assert_eq!(run_child(child_sees_parent), 0);
+    for i in 0..LEN / STRIDE {
+        assert_eq!(unsafe { page(i).read_volatile() }, i);
+    }
+    assert_eq!(
+        unsafe { ((START + PAGE_SIZE) as *const usize).read_volatile() },
+        0
+    );
+
+    // 内核直接写入一个从未访问过的页面
+    let exit_code = (START + 2 * PAGE_SIZE) as *mut i32;
+    let pid = fork();
+    if pid == 0 {
+        exit(42);
+    }
+    assert_eq!(waitpid(pid as usize, unsafe { &mut *exit_code }), pid);
+    assert_eq!(unsafe { exit_code.read_volatile() }, 42);
+
+    assert_eq!(run_child(write_read_only), -SIGSEGV);
+
+    assert_eq!(munmap(START, LEN), 0);
+    assert_eq!(mmap(START, PAGE_SIZE, 3), 0);
+    assert_eq!(unsafe { page(0).read_volatile() }, 0);
+    assert_eq!(munmap(START, PAGE_SIZE), 0);
+    println!("Test lazy mmap OK!");
+    0
+}
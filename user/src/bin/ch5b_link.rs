@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, link, open, read, unlink, write, OpenFlags, Stat};
+
+/// 硬链接测试：link 之后两个路径指向同一个 inode，链接数为 2，从任一路径都能读到相同的内容；
+/// unlink 删除一个链接后链接数减 1，原路径不再能打开。删除最后一个链接时文件仍被打开，
+/// 已打开的文件描述符照常读写，它的 inode 在关闭前不会被新文件复用；关闭后 inode 被回收。
+/// 正确输出：
+/// Test link OK!
+
+const ENOENT: isize = -2;
+const EEXIST: isize = -17;
+const DATA: &[u8] = b"hard link data";
+
+fn open_fd(path: &str, flags: OpenFlags) -> usize {
+    let fd = open(path, flags);
+    assert!(fd > 2);
+    fd as usize
+}
+
+fn stat_of(fd: usize) -> Stat {
+    let st = Stat::new();
+    assert_eq!(fstat(fd, &st), 0);
+    st
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let a = "ch5b_link_a\0";
+    let b = "ch5b_link_b\0";
+    let c = "ch5b_link_c\0";
+    let fd = open_fd(a, OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC);
+    assert_eq!(write(fd, DATA), DATA.len() as isize);
+    assert_eq!(close(fd), 0);
+
+    // 两个路径指向同一个 inode
+    assert_eq!(link(a, b), 0);
+    assert_eq!(link(a, b), EEXIST);
+    assert_eq!(link("ch5b_link_none\0", c), ENOENT);
+    let fd_a = open_fd(a, OpenFlags::RDONLY);
+    let fd_b = open_fd(b, OpenFlags::RDONLY);
+    let (st_a, st_b) = (stat_of(fd_a), stat_of(fd_b));
+    assert_eq!(st_a.ino, st_b.ino);
+    assert_eq!(st_a.nlink, 2);
+    let mut buf = [0u8; 32];
+    assert_eq!(read(fd_b, &mut buf), DATA.len() as isize);
+    assert_eq!(&buf[..DATA.len()], DATA);
+    assert_eq!(close(fd_a), 0);
+    assert_eq!(close(fd_b), 0);
+
+    // 删除一个链接
+    assert_eq!(unlink(a), 0);
+    assert_eq!(unlink(a), ENOENT);
+    assert_eq!(open(a, OpenFlags::RDONLY), ENOENT);
+    let fd = open_fd(b, OpenFlags::RDWR);
+    assert_eq!(stat_of(fd).nlink, 1);
+
+    // 删除最后一个链接，打开的文件描述符仍然可用，inode 不会被复用
+    assert_eq!(unlink(b), 0);
+    assert_eq!(open(b, OpenFlags::RDONLY), ENOENT);
+    assert_eq!(stat_of(fd).nlink, 0);
+    assert_eq!(write(fd, b"!"), 1);
+    let fd_c = open_fd(c, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert_ne!(stat_of(fd_c).ino, st_a.ino);
+    assert_eq!(close(fd_c), 0);
+    assert_eq!(unlink(c), 0);
+    assert_eq!(close(fd), 0);
+
+    // 关闭之后 inode 被回收，新文件可以使用它
+    let fd_c = open_fd(c, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(stat_of(fd_c).ino <= st_a.ino);
+    assert_eq!(close(fd_c), 0);
+    assert_eq!(unlink(c), 0);
+    println!("Test link OK!");
+    0
+}
@@ -0,0 +1,90 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{exit, fork, mmap_with_flags, munmap, waitpid, yield_, MAP_PRIVATE, MAP_SHARED};
+
+/// 共享映射测试：MAP_SHARED 的映射在 fork 之后父子进程看到彼此的修改，可以用作简单的通信通道；
+/// MAP_PRIVATE 的映射仍然在 fork 时复制；父进程解除映射后子进程中的映射依然有效。
+/// 正确输出：
+/// Test map shared OK!
+
+const EINVAL: isize = -22;
+const PAGE_SIZE: usize = 4096;
+const SHARED: usize = 0x6800_0000;
+const PRIVATE: usize = 0x6900_0000;
+const ROUNDS: usize = 100;
+
+fn slot(i: usize) -> &'static AtomicUsize {
+    unsafe { &*((SHARED + i * PAGE_SIZE) as *const AtomicUsize) }
+}
+
+fn private_word() -> *mut usize {
+    PRIVATE as *mut usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap_with_flags(SHARED, 2 * PAGE_SIZE, 3, MAP_SHARED), 0);
+    assert_eq!(mmap_with_flags(PRIVATE, PAGE_SIZE, 3, MAP_PRIVATE), 0);
+    assert_eq!(
+        mmap_with_flags(0, PAGE_SIZE, 3, MAP_SHARED | MAP_PRIVATE),
+        EINVAL
+    );
+    assert_eq!(mmap_with_flags(0, PAGE_SIZE, 3, 0x10), EINVAL);
+    slot(1).store(7, Ordering::SeqCst);
+    unsafe { private_word().write_volatile(1) };
+
+    let pid = fork();
+    if pid == 0 {
+        // 第二页在 fork 之前写入，子进程看到的是同一个页帧
+        assert_eq!(slot(1).load(Ordering::SeqCst), 7);
+        // 与父进程轮流递增，没有共享时双方都会卡住
+        for round in 0..ROUNDS {
+            while slot(0).load(Ordering::SeqCst) != 2 * round + 1 {
+                yield_();
+            }
+            slot(0).store(2 * round + 2, Ordering::SeqCst);
+        }
+        // 私有映射的修改对父进程不可见
+        unsafe { private_word().write_volatile(2) };
+        while slot(1).load(Ordering::SeqCst) != 8 {
+            yield_();
+        }
+        slot(1).store(9, Ordering::SeqCst);
+        exit(0);
+    }
+    for round in 0..ROUNDS {
+        slot(0).store(2 * round + 1, Ordering::SeqCst);
+        while slot(0).load(Ordering::SeqCst) != 2 * round + 2 {
+            yield_();
+        }
+    }
+    slot(1).store(8, Ordering::SeqCst);
+    while slot(1).load(Ordering::SeqCst) != 9 {
+        yield_();
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(unsafe { private_word().read_volatile() }, 1);
+
+    // 父进程先解除映射，子进程仍然可以使用
+    let pid = fork();
+    if pid == 0 {
+        while slot(1).load(Ordering::SeqCst) != 10 {
+            yield_();
+        }
+        exit(slot(0).load(Ordering::SeqCst) as i32);
+    }
+    slot(1).store(10, Ordering::SeqCst);
+    assert_eq!(munmap(SHARED, 2 * PAGE_SIZE), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, (2 * ROUNDS) as i32);
+    assert_eq!(munmap(PRIVATE, PAGE_SIZE), 0);
+    println!("Test map shared OK!");
+    0
+}
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, meminfo, mmap, munmap, waitpid, MemInfo};
+
+/// 内存统计测试：mmap 并访问页面后进程占有的页面数和被占用的页帧数相应增加，
+/// munmap 以及子进程退出后页帧全部被回收；页帧占用的峰值不小于当前值。
+/// 正确输出：
+/// Test meminfo OK!
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x5c00_0000;
+const PAGES: usize = 64;
+
+fn info() -> MemInfo {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info
+}
+
+fn map_and_touch() {
+    assert_eq!(mmap(START, PAGES * PAGE_SIZE, 3), 0);
+    for i in 0..PAGES {
+        unsafe { ((START + i * PAGE_SIZE) as *mut usize).write_volatile(i) };
+    }
+}
+
+fn fork_and_wait() {
+    let pid = fork();
+    if pid == 0 {
+        map_and_touch();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = info();
+    assert!(start.free_frames < start.total_frames);
+    assert!(start.peak_frames >= start.total_frames - start.free_frames);
+    assert!(start.heap_used > 0 && start.heap_used <= start.heap_total);
+    assert!(start.mapped_pages > 0);
+
+    // 第一次映射时还会分配页表，之后再比较
+    map_and_touch();
+    assert_eq!(munmap(START, PAGES * PAGE_SIZE), 0);
+    let before = info();
+    map_and_touch();
+    let mapped = info();
+    assert_eq!(mapped.mapped_pages, before.mapped_pages + PAGES);
+    assert_eq!(mapped.free_frames, before.free_frames - PAGES);
+    assert!(mapped.peak_frames >= mapped.total_frames - mapped.free_frames);
+    assert_eq!(munmap(START, PAGES * PAGE_SIZE), 0);
+    let unmapped = info();
+    assert_eq!(unmapped.mapped_pages, before.mapped_pages);
+    assert_eq!(unmapped.free_frames, before.free_frames);
+
+    // 子进程退出并被回收后，它占用的页帧全部释放
+    fork_and_wait();
+    let before = info();
+    fork_and_wait();
+    let after = info();
+    assert_eq!(after.free_frames, before.free_frames);
+    assert_eq!(after.mapped_pages, before.mapped_pages);
+    println!("Test meminfo OK!");
+    0
+}
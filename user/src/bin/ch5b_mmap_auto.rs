@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap};
+
+/// mmap 自动选址测试：start 为 0 时内核从 mmap 区域的起始处按 first-fit 选择位置，
+/// 释放后留下的空洞会被之后足够小的映射复用；未对齐的 start 作为提示向上取整；
+/// 指定对齐的地址时行为不变。
+/// 正确输出：
+/// Test mmap auto OK!
+
+const EEXIST: isize = -17;
+const EINVAL: isize = -22;
+const PAGE_SIZE: usize = 4096;
+const MMAP_BASE: usize = 0x10_0000_0000;
+
+fn check_rw(addr: usize, pages: usize) {
+    for i in 0..pages {
+        let p = (addr + i * PAGE_SIZE) as *mut usize;
+        unsafe {
+            assert_eq!(p.read_volatile(), 0);
+            p.write_volatile(addr + i);
+            assert_eq!(p.read_volatile(), addr + i);
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let a = mmap(0, 3 * PAGE_SIZE, 3);
+    assert!(a >= MMAP_BASE as isize);
+    let a = a as usize;
+    assert_eq!(a % PAGE_SIZE, 0);
+    check_rw(a, 3);
+    // 长度不足一页按一页计算，紧接着上一段映射
+    let b = mmap(0, 100, 3);
+    assert_eq!(b, (a + 3 * PAGE_SIZE) as isize);
+    let b = b as usize;
+    check_rw(b, 1);
+
+    // 释放之后的空洞被复用，放不下的映射排到后面
+    assert_eq!(munmap(a, 3 * PAGE_SIZE), 0);
+    assert_eq!(mmap(0, 2 * PAGE_SIZE, 3), a as isize);
+    check_rw(a, 2);
+    assert_eq!(mmap(0, 2 * PAGE_SIZE, 3), (b + PAGE_SIZE) as isize);
+    assert_eq!(mmap(0, PAGE_SIZE, 1), (a + 2 * PAGE_SIZE) as isize);
+
+    // 未对齐的地址作为提示
+    let hint = 0x7000_0001;
+    assert_eq!(mmap(hint, PAGE_SIZE, 3), 0x7000_1000);
+    check_rw(0x7000_1000, 1);
+    assert_eq!(mmap(hint, PAGE_SIZE, 3), 0x7000_2000);
+    // 指定对齐的地址时成功返回 0，与已有映射重叠时失败
+    assert_eq!(mmap(0x7000_0000, PAGE_SIZE, 3), 0);
+    assert_eq!(mmap(0x7000_1000, PAGE_SIZE, 3), EEXIST);
+    assert_eq!(mmap(0, 0, 3), EINVAL);
+    assert_eq!(mmap(0, PAGE_SIZE, 0), EINVAL);
+
+    assert_eq!(munmap(0x7000_0000, 3 * PAGE_SIZE), 0);
+    assert_eq!(munmap(a, 4 * PAGE_SIZE), 0);
+    assert_eq!(munmap(b + PAGE_SIZE, 2 * PAGE_SIZE), 0);
+    println!("Test mmap auto OK!");
+    0
+}
@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::mmap;
+
+/// mmap 提示地址测试：未按页对齐的 start 不是固定地址，只作为提示。
+/// 内核返回的地址按页对齐，不小于提示地址，也不会与已有的映射重叠；返回的区间确实已经映射。
+/// 正确输出：
+/// Test mmap hint OK!
+
+const EEXIST: isize = -17;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x10000000;
+
+//[a, a + a_len) 与 [b, b + b_len) 是否相交
+fn overlaps(a: usize, a_len: usize, b: usize, b_len: usize) -> bool {
+    a < b + b_len && b < a + a_len
+}
+
+#[no_mangle]
+fn main() -> i32 {
+    let len = 2 * PAGE_SIZE;
+    let prot: usize = 3;
+    assert_eq!(mmap(START, len, prot), 0);
+    // 提示地址落在已有映射的中间
+    let hint = START + 1;
+    let first = mmap(hint, len, prot);
+    assert!(first > 0);
+    let first = first as usize;
+    assert_eq!(first % PAGE_SIZE, 0);
+    assert!(first >= hint);
+    assert!(!overlaps(first, len, START, len));
+    // 提示地址落在上一次返回的区间中
+    let hint = first + PAGE_SIZE + 1;
+    let second = mmap(hint, PAGE_SIZE + 1, prot);
+    assert!(second > 0);
+    let second = second as usize;
+    assert_eq!(second % PAGE_SIZE, 0);
+    assert!(second >= hint);
+    assert!(!overlaps(second, len, START, len));
+    assert!(!overlaps(second, len, first, len));
+    // 返回的区间已经映射并且可以读写
+    for addr in [first, first + PAGE_SIZE, second, second + PAGE_SIZE] {
+        let p = addr as *mut usize;
+        unsafe {
+            p.write_volatile(addr);
+            assert_eq!(p.read_volatile(), addr);
+        }
+    }
+    assert_eq!(mmap(first, PAGE_SIZE, prot), EEXIST);
+    assert_eq!(mmap(second, PAGE_SIZE, prot), EEXIST);
+    println!("Test mmap hint OK!");
+    0
+}
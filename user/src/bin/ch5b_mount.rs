@@ -0,0 +1,90 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fstat, link, mkdir, mount, open, read, umount, unlink, write, OpenFlags, Stat,
+};
+
+/// 挂载测试：把 easy-fs 再挂载到一个子目录上，经过挂载点的路径进入挂载的文件系统，与根目录下看到的是同一个文件；
+/// 挂载点之下还可以再挂载，卸载时要先卸载里层。不认识的类型返回 -ENODEV，重复挂载返回 -EBUSY，
+/// 挂载到普通文件上返回 -ENOTDIR，跨挂载点创建硬链接返回 -EXDEV，卸载根目录返回 -EBUSY，
+/// 卸载不是挂载点的目录返回 -EINVAL。
+/// 正确输出：
+/// Test mount OK!
+
+const ENOENT: isize = -2;
+const EBUSY: isize = -16;
+const EEXIST: isize = -17;
+const EXDEV: isize = -18;
+const ENODEV: isize = -19;
+const ENOTDIR: isize = -20;
+const EINVAL: isize = -22;
+const DATA: &[u8] = b"seen through a mount point";
+
+fn ino_of(path: &str) -> u64 {
+    let fd = open(path, OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let st = Stat::new();
+    assert_eq!(fstat(fd as usize, &st), 0);
+    assert_eq!(close(fd as usize), 0);
+    st.ino
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let r = mkdir("/ch5b_mnt\0");
+    assert!(r == 0 || r == EEXIST);
+    let fd = open(
+        "/ch5b_mount.tmp\0",
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC,
+    );
+    assert!(fd > 2);
+    assert_eq!(write(fd as usize, DATA), DATA.len() as isize);
+    assert_eq!(close(fd as usize), 0);
+
+    assert_eq!(mount("none\0", "/ch5b_mnt\0", "nofs\0"), ENODEV);
+    assert_eq!(mount("none\0", "/ch5b_mount.tmp\0", "easyfs\0"), ENOTDIR);
+    assert_eq!(mount("none\0", "/ch5b_no_mnt\0", "easyfs\0"), ENOENT);
+    assert_eq!(mount("none\0", "/ch5b_mnt\0", "easyfs\0"), 0);
+    assert_eq!(mount("none\0", "/ch5b_mnt/\0", "easyfs\0"), EBUSY);
+
+    // 经过挂载点看到同一个文件
+    let fd = open("/ch5b_mnt/ch5b_mount.tmp\0", OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let mut buf = [0u8; 32];
+    assert_eq!(read(fd as usize, &mut buf), DATA.len() as isize);
+    assert_eq!(&buf[..DATA.len()], DATA);
+    assert_eq!(close(fd as usize), 0);
+    assert_eq!(
+        ino_of("/ch5b_mnt/ch5b_mount.tmp\0"),
+        ino_of("/ch5b_mount.tmp\0")
+    );
+    assert_eq!(
+        ino_of("/ch5b_mnt/../ch5b_mount.tmp\0"),
+        ino_of("/ch5b_mount.tmp\0")
+    );
+    assert_eq!(link("/ch5b_mount.tmp\0", "/ch5b_mnt/ch5b_link\0"), EXDEV);
+
+    // 嵌套的挂载点
+    assert_eq!(mount("none\0", "/ch5b_mnt/ch5b_mnt\0", "easyfs\0"), 0);
+    assert_eq!(
+        ino_of("/ch5b_mnt/ch5b_mnt/ch5b_mount.tmp\0"),
+        ino_of("/ch5b_mount.tmp\0")
+    );
+    assert_eq!(umount("/ch5b_mnt\0"), EBUSY);
+    assert_eq!(umount("/ch5b_mnt/ch5b_mnt\0"), 0);
+
+    assert_eq!(umount("/\0"), EBUSY);
+    assert_eq!(umount("/ch5b_mnt\0"), 0);
+    assert_eq!(umount("/ch5b_mnt\0"), EINVAL);
+    assert_eq!(
+        open("/ch5b_mnt/ch5b_mount.tmp\0", OpenFlags::RDONLY),
+        ENOENT
+    );
+    assert_eq!(unlink("/ch5b_mount.tmp\0"), 0);
+    println!("Test mount OK!");
+    0
+}
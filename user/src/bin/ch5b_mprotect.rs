@@ -0,0 +1,103 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, munmap, sbrk, waitpid};
+
+/// mprotect 测试：修改一段映射中间几页的权限后，只读页面不能写、其余页面不受影响；
+/// 写入机器码后改为可执行即可调用（JIT）；范围中有未映射的页面、参数不合法或与用户堆重叠时失败。
+/// 正确输出：
+/// Test mprotect OK!
+
+const EINVAL: isize = -22;
+const ENOMEM: isize = -12;
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x5000_0000;
+const PAGES: usize = 4;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+const PROT_X: usize = 4;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE_SIZE) as *mut usize
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn write_touched_read_only() -> i32 {
+    unsafe { page(2).write_volatile(0) };
+    0
+}
+
+fn write_untouched_read_only() -> i32 {
+    unsafe { page(1).write_volatile(0) };
+    0
+}
+
+fn call_code() -> i32 {
+    let f: fn() -> usize = unsafe { core::mem::transmute(page(0)) };
+    f() as i32
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, PAGES * PAGE_SIZE, PROT_R | PROT_W), 0);
+    unsafe {
+        page(0).write_volatile(10);
+        page(2).write_volatile(12);
+    }
+
+    // 中间两页改为只读，两端的页面仍然可写
+    assert_eq!(mprotect(START + PAGE_SIZE, 2 * PAGE_SIZE, PROT_R), 0);
+    assert_eq!(unsafe { page(2).read_volatile() }, 12);
+    assert_eq!(run_child(write_untouched_read_only), -SIGSEGV);
+    assert_eq!(unsafe { page(1).read_volatile() }, 0);
+    assert_eq!(run_child(write_touched_read_only), -SIGSEGV);
+    unsafe {
+        page(0).write_volatile(20);
+        page(3).write_volatile(23);
+    }
+    assert_eq!(
+        mprotect(START + PAGE_SIZE, 2 * PAGE_SIZE, PROT_R | PROT_W),
+        0
+    );
+    unsafe { page(2).write_volatile(22) };
+    assert_eq!(unsafe { page(2).read_volatile() }, 22);
+
+    // 参数不合法、范围中有未映射的页面或与用户堆重叠
+    assert_eq!(mprotect(START + 1, PAGE_SIZE, PROT_R), EINVAL);
+    assert_eq!(mprotect(START, PAGE_SIZE, 0), EINVAL);
+    assert_eq!(mprotect(START, PAGE_SIZE, 8), EINVAL);
+    assert_eq!(mprotect(START, (PAGES + 1) * PAGE_SIZE, PROT_R), ENOMEM);
+    let heap = sbrk(0) as usize;
+    assert_eq!(sbrk(PAGE_SIZE as i32), heap as isize);
+    assert_eq!(mprotect(heap, PAGE_SIZE, PROT_R), EINVAL);
+    assert_eq!(sbrk(-(PAGE_SIZE as i32)), (heap + PAGE_SIZE) as isize);
+    // 失败的调用不改变权限
+    unsafe { page(3).write_volatile(33) };
+
+    // li a0, 42; ret
+    let code: [u32; 2] = [0x02a0_0513, 0x0000_8067];
+    unsafe {
+        core::ptr::copy_nonoverlapping(code.as_ptr(), page(0) as *mut u32, code.len());
+        core::arch::asm!("fence.i");
+    }
+    assert_eq!(run_child(call_code), -SIGSEGV);
+    assert_eq!(mprotect(START, PAGE_SIZE, PROT_R | PROT_X), 0);
+    assert_eq!(call_code(), 42);
+
+    assert_eq!(munmap(START, PAGES * PAGE_SIZE), 0);
+    println!("Test mprotect OK!");
+    0
+}
@@ -0,0 +1,95 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mremap, munmap, waitpid, MREMAP_MAYMOVE};
+
+/// mremap 测试：其后空闲时原地扩大；其后已被占用时不允许移动则失败，允许移动则连同数据
+/// 一起移动到新的地址，原地址不再可以访问；缩小后回收的页面不能再访问。
+/// 正确输出：
+/// Test mremap OK!
+
+const ENOMEM: isize = -12;
+const EINVAL: isize = -22;
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x6000_0000;
+
+static mut TARGET: usize = 0;
+
+fn at(addr: usize, page: usize) -> *mut usize {
+    (addr + page * PAGE_SIZE) as *mut usize
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn touch() -> i32 {
+    unsafe { at(TARGET, 0).read_volatile() as i32 }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, 2 * PAGE_SIZE, 3), 0);
+    unsafe {
+        at(START, 0).write_volatile(100);
+        at(START, 1).write_volatile(101);
+    }
+
+    // 其后空闲，原地扩大
+    assert_eq!(
+        mremap(START, 2 * PAGE_SIZE, 4 * PAGE_SIZE, 0),
+        START as isize
+    );
+    assert_eq!(unsafe { at(START, 1).read_volatile() }, 101);
+    assert_eq!(unsafe { at(START, 3).read_volatile() }, 0);
+    unsafe { at(START, 3).write_volatile(103) };
+
+    // 其后被另一段映射占用
+    let next = START + 4 * PAGE_SIZE;
+    assert_eq!(mmap(next, PAGE_SIZE, 3), 0);
+    assert_eq!(mremap(START, 4 * PAGE_SIZE, 6 * PAGE_SIZE, 0), ENOMEM);
+    assert_eq!(unsafe { at(START, 0).read_volatile() }, 100);
+    let moved = mremap(START, 4 * PAGE_SIZE, 6 * PAGE_SIZE, MREMAP_MAYMOVE);
+    assert!(moved >= (next + PAGE_SIZE) as isize);
+    let moved = moved as usize;
+    for (page, value) in [(0, 100), (1, 101), (2, 0), (3, 103), (5, 0)].iter() {
+        assert_eq!(unsafe { at(moved, *page).read_volatile() }, *value);
+    }
+    unsafe {
+        TARGET = START;
+    }
+    assert_eq!(run_child(touch), -SIGSEGV);
+    unsafe {
+        TARGET = moved;
+    }
+    assert_eq!(run_child(touch), 100);
+
+    // 缩小
+    assert_eq!(mremap(moved, 6 * PAGE_SIZE, PAGE_SIZE, 0), moved as isize);
+    unsafe {
+        TARGET = moved + PAGE_SIZE;
+    }
+    assert_eq!(run_child(touch), -SIGSEGV);
+
+    // 参数不合法或不是一段完整的 mmap 映射
+    assert_eq!(mremap(moved + 1, PAGE_SIZE, 2 * PAGE_SIZE, 0), EINVAL);
+    assert_eq!(mremap(moved, 2 * PAGE_SIZE, 3 * PAGE_SIZE, 0), ENOMEM);
+    assert_eq!(mremap(moved, PAGE_SIZE, 0, 0), EINVAL);
+    assert_eq!(mremap(moved, PAGE_SIZE, 2 * PAGE_SIZE, 2), EINVAL);
+    assert_eq!(mremap(START, PAGE_SIZE, 2 * PAGE_SIZE, MREMAP_MAYMOVE), ENOMEM);
+
+    assert_eq!(munmap(moved, PAGE_SIZE), 0);
+    assert_eq!(munmap(next, PAGE_SIZE), 0);
+    println!("Test mremap OK!");
+    0
+}
@@ -0,0 +1,89 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, mutex_blocking_create, mutex_lock, mutex_unlock, set_priority,
+    sleep_blocking, wait,
+};
+
+/// 优先级继承测试：低优先级进程 L 持有锁做一段计算，高优先级进程 H 等待这把锁，
+/// 同时两个中等优先级进程 M 一直占用 CPU。
+/// 没有优先级继承时 L 只能分到很少的 CPU，H 要等到 M 结束才能拿到锁；
+/// 有优先级继承时 L 被提升到 H 的优先级，H 很快就能拿到锁。
+/// 正确输出：
+/// Test mutex priority inheritance OK!
+
+const LOW: isize = 2;
+const MID: isize = 32;
+const HIGH: isize = 64;
+/// L 持有锁期间的计算量，以单独运行时所需的毫秒数计
+const WORK_MS: isize = 100;
+/// M 占用 CPU 的时长
+const HOG_MS: isize = 1500;
+
+fn spin_delay() {
+    let mut j = true;
+    for _ in 0..10 {
+        j = !j;
+    }
+}
+
+//测量单独运行时 ms 毫秒内能完成多少次 spin_delay
+fn calibrate(ms: isize) -> usize {
+    let start = get_time();
+    let mut n = 0;
+    while get_time() - start < ms {
+        spin_delay();
+        n += 1;
+    }
+    n
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let work = calibrate(WORK_MS);
+    let mutex_id = mutex_blocking_create() as usize;
+    // L：拿到锁后做计算
+    if fork() == 0 {
+        set_priority(LOW);
+        mutex_lock(mutex_id);
+        for _ in 0..work {
+            spin_delay();
+        }
+        mutex_unlock(mutex_id);
+        exit(0);
+    }
+    // H：等 L 拿到锁后再来竞争，退出码为等待锁的时间
+    if fork() == 0 {
+        set_priority(HIGH);
+        sleep_blocking(20);
+        let start = get_time();
+        mutex_lock(mutex_id);
+        let waited = get_time() - start;
+        mutex_unlock(mutex_id);
+        exit(waited as i32);
+    }
+    for _ in 0..2 {
+        if fork() == 0 {
+            set_priority(MID);
+            sleep_blocking(10);
+            let start = get_time();
+            while get_time() - start < HOG_MS {}
+            exit(0);
+        }
+    }
+    let mut waited = 0;
+    let mut exit_code: i32 = 0;
+    for _ in 0..4 {
+        assert!(wait(&mut exit_code) > 0);
+        waited = waited.max(exit_code);
+    }
+    println!("high priority task waited {} ms", waited);
+    // 被提升后 L 至少能分到一半的 CPU
+    assert!((waited as isize) < HOG_MS / 2);
+    println!("Test mutex priority inheritance OK!");
+    0
+}
@@ -0,0 +1,87 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, meminfo, mmap_with_flags, munmap, waitpid, MemInfo, MAP_SHARED};
+
+/// 内存耗尽测试：子进程用 MAP_SHARED 的映射（页帧立即分配且不会被换出）占满物理内存，
+/// 此时 mmap 返回 -ENOMEM；再释放少量映射，剩余的页帧不足以为子进程复制页表，fork 返回 -ENOMEM。
+/// 内核在这一过程中不会 panic，释放映射之后又可以正常 fork。
+/// 正确输出：
+/// Test oom OK!
+
+const PAGE_SIZE: usize = 4096;
+const ENOMEM: isize = -12;
+const START: usize = 0x28_0000_0000;
+const CHUNK: usize = 16 * PAGE_SIZE;
+// 留给缺页使用的页帧数，远少于复制占满内存的共享映射所需的页表
+const LEVEL: usize = 64;
+const EXIT_CODE: i32 = 7;
+
+fn chunk(i: usize) -> usize {
+    START + i * CHUNK
+}
+
+fn free_frames() -> usize {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info.free_frames
+}
+
+fn wait_for(pid: isize, code: i32) {
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, code);
+}
+
+fn exhaust() -> ! {
+    let mut chunks = 0;
+    loop {
+        let ret = mmap_with_flags(chunk(chunks), CHUNK, 3, MAP_SHARED);
+        if ret != 0 {
+            assert_eq!(ret, ENOMEM);
+            break;
+        }
+        unsafe { (chunk(chunks) as *mut usize).write_volatile(chunks) };
+        chunks += 1;
+    }
+    assert!(chunks > 0);
+    while free_frames() < LEVEL {
+        chunks -= 1;
+        assert_eq!(munmap(chunk(chunks), CHUNK), 0);
+    }
+    assert_eq!(fork(), ENOMEM);
+    assert_eq!(fork(), ENOMEM);
+    for i in 0..chunks {
+        assert_eq!(unsafe { (chunk(i) as *const usize).read_volatile() }, i);
+        assert_eq!(munmap(chunk(i), CHUNK), 0);
+    }
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    wait_for(pid, 0);
+    exit(EXIT_CODE);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..2 {
+        let pid = fork();
+        if pid == 0 {
+            exhaust();
+        }
+        wait_for(pid, EXIT_CODE);
+        // 内核仍然正常工作
+        let pid = fork();
+        if pid == 0 {
+            exit(0);
+        }
+        wait_for(pid, 0);
+    }
+    println!("Test oom OK!");
+    0
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpgid, getpid, getsid, kill, setpgid, setsid, sleep_blocking, wait, waitpid,
+    SIGKILL,
+};
+
+/// 进程组与会话测试：把一组子进程放进同一个进程组，一次性终止整个进程组。
+/// 正确输出：
+/// Test pgid OK!
+
+const EPERM: isize = -1;
+const ESRCH: isize = -3;
+const N: usize = 3;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let pgid = getpgid(0);
+    let sid = getsid(0);
+    assert!(pgid >= 0 && sid >= 0);
+    // 子进程继承进程组与会话
+    let leader = fork();
+    if leader == 0 {
+        assert_eq!(getpgid(0), pgid);
+        assert_eq!(getsid(0), sid);
+        // 新建以自己为组长的进程组
+        assert_eq!(setpgid(0, 0), 0);
+        assert_eq!(getpgid(0), getpid());
+        // 组长不能新建会话
+        assert_eq!(setsid(), EPERM);
+        loop {
+            sleep_blocking(10);
+        }
+    }
+    // 等待组长设置好进程组
+    while getpgid(leader as usize) != leader {
+        sleep_blocking(1);
+    }
+    for _ in 1..N {
+        let child = fork();
+        if child == 0 {
+            loop {
+                sleep_blocking(10);
+            }
+        }
+        // 父进程把子进程加入组长的进程组
+        assert_eq!(setpgid(child as usize, leader as usize), 0);
+        assert_eq!(getpgid(child as usize), leader);
+    }
+    // 不存在的进程组
+    assert_eq!(setpgid(0, 1 << 20), EPERM);
+    assert_eq!(getpgid(0), pgid);
+    assert_eq!(kill(-leader, SIGKILL), 0);
+    let mut exit_code: i32 = 0;
+    for _ in 0..N {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, -SIGKILL);
+    }
+    // 进程组已经不存在
+    assert_eq!(kill(-leader, 0), ESRCH);
+    // 新会话中的子进程
+    let child = fork();
+    if child == 0 {
+        assert_eq!(setsid(), getpid());
+        assert_eq!(getsid(0), getpid());
+        assert_eq!(getpgid(0), getpid());
+        exit(0);
+    }
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    assert_eq!(exit_code, 0);
+    assert_eq!(getpid(), pid);
+    println!("Test pgid OK!");
+    0
+}
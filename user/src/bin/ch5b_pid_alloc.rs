@@ -0,0 +1,48 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sleep, waitpid};
+
+/// PID 分配测试：总是分配编号最小的空闲 PID，回收后按从小到大的顺序复用。
+/// 需要单独运行，运行期间不应有其他进程被创建。
+/// 正确输出：
+/// Test pid alloc OK!
+
+fn child() -> isize {
+    let pid = fork();
+    if pid == 0 {
+        // 等待父进程按指定的顺序回收
+        sleep(10);
+        exit(0);
+    }
+    assert!(pid > 0);
+    pid
+}
+
+fn reap(pid: isize) {
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let a = child();
+    let b = child();
+    let c = child();
+    assert!(a < b && b < c);
+    // 先回收 a 再回收 c：后进先出的分配器会先复用 c，按最小编号分配则先复用 a
+    reap(a);
+    reap(c);
+    let d = child();
+    let e = child();
+    assert_eq!(d, a);
+    assert_eq!(e, c);
+    reap(b);
+    reap(d);
+    reap(e);
+    println!("Test pid alloc OK!");
+    0
+}
@@ -0,0 +1,80 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, fstat, pipe, read, syscall, waitpid, write, Stat, StatMode, SYSCALL_PIPE,
+};
+
+/// 管道阻塞测试：写入超过管道缓冲区大小的数据时写者阻塞，直到读者取走数据；
+/// 缓冲区为空时读者阻塞，直到写者写入数据，读到的数据与写入的完全一致。
+/// 管道的读端只能读、写端只能写，fstat 报告它们是管道。
+/// 正确输出：
+/// Test pipe block OK!
+
+const PIPE_BUF_SIZE: usize = 4096;
+const TOTAL: usize = 3 * PIPE_BUF_SIZE + 100;
+const EBADF: isize = -9;
+const EFAULT: isize = -14;
+
+fn pattern(i: usize) -> u8 {
+    (i * 7 % 251) as u8
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(syscall(SYSCALL_PIPE, [0, 0, 0]), EFAULT);
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (rfd, wfd) = (fds[0], fds[1]);
+    assert!(rfd > 2 && wfd > 2 && rfd != wfd);
+    let mut byte = [0u8; 1];
+    assert_eq!(write(rfd, b"x"), EBADF);
+    assert_eq!(read(wfd, &mut byte), EBADF);
+    for &fd in [rfd, wfd].iter() {
+        let stat = Stat::new();
+        assert_eq!(fstat(fd, &stat), 0);
+        assert_eq!(stat.mode, StatMode::FIFO);
+    }
+
+    // 子进程一次写入多于缓冲区大小的数据，缓冲区满时阻塞，直到父进程读出
+    let pid = fork();
+    if pid == 0 {
+        let mut data = [0u8; TOTAL];
+        for (i, byte) in data.iter_mut().enumerate() {
+            *byte = pattern(i);
+        }
+        assert_eq!(write(wfd, &data), TOTAL as isize);
+        exit(0);
+    }
+    let mut buf = [0u8; 1000];
+    let mut received = 0;
+    while received < TOTAL {
+        let n = read(rfd, &mut buf);
+        assert!(n > 0);
+        for (i, &byte) in buf[..n as usize].iter().enumerate() {
+            assert_eq!(byte, pattern(received + i));
+        }
+        received += n as usize;
+    }
+    assert_eq!(received, TOTAL);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // 缓冲区为空时读者阻塞，直到子进程写入
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(write(wfd, b"ping"), 4);
+        exit(0);
+    }
+    let mut buf = [0u8; 16];
+    assert_eq!(read(rfd, &mut buf), 4);
+    assert_eq!(&buf[..4], b"ping");
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test pipe block OK!");
+    0
+}
@@ -0,0 +1,120 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fcntl, fork, get_time, pipe, poll, read, sleep, waitpid, write, OpenFlags, PollFd,
+    F_SETFL, POLLERR, POLLHUP, POLLIN, POLLNVAL, POLLOUT,
+};
+
+/// ppoll 测试：两个子进程在不同时刻向各自的管道写入，父进程用 poll 同时等待两个读端，
+/// 收齐全部数据并在两个写端都关闭之后看到 POLLHUP；空管道在超时之后返回 0，满管道不可写，
+/// 读端都关闭的管道报告 POLLERR，没有打开的文件描述符报告 POLLNVAL，fd 为负数的项被忽略。
+/// 正确输出：
+/// Test poll OK!
+
+const EAGAIN: isize = -11;
+
+fn make_pipe() -> (usize, usize) {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    (fds[0], fds[1])
+}
+
+// 子进程每隔 delay_ms 毫秒写入一个 byte，共 count 次，然后退出
+fn writer(fd: usize, byte: u8, count: usize, delay_ms: usize) -> isize {
+    let pid = fork();
+    if pid == 0 {
+        for _ in 0..count {
+            sleep(delay_ms);
+            assert_eq!(write(fd, &[byte]), 1);
+        }
+        exit(0);
+    }
+    assert!(pid > 0);
+    pid
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 同时等待两个子进程的输出
+    let (a_read, a_write) = make_pipe();
+    let (b_read, b_write) = make_pipe();
+    let a_pid = writer(a_write, b'a', 3, 10);
+    let b_pid = writer(b_write, b'b', 2, 25);
+    assert_eq!(close(a_write), 0);
+    assert_eq!(close(b_write), 0);
+    let mut fds = [PollFd::new(a_read, POLLIN), PollFd::new(b_read, POLLIN)];
+    let mut received = [0usize; 2];
+    let mut closed = [false; 2];
+    while !(closed[0] && closed[1]) {
+        let ready = poll(&mut fds, -1);
+        assert!(ready > 0);
+        for (i, fd) in fds.iter().enumerate() {
+            if fd.revents & POLLIN != 0 {
+                let mut buf = [0u8; 8];
+                let n = read(fd.fd as usize, &mut buf);
+                assert!(n > 0);
+                for &byte in buf[..n as usize].iter() {
+                    assert_eq!(byte, [b'a', b'b'][i]);
+                }
+                received[i] += n as usize;
+            } else if fd.revents & POLLHUP != 0 {
+                closed[i] = true;
+            }
+        }
+    }
+    assert_eq!(received, [3, 2]);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(a_pid as usize, &mut exit_code), a_pid);
+    assert_eq!(waitpid(b_pid as usize, &mut exit_code), b_pid);
+    assert_eq!(close(a_read), 0);
+    assert_eq!(close(b_read), 0);
+
+    // 空管道等到超时
+    let (read_end, write_end) = make_pipe();
+    let mut fds = [PollFd::new(read_end, POLLIN)];
+    assert_eq!(poll(&mut fds, 0), 0);
+    let start = get_time();
+    assert_eq!(poll(&mut fds, 30), 0);
+    assert!(get_time() - start >= 30);
+    assert_eq!(fds[0].revents, 0);
+
+    // 写满之后不可写，缓冲区中有数据时可读
+    let mut fds = [PollFd::new(write_end, POLLOUT)];
+    assert_eq!(poll(&mut fds, 0), 1);
+    assert_eq!(fds[0].revents, POLLOUT);
+    assert_eq!(
+        fcntl(write_end, F_SETFL, OpenFlags::NONBLOCK.bits() as usize),
+        0
+    );
+    let chunk = [0u8; 256];
+    while write(write_end, &chunk) != EAGAIN {}
+    assert_eq!(poll(&mut fds, 0), 0);
+    let mut fds = [PollFd::new(read_end, POLLIN | POLLOUT)];
+    assert_eq!(poll(&mut fds, 0), 1);
+    assert_eq!(fds[0].revents, POLLIN);
+
+    // 读端关闭、没有打开的文件描述符与被忽略的项
+    assert_eq!(close(read_end), 0);
+    let mut fds = [
+        PollFd::new(write_end, POLLOUT),
+        PollFd::new(99, POLLIN),
+        PollFd {
+            fd: -1,
+            events: POLLIN,
+            revents: 0,
+        },
+        PollFd::new(1, POLLOUT),
+    ];
+    assert_eq!(poll(&mut fds, 0), 3);
+    assert_eq!(fds[0].revents, POLLERR);
+    assert_eq!(fds[1].revents, POLLNVAL);
+    assert_eq!(fds[2].revents, 0);
+    assert_eq!(fds[3].revents, POLLOUT);
+    assert_eq!(close(write_end), 0);
+    println!("Test poll OK!");
+    0
+}
@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_name, set_name, waitpid, TASK_NAME_LEN};
+
+/// 进程名测试：默认进程名为应用名，fork 时继承，可以通过 prctl 修改，过长的名字会被截断。
+/// 正确输出：
+/// Test proc name OK!
+
+fn name_of(buf: &[u8; TASK_NAME_LEN]) -> &str {
+    let len = buf.iter().position(|c| *c == 0).unwrap();
+    core::str::from_utf8(&buf[..len]).unwrap()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut buf = [0u8; TASK_NAME_LEN];
+    assert_eq!(get_name(&mut buf), 0);
+    assert_eq!(name_of(&buf), "ch5b_proc_name");
+    let pid = fork();
+    if pid == 0 {
+        let mut buf = [0u8; TASK_NAME_LEN];
+        get_name(&mut buf);
+        assert_eq!(name_of(&buf), "ch5b_proc_name");
+        assert_eq!(set_name("worker\0"), 0);
+        get_name(&mut buf);
+        assert_eq!(name_of(&buf), "worker");
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // 子进程改名不影响父进程
+    get_name(&mut buf);
+    assert_eq!(name_of(&buf), "ch5b_proc_name");
+    assert_eq!(set_name("0123456789abcdef0123456789abcdefXYZ\0"), 0);
+    get_name(&mut buf);
+    assert_eq!(name_of(&buf), "0123456789abcdef0123456789abcde");
+    println!("Test proc name OK!");
+    0
+}
@@ -0,0 +1,127 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use alloc::string::String;
+use user_lib::{
+    close, getdents, getpid, lseek, mkdir, open, read, Dirents, OpenFlags, DT_DIR, SEEK_SET,
+};
+
+/// procfs 测试：/proc/meminfo 给出内存的使用情况；/proc/<pid>/status 给出进程的名字、状态、驻留集大小
+/// 与系统调用次数，移动到开头之后再读会重新生成内容；getdents 列出 meminfo 与当前进程的目录。
+/// 以写方式打开返回 -EACCES，不存在的进程返回 -ENOENT，不能创建目录，返回 -EPERM。
+/// 正确输出：
+/// Test procfs OK!
+
+const EPERM: isize = -1;
+const ENOENT: isize = -2;
+const EACCES: isize = -13;
+const ENOTDIR: isize = -20;
+const SYSCALL_OPENAT: usize = 56;
+
+fn read_to_string(fd: usize) -> String {
+    let mut s = String::new();
+    let mut buf = [0u8; 64];
+    loop {
+        let n = read(fd, &mut buf);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        s.push_str(core::str::from_utf8(&buf[..n as usize]).unwrap());
+    }
+    s
+}
+
+// 取出 "Key:\tvalue" 一行中的 value
+fn field<'a>(text: &'a str, key: &str) -> &'a str {
+    text.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(":\t"))
+        .unwrap()
+}
+
+// status 中某个系统调用的调用次数
+fn syscall_times(status: &str, id: usize) -> usize {
+    field(status, "Syscalls")
+        .split(' ')
+        .find_map(|item| {
+            let (sid, times) = item.split_once(':')?;
+            if sid.parse::<usize>().ok()? == id {
+                times.parse().ok()
+            } else {
+                None
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // /proc/meminfo
+    let fd = open("/proc/meminfo\0", OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let meminfo = read_to_string(fd as usize);
+    assert_eq!(close(fd as usize), 0);
+    let total: usize = field(&meminfo, "MemTotal")
+        .trim_end_matches(" kB")
+        .parse()
+        .unwrap();
+    let free: usize = field(&meminfo, "MemFree")
+        .trim_end_matches(" kB")
+        .parse()
+        .unwrap();
+    assert!(total > 0 && free <= total);
+
+    // /proc/<pid>/status
+    let pid = getpid();
+    let path = format!("/proc/{}/status\0", pid);
+    let fd = open(&path, OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let status = read_to_string(fd as usize);
+    assert_eq!(field(&status, "Name"), "ch5b_procfs");
+    assert_eq!(field(&status, "Pid"), format!("{}", pid));
+    assert_eq!(field(&status, "State"), "R (running)");
+    assert_ne!(field(&status, "VmRSS"), "0 kB");
+    let opens = syscall_times(&status, SYSCALL_OPENAT);
+    assert!(opens >= 2);
+    // 再打开一次文件，重新读取时计数增加
+    let fd2 = open("/proc/meminfo\0", OpenFlags::RDONLY);
+    assert!(fd2 > 2);
+    assert_eq!(close(fd2 as usize), 0);
+    assert_eq!(lseek(fd as usize, 0, SEEK_SET), 0);
+    let status = read_to_string(fd as usize);
+    assert_eq!(syscall_times(&status, SYSCALL_OPENAT), opens + 1);
+    assert_eq!(close(fd as usize), 0);
+
+    // 列出 /proc
+    let fd = open("/proc\0", OpenFlags::RDONLY);
+    assert!(fd > 2);
+    let mut dents = [0u8; 512];
+    let name = format!("{}", pid);
+    let (mut found_meminfo, mut found_self) = (false, false);
+    loop {
+        let n = getdents(fd as usize, &mut dents);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        for dirent in Dirents::new(&dents[..n as usize]) {
+            found_meminfo |= dirent.name == "meminfo";
+            found_self |= dirent.name == name && dirent.d_type == DT_DIR;
+        }
+    }
+    assert!(found_meminfo && found_self);
+    assert_eq!(close(fd as usize), 0);
+
+    // 只读的文件系统
+    assert_eq!(open("/proc/meminfo\0", OpenFlags::WRONLY), EACCES);
+    assert_eq!(open("/proc/99999/status\0", OpenFlags::RDONLY), ENOENT);
+    assert_eq!(open("/proc/meminfo/x\0", OpenFlags::RDONLY), ENOTDIR);
+    assert_eq!(mkdir("/proc/x\0"), EPERM);
+    println!("Test procfs OK!");
+    0
+}
@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    fork, getpid, kill, ps, set_name, sleep_blocking, waitpid, ProcInfo, PROC_BLOCKED,
+    PROC_RUNNING, SIGKILL,
+};
+
+/// sys_ps 测试：列出所有进程，检查当前进程与两个处于睡眠（阻塞）状态的子进程的信息。
+/// 正确输出：
+/// Test ps OK!
+
+const MAX_PROCS: usize = 64;
+
+fn status_name(status: usize) -> &'static str {
+    match status {
+        1 => "Ready",
+        2 => "Running",
+        3 => "Blocked",
+        4 => "Zombie",
+        _ => "UnInit",
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid() as usize;
+    let mut children = [0usize; 2];
+    for child in children.iter_mut() {
+        let ret = fork();
+        if ret == 0 {
+            set_name("ps_sleeper\0");
+            loop {
+                sleep_blocking(100);
+            }
+        }
+        *child = ret as usize;
+    }
+    // 等子进程改好名字并进入睡眠
+    sleep_blocking(20);
+    let mut procs = [ProcInfo::empty(); MAX_PROCS];
+    let count = ps(&mut procs) as usize;
+    assert!(count >= 4);
+    println!("  PID  PPID STATUS   PRIO TIME(ms) NAME");
+    for info in procs[..count].iter() {
+        println!(
+            "{:>5} {:>5} {:<8} {:>4} {:>8} {}",
+            info.pid,
+            info.ppid,
+            status_name(info.status),
+            info.priority,
+            info.run_time,
+            info.name()
+        );
+    }
+    // 按 PID 从小到大排列，第一个是 initproc
+    assert!(procs[..count].windows(2).all(|w| w[0].pid < w[1].pid));
+    assert_eq!(procs[0].name(), "ch5b_initproc");
+    let me = procs[..count].iter().find(|info| info.pid == pid).unwrap();
+    assert_eq!(me.status, PROC_RUNNING);
+    assert_eq!(me.name(), "ch5b_ps");
+    for &child in children.iter() {
+        let info = procs[..count]
+            .iter()
+            .find(|info| info.pid == child)
+            .unwrap();
+        assert_eq!(info.ppid, pid);
+        assert_eq!(info.status, PROC_BLOCKED);
+        assert_eq!(info.name(), "ps_sleeper");
+        assert_eq!(kill(child as isize, SIGKILL), 0);
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(child, &mut exit_code), child as isize);
+    }
+    // 被回收的进程不再出现
+    let count = ps(&mut procs) as usize;
+    assert!(procs[..count]
+        .iter()
+        .all(|info| !children.contains(&info.pid)));
+    println!("Test ps OK!");
+    0
+}
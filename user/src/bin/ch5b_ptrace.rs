@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, ptrace, waitpid, wifstopped, wstopsig, PTRACE_CONT, PTRACE_PEEKDATA,
+    PTRACE_PEEKUSER, PTRACE_POKEDATA, PTRACE_POKEUSER, PTRACE_SINGLESTEP, PTRACE_TRACEME, SIGTRAP,
+};
+
+/// ptrace 测试：子进程请求被父进程跟踪后执行 ebreak 停下，父进程在 waitpid 中得知它停下，
+/// 读出它的 pc 与断点指令，把 pc 移过断点，单步执行几条指令，每次都重新停下且 pc 前进；
+/// 最后改写子进程中的变量并让它继续运行，子进程以这个变量为退出码退出。
+/// 访问不存在的寄存器与没有被跟踪的进程都会失败。
+/// 正确输出：
+/// Test ptrace OK!
+
+const EIO: isize = 5;
+const ESRCH: isize = 3;
+const STEPS: usize = 3;
+
+static mut FLAG: usize = 0;
+
+fn peek(request: usize, pid: usize, addr: usize) -> usize {
+    let mut value = 0usize;
+    assert_eq!(ptrace(request, pid, addr, &mut value as *mut _ as usize), 0);
+    value
+}
+
+fn wait_stop(pid: usize) {
+    let mut status = 0;
+    assert_eq!(waitpid(pid, &mut status), pid as isize);
+    assert!(wifstopped(status));
+    assert_eq!(wstopsig(status), SIGTRAP);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let flag = unsafe { core::ptr::addr_of_mut!(FLAG) };
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(ptrace(PTRACE_TRACEME, 0, 0, 0), 0);
+        unsafe { core::arch::asm!("ebreak") };
+        exit(unsafe { flag.read_volatile() } as i32);
+    }
+    let pid = pid as usize;
+    wait_stop(pid);
+    //停在断点上：pc 指向 ebreak（压缩指令时为 c.ebreak），跳过它继续执行
+    let pc = peek(PTRACE_PEEKUSER, pid, 0);
+    let insn = peek(PTRACE_PEEKDATA, pid, pc);
+    let len = if insn & 0b11 == 0b11 {
+        assert_eq!(insn as u32, 0x0010_0073);
+        4
+    } else {
+        assert_eq!(insn as u16, 0x9002);
+        2
+    };
+    assert_eq!(ptrace(PTRACE_POKEUSER, pid, 0, pc + len), 0);
+    assert_eq!(peek(PTRACE_PEEKUSER, pid, 0), pc + len);
+    assert_eq!(
+        ptrace(PTRACE_PEEKUSER, pid, 32, &mut 0usize as *mut _ as usize),
+        -EIO
+    );
+    assert_eq!(ptrace(PTRACE_CONT, getpid() as usize, 0, 0), -ESRCH);
+
+    let mut pc = pc + len;
+    for _ in 0..STEPS {
+        assert_eq!(ptrace(PTRACE_SINGLESTEP, pid, 0, 0), 0);
+        wait_stop(pid);
+        let next = peek(PTRACE_PEEKUSER, pid, 0);
+        assert_ne!(next, pc);
+        pc = next;
+    }
+
+    assert_eq!(peek(PTRACE_PEEKDATA, pid, flag as usize), 0);
+    assert_eq!(ptrace(PTRACE_POKEDATA, pid, flag as usize, 42), 0);
+    assert_eq!(ptrace(PTRACE_CONT, pid, 0, 0), 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(exit_code, 42);
+    println!("Test ptrace OK!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getppid, reboot, shutdown, sys_reboot, waitpid, LINUX_REBOOT_CMD_POWER_OFF,
+};
+
+/// 关机/重启权限测试：不是由 initproc 直接创建的进程调用 shutdown/reboot 时返回 -EPERM，
+/// 权限检查先于参数检查。
+/// 正确输出：
+/// Test reboot OK!
+
+const EPERM: isize = 1;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 测试程序通常由 shell 启动，此时它不是 initproc 的直接子进程
+    if getppid() == 0 {
+        println!("ch5b_reboot must not be started by initproc directly");
+        return -1;
+    }
+    assert_eq!(shutdown(), -EPERM);
+    assert_eq!(reboot(), -EPERM);
+    assert_eq!(sys_reboot(0, 0, LINUX_REBOOT_CMD_POWER_OFF), -EPERM);
+    // 子进程同样没有权限
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(shutdown(), -EPERM);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test reboot OK!");
+    0
+}
@@ -0,0 +1,149 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getrlimit, mmap, munmap, ps, setrlimit, sigaction, waitpid, ProcInfo,
+    Rlimit, SignalAction, RLIMIT_AS, RLIMIT_CPU, RLIMIT_NPROC, RLIM_INFINITY, SIGKILL, SIGXCPU,
+    SIG_IGN,
+};
+
+/// 资源限制测试：RLIMIT_NPROC 限制 fork，RLIMIT_AS 限制 mmap，
+/// 超过 RLIMIT_CPU 的软限制收到 SIGXCPU，超过硬限制收到 SIGKILL。
+/// 正确输出：
+/// Test rlimit OK!
+
+const EPERM: isize = -1;
+const EAGAIN: isize = -11;
+const ENOMEM: isize = -12;
+const EINVAL: isize = -22;
+
+fn spin_forever() -> ! {
+    loop {
+        get_time();
+    }
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn test_nproc() -> i32 {
+    let mut procs = [ProcInfo::empty(); 64];
+    let count = ps(&mut procs) as usize;
+    // 只允许再创建一个进程
+    assert_eq!(
+        setrlimit(
+            RLIMIT_NPROC,
+            &Rlimit {
+                cur: count + 1,
+                max: RLIM_INFINITY
+            }
+        ),
+        0
+    );
+    let pid = fork();
+    if pid == 0 {
+        // 子进程继承了限制
+        assert_eq!(fork(), EAGAIN);
+        exit(0);
+    }
+    assert!(pid > 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    0
+}
+
+fn test_as() -> i32 {
+    let start: usize = 0x10000000;
+    let len: usize = 4096;
+    assert_eq!(
+        setrlimit(
+            RLIMIT_AS,
+            &Rlimit {
+                cur: 0,
+                max: RLIM_INFINITY
+            }
+        ),
+        0
+    );
+    assert_eq!(mmap(start, len, 3), ENOMEM);
+    assert_eq!(
+        setrlimit(
+            RLIMIT_AS,
+            &Rlimit {
+                cur: RLIM_INFINITY,
+                max: RLIM_INFINITY
+            }
+        ),
+        0
+    );
+    assert_eq!(mmap(start, len, 3), 0);
+    assert_eq!(munmap(start, len), 0);
+    0
+}
+
+fn test_cpu_soft() -> i32 {
+    assert_eq!(
+        setrlimit(
+            RLIMIT_CPU,
+            &Rlimit {
+                cur: 1,
+                max: RLIM_INFINITY
+            }
+        ),
+        0
+    );
+    spin_forever()
+}
+
+fn test_cpu_hard() -> i32 {
+    let action = SignalAction {
+        handler: SIG_IGN,
+        mask: 0,
+    };
+    assert_eq!(sigaction(SIGXCPU, Some(&action), None), 0);
+    assert_eq!(setrlimit(RLIMIT_CPU, &Rlimit { cur: 1, max: 2 }), 0);
+    spin_forever()
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut limit = Rlimit::default();
+    assert_eq!(getrlimit(RLIMIT_NPROC, &mut limit), 0);
+    assert_eq!(limit.cur, RLIM_INFINITY);
+    assert_eq!(limit.max, RLIM_INFINITY);
+    // 不支持的资源
+    assert_eq!(getrlimit(1, &mut limit), EINVAL);
+    // 软限制不能超过硬限制，硬限制只能降低
+    assert_eq!(setrlimit(RLIMIT_CPU, &Rlimit { cur: 2, max: 1 }), EINVAL);
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(setrlimit(RLIMIT_CPU, &Rlimit { cur: 10, max: 10 }), 0);
+        assert_eq!(setrlimit(RLIMIT_CPU, &Rlimit { cur: 10, max: 20 }), EPERM);
+        assert_eq!(setrlimit(RLIMIT_CPU, &Rlimit { cur: 5, max: 5 }), 0);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // 子进程的修改不影响父进程
+    assert_eq!(getrlimit(RLIMIT_CPU, &mut limit), 0);
+    assert_eq!(limit.max, RLIM_INFINITY);
+
+    assert_eq!(run_child(test_nproc), 0);
+    assert_eq!(run_child(test_as), 0);
+    assert_eq!(run_child(test_cpu_soft), -SIGXCPU);
+    assert_eq!(run_child(test_cpu_hard), -SIGKILL);
+    println!("Test rlimit OK!");
+    0
+}
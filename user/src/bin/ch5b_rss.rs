@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, task_info, waitpid, TaskInfo};
+
+/// 常驻集测试：task_info 返回当前进程占有页帧的用户页面数及其最大值。写 mmap 的页面之后常驻集增大，
+/// 只读过的页面映射到零页，不计入常驻集；解除映射后常驻集恢复，最大值保持不变。
+/// fork 出的子进程从父进程当前的常驻集开始记录。
+/// 正确输出：
+/// Test rss OK!
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x7600_0000;
+const PAGES: usize = 64;
+const LEN: usize = PAGES * PAGE_SIZE;
+
+fn info() -> TaskInfo {
+    let info = TaskInfo::new();
+    assert_eq!(task_info(&info), 0);
+    info
+}
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE_SIZE) as *mut usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = info();
+    assert!(start.rss > 0);
+    assert!(start.peak_rss >= start.rss);
+
+    // 只读过的页面不占用页帧
+    assert_eq!(mmap(START, LEN, 3), 0);
+    for i in 0..PAGES {
+        assert_eq!(unsafe { page(i).read_volatile() }, 0);
+    }
+    assert_eq!(info().rss, start.rss);
+
+    for i in 0..PAGES {
+        unsafe { page(i).write_volatile(i) };
+    }
+    let written = info();
+    assert_eq!(written.rss, start.rss + PAGES);
+    assert!(written.peak_rss >= written.rss);
+
+    assert_eq!(munmap(START, LEN), 0);
+    let unmapped = info();
+    assert_eq!(unmapped.rss, start.rss);
+    assert_eq!(unmapped.peak_rss, written.peak_rss);
+
+    // 子进程的最大值从 fork 时的常驻集开始
+    let pid = fork();
+    if pid == 0 {
+        let child = info();
+        assert_eq!(child.rss, unmapped.rss);
+        assert!(child.peak_rss < unmapped.peak_rss);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("Test rss OK!");
+    0
+}
@@ -0,0 +1,83 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, get_time, getrlimit, sched_getscheduler, sched_setscheduler, setrlimit, sleep_blocking, wait,
+    Rlimit, RLIMIT_RTPRIO, SCHED_FIFO, SCHED_OTHER, SCHED_RR,
+};
+
+/// 实时调度类测试：
+/// 1. SCHED_FIFO 任务持续运行时，普通任务得不到 CPU；
+/// 2. 相同实时优先级的 SCHED_RR 任务之间按时间片轮转；
+/// 3. 没有特权的进程只能把实时优先级提高到 RLIMIT_RTPRIO 的软限制，默认为 0。
+/// 正确输出：
+/// Test rt sched OK!
+
+const SPIN_MS: isize = 300;
+const EPERM: isize = -1;
+const EINVAL: isize = -22;
+
+fn spin(ms: isize) {
+    let start = get_time();
+    while get_time() - start < ms {}
+}
+
+fn fifo_starves_normal() {
+    // 子进程是普通任务，睡眠结束后立即退出，退出码为它醒来后第一次得到 CPU 的时刻
+    if fork() == 0 {
+        sleep_blocking(SPIN_MS as usize / 6);
+        exit(get_time() as i32);
+    }
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 10), 0);
+    assert_eq!(sched_getscheduler(0), SCHED_FIFO as isize);
+    spin(SPIN_MS);
+    let end = get_time();
+    assert_eq!(sched_setscheduler(0, SCHED_OTHER, 0), 0);
+    let mut started: i32 = 0;
+    assert!(wait(&mut started) > 0);
+    assert!(started as isize >= end);
+}
+
+fn rr_rotates() {
+    assert_eq!(sched_setscheduler(0, SCHED_RR, 20), 0);
+    // 子进程继承 SCHED_RR 与实时优先级
+    for _ in 0..2 {
+        if fork() == 0 {
+            let start = get_time();
+            spin(SPIN_MS);
+            exit(start as i32);
+        }
+    }
+    let mut first: i32 = 0;
+    let mut second: i32 = 0;
+    assert!(wait(&mut first) > 0);
+    assert!(wait(&mut second) > 0);
+    // 若不轮转，第二个子进程要等第一个运行完 SPIN_MS 才能开始
+    assert!(((first - second).abs() as isize) < SPIN_MS / 2);
+    assert_eq!(sched_setscheduler(0, SCHED_OTHER, 0), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 非法的参数
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 0), EINVAL);
+    assert_eq!(sched_setscheduler(0, SCHED_OTHER, 1), EINVAL);
+    assert_eq!(sched_setscheduler(0, 3, 1), EINVAL);
+    // 测试程序由 shell 启动，没有特权，需要先在硬限制内提高 RLIMIT_RTPRIO 的软限制
+    let mut limit = Rlimit { cur: 0, max: 0 };
+    assert_eq!(getrlimit(RLIMIT_RTPRIO, &mut limit), 0);
+    assert_eq!(limit.cur, 0);
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 1), EPERM);
+    limit.cur = 10;
+    assert_eq!(setrlimit(RLIMIT_RTPRIO, &limit), 0);
+    assert_eq!(sched_setscheduler(0, SCHED_FIFO, 11), EPERM);
+    limit.cur = 20;
+    assert_eq!(setrlimit(RLIMIT_RTPRIO, &limit), 0);
+    fifo_starves_normal();
+    rr_rotates();
+    println!("Test rt sched OK!");
+    0
+}
@@ -0,0 +1,88 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{brk, exit, fork, sbrk, waitpid};
+
+/// 用户堆测试：sbrk 扩大堆后新增的内存可读写且初始为 0，缩小后回收的页面不能再访问；
+/// fork 出的子进程得到一份堆的拷贝，对堆的修改与调整互不影响；
+/// 堆不能缩小到起始地址之下，也不能超出物理内存。
+/// 正确输出：
+/// Test sbrk OK!
+
+const EINVAL: isize = -22;
+const ENOMEM: isize = -12;
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const HEAP_LEN: usize = 3 * PAGE_SIZE + 100;
+
+fn run_child(f: fn(usize) -> i32, origin: usize) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f(origin));
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn heap(origin: usize) -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(origin as *mut u8, HEAP_LEN) }
+}
+
+fn child_copy(origin: usize) -> i32 {
+    if sbrk(0) != (origin + HEAP_LEN) as isize {
+        return -1;
+    }
+    for (i, byte) in heap(origin).iter_mut().enumerate() {
+        if *byte != i as u8 {
+            return -1;
+        }
+        *byte = 0;
+    }
+    assert_eq!(brk(origin), 0);
+    0
+}
+
+fn touch_released(origin: usize) -> i32 {
+    assert_eq!(brk(origin + PAGE_SIZE), 0);
+    heap(origin)[PAGE_SIZE - 1] = 1;
+    unsafe { ((origin + PAGE_SIZE) as *mut u8).write_volatile(1) };
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let origin = sbrk(0);
+    assert!(origin > 0);
+    let origin = origin as usize;
+    // 不能缩小到堆的起始地址之下
+    assert_eq!(sbrk(-1), EINVAL);
+    assert_eq!(sbrk(i32::MAX), ENOMEM);
+    assert_eq!(sbrk(0), origin as isize);
+
+    assert_eq!(sbrk(HEAP_LEN as i32), origin as isize);
+    assert_eq!(sbrk(0), (origin + HEAP_LEN) as isize);
+    for (i, byte) in heap(origin).iter_mut().enumerate() {
+        assert_eq!(*byte, 0);
+        *byte = i as u8;
+    }
+
+    assert_eq!(run_child(child_copy, origin), 0);
+    assert_eq!(sbrk(0), (origin + HEAP_LEN) as isize);
+    for (i, byte) in heap(origin).iter().enumerate() {
+        assert_eq!(*byte, i as u8);
+    }
+    assert_eq!(run_child(touch_released, origin), -SIGSEGV);
+
+    // 缩小后再扩大，重新分配的页面内容为 0
+    assert_eq!(sbrk(-(HEAP_LEN as i32)), (origin + HEAP_LEN) as isize);
+    assert_eq!(sbrk(PAGE_SIZE as i32), origin as isize);
+    assert!(heap(origin)[..PAGE_SIZE].iter().all(|byte| *byte == 0));
+    assert_eq!(brk(origin), 0);
+    assert_eq!(sbrk(0), origin as isize);
+    println!("Test sbrk OK!");
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{exit, fork, mmap_with_flags, set_priority, wait, yield_, MAP_SHARED};
+
+/// stride 调度顺序测试：两个任务在共享内存中依次记下自己每次被调度到的顺序。
+/// 就绪队列总是取出 pass 最小的任务，低优先级任务的步长是高优先级任务的 4 倍，
+/// 因此它每运行一次之后至少轮到高优先级任务一次，不会连续运行两次，
+/// 总的运行次数也大致为 1:4。
+/// 正确输出：
+/// Test sched order OK!
+
+const PAGE_SIZE: usize = 4096;
+const SHARED: usize = 0x6a00_0000;
+const ROUNDS: usize = 200;
+const HIGH: usize = 1;
+const LOW: usize = 2;
+const PRIORITIES: [(usize, isize); 2] = [(LOW, 2), (HIGH, 8)];
+
+/// 0 号槽为已经记录的次数，1 号槽为已经设置好优先级的任务数，之后是调度记录
+fn slot(i: usize) -> &'static AtomicUsize {
+    unsafe { &*((SHARED + i * core::mem::size_of::<usize>()) as *const AtomicUsize) }
+}
+
+fn record(tag: usize) -> ! {
+    slot(1).fetch_add(1, Ordering::SeqCst);
+    while slot(1).load(Ordering::SeqCst) < PRIORITIES.len() {
+        yield_();
+    }
+    loop {
+        yield_();
+        let idx = slot(0).fetch_add(1, Ordering::SeqCst);
+        if idx >= ROUNDS {
+            exit(0);
+        }
+        slot(2 + idx).store(tag, Ordering::SeqCst);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap_with_flags(SHARED, PAGE_SIZE, 3, MAP_SHARED), 0);
+    for &(tag, prio) in PRIORITIES.iter() {
+        if fork() == 0 {
+            assert_eq!(set_priority(prio), prio);
+            record(tag);
+        }
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..PRIORITIES.len() {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    let mut counts = [0usize; 3];
+    let mut last = 0;
+    for i in 0..ROUNDS {
+        let tag = slot(2 + i).load(Ordering::SeqCst);
+        assert!(tag == HIGH || tag == LOW);
+        assert!(!(tag == LOW && last == LOW));
+        counts[tag] += 1;
+        last = tag;
+    }
+    println!("sched order: high {} low {}", counts[HIGH], counts[LOW]);
+    assert!(counts[LOW] > 0);
+    assert!(counts[HIGH] >= counts[LOW] * 2);
+    println!("Test sched order OK!");
+    0
+}
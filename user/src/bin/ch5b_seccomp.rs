@@ -0,0 +1,131 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, getpid, getppid, seccomp, thread_create, waitpid, waittid, yield_,
+    SECCOMP_BITMAP_BYTES, SECCOMP_MODE_ERRNO, SECCOMP_MODE_KILL, SYSCALL_FORK, SYSCALL_GETPID,
+    SYSCALL_GETPPID, SYSCALL_SECCOMP, SYSCALL_THREAD_CREATE, SYSCALL_WAIT4, SYSCALL_WAITTID,
+    SYSCALL_WRITE, SYSCALL_YIELD,
+};
+
+/// 系统调用过滤测试：安装过滤器之后不在允许集合中的系统调用返回 -EPERM，或者以 SIGSYS 终止进程；
+/// 再次安装只能进一步收紧，过滤器对进程中的所有线程生效并被子进程继承，exit 总是允许。
+/// 正确输出：
+/// Test seccomp OK!
+
+const EPERM: isize = -1;
+const EINVAL: isize = -22;
+const SIGSYS: i32 = 31;
+/// 子进程中用到的系统调用
+const BASE: [usize; 5] = [
+    SYSCALL_WRITE,
+    SYSCALL_YIELD,
+    SYSCALL_FORK,
+    SYSCALL_WAIT4,
+    SYSCALL_SECCOMP,
+];
+
+fn bitmap(ids: &[usize]) -> [u8; SECCOMP_BITMAP_BYTES] {
+    let mut bitmap = [0u8; SECCOMP_BITMAP_BYTES];
+    for &id in BASE.iter().chain(ids.iter()) {
+        bitmap[id / 8] |= 1 << (id % 8);
+    }
+    bitmap
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn errno_mode() -> i32 {
+    assert_eq!(seccomp(SECCOMP_MODE_ERRNO, &bitmap(&[SYSCALL_GETPID])), 0);
+    assert!(getpid() > 0);
+    assert_eq!(getppid(), EPERM);
+    // 新的过滤器允许 getppid 也不能放宽，只会再去掉 getpid
+    assert_eq!(seccomp(SECCOMP_MODE_ERRNO, &bitmap(&[SYSCALL_GETPPID])), 0);
+    assert_eq!(getppid(), EPERM);
+    assert_eq!(getpid(), EPERM);
+    // 子进程继承过滤器
+    assert_eq!(
+        run_child(|| {
+            assert_eq!(getpid(), EPERM);
+            assert_eq!(getppid(), EPERM);
+            0
+        }),
+        0
+    );
+    0
+}
+
+fn kill_mode() -> i32 {
+    assert_eq!(seccomp(SECCOMP_MODE_KILL, &bitmap(&[])), 0);
+    getpid();
+    0
+}
+
+fn installer() -> ! {
+    exit(seccomp(SECCOMP_MODE_ERRNO, &bitmap(&[SYSCALL_WAITTID])) as i32)
+}
+
+//由另一个线程安装的过滤器同样限制主线程
+fn thread_mode() -> i32 {
+    let tid = thread_create(installer as usize, 0);
+    assert!(tid > 0);
+    assert_eq!(waittid(tid as usize), 0);
+    assert_eq!(getpid(), EPERM);
+    0
+}
+
+fn killed_thread() -> ! {
+    getppid();
+    exit(0)
+}
+
+//普通线程被过滤器终止时整个进程随之终止
+fn thread_kill_mode() -> i32 {
+    assert_eq!(
+        seccomp(SECCOMP_MODE_KILL, &bitmap(&[SYSCALL_THREAD_CREATE])),
+        0
+    );
+    assert!(thread_create(killed_thread as usize, 0) > 0);
+    loop {
+        yield_();
+    }
+}
+
+//先安装返回 -EPERM 的过滤器，再叠加一个终止进程的过滤器
+fn kill_after_errno() -> i32 {
+    assert_eq!(seccomp(SECCOMP_MODE_ERRNO, &bitmap(&[SYSCALL_GETPID])), 0);
+    assert_eq!(getppid(), EPERM);
+    assert_eq!(seccomp(SECCOMP_MODE_KILL, &bitmap(&[SYSCALL_GETPID])), 0);
+    assert!(getpid() > 0);
+    getppid();
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(seccomp(2, &bitmap(&[])), EINVAL);
+    assert_eq!(
+        seccomp(SECCOMP_MODE_ERRNO, &[0; SECCOMP_BITMAP_BYTES + 1]),
+        EINVAL
+    );
+    assert_eq!(run_child(errno_mode), 0);
+    assert_eq!(run_child(thread_mode), 0);
+    assert_eq!(run_child(kill_mode), -SIGSYS);
+    assert_eq!(run_child(thread_kill_mode), -SIGSYS);
+    assert_eq!(run_child(kill_after_errno), -SIGSYS);
+    // 父进程不受子进程的过滤器影响
+    assert!(getppid() >= 0);
+    println!("Test seccomp OK!");
+    0
+}
@@ -0,0 +1,106 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, shmat, shmctl, shmdt, shmget, waitpid, ShmStat, IPC_CREAT, IPC_EXCL, IPC_PRIVATE,
+    IPC_RMID, IPC_STAT, SHM_RDONLY,
+};
+
+/// System V 共享内存测试：按键查找与创建共享内存段，多次映射与 fork 出的子进程看到同一份内存，
+/// nattch 随映射与解除映射变化；只读映射不能写；段被删除后不能再查找或映射，已有的映射仍然有效。
+/// 正确输出：
+/// Test shm OK!
+
+const ENOENT: isize = 2;
+const EEXIST: isize = 17;
+const EINVAL: isize = 22;
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const KEY: usize = 0x5348;
+
+fn word(addr: usize, page: usize) -> *mut usize {
+    (addr + page * PAGE_SIZE) as *mut usize
+}
+
+fn nattch(id: usize) -> usize {
+    let mut stat = ShmStat::default();
+    assert_eq!(shmctl(id, IPC_STAT, &mut stat), 0);
+    assert_eq!(stat.size, 2 * PAGE_SIZE);
+    stat.nattch
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(shmget(KEY, PAGE_SIZE, 0), -ENOENT);
+    let id = shmget(KEY, 2 * PAGE_SIZE, IPC_CREAT);
+    assert!(id > 0);
+    let id = id as usize;
+    assert_eq!(shmget(KEY, PAGE_SIZE, 0), id as isize);
+    assert_eq!(shmget(KEY, PAGE_SIZE, IPC_CREAT), id as isize);
+    assert_eq!(shmget(KEY, 3 * PAGE_SIZE, 0), -EINVAL);
+    assert_eq!(shmget(KEY, PAGE_SIZE, IPC_CREAT | IPC_EXCL), -EEXIST);
+    let other = shmget(IPC_PRIVATE, PAGE_SIZE, 0);
+    assert!(other > 0 && other as usize != id);
+    assert_eq!(shmctl(other as usize, IPC_RMID, &mut ShmStat::default()), 0);
+    assert_eq!(nattch(id), 0);
+
+    // 同一个段映射两次，两处看到同一份内存
+    let a = shmat(id, 0, 0);
+    assert!(a > 0);
+    let a = a as usize;
+    let b = shmat(id, 0, 0) as usize;
+    assert_ne!(a, b);
+    assert_eq!(nattch(id), 2);
+    unsafe {
+        assert_eq!(word(a, 1).read_volatile(), 0);
+        word(a, 1).write_volatile(42);
+        assert_eq!(word(b, 1).read_volatile(), 42);
+    }
+    assert_eq!(shmat(id, a, 0), -EINVAL);
+    assert_eq!(shmat(id, a + 1, 0), -EINVAL);
+
+    // 子进程继承映射，它的修改父进程可见
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(nattch(id), 4);
+        unsafe { word(b, 0).write_volatile(7) };
+        assert_eq!(shmdt(a), 0);
+        assert_eq!(shmdt(a), -EINVAL);
+        assert_eq!(nattch(id), 3);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(nattch(id), 2);
+    assert_eq!(unsafe { word(a, 0).read_volatile() }, 7);
+
+    // 只读映射
+    let pid = fork();
+    if pid == 0 {
+        let r = shmat(id, 0, SHM_RDONLY) as usize;
+        assert_eq!(unsafe { word(r, 1).read_volatile() }, 42);
+        unsafe { word(r, 1).write_volatile(0) };
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -SIGSEGV);
+
+    // 删除之后不能再查找或映射，已有的映射在解除之前仍然有效
+    assert_eq!(shmctl(id, IPC_RMID, &mut ShmStat::default()), 0);
+    assert_eq!(shmget(KEY, PAGE_SIZE, 0), -ENOENT);
+    assert_eq!(shmat(id, 0, 0), -EINVAL);
+    assert_eq!(shmctl(id, IPC_STAT, &mut ShmStat::default()), -EINVAL);
+    unsafe {
+        word(b, 0).write_volatile(8);
+        assert_eq!(word(a, 0).read_volatile(), 8);
+    }
+    assert_eq!(shmdt(a), 0);
+    assert_eq!(shmdt(b), 0);
+    assert_eq!(shmdt(b), -EINVAL);
+    println!("Test shm OK!");
+    0
+}
@@ -0,0 +1,93 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    exit, fork, getpid, kill, sigaction, sigprocmask, wait, SignalAction, SIGKILL, SIGSEGV,
+    SIGUSR1, SIG_DFL, SIG_IGN,
+};
+
+/// 信号处理函数测试：
+/// 1. 处理函数在 kill 返回前运行，返回后被打断的执行流继续；
+/// 2. 被屏蔽的信号在解除屏蔽后才被处理；
+/// 3. 访存错误以 SIGSEGV 的形式交给处理函数。
+/// 正确输出：
+/// Test sigaction OK!
+
+static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn usr1_handler(signum: i32) {
+    assert_eq!(signum, SIGUSR1);
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+extern "C" fn segv_handler(signum: i32) {
+    assert_eq!(signum, SIGSEGV);
+    // 返回会再次执行出错的指令，只能退出
+    exit(SIGSEGV);
+}
+
+fn handled() -> usize {
+    HANDLED.load(Ordering::SeqCst)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let action = SignalAction {
+        handler: usr1_handler as usize,
+        mask: 0,
+    };
+    let mut old_action = SignalAction::default();
+    assert_eq!(sigaction(SIGUSR1, Some(&action), Some(&mut old_action)), 0);
+    assert_eq!(old_action.handler, SIG_DFL);
+    // SIGKILL 不能被捕获
+    assert_eq!(sigaction(SIGKILL, Some(&action), None), -1);
+
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(handled(), 1);
+
+    assert_eq!(sigprocmask(1 << SIGUSR1), 0);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(handled(), 1);
+    assert_eq!(sigprocmask(0), 1 << SIGUSR1);
+    assert_eq!(handled(), 2);
+
+    let ignore = SignalAction {
+        handler: SIG_IGN,
+        mask: 0,
+    };
+    assert_eq!(sigaction(SIGUSR1, Some(&ignore), None), 0);
+    assert_eq!(kill(pid, SIGUSR1), 0);
+    assert_eq!(handled(), 2);
+
+    let mut exit_code: i32 = 0;
+    // 注册了 SIGSEGV 处理函数的子进程
+    if fork() == 0 {
+        let segv = SignalAction {
+            handler: segv_handler as usize,
+            mask: 0,
+        };
+        sigaction(SIGSEGV, Some(&segv), None);
+        unsafe {
+            (0 as *mut u8).write_volatile(0);
+        }
+        exit(0);
+    }
+    assert!(wait(&mut exit_code) > 0);
+    assert_eq!(exit_code, SIGSEGV);
+    // 没有处理函数时被 SIGSEGV 终止
+    if fork() == 0 {
+        unsafe {
+            (0 as *mut u8).write_volatile(0);
+        }
+        exit(0);
+    }
+    assert!(wait(&mut exit_code) > 0);
+    assert_eq!(exit_code, -SIGSEGV);
+    println!("Test sigaction OK!");
+    0
+}
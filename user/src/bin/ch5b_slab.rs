@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, slab_stats, waitpid, SlabInfo};
+
+/// slab 分配器测试：任务控制块和进程控制块有各自的对象缓存，
+/// 反复 fork/exit 时对象被重复使用，缓存不再申请新的 slab，回收子进程后正在使用的对象数恢复原状。
+/// 正确输出：
+/// Test slab OK!
+
+const ROUND: usize = 32;
+
+fn cache(name: &str) -> SlabInfo {
+    let mut infos = [
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+        SlabInfo::empty(),
+    ];
+    let count = slab_stats(&mut infos);
+    assert!(count > 0);
+    let idx = infos[..count as usize]
+        .iter()
+        .position(|info| info.name() == name)
+        .unwrap();
+    core::mem::replace(&mut infos[idx], SlabInfo::empty())
+}
+
+fn fork_round() {
+    for _ in 0..ROUND {
+        let pid = fork();
+        if pid == 0 {
+            exit(0);
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 第一轮之后缓存中已经有足够的对象
+    fork_round();
+    for name in ["TaskControlBlock", "ProcessControlBlock"].iter() {
+        let before = cache(name);
+        assert!(before.in_use > 0);
+        assert!(before.object_size > 0 && before.objects_per_slab > 0);
+        fork_round();
+        let after = cache(name);
+        assert!(after.allocs >= before.allocs + ROUND);
+        assert_eq!(after.slabs, before.slabs);
+        assert_eq!(after.in_use, before.in_use);
+    }
+    println!("Test slab OK!");
+    0
+}
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, sleep_blocking, wait};
+
+/// 睡眠队列测试：多个子进程以不同时长阻塞睡眠，
+/// 睡眠时长越短的进程应当越早被时钟中断唤醒并退出。
+/// 正确输出：
+/// Test sleep queue OK!
+
+const SLEEP_MS: [usize; 4] = [400, 100, 300, 200];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0isize; SLEEP_MS.len()];
+    for (i, &ms) in SLEEP_MS.iter().enumerate() {
+        let pid = fork();
+        if pid == 0 {
+            let start = get_time();
+            sleep_blocking(ms);
+            // 不能早于预定时刻醒来
+            assert!(get_time() - start >= ms as isize);
+            exit(i as i32);
+        }
+        pids[i] = pid;
+    }
+    let mut last_ms = 0;
+    let mut exit_code: i32 = 0;
+    for _ in 0..SLEEP_MS.len() {
+        let pid = wait(&mut exit_code);
+        assert_eq!(pid, pids[exit_code as usize]);
+        let ms = SLEEP_MS[exit_code as usize];
+        assert!(ms > last_ms);
+        last_ms = ms;
+    }
+    println!("Test sleep queue OK!");
+    0
+}
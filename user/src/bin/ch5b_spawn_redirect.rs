@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, pipe, read, spawn_redirect, waitpid, SpawnRedirect};
+
+/// spawn 重定向测试：把子进程的标准输出重定向到管道写端，父进程从管道读端读到子进程的全部输出；
+/// 父进程中不存在的文件描述符返回 -EBADF，重定向表过长返回 -EINVAL。
+/// 正确输出：
+/// Test spawn redirect OK!
+
+const EBADF: isize = -9;
+const EINVAL: isize = -22;
+const HELLO: &[u8] = b"Hello, world from user mode program!\n";
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let args = [core::ptr::null::<u8>()];
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let (rfd, wfd) = (fds[0], fds[1]);
+
+    let redirects = [SpawnRedirect {
+        child_fd: 1,
+        parent_fd: wfd,
+    }];
+    let pid = spawn_redirect("ch2b_hello_world\0", &args, &redirects);
+    assert!(pid > 0);
+    // 关闭父进程的写端，子进程退出后读端读到文件末尾
+    assert_eq!(close(wfd), 0);
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    loop {
+        let n = read(rfd, &mut buf[len..]);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    assert_eq!(&buf[..len], HELLO);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(close(rfd), 0);
+
+    // 父进程中不存在的文件描述符
+    let bad = [SpawnRedirect {
+        child_fd: 1,
+        parent_fd: 64,
+    }];
+    assert_eq!(spawn_redirect("ch2b_hello_world\0", &args, &bad), EBADF);
+    // 子进程的文件描述符超出上限
+    let bad = [SpawnRedirect {
+        child_fd: 256,
+        parent_fd: 1,
+    }];
+    assert_eq!(spawn_redirect("ch2b_hello_world\0", &args, &bad), EBADF);
+    // 重定向表过长
+    let many = [SpawnRedirect {
+        child_fd: 1,
+        parent_fd: 1,
+    }; 257];
+    assert_eq!(spawn_redirect("ch2b_hello_world\0", &args, &many), EINVAL);
+    println!("Test spawn redirect OK!");
+    0
+}
@@ -0,0 +1,100 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::MaybeUninit;
+use user_lib::{
+    exit, fork, getrlimit, setrlimit, thread_create, waitpid, waittid, Rlimit, RLIMIT_STACK,
+    RLIM_INFINITY,
+};
+
+/// 用户栈自动增长测试：主线程与其他线程的深度递归都能超出初始映射的用户栈；
+/// 内核也可以直接写入尚未增长到的栈空间；超出 RLIMIT_STACK 或整个预留区域时进程收到 SIGSEGV。
+/// 正确输出：
+/// Test stack grow OK!
+
+const SIGSEGV: i32 = 11;
+// 每层递归占用至少 1 KiB 栈空间
+const FRAME: usize = 1024;
+
+fn recurse(depth: usize) -> usize {
+    let mut buf = [0u8; FRAME];
+    for (i, byte) in buf.iter_mut().enumerate() {
+        unsafe { (byte as *mut u8).write_volatile((depth + i) as u8) };
+    }
+    let below = if depth == 0 { 0 } else { recurse(depth - 1) };
+    below + unsafe { (&buf[depth % FRAME] as *const u8).read_volatile() } as usize
+}
+
+fn expected(depth: usize) -> usize {
+    (0..=depth).map(|d| ((d + d % FRAME) as u8) as usize).sum()
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+// 在一个很大的栈帧的最低处让内核写入数据，用户态此前从未访问过这段栈
+#[inline(never)]
+fn kernel_writes_far_below() -> usize {
+    let mut buf: MaybeUninit<[Rlimit; 4096]> = MaybeUninit::uninit();
+    let lowest = unsafe { &mut (*buf.as_mut_ptr())[0] };
+    assert_eq!(getrlimit(RLIMIT_STACK, lowest), 0);
+    lowest.cur
+}
+
+fn limited() -> i32 {
+    let limit = Rlimit {
+        cur: 64 * FRAME,
+        max: RLIM_INFINITY,
+    };
+    assert_eq!(setrlimit(RLIMIT_STACK, &limit), 0);
+    assert_eq!(recurse(32), expected(32));
+    recurse(128);
+    0
+}
+
+fn beyond_reserve() -> i32 {
+    recurse(4096);
+    0
+}
+
+fn thread_main(depth: usize) -> ! {
+    exit((recurse(depth) == expected(depth)) as i32)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut limit = Rlimit::default();
+    assert_eq!(getrlimit(RLIMIT_STACK, &mut limit), 0);
+    assert!(limit.cur >= 256 * FRAME);
+    // 在栈增长之前检查上限，子进程会继承父进程已经增长的栈
+    assert_eq!(run_child(limited), -SIGSEGV);
+    assert_eq!(run_child(beyond_reserve), -SIGSEGV);
+
+    assert_eq!(recurse(256), expected(256));
+    assert_eq!(kernel_writes_far_below(), limit.cur);
+
+    // fork 出的子进程得到已增长的栈的拷贝，并可以继续增长
+    let pid = fork();
+    if pid == 0 {
+        exit((recurse(512) == expected(512)) as i32);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 1);
+
+    let tid = thread_create(thread_main as usize, 256);
+    assert!(tid > 0);
+    assert_eq!(waittid(tid as usize), 1);
+    println!("Test stack grow OK!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    fstat, lseek, syscall, Stat, StatMode, SEEK_CUR, SEEK_SET, STDIN, STDOUT, SYSCALL_FSTAT,
+};
+
+/// 标准输入输出的文件接口测试：fstat 报告标准输入、标准输出与标准错误输出都是字符设备；
+/// 它们不支持 lseek，返回 -ESPIPE；对未打开的文件描述符返回 -EBADF。
+/// 正确输出：
+/// Test stdio stat OK!
+
+const STDERR: usize = 2;
+const EBADF: isize = -9;
+const EFAULT: isize = -14;
+const ESPIPE: isize = -29;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for &fd in [STDIN, STDOUT, STDERR].iter() {
+        let stat = Stat::new();
+        assert_eq!(fstat(fd, &stat), 0);
+        assert_eq!(stat.mode, StatMode::CHR);
+        assert_eq!(stat.nlink, 1);
+        assert_eq!(lseek(fd, 0, SEEK_SET), ESPIPE);
+        assert_eq!(lseek(fd, 1, SEEK_CUR), ESPIPE);
+    }
+    let stat = Stat::new();
+    assert_eq!(fstat(3, &stat), EBADF);
+    assert_eq!(lseek(3, 0, SEEK_SET), EBADF);
+    assert_eq!(syscall(SYSCALL_FSTAT, [STDOUT, 0, 0]), EFAULT);
+    println!("Test stdio stat OK!");
+    0
+}
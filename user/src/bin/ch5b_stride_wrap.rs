@@ -0,0 +1,57 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, set_priority, wait};
+
+/// stride 回绕回归测试：低优先级任务的步长接近 BIG_STRIDE / 2，
+/// 运行数秒后 pass 会多次回绕，此时各任务仍应按优先级比例获得 CPU。
+/// 正确输出：
+/// Test stride wrap OK!
+
+const PRIORITIES: [isize; 4] = [2, 3, 4, 5];
+const MAX_TIME: isize = 2000;
+
+fn spin_delay() {
+    let mut j = true;
+    for _ in 0..10 {
+        j = !j;
+    }
+}
+
+fn count_during(prio: isize) -> isize {
+    set_priority(prio);
+    let start_time = get_time();
+    let mut acc = 0;
+    loop {
+        spin_delay();
+        acc += 1;
+        if acc % 400 == 0 && get_time() - start_time > MAX_TIME {
+            return acc;
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    for &prio in PRIORITIES.iter() {
+        if fork() == 0 {
+            // 退出码只有 32 位，缩小后再返回
+            exit((count_during(prio) / prio) as i32);
+        }
+    }
+    let mut ratios = [0i32; PRIORITIES.len()];
+    for ratio in ratios.iter_mut() {
+        assert!(wait(ratio) > 0);
+        // 任何一个任务都不应被饿死
+        assert!(*ratio > 0);
+    }
+    let max = *ratios.iter().max().unwrap();
+    let min = *ratios.iter().min().unwrap();
+    println!("stride wrap ratios = {:?}", ratios);
+    assert!(max < min * 2);
+    println!("Test stride wrap OK!");
+    0
+}
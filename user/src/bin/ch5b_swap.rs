@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap};
+
+/// 交换测试：mmap 一段大于物理内存的区域并写满其中的每一个页面，
+/// 物理内存耗尽后页面被换出到交换设备，再次访问时换入，读出的内容与写入的一致。
+/// 需要 QEMU 挂载交换设备（make run 会自动创建）。
+/// 正确输出：
+/// Test swap OK!
+
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x4000_0000;
+// 136 MiB，超过 QEMU 的 128 MiB 物理内存，但不超过物理内存与交换区之和
+const LEN: usize = 136 << 20;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE_SIZE) as *mut usize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, LEN, 3), 0);
+    for i in 0..LEN / PAGE_SIZE {
+        unsafe { page(i).write_volatile(i) };
+    }
+    // 两遍检查，第二遍时前半部分的页面已经被换出又换入过一次
+    for _ in 0..2 {
+        for i in 0..LEN / PAGE_SIZE {
+            assert_eq!(unsafe { page(i).read_volatile() }, i);
+        }
+    }
+    assert_eq!(munmap(START, LEN), 0);
+    println!("Test swap OK!");
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{get_time, mmap, munmap, swap_stats, SwapStats};
+
+/// 页面置换统计测试：时钟中断定时收集访问位，读写过的页面计入访问位和脏位的统计；
+/// 在大于物理内存的区域中顺序写入时，反复访问的热点页面不会被选为牺牲页，
+/// 之后读取热点页面不需要换入。需要 QEMU 挂载交换设备（make run 会自动创建）。
+/// 正确输出：
+/// Test swap stats OK!
+
+const PAGE_SIZE: usize = 4096;
+const HOT: usize = 0x3000_0000;
+const HOT_PAGES: usize = 16;
+const START: usize = 0x4000_0000;
+// 136 MiB，超过 QEMU 的 128 MiB 物理内存
+const LEN: usize = 136 << 20;
+
+fn hot(i: usize) -> *mut usize {
+    (HOT + i * PAGE_SIZE) as *mut usize
+}
+
+fn stats() -> SwapStats {
+    let mut stats = SwapStats::default();
+    assert_eq!(swap_stats(&mut stats), 0);
+    stats
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(HOT, HOT_PAGES * PAGE_SIZE, 3), 0);
+    let before = stats();
+    // 访问热点页面直到访问位被收集了至少两次
+    let start = get_time();
+    while stats().harvests < before.harvests + 2 {
+        for i in 0..HOT_PAGES {
+            unsafe { hot(i).write_volatile(i) };
+        }
+        assert!(get_time() - start < 5000);
+    }
+    let after = stats();
+    assert!(after.scanned >= before.scanned + HOT_PAGES);
+    assert!(after.accessed >= before.accessed + HOT_PAGES);
+    assert!(after.dirty >= before.dirty + HOT_PAGES);
+
+    // 顺序写入大区域，每写一页都访问一遍热点页面
+    assert_eq!(mmap(START, LEN, 3), 0);
+    for page in 0..LEN / PAGE_SIZE {
+        unsafe { ((START + page * PAGE_SIZE) as *mut usize).write_volatile(page) };
+        for i in 0..HOT_PAGES {
+            assert_eq!(unsafe { hot(i).read_volatile() }, i);
+        }
+    }
+    let pressed = stats();
+    assert!(pressed.swap_outs > after.swap_outs);
+    assert!(pressed.swapped > 0);
+    for i in 0..HOT_PAGES {
+        assert_eq!(unsafe { hot(i).read_volatile() }, i);
+    }
+    assert_eq!(stats().swap_ins, pressed.swap_ins);
+
+    // 被换出的页面读回时换入
+    assert_eq!(unsafe { (START as *const usize).read_volatile() }, 0);
+    assert!(stats().swap_ins > pressed.swap_ins);
+    assert_eq!(munmap(START, LEN), 0);
+    assert_eq!(munmap(HOT, HOT_PAGES * PAGE_SIZE), 0);
+    println!("Test swap stats OK!");
+    0
+}
@@ -0,0 +1,71 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, sleep_blocking, switch_trace, wait, yield_, SwitchRecord};
+
+/// 任务切换跟踪：运行几个行为不同的子进程，然后导出并打印内核记录的任务切换序列。
+/// 每行格式为：时刻(us) 上一个任务 -> 下一个任务 (原因)
+/// 缓冲区较小时只导出最近的记录。
+/// 正确输出：
+/// Test switch trace OK!
+
+const TRACE_LEN: usize = 256;
+const TAIL_LEN: usize = 4;
+const REASONS: [&str; 4] = ["yield", "preempt", "block", "exit"];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // 主动让出
+    if fork() == 0 {
+        for _ in 0..3 {
+            yield_();
+        }
+        exit(0);
+    }
+    // 阻塞睡眠
+    if fork() == 0 {
+        sleep_blocking(20);
+        exit(0);
+    }
+    // 忙等，被时钟中断抢占
+    if fork() == 0 {
+        let start = get_time();
+        while get_time() - start < 50 {}
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    for _ in 0..3 {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    let mut records = [SwitchRecord::default(); TRACE_LEN];
+    let n = switch_trace(&mut records);
+    assert!(n > 0);
+    let mut last_time = 0;
+    for record in records[..n as usize].iter() {
+        assert!(record.time_us >= last_time);
+        assert!(record.reason < REASONS.len());
+        last_time = record.time_us;
+        if record.from_pid == usize::MAX {
+            println!("{:>10} - -> {} (start)", record.time_us, record.to_pid);
+        } else {
+            println!(
+                "{:>10} {} -> {} ({})",
+                record.time_us, record.from_pid, record.to_pid, REASONS[record.reason]
+            );
+        }
+    }
+    // 只导出最近的 TAIL_LEN 条，它们不早于完整记录中的最后 TAIL_LEN 条
+    let mut tail = [SwitchRecord::default(); TAIL_LEN];
+    assert_eq!(switch_trace(&mut tail), TAIL_LEN as isize);
+    let n = n as usize;
+    assert!(n >= TAIL_LEN);
+    for (record, older) in tail.iter().zip(records[n - TAIL_LEN..n].iter()) {
+        assert!(record.time_us >= older.time_us);
+    }
+    println!("Test switch trace OK!");
+    0
+}
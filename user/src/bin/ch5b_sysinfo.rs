@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, syscall, sysinfo, waitpid, SysInfo, SYSCALL_SYSINFO};
+
+/// 系统统计测试：sysinfo 返回启动以来的秒数、以页为单位的内存大小和进程数；
+/// fork 出的子进程计入进程数，几个子进程持续运行一个采样周期以上之后，1 分钟负载大于 0。
+/// 正确输出：
+/// Test sysinfo OK!
+
+const PAGE_SIZE: u32 = 4096;
+const CHILDREN: usize = 3;
+/// 子进程运行的秒数，多于一个 5 秒的负载采样周期
+const BUSY_SECS: isize = 6;
+const EFAULT: isize = -14;
+
+fn info() -> SysInfo {
+    let mut info = SysInfo::default();
+    assert_eq!(sysinfo(&mut info), 0);
+    info
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = info();
+    assert_eq!(start.mem_unit, PAGE_SIZE);
+    assert!(start.totalram > 0);
+    assert!(start.freeram < start.totalram);
+    assert!(start.freeswap <= start.totalswap);
+    // 至少有 initproc 与本进程
+    assert!(start.procs >= 2);
+    assert_eq!(syscall(SYSCALL_SYSINFO, [0, 0, 0]), EFAULT);
+
+    let deadline = start.uptime + BUSY_SECS;
+    let mut pids = [0; CHILDREN];
+    for pid in pids.iter_mut() {
+        *pid = fork();
+        if *pid == 0 {
+            // 一直处于可运行状态，直到经过一个负载采样周期
+            while info().uptime < deadline {}
+            exit(0);
+        }
+    }
+    let running = info();
+    assert!(running.procs as usize >= start.procs as usize + CHILDREN);
+    for &pid in pids.iter() {
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, 0);
+    }
+    let end = info();
+    assert!(end.uptime >= deadline);
+    assert!(end.loads[0] > 0);
+    assert!(end.procs < running.procs);
+    println!("Test sysinfo OK!");
+    0
+}
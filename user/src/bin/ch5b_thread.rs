@@ -0,0 +1,98 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use user_lib::{
+    clone, exit, fork, getpid, gettid, mutex_blocking_create, mutex_lock, mutex_unlock,
+    thread_create, waitpid, waittid, yield_,
+};
+
+/// 线程测试：多个线程共享地址空间与互斥锁，waittid 收集各自的退出码；
+/// 不持有互斥锁时解锁返回 -EPERM，持有锁的线程退出后锁被释放；
+/// 进程中还有其他线程时不能 fork，线程全部回收后 fork（即 flags 为 0 的 clone）恢复正常。
+/// 正确输出：
+/// Test thread OK!
+
+const ESRCH: isize = -3;
+const EINVAL: isize = -22;
+const EPERM: isize = -1;
+const THREADS: usize = 4;
+const PER_THREAD: usize = 1000;
+
+static mut COUNTER: usize = 0;
+static MUTEX_ID: AtomicUsize = AtomicUsize::new(0);
+static MAIN_PID: AtomicUsize = AtomicUsize::new(0);
+static RELEASE: AtomicBool = AtomicBool::new(false);
+
+fn adder(arg: usize) -> ! {
+    assert_eq!(getpid() as usize, MAIN_PID.load(Ordering::Relaxed));
+    assert!(gettid() > 0);
+    let mutex_id = MUTEX_ID.load(Ordering::Relaxed);
+    for _ in 0..PER_THREAD {
+        mutex_lock(mutex_id);
+        // 读与写之间让出 CPU，没有互斥锁时一定会丢失更新
+        let value = unsafe { COUNTER };
+        yield_();
+        unsafe {
+            COUNTER = value + 1;
+        }
+        mutex_unlock(mutex_id);
+    }
+    exit(100 + arg as i32)
+}
+
+fn holder() -> ! {
+    mutex_lock(MUTEX_ID.load(Ordering::Relaxed));
+    exit(0)
+}
+
+fn waiter() -> ! {
+    while !RELEASE.load(Ordering::Acquire) {
+        yield_();
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(gettid(), 0);
+    MAIN_PID.store(getpid() as usize, Ordering::Relaxed);
+    MUTEX_ID.store(mutex_blocking_create() as usize, Ordering::Relaxed);
+    let mut tids = [0isize; THREADS];
+    for (i, tid) in tids.iter_mut().enumerate() {
+        *tid = thread_create(adder as usize, i);
+        assert!(*tid > 0);
+    }
+    for (i, &tid) in tids.iter().enumerate() {
+        assert_eq!(waittid(tid as usize), 100 + i as isize);
+    }
+    assert_eq!(unsafe { COUNTER }, THREADS * PER_THREAD);
+    // 不能等待自己，也不能等待不存在的线程
+    assert_eq!(waittid(0), EINVAL);
+    assert_eq!(waittid(tids[0] as usize), ESRCH);
+
+    let mutex_id = MUTEX_ID.load(Ordering::Relaxed);
+    assert_eq!(mutex_unlock(mutex_id), EPERM);
+    let tid = thread_create(holder as usize, 0);
+    assert_eq!(waittid(tid as usize), 0);
+    mutex_lock(mutex_id);
+    assert_eq!(mutex_unlock(mutex_id), 0);
+
+    let tid = thread_create(waiter as usize, 0);
+    assert_eq!(fork(), EINVAL);
+    RELEASE.store(true, Ordering::Release);
+    assert_eq!(waittid(tid as usize), 0);
+
+    let pid = clone(0, 0, 0);
+    if pid == 0 {
+        exit(7);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    println!("Test thread OK!");
+    0
+}
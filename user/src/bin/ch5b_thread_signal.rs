@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sleep_blocking, thread_create, waitpid, SIGSEGV};
+
+/// 线程收到默认动作为终止的信号测试：非法访存的是普通线程时，整个进程随之终止，
+/// 父进程回收到的退出码为 -SIGSEGV，仍在睡眠的主线程不会再运行。
+/// 正确输出：
+/// Test thread signal OK!
+
+const NULL: usize = 0;
+
+fn faulting_thread() -> ! {
+    unsafe {
+        (NULL as *mut u8).write_volatile(1);
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        assert!(thread_create(faulting_thread as usize, 0) > 0);
+        loop {
+            sleep_blocking(10);
+        }
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -SIGSEGV);
+    println!("Test thread signal OK!");
+    0
+}
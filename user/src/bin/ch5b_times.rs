@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, getpid, sleep_blocking, times, waitpid, Tms};
+
+/// CPU 时间统计测试：用户态计算计入 utime，频繁的系统调用计入 stime，
+/// 睡眠时间不计入两者，子进程被回收后其时间计入 cutime/cstime。
+/// 正确输出：
+/// Test times OK!
+
+const SPIN_MS: isize = 200;
+
+// 只在用户态计算，偶尔检查时间
+fn spin_user(ms: isize) {
+    let start = get_time();
+    let mut x: usize = 0;
+    loop {
+        for i in 0..10000 {
+            x = x.wrapping_mul(31).wrapping_add(i);
+        }
+        unsafe {
+            core::ptr::write_volatile(&mut x, x);
+        }
+        if get_time() - start >= ms {
+            return;
+        }
+    }
+}
+
+fn spin_syscall(ms: isize) {
+    let start = get_time();
+    while get_time() - start < ms {
+        getpid();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = Tms::default();
+    let mut after = Tms::default();
+    assert!(times(&mut before) > 0);
+    spin_user(SPIN_MS);
+    times(&mut after);
+    let utime = after.tms_utime - before.tms_utime;
+    let stime = after.tms_stime - before.tms_stime;
+    println!(
+        "spin in user mode: utime = {}us, stime = {}us",
+        utime, stime
+    );
+    assert!(utime > stime);
+
+    times(&mut before);
+    spin_syscall(SPIN_MS);
+    times(&mut after);
+    let stime = after.tms_stime - before.tms_stime;
+    println!("spin in syscalls: stime = {}us", stime);
+    assert!(stime > 0);
+
+    // 睡眠期间不在处理器上，不计入 CPU 时间
+    times(&mut before);
+    sleep_blocking(SPIN_MS as usize);
+    times(&mut after);
+    let cpu = after.tms_utime + after.tms_stime - before.tms_utime - before.tms_stime;
+    println!("sleep: cpu = {}us", cpu);
+    assert!(cpu < (SPIN_MS as usize) * 1000 / 2);
+
+    let pid = fork();
+    if pid == 0 {
+        spin_user(SPIN_MS);
+        exit(0);
+    }
+    times(&mut before);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    times(&mut after);
+    let cutime = after.tms_cutime - before.tms_cutime;
+    println!("child: cutime = {}us", cutime);
+    assert!(cutime >= (SPIN_MS as usize) * 1000 / 2);
+    println!("Test times OK!");
+    0
+}
@@ -0,0 +1,75 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    clone, exit, gettid, set_thread_pointer, set_tid_address, thread_create, thread_pointer,
+    waittid, yield_, CLONE_SETTLS, CLONE_VM,
+};
+
+/// 线程局部存储测试：每个线程的 tp 在多次让出 CPU 后保持不变，CLONE_SETTLS 设置新线程的 tp；
+/// set_tid_address 登记的变量在线程退出后被内核清零。
+/// 正确输出：
+/// Test tls OK!
+
+const THREADS: usize = 4;
+const ROUNDS: usize = 20;
+
+static mut CLEAR_SLOT: u32 = u32::MAX;
+
+fn tls_value(i: usize) -> usize {
+    0x5a5a_0000 + i
+}
+
+fn spinner(i: usize) -> ! {
+    set_thread_pointer(tls_value(i));
+    for _ in 0..ROUNDS {
+        yield_();
+        assert_eq!(thread_pointer(), tls_value(i));
+    }
+    exit(i as i32)
+}
+
+fn register_and_exit(_: usize) -> ! {
+    assert_eq!(
+        set_tid_address(unsafe { &mut CLEAR_SLOT as *mut u32 }),
+        gettid()
+    );
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let main_tp = tls_value(100);
+    set_thread_pointer(main_tp);
+    let mut tids = [0isize; THREADS];
+    for (i, tid) in tids.iter_mut().enumerate() {
+        *tid = thread_create(spinner as usize, i);
+        assert!(*tid > 0);
+    }
+    for _ in 0..ROUNDS {
+        yield_();
+        assert_eq!(thread_pointer(), main_tp);
+    }
+    for (i, &tid) in tids.iter().enumerate() {
+        assert_eq!(waittid(tid as usize), i as isize);
+    }
+
+    // CLONE_SETTLS：新线程从 clone 返回时 tp 已经是 tls
+    let tid = clone(CLONE_VM | CLONE_SETTLS, 0, tls_value(200));
+    if tid == 0 {
+        assert_eq!(thread_pointer(), tls_value(200));
+        exit(9);
+    }
+    assert!(tid > 0);
+    assert_eq!(waittid(tid as usize), 9);
+    assert_eq!(thread_pointer(), main_tp);
+
+    let tid = thread_create(register_and_exit as usize, 0);
+    assert_eq!(waittid(tid as usize), 0);
+    assert_eq!(unsafe { core::ptr::read_volatile(&CLEAR_SLOT) }, 0);
+    println!("Test tls OK!");
+    0
+}
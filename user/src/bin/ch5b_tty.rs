@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, ioctl, open, pipe, tcgetattr, tcsetattr, OpenFlags, Termios, ECHO, ICANON, TCGETS,
+};
+
+/// 终端测试：标准输入输出与 /dev/console 是同一个终端，默认为不回显的原始模式；
+/// 用 TCSETS 打开规范模式与回显之后从任何一个文件描述符读出的都是新的属性，最后恢复原来的属性。
+/// 对管道使用终端的 ioctl 以及不认识的请求返回 -ENOTTY，没有打开的文件描述符返回 -EBADF。
+/// 正确输出：
+/// Test tty OK!
+
+const EBADF: isize = -9;
+const ENOTTY: isize = -25;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut saved = Termios::default();
+    assert_eq!(tcgetattr(0, &mut saved), 0);
+    assert_eq!(saved.c_lflag & (ICANON | ECHO), 0);
+
+    let mut cooked = saved;
+    cooked.c_lflag |= ICANON | ECHO;
+    assert_eq!(tcsetattr(0, &cooked), 0);
+    let console = open("/dev/console\0", OpenFlags::RDWR);
+    assert!(console > 2);
+    let mut termios = Termios::default();
+    assert_eq!(tcgetattr(console as usize, &mut termios), 0);
+    assert_eq!(termios.c_lflag & (ICANON | ECHO), ICANON | ECHO);
+    let mut termios = Termios::default();
+    assert_eq!(tcgetattr(1, &mut termios), 0);
+    assert_eq!(termios.c_lflag & (ICANON | ECHO), ICANON | ECHO);
+    assert_eq!(tcsetattr(console as usize, &saved), 0);
+    assert_eq!(tcgetattr(0, &mut termios), 0);
+    assert_eq!(termios.c_lflag & (ICANON | ECHO), 0);
+    assert_eq!(close(console as usize), 0);
+
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(tcgetattr(fds[0], &mut termios), ENOTTY);
+    assert_eq!(ioctl(0, TCGETS + 0x100, 0), ENOTTY);
+    assert_eq!(tcgetattr(99, &mut termios), EBADF);
+    assert_eq!(close(fds[0]), 0);
+    assert_eq!(close(fds[1]), 0);
+    println!("Test tty OK!");
+    0
+}
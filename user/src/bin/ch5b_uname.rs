@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{syscall, uname, uts_str, UtsName, SYSCALL_UNAME, UTSNAME_LEN};
+
+/// uname 测试：内核名为 rCore-lab，硬件平台为 riscv64，其余字段非空，所有字段都以 \0 结尾；
+/// 结果不可写时返回 -EFAULT。
+/// 正确输出：
+/// Test uname OK!
+
+const EFAULT: isize = -14;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut uts = UtsName::new();
+    assert_eq!(uname(&mut uts), 0);
+    assert_eq!(uts_str(&uts.sysname), "rCore-lab");
+    assert_eq!(uts_str(&uts.machine), "riscv64");
+    let fields = [
+        &uts.sysname,
+        &uts.nodename,
+        &uts.release,
+        &uts.version,
+        &uts.machine,
+        &uts.domainname,
+    ];
+    for field in fields {
+        assert!(field.contains(&0));
+        assert!(!uts_str(field).is_empty());
+        assert!(uts_str(field).len() < UTSNAME_LEN);
+    }
+    assert_eq!(syscall(SYSCALL_UNAME, [0, 0, 0]), EFAULT);
+    println!("Test uname OK!");
+    0
+}
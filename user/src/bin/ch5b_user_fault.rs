@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid};
+
+/// 用户程序出错测试：各种访存错误、非法指令与断点只终止出错的进程，退出码为对应信号编号的相反数，
+/// 内核在串口上报告出错的地址与指令后继续调度其他任务。
+/// 正确输出：
+/// Test user fault OK!
+
+const SIGILL: i32 = 4;
+const SIGTRAP: i32 = 5;
+const SIGSEGV: i32 = 11;
+
+fn run_child(f: fn()) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn store_null() {
+    unsafe { core::ptr::null_mut::<usize>().write_volatile(0) };
+}
+
+fn load_kernel() {
+    unsafe { (0xffff_ffff_ffff_f000 as *const usize).read_volatile() };
+}
+
+fn jump_unmapped() {
+    let f: fn() = unsafe { core::mem::transmute(0x10usize) };
+    f();
+}
+
+fn privileged() {
+    unsafe { core::arch::asm!("sret") };
+}
+
+fn illegal() {
+    unsafe { core::arch::asm!("unimp") };
+}
+
+fn breakpoint() {
+    unsafe { core::arch::asm!("ebreak") };
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(run_child(store_null), -SIGSEGV);
+    assert_eq!(run_child(load_kernel), -SIGSEGV);
+    assert_eq!(run_child(jump_unmapped), -SIGSEGV);
+    assert_eq!(run_child(privileged), -SIGILL);
+    assert_eq!(run_child(illegal), -SIGILL);
+    assert_eq!(run_child(breakpoint), -SIGTRAP);
+    println!("Test user fault OK!");
+    0
+}
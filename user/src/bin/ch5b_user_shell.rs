@@ -13,7 +13,7 @@ const BS: u8 = 0x08u8;
 
 use alloc::string::String;
 use user_lib::console::getchar;
-use user_lib::{exec, flush, fork, waitpid};
+use user_lib::{exec, flush, fork, reboot, shutdown, waitpid};
 
 #[no_mangle]
 pub fn main() -> i32 {
@@ -26,12 +26,21 @@ pub fn main() -> i32 {
         match c {
             LF | CR => {
                 print!("\n");
-                if !line.is_empty() {
+                if line == "shutdown" || line == "reboot" {
+                    // 内建命令：由 shell 自己调用，只有 initproc 直接创建的 shell 有权限
+                    let ret = if line == "shutdown" {
+                        shutdown()
+                    } else {
+                        reboot()
+                    };
+                    println!("Shell: {} failed with {}", line, ret);
+                    line.clear();
+                } else if !line.is_empty() {
                     line.push('\0');
                     let pid = fork();
                     if pid == 0 {
                         // child process
-                        if exec(line.as_str(), &[0 as *const u8]) == -1 {
+                        if exec(line.as_str(), &[0 as *const u8]) < 0 {
                             println!("Error when executing!");
                             return -4;
                         }
@@ -39,8 +48,12 @@ pub fn main() -> i32 {
                     } else {
                         let mut exit_code: i32 = 0;
                         let exit_pid = waitpid(pid as usize, &mut exit_code);
-                        assert_eq!(pid, exit_pid);
-                        println!("Shell: Process {} exited with code {}", pid, exit_code);
+                        if exit_pid < 0 {
+                            println!("Shell: waitpid {} failed with {}", pid, exit_pid);
+                        } else {
+                            assert_eq!(pid, exit_pid);
+                            println!("Shell: Process {} exited with code {}", pid, exit_code);
+                        }
                     }
                     line.clear();
                 }
@@ -0,0 +1,163 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, mremap, munmap, waitpid};
+
+/// 逻辑段拆分与合并测试：解除一段映射中间的几页之后两侧的页面保持原样，空洞中的页面不能再访问；
+/// 重新映射空洞、或把中间几页的权限改回原样之后，相邻的映射合并为一段，可以整体 mremap；
+/// munmap 可以跨越多段映射，区间中有未映射的页面时不做任何修改；大量互不相邻的小映射各自正常缺页。
+/// 正确输出：
+/// Test vma OK!
+
+const EINVAL: isize = -22;
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x6800_0000;
+const PAGES: usize = 8;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+const PROT_RW: usize = PROT_R | PROT_W;
+/// 互不相邻的小映射的个数，每两个之间留一页空洞，不会合并
+const SCATTERED: usize = 256;
+const SCATTERED_START: usize = 0x6900_0000;
+
+static mut TARGET: usize = 0;
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE_SIZE) as *mut usize
+}
+
+fn fill(pages: core::ops::Range<usize>) {
+    for i in pages {
+        unsafe { page(i).write_volatile(i + 1) };
+    }
+}
+
+fn check(pages: core::ops::Range<usize>) {
+    for i in pages {
+        assert_eq!(unsafe { page(i).read_volatile() }, i + 1);
+    }
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn touch() -> i32 {
+    unsafe { (TARGET as *const usize).read_volatile() as i32 }
+}
+
+fn expect_segv(addr: usize) {
+    unsafe {
+        TARGET = addr;
+    }
+    assert_eq!(run_child(touch), -SIGSEGV);
+}
+
+fn write_target() -> i32 {
+    unsafe { (TARGET as *mut usize).write_volatile(0) };
+    0
+}
+
+fn expect_segv_on_write(addr: usize) {
+    unsafe {
+        TARGET = addr;
+    }
+    assert_eq!(run_child(write_target), -SIGSEGV);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, PAGES * PAGE_SIZE, PROT_RW), 0);
+    fill(0..PAGES);
+
+    // 在中间挖一个洞，两侧的页面保持原样
+    assert_eq!(munmap(START + 2 * PAGE_SIZE, 2 * PAGE_SIZE), 0);
+    check(0..2);
+    check(4..PAGES);
+    expect_segv(page(2) as usize);
+    expect_segv(page(3) as usize);
+    // 拆分之后的两段在子进程中同样保持原样
+    assert_eq!(
+        run_child(|| {
+            check(0..2);
+            check(4..PAGES);
+            0
+        }),
+        0
+    );
+
+    // 重新映射空洞，得到新的清零页面，三段合并为一段之后可以整体扩大
+    assert_eq!(mmap(START + 2 * PAGE_SIZE, 2 * PAGE_SIZE, PROT_RW), 0);
+    assert_eq!(unsafe { page(2).read_volatile() }, 0);
+    fill(2..4);
+    assert_eq!(
+        mremap(START, PAGES * PAGE_SIZE, (PAGES + 2) * PAGE_SIZE, 0),
+        START as isize
+    );
+    check(0..PAGES);
+
+    // 中间几页的权限改为只读再改回来，同样合并为一段
+    assert_eq!(mprotect(START + PAGE_SIZE, 3 * PAGE_SIZE, PROT_R), 0);
+    expect_segv_on_write(page(2) as usize);
+    assert_eq!(mprotect(START + PAGE_SIZE, 3 * PAGE_SIZE, PROT_RW), 0);
+    assert_eq!(
+        mremap(START, (PAGES + 2) * PAGE_SIZE, PAGES * PAGE_SIZE, 0),
+        START as isize
+    );
+    check(0..PAGES);
+
+    // 映射的一部分可以单独 mremap，剩下的部分不受影响
+    assert_eq!(
+        mremap(START + 6 * PAGE_SIZE, 2 * PAGE_SIZE, PAGE_SIZE, 0),
+        (START + 6 * PAGE_SIZE) as isize
+    );
+    check(0..7);
+    expect_segv(page(7) as usize);
+
+    // 区间中有未映射的页面时什么也不做
+    assert_eq!(munmap(START, PAGES * PAGE_SIZE), EINVAL);
+    check(0..7);
+    // 跨越权限不同的两段映射
+    assert_eq!(mprotect(START, 3 * PAGE_SIZE, PROT_R), 0);
+    assert_eq!(munmap(START + 2 * PAGE_SIZE, 2 * PAGE_SIZE), 0);
+    check(0..2);
+    check(4..7);
+    assert_eq!(munmap(START, 2 * PAGE_SIZE), 0);
+    assert_eq!(munmap(START + 4 * PAGE_SIZE, 3 * PAGE_SIZE), 0);
+    expect_segv(page(0) as usize);
+    expect_segv(page(6) as usize);
+
+    // 大量不能合并的小映射
+    for i in 0..SCATTERED {
+        let prot = if i % 2 == 0 { PROT_RW } else { PROT_R };
+        assert_eq!(
+            mmap(SCATTERED_START + 2 * i * PAGE_SIZE, PAGE_SIZE, prot),
+            0
+        );
+    }
+    for i in (0..SCATTERED).step_by(2) {
+        let p = (SCATTERED_START + 2 * i * PAGE_SIZE) as *mut usize;
+        unsafe { p.write_volatile(i) };
+    }
+    for i in 0..SCATTERED {
+        let p = (SCATTERED_START + 2 * i * PAGE_SIZE) as *const usize;
+        let expected = if i % 2 == 0 { i } else { 0 };
+        assert_eq!(unsafe { p.read_volatile() }, expected);
+    }
+    assert_eq!(munmap(SCATTERED_START, 2 * SCATTERED * PAGE_SIZE), EINVAL);
+    for i in 0..SCATTERED {
+        assert_eq!(munmap(SCATTERED_START + 2 * i * PAGE_SIZE, PAGE_SIZE), 0);
+    }
+    println!("Test vma OK!");
+    0
+}
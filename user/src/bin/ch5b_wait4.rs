@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, wait4, Rusage, WNOHANG};
+
+/// wait4 测试：回收子进程的同时取得它在用户态运行的时间。
+/// 正确输出：
+/// Test wait4 OK!
+
+const ECHILD: isize = -10;
+const SPIN_MS: isize = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        let start = get_time();
+        let mut x: usize = 0;
+        while get_time() - start < SPIN_MS {
+            for i in 0..10000 {
+                x = x.wrapping_mul(31).wrapping_add(i);
+            }
+            unsafe {
+                core::ptr::write_volatile(&mut x, x);
+            }
+        }
+        exit(7);
+    }
+    let mut exit_code: i32 = 0;
+    let mut rusage = Rusage::default();
+    // 子进程还在运行
+    assert_eq!(wait4(pid, &mut exit_code, WNOHANG, &mut rusage), 0);
+    assert_eq!(wait4(pid, &mut exit_code, 0, &mut rusage), pid);
+    assert_eq!(exit_code, 7);
+    let utime_us = rusage.ru_utime.sec * 1_000_000 + rusage.ru_utime.usec;
+    println!(
+        "child rusage: utime = {}.{:06}s, stime = {}.{:06}s",
+        rusage.ru_utime.sec, rusage.ru_utime.usec, rusage.ru_stime.sec, rusage.ru_stime.usec
+    );
+    assert!(rusage.ru_utime.usec < 1_000_000);
+    assert!(utime_us >= (SPIN_MS as usize) * 1000 / 2);
+    // 已经没有子进程了
+    assert_eq!(wait4(-1, &mut exit_code, 0, &mut rusage), ECHILD);
+    println!("Test wait4 OK!");
+    0
+}
@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sleep_blocking, wait, waitpid, waitpid_nohang};
+
+/// waitpid WNOHANG 测试：区分“没有子进程”与“子进程仍在运行”两种情况。
+/// 正确输出：
+/// Test waitpid nohang OK!
+
+const ECHILD: isize = -10;
+const MAGIC: i32 = 0x5a;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code: i32 = 0;
+    // 没有任何子进程
+    assert_eq!(waitpid_nohang(-1, &mut exit_code), ECHILD);
+    let pid = fork();
+    if pid == 0 {
+        sleep_blocking(100);
+        exit(MAGIC);
+    }
+    // 子进程还在睡眠
+    assert_eq!(waitpid_nohang(pid, &mut exit_code), 0);
+    assert_eq!(waitpid_nohang(-1, &mut exit_code), 0);
+    // 不是自己的子进程
+    assert_eq!(waitpid_nohang(pid + 1, &mut exit_code), ECHILD);
+    let mut polls = 0;
+    loop {
+        match waitpid_nohang(pid, &mut exit_code) {
+            0 => {
+                polls += 1;
+                sleep_blocking(10);
+            }
+            found => {
+                assert_eq!(found, pid);
+                break;
+            }
+        }
+    }
+    assert!(polls > 0);
+    assert_eq!(exit_code, MAGIC);
+    // 子进程已被回收
+    assert_eq!(waitpid(pid as usize, &mut exit_code), ECHILD);
+    assert_eq!(wait(&mut exit_code), ECHILD);
+    println!("Test waitpid nohang OK!");
+    0
+}
@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, mprotect, munmap};
+
+/// W^X 测试：不能映射同时可写与可执行的页面，也不能把已有的页面改为同时可写与可执行；
+/// 被拒绝的请求不改变地址空间，可写与可执行分开设置时仍然可以使用。
+/// 正确输出：
+/// Test W^X OK!
+
+const EACCES: isize = -13;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x5800_0000;
+const PROT_R: usize = 1;
+const PROT_W: usize = 2;
+const PROT_X: usize = 4;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_W | PROT_X), EACCES);
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_R | PROT_W | PROT_X), EACCES);
+    assert_eq!(mmap(0, PAGE_SIZE, PROT_R | PROT_W | PROT_X), EACCES);
+    // 被拒绝的映射没有占用地址
+    assert_eq!(mmap(START, 2 * PAGE_SIZE, PROT_R | PROT_W), 0);
+    unsafe { (START as *mut usize).write_volatile(1) };
+
+    assert_eq!(mprotect(START, PAGE_SIZE, PROT_R | PROT_W | PROT_X), EACCES);
+    assert_eq!(mprotect(START, 2 * PAGE_SIZE, PROT_W | PROT_X), EACCES);
+    // 权限没有改变，仍然可写
+    unsafe { (START as *mut usize).write_volatile(2) };
+    assert_eq!(mprotect(START, PAGE_SIZE, PROT_R | PROT_X), 0);
+    assert_eq!(mprotect(START, PAGE_SIZE, PROT_R | PROT_W), 0);
+    assert_eq!(unsafe { (START as *const usize).read_volatile() }, 2);
+
+    assert_eq!(munmap(START, 2 * PAGE_SIZE), 0);
+    println!("Test W^X OK!");
+    0
+}
@@ -0,0 +1,117 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, meminfo, mmap, mprotect, munmap, syscall, waitpid, MemInfo, SYSCALL_GETTIMEOFDAY,
+};
+
+/// 零页测试：读遍一段比物理内存还大的 mmap 区域，所有页面都映射到同一个共享的零页，只额外占用页表；
+/// 第一次写某个页面时才为它分配页帧，其余页面仍读出 0。fork 之后父子进程的零页互不影响，
+/// 改为只读之后不能写；内核代替用户写入只映射到零页的页面时同样先为它分配页帧，零页保持全零。
+/// 正确输出：
+/// Test zero page OK!
+
+const SIGSEGV: i32 = 11;
+const PAGE_SIZE: usize = 4096;
+const START: usize = 0x2000_0000;
+const LEN: usize = 256 * 1024 * 1024;
+const PAGES: usize = LEN / PAGE_SIZE;
+/// 每隔这么多页写一个页面
+const STRIDE: usize = 64;
+const PROT_R: usize = 1;
+const PROT_RW: usize = 3;
+
+fn info() -> MemInfo {
+    let mut info = MemInfo::default();
+    assert_eq!(meminfo(&mut info), 0);
+    info
+}
+
+fn page(i: usize) -> *mut usize {
+    (START + i * PAGE_SIZE) as *mut usize
+}
+
+fn read(i: usize) -> usize {
+    unsafe { page(i).read_volatile() }
+}
+
+fn run_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+//子进程中零页同样只读地共享，写一个页面不影响父进程
+fn child_writes() -> i32 {
+    assert_eq!(info().zero_pages, PAGES - PAGES / STRIDE);
+    assert_eq!(read(1), 0);
+    unsafe { page(1).write_volatile(42) };
+    assert_eq!(read(1), 42);
+    assert_eq!(read(2), 0);
+    0
+}
+
+fn write_read_only() -> i32 {
+    unsafe { page(1).write_volatile(1) };
+    0
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = info();
+    assert_eq!(start.zero_pages, 0);
+    assert_eq!(mmap(START, LEN, PROT_RW), 0);
+
+    // 读遍所有页面，只需要为页表分配页帧
+    for i in 0..PAGES {
+        assert_eq!(read(i), 0);
+    }
+    let read_all = info();
+    assert_eq!(read_all.zero_pages, PAGES);
+    assert_eq!(read_all.mapped_pages, start.mapped_pages);
+    assert!(start.free_frames - read_all.free_frames <= PAGES / 512 + 2);
+
+    // 写过的页面得到自己的页帧
+    for i in (0..PAGES).step_by(STRIDE) {
+        unsafe { page(i).write_volatile(i + 1) };
+    }
+    let written = info();
+    assert_eq!(written.zero_pages, PAGES - PAGES / STRIDE);
+    assert_eq!(written.mapped_pages, start.mapped_pages + PAGES / STRIDE);
+    assert_eq!(written.free_frames, read_all.free_frames - PAGES / STRIDE);
+    for i in 0..2 * STRIDE {
+        let expected = if i % STRIDE == 0 { i + 1 } else { 0 };
+        assert_eq!(read(i), expected);
+    }
+
+    assert_eq!(run_child(child_writes), 0);
+    assert_eq!(read(1), 0);
+
+    // 只读的零页不能写，改回可写之后第一次写时分配页帧
+    assert_eq!(mprotect(START, STRIDE * PAGE_SIZE, PROT_R), 0);
+    assert_eq!(run_child(write_read_only), -SIGSEGV);
+    assert_eq!(mprotect(START, STRIDE * PAGE_SIZE, PROT_RW), 0);
+    unsafe { page(1).write_volatile(1) };
+    assert_eq!(read(1), 1);
+
+    // 内核写入只映射到零页的页面
+    assert_eq!(syscall(SYSCALL_GETTIMEOFDAY, [page(2) as usize, 0, 0]), 0);
+    let usec = unsafe { page(2).add(1).read_volatile() };
+    assert!(read(2) > 0 || usec > 0);
+    assert_eq!(read(3), 0);
+    assert_eq!(info().zero_pages, PAGES - PAGES / STRIDE - 2);
+
+    assert_eq!(munmap(START, LEN), 0);
+    let end = info();
+    assert_eq!(end.zero_pages, 0);
+    assert_eq!(end.mapped_pages, start.mapped_pages);
+    println!("Test zero page OK!");
+    0
+}
@@ -31,7 +31,7 @@ pub fn main() -> i32 {
                     let pid = fork();
                     if pid == 0 {
                         // child process
-                        if exec(line.as_str(), &[0 as *const u8]) == -1 {
+                        if exec(line.as_str(), &[0 as *const u8]) < 0 {
                             println!("Error when executing!");
                             return -4;
                         }
@@ -39,8 +39,12 @@ pub fn main() -> i32 {
                     } else {
                         let mut exit_code: i32 = 0;
                         let exit_pid = waitpid(pid as usize, &mut exit_code);
-                        assert_eq!(pid, exit_pid);
-                        println!("Shell: Process {} exited with code {}", pid, exit_code);
+                        if exit_pid < 0 {
+                            println!("Shell: waitpid {} failed with {}", pid, exit_pid);
+                        } else {
+                            assert_eq!(pid, exit_pid);
+                            println!("Shell: Process {} exited with code {}", pid, exit_code);
+                        }
                     }
                     line.clear();
                 }
@@ -72,7 +72,7 @@ pub fn main() -> i32 {
                         // input redirection
                         if !input.is_empty() {
                             let input_fd = open(input.as_str(), OpenFlags::RDONLY);
-                            if input_fd == -1 {
+                            if input_fd < 0 {
                                 println!("Error when opening file {}", input);
                                 return -4;
                             }
@@ -85,7 +85,7 @@ pub fn main() -> i32 {
                         if !output.is_empty() {
                             let output_fd =
                                 open(output.as_str(), OpenFlags::CREATE | OpenFlags::WRONLY);
-                            if output_fd == -1 {
+                            if output_fd < 0 {
                                 println!("Error when opening file {}", output);
                                 return -4;
                             }
@@ -95,7 +95,7 @@ pub fn main() -> i32 {
                             close(output_fd);
                         }
                         // child process
-                        if exec(args_copy[0].as_str(), args_addr.as_slice()) == -1 {
+                        if exec(args_copy[0].as_str(), args_addr.as_slice()) < 0 {
                             println!("Error when executing!");
                             return -4;
                         }
@@ -103,8 +103,12 @@ pub fn main() -> i32 {
                     } else {
                         let mut exit_code: i32 = 0;
                         let exit_pid = waitpid(pid as usize, &mut exit_code);
-                        assert_eq!(pid, exit_pid);
-                        println!("Shell: Process {} exited with code {}", pid, exit_code);
+                        if exit_pid < 0 {
+                            println!("Shell: waitpid {} failed with {}", pid, exit_pid);
+                        } else {
+                            assert_eq!(pid, exit_pid);
+                            println!("Shell: Process {} exited with code {}", pid, exit_code);
+                        }
                     }
                     line.clear();
                 }
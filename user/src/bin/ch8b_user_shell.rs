@@ -131,7 +131,7 @@ pub fn main() -> i32 {
                                 // redirect input
                                 if !input.is_empty() {
                                     let input_fd = open(input.as_str(), OpenFlags::RDONLY);
-                                    if input_fd == -1 {
+                                    if input_fd < 0 {
                                         println!("Error when opening file {}", input);
                                         return -4;
                                     }
@@ -146,7 +146,7 @@ pub fn main() -> i32 {
                                         output.as_str(),
                                         OpenFlags::CREATE | OpenFlags::WRONLY,
                                     );
-                                    if output_fd == -1 {
+                                    if output_fd < 0 {
                                         println!("Error when opening file {}", output);
                                         return -4;
                                     }
@@ -173,7 +173,7 @@ pub fn main() -> i32 {
                                     close(pipe_fd[1]);
                                 }
                                 // execute new application
-                                if exec(args_copy[0].as_str(), args_addr.as_slice()) == -1 {
+                                if exec(args_copy[0].as_str(), args_addr.as_slice()) < 0 {
                                     println!("Error when executing!");
                                     return -4;
                                 }
@@ -189,6 +189,10 @@ pub fn main() -> i32 {
                         let mut exit_code: i32 = 0;
                         for pid in children.into_iter() {
                             let exit_pid = waitpid(pid as usize, &mut exit_code);
+                            if exit_pid < 0 {
+                                println!("Shell: waitpid {} failed with {}", pid, exit_pid);
+                                continue;
+                            }
                             assert_eq!(pid, exit_pid);
                             //println!("Shell: Process {} exited with code {}", pid, exit_code);
                         }
@@ -16,6 +16,8 @@ extern crate bitflags;
 use alloc::vec::Vec;
 use buddy_system_allocator::LockedHeap;
 pub use console::{flush, STDIN, STDOUT};
+use core::convert::TryInto;
+use core::sync::atomic::AtomicU32;
 pub use syscall::*;
 
 const USER_HEAP_SIZE: usize = 16384;
@@ -44,10 +46,16 @@ fn clear_bss() {
     }
 }
 
+/// 内核放在用户栈上的环境变量指针数组，以空指针结尾
+static mut ENVP: usize = 0;
+
 #[no_mangle]
 #[link_section = ".text.entry"]
-pub extern "C" fn _start(argc: usize, argv: usize) -> ! {
+pub extern "C" fn _start(argc: usize, argv: usize, envp: usize) -> ! {
     clear_bss();
+    unsafe {
+        ENVP = envp;
+    }
     unsafe {
         HEAP.lock()
             .init(HEAP_SPACE.as_ptr() as usize, USER_HEAP_SIZE);
@@ -82,6 +90,8 @@ bitflags! {
         const RDWR = 1 << 1;
         const CREATE = 1 << 9;
         const TRUNC = 1 << 10;
+        const NONBLOCK = 1 << 11;
+        const APPEND = 1 << 12;
     }
 }
 
@@ -98,6 +108,287 @@ impl TimeVal {
     }
 }
 
+/// 与 Linux 的 struct timespec 布局一致
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct TimeSpec {
+    pub tv_sec: usize,
+    pub tv_nsec: usize,
+}
+
+/// 时钟：墙上时间，即自 1970-01-01 00:00:00 UTC 以来的时间
+pub const CLOCK_REALTIME: usize = 0;
+/// 时钟：启动以来经过的时间，单调递增
+pub const CLOCK_MONOTONIC: usize = 1;
+
+pub const WNOHANG: usize = 1;
+
+pub const SIGINT: i32 = 2;
+pub const SIGTRAP: i32 = 5;
+pub const SIGKILL: i32 = 9;
+pub const SIGUSR1: i32 = 10;
+pub const SIGSEGV: i32 = 11;
+pub const SIGPIPE: i32 = 13;
+pub const SIGTERM: i32 = 15;
+pub const SIGXCPU: i32 = 24;
+
+/// 采取默认动作
+pub const SIG_DFL: usize = 0;
+/// 忽略信号
+pub const SIG_IGN: usize = 1;
+
+/// 信号处理动作，处理函数的参数为信号编号，返回后由内核恢复被打断的执行流
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalAction {
+    pub handler: usize,
+    /// 处理函数运行期间额外屏蔽的信号
+    pub mask: u32,
+}
+
+/// 子进程的资源使用情况，目前只统计 ru_utime 与 ru_stime
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Rusage {
+    pub ru_utime: TimeVal,
+    pub ru_stime: TimeVal,
+    pub ru_maxrss: usize,
+    pub ru_ixrss: usize,
+    pub ru_idrss: usize,
+    pub ru_isrss: usize,
+    pub ru_minflt: usize,
+    pub ru_majflt: usize,
+    pub ru_nswap: usize,
+    pub ru_inblock: usize,
+    pub ru_oublock: usize,
+    pub ru_msgsnd: usize,
+    pub ru_msgrcv: usize,
+    pub ru_nsignals: usize,
+    pub ru_nvcsw: usize,
+    pub ru_nivcsw: usize,
+}
+
+/// utsname 中每个字段的长度，包括结尾的 \0
+pub const UTSNAME_LEN: usize = 65;
+
+/// 与 Linux 的 struct utsname 布局一致，每个字段都是以 \0 结尾的字符串
+#[repr(C)]
+pub struct UtsName {
+    pub sysname: [u8; UTSNAME_LEN],
+    pub nodename: [u8; UTSNAME_LEN],
+    pub release: [u8; UTSNAME_LEN],
+    pub version: [u8; UTSNAME_LEN],
+    pub machine: [u8; UTSNAME_LEN],
+    pub domainname: [u8; UTSNAME_LEN],
+}
+
+impl UtsName {
+    pub fn new() -> Self {
+        Self {
+            sysname: [0; UTSNAME_LEN],
+            nodename: [0; UTSNAME_LEN],
+            release: [0; UTSNAME_LEN],
+            version: [0; UTSNAME_LEN],
+            machine: [0; UTSNAME_LEN],
+            domainname: [0; UTSNAME_LEN],
+        }
+    }
+}
+
+/// SysInfo 中系统负载的定点数表示中小数部分的位数
+pub const SI_LOAD_SHIFT: usize = 16;
+
+/// 与 Linux 的 struct sysinfo 布局一致，内存与交换区的大小以 mem_unit 字节为单位
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SysInfo {
+    pub uptime: isize,
+    pub loads: [usize; 3],
+    pub totalram: usize,
+    pub freeram: usize,
+    pub sharedram: usize,
+    pub bufferram: usize,
+    pub totalswap: usize,
+    pub freeswap: usize,
+    pub procs: u16,
+    pub pad: u16,
+    pub totalhigh: usize,
+    pub freehigh: usize,
+    pub mem_unit: u32,
+}
+
+/// 进程的 CPU 时间，单位为微秒
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct Tms {
+    pub tms_utime: usize,
+    pub tms_stime: usize,
+    pub tms_cutime: usize,
+    pub tms_cstime: usize,
+}
+
+pub const RLIM_INFINITY: usize = usize::MAX;
+pub const RLIMIT_CPU: usize = 0;
+/// 用户栈的字节数上限，用户栈在缺页时自动向下增长，不能超过软限制
+pub const RLIMIT_STACK: usize = 3;
+/// core dump 中内存部分的字节数上限，默认为 0，即不生成 core dump
+pub const RLIMIT_CORE: usize = 4;
+pub const RLIMIT_NPROC: usize = 6;
+pub const RLIMIT_AS: usize = 9;
+/// 没有特权的进程能设置的最高实时优先级，默认软限制为 0，硬限制为 99
+pub const RLIMIT_RTPRIO: usize = 14;
+
+/// 资源限制，cur 为软限制，max 为硬限制
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rlimit {
+    pub cur: usize,
+    pub max: usize,
+}
+
+pub const SCHED_OTHER: usize = 0;
+pub const SCHED_FIFO: usize = 1;
+pub const SCHED_RR: usize = 2;
+
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SchedParam {
+    pub sched_priority: i32,
+}
+
+/// 任务切换记录，from_pid 为 usize::MAX 表示没有上一个任务
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SwitchRecord {
+    pub time_us: usize,
+    pub from_pid: usize,
+    pub to_pid: usize,
+    /// 0: yield, 1: preempt, 2: block, 3: exit
+    pub reason: usize,
+}
+
+/// 调度统计信息，时间以微秒计
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SchedStats {
+    pub uptime_us: usize,
+    pub idle_us: usize,
+    pub switches: usize,
+}
+
+/// 页面置换统计信息，除了 swapped、free_frames 和时钟指针之外都是启动以来的累计值
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct SwapStats {
+    pub harvests: usize,
+    pub scanned: usize,
+    pub accessed: usize,
+    pub dirty: usize,
+    pub swap_outs: usize,
+    pub swap_ins: usize,
+    pub swapped: usize,
+    pub free_frames: usize,
+    pub clock_pid: usize,
+    pub clock_vpn: usize,
+}
+
+/// 物理内存、内核堆和当前进程的内存使用情况
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct MemInfo {
+    pub total_frames: usize,
+    pub free_frames: usize,
+    pub peak_frames: usize,
+    pub heap_total: usize,
+    pub heap_used: usize,
+    pub mapped_pages: usize,
+    pub swapped_pages: usize,
+    pub huge_pages: usize,
+    pub zero_pages: usize,
+}
+
+/// 文件系统块缓存的统计信息，都是启动以来的累计值
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BlockCacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub evictions: usize,
+    pub writebacks: usize,
+}
+
+/// SlabInfo 中缓存名的长度，包括结尾的 \0
+pub const SLAB_NAME_LEN: usize = 32;
+
+/// 内核 slab 对象缓存的统计信息
+#[repr(C)]
+#[derive(Debug)]
+pub struct SlabInfo {
+    pub name: [u8; SLAB_NAME_LEN],
+    pub object_size: usize,
+    pub objects_per_slab: usize,
+    pub slabs: usize,
+    pub in_use: usize,
+    pub allocs: usize,
+}
+
+impl SlabInfo {
+    pub fn empty() -> Self {
+        Self {
+            name: [0; SLAB_NAME_LEN],
+            object_size: 0,
+            objects_per_slab: 0,
+            slabs: 0,
+            in_use: 0,
+            allocs: 0,
+        }
+    }
+    pub fn name(&self) -> &str {
+        let len = self
+            .name
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(SLAB_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap()
+    }
+}
+
+/// sys_ps 返回的进程状态
+pub const PROC_READY: usize = 1;
+pub const PROC_RUNNING: usize = 2;
+pub const PROC_BLOCKED: usize = 3;
+pub const PROC_ZOMBIE: usize = 4;
+
+/// sys_ps 返回的进程信息
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ProcInfo {
+    pub pid: usize,
+    pub ppid: usize,
+    pub status: usize,
+    pub priority: usize,
+    pub name: [u8; TASK_NAME_LEN],
+    /// 第一次被调度以来经过的时间（毫秒）
+    pub run_time: usize,
+}
+
+impl ProcInfo {
+    pub fn empty() -> Self {
+        Self {
+            pid: 0,
+            ppid: 0,
+            status: 0,
+            priority: 0,
+            name: [0; TASK_NAME_LEN],
+            run_time: 0,
+        }
+    }
+    pub fn name(&self) -> &str {
+        let len = self.name.iter().position(|c| *c == 0).unwrap_or(TASK_NAME_LEN);
+        core::str::from_utf8(&self.name[..len]).unwrap()
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum TaskStatus {
     UnInit,
@@ -119,6 +410,13 @@ pub struct TaskInfo {
     pub status: TaskStatus,
     pub syscall_times: [u32; MAX_SYSCALL_NUM],
     pub time: usize,
+    /// 用户态运行时间（毫秒）
+    pub utime: usize,
+    /// 内核态运行时间（毫秒）
+    pub stime: usize,
+    /// 常驻集大小，即当前占有页帧的用户页面数，以及它曾经达到的最大值
+    pub rss: usize,
+    pub peak_rss: usize,
 }
 
 impl TaskInfo {
@@ -127,6 +425,10 @@ impl TaskInfo {
             status: TaskStatus::UnInit,
             syscall_times: [0; MAX_SYSCALL_NUM],
             time: 0,
+            utime: 0,
+            stime: 0,
+            rss: 0,
+            peak_rss: 0,
         }
     }
 }
@@ -142,8 +444,10 @@ pub struct Stat {
     pub mode: StatMode,
     /// number of hard links
     pub nlink: u32,
+    /// total size in bytes
+    pub size: u64,
     /// unused pad
-    pad: [u64; 7],
+    pad: [u64; 6],
 }
 
 impl Stat {
@@ -153,7 +457,8 @@ impl Stat {
             ino: 0,
             mode: StatMode::NULL,
             nlink: 0,
-            pad: [0; 7],
+            size: 0,
+            pad: [0; 6],
         }
     }
 }
@@ -167,6 +472,10 @@ impl Default for Stat {
 bitflags! {
     pub struct StatMode: u32 {
         const NULL  = 0;
+        /// pipe
+        const FIFO  = 0o010000;
+        /// character device
+        const CHR   = 0o020000;
         /// directory
         const DIR   = 0o040000;
         /// ordinary regular file
@@ -207,6 +516,90 @@ pub fn fstat(fd: usize, st: &Stat) -> isize {
     sys_fstat(fd, st)
 }
 
+/// 把文件 fd 的数据与元数据写回磁盘
+pub fn fsync(fd: usize) -> isize {
+    sys_fsync(fd)
+}
+
+/// 把文件系统中所有修改过的数据写回磁盘，fd 为其中任意一个打开的文件
+pub fn syncfs(fd: usize) -> isize {
+    sys_syncfs(fd)
+}
+
+pub fn mkdir(path: &str) -> isize {
+    sys_mkdirat(AT_FDCWD as usize, path, 0)
+}
+
+/// 创建命名管道
+pub fn mkfifo(path: &str) -> isize {
+    sys_mknodat(AT_FDCWD as usize, path, StatMode::FIFO.bits(), 0)
+}
+
+/// 把 fstype 类型的文件系统挂载到目录 target 上，source 暂不使用
+pub fn mount(source: &str, target: &str, fstype: &str) -> isize {
+    sys_mount(source, target, fstype, 0, 0)
+}
+
+pub fn umount(target: &str) -> isize {
+    sys_umount2(target, 0)
+}
+
+/// 把打开的目录 fd 中的目录项读入 buf，用 [`Dirents`] 逐条解析
+pub fn getdents(fd: usize, buf: &mut [u8]) -> isize {
+    sys_getdents64(fd, buf)
+}
+
+/// getdents 返回的目录项的类型
+pub const DT_FIFO: u8 = 1;
+pub const DT_CHR: u8 = 2;
+pub const DT_DIR: u8 = 4;
+pub const DT_REG: u8 = 8;
+
+/// getdents 填入缓冲区的一条目录项
+pub struct Dirent<'a> {
+    pub ino: u64,
+    pub d_type: u8,
+    pub name: &'a str,
+}
+
+/// 逐条解析 getdents 填入缓冲区的 linux_dirent64 记录
+pub struct Dirents<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Dirents<'a> {
+    /// buf 是 getdents 填入的那部分缓冲区
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+}
+
+impl<'a> Iterator for Dirents<'a> {
+    type Item = Dirent<'a>;
+    fn next(&mut self) -> Option<Dirent<'a>> {
+        if self.buf.len() < 19 {
+            return None;
+        }
+        let ino = u64::from_ne_bytes(self.buf[0..8].try_into().unwrap());
+        let reclen = u16::from_ne_bytes(self.buf[16..18].try_into().unwrap()) as usize;
+        let d_type = self.buf[18];
+        let name = &self.buf[19..reclen];
+        let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+        let name = core::str::from_utf8(&name[..len]).unwrap();
+        self.buf = &self.buf[reclen..];
+        Some(Dirent { ino, d_type, name })
+    }
+}
+
+/// lseek 的 whence：从文件开头、当前位置、文件末尾开始计算偏移
+pub const SEEK_SET: usize = 0;
+pub const SEEK_CUR: usize = 1;
+pub const SEEK_END: usize = 2;
+
+pub fn lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    sys_lseek(fd, offset, whence)
+}
+
 pub fn mail_read(buf: &mut [u8]) -> isize {
     sys_mail_read(buf)
 }
@@ -232,48 +625,312 @@ pub fn get_time() -> isize {
     }
 }
 
+pub fn clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    sys_clock_gettime(clock_id, tp)
+}
+
 pub fn getpid() -> isize {
     sys_getpid()
 }
 
+pub fn getppid() -> isize {
+    sys_getppid()
+}
+
+/// 返回启动以来经过的时间（微秒）
+pub fn times(tms: &mut Tms) -> isize {
+    sys_times(tms)
+}
+
+pub fn getrlimit(resource: usize, rlim: &mut Rlimit) -> isize {
+    sys_getrlimit(resource, rlim)
+}
+
+pub fn setrlimit(resource: usize, rlim: &Rlimit) -> isize {
+    sys_setrlimit(resource, rlim)
+}
+
+pub const PR_SET_NAME: usize = 15;
+pub const PR_GET_NAME: usize = 16;
+/// 进程名的最大长度（字节，含结尾的 \0）
+pub const TASK_NAME_LEN: usize = 32;
+
+/// name 必须以 \0 结尾，过长的名字会被内核截断
+pub fn set_name(name: &str) -> isize {
+    sys_prctl(PR_SET_NAME, name.as_ptr() as usize)
+}
+
+pub fn get_name(buf: &mut [u8; TASK_NAME_LEN]) -> isize {
+    sys_prctl(PR_GET_NAME, buf.as_mut_ptr() as usize)
+}
+
+/// getrandom 标志：熵不足时不阻塞
+pub const GRND_NONBLOCK: usize = 1;
+/// getrandom 标志：使用阻塞的熵源
+pub const GRND_RANDOM: usize = 2;
+
+/// 用随机字节填满 buf，返回写入的字节数
+pub fn getrandom(buf: &mut [u8], flags: usize) -> isize {
+    sys_getrandom(buf, flags)
+}
+
+/// 系统调用过滤模式：被拒绝的系统调用返回 -EPERM
+pub const SECCOMP_MODE_ERRNO: usize = 0;
+/// 系统调用过滤模式：调用被拒绝的系统调用时以 SIGSYS 终止进程
+pub const SECCOMP_MODE_KILL: usize = 1;
+/// 过滤器位图的最大字节数
+pub const SECCOMP_BITMAP_BYTES: usize = (MAX_SYSCALL_NUM + 7) / 8;
+
+/// 只允许 allowed 中第 i / 8 字节的第 i % 8 位为 1 的系统调用 i，已有过滤器时只能进一步收紧
+pub fn seccomp(mode: usize, allowed: &[u8]) -> isize {
+    sys_seccomp(mode, allowed)
+}
+
 pub fn fork() -> isize {
     sys_fork()
 }
 
+/// clone 标志：在当前进程中创建新线程
+pub const CLONE_VM: usize = 0x100;
+/// clone 标志：把新执行流的 tp 设置为参数 tls
+pub const CLONE_SETTLS: usize = 0x80000;
+
+/// flags 不含 CLONE_VM 时等同于 fork；含 CLONE_VM 时创建一个从 clone 返回处继续执行的线程，
+/// stack 为 0 时使用内核分配的用户栈；含 CLONE_SETTLS 时新执行流的 tp 为 tls。新的执行流中返回 0
+pub fn clone(flags: usize, stack: usize, tls: usize) -> isize {
+    sys_clone(flags, stack, tls)
+}
+
 pub fn exec(path: &str, args: &[*const u8]) -> isize {
     sys_exec(path, args)
 }
 
+/// envp 是以空指针结尾、指向形如 "NAME=value\0" 的字符串的指针数组
+pub fn execve(path: &str, args: &[*const u8], envp: &[*const u8]) -> isize {
+    sys_execve(path, args, envp)
+}
+
+/// 当前进程的全部环境变量，每一项形如 "NAME=value"
+pub fn environ() -> Vec<&'static str> {
+    let mut v = Vec::new();
+    let envp = unsafe { ENVP };
+    if envp == 0 {
+        return v;
+    }
+    for i in 0.. {
+        let str_start =
+            unsafe { ((envp + i * core::mem::size_of::<usize>()) as *const usize).read_volatile() };
+        if str_start == 0 {
+            break;
+        }
+        let len = (0usize..)
+            .find(|i| unsafe { ((str_start + *i) as *const u8).read_volatile() == 0 })
+            .unwrap();
+        v.push(
+            core::str::from_utf8(unsafe {
+                core::slice::from_raw_parts(str_start as *const u8, len)
+            })
+            .unwrap(),
+        );
+    }
+    v
+}
+
+/// 查找名为 name 的环境变量
+pub fn getenv(name: &str) -> Option<&'static str> {
+    environ().into_iter().find_map(|env| {
+        let (key, value) = env.split_once('=')?;
+        if key == name {
+            Some(value)
+        } else {
+            None
+        }
+    })
+}
+
 pub fn set_priority(prio: isize) -> isize {
     sys_set_priority(prio)
 }
 
+pub const LINUX_REBOOT_MAGIC1: usize = 0xfee1dead;
+pub const LINUX_REBOOT_MAGIC2: usize = 672274793;
+pub const LINUX_REBOOT_CMD_RESTART: usize = 0x01234567;
+pub const LINUX_REBOOT_CMD_POWER_OFF: usize = 0x4321fedc;
+
+/// 关机，只有 initproc 以及由它直接创建的进程可以调用；成功时不会返回
+pub fn shutdown() -> isize {
+    sys_reboot(
+        LINUX_REBOOT_MAGIC1,
+        LINUX_REBOOT_MAGIC2,
+        LINUX_REBOOT_CMD_POWER_OFF,
+    )
+}
+/// 重启，权限要求同 shutdown
+pub fn reboot() -> isize {
+    sys_reboot(LINUX_REBOOT_MAGIC1, LINUX_REBOOT_MAGIC2, LINUX_REBOOT_CMD_RESTART)
+}
+
+pub const SYSLOG_ACTION_READ_ALL: usize = 3;
+pub const SYSLOG_ACTION_READ_CLEAR: usize = 4;
+pub const SYSLOG_ACTION_CLEAR: usize = 5;
+pub const SYSLOG_ACTION_SIZE_UNREAD: usize = 9;
+pub const SYSLOG_ACTION_SIZE_BUFFER: usize = 10;
+
+/// 读取最近的内核日志到 buf 中，返回读到的字节数
+pub fn dmesg(buf: &mut [u8]) -> isize {
+    sys_syslog(SYSLOG_ACTION_READ_ALL, buf)
+}
+/// 清空内核日志，权限要求同 shutdown
+pub fn dmesg_clear() -> isize {
+    sys_syslog(SYSLOG_ACTION_CLEAR, &mut [])
+}
+/// 内核日志缓冲区中的字节数
+pub fn dmesg_len() -> isize {
+    sys_syslog(SYSLOG_ACTION_SIZE_UNREAD, &mut [])
+}
+
+pub fn sched_setscheduler(pid: usize, policy: usize, rt_priority: i32) -> isize {
+    sys_sched_setscheduler(
+        pid,
+        policy,
+        &SchedParam {
+            sched_priority: rt_priority,
+        },
+    )
+}
+
+pub fn sched_getscheduler(pid: usize) -> isize {
+    sys_sched_getscheduler(pid)
+}
+
+pub fn sched_setaffinity(pid: usize, mask: usize) -> isize {
+    sys_sched_setaffinity(pid, &mask)
+}
+
+pub fn sched_getaffinity(pid: usize, mask: &mut usize) -> isize {
+    sys_sched_getaffinity(pid, mask)
+}
+
+/// 等待任意一个子进程结束，被信号打断时重新等待；没有子进程时返回 -ECHILD
 pub fn wait(exit_code: &mut i32) -> isize {
-    loop {
-        match sys_waitpid(-1, exit_code as *mut _) {
-            -2 => {
-                sys_yield();
-            }
-            n => {
-                return n;
-            }
-        }
-    }
+    waitpid_blocking(-1, exit_code)
 }
 
+/// 等待指定的子进程结束，被信号打断时重新等待；该子进程不存在时返回 -ECHILD
 pub fn waitpid(pid: usize, exit_code: &mut i32) -> isize {
+    waitpid_blocking(pid as isize, exit_code)
+}
+
+/// 被信号打断的系统调用返回 -EINTR
+const EINTR: isize = -4;
+
+fn waitpid_blocking(pid: isize, exit_code: &mut i32) -> isize {
     loop {
-        match sys_waitpid(pid as isize, exit_code as *mut _) {
-            -2 => {
-                sys_yield();
-            }
-            n => {
-                return n;
-            }
+        match sys_waitpid(pid, exit_code as *mut _, 0) {
+            EINTR => continue,
+            n => return n,
         }
     }
 }
 
+/// 不阻塞地检查子进程，pid 为 -1 时检查任意子进程；
+/// 没有符合条件的子进程时返回 -ECHILD，子进程均未结束时返回 0
+pub fn waitpid_nohang(pid: isize, exit_code: &mut i32) -> isize {
+    sys_waitpid(pid, exit_code as *mut _, WNOHANG)
+}
+
+/// 等待子进程结束并取得它的资源使用情况，pid 与 options 的含义同 waitpid 系统调用
+pub fn wait4(pid: isize, exit_code: &mut i32, options: usize, rusage: &mut Rusage) -> isize {
+    sys_wait4(pid, exit_code as *mut _, options, rusage as *mut _)
+}
+
+/// pid 为 0 时发送给当前进程组，小于 -1 时发送给进程组 -pid
+pub fn kill(pid: isize, signum: i32) -> isize {
+    sys_kill(pid, signum)
+}
+
+/// ptrace 请求：当前进程由父进程跟踪，此后执行 ebreak 或 exec 成功时停下
+pub const PTRACE_TRACEME: usize = 0;
+/// ptrace 请求：读出子进程地址 addr 处的 8 字节，保存到 data 指向的位置
+pub const PTRACE_PEEKDATA: usize = 2;
+/// ptrace 请求：读出编号为 addr 的寄存器（0 为 pc，1~31 为 x1~x31），保存到 data 指向的位置
+pub const PTRACE_PEEKUSER: usize = 3;
+/// ptrace 请求：把 data 写入子进程地址 addr 处的 8 字节
+pub const PTRACE_POKEDATA: usize = 5;
+/// ptrace 请求：把 data 写入编号为 addr 的寄存器
+pub const PTRACE_POKEUSER: usize = 6;
+/// ptrace 请求：让子进程继续运行，data 不为 0 时同时发送编号为 data 的信号
+pub const PTRACE_CONT: usize = 7;
+/// ptrace 请求：终止子进程
+pub const PTRACE_KILL: usize = 8;
+/// ptrace 请求：让子进程执行一条指令后再次停下，data 的含义同 PTRACE_CONT
+pub const PTRACE_SINGLESTEP: usize = 9;
+
+/// 跟踪子进程，除 PTRACE_TRACEME 之外 pid 必须是当前进程跟踪的、已经停下的子进程
+pub fn ptrace(request: usize, pid: usize, addr: usize, data: usize) -> isize {
+    sys_ptrace(request, pid, addr, data)
+}
+
+/// waitpid 得到的状态表示被跟踪的子进程停了下来，而不是结束
+pub fn wifstopped(status: i32) -> bool {
+    status & 0xff == 0x7f
+}
+
+/// 被跟踪的子进程停下的原因（信号编号）
+pub fn wstopsig(status: i32) -> i32 {
+    status >> 8 & 0xff
+}
+
+pub fn setpgid(pid: usize, pgid: usize) -> isize {
+    sys_setpgid(pid, pgid)
+}
+
+pub fn getpgid(pid: usize) -> isize {
+    sys_getpgid(pid)
+}
+
+pub fn getsid(pid: usize) -> isize {
+    sys_getsid(pid)
+}
+
+pub fn setsid() -> isize {
+    sys_setsid()
+}
+
+pub fn uname(buf: &mut UtsName) -> isize {
+    sys_uname(buf)
+}
+
+pub fn sysinfo(info: &mut SysInfo) -> isize {
+    sys_sysinfo(info)
+}
+
+/// utsname 中的一个字段去掉结尾的 \0 之后的字符串
+pub fn uts_str(field: &[u8; UTSNAME_LEN]) -> &str {
+    let len = field.iter().position(|&c| c == 0).unwrap_or(UTSNAME_LEN);
+    core::str::from_utf8(&field[..len]).unwrap()
+}
+
+pub fn sigaction(
+    signum: i32,
+    action: Option<&SignalAction>,
+    old_action: Option<&mut SignalAction>,
+) -> isize {
+    sys_sigaction(
+        signum,
+        action.map_or(core::ptr::null(), |a| a),
+        old_action.map_or(core::ptr::null_mut(), |a| a),
+    )
+}
+
+pub fn sigprocmask(mask: u32) -> isize {
+    sys_sigprocmask(mask)
+}
+
+pub fn sigreturn() -> isize {
+    sys_sigreturn()
+}
+
 pub fn sleep_blocking(sleep_ms: usize) {
     sys_sleep(sleep_ms);
 }
@@ -284,21 +941,221 @@ pub fn sleep(period_ms: usize) {
         sys_yield();
     }
 }
+/// start 为按页对齐的非零地址时映射到 start，成功返回 0；
+/// start 为 0 或未按页对齐时由内核选择映射的位置并返回该地址。失败时返回 -1，
+/// prot 同时包含可写与可执行（W^X）时同样失败
 pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
-    sys_mmap(start, len, prot)
+    sys_mmap(start, len, prot, MAP_PRIVATE)
+}
+
+/// mmap 的标志：映射在 fork 之后由父子进程共享
+pub const MAP_SHARED: usize = 0x01;
+/// mmap 的标志：映射在 fork 时复制，即 mmap 的默认行为
+pub const MAP_PRIVATE: usize = 0x02;
+
+/// 与 mmap 相同，flags 为 MAP_SHARED 时父子进程共享这段映射中的内存
+pub fn mmap_with_flags(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    sys_mmap(start, len, prot, flags)
 }
 
 pub fn munmap(start: usize, len: usize) -> isize {
     sys_munmap(start, len)
 }
 
+/// mremap 的标志：原地无法扩大时允许移动到新的地址
+pub const MREMAP_MAYMOVE: usize = 1;
+
+/// 将 mmap 得到的 [old_addr, old_addr + old_size) 调整为 new_size 字节，返回调整后的地址，失败时返回 -1
+pub fn mremap(old_addr: usize, old_size: usize, new_size: usize, flags: usize) -> isize {
+    sys_mremap(old_addr, old_size, new_size, flags)
+}
+
+/// 不与任何键关联、总是创建新段的键
+pub const IPC_PRIVATE: usize = 0;
+/// shmget 的标志：键不存在时创建
+pub const IPC_CREAT: usize = 0o1000;
+/// shmget 的标志：与 IPC_CREAT 一起使用，键已存在时失败
+pub const IPC_EXCL: usize = 0o2000;
+/// shmat 的标志：只读映射
+pub const SHM_RDONLY: usize = 0o10000;
+/// shmctl 的命令：删除共享内存段
+pub const IPC_RMID: usize = 0;
+/// shmctl 的命令：查询共享内存段的状态
+pub const IPC_STAT: usize = 2;
+
+/// 共享内存段的状态
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShmStat {
+    /// 段的大小（字节）
+    pub size: usize,
+    /// 当前映射了这个段的次数
+    pub nattch: usize,
+}
+
+/// 按键查找或创建共享内存段，返回段的编号，失败时返回错误码的相反数
+pub fn shmget(key: usize, size: usize, flags: usize) -> isize {
+    sys_shmget(key, size, flags)
+}
+
+/// 将共享内存段映射到 addr（为 0 时由内核选择），返回映射的地址，失败时返回错误码的相反数
+pub fn shmat(id: usize, addr: usize, flags: usize) -> isize {
+    sys_shmat(id, addr, flags)
+}
+
+/// 解除 shmat 在 addr 处建立的映射
+pub fn shmdt(addr: usize) -> isize {
+    sys_shmdt(addr)
+}
+
+/// 删除共享内存段（IPC_RMID）或将其状态写入 buf（IPC_STAT）
+pub fn shmctl(id: usize, cmd: usize, buf: &mut ShmStat) -> isize {
+    sys_shmctl(id, cmd, buf as *mut ShmStat)
+}
+
+/// 修改 [start, start + len) 中已映射页面的权限，prot 的编码与 mmap 相同，同样不能既可写又可执行
+pub fn mprotect(start: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(start, len, prot)
+}
+
+/// 将堆扩大或缩小 size 字节，返回调整之前的堆顶，失败时返回 -1
+pub fn sbrk(size: i32) -> isize {
+    sys_sbrk(size)
+}
+
+/// 将堆顶设置为 addr，成功时返回 0，失败时返回 -1
+pub fn brk(addr: usize) -> isize {
+    let diff = addr as isize - sbrk(0);
+    if diff < i32::MIN as isize || diff > i32::MAX as isize || sbrk(diff as i32) < 0 {
+        return -1;
+    }
+    0
+}
+
 pub fn spawn(path: &str) -> isize {
-    sys_spawn(path)
+    sys_spawn(path, core::ptr::null(), core::ptr::null(), &[])
+}
+
+/// args 与 exec 相同，是以空指针结尾、指向 \0 结尾字符串的指针数组
+pub fn spawn_args(path: &str, args: &[*const u8]) -> isize {
+    sys_spawn(path, args.as_ptr(), core::ptr::null(), &[])
+}
+
+/// 以指定的命令行参数与环境变量创建子进程，args 与 envp 的格式同 execve
+pub fn spawnve(path: &str, args: &[*const u8], envp: &[*const u8]) -> isize {
+    sys_spawn(path, args.as_ptr(), envp.as_ptr(), &[])
+}
+
+/// spawn 的一项重定向：子进程的文件描述符 child_fd 指向父进程的文件描述符 parent_fd 所指的文件
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SpawnRedirect {
+    pub child_fd: usize,
+    pub parent_fd: usize,
+}
+
+/// 创建子进程并按 redirects 重定向它的文件描述符，args 的格式同 spawn_args
+pub fn spawn_redirect(path: &str, args: &[*const u8], redirects: &[SpawnRedirect]) -> isize {
+    sys_spawn(path, args.as_ptr(), core::ptr::null(), redirects)
 }
 
 pub fn dup(fd: usize) -> isize {
     sys_dup(fd)
 }
+
+/// fcntl 的命令
+pub const F_DUPFD: usize = 0;
+pub const F_GETFL: usize = 3;
+pub const F_SETFL: usize = 4;
+
+pub fn fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    sys_fcntl(fd, cmd, arg)
+}
+
+/// ppoll 等待的一项，与内核中的 PollFd 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PollFd {
+    pub fd: i32,
+    /// 关心的事件
+    pub events: i16,
+    /// 就绪的事件，由内核填入
+    pub revents: i16,
+}
+
+impl PollFd {
+    pub fn new(fd: usize, events: i16) -> Self {
+        Self {
+            fd: fd as i32,
+            events,
+            revents: 0,
+        }
+    }
+}
+
+/// ppoll 的事件：可读、可写、出错、对端关闭、文件描述符没有打开
+pub const POLLIN: i16 = 0x001;
+pub const POLLOUT: i16 = 0x004;
+pub const POLLERR: i16 = 0x008;
+pub const POLLHUP: i16 = 0x010;
+pub const POLLNVAL: i16 = 0x020;
+
+/// 等待 fds 中任意一个文件就绪，返回就绪的项数，超时返回 0；timeout 为 None 时一直等待
+pub fn ppoll(fds: &mut [PollFd], timeout: Option<&TimeSpec>) -> isize {
+    sys_ppoll(fds, timeout.map_or(core::ptr::null(), |t| t as *const _))
+}
+
+/// 与 ppoll 相同，超时时间以毫秒为单位，为负数时一直等待
+pub fn poll(fds: &mut [PollFd], timeout_ms: isize) -> isize {
+    if timeout_ms < 0 {
+        return ppoll(fds, None);
+    }
+    let timeout = TimeSpec {
+        tv_sec: timeout_ms as usize / 1000,
+        tv_nsec: timeout_ms as usize % 1000 * 1_000_000,
+    };
+    ppoll(fds, Some(&timeout))
+}
+
+pub fn ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    sys_ioctl(fd, request, arg)
+}
+
+/// ioctl 的请求：读取与设置终端属性
+pub const TCGETS: usize = 0x5401;
+pub const TCSETS: usize = 0x5402;
+
+/// c_lflag：按行编辑的规范模式
+pub const ICANON: u32 = 0o2;
+/// c_lflag：回显输入
+pub const ECHO: u32 = 0o10;
+
+/// 终端属性，与内核中的 Termios 一致，目前只有 c_lflag 中的 ICANON 与 ECHO 起作用
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Termios {
+    pub c_iflag: u32,
+    pub c_oflag: u32,
+    pub c_cflag: u32,
+    pub c_lflag: u32,
+    pub c_line: u8,
+    pub c_cc: [u8; 19],
+}
+
+/// 读取 fd 所在终端的属性
+pub fn tcgetattr(fd: usize, termios: &mut Termios) -> isize {
+    ioctl(fd, TCGETS, termios as *mut _ as usize)
+}
+
+/// 设置 fd 所在终端的属性
+pub fn tcsetattr(fd: usize, termios: &Termios) -> isize {
+    ioctl(fd, TCSETS, termios as *const _ as usize)
+}
+
+/// 把 old_fd 复制到 new_fd，new_fd 原来指向的文件先被关闭
+pub fn dup2(old_fd: usize, new_fd: usize) -> isize {
+    sys_dup2(old_fd, new_fd)
+}
 pub fn pipe(pipe_fd: &mut [usize]) -> isize {
     sys_pipe(pipe_fd)
 }
@@ -307,12 +1164,75 @@ pub fn task_info(info: &TaskInfo) -> isize {
     sys_task_info(info)
 }
 
+pub fn switch_trace(records: &mut [SwitchRecord]) -> isize {
+    sys_switch_trace(records)
+}
+
+pub fn sched_stats(stats: &mut SchedStats) -> isize {
+    sys_sched_stats(stats)
+}
+
+pub fn swap_stats(stats: &mut SwapStats) -> isize {
+    sys_swap_stats(stats)
+}
+
+pub fn meminfo(info: &mut MemInfo) -> isize {
+    sys_meminfo(info)
+}
+
+pub fn block_cache_stats(stats: &mut BlockCacheStats) -> isize {
+    sys_block_cache_stats(stats)
+}
+
+/// 获取内核各个 slab 缓存的统计信息，返回写入 infos 的条数
+pub fn slab_stats(infos: &mut [SlabInfo]) -> isize {
+    sys_slab_stats(infos)
+}
+
+/// 列出所有尚未被回收的进程，返回写入 procs 的条数
+pub fn ps(procs: &mut [ProcInfo]) -> isize {
+    sys_ps(procs)
+}
+
 pub fn thread_create(entry: usize, arg: usize) -> isize {
     sys_thread_create(entry, arg)
 }
 pub fn gettid() -> isize {
     sys_gettid()
 }
+/// 登记线程退出时由内核清零的 32 位变量，传入空指针取消登记，返回当前线程号
+pub fn set_tid_address(tidptr: *mut u32) -> isize {
+    sys_set_tid_address(tidptr)
+}
+pub const FUTEX_WAIT: usize = 0;
+pub const FUTEX_WAKE: usize = 1;
+
+/// futex 当前值仍为 expected 时阻塞，直到被 futex_wake 唤醒；值已改变时立即返回 -1
+pub fn futex_wait(futex: &AtomicU32, expected: u32) -> isize {
+    sys_futex(
+        futex as *const AtomicU32 as *const u32,
+        FUTEX_WAIT,
+        expected as usize,
+    )
+}
+/// 唤醒至多 count 个在 futex 上等待的线程，返回被唤醒的线程数
+pub fn futex_wake(futex: &AtomicU32, count: usize) -> isize {
+    sys_futex(futex as *const AtomicU32 as *const u32, FUTEX_WAKE, count)
+}
+/// 读取 tp 寄存器，内核在 Trap 前后保持它不变，线程库可以用它指向线程局部存储
+pub fn thread_pointer() -> usize {
+    let tp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, tp", out(reg) tp);
+    }
+    tp
+}
+/// 设置 tp 寄存器
+pub fn set_thread_pointer(tp: usize) {
+    unsafe {
+        core::arch::asm!("mv tp, {}", in(reg) tp);
+    }
+}
 pub fn waittid(tid: usize) -> isize {
     loop {
         match sys_waittid(tid) {
@@ -333,8 +1253,8 @@ pub fn mutex_blocking_create() -> isize {
 pub fn mutex_lock(mutex_id: usize) -> isize {
     sys_mutex_lock(mutex_id)
 }
-pub fn mutex_unlock(mutex_id: usize) {
-    sys_mutex_unlock(mutex_id);
+pub fn mutex_unlock(mutex_id: usize) -> isize {
+    sys_mutex_unlock(mutex_id)
 }
 pub fn semaphore_create(res_count: usize) -> isize {
     sys_semaphore_create(res_count)
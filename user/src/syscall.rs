@@ -1,32 +1,90 @@
 use crate::TaskInfo;
 
-use super::{Stat, TimeVal};
+use super::{
+    BlockCacheStats, MemInfo, PollFd, ProcInfo, Rlimit, Rusage, SchedParam, SchedStats, ShmStat,
+    SignalAction, SlabInfo, SpawnRedirect, Stat, SwapStats, SwitchRecord, SysInfo, TimeSpec,
+    TimeVal, Tms, UtsName,
+};
 
 pub const SYSCALL_OPENAT: usize = 56;
 pub const SYSCALL_CLOSE: usize = 57;
+pub const SYSCALL_GETDENTS64: usize = 61;
+pub const SYSCALL_LSEEK: usize = 62;
 pub const SYSCALL_READ: usize = 63;
 pub const SYSCALL_WRITE: usize = 64;
+pub const SYSCALL_MKNODAT: usize = 33;
+pub const SYSCALL_MKDIRAT: usize = 34;
 pub const SYSCALL_UNLINKAT: usize = 35;
 pub const SYSCALL_LINKAT: usize = 37;
+pub const SYSCALL_UMOUNT2: usize = 39;
+pub const SYSCALL_MOUNT: usize = 40;
+pub const SYSCALL_PPOLL: usize = 73;
 pub const SYSCALL_FSTAT: usize = 80;
+pub const SYSCALL_FSYNC: usize = 82;
+pub const SYSCALL_SYNCFS: usize = 267;
 pub const SYSCALL_EXIT: usize = 93;
+pub const SYSCALL_SET_TID_ADDRESS: usize = 96;
+pub const SYSCALL_FUTEX: usize = 98;
 pub const SYSCALL_SLEEP: usize = 101;
+pub const SYSCALL_CLOCK_GETTIME: usize = 113;
+pub const SYSCALL_SYSLOG: usize = 116;
+pub const SYSCALL_PTRACE: usize = 117;
+pub const SYSCALL_KILL: usize = 129;
+pub const SYSCALL_SIGACTION: usize = 134;
+pub const SYSCALL_SIGPROCMASK: usize = 135;
+pub const SYSCALL_SIGRETURN: usize = 139;
+pub const SYSCALL_TIMES: usize = 153;
+pub const SYSCALL_SETPGID: usize = 154;
+pub const SYSCALL_GETPGID: usize = 155;
+pub const SYSCALL_GETSID: usize = 156;
+pub const SYSCALL_SETSID: usize = 157;
+pub const SYSCALL_UNAME: usize = 160;
+pub const SYSCALL_SCHED_SETSCHEDULER: usize = 119;
+pub const SYSCALL_SCHED_GETSCHEDULER: usize = 120;
+pub const SYSCALL_SCHED_SETAFFINITY: usize = 122;
+pub const SYSCALL_SCHED_GETAFFINITY: usize = 123;
 pub const SYSCALL_YIELD: usize = 124;
 pub const SYSCALL_GETTIMEOFDAY: usize = 169;
+pub const SYSCALL_GETRLIMIT: usize = 163;
+pub const SYSCALL_SETRLIMIT: usize = 164;
+pub const SYSCALL_PRCTL: usize = 167;
+pub const SYSCALL_SECCOMP: usize = 277;
+pub const SYSCALL_GETRANDOM: usize = 278;
 pub const SYSCALL_GETPID: usize = 172;
+pub const SYSCALL_GETPPID: usize = 173;
 pub const SYSCALL_GETTID: usize = 178;
+pub const SYSCALL_SYSINFO: usize = 179;
+pub const SYSCALL_SHMGET: usize = 194;
+pub const SYSCALL_SHMCTL: usize = 195;
+pub const SYSCALL_SHMAT: usize = 196;
+pub const SYSCALL_SHMDT: usize = 197;
 pub const SYSCALL_FORK: usize = 220;
+pub const SYSCALL_CLONE: usize = 220;
 pub const SYSCALL_EXEC: usize = 221;
-pub const SYSCALL_WAITPID: usize = 260;
+pub const SYSCALL_WAIT4: usize = 260;
 pub const SYSCALL_SET_PRIORITY: usize = 140;
+pub const SYSCALL_REBOOT: usize = 142;
+pub const SYSCALL_SBRK: usize = 214;
 pub const SYSCALL_MUNMAP: usize = 215;
+pub const SYSCALL_MREMAP: usize = 216;
 pub const SYSCALL_MMAP: usize = 222;
+pub const SYSCALL_MPROTECT: usize = 226;
 pub const SYSCALL_SPAWN: usize = 400;
 pub const SYSCALL_MAIL_READ: usize = 401;
 pub const SYSCALL_MAIL_WRITE: usize = 402;
+pub const SYSCALL_DUP2: usize = 23;
 pub const SYSCALL_DUP: usize = 24;
+pub const SYSCALL_FCNTL: usize = 25;
+pub const SYSCALL_IOCTL: usize = 29;
 pub const SYSCALL_PIPE: usize = 59;
 pub const SYSCALL_TASK_INFO: usize = 410;
+pub const SYSCALL_SWITCH_TRACE: usize = 411;
+pub const SYSCALL_SCHED_STATS: usize = 412;
+pub const SYSCALL_PS: usize = 413;
+pub const SYSCALL_SWAP_STATS: usize = 414;
+pub const SYSCALL_SLAB_STATS: usize = 415;
+pub const SYSCALL_MEMINFO: usize = 416;
+pub const SYSCALL_BLOCK_CACHE_STATS: usize = 417;
 pub const SYSCALL_THREAD_CREATE: usize = 460;
 pub const SYSCALL_WAITTID: usize = 462;
 pub const SYSCALL_MUTEX_CREATE: usize = 463;
@@ -40,18 +98,9 @@ pub const SYSCALL_CONDVAR_CREATE: usize = 471;
 pub const SYSCALL_CONDVAR_SIGNAL: usize = 472;
 pub const SYSCALL_CONDVAR_WAIT: usize = 473;
 
+/// 其余参数寄存器置 0，内核读取可选的参数（如 spawn 的重定向表）时不会读到残留的值
 pub fn syscall(id: usize, args: [usize; 3]) -> isize {
-    let mut ret: isize;
-    unsafe {
-        core::arch::asm!(
-            "ecall",
-            inlateout("x10") args[0] => ret,
-            in("x11") args[1],
-            in("x12") args[2],
-            in("x17") id
-        );
-    }
-    ret
+    syscall6(id, [args[0], args[1], args[2], 0, 0, 0])
 }
 
 pub fn syscall6(id: usize, args: [usize; 6]) -> isize {
@@ -123,10 +172,57 @@ pub fn sys_unlinkat(dirfd: usize, path: &str, flags: usize) -> isize {
     syscall(SYSCALL_UNLINKAT, [dirfd, path.as_ptr() as usize, flags])
 }
 
+pub fn sys_mknodat(dirfd: usize, path: &str, mode: u32, dev: usize) -> isize {
+    syscall6(SYSCALL_MKNODAT, [dirfd, path.as_ptr() as usize, mode as usize, dev, 0, 0])
+}
+
+pub fn sys_mkdirat(dirfd: usize, path: &str, mode: u32) -> isize {
+    syscall(SYSCALL_MKDIRAT, [dirfd, path.as_ptr() as usize, mode as usize])
+}
+
+pub fn sys_getdents64(fd: usize, buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETDENTS64, [fd, buf.as_mut_ptr() as usize, buf.len()])
+}
+
+pub fn sys_mount(source: &str, target: &str, fstype: &str, flags: usize, data: usize) -> isize {
+    syscall6(
+        SYSCALL_MOUNT,
+        [
+            source.as_ptr() as usize,
+            target.as_ptr() as usize,
+            fstype.as_ptr() as usize,
+            flags,
+            data,
+            0,
+        ],
+    )
+}
+
+pub fn sys_umount2(target: &str, flags: usize) -> isize {
+    syscall(SYSCALL_UMOUNT2, [target.as_ptr() as usize, flags, 0])
+}
+
 pub fn sys_fstat(fd: usize, st: &Stat) -> isize {
     syscall(SYSCALL_FSTAT, [fd, st as *const _ as usize, 0])
 }
 
+pub fn sys_ppoll(fds: &mut [PollFd], timeout: *const TimeSpec) -> isize {
+    let (ptr, len) = (fds.as_mut_ptr() as usize, fds.len());
+    syscall6(SYSCALL_PPOLL, [ptr, len, timeout as usize, 0, 0, 0])
+}
+
+pub fn sys_fsync(fd: usize) -> isize {
+    syscall(SYSCALL_FSYNC, [fd, 0, 0])
+}
+
+pub fn sys_syncfs(fd: usize) -> isize {
+    syscall(SYSCALL_SYNCFS, [fd, 0, 0])
+}
+
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    syscall(SYSCALL_LSEEK, [fd, offset as usize, whence])
+}
+
 pub fn sys_mail_read(buffer: &mut [u8]) -> isize {
     syscall(
         SYSCALL_MAIL_READ,
@@ -146,6 +242,57 @@ pub fn sys_exit(exit_code: i32) -> ! {
     panic!("sys_exit never returns!");
 }
 
+pub fn sys_kill(pid: isize, signum: i32) -> isize {
+    syscall(SYSCALL_KILL, [pid as usize, signum as usize, 0])
+}
+
+pub fn sys_ptrace(request: usize, pid: usize, addr: usize, data: usize) -> isize {
+    syscall6(SYSCALL_PTRACE, [request, pid, addr, data, 0, 0])
+}
+
+pub fn sys_setpgid(pid: usize, pgid: usize) -> isize {
+    syscall(SYSCALL_SETPGID, [pid, pgid, 0])
+}
+
+pub fn sys_getpgid(pid: usize) -> isize {
+    syscall(SYSCALL_GETPGID, [pid, 0, 0])
+}
+
+pub fn sys_getsid(pid: usize) -> isize {
+    syscall(SYSCALL_GETSID, [pid, 0, 0])
+}
+
+pub fn sys_setsid() -> isize {
+    syscall(SYSCALL_SETSID, [0, 0, 0])
+}
+
+pub fn sys_uname(buf: &mut UtsName) -> isize {
+    syscall(SYSCALL_UNAME, [buf as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_sysinfo(info: &mut SysInfo) -> isize {
+    syscall(SYSCALL_SYSINFO, [info as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_sigaction(
+    signum: i32,
+    action: *const SignalAction,
+    old_action: *mut SignalAction,
+) -> isize {
+    syscall(
+        SYSCALL_SIGACTION,
+        [signum as usize, action as usize, old_action as usize],
+    )
+}
+
+pub fn sys_sigprocmask(mask: u32) -> isize {
+    syscall(SYSCALL_SIGPROCMASK, [mask as usize, 0, 0])
+}
+
+pub fn sys_sigreturn() -> isize {
+    syscall(SYSCALL_SIGRETURN, [0, 0, 0])
+}
+
 pub fn sys_sleep(sleep_ms: usize) -> isize {
     syscall(SYSCALL_SLEEP, [sleep_ms, 0, 0])
 }
@@ -158,14 +305,64 @@ pub fn sys_get_time(time: &TimeVal, tz: usize) -> isize {
     syscall(SYSCALL_GETTIMEOFDAY, [time as *const _ as usize, tz, 0])
 }
 
+pub fn sys_clock_gettime(clock_id: usize, tp: &mut TimeSpec) -> isize {
+    syscall(SYSCALL_CLOCK_GETTIME, [clock_id, tp as *mut _ as usize, 0])
+}
+
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
+pub fn sys_times(tms: &mut Tms) -> isize {
+    syscall(SYSCALL_TIMES, [tms as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_getrlimit(resource: usize, rlim: &mut Rlimit) -> isize {
+    syscall(SYSCALL_GETRLIMIT, [resource, rlim as *mut _ as usize, 0])
+}
+
+pub fn sys_setrlimit(resource: usize, rlim: &Rlimit) -> isize {
+    syscall(SYSCALL_SETRLIMIT, [resource, rlim as *const _ as usize, 0])
+}
+
+pub fn sys_prctl(option: usize, arg2: usize) -> isize {
+    syscall(SYSCALL_PRCTL, [option, arg2, 0])
+}
+
+pub fn sys_getrandom(buf: &mut [u8], flags: usize) -> isize {
+    syscall(
+        SYSCALL_GETRANDOM,
+        [buf.as_mut_ptr() as usize, buf.len(), flags],
+    )
+}
+
+pub fn sys_seccomp(mode: usize, allowed: &[u8]) -> isize {
+    syscall(
+        SYSCALL_SECCOMP,
+        [mode, allowed.as_ptr() as usize, allowed.len()],
+    )
+}
+
+pub fn sys_getppid() -> isize {
+    syscall(SYSCALL_GETPPID, [0, 0, 0])
+}
+
 pub fn sys_fork() -> isize {
     syscall(SYSCALL_FORK, [0, 0, 0])
 }
 
+pub fn sys_clone(flags: usize, stack: usize, tls: usize) -> isize {
+    syscall6(SYSCALL_CLONE, [flags, stack, 0, tls, 0, 0])
+}
+
+pub fn sys_set_tid_address(tidptr: *mut u32) -> isize {
+    syscall(SYSCALL_SET_TID_ADDRESS, [tidptr as usize, 0, 0])
+}
+
+pub fn sys_futex(uaddr: *const u32, op: usize, val: usize) -> isize {
+    syscall(SYSCALL_FUTEX, [uaddr as usize, op, val])
+}
+
 pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
     syscall(
         SYSCALL_EXEC,
@@ -173,38 +370,175 @@ pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
     )
 }
 
-pub fn sys_waitpid(pid: isize, xstatus: *mut i32) -> isize {
-    syscall(SYSCALL_WAITPID, [pid as usize, xstatus as usize, 0])
+pub fn sys_execve(path: &str, args: &[*const u8], envp: &[*const u8]) -> isize {
+    syscall(
+        SYSCALL_EXEC,
+        [
+            path.as_ptr() as usize,
+            args.as_ptr() as usize,
+            envp.as_ptr() as usize,
+        ],
+    )
+}
+
+pub fn sys_waitpid(pid: isize, xstatus: *mut i32, options: usize) -> isize {
+    sys_wait4(pid, xstatus, options, core::ptr::null_mut())
+}
+
+pub fn sys_wait4(pid: isize, xstatus: *mut i32, options: usize, rusage: *mut Rusage) -> isize {
+    syscall6(
+        SYSCALL_WAIT4,
+        [pid as usize, xstatus as usize, options, rusage as usize, 0, 0],
+    )
 }
 
 pub fn sys_set_priority(prio: isize) -> isize {
     syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
 }
 
-pub fn sys_mmap(start: usize, len: usize, prot: usize) -> isize {
-    syscall(SYSCALL_MMAP, [start, len, prot])
+pub fn sys_reboot(magic1: usize, magic2: usize, cmd: usize) -> isize {
+    syscall(SYSCALL_REBOOT, [magic1, magic2, cmd])
+}
+
+pub fn sys_syslog(action: usize, buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_SYSLOG, [action, buf.as_mut_ptr() as usize, buf.len()])
+}
+
+pub fn sys_mmap(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    syscall6(SYSCALL_MMAP, [start, len, prot, flags, 0, 0])
 }
 
 pub fn sys_munmap(start: usize, len: usize) -> isize {
     syscall(SYSCALL_MUNMAP, [start, len, 0])
 }
 
-pub fn sys_spawn(path: &str) -> isize {
-    syscall(SYSCALL_SPAWN, [path.as_ptr() as usize, 0, 0])
+pub fn sys_shmget(key: usize, size: usize, flags: usize) -> isize {
+    syscall(SYSCALL_SHMGET, [key, size, flags])
+}
+
+pub fn sys_shmctl(id: usize, cmd: usize, buf: *mut ShmStat) -> isize {
+    syscall(SYSCALL_SHMCTL, [id, cmd, buf as usize])
+}
+
+pub fn sys_shmat(id: usize, addr: usize, flags: usize) -> isize {
+    syscall(SYSCALL_SHMAT, [id, addr, flags])
+}
+
+pub fn sys_shmdt(addr: usize) -> isize {
+    syscall(SYSCALL_SHMDT, [addr, 0, 0])
+}
+
+pub fn sys_mremap(old_addr: usize, old_size: usize, new_size: usize, flags: usize) -> isize {
+    syscall6(SYSCALL_MREMAP, [old_addr, old_size, new_size, flags, 0, 0])
+}
+
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [start, len, prot])
+}
+
+pub fn sys_sbrk(size: i32) -> isize {
+    syscall(SYSCALL_SBRK, [size as usize, 0, 0])
+}
+
+pub fn sys_spawn(
+    path: &str,
+    args: *const *const u8,
+    envp: *const *const u8,
+    redirects: &[SpawnRedirect],
+) -> isize {
+    syscall6(
+        SYSCALL_SPAWN,
+        [
+            path.as_ptr() as usize,
+            args as usize,
+            envp as usize,
+            redirects.as_ptr() as usize,
+            redirects.len(),
+            0,
+        ],
+    )
 }
 
 pub fn sys_dup(fd: usize) -> isize {
     syscall(SYSCALL_DUP, [fd, 0, 0])
 }
 
+pub fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> isize {
+    syscall(SYSCALL_FCNTL, [fd, cmd, arg])
+}
+
+pub fn sys_ioctl(fd: usize, request: usize, arg: usize) -> isize {
+    syscall(SYSCALL_IOCTL, [fd, request, arg])
+}
+
+pub fn sys_dup2(old_fd: usize, new_fd: usize) -> isize {
+    syscall(SYSCALL_DUP2, [old_fd, new_fd, 0])
+}
+
 pub fn sys_pipe(pipe: &mut [usize]) -> isize {
     syscall(SYSCALL_PIPE, [pipe.as_mut_ptr() as usize, 0, 0])
 }
 
+pub fn sys_sched_setscheduler(pid: usize, policy: usize, param: &SchedParam) -> isize {
+    syscall(
+        SYSCALL_SCHED_SETSCHEDULER,
+        [pid, policy, param as *const _ as usize],
+    )
+}
+
+pub fn sys_sched_getscheduler(pid: usize) -> isize {
+    syscall(SYSCALL_SCHED_GETSCHEDULER, [pid, 0, 0])
+}
+
+pub fn sys_sched_setaffinity(pid: usize, mask: &usize) -> isize {
+    syscall(
+        SYSCALL_SCHED_SETAFFINITY,
+        [pid, core::mem::size_of::<usize>(), mask as *const _ as usize],
+    )
+}
+
+pub fn sys_sched_getaffinity(pid: usize, mask: &mut usize) -> isize {
+    syscall(
+        SYSCALL_SCHED_GETAFFINITY,
+        [pid, core::mem::size_of::<usize>(), mask as *mut _ as usize],
+    )
+}
+
 pub fn sys_task_info(info: &TaskInfo) -> isize {
     syscall(SYSCALL_TASK_INFO, [info as *const _ as usize, 0, 0])
 }
 
+pub fn sys_sched_stats(stats: &mut SchedStats) -> isize {
+    syscall(SYSCALL_SCHED_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_swap_stats(stats: &mut SwapStats) -> isize {
+    syscall(SYSCALL_SWAP_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_meminfo(info: &mut MemInfo) -> isize {
+    syscall(SYSCALL_MEMINFO, [info as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_block_cache_stats(stats: &mut BlockCacheStats) -> isize {
+    syscall(SYSCALL_BLOCK_CACHE_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_slab_stats(infos: &mut [SlabInfo]) -> isize {
+    syscall(SYSCALL_SLAB_STATS, [infos.as_mut_ptr() as usize, infos.len(), 0])
+}
+
+pub fn sys_ps(procs: &mut [ProcInfo]) -> isize {
+    syscall(SYSCALL_PS, [procs.as_mut_ptr() as usize, procs.len(), 0])
+}
+
+pub fn sys_switch_trace(records: &mut [SwitchRecord]) -> isize {
+    syscall(
+        SYSCALL_SWITCH_TRACE,
+        [records.as_mut_ptr() as usize, records.len(), 0],
+    )
+}
+
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
 }